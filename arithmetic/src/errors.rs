@@ -7,10 +7,12 @@
 //! Error module.
 
 use ark_std::string::String;
+#[cfg(feature = "display")]
 use displaydoc::Display;
 
 /// A `enum` specifying the possible failure modes of the arithmetics.
-#[derive(Display, Debug)]
+#[derive(Debug)]
+#[cfg_attr(feature = "display", derive(Display))]
 pub enum ArithErrors {
     /// Invalid parameters: {0}
     InvalidParameters(String),
@@ -20,6 +22,15 @@ pub enum ArithErrors {
     SerializationErrors(ark_serialize::SerializationError),
 }
 
+/// Without the `display` feature, the errors are displayed as their `Debug`
+/// representation.
+#[cfg(not(feature = "display"))]
+impl ark_std::fmt::Display for ArithErrors {
+    fn fmt(&self, f: &mut ark_std::fmt::Formatter<'_>) -> ark_std::fmt::Result {
+        ark_std::fmt::Debug::fmt(self, f)
+    }
+}
+
 impl From<ark_serialize::SerializationError> for ArithErrors {
     fn from(e: ark_serialize::SerializationError) -> Self {
         Self::SerializationErrors(e)
@@ -162,7 +162,7 @@ fn fix_one_variable_helper<F: Field>(data: &[F], nv: usize, point: &F) -> Vec<F>
     // evaluate single variable of partial point from left to right
     #[cfg(not(feature = "parallel"))]
     for i in 0..(1 << (nv - 1)) {
-        res[i] = data[i] + (data[(i << 1) + 1] - data[i << 1]) * point;
+        res[i] = data[i << 1] + (data[(i << 1) + 1] - data[i << 1]) * point;
     }
 
     #[cfg(feature = "parallel")]
@@ -284,3 +284,30 @@ fn fix_last_variable_helper<F: Field>(data: &[F], nv: usize, point: &F) -> Vec<F
 
     res
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_bls12_381::Fr;
+    use ark_std::{test_rng, UniformRand};
+
+    /// `fix_variables` takes the parallel or the serial branch of
+    /// `fix_one_variable_helper` depending on the `parallel` feature; run
+    /// with and without it to check both against `fix_variables_no_par`.
+    #[test]
+    fn test_fix_variables_matches_serial() {
+        let mut rng = test_rng();
+        for nv in 1..8 {
+            let poly = DenseMultilinearExtension::<Fr>::rand(nv, &mut rng);
+            let point: Vec<Fr> = (0..nv).map(|_| Fr::rand(&mut rng)).collect();
+            for dim in 0..=nv {
+                assert_eq!(
+                    fix_variables(&poly, &point[..dim]),
+                    fix_variables_no_par(&poly, &point[..dim])
+                );
+            }
+            assert_eq!(evaluate_opt(&poly, &point), evaluate_no_par(&poly, &point));
+            assert_eq!(Some(evaluate_opt(&poly, &point)), poly.evaluate(&point));
+        }
+    }
+}
@@ -7,15 +7,23 @@
 //! This module defines our main mathematical object `VirtualPolynomial`; and
 //! various functions associated with it.
 
-use crate::{errors::ArithErrors, multilinear_polynomial::random_zero_mle_list, random_mle_list};
+use crate::{
+    errors::ArithErrors,
+    multilinear_polynomial::{fix_variables, random_zero_mle_list},
+    random_mle_list,
+};
 use ark_ff::PrimeField;
 use ark_poly::{DenseMultilinearExtension, MultilinearExtension};
-use ark_serialize::CanonicalSerialize;
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, Read, SerializationError, Valid, Validate,
+    Write,
+};
 use ark_std::{
     end_timer,
     rand::{Rng, RngCore},
     start_timer,
 };
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
 use std::{cmp::max, collections::HashMap, marker::PhantomData, ops::Add, sync::Arc};
 
@@ -39,13 +47,17 @@ use std::{cmp::max, collections::HashMap, marker::PhantomData, ops::Add, sync::A
 ///
 /// - flattened_ml_extensions stores the multilinear extension representation of
 ///   f0, f1, f2, f3 and f4
-/// - products is 
-///     \[ 
-///         (c0, \[0, 1, 2\]), 
-///         (c1, \[3, 4\]) 
-///     \]
+/// - products is
+///   \[
+///   (c0, \[0, 1, 2\]),
+///   (c1, \[3, 4\])
+///   \]
 /// - raw_pointers_lookup_table maps fi to i
 ///
+/// A multilinear extension may only depend on a subset of the variables:
+/// `mle_variables` then lists them, and the extension is stored over these
+/// variables only.
+///
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct VirtualPolynomial<F: PrimeField> {
     /// Aux information about the multilinear polynomial
@@ -55,43 +67,229 @@ pub struct VirtualPolynomial<F: PrimeField> {
     /// Stores multilinear extensions in which product multiplicand can refer
     /// to.
     pub flattened_ml_extensions: Vec<Arc<DenseMultilinearExtension<F>>>,
-    /// Pointers to the above poly extensions
-    raw_pointers_lookup_table: HashMap<*const DenseMultilinearExtension<F>, usize>,
+    /// The variables of each of the above extensions, as increasing indices
+    /// into the variables of the polynomial; `None` for an extension over
+    /// all the variables.
+    pub mle_variables: Vec<Option<Vec<usize>>>,
+    /// Addresses of the above poly extensions, stored as `usize` rather than
+    /// raw pointers so that the polynomial is `Send` and `Sync`
+    raw_pointers_lookup_table: HashMap<usize, usize>,
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Eq, CanonicalSerialize)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 /// Auxiliary information about the multilinear polynomial
 pub struct VPAuxInfo<F: PrimeField> {
     /// max number of multiplicands in each product
     pub max_degree: usize,
     /// number of variables of the polynomial
     pub num_variables: usize,
+    /// the degree of the polynomial in each variable, in the order of the
+    /// rounds of the sum check, each between 1 and `max_degree`; empty if
+    /// it is `max_degree` in every variable. A round of the sum check has
+    /// one evaluation more than the degree of its variable. It is not
+    /// serialized, see [`VirtualPolynomial::with_per_variable_degree`].
+    pub per_variable_degree: Vec<usize>,
+    /// whether the sum check prover should drop the zero products of the
+    /// polynomial first, see [`SparseVirtualPolynomial`]. It does not change
+    /// the proof, and is neither serialized nor absorbed in the transcript.
+    pub is_sparse: bool,
     /// Associated field
     #[doc(hidden)]
     pub phantom: PhantomData<F>,
 }
 
+impl<F: PrimeField> VPAuxInfo<F> {
+    /// Version of the layout of [`Self::transcript_bytes`].
+    pub const TRANSCRIPT_LAYOUT_VERSION: u8 = 2;
+
+    /// The bytes of the auxiliary information absorbed in the transcript.
+    ///
+    /// Layout version 2 has 16 bytes, followed by 8 bytes per variable if
+    /// `per_variable_degree` is not empty:
+    /// - `max_degree`, as a little-endian u64;
+    /// - `num_variables`, as a little-endian u64;
+    /// - each of `per_variable_degree`, as a little-endian u64.
+    ///
+    /// These are all the fields the soundness of the sum check depends on.
+    /// Without `per_variable_degree`, the layout is version 1, i.e., the
+    /// canonical serialization of the struct, so that proofs of the existing
+    /// protocol versions still verify. A change of the layout is a new
+    /// protocol version.
+    pub fn transcript_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(16 + 8 * self.per_variable_degree.len());
+        bytes.extend_from_slice(&(self.max_degree as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.num_variables as u64).to_le_bytes());
+        for &degree in self.per_variable_degree.iter() {
+            bytes.extend_from_slice(&(degree as u64).to_le_bytes());
+        }
+        bytes
+    }
+
+    /// The degree of the round `round` of the sum check, i.e., of the
+    /// polynomial in its `round`-th variable.
+    pub fn round_degree(&self, round: usize) -> usize {
+        self.per_variable_degree
+            .get(round)
+            .copied()
+            .unwrap_or(self.max_degree)
+    }
+}
+
+impl<F: PrimeField> CanonicalSerialize for VPAuxInfo<F> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.max_degree.serialize_with_mode(&mut writer, compress)?;
+        self.num_variables
+            .serialize_with_mode(&mut writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.max_degree.serialized_size(compress) + self.num_variables.serialized_size(compress)
+    }
+}
+
+impl<F: PrimeField> Valid for VPAuxInfo<F> {
+    fn check(&self) -> Result<(), SerializationError> {
+        Ok(())
+    }
+}
+
+impl<F: PrimeField> CanonicalDeserialize for VPAuxInfo<F> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        Ok(Self {
+            max_degree: usize::deserialize_with_mode(&mut reader, compress, validate)?,
+            num_variables: usize::deserialize_with_mode(&mut reader, compress, validate)?,
+            per_variable_degree: vec![],
+            is_sparse: false,
+            phantom: PhantomData,
+        })
+    }
+}
+
 impl<F: PrimeField> Add for &VirtualPolynomial<F> {
     type Output = VirtualPolynomial<F>;
     fn add(self, other: &VirtualPolynomial<F>) -> Self::Output {
-        let start = start_timer!(|| "virtual poly add");
-        let mut res = self.clone();
-        for products in other.products.iter() {
-            let cur: Vec<Arc<DenseMultilinearExtension<F>>> = products
-                .1
+        self.try_add(other).expect("add product failed")
+    }
+}
+
+// TODO: convert this into a trait
+// The extensions are serialized by value, and the lookup table is rebuilt
+// from the deserialized extensions.
+impl<F: PrimeField> CanonicalSerialize for VirtualPolynomial<F> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.aux_info.serialize_with_mode(&mut writer, compress)?;
+        self.products.serialize_with_mode(&mut writer, compress)?;
+        self.flattened_ml_extensions
+            .len()
+            .serialize_with_mode(&mut writer, compress)?;
+        for mle in self.flattened_ml_extensions.iter() {
+            mle.as_ref().serialize_with_mode(&mut writer, compress)?;
+        }
+        self.mle_variables
+            .serialize_with_mode(&mut writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.aux_info.serialized_size(compress)
+            + self.products.serialized_size(compress)
+            + self.flattened_ml_extensions.len().serialized_size(compress)
+            + self
+                .flattened_ml_extensions
                 .iter()
-                .map(|&x| other.flattened_ml_extensions[x].clone())
-                .collect();
+                .map(|mle| mle.as_ref().serialized_size(compress))
+                .sum::<usize>()
+            + self.mle_variables.serialized_size(compress)
+    }
+}
 
-            res.add_mle_list(cur, products.0)
-                .expect("add product failed");
+impl<F: PrimeField> Valid for VirtualPolynomial<F> {
+    fn check(&self) -> Result<(), SerializationError> {
+        let num_mles = self.flattened_ml_extensions.len();
+        if self.mle_variables.len() != num_mles
+            || self.products.iter().any(|(_, product)| {
+                product.is_empty()
+                    || product.len() > self.aux_info.max_degree
+                    || product.iter().any(|&i| i >= num_mles)
+            })
+        {
+            return Err(SerializationError::InvalidData);
         }
-        end_timer!(start);
-        res
+        for (mle, variables) in self
+            .flattened_ml_extensions
+            .iter()
+            .zip(self.mle_variables.iter())
+        {
+            let num_vars = match variables {
+                Some(variables) => {
+                    if !variables.windows(2).all(|w| w[0] < w[1])
+                        || variables
+                            .last()
+                            .is_some_and(|&v| v >= self.aux_info.num_variables)
+                    {
+                        return Err(SerializationError::InvalidData);
+                    }
+                    variables.len()
+                },
+                None => self.aux_info.num_variables,
+            };
+            // the extensions of a sum check prover state are partially fixed
+            if mle.num_vars > num_vars || mle.evaluations.len() != 1 << mle.num_vars {
+                return Err(SerializationError::InvalidData);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<F: PrimeField> CanonicalDeserialize for VirtualPolynomial<F> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let aux_info = VPAuxInfo::deserialize_with_mode(&mut reader, compress, validate)?;
+        let products = Vec::deserialize_with_mode(&mut reader, compress, validate)?;
+        let flattened_ml_extensions: Vec<Arc<DenseMultilinearExtension<F>>> =
+            Vec::<DenseMultilinearExtension<F>>::deserialize_with_mode(
+                &mut reader,
+                compress,
+                validate,
+            )?
+            .into_iter()
+            .map(Arc::new)
+            .collect();
+        let mle_variables = Vec::deserialize_with_mode(&mut reader, compress, validate)?;
+        let raw_pointers_lookup_table = flattened_ml_extensions
+            .iter()
+            .enumerate()
+            .map(|(i, mle)| (Arc::as_ptr(mle) as usize, i))
+            .collect();
+        let poly = Self {
+            aux_info,
+            products,
+            flattened_ml_extensions,
+            mle_variables,
+            raw_pointers_lookup_table,
+        };
+        if validate == Validate::Yes {
+            poly.check()?;
+        }
+        Ok(poly)
     }
 }
 
-// TODO: convert this into a trait
 impl<F: PrimeField> VirtualPolynomial<F> {
     /// Creates an empty virtual polynomial with `num_variables`.
     pub fn new(num_variables: usize) -> Self {
@@ -99,17 +297,20 @@ impl<F: PrimeField> VirtualPolynomial<F> {
             aux_info: VPAuxInfo {
                 max_degree: 0,
                 num_variables,
+                per_variable_degree: vec![],
+                is_sparse: false,
                 phantom: PhantomData,
             },
             products: Vec::new(),
             flattened_ml_extensions: Vec::new(),
+            mle_variables: Vec::new(),
             raw_pointers_lookup_table: HashMap::new(),
         }
     }
 
     /// Creates an new virtual polynomial from a MLE and its coefficient.
     pub fn new_from_mle(mle: &Arc<DenseMultilinearExtension<F>>, coefficient: F) -> Self {
-        let mle_ptr: *const DenseMultilinearExtension<F> = Arc::as_ptr(mle);
+        let mle_ptr = Arc::as_ptr(mle) as usize;
         let mut hm = HashMap::new();
         hm.insert(mle_ptr, 0);
 
@@ -118,11 +319,14 @@ impl<F: PrimeField> VirtualPolynomial<F> {
                 // The max degree is the max degree of any individual variable
                 max_degree: 1,
                 num_variables: mle.num_vars,
+                per_variable_degree: vec![],
+                is_sparse: false,
                 phantom: PhantomData,
             },
             // here `0` points to the first polynomial of `flattened_ml_extensions`
             products: vec![(coefficient, vec![0])],
             flattened_ml_extensions: vec![mle.clone()],
+            mle_variables: vec![None],
             raw_pointers_lookup_table: hm,
         }
     }
@@ -138,7 +342,42 @@ impl<F: PrimeField> VirtualPolynomial<F> {
         mle_list: impl IntoIterator<Item = Arc<DenseMultilinearExtension<F>>>,
         coefficient: F,
     ) -> Result<(), ArithErrors> {
-        let mle_list: Vec<Arc<DenseMultilinearExtension<F>>> = mle_list.into_iter().collect();
+        self.add_product(
+            mle_list.into_iter().map(|mle| (mle, None)).collect(),
+            coefficient,
+        )
+    }
+
+    /// Add a product of list of multilinear extensions to self, where each
+    /// MLE only depends on the listed variables of self, in increasing
+    /// order.
+    /// Returns an error if the list is empty, a list of variables is not
+    /// increasing or out of range, or an MLE does not have one variable per
+    /// listed variable.
+    ///
+    /// An MLE over `k` of the `num_vars` variables takes `2^(num_vars - k)`
+    /// times less space than its extension to all the variables, and the sum
+    /// check prover only folds it on the rounds of its variables.
+    pub fn add_partial_mle_list(
+        &mut self,
+        mle_list: impl IntoIterator<Item = (Arc<DenseMultilinearExtension<F>>, Vec<usize>)>,
+        coefficient: F,
+    ) -> Result<(), ArithErrors> {
+        self.add_product(
+            mle_list
+                .into_iter()
+                .map(|(mle, variables)| (mle, Some(variables)))
+                .collect(),
+            coefficient,
+        )
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn add_product(
+        &mut self,
+        mle_list: Vec<(Arc<DenseMultilinearExtension<F>>, Option<Vec<usize>>)>,
+        coefficient: F,
+    ) -> Result<(), ArithErrors> {
         let mut indexed_product = Vec::with_capacity(mle_list.len());
 
         if mle_list.is_empty() {
@@ -149,25 +388,103 @@ impl<F: PrimeField> VirtualPolynomial<F> {
 
         self.aux_info.max_degree = max(self.aux_info.max_degree, mle_list.len());
 
-        for mle in mle_list {
-            if mle.num_vars != self.aux_info.num_variables {
-                return Err(ArithErrors::InvalidParameters(format!(
-                    "product has a multiplicand with wrong number of variables {} vs {}",
-                    mle.num_vars, self.aux_info.num_variables
-                )));
-            }
+        for (mle, variables) in mle_list {
+            self.check_mle_variables(&mle, &variables)?;
 
-            let mle_ptr: *const DenseMultilinearExtension<F> = Arc::as_ptr(&mle);
+            let mle_ptr = Arc::as_ptr(&mle) as usize;
             if let Some(index) = self.raw_pointers_lookup_table.get(&mle_ptr) {
+                if self.mle_variables[*index] != variables {
+                    return Err(ArithErrors::InvalidParameters(
+                        "product has a multiplicand already added over other variables".to_string(),
+                    ));
+                }
                 indexed_product.push(*index)
             } else {
                 let curr_index = self.flattened_ml_extensions.len();
                 self.flattened_ml_extensions.push(mle.clone());
+                self.mle_variables.push(variables);
                 self.raw_pointers_lookup_table.insert(mle_ptr, curr_index);
                 indexed_product.push(curr_index);
             }
         }
         self.products.push((coefficient, indexed_product));
+        self.update_per_variable_degree();
+        Ok(())
+    }
+
+    /// The degree of the polynomial in each variable, i.e., the largest
+    /// number of multiplicands of a product that depend on the variable, and
+    /// at least one.
+    pub fn variable_degrees(&self) -> Vec<usize> {
+        let mut degrees = vec![1; self.aux_info.num_variables];
+        for (_coefficient, product) in self.products.iter() {
+            let mut product_degrees = vec![0; self.aux_info.num_variables];
+            for &index in product.iter() {
+                match &self.mle_variables[index] {
+                    None => product_degrees.iter_mut().for_each(|degree| *degree += 1),
+                    Some(variables) => variables
+                        .iter()
+                        .for_each(|&variable| product_degrees[variable] += 1),
+                }
+            }
+            degrees
+                .iter_mut()
+                .zip(product_degrees)
+                .for_each(|(degree, product_degree)| *degree = max(*degree, product_degree));
+        }
+        degrees
+    }
+
+    /// The polynomial whose sum check messages only have as many
+    /// evaluations as needed for the degree of each variable, see
+    /// [`VPAuxInfo::per_variable_degree`], which is then kept up to date as
+    /// products are added.
+    ///
+    /// The sum check proof of a polynomial with MLEs over some of the
+    /// variables is then shorter. The verifier must use the same auxiliary
+    /// information: as it is not serialized, a deserialized polynomial is
+    /// proven with `max_degree` in every round.
+    pub fn with_per_variable_degree(mut self) -> Self {
+        self.aux_info.per_variable_degree = self.variable_degrees();
+        self
+    }
+
+    /// Recompute the degree of each variable, if they are tracked.
+    fn update_per_variable_degree(&mut self) {
+        if !self.aux_info.per_variable_degree.is_empty() {
+            self.aux_info.per_variable_degree = self.variable_degrees();
+        }
+    }
+
+    /// Check that `mle` is an MLE over `variables`, or over all the variables
+    /// if `None`.
+    fn check_mle_variables(
+        &self,
+        mle: &DenseMultilinearExtension<F>,
+        variables: &Option<Vec<usize>>,
+    ) -> Result<(), ArithErrors> {
+        let num_vars = match variables {
+            None => self.aux_info.num_variables,
+            Some(variables) => {
+                if variables.windows(2).any(|w| w[0] >= w[1])
+                    || variables
+                        .last()
+                        .is_some_and(|&v| v >= self.aux_info.num_variables)
+                {
+                    return Err(ArithErrors::InvalidParameters(format!(
+                        "variables {:?} are not increasing indices of {} variables",
+                        variables, self.aux_info.num_variables
+                    )));
+                }
+                variables.len()
+            },
+        };
+        if mle.num_vars != num_vars {
+            return Err(ArithErrors::InvalidParameters(format!(
+                "product has a multiplicand with wrong number of variables {} vs {}",
+                mle.num_vars, num_vars
+            )));
+        }
         Ok(())
     }
 
@@ -190,7 +507,7 @@ impl<F: PrimeField> VirtualPolynomial<F> {
             )));
         }
 
-        let mle_ptr: *const DenseMultilinearExtension<F> = Arc::as_ptr(&mle);
+        let mle_ptr = Arc::as_ptr(&mle) as usize;
 
         // check if this mle already exists in the virtual polynomial
         let mle_index = match self.raw_pointers_lookup_table.get(&mle_ptr) {
@@ -199,6 +516,7 @@ impl<F: PrimeField> VirtualPolynomial<F> {
                 self.raw_pointers_lookup_table
                     .insert(mle_ptr, self.flattened_ml_extensions.len());
                 self.flattened_ml_extensions.push(mle);
+                self.mle_variables.push(None);
                 self.flattened_ml_extensions.len() - 1
             },
         };
@@ -212,10 +530,57 @@ impl<F: PrimeField> VirtualPolynomial<F> {
 
         // increase the max degree by one as the MLE has degree 1.
         self.aux_info.max_degree += 1;
+        self.update_per_variable_degree();
         end_timer!(start);
         Ok(())
     }
 
+    /// The sum of self and `other`: the products of `other` are appended to
+    /// those of self, and the MLEs shared by both are only stored once.
+    ///
+    /// Returns an error if the polynomials have different numbers of
+    /// variables, or share an MLE over different variables.
+    pub fn try_add(&self, other: &Self) -> Result<Self, ArithErrors> {
+        let start = start_timer!(|| "virtual poly add");
+        if self.aux_info.num_variables != other.aux_info.num_variables {
+            return Err(ArithErrors::InvalidParameters(format!(
+                "cannot add polynomials of {} and {} variables",
+                self.aux_info.num_variables, other.aux_info.num_variables
+            )));
+        }
+
+        let mut res = self.clone();
+        for (coefficient, indices) in other.products.iter() {
+            let cur: Vec<_> = indices
+                .iter()
+                .map(|&x| {
+                    (
+                        other.flattened_ml_extensions[x].clone(),
+                        other.mle_variables[x].clone(),
+                    )
+                })
+                .collect();
+            res.add_product(cur, *coefficient)?;
+        }
+        end_timer!(start);
+        Ok(res)
+    }
+
+    /// The polynomial multiplied by the constant `c`, which shares the MLEs
+    /// of self.
+    pub fn scale(&self, c: F) -> Self {
+        let mut res = self.clone();
+        for (coefficient, _) in res.products.iter_mut() {
+            *coefficient *= c;
+        }
+        res
+    }
+
+    /// The opposite of the polynomial, which shares the MLEs of self.
+    pub fn negate(&self) -> Self {
+        self.scale(-F::one())
+    }
+
     /// Evaluate the virtual polynomial at point `point`.
     /// Returns an error is point.len() does not match `num_variables`.
     pub fn evaluate(&self, point: &[F]) -> Result<F, ArithErrors> {
@@ -229,26 +594,117 @@ impl<F: PrimeField> VirtualPolynomial<F> {
             )));
         }
 
-        let evals: Vec<F> = self
+        let res = evaluate_products(
+            &self.products,
+            &self.flattened_ml_extensions,
+            &self.mle_variables,
+            point,
+        );
+
+        end_timer!(start);
+        Ok(res)
+    }
+
+    /// Evaluate the virtual polynomial at each of `points`.
+    /// Returns an error if the length of a point does not match
+    /// `num_variables`.
+    ///
+    /// The MLEs are evaluated one variable at a time, and the tables with
+    /// the first coordinates fixed are shared by all the points with the same
+    /// first coordinates, so `k` points with the same first `d` coordinates
+    /// fix these once rather than `k` times. A single point is evaluated with
+    /// [`Self::evaluate`].
+    pub fn batch_evaluate(&self, points: &[Vec<F>]) -> Result<Vec<F>, ArithErrors> {
+        if points.len() == 1 {
+            return Ok(vec![self.evaluate(&points[0])?]);
+        }
+        let start = start_timer!(|| format!("batch evaluation at {} points", points.len()));
+
+        if let Some(point) = points
+            .iter()
+            .find(|point| point.len() != self.aux_info.num_variables)
+        {
+            return Err(ArithErrors::InvalidParameters(format!(
+                "wrong number of variables {} vs {}",
+                self.aux_info.num_variables,
+                point.len()
+            )));
+        }
+
+        // `mle_evals[i][j]` is the evaluation of the `i`-th MLE at the `j`-th
+        // point
+        let mle_evals: Vec<Vec<F>> = self
             .flattened_ml_extensions
             .iter()
-            .map(|x| {
-                x.evaluate(point).unwrap() // safe unwrap here since we have
-                                           // already checked that num_var
-                                           // matches
+            .zip(self.mle_variables.iter())
+            .map(|(mle, variables)| {
+                let mle_points: Vec<Vec<F>> = points
+                    .iter()
+                    .map(|point| mle_point(variables, point))
+                    .collect();
+                evaluate_mle_shared(mle, &mle_points)
             })
             .collect();
 
-        let res = self
-            .products
-            .iter()
-            .map(|(c, p)| *c * p.iter().map(|&i| evals[i]).product::<F>())
-            .sum();
+        let res = (0..points.len())
+            .map(|j| {
+                self.products
+                    .iter()
+                    .map(|(c, p)| *c * p.iter().map(|&i| mle_evals[i][j]).product::<F>())
+                    .sum()
+            })
+            .collect();
 
         end_timer!(start);
         Ok(res)
     }
 
+    /// The coordinates of `point` that the `i`-th MLE depends on, i.e., the
+    /// point at which the MLE is evaluated when self is evaluated at `point`.
+    pub fn mle_point(&self, i: usize, point: &[F]) -> Vec<F> {
+        mle_point(&self.mle_variables[i], point)
+    }
+
+    /// The same polynomial, with every MLE extended to all the variables.
+    ///
+    /// The lifted MLEs do not depend on the variables they were extended
+    /// to, so the degrees of the variables are kept, if they are declared.
+    pub fn lift(&self) -> Self {
+        let num_vars = self.aux_info.num_variables;
+        let mles: Vec<Arc<DenseMultilinearExtension<F>>> = self
+            .flattened_ml_extensions
+            .iter()
+            .zip(self.mle_variables.iter())
+            .map(|(mle, variables)| match variables {
+                None => mle.clone(),
+                Some(variables) => {
+                    let evals = (0..1usize << num_vars)
+                        .map(|x| {
+                            let index = variables
+                                .iter()
+                                .enumerate()
+                                .map(|(k, &v)| ((x >> v) & 1) << k)
+                                .sum::<usize>();
+                            mle.evaluations[index]
+                        })
+                        .collect();
+                    Arc::new(DenseMultilinearExtension::from_evaluations_vec(
+                        num_vars, evals,
+                    ))
+                },
+            })
+            .collect();
+        let mut res = VirtualPolynomial::new(num_vars);
+        for (coefficient, indices) in self.products.iter() {
+            res.add_mle_list(indices.iter().map(|&i| mles[i].clone()), *coefficient)
+                .expect("the lifted MLEs are over all the variables");
+        }
+        res.aux_info
+            .per_variable_degree
+            .clone_from(&self.aux_info.per_variable_degree);
+        res
+    }
+
     /// Sample a random virtual polynomial, return the polynomial and its sum.
     pub fn rand<R: RngCore>(
         nv: usize,
@@ -265,7 +721,7 @@ impl<F: PrimeField> VirtualPolynomial<F> {
                 rng.gen_range(num_multiplicands_range.0..num_multiplicands_range.1);
             let (product, product_sum) = random_mle_list(nv, num_multiplicands, rng);
             let coefficient = F::rand(rng);
-            poly.add_mle_list(product.into_iter(), coefficient)?;
+            poly.add_mle_list(product, coefficient)?;
             sum += product_sum * coefficient;
         }
 
@@ -287,12 +743,56 @@ impl<F: PrimeField> VirtualPolynomial<F> {
                 rng.gen_range(num_multiplicands_range.0..num_multiplicands_range.1);
             let product = random_zero_mle_list(nv, num_multiplicands, rng);
             let coefficient = F::rand(rng);
-            poly.add_mle_list(product.into_iter(), coefficient)?;
+            poly.add_mle_list(product, coefficient)?;
         }
 
         Ok(poly)
     }
 
+    /// Build a virtual polynomial from explicit products, return the
+    /// polynomial and its sum.
+    ///
+    /// Each product is a coefficient and the evaluations of its multiplicands
+    /// over the boolean hypercube. Returns an error if a product has no
+    /// multiplicand, or a multiplicand does not have `2^num_variables`
+    /// evaluations.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn from_products(
+        num_variables: usize,
+        products: &[(F, Vec<Vec<F>>)],
+    ) -> Result<(Self, F), ArithErrors> {
+        let mut sum = F::zero();
+        let mut poly = VirtualPolynomial::new(num_variables);
+        for (coefficient, multiplicands) in products.iter() {
+            if let Some(evals) = multiplicands
+                .iter()
+                .find(|evals| evals.len() != 1 << num_variables)
+            {
+                return Err(ArithErrors::InvalidParameters(format!(
+                    "multiplicand has {} evaluations, expected {}",
+                    evals.len(),
+                    1 << num_variables
+                )));
+            }
+            if !multiplicands.is_empty() {
+                sum += *coefficient
+                    * (0..1 << num_variables)
+                        .map(|i| multiplicands.iter().map(|evals| evals[i]).product::<F>())
+                        .sum::<F>();
+            }
+            poly.add_mle_list(
+                multiplicands.iter().map(|evals| {
+                    Arc::new(DenseMultilinearExtension::from_evaluations_slice(
+                        num_variables,
+                        evals,
+                    ))
+                }),
+                *coefficient,
+            )?;
+        }
+        Ok((poly, sum))
+    }
+
     // Input poly f(x) and a random vector r, output
     //      \hat f(x) = \sum_{x_i \in eval_x} f(x_i) eq(x, r)
     // where
@@ -332,6 +832,193 @@ impl<F: PrimeField> VirtualPolynomial<F> {
     }
 }
 
+/// A virtual polynomial that only stores its non-zero products, i.e., the
+/// products with a non-zero coefficient and no multiplicand that is zero
+/// everywhere, and the multilinear extensions they refer to.
+///
+/// For circuits where most selectors are zero on most gates, it is evaluated
+/// in time proportional to the number of non-zero products. It converts from
+/// and to a [`VirtualPolynomial`]; the latter has `aux_info.is_sparse` set, so
+/// that the sum check prover drops the zero products as well.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SparseVirtualPolynomial<F: PrimeField> {
+    /// Aux information about the multilinear polynomial, with the max degree
+    /// of the polynomial it was built from
+    pub aux_info: VPAuxInfo<F>,
+    /// the non-zero products, as in [`VirtualPolynomial::products`]
+    pub products: Vec<(F, Vec<usize>)>,
+    /// the multilinear extensions of the non-zero products
+    pub flattened_ml_extensions: Vec<Arc<DenseMultilinearExtension<F>>>,
+    /// the variables of each of the above extensions, as in
+    /// [`VirtualPolynomial::mle_variables`]
+    pub mle_variables: Vec<Option<Vec<usize>>>,
+}
+
+impl<F: PrimeField> SparseVirtualPolynomial<F> {
+    /// Evaluate the sparse virtual polynomial at point `point`.
+    /// Returns an error is point.len() does not match `num_variables`.
+    pub fn evaluate(&self, point: &[F]) -> Result<F, ArithErrors> {
+        let start = start_timer!(|| "sparse evaluation");
+
+        if self.aux_info.num_variables != point.len() {
+            return Err(ArithErrors::InvalidParameters(format!(
+                "wrong number of variables {} vs {}",
+                self.aux_info.num_variables,
+                point.len()
+            )));
+        }
+        let res = evaluate_products(
+            &self.products,
+            &self.flattened_ml_extensions,
+            &self.mle_variables,
+            point,
+        );
+
+        end_timer!(start);
+        Ok(res)
+    }
+}
+
+impl<F: PrimeField> From<&VirtualPolynomial<F>> for SparseVirtualPolynomial<F> {
+    fn from(poly: &VirtualPolynomial<F>) -> Self {
+        let is_zero: Vec<bool> = poly
+            .flattened_ml_extensions
+            .iter()
+            .map(|mle| mle.evaluations.iter().all(|x| x.is_zero()))
+            .collect();
+
+        // the new index of each extension of a non-zero product
+        let mut indices = vec![None; poly.flattened_ml_extensions.len()];
+        let mut flattened_ml_extensions = vec![];
+        let mut mle_variables = vec![];
+        let products = poly
+            .products
+            .iter()
+            .filter(|(coefficient, product)| {
+                !coefficient.is_zero() && product.iter().all(|&i| !is_zero[i])
+            })
+            .map(|(coefficient, product)| {
+                let product = product
+                    .iter()
+                    .map(|&i| {
+                        *indices[i].get_or_insert_with(|| {
+                            flattened_ml_extensions.push(poly.flattened_ml_extensions[i].clone());
+                            mle_variables.push(poly.mle_variables[i].clone());
+                            flattened_ml_extensions.len() - 1
+                        })
+                    })
+                    .collect();
+                (*coefficient, product)
+            })
+            .collect();
+
+        Self {
+            aux_info: VPAuxInfo {
+                is_sparse: true,
+                ..poly.aux_info.clone()
+            },
+            products,
+            flattened_ml_extensions,
+            mle_variables,
+        }
+    }
+}
+
+impl<F: PrimeField> From<&SparseVirtualPolynomial<F>> for VirtualPolynomial<F> {
+    fn from(poly: &SparseVirtualPolynomial<F>) -> Self {
+        let raw_pointers_lookup_table = poly
+            .flattened_ml_extensions
+            .iter()
+            .enumerate()
+            .map(|(i, mle)| (Arc::as_ptr(mle) as usize, i))
+            .collect();
+        Self {
+            aux_info: VPAuxInfo {
+                is_sparse: true,
+                ..poly.aux_info.clone()
+            },
+            products: poly.products.clone(),
+            flattened_ml_extensions: poly.flattened_ml_extensions.clone(),
+            mle_variables: poly.mle_variables.clone(),
+            raw_pointers_lookup_table,
+        }
+    }
+}
+
+/// Evaluate the sum of `products` of the extensions `mles` over
+/// `mle_variables` at `point`.
+fn evaluate_products<F: PrimeField>(
+    products: &[(F, Vec<usize>)],
+    mles: &[Arc<DenseMultilinearExtension<F>>],
+    mle_variables: &[Option<Vec<usize>>],
+    point: &[F],
+) -> F {
+    let evals: Vec<F> = mles
+        .iter()
+        .zip(mle_variables.iter())
+        .map(|(x, variables)| {
+            // safe unwrap here since we have already checked that
+            // num_var matches
+            x.evaluate(&mle_point(variables, point)).unwrap()
+        })
+        .collect();
+
+    products
+        .iter()
+        .map(|(c, p)| *c * p.iter().map(|&i| evals[i]).product::<F>())
+        .sum()
+}
+
+/// Evaluate `mle` at each of `points`, fixing the coordinates one at a time,
+/// once for all the points that share them and the coordinates before them.
+fn evaluate_mle_shared<F: PrimeField>(
+    mle: &DenseMultilinearExtension<F>,
+    points: &[Vec<F>],
+) -> Vec<F> {
+    // sorted, the points sharing their first coordinates are consecutive
+    let mut order: Vec<usize> = (0..points.len()).collect();
+    order.sort_by(|&i, &j| points[i].cmp(&points[j]));
+
+    let mut evals = vec![F::zero(); points.len()];
+    evaluate_mle_group(mle, points, &order, 0, &mut evals);
+    evals
+}
+
+/// Evaluate `mle`, with the first `depth` coordinates of the points of
+/// `group` already fixed, at the points of `group`.
+fn evaluate_mle_group<F: PrimeField>(
+    mle: &DenseMultilinearExtension<F>,
+    points: &[Vec<F>],
+    group: &[usize],
+    depth: usize,
+    evals: &mut [F],
+) {
+    if mle.num_vars == 0 {
+        group.iter().for_each(|&j| evals[j] = mle.evaluations[0]);
+        return;
+    }
+    let mut rest = group;
+    while let Some(&first) = rest.first() {
+        let coordinate = points[first][depth];
+        let len = rest
+            .iter()
+            .position(|&j| points[j][depth] != coordinate)
+            .unwrap_or(rest.len());
+        let fixed = fix_variables(mle, &[coordinate]);
+        evaluate_mle_group(&fixed, points, &rest[..len], depth + 1, evals);
+        rest = &rest[len..];
+    }
+}
+
+/// The coordinates of `point` of the listed `variables`, or all of them if
+/// `None`.
+fn mle_point<F: PrimeField>(variables: &Option<Vec<usize>>, point: &[F]) -> Vec<F> {
+    match variables {
+        None => point.to_vec(),
+        Some(variables) => variables.iter().map(|&v| point[v]).collect(),
+    }
+}
+
 /// Evaluate eq polynomial.
 pub fn eq_eval<F: PrimeField>(x: &[F], y: &[F]) -> Result<F, ArithErrors> {
     if x.len() != y.len() {
@@ -414,7 +1101,7 @@ fn build_eq_x_r_helper<F: PrimeField>(r: &[F], buf: &mut Vec<F>) -> Result<(), A
         // *buf = res;
 
         let mut res = vec![F::zero(); buf.len() << 1];
-        res.par_iter_mut().enumerate().for_each(|(i, val)| {
+        let step = |(i, val): (usize, &mut F)| {
             let bi = buf[i >> 1];
             let tmp = r[0] * bi;
             if i & 1 == 0 {
@@ -422,7 +1109,15 @@ fn build_eq_x_r_helper<F: PrimeField>(r: &[F], buf: &mut Vec<F>) -> Result<(), A
             } else {
                 *val = tmp;
             }
-        });
+        };
+        #[cfg(feature = "parallel")]
+        if ::util::serial_route() {
+            res.iter_mut().enumerate().for_each(step);
+        } else {
+            res.par_iter_mut().enumerate().for_each(step);
+        }
+        #[cfg(not(feature = "parallel"))]
+        res.iter_mut().enumerate().for_each(step);
         *buf = res;
     }
 
@@ -444,7 +1139,7 @@ pub fn bit_decompose(input: u64, num_var: usize) -> Vec<bool> {
 mod test {
     use super::*;
     use ark_bls12_381::Fr;
-    use ark_ff::UniformRand;
+    use ark_ff::{One, UniformRand, Zero};
     use ark_std::test_rng;
 
     #[test]
@@ -470,6 +1165,85 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_virtual_polynomial_arithmetic() -> Result<(), ArithErrors> {
+        let mut rng = test_rng();
+        let nv = 4;
+        let point: Vec<Fr> = (0..nv).map(|_| Fr::rand(&mut rng)).collect();
+        let (p, _) = VirtualPolynomial::<Fr>::rand(nv, (2, 3), 3, &mut rng)?;
+        let (q, _) = VirtualPolynomial::<Fr>::rand(nv, (3, 5), 2, &mut rng)?;
+
+        let sum = p.try_add(&q)?;
+        assert_eq!(
+            sum.evaluate(&point)?,
+            p.evaluate(&point)? + q.evaluate(&point)?
+        );
+        assert_eq!(sum.aux_info.max_degree, q.aux_info.max_degree);
+        assert_eq!(sum.products.len(), p.products.len() + q.products.len());
+
+        let c = Fr::rand(&mut rng);
+        assert_eq!(p.scale(c).evaluate(&point)?, c * p.evaluate(&point)?);
+        assert_eq!(p.negate().evaluate(&point)?, -p.evaluate(&point)?);
+        assert!(p.try_add(&p.negate())?.evaluate(&point)?.is_zero());
+
+        // the MLEs shared by both operands are stored once
+        let doubled = p.try_add(&p.scale(Fr::from(2u64)))?;
+        assert_eq!(
+            doubled.flattened_ml_extensions.len(),
+            p.flattened_ml_extensions.len()
+        );
+        assert_eq!(
+            doubled.evaluate(&point)?,
+            Fr::from(3u64) * p.evaluate(&point)?
+        );
+
+        let (other, _) = VirtualPolynomial::<Fr>::rand(nv + 1, (2, 3), 2, &mut rng)?;
+        assert!(p.try_add(&other).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_batch_evaluate() -> Result<(), ArithErrors> {
+        let mut rng = test_rng();
+        let nv = 5;
+        let (mut poly, _) = VirtualPolynomial::<Fr>::rand(nv, (4, 5), 3, &mut rng)?;
+        let partial: Vec<_> = (0..4)
+            .map(|_| Arc::new(DenseMultilinearExtension::rand(2, &mut rng)))
+            .collect();
+        poly.add_partial_mle_list(
+            partial.into_iter().map(|mle| (mle, vec![1, 3])),
+            Fr::rand(&mut rng),
+        )?;
+        assert_eq!(poly.aux_info.max_degree, 4);
+
+        // two points share their first three coordinates, and a third one
+        // none of them
+        let shared: Vec<Fr> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+        let mut points = vec![];
+        for _ in 0..2 {
+            let mut point = shared.clone();
+            point.extend((0..nv - 3).map(|_| Fr::rand(&mut rng)));
+            points.push(point);
+        }
+        points.push((0..nv).map(|_| Fr::rand(&mut rng)).collect());
+        points.push(points[0].clone());
+
+        let evals = poly.batch_evaluate(&points)?;
+        assert_eq!(evals.len(), points.len());
+        for (point, eval) in points.iter().zip(evals.iter()) {
+            assert_eq!(poly.evaluate(point)?, *eval);
+        }
+        assert_eq!(
+            poly.batch_evaluate(&points[..1])?,
+            vec![poly.evaluate(&points[0])?]
+        );
+        assert!(poly.batch_evaluate(&[]).map(|evals| evals.is_empty())?);
+
+        points.push(vec![Fr::one(); nv - 1]);
+        assert!(poly.batch_evaluate(&points).is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_virtual_polynomial_mul_by_mle() -> Result<(), ArithErrors> {
         let mut rng = test_rng();
@@ -498,6 +1272,249 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_virtual_polynomial_from_products() -> Result<(), ArithErrors> {
+        let mut rng = test_rng();
+        let nv = 3;
+        let (poly, sum) = VirtualPolynomial::<Fr>::rand(nv, (2, 4), 3, &mut rng)?;
+        let products: Vec<(Fr, Vec<Vec<Fr>>)> = poly
+            .products
+            .iter()
+            .map(|(coefficient, indices)| {
+                (
+                    *coefficient,
+                    indices
+                        .iter()
+                        .map(|&i| poly.flattened_ml_extensions[i].evaluations.clone())
+                        .collect(),
+                )
+            })
+            .collect();
+        let (rebuilt, rebuilt_sum) = VirtualPolynomial::from_products(nv, &products)?;
+        assert_eq!(rebuilt_sum, sum);
+        assert_eq!(rebuilt.aux_info, poly.aux_info);
+        let point: Vec<Fr> = (0..nv).map(|_| Fr::rand(&mut rng)).collect();
+        assert_eq!(rebuilt.evaluate(&point)?, poly.evaluate(&point)?);
+
+        // bad path: wrong number of evaluations, or an empty product
+        let bad = [(Fr::one(), vec![vec![Fr::one(); 1 << (nv - 1)]])];
+        assert!(VirtualPolynomial::from_products(nv, &bad).is_err());
+        let bad = [(Fr::one(), vec![])];
+        assert!(VirtualPolynomial::from_products(nv, &bad).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_virtual_polynomial_partial_mles() -> Result<(), ArithErrors> {
+        let mut rng = test_rng();
+        let nv = 4;
+        let full = Arc::new(DenseMultilinearExtension::<Fr>::rand(nv, &mut rng));
+        let half = Arc::new(DenseMultilinearExtension::rand(2, &mut rng));
+        let other_half = Arc::new(DenseMultilinearExtension::rand(2, &mut rng));
+        let constant = Arc::new(DenseMultilinearExtension::rand(0, &mut rng));
+
+        let mut poly = VirtualPolynomial::new(nv);
+        poly.add_partial_mle_list(
+            [(half.clone(), vec![0, 2]), (other_half.clone(), vec![1, 3])],
+            Fr::rand(&mut rng),
+        )?;
+        poly.add_partial_mle_list(
+            [(half.clone(), vec![0, 2]), (constant, vec![])],
+            Fr::rand(&mut rng),
+        )?;
+        poly.add_mle_list([full.clone()], Fr::rand(&mut rng))?;
+        assert_eq!(poly.flattened_ml_extensions.len(), 4);
+        assert_eq!(poly.mle_variables[0], Some(vec![0, 2]));
+        assert_eq!(poly.mle_variables[3], None);
+
+        let lifted = poly.lift();
+        assert_eq!(lifted.aux_info, poly.aux_info);
+        assert!(lifted
+            .flattened_ml_extensions
+            .iter()
+            .all(|mle| mle.num_vars == nv));
+        assert!(lifted.mle_variables.iter().all(Option::is_none));
+        let point: Vec<Fr> = (0..nv).map(|_| Fr::rand(&mut rng)).collect();
+        assert_eq!(poly.evaluate(&point)?, lifted.evaluate(&point)?);
+        assert_eq!(poly.mle_point(0, &point), vec![point[0], point[2]]);
+        let sum = &poly + &poly;
+        assert_eq!(
+            sum.evaluate(&point)?,
+            poly.evaluate(&point)? * Fr::from(2u64)
+        );
+
+        // bad path: variables not increasing, out of range, of the wrong
+        // number, or a multiplicand already added over other variables
+        for variables in [vec![2, 0], vec![0, nv], vec![0, 1, 2]] {
+            assert!(poly
+                .add_partial_mle_list([(half.clone(), variables)], Fr::one())
+                .is_err());
+        }
+        assert!(poly
+            .add_partial_mle_list([(other_half, vec![0, 1])], Fr::one())
+            .is_err());
+        assert!(poly
+            .add_partial_mle_list([(full, vec![0, 1, 2, 3])], Fr::one())
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_virtual_polynomial_serialization() -> Result<(), ArithErrors> {
+        let mut rng = test_rng();
+        let nv = 3;
+        let (mut poly, _) = VirtualPolynomial::<Fr>::rand(nv, (2, 3), 2, &mut rng)?;
+        let half = Arc::new(DenseMultilinearExtension::rand(2, &mut rng));
+        poly.add_partial_mle_list([(half, vec![0, 2])], Fr::one())?;
+        // a second product sharing an extension with the first one
+        let shared = poly.flattened_ml_extensions[poly.products[0].1[0]].clone();
+        let fresh = Arc::new(DenseMultilinearExtension::rand(nv, &mut rng));
+        poly.add_mle_list([shared, fresh], Fr::rand(&mut rng))?;
+
+        let mut bytes = vec![];
+        poly.serialize_compressed(&mut bytes)?;
+        assert_eq!(bytes.len(), poly.compressed_size());
+        let read = VirtualPolynomial::<Fr>::deserialize_compressed(bytes.as_slice())?;
+        assert_eq!(read.aux_info, poly.aux_info);
+        assert_eq!(read.products, poly.products);
+        assert_eq!(read.flattened_ml_extensions, poly.flattened_ml_extensions);
+        assert_eq!(read.mle_variables, poly.mle_variables);
+        // the shared extension is still stored once
+        let last = read.products.last().unwrap();
+        assert_eq!(last.1[0], read.products[0].1[0]);
+        for _ in 0..10 {
+            let point: Vec<Fr> = (0..nv).map(|_| Fr::rand(&mut rng)).collect();
+            assert_eq!(read.evaluate(&point)?, poly.evaluate(&point)?);
+        }
+        // the lookup table points to the deserialized extensions
+        let mut extended = read.clone();
+        extended.mul_by_mle(read.flattened_ml_extensions[0].clone(), Fr::one())?;
+        assert_eq!(
+            extended.flattened_ml_extensions.len(),
+            read.flattened_ml_extensions.len()
+        );
+
+        // bad path: a product refers to a missing extension
+        poly.products[0].1[0] = poly.flattened_ml_extensions.len();
+        let mut bytes = vec![];
+        poly.serialize_compressed(&mut bytes)?;
+        assert!(VirtualPolynomial::<Fr>::deserialize_compressed(bytes.as_slice()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sparse_virtual_polynomial() -> Result<(), ArithErrors> {
+        let mut rng = test_rng();
+        let nv = 4;
+        let (mut poly, _) = VirtualPolynomial::<Fr>::rand(nv, (2, 4), 3, &mut rng)?;
+        // a product with a zero coefficient and one with a zero multiplicand
+        let (zero_products, _) = VirtualPolynomial::<Fr>::rand(nv, (2, 3), 2, &mut rng)?;
+        let zero = Arc::new(DenseMultilinearExtension::from_evaluations_vec(
+            nv,
+            vec![Fr::zero(); 1 << nv],
+        ));
+        poly.add_mle_list(
+            zero_products.flattened_ml_extensions[..2].iter().cloned(),
+            Fr::zero(),
+        )?;
+        poly.add_mle_list(
+            [zero, zero_products.flattened_ml_extensions[0].clone()],
+            Fr::one(),
+        )?;
+
+        let sparse = SparseVirtualPolynomial::from(&poly);
+        assert_eq!(sparse.products.len(), 3);
+        assert!(sparse.aux_info.is_sparse);
+        assert_eq!(sparse.aux_info.max_degree, poly.aux_info.max_degree);
+        let dense = VirtualPolynomial::from(&sparse);
+        assert!(dense.aux_info.is_sparse);
+
+        for _ in 0..10 {
+            let point: Vec<Fr> = (0..nv).map(|_| Fr::rand(&mut rng)).collect();
+            let eval = poly.evaluate(&point)?;
+            assert_eq!(sparse.evaluate(&point)?, eval);
+            assert_eq!(dense.evaluate(&point)?, eval);
+        }
+        assert!(sparse.evaluate(&[Fr::one()]).is_err());
+
+        // the sparse flag is not serialized
+        let mut bytes = vec![];
+        dense.serialize_compressed(&mut bytes)?;
+        let read = VirtualPolynomial::<Fr>::deserialize_compressed(bytes.as_slice())?;
+        assert!(!read.aux_info.is_sparse);
+        assert_eq!(
+            read.evaluate(&[Fr::one(); 4])?,
+            poly.evaluate(&[Fr::one(); 4])?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_aux_info_transcript_bytes() {
+        let mut aux_info = VPAuxInfo::<Fr> {
+            max_degree: 3,
+            num_variables: 10,
+            per_variable_degree: vec![],
+            is_sparse: false,
+            phantom: PhantomData,
+        };
+        let hex: String = aux_info
+            .transcript_bytes()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        assert_eq!(hex, "03000000000000000a00000000000000");
+
+        // layout version 1 is the canonical serialization
+        let mut bytes = vec![];
+        aux_info.serialize_compressed(&mut bytes).unwrap();
+        assert_eq!(aux_info.transcript_bytes(), bytes);
+
+        // the degrees of the variables follow
+        aux_info.per_variable_degree = vec![1; 10];
+        aux_info.per_variable_degree[0] = 3;
+        let bytes = aux_info.transcript_bytes();
+        assert_eq!(bytes.len(), 16 + 8 * 10);
+        assert_eq!(bytes[16..24], 3u64.to_le_bytes());
+        assert_eq!(bytes[24..32], 1u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_variable_degrees() -> Result<(), ArithErrors> {
+        let mut rng = test_rng();
+        let nv = 4;
+        let full = Arc::new(DenseMultilinearExtension::<Fr>::rand(nv, &mut rng));
+        let half = Arc::new(DenseMultilinearExtension::rand(2, &mut rng));
+        let quarter = Arc::new(DenseMultilinearExtension::rand(1, &mut rng));
+
+        // half^2 * quarter + full
+        let mut poly = VirtualPolynomial::new(nv);
+        poly.add_partial_mle_list(
+            [
+                (half.clone(), vec![0, 1]),
+                (half, vec![0, 1]),
+                (quarter, vec![0]),
+            ],
+            Fr::rand(&mut rng),
+        )?;
+        poly.add_mle_list([full.clone()], Fr::rand(&mut rng))?;
+        assert_eq!(poly.variable_degrees(), vec![3, 2, 1, 1]);
+        assert_eq!(poly.aux_info.max_degree, 3);
+        assert!(poly.aux_info.per_variable_degree.is_empty());
+        assert_eq!(poly.aux_info.round_degree(1), 3);
+
+        // the degrees are kept up to date once tracked
+        let mut poly = poly.with_per_variable_degree();
+        assert_eq!(poly.aux_info.round_degree(1), 2);
+        poly.mul_by_mle(full, Fr::one())?;
+        assert_eq!(poly.aux_info.per_variable_degree, vec![4, 3, 2, 2]);
+        assert_eq!(poly.aux_info.max_degree, 4);
+        let other = Arc::new(DenseMultilinearExtension::rand(nv, &mut rng));
+        poly.add_mle_list([other.clone(), other], Fr::one())?;
+        assert_eq!(poly.aux_info.per_variable_degree, vec![4, 3, 2, 2]);
+        Ok(())
+    }
+
     #[test]
     fn test_eq_xr() {
         let mut rng = test_rng();
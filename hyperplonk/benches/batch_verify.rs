@@ -0,0 +1,85 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+use std::{sync::Arc, time::Instant};
+
+use ark_bls12_381::{Bls12_381, Fr};
+use ark_std::test_rng;
+use hyperplonk::{
+    batch::verify_all,
+    prelude::{CustomizedGates, HyperPlonkErrors, MockCircuit},
+    HyperPlonkSNARK,
+};
+use subroutines::{
+    pcs::{prelude::MultilinearKzgPCS, PolynomialCommitmentScheme},
+    poly_iop::PolyIOP,
+};
+
+const SUPPORTED_SIZE: usize = 12;
+const NUM_VARS: usize = 10;
+const NUM_PROOFS: usize = 200;
+
+fn main() -> Result<(), HyperPlonkErrors> {
+    let thread = rayon::current_num_threads();
+    println!(
+        "start batch verification benchmark with #{} threads",
+        thread
+    );
+    let mut rng = test_rng();
+    let pcs_srs = MultilinearKzgPCS::<Bls12_381>::gen_srs_for_testing(&mut rng, SUPPORTED_SIZE)?;
+
+    let gates = [
+        CustomizedGates::vanilla_plonk_gate(),
+        CustomizedGates::jellyfish_turbo_plonk_gate(),
+    ];
+    let mut circuits = vec![];
+    for gate in gates.iter() {
+        let circuit = MockCircuit::<Fr>::new(1 << NUM_VARS, gate);
+        let (pk, vk) =
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, MultilinearKzgPCS<Bls12_381>>>::preprocess(
+                &circuit.index,
+                &pcs_srs,
+            )?;
+        let proof =
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, MultilinearKzgPCS<Bls12_381>>>::prove(
+                &pk,
+                &circuit.public_inputs,
+                &circuit.witnesses,
+            )?;
+        circuits.push((Arc::new(vk), circuit.public_inputs, proof));
+    }
+    let items: Vec<_> = (0..NUM_PROOFS)
+        .map(|i| circuits[i % circuits.len()].clone())
+        .collect();
+
+    let start = Instant::now();
+    for (vk, pub_input, proof) in items.iter() {
+        assert!(<PolyIOP<Fr> as HyperPlonkSNARK<
+            Bls12_381,
+            MultilinearKzgPCS<Bls12_381>,
+        >>::verify(vk, pub_input, proof,)?);
+    }
+    let serial = start.elapsed();
+    println!(
+        "serial verification of {} proofs: {} us ({:.2} proofs/s)",
+        NUM_PROOFS,
+        serial.as_micros(),
+        NUM_PROOFS as f64 / serial.as_secs_f64()
+    );
+
+    let start = Instant::now();
+    let res = verify_all(&items, None);
+    let batched = start.elapsed();
+    assert!(res.iter().all(|r| r.is_ok()));
+    println!(
+        "batch verification of {} proofs: {} us ({:.2} proofs/s)",
+        NUM_PROOFS,
+        batched.as_micros(),
+        NUM_PROOFS as f64 / batched.as_secs_f64()
+    );
+
+    Ok(())
+}
@@ -7,15 +7,18 @@
 use std::{fs::File, io, time::Instant};
 
 use ark_bls12_381::{Bls12_381, Fr};
-use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Write};
+use ark_serialize::{CanonicalSerialize, Write};
 use ark_std::test_rng;
+#[cfg(feature = "profile")]
+use hyperplonk::prelude::prove_with_profile;
 use hyperplonk::{
-    prelude::{CustomizedGates, HyperPlonkErrors, MockCircuit},
+    prelude::{serialize_streaming, CustomizedGates, HyperPlonkErrors, MockCircuit},
+    sizing::estimate_proof_size,
     HyperPlonkSNARK,
 };
 use subroutines::{
     pcs::{
-        prelude::{MultilinearKzgPCS, MultilinearUniversalParams},
+        prelude::{MultilinearKzgPCS, MultilinearUniversalParams, SrsValidation},
         PolynomialCommitmentScheme,
     },
     poly_iop::PolyIOP,
@@ -27,6 +30,8 @@ const MAX_NUM_VARS: usize = 20;
 const MIN_CUSTOM_DEGREE: usize = 1;
 const MAX_CUSTOM_DEGREE: usize = 32;
 const HIGH_DEGREE_TEST_NV: usize = 15;
+const PROVER_THREADS: [usize; 4] = [1, 4, 8, 16];
+const PUBLIC_INPUT_NV: usize = 6;
 
 fn main() -> Result<(), HyperPlonkErrors> {
     let thread = rayon::current_num_threads();
@@ -47,6 +52,8 @@ fn main() -> Result<(), HyperPlonkErrors> {
     println!();
     bench_vanilla_plonk(&pcs_srs, thread)?;
     println!();
+    bench_public_input_plonk(&pcs_srs, thread)?;
+    println!();
     for degree in MIN_CUSTOM_DEGREE..=MAX_CUSTOM_DEGREE {
         bench_high_degree_plonk(&pcs_srs, degree, thread)?;
         println!();
@@ -56,14 +63,19 @@ fn main() -> Result<(), HyperPlonkErrors> {
     Ok(())
 }
 
+/// Read the SRS written by an earlier run of this benchmark, and check a
+/// sample of it in case the file is stale or corrupted.
 fn read_srs() -> Result<MultilinearUniversalParams<Bls12_381>, io::Error> {
-    let mut f = File::open("srs.params")?;
-    Ok(MultilinearUniversalParams::<Bls12_381>::deserialize_compressed_unchecked(&mut f).unwrap())
+    let f = File::open("srs.params")?;
+    let srs = MultilinearUniversalParams::<Bls12_381>::deserialize_trusted(f).unwrap();
+    srs.validate(SrsValidation::Sampled, &mut test_rng())
+        .unwrap();
+    Ok(srs)
 }
 
 fn write_srs(pcs_srs: &MultilinearUniversalParams<Bls12_381>) {
     let mut f = File::create("srs.params").unwrap();
-    pcs_srs.serialize_uncompressed(&mut f).unwrap();
+    pcs_srs.serialize_compressed(&mut f).unwrap();
 }
 
 fn bench_vanilla_plonk(
@@ -80,6 +92,21 @@ fn bench_vanilla_plonk(
     Ok(())
 }
 
+fn bench_public_input_plonk(
+    pcs_srs: &MultilinearUniversalParams<Bls12_381>,
+    thread: usize,
+) -> Result<(), HyperPlonkErrors> {
+    let filename = format!("public input threads {}.txt", thread);
+    let mut file = File::create(filename).unwrap();
+    let vanilla_gate = CustomizedGates::vanilla_plonk_gate();
+    for nv in MIN_NUM_VARS..=MAX_NUM_VARS {
+        let public: Vec<Fr> = (0..1u64 << PUBLIC_INPUT_NV).map(Fr::from).collect();
+        bench_mock_circuit_zkp_public_input_helper(&mut file, nv, &vanilla_gate, &public, pcs_srs)?;
+    }
+
+    Ok(())
+}
+
 fn bench_jellyfish_plonk(
     pcs_srs: &MultilinearUniversalParams<Bls12_381>,
     thread: usize,
@@ -126,6 +153,7 @@ fn bench_mock_circuit_zkp_helper(
     let circuit = MockCircuit::<Fr>::new(1 << nv, gate);
     assert!(circuit.is_satisfied());
     let index = circuit.index;
+    println!("circuit of {} variables:\n{}", nv, index.statistics());
     //==========================================================
     // generate pk and vks
     let start = Instant::now();
@@ -144,6 +172,11 @@ fn bench_mock_circuit_zkp_helper(
         <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, MultilinearKzgPCS<Bls12_381>>>::preprocess(
             &index, pcs_srs,
         )?;
+    let vk_size = vk.compress().compressed_size();
+    println!(
+        "compressed verifying key for {} variables: {} bytes",
+        nv, vk_size
+    );
     //==========================================================
     // generate a proof
     let start = Instant::now();
@@ -161,13 +194,56 @@ fn bench_mock_circuit_zkp_helper(
         nv,
         start.elapsed().as_micros() / repetition as u128
     );
-    file.write_all(format!("{} {}\n", nv, t).as_ref()).unwrap();
+    file.write_all(format!("{} {} {}\n", nv, t, vk_size).as_ref())
+        .unwrap();
+
+    // proving on pools of several sizes, with the speedup over one thread
+    let mut single_thread = None;
+    for num_threads in PROVER_THREADS {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()
+            .unwrap();
+        let start = Instant::now();
+        for _ in 0..repetition {
+            pool.install(|| {
+                <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, MultilinearKzgPCS<Bls12_381>>>::prove(
+                    &pk,
+                    &circuit.public_inputs,
+                    &circuit.witnesses,
+                )
+            })?;
+        }
+        let elapsed = start.elapsed().as_micros() / repetition as u128;
+        let single_thread = *single_thread.get_or_insert(elapsed);
+        println!(
+            "proving for {} variables with {} threads: {} us ({:.2}x)",
+            nv,
+            num_threads,
+            elapsed,
+            single_thread as f64 / elapsed as f64
+        );
+    }
 
     let proof = <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, MultilinearKzgPCS<Bls12_381>>>::prove(
         &pk,
         &circuit.public_inputs,
         &circuit.witnesses,
     )?;
+    // the estimated proof size is exact
+    let mut proof_bytes = vec![];
+    serialize_streaming(&proof, &mut proof_bytes)?;
+    let estimate = estimate_proof_size::<Bls12_381>(&index);
+    assert_eq!(estimate.proof_bytes, proof_bytes.len());
+    println!(
+        "proof for {} variables: {} bytes, {} sum check rounds, {} openings",
+        nv, estimate.proof_bytes, estimate.num_sumcheck_rounds, estimate.num_pcs_openings
+    );
+    #[cfg(feature = "profile")]
+    {
+        let (_, profile) = prove_with_profile(&pk, &circuit.public_inputs, &circuit.witnesses)?;
+        println!("prover phases for {} variables:\n{}", nv, profile);
+    }
     //==========================================================
     // verify a proof
     let start = Instant::now();
@@ -187,3 +263,91 @@ fn bench_mock_circuit_zkp_helper(
     );
     Ok(())
 }
+
+/// Prove and verify a mock circuit whose public input is `public`, and check
+/// that a wrong public input is rejected.
+fn bench_mock_circuit_zkp_public_input_helper(
+    file: &mut File,
+    nv: usize,
+    gate: &CustomizedGates,
+    public: &[Fr],
+    pcs_srs: &MultilinearUniversalParams<Bls12_381>,
+) -> Result<(), HyperPlonkErrors> {
+    let repetition = if nv < 10 {
+        5
+    } else if nv < 20 {
+        2
+    } else {
+        1
+    };
+
+    //==========================================================
+    let circuit = MockCircuit::<Fr>::new_with_public_inputs(1 << nv, gate, public);
+    assert!(circuit.is_satisfied());
+    println!(
+        "circuit of {} variables with {} public inputs:\n{}",
+        nv,
+        public.len(),
+        circuit.index.statistics()
+    );
+    let (pk, vk) =
+        <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, MultilinearKzgPCS<Bls12_381>>>::preprocess(
+            &circuit.index,
+            pcs_srs,
+        )?;
+    //==========================================================
+    // generate a proof
+    let start = Instant::now();
+    for _ in 0..repetition {
+        let _proof =
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, MultilinearKzgPCS<Bls12_381>>>::prove(
+                &pk,
+                public,
+                &circuit.witnesses,
+            )?;
+    }
+    let t = start.elapsed().as_micros() / repetition as u128;
+    println!(
+        "proving for {} variables with {} public inputs: {} us",
+        nv,
+        public.len(),
+        t
+    );
+    file.write_all(format!("{} {} {}\n", nv, public.len(), t).as_ref())
+        .unwrap();
+
+    let proof = <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, MultilinearKzgPCS<Bls12_381>>>::prove(
+        &pk,
+        public,
+        &circuit.witnesses,
+    )?;
+    //==========================================================
+    // verify a proof
+    let start = Instant::now();
+    for _ in 0..repetition {
+        let verify =
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, MultilinearKzgPCS<Bls12_381>>>::verify(
+                &vk, public, &proof,
+            )?;
+        assert!(verify);
+    }
+    println!(
+        "verifying for {} variables with {} public inputs: {} us",
+        nv,
+        public.len(),
+        start.elapsed().as_micros() / repetition as u128
+    );
+
+    // a wrong public input is rejected
+    let mut bad_public = public.to_vec();
+    bad_public[0] += Fr::from(1u64);
+    assert!(
+        !<PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, MultilinearKzgPCS<Bls12_381>>>::verify(
+            &vk,
+            &bad_public,
+            &proof,
+        )
+        .unwrap_or(false)
+    );
+    Ok(())
+}
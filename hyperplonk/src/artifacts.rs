@@ -0,0 +1,920 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! Preprocessing in two phases.
+//!
+//! [`analyze`] does the work that only depends on the circuit: it validates
+//! the index and builds the selector, permutation, fixed and table oracles.
+//! [`commit_artifacts`] does the work that depends on the SRS: it trims the
+//! SRS and commits to the oracles. `HyperPlonkSNARK::preprocess` is the
+//! composition of the two, so that keys can be regenerated for a new SRS
+//! without analyzing the circuit again, and the [`CircuitArtifacts`] of a
+//! large circuit can be cached on disk.
+//!
+//! When only the selector values of a circuit change,
+//! [`HyperPlonkVerifyingKey::update_selectors`] updates existing keys
+//! instead.
+
+use crate::{
+    committer::column_digest,
+    custom_gate::CustomizedGates,
+    errors::HyperPlonkErrors,
+    selectors::{SelectorColumn, SelectorDescription},
+    sizing::required_srs_num_vars,
+    structs::{HyperPlonkIndex, HyperPlonkParams, HyperPlonkProvingKey, HyperPlonkVerifyingKey},
+    utils::compact_permutation,
+    PROTOCOL_VERSION,
+};
+use ark_ec::pairing::Pairing;
+use ark_ff::PrimeField;
+use ark_poly::DenseMultilinearExtension;
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, Read, SerializationError, Valid, Validate,
+    Write,
+};
+use ark_std::{cfg_iter, end_timer, start_timer};
+#[cfg(feature = "parallel")]
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use std::sync::Arc;
+use subroutines::pcs::prelude::{Commitment, PolynomialCommitmentScheme};
+
+/// The output of the circuit analysis: the instance parameters and the
+/// preprocessed polynomials, without any commitment.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CircuitArtifacts<F: PrimeField> {
+    /// Hyperplonk instance parameters
+    pub params: HyperPlonkParams,
+    /// The preprocessed permutation polynomials
+    pub permutation_oracles: Vec<Arc<DenseMultilinearExtension<F>>>,
+    /// The preprocessed selector polynomials
+    pub selector_oracles: Vec<Arc<DenseMultilinearExtension<F>>>,
+    /// The preprocessed fixed polynomials
+    pub fixed_oracles: Vec<Arc<DenseMultilinearExtension<F>>>,
+    /// The lookup tables, repeated to the number of variables of the circuit
+    pub table_oracles: Vec<Arc<DenseMultilinearExtension<F>>>,
+}
+
+impl<F: PrimeField> CircuitArtifacts<F> {
+    /// Check that the oracles match the instance parameters.
+    fn check_consistency(&self) -> Result<(), HyperPlonkErrors> {
+        let num_vars = self.params.num_variables();
+        for (name, oracles, expected) in [
+            (
+                "permutation",
+                &self.permutation_oracles,
+                self.params.num_permutation_columns(),
+            ),
+            (
+                "selector",
+                &self.selector_oracles,
+                self.params.num_selector_columns(),
+            ),
+            ("fixed", &self.fixed_oracles, self.params.num_fixed_columns),
+            ("table", &self.table_oracles, self.table_oracles.len()),
+        ] {
+            if oracles.len() != expected {
+                return Err(HyperPlonkErrors::InvalidParameters(format!(
+                    "Number of {} oracles is not correct: got {}, expect {}",
+                    name,
+                    oracles.len(),
+                    expected
+                )));
+            }
+            if let Some(oracle) = oracles.iter().find(|o| o.num_vars != num_vars) {
+                return Err(HyperPlonkErrors::InvalidParameters(format!(
+                    "{} oracle has different number of vars: {} vs {}",
+                    name, oracle.num_vars, num_vars
+                )));
+            }
+        }
+        check_lookups(&self.params, self.table_oracles.len())
+    }
+}
+
+/// Check that the lookups of the gate of `params` are into some of the
+/// `num_tables` tables.
+pub(crate) fn check_lookups(
+    params: &HyperPlonkParams,
+    num_tables: usize,
+) -> Result<(), HyperPlonkErrors> {
+    match params
+        .gate_func
+        .lookups
+        .iter()
+        .find(|(_, table_id)| *table_id >= num_tables)
+    {
+        Some((_, table_id)) => Err(HyperPlonkErrors::InvalidParameters(format!(
+            "Lookup into table {} out of {} tables",
+            table_id, num_tables
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Analyze the circuit `index`: validate it and build its preprocessed
+/// polynomials. Nothing here depends on the SRS.
+pub fn analyze<F: PrimeField>(
+    index: &HyperPlonkIndex<F>,
+) -> Result<CircuitArtifacts<F>, HyperPlonkErrors> {
+    let start = start_timer!(|| "analyze circuit");
+    let num_vars = index.num_variables();
+
+    // build permutation oracles
+    let chunk_size = 1 << num_vars;
+    if index.permutation.len() < index.num_permutation_columns() * chunk_size {
+        return Err(HyperPlonkErrors::InvalidParameters(format!(
+            "Permutation is too short: got {}, expect at least {}",
+            index.permutation.len(),
+            index.num_permutation_columns() * chunk_size
+        )));
+    }
+    if index.fixed_columns.len() != index.num_fixed_columns() {
+        return Err(HyperPlonkErrors::InvalidParameters(format!(
+            "Number of fixed columns is not correct: got {}, expect {}",
+            index.fixed_columns.len(),
+            index.num_fixed_columns()
+        )));
+    }
+    let permutation_oracles = (0..index.num_permutation_columns())
+        .map(|i| {
+            Arc::new(DenseMultilinearExtension::from_evaluations_slice(
+                num_vars,
+                &index.permutation[i * chunk_size..(i + 1) * chunk_size],
+            ))
+        })
+        .collect();
+
+    // build selector oracles
+    let selector_oracles = index
+        .selectors
+        .iter()
+        .map(|s| Arc::new(DenseMultilinearExtension::from(s)))
+        .collect();
+
+    // build fixed oracles
+    let fixed_oracles: Vec<Arc<DenseMultilinearExtension<F>>> = index
+        .fixed_columns
+        .iter()
+        .map(|s| Arc::new(DenseMultilinearExtension::from(s)))
+        .collect();
+    for fixed_oracle in fixed_oracles.iter() {
+        if fixed_oracle.num_vars != num_vars {
+            return Err(HyperPlonkErrors::InvalidParameters(format!(
+                "Fixed column has different number of vars: {} vs {}",
+                fixed_oracle.num_vars, num_vars
+            )));
+        }
+    }
+
+    // build table oracles, repeating each table to fill the rows
+    let table_oracles = index
+        .tables
+        .iter()
+        .map(|table| {
+            if table.num_vars > num_vars {
+                return Err(HyperPlonkErrors::InvalidParameters(format!(
+                    "Lookup table has more vars than the circuit: {} vs {}",
+                    table.num_vars, num_vars
+                )));
+            }
+            let len = table.evaluations.len();
+            Ok(Arc::new(DenseMultilinearExtension::from_evaluations_vec(
+                num_vars,
+                (0..chunk_size)
+                    .map(|i| table.evaluations[i % len])
+                    .collect(),
+            )))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    check_lookups(&index.params, table_oracles.len())?;
+
+    end_timer!(start);
+    Ok(CircuitArtifacts {
+        params: index.params.clone(),
+        permutation_oracles,
+        selector_oracles,
+        fixed_oracles,
+        table_oracles,
+    })
+}
+
+/// Trim `pcs_srs` for the circuit of `artifacts` and commit to its
+/// preprocessed polynomials.
+///
+/// The keys share the selector and fixed oracles of `artifacts`, so the same
+/// artifacts can be committed with several SRS at little cost. The proving
+/// key keeps the permutation in compact form only.
+#[allow(clippy::type_complexity)]
+pub fn commit_artifacts<E, PCS>(
+    artifacts: &CircuitArtifacts<E::ScalarField>,
+    pcs_srs: &PCS::SRS,
+) -> Result<(HyperPlonkProvingKey<E, PCS>, HyperPlonkVerifyingKey<E, PCS>), HyperPlonkErrors>
+where
+    E: Pairing,
+    PCS: PolynomialCommitmentScheme<
+        E,
+        Polynomial = Arc<DenseMultilinearExtension<E::ScalarField>>,
+        Commitment = Commitment<E>,
+    >,
+{
+    let start = start_timer!(|| "commit circuit artifacts");
+    artifacts.check_consistency()?;
+    let srs_num_vars = required_srs_num_vars(&artifacts.params);
+
+    // extract PCS prover and verifier keys from SRS: a too small SRS is
+    // rejected here, before any proof
+    let (pcs_prover_param, pcs_verifier_param) = PCS::trim(pcs_srs, None, Some(srs_num_vars))
+        .map_err(|e| {
+            HyperPlonkErrors::InvalidParameters(format!(
+                "the SRS does not support the {} variables the circuit requires: {}",
+                srs_num_vars, e
+            ))
+        })?;
+
+    let perm_comms = cfg_iter!(artifacts.permutation_oracles)
+        .map(|poly| PCS::commit(&pcs_prover_param, poly))
+        .collect::<Result<Vec<_>, _>>()?;
+    let selectors = cfg_iter!(artifacts.selector_oracles)
+        .map(|poly| describe_selector::<E, PCS>(&pcs_prover_param, poly))
+        .collect::<Result<Vec<_>, HyperPlonkErrors>>()?;
+    let fixed_commitments = cfg_iter!(artifacts.fixed_oracles)
+        .map(|poly| PCS::commit(&pcs_prover_param, poly))
+        .collect::<Result<Vec<_>, _>>()?;
+    let table_commitments = cfg_iter!(artifacts.table_oracles)
+        .map(|poly| PCS::commit(&pcs_prover_param, poly))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    end_timer!(start);
+    Ok((
+        HyperPlonkProvingKey {
+            params: artifacts.params.clone(),
+            permutation: Arc::new(compact_permutation(&artifacts.permutation_oracles)?),
+            permutation_oracles_cache: None,
+            selector_oracles: artifacts.selector_oracles.clone(),
+            selectors: selectors.clone(),
+            permutation_commitments: perm_comms.clone(),
+            fixed_oracles: artifacts.fixed_oracles.clone(),
+            fixed_commitments: fixed_commitments.clone(),
+            table_oracles: artifacts.table_oracles.clone(),
+            table_commitments: table_commitments.clone(),
+            pcs_param: Arc::new(pcs_prover_param),
+        },
+        HyperPlonkVerifyingKey {
+            protocol_version: PROTOCOL_VERSION,
+            params: artifacts.params.clone(),
+            pcs_param: pcs_verifier_param,
+            selectors,
+            perm_commitments: perm_comms,
+            fixed_commitments,
+            table_commitments,
+        },
+    ))
+}
+
+/// Describe the selector `poly` for the verifier: compactly when its
+/// description is smaller than its commitment, and committed otherwise.
+fn describe_selector<E, PCS>(
+    pcs_prover_param: &PCS::ProverParam,
+    poly: &Arc<DenseMultilinearExtension<E::ScalarField>>,
+) -> Result<SelectorDescription<E::ScalarField, Commitment<E>>, HyperPlonkErrors>
+where
+    E: Pairing,
+    PCS: PolynomialCommitmentScheme<
+        E,
+        Polynomial = Arc<DenseMultilinearExtension<E::ScalarField>>,
+        Commitment = Commitment<E>,
+    >,
+{
+    Ok(match SelectorDescription::compact(&poly.evaluations) {
+        Some(description) => description,
+        None => SelectorDescription::Committed(PCS::commit(pcs_prover_param, poly)?),
+    })
+}
+
+impl<E, PCS> HyperPlonkVerifyingKey<E, PCS>
+where
+    E: Pairing,
+    PCS: PolynomialCommitmentScheme<
+        E,
+        Polynomial = Arc<DenseMultilinearExtension<E::ScalarField>>,
+        Commitment = Commitment<E>,
+    >,
+{
+    /// Update the keys of a circuit whose selector values change, but not
+    /// its gates, number of variables, wiring or lookup tables.
+    ///
+    /// Only the selectors whose digest changed are described or committed
+    /// again, with the PCS parameters of `pk`; the permutation and the fixed
+    /// columns are kept. `pk` is updated in place, only if the update
+    /// succeeds, and the updated verifying key is returned. The keys are the
+    /// same as the ones of a full preprocessing of the updated circuit.
+    pub fn update_selectors(
+        &self,
+        pk: &mut HyperPlonkProvingKey<E, PCS>,
+        new_selectors: &[SelectorColumn<E::ScalarField>],
+    ) -> Result<Self, HyperPlonkErrors> {
+        let start = start_timer!(|| "update selectors");
+        if pk.params != self.params || pk.selectors != self.selectors {
+            return Err(HyperPlonkErrors::InvalidParameters(
+                "The proving key does not match the verifying key".to_string(),
+            ));
+        }
+        if new_selectors.len() != self.selectors.len()
+            || pk.selector_oracles.len() != self.selectors.len()
+        {
+            return Err(HyperPlonkErrors::InvalidParameters(format!(
+                "Number of selector columns is not correct: got {}, expect {}",
+                new_selectors.len(),
+                self.selectors.len()
+            )));
+        }
+        let num_constraints = 1 << self.params.num_variables();
+        if let Some(column) = new_selectors
+            .iter()
+            .find(|column| column.0.len() != num_constraints)
+        {
+            return Err(HyperPlonkErrors::InvalidParameters(format!(
+                "Selector column has wrong length: got {}, expect {}",
+                column.0.len(),
+                num_constraints
+            )));
+        }
+
+        let mut updates = Vec::new();
+        for (i, (column, oracle)) in new_selectors
+            .iter()
+            .zip(pk.selector_oracles.iter())
+            .enumerate()
+        {
+            if column_digest(&column.0)? != column_digest(&oracle.evaluations)? {
+                let poly = Arc::new(DenseMultilinearExtension::from(column));
+                let description = describe_selector::<E, PCS>(&pk.pcs_param, &poly)?;
+                updates.push((i, poly, description));
+            }
+        }
+        for (i, poly, description) in updates {
+            pk.selector_oracles[i] = poly;
+            pk.selectors[i] = description;
+        }
+
+        end_timer!(start);
+        Ok(Self {
+            protocol_version: self.protocol_version,
+            params: self.params.clone(),
+            pcs_param: self.pcs_param.clone(),
+            selectors: pk.selectors.clone(),
+            perm_commitments: self.perm_commitments.clone(),
+            fixed_commitments: self.fixed_commitments.clone(),
+            table_commitments: self.table_commitments.clone(),
+        })
+    }
+}
+
+// The artifacts are serialized as the instance parameters, see
+// `serialize_params`, followed by the four vectors of oracles.
+
+impl<F: PrimeField> CanonicalSerialize for CircuitArtifacts<F> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        serialize_params(&self.params, &mut writer, compress)?;
+        for oracles in [
+            &self.permutation_oracles,
+            &self.selector_oracles,
+            &self.fixed_oracles,
+            &self.table_oracles,
+        ] {
+            oracles.len().serialize_with_mode(&mut writer, compress)?;
+            for oracle in oracles.iter() {
+                oracle.as_ref().serialize_with_mode(&mut writer, compress)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        let usize_size = 0usize.serialized_size(compress);
+        let oracles_size: usize = [
+            &self.permutation_oracles,
+            &self.selector_oracles,
+            &self.fixed_oracles,
+            &self.table_oracles,
+        ]
+        .iter()
+        .map(|oracles| {
+            usize_size
+                + oracles
+                    .iter()
+                    .map(|oracle| oracle.as_ref().serialized_size(compress))
+                    .sum::<usize>()
+        })
+        .sum();
+        params_serialized_size(&self.params, compress) + oracles_size
+    }
+}
+
+impl<F: PrimeField> Valid for CircuitArtifacts<F> {
+    fn check(&self) -> Result<(), SerializationError> {
+        self.check_consistency()
+            .map_err(|_| SerializationError::InvalidData)
+    }
+}
+
+impl<F: PrimeField> CanonicalDeserialize for CircuitArtifacts<F> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let params = deserialize_params(&mut reader, compress, validate)?;
+        let mut oracles = Vec::with_capacity(4);
+        for _ in 0..4 {
+            let len = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+            let mut column = Vec::new();
+            for _ in 0..len {
+                column.push(Arc::new(DenseMultilinearExtension::deserialize_with_mode(
+                    &mut reader,
+                    compress,
+                    validate,
+                )?));
+            }
+            oracles.push(column);
+        }
+        let table_oracles = oracles.pop().unwrap_or_default();
+        let fixed_oracles = oracles.pop().unwrap_or_default();
+        let selector_oracles = oracles.pop().unwrap_or_default();
+        let permutation_oracles = oracles.pop().unwrap_or_default();
+
+        let res = Self {
+            params,
+            permutation_oracles,
+            selector_oracles,
+            fixed_oracles,
+            table_oracles,
+        };
+        if validate == Validate::Yes {
+            Valid::check(&res)?;
+        }
+        Ok(res)
+    }
+}
+
+/// Serialize the instance parameters, with the gate coefficients as two's
+/// complement `u64` and each selector index preceded by a presence flag,
+/// followed by the lookups of the gate.
+pub(crate) fn serialize_params<W: Write>(
+    params: &HyperPlonkParams,
+    mut writer: W,
+    compress: Compress,
+) -> Result<(), SerializationError> {
+    params
+        .num_constraints
+        .serialize_with_mode(&mut writer, compress)?;
+    params
+        .num_pub_input
+        .serialize_with_mode(&mut writer, compress)?;
+    params
+        .num_fixed_columns
+        .serialize_with_mode(&mut writer, compress)?;
+    params
+        .gate_func
+        .gates
+        .len()
+        .serialize_with_mode(&mut writer, compress)?;
+    for (coeff, selector, witnesses) in params.gate_func.gates.iter() {
+        (*coeff as u64).serialize_with_mode(&mut writer, compress)?;
+        selector
+            .is_some()
+            .serialize_with_mode(&mut writer, compress)?;
+        if let Some(selector) = selector {
+            selector.serialize_with_mode(&mut writer, compress)?;
+        }
+        witnesses.serialize_with_mode(&mut writer, compress)?;
+    }
+    params
+        .gate_func
+        .lookups
+        .len()
+        .serialize_with_mode(&mut writer, compress)?;
+    for (witness, table_id) in params.gate_func.lookups.iter() {
+        witness.serialize_with_mode(&mut writer, compress)?;
+        table_id.serialize_with_mode(&mut writer, compress)?;
+    }
+    Ok(())
+}
+
+/// The size of the instance parameters serialized by [`serialize_params`].
+pub(crate) fn params_serialized_size(params: &HyperPlonkParams, compress: Compress) -> usize {
+    let usize_size = 0usize.serialized_size(compress);
+    let gates_size: usize = params
+        .gate_func
+        .gates
+        .iter()
+        .map(|(_, selector, witnesses)| {
+            usize_size
+                + true.serialized_size(compress)
+                + selector.map_or(0, |_| usize_size)
+                + witnesses.serialized_size(compress)
+        })
+        .sum();
+    let lookups_size = 2 * usize_size * params.gate_func.lookups.len();
+    5 * usize_size + gates_size + lookups_size
+}
+
+/// Deserialize instance parameters serialized by [`serialize_params`].
+pub(crate) fn deserialize_params<R: Read>(
+    mut reader: R,
+    compress: Compress,
+    validate: Validate,
+) -> Result<HyperPlonkParams, SerializationError> {
+    let num_constraints = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+    let num_pub_input = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+    let num_fixed_columns = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+    let num_gates = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+    let mut gates = Vec::new();
+    for _ in 0..num_gates {
+        let coeff = u64::deserialize_with_mode(&mut reader, compress, validate)? as i64;
+        let selector = if bool::deserialize_with_mode(&mut reader, compress, validate)? {
+            Some(usize::deserialize_with_mode(
+                &mut reader,
+                compress,
+                validate,
+            )?)
+        } else {
+            None
+        };
+        let witnesses = Vec::<usize>::deserialize_with_mode(&mut reader, compress, validate)?;
+        gates.push((coeff, selector, witnesses));
+    }
+    let num_lookups = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+    let mut lookups = Vec::new();
+    for _ in 0..num_lookups {
+        let witness = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+        let table_id = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+        lookups.push((witness, table_id));
+    }
+    Ok(HyperPlonkParams {
+        num_constraints,
+        num_pub_input,
+        gate_func: CustomizedGates { gates, lookups },
+        num_fixed_columns,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock::MockCircuit, streaming::serialize_streaming, witness::WitnessColumn, HyperPlonkSNARK,
+    };
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ff::{Field, UniformRand};
+    use ark_std::{
+        rand::{rngs::StdRng, RngCore, SeedableRng},
+        test_rng,
+    };
+    use subroutines::{pcs::prelude::MultilinearKzgPCS, poly_iop::PolyIOP};
+
+    type Kzg = MultilinearKzgPCS<Bls12_381>;
+
+    fn to_bytes<T: CanonicalSerialize>(x: &T) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        x.serialize_compressed(&mut bytes)
+            .expect("serialization into a vector cannot fail");
+        bytes
+    }
+
+    fn assert_same_keys(
+        (pk, vk): &(
+            HyperPlonkProvingKey<Bls12_381, Kzg>,
+            HyperPlonkVerifyingKey<Bls12_381, Kzg>,
+        ),
+        (other_pk, other_vk): &(
+            HyperPlonkProvingKey<Bls12_381, Kzg>,
+            HyperPlonkVerifyingKey<Bls12_381, Kzg>,
+        ),
+    ) {
+        assert_eq!(pk.params, other_pk.params);
+        assert_eq!(pk.permutation, other_pk.permutation);
+        assert_eq!(pk.selector_oracles, other_pk.selector_oracles);
+        assert_eq!(pk.fixed_oracles, other_pk.fixed_oracles);
+        assert_eq!(pk.selectors, other_pk.selectors);
+        assert_eq!(pk.permutation_commitments, other_pk.permutation_commitments);
+        assert_eq!(pk.fixed_commitments, other_pk.fixed_commitments);
+        assert_eq!(to_bytes(&*pk.pcs_param), to_bytes(&*other_pk.pcs_param));
+        assert_eq!(vk.params, other_vk.params);
+        assert_eq!(to_bytes(&vk.pcs_param), to_bytes(&other_vk.pcs_param));
+        assert_eq!(vk.selectors, other_vk.selectors);
+        assert_eq!(vk.perm_commitments, other_vk.perm_commitments);
+        assert_eq!(vk.fixed_commitments, other_vk.fixed_commitments);
+    }
+
+    #[test]
+    fn test_preprocess_trimmed_srs() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let pcs_srs = Kzg::gen_srs_for_testing(&mut rng, 8)?;
+
+        for nv in 2..=4 {
+            let circuit = MockCircuit::<Fr>::new(1 << nv, &CustomizedGates::vanilla_plonk_gate());
+            let srs_num_vars = required_srs_num_vars(&circuit.index.params);
+            let trimmed = pcs_srs.trim_universal(srs_num_vars)?;
+            let keys = <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::preprocess(
+                &circuit.index,
+                &trimmed,
+            )?;
+            assert_same_keys(
+                &keys,
+                &<PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::preprocess(
+                    &circuit.index,
+                    &pcs_srs,
+                )?,
+            );
+            let (pk, vk) = keys;
+
+            let proof = <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::prove(
+                &pk,
+                &circuit.public_inputs,
+                &circuit.witnesses,
+            )?;
+            assert!(<PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::verify(
+                &vk,
+                &circuit.public_inputs,
+                &proof,
+            )?);
+
+            // bad path: an SRS trimmed below the circuit size
+            let too_small = pcs_srs.trim_universal(srs_num_vars - 1)?;
+            assert!(
+                <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::preprocess(
+                    &circuit.index,
+                    &too_small
+                )
+                .is_err()
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_two_phase_preprocess() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let nv = 4;
+        let pcs_srs = Kzg::gen_srs_for_testing(&mut rng, nv)?;
+
+        let gate = CustomizedGates::jellyfish_turbo_plonk_gate();
+        let circuit = MockCircuit::<Fr>::new(1 << nv, &gate);
+        let keys =
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::preprocess(&circuit.index, &pcs_srs)?;
+
+        let artifacts = analyze(&circuit.index)?;
+        let two_phase_keys = commit_artifacts::<Bls12_381, Kzg>(&artifacts, &pcs_srs)?;
+        assert_same_keys(&keys, &two_phase_keys);
+
+        // the artifacts round trip through their serialization
+        for compress in [Compress::Yes, Compress::No] {
+            let mut bytes = Vec::new();
+            artifacts.serialize_with_mode(&mut bytes, compress)?;
+            assert_eq!(bytes.len(), artifacts.serialized_size(compress));
+            let deserialized =
+                CircuitArtifacts::<Fr>::deserialize_with_mode(&bytes[..], compress, Validate::Yes)?;
+            assert_eq!(deserialized, artifacts);
+            assert_same_keys(
+                &keys,
+                &commit_artifacts::<Bls12_381, Kzg>(&deserialized, &pcs_srs)?,
+            );
+        }
+
+        // and recombine with another SRS of the same size
+        let other_srs = Kzg::gen_srs_for_testing(&mut StdRng::seed_from_u64(42), nv)?;
+        let (other_pk, other_vk) = commit_artifacts::<Bls12_381, Kzg>(&artifacts, &other_srs)?;
+        assert_ne!(other_vk.selectors, keys.1.selectors);
+        let proof = <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::prove(
+            &other_pk,
+            &circuit.public_inputs,
+            &circuit.witnesses,
+        )?;
+        assert!(<PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::verify(
+            &other_vk,
+            &circuit.public_inputs,
+            &proof,
+        )?);
+        assert!(!matches!(
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::verify(
+                &keys.1,
+                &circuit.public_inputs,
+                &proof,
+            ),
+            Ok(true)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_inconsistent_artifacts() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let nv = 3;
+        let pcs_srs = Kzg::gen_srs_for_testing(&mut rng, nv)?;
+
+        let gate = CustomizedGates::vanilla_plonk_gate();
+        let circuit = MockCircuit::<Fr>::new(1 << nv, &gate);
+        let artifacts = analyze(&circuit.index)?;
+
+        // a missing selector oracle
+        let mut bad_artifacts = artifacts.clone();
+        bad_artifacts.selector_oracles.pop();
+        assert!(commit_artifacts::<Bls12_381, Kzg>(&bad_artifacts, &pcs_srs).is_err());
+        let mut bytes = Vec::new();
+        bad_artifacts.serialize_compressed(&mut bytes)?;
+        assert!(CircuitArtifacts::<Fr>::deserialize_compressed(&bytes[..]).is_err());
+        assert!(CircuitArtifacts::<Fr>::deserialize_compressed_unchecked(&bytes[..]).is_ok());
+
+        // an oracle with the wrong number of variables
+        let mut bad_artifacts = artifacts;
+        bad_artifacts.permutation_oracles[0] = Arc::new(
+            DenseMultilinearExtension::from_evaluations_vec(nv - 1, vec![Fr::from(1u64); 4]),
+        );
+        assert!(commit_artifacts::<Bls12_381, Kzg>(&bad_artifacts, &pcs_srs).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_permutation_oracles_on_demand() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let nv = 4;
+        let pcs_srs = Kzg::gen_srs_for_testing(&mut rng, nv)?;
+
+        let gate = CustomizedGates::jellyfish_turbo_plonk_gate();
+        let circuit = MockCircuit::<Fr>::new(1 << nv, &gate);
+        let artifacts = analyze(&circuit.index)?;
+        let (pk, vk) = commit_artifacts::<Bls12_381, Kzg>(&artifacts, &pcs_srs)?;
+
+        // the tables built on demand are the ones the key used to store
+        assert!(pk.permutation_oracles_cache.is_none());
+        assert_eq!(pk.permutation_oracles(), artifacts.permutation_oracles);
+        let mut cached_pk = pk.clone();
+        cached_pk.cache_permutation_oracles();
+        assert_eq!(
+            cached_pk.permutation_oracles_cache.as_ref(),
+            Some(&artifacts.permutation_oracles)
+        );
+
+        // and the proofs are the same with or without the cache
+        let proof = <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::prove(
+            &pk,
+            &circuit.public_inputs,
+            &circuit.witnesses,
+        )?;
+        let cached_proof = <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::prove(
+            &cached_pk,
+            &circuit.public_inputs,
+            &circuit.witnesses,
+        )?;
+        let (mut bytes, mut cached_bytes) = (Vec::new(), Vec::new());
+        serialize_streaming(&proof, &mut bytes)?;
+        serialize_streaming(&cached_proof, &mut cached_bytes)?;
+        assert_eq!(bytes, cached_bytes);
+        assert!(<PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::verify(
+            &vk,
+            &circuit.public_inputs,
+            &proof
+        )?);
+
+        // a permutation value that is not a cell index
+        let mut bad_artifacts = artifacts;
+        bad_artifacts.permutation_oracles[0] = Arc::new(
+            DenseMultilinearExtension::from_evaluations_vec(nv, vec![-Fr::from(1u64); 1 << nv]),
+        );
+        assert!(commit_artifacts::<Bls12_381, Kzg>(&bad_artifacts, &pcs_srs).is_err());
+
+        Ok(())
+    }
+
+    fn random_column<R: RngCore>(nv: usize, rng: &mut R) -> SelectorColumn<Fr> {
+        SelectorColumn((0..1 << nv).map(|_| Fr::rand(rng)).collect())
+    }
+
+    /// A circuit with the gate `q0 * w0 + q1 * w1 - q2 * w2 = 0` for the
+    /// selectors `selectors`, without wiring, and a witness satisfying it.
+    fn weighted_sum_circuit<R: RngCore>(
+        selectors: Vec<SelectorColumn<Fr>>,
+        rng: &mut R,
+    ) -> (HyperPlonkIndex<Fr>, Vec<Fr>, Vec<WitnessColumn<Fr>>) {
+        let n = selectors[0].0.len();
+        let w0: Vec<Fr> = (0..n).map(|_| Fr::rand(rng)).collect();
+        let w1: Vec<Fr> = (0..n).map(|_| Fr::rand(rng)).collect();
+        let w2 = (0..n)
+            .map(|row| {
+                let (q0, q1, q2) = (
+                    selectors[0].0[row],
+                    selectors[1].0[row],
+                    selectors[2].0[row],
+                );
+                (q0 * w0[row] + q1 * w1[row]) * q2.inverse().unwrap()
+            })
+            .collect();
+        let index = HyperPlonkIndex {
+            params: HyperPlonkParams {
+                num_constraints: n,
+                num_pub_input: 2,
+                gate_func: CustomizedGates {
+                    gates: vec![
+                        (1, Some(0), vec![0]),
+                        (1, Some(1), vec![1]),
+                        (-1, Some(2), vec![2]),
+                    ],
+                    lookups: vec![],
+                },
+                num_fixed_columns: 0,
+            },
+            permutation: (0..3 * n as u64).map(Fr::from).collect(),
+            selectors,
+            fixed_columns: vec![],
+            tables: vec![],
+        };
+        (
+            index,
+            w0[..2].to_vec(),
+            vec![WitnessColumn(w0), WitnessColumn(w1), WitnessColumn(w2)],
+        )
+    }
+
+    #[test]
+    fn test_update_selectors() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let nv = 4;
+        let pcs_srs = Kzg::gen_srs_for_testing(&mut rng, nv)?;
+
+        let selectors: Vec<_> = (0..3).map(|_| random_column(nv, &mut rng)).collect();
+        let (index, _, _) = weighted_sum_circuit(selectors.clone(), &mut rng);
+        let (mut pk, vk) =
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::preprocess(&index, &pcs_srs)?;
+        assert!(vk.selectors.iter().all(|selector| selector.is_committed()));
+
+        // update the second selector
+        let mut new_selectors = selectors;
+        new_selectors[1] = random_column(nv, &mut rng);
+        let (new_index, pub_input, witnesses) =
+            weighted_sum_circuit(new_selectors.clone(), &mut rng);
+        let old_pk = pk.clone();
+        let new_vk = vk.update_selectors(&mut pk, &new_selectors)?;
+
+        // the keys are the ones of a full preprocessing
+        let full_keys =
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::preprocess(&new_index, &pcs_srs)?;
+        let updated_keys = (pk, new_vk);
+        assert_same_keys(&full_keys, &updated_keys);
+        let (pk, new_vk) = updated_keys;
+        assert_eq!(pk.selectors[0], old_pk.selectors[0]);
+        assert_ne!(pk.selectors[1], old_pk.selectors[1]);
+        assert_eq!(pk.selectors[2], old_pk.selectors[2]);
+        assert!(Arc::ptr_eq(&pk.permutation, &old_pk.permutation));
+
+        // and the proofs under the updated keys verify
+        let proof =
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::prove(&pk, &pub_input, &witnesses)?;
+        assert!(<PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::verify(
+            &new_vk, &pub_input, &proof
+        )?);
+        assert!(!matches!(
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::verify(&vk, &pub_input, &proof),
+            Ok(true)
+        ));
+
+        // unchanged selectors leave the keys unchanged
+        let mut same_pk = pk.clone();
+        let same_vk = new_vk.update_selectors(&mut same_pk, &new_selectors)?;
+        assert_same_keys(&(pk.clone(), new_vk.clone()), &(same_pk, same_vk));
+
+        // the number and the length of the selector columns cannot change,
+        // and a failed update leaves the proving key unchanged
+        let mut bad_pk = pk.clone();
+        assert!(new_vk
+            .update_selectors(&mut bad_pk, &new_selectors[..2])
+            .is_err());
+        let mut short_selectors = new_selectors.clone();
+        short_selectors[0].0.pop();
+        assert!(new_vk
+            .update_selectors(&mut bad_pk, &short_selectors)
+            .is_err());
+        let mut long_selectors = new_selectors.clone();
+        long_selectors[2] = random_column(nv + 1, &mut rng);
+        long_selectors[0] = random_column(nv, &mut rng);
+        assert!(new_vk
+            .update_selectors(&mut bad_pk, &long_selectors)
+            .is_err());
+        assert_same_keys(&(pk.clone(), new_vk.clone()), &(bad_pk, new_vk.clone()));
+        // nor can keys that are not a pair be updated
+        assert!(vk
+            .update_selectors(&mut pk.clone(), &new_selectors)
+            .is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,321 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! Batched verification of HyperPlonk proofs.
+//!
+//! [`verify_all`] verifies many proofs, possibly for different circuits, in
+//! a single call. The PolyIOP part of each proof is verified independently
+//! and in parallel; the pairing checks of all proofs sharing a verifying key
+//! are then merged into a single multi-pairing.
+//!
+//! [`batch_verify`] does the same for many proofs of a single circuit, with
+//! one result for the whole batch.
+
+use crate::{errors::HyperPlonkErrors, snark::verify_iop, HyperPlonkSNARK};
+use ark_ec::pairing::Pairing;
+use ark_std::{end_timer, format, start_timer, sync::Arc, vec::Vec};
+use rayon::{
+    iter::{
+        IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator, ParallelIterator,
+    },
+    ThreadPool,
+};
+use subroutines::{
+    pcs::prelude::{MultilinearKzgPCS, MultilinearKzgPairingCheck},
+    poly_iop::PolyIOP,
+};
+
+/// The HyperPlonk verifying key over multilinear KZG.
+pub type VerifyingKey<E> = <PolyIOP<<E as Pairing>::ScalarField> as HyperPlonkSNARK<
+    E,
+    MultilinearKzgPCS<E>,
+>>::VerifyingKey;
+
+/// The HyperPlonk proof over multilinear KZG.
+pub type Proof<E> =
+    <PolyIOP<<E as Pairing>::ScalarField> as HyperPlonkSNARK<E, MultilinearKzgPCS<E>>>::Proof;
+
+/// A `(verifying key, public input, proof)` triple to be verified.
+pub type BatchItem<E> = (
+    Arc<VerifyingKey<E>>,
+    Vec<<E as Pairing>::ScalarField>,
+    Proof<E>,
+);
+
+/// Verify the proofs `proofs` of the circuit of `vk` for the public inputs
+/// `public_inputs`, with a single multi-pairing for all of them.
+///
+/// The pairing checks are combined with random coefficients derived from a
+/// transcript over the pairing inputs of every proof, so that a single
+/// invalid proof makes the whole batch fail. The PolyIOP errors of the
+/// first invalid proof, if any, are returned as is.
+pub fn batch_verify<E: Pairing>(
+    vk: &VerifyingKey<E>,
+    public_inputs: &[&[E::ScalarField]],
+    proofs: &[Proof<E>],
+) -> Result<bool, HyperPlonkErrors> {
+    let start = start_timer!(|| format!("batch verify {} proofs of a circuit", proofs.len()));
+    let checks = pairing_checks(vk, public_inputs, proofs)?;
+    let res = MultilinearKzgPairingCheck::batch_check(&checks)?;
+    end_timer!(start);
+    Ok(res)
+}
+
+/// The deferred pairing checks of `proofs`, after verifying everything else
+/// in parallel. Returns the error of the first proof that fails.
+fn pairing_checks<E: Pairing, I: AsRef<[E::ScalarField]> + Sync>(
+    vk: &VerifyingKey<E>,
+    instances: &[I],
+    proofs: &[Proof<E>],
+) -> Result<Vec<MultilinearKzgPairingCheck<E>>, HyperPlonkErrors> {
+    if instances.len() != proofs.len() {
+        return Err(HyperPlonkErrors::InvalidParameters(format!(
+            "{} public inputs for {} proofs",
+            instances.len(),
+            proofs.len()
+        )));
+    }
+    let checks: Vec<Result<MultilinearKzgPairingCheck<E>, HyperPlonkErrors>> = instances
+        .par_iter()
+        .zip(proofs.par_iter())
+        .map(|(pub_input, proof)| {
+            let (comms, points, mut transcript) = verify_iop(vk, pub_input.as_ref(), proof)?;
+            Ok(MultilinearKzgPCS::batch_verify_deferred(
+                &vk.pcs_param,
+                &comms,
+                &points,
+                &proof.batch_openings,
+                &mut transcript,
+            )?)
+        })
+        .collect();
+    checks.into_iter().collect()
+}
+
+/// Verify a list of `(verifying key, public input, proof)` triples.
+///
+/// Items sharing the same verifying key (i.e., the same `Arc`) are grouped
+/// together and their pairing checks are evaluated with one multi-pairing.
+/// If a group fails, its pairing checks are re-evaluated one by one so that
+/// each failure is attributed to the right item.
+///
+/// The `i`-th output is the verification result of the `i`-th item. The
+/// output does not depend on `pool` or on thread scheduling. If `pool` is
+/// `None` the global rayon thread pool is used.
+pub fn verify_all<E: Pairing>(
+    items: &[BatchItem<E>],
+    pool: Option<&ThreadPool>,
+) -> Vec<Result<(), HyperPlonkErrors>> {
+    match pool {
+        Some(pool) => pool.install(|| verify_all_internal(items)),
+        None => verify_all_internal(items),
+    }
+}
+
+fn verify_all_internal<E: Pairing>(items: &[BatchItem<E>]) -> Vec<Result<(), HyperPlonkErrors>> {
+    let start = start_timer!(|| format!("batch verify {} proofs", items.len()));
+
+    // group the items by verifying key, in order of first appearance
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for (i, (vk, _, _)) in items.iter().enumerate() {
+        match groups
+            .iter_mut()
+            .find(|group| Arc::ptr_eq(&items[group[0]].0, vk))
+        {
+            Some(group) => group.push(i),
+            None => groups.push(vec![i]),
+        }
+    }
+
+    let step = start_timer!(|| "PolyIOP verification");
+    let checks: Vec<Result<MultilinearKzgPairingCheck<E>, HyperPlonkErrors>> = items
+        .par_iter()
+        .map(|(vk, pub_input, proof)| {
+            let (comms, points, mut transcript) = verify_iop(vk, pub_input, proof)?;
+            Ok(MultilinearKzgPCS::batch_verify_deferred(
+                &vk.pcs_param,
+                &comms,
+                &points,
+                &proof.batch_openings,
+                &mut transcript,
+            )?)
+        })
+        .collect();
+    end_timer!(step);
+
+    let step = start_timer!(|| "pairing checks");
+    let failed: Vec<Vec<usize>> = groups
+        .into_par_iter()
+        .map(|group| {
+            let passed: Vec<usize> = group.into_iter().filter(|&i| checks[i].is_ok()).collect();
+            let group_checks: Vec<_> = passed
+                .iter()
+                .filter_map(|&i| checks[i].as_ref().ok().cloned())
+                .collect();
+            match MultilinearKzgPairingCheck::batch_check(&group_checks) {
+                Ok(true) => vec![],
+                _ => passed
+                    .into_iter()
+                    .zip(group_checks.iter())
+                    .filter(|(_, check)| !check.check())
+                    .map(|(i, _)| i)
+                    .collect(),
+            }
+        })
+        .collect();
+    end_timer!(step);
+
+    let mut res: Vec<Result<(), HyperPlonkErrors>> =
+        checks.into_iter().map(|check| check.map(|_| ())).collect();
+    for i in failed.into_iter().flatten() {
+        res[i] = Err(HyperPlonkErrors::InvalidProof(format!(
+            "pairing check of proof {} failed",
+            i
+        )));
+    }
+
+    end_timer!(start);
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{custom_gate::CustomizedGates, mock::MockCircuit};
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_std::{test_rng, One};
+    use rayon::ThreadPoolBuilder;
+    use subroutines::pcs::PolynomialCommitmentScheme;
+
+    type Snark = PolyIOP<Fr>;
+    type Pcs = MultilinearKzgPCS<Bls12_381>;
+
+    #[test]
+    fn test_verify_all() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let pcs_srs = Pcs::gen_srs_for_testing(&mut rng, 8)?;
+
+        let vanilla = MockCircuit::<Fr>::new(1 << 6, &CustomizedGates::vanilla_plonk_gate());
+        let turbo = MockCircuit::<Fr>::new(1 << 5, &CustomizedGates::jellyfish_turbo_plonk_gate());
+
+        let (vanilla_pk, vanilla_vk) =
+            <Snark as HyperPlonkSNARK<Bls12_381, Pcs>>::preprocess(&vanilla.index, &pcs_srs)?;
+        let (turbo_pk, turbo_vk) =
+            <Snark as HyperPlonkSNARK<Bls12_381, Pcs>>::preprocess(&turbo.index, &pcs_srs)?;
+        let vanilla_vk = Arc::new(vanilla_vk);
+        let turbo_vk = Arc::new(turbo_vk);
+
+        let vanilla_proof = <Snark as HyperPlonkSNARK<Bls12_381, Pcs>>::prove(
+            &vanilla_pk,
+            &vanilla.public_inputs,
+            &vanilla.witnesses,
+        )?;
+        let turbo_proof = <Snark as HyperPlonkSNARK<Bls12_381, Pcs>>::prove(
+            &turbo_pk,
+            &turbo.public_inputs,
+            &turbo.witnesses,
+        )?;
+
+        // bad vanilla item: wrong public input, rejected by the PolyIOP
+        let mut bad_pub_input = vanilla.public_inputs.clone();
+        bad_pub_input[0] += Fr::one();
+
+        // bad turbo item: proof generated with the key of another SRS, only
+        // rejected by the pairing check
+        let other_srs = Pcs::gen_srs_for_testing(&mut rng, 8)?;
+        let (bad_pk, _) =
+            <Snark as HyperPlonkSNARK<Bls12_381, Pcs>>::preprocess(&turbo.index, &other_srs)?;
+        let bad_turbo_proof = <Snark as HyperPlonkSNARK<Bls12_381, Pcs>>::prove(
+            &bad_pk,
+            &turbo.public_inputs,
+            &turbo.witnesses,
+        )?;
+
+        let mut items: Vec<_> = (0..8)
+            .map(|i| {
+                if i % 2 == 0 {
+                    (
+                        vanilla_vk.clone(),
+                        vanilla.public_inputs.clone(),
+                        vanilla_proof.clone(),
+                    )
+                } else {
+                    (
+                        turbo_vk.clone(),
+                        turbo.public_inputs.clone(),
+                        turbo_proof.clone(),
+                    )
+                }
+            })
+            .collect();
+        items[3].2 = bad_turbo_proof;
+        items[4].1 = bad_pub_input;
+
+        let pool = ThreadPoolBuilder::new().num_threads(3).build().unwrap();
+        for pool in [None, Some(&pool)] {
+            let res = verify_all(&items, pool);
+            assert_eq!(res.len(), items.len());
+            let failed: Vec<usize> = res
+                .iter()
+                .enumerate()
+                .filter(|(_, r)| r.is_err())
+                .map(|(i, _)| i)
+                .collect();
+            assert_eq!(failed, vec![3, 4]);
+            assert!(matches!(res[3], Err(HyperPlonkErrors::InvalidProof(_))));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_batch_verify() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let pcs_srs = Pcs::gen_srs_for_testing(&mut rng, 8)?;
+        let circuit =
+            MockCircuit::<Fr>::new(1 << 5, &CustomizedGates::jellyfish_turbo_plonk_gate());
+        let (pk, vk) =
+            <Snark as HyperPlonkSNARK<Bls12_381, Pcs>>::preprocess(&circuit.index, &pcs_srs)?;
+        let proof = <Snark as HyperPlonkSNARK<Bls12_381, Pcs>>::prove(
+            &pk,
+            &circuit.public_inputs,
+            &circuit.witnesses,
+        )?;
+        let public_inputs = vec![circuit.public_inputs.as_slice(); 3];
+        let proofs = vec![proof; 3];
+        assert!(batch_verify(&vk, &public_inputs, &proofs)?);
+
+        // a proof generated with the key of another SRS is only caught by
+        // the pairing check, and fails the whole batch
+        let other_srs = Pcs::gen_srs_for_testing(&mut rng, 8)?;
+        let (bad_pk, _) =
+            <Snark as HyperPlonkSNARK<Bls12_381, Pcs>>::preprocess(&circuit.index, &other_srs)?;
+        let mut tampered = proofs.clone();
+        tampered[1] = <Snark as HyperPlonkSNARK<Bls12_381, Pcs>>::prove(
+            &bad_pk,
+            &circuit.public_inputs,
+            &circuit.witnesses,
+        )?;
+        assert!(!batch_verify(&vk, &public_inputs, &tampered)?);
+        assert!(!<Snark as HyperPlonkSNARK<Bls12_381, Pcs>>::verify(
+            &vk,
+            &circuit.public_inputs,
+            &tampered[1]
+        )?);
+
+        // so is a proof for another public input
+        let mut other_input = circuit.public_inputs.clone();
+        other_input[0] += Fr::one();
+        let mut other_inputs = public_inputs.clone();
+        other_inputs[2] = &other_input;
+        assert!(!matches!(
+            batch_verify(&vk, &other_inputs, &proofs),
+            Ok(true)
+        ));
+        assert!(batch_verify(&vk, &public_inputs[1..], &proofs).is_err());
+        Ok(())
+    }
+}
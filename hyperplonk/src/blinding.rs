@@ -0,0 +1,286 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! Blinding rows: a cheap alternative to the masked sum checks.
+//!
+//! The last [`ProverConfig::blinding_rows`] rows of the circuit are
+//! reserved: the prover fills them with uniformly random witness values
+//! before proving. A proof only reveals a bounded number of field elements
+//! that depend on the witnesses; once every witness column has at least as
+//! many uniformly random entries, each of these elements is a linear
+//! combination that the random entries make statistically independent of
+//! the rest of the witness, for generic evaluation points.
+//!
+//! The reserved rows must not constrain the random values:
+//! - every monomial of the gate has a selector, and all the selectors are
+//!   zero on the reserved rows, so that any witness satisfies the gate;
+//! - the permutation maps every witness cell of the reserved rows to
+//!   itself, so that they are not wired to any other cell;
+//! - the reserved rows come after the public input.
+//!
+//! The circuit must leave the witnesses of the reserved rows at zero: a
+//! witness overlapping them is rejected.
+//!
+//! [`ProverConfig::blinding_rows`]: crate::config::ProverConfig::blinding_rows
+
+use crate::{
+    errors::HyperPlonkErrors,
+    snark::{num_opened_evaluations, perm_check_aux_info, zero_check_aux_info},
+    structs::{HyperPlonkProvingKey, HyperPlonkVerifyingKey},
+    witness::WitnessColumn,
+};
+use ark_ec::pairing::Pairing;
+use ark_ff::UniformRand;
+use ark_std::{rand::RngCore, Zero};
+use subroutines::{pcs::PolynomialCommitmentScheme, poly_iop::prelude::lookup_check_aux_info};
+
+/// Number of elements of the batch opening sum check messages, whose
+/// polynomial has degree 2.
+const BATCH_SUM_CHECK_MESSAGE_LEN: usize = 3;
+
+/// The number of blinding rows that hides the field elements that a proof
+/// for `vk` reveals about the witnesses.
+///
+/// Every element of the proof that depends on the witnesses is counted as
+/// one revealed evaluation:
+/// - the witness commitments, each an evaluation at the secret of the SRS;
+/// - the commitments to m(x), a(x) and b(x) of each lookup;
+/// - the opened evaluations;
+/// - the prover messages of the gate zero check, of the permutation zero
+///   check, of the lookup sum check and of the batch opening sum check;
+/// - the opening proof of the batch, with one element per variable.
+///
+/// The bound is conservative: some of these elements only depend on a few
+/// of the witness columns. The circuit must be large enough to reserve the
+/// rows.
+pub fn recommended_blinding_rows<E, PCS>(vk: &HyperPlonkVerifyingKey<E, PCS>) -> usize
+where
+    E: Pairing,
+    PCS: PolynomialCommitmentScheme<E>,
+{
+    let num_vars = vk.params.num_variables();
+    let zero_check_message_len = zero_check_aux_info(vk).max_degree + 2;
+    let perm_check_message_len = perm_check_aux_info(vk).max_degree + 2;
+    let (lookup_commitments, lookup_check_message_len) = match vk.params.gate_func.lookups().len() {
+        0 => (0, 0),
+        num_lookups => (
+            3 * num_lookups,
+            lookup_check_aux_info::<E::ScalarField>(num_vars).max_degree + 1,
+        ),
+    };
+    vk.params.num_witness_columns()
+        + lookup_commitments
+        + num_opened_evaluations(vk)
+        + num_vars
+            * (zero_check_message_len
+                + perm_check_message_len
+                + lookup_check_message_len
+                + BATCH_SUM_CHECK_MESSAGE_LEN
+                + 1)
+}
+
+/// Fill the last `rows` rows of `witnesses` with uniformly random values
+/// from `rng`, after checking that `pk` reserves them and that `witnesses`
+/// leaves them at zero.
+pub(crate) fn blind_witnesses<E, PCS, R>(
+    pk: &HyperPlonkProvingKey<E, PCS>,
+    witnesses: &[WitnessColumn<E::ScalarField>],
+    rows: usize,
+    rng: &mut R,
+) -> Result<Vec<WitnessColumn<E::ScalarField>>, HyperPlonkErrors>
+where
+    E: Pairing,
+    PCS: PolynomialCommitmentScheme<E>,
+    R: RngCore,
+{
+    check_reserved_rows(pk, rows)?;
+    let num_constraints = pk.params.num_constraints;
+    let reserved = num_constraints - rows..num_constraints;
+
+    let mut blinded = witnesses.to_vec();
+    for (i, witness) in blinded.iter_mut().enumerate() {
+        if let Some(row) = reserved.clone().find(|&row| !witness.0[row].is_zero()) {
+            return Err(HyperPlonkErrors::InvalidProver(format!(
+                "witness {} overlaps the blinding rows at row {}",
+                i, row
+            )));
+        }
+        for row in reserved.clone() {
+            witness.0[row] = E::ScalarField::rand(rng);
+        }
+    }
+    Ok(blinded)
+}
+
+/// Check that the last `rows` rows of the circuit of `pk` can hold random
+/// witness values, see the module documentation.
+fn check_reserved_rows<E, PCS>(
+    pk: &HyperPlonkProvingKey<E, PCS>,
+    rows: usize,
+) -> Result<(), HyperPlonkErrors>
+where
+    E: Pairing,
+    PCS: PolynomialCommitmentScheme<E>,
+{
+    let num_constraints = pk.params.num_constraints;
+    if rows > num_constraints - pk.params.num_pub_input {
+        return Err(HyperPlonkErrors::InvalidParameters(format!(
+            "{} blinding rows overlap the public input: the circuit has {} rows and {} public inputs",
+            rows, num_constraints, pk.params.num_pub_input
+        )));
+    }
+    if pk
+        .params
+        .gate_func
+        .gates
+        .iter()
+        .any(|(_, selector, _)| selector.is_none())
+    {
+        return Err(HyperPlonkErrors::InvalidParameters(
+            "the gate has a monomial without selector, rows cannot be blinded".to_string(),
+        ));
+    }
+
+    let reserved = num_constraints - rows..num_constraints;
+    for (i, selector) in pk.selector_oracles.iter().enumerate() {
+        if let Some(row) = reserved
+            .clone()
+            .find(|&row| !selector.evaluations[row].is_zero())
+        {
+            return Err(HyperPlonkErrors::InvalidParameters(format!(
+                "selector {} is not zero on the blinding row {}",
+                i, row
+            )));
+        }
+    }
+    for column in 0..pk.params.num_witness_columns() {
+        for row in reserved.clone() {
+            let cell = column * num_constraints + row;
+            if pk.permutation[cell] != cell as u64 {
+                return Err(HyperPlonkErrors::InvalidParameters(format!(
+                    "the cell of witness {} on the blinding row {} is wired",
+                    column, row
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{prove_with_config, ProverConfig},
+        custom_gate::CustomizedGates,
+        mock::MockCircuit,
+        HyperPlonkSNARK,
+    };
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_std::{test_rng, One};
+    use subroutines::{pcs::prelude::MultilinearKzgPCS, poly_iop::PolyIOP};
+
+    type Kzg = MultilinearKzgPCS<Bls12_381>;
+
+    /// A mock circuit whose last `rows` rows are reserved for blinding.
+    fn reserved_circuit(nv: usize, rows: usize) -> MockCircuit<Fr> {
+        let mut circuit = MockCircuit::new(1 << nv, &CustomizedGates::vanilla_plonk_gate());
+        for row in (1 << nv) - rows..1 << nv {
+            for selector in circuit.index.selectors.iter_mut() {
+                selector.0[row] = Fr::zero();
+            }
+            for witness in circuit.witnesses.iter_mut() {
+                witness.0[row] = Fr::zero();
+            }
+        }
+        circuit
+    }
+
+    #[test]
+    fn test_blinding_rows() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let nv = 4;
+        let rows = 4;
+        let pcs_srs = Kzg::gen_srs_for_testing(&mut rng, nv)?;
+        let circuit = reserved_circuit(nv, rows);
+        let (pk, vk) =
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::preprocess(&circuit.index, &pcs_srs)?;
+        assert!(recommended_blinding_rows(&vk) > num_opened_evaluations(&vk));
+
+        // proofs with blinding rows verify
+        let config = ProverConfig {
+            blinding_rows: rows,
+            ..ProverConfig::default()
+        };
+        let prove = |rng: &mut _| {
+            prove_with_config(
+                &pk,
+                &circuit.public_inputs,
+                &circuit.witnesses,
+                &config,
+                rng,
+            )
+        };
+        let proof = prove(&mut rng)?;
+        let other_proof = prove(&mut rng)?;
+        for proof in [&proof, &other_proof] {
+            assert!(<PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::verify(
+                &vk,
+                &circuit.public_inputs,
+                proof,
+            )?);
+        }
+        assert_ne!(proof.witness_commits, other_proof.witness_commits);
+
+        // the reserved rows are random, the others are untouched
+        let blinded = blind_witnesses(&pk, &circuit.witnesses, rows, &mut rng)?;
+        let other_blinded = blind_witnesses(&pk, &circuit.witnesses, rows, &mut rng)?;
+        let reserved = (1 << nv) - rows;
+        for ((witness, a), b) in circuit
+            .witnesses
+            .iter()
+            .zip(blinded.iter())
+            .zip(other_blinded.iter())
+        {
+            assert_eq!(a.0[..reserved], witness.0[..reserved]);
+            assert_eq!(b.0[..reserved], witness.0[..reserved]);
+            for row in reserved..1 << nv {
+                assert!(!a.0[row].is_zero());
+                assert_ne!(a.0[row], b.0[row]);
+            }
+        }
+
+        // bad path: a witness overlapping the reserved rows
+        let mut bad_witnesses = circuit.witnesses.clone();
+        bad_witnesses[1].0[reserved] = Fr::one();
+        assert!(prove_with_config(
+            &pk,
+            &circuit.public_inputs,
+            &bad_witnesses,
+            &config,
+            &mut rng,
+        )
+        .is_err());
+
+        // bad path: rows that the circuit does not reserve
+        let bad_config = ProverConfig {
+            blinding_rows: rows + 1,
+            ..config
+        };
+        assert!(prove_with_config(
+            &pk,
+            &circuit.public_inputs,
+            &circuit.witnesses,
+            &bad_config,
+            &mut rng,
+        )
+        .is_err());
+        // nor the public input rows
+        assert!(blind_witnesses(&pk, &circuit.witnesses, 1 << nv, &mut rng).is_err());
+
+        Ok(())
+    }
+}
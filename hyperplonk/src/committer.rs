@@ -0,0 +1,320 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! Committing to witness columns while they are being generated.
+//!
+//! Witness columns typically become final at different times during witness
+//! generation. An [`IncrementalCommitter`] commits to each column as soon as
+//! it is ready, possibly from different threads, so that the commitments
+//! overlap with the generation of the remaining columns. The result is then
+//! handed to [`prove_with_generator`], which skips recommitting.
+
+use crate::{
+    errors::HyperPlonkErrors,
+    stepper::prove_in_steps,
+    structs::{HyperPlonkProof, HyperPlonkProvingKey},
+    witness::WitnessColumn,
+};
+use ark_ec::pairing::Pairing;
+use ark_ff::PrimeField;
+use ark_poly::DenseMultilinearExtension;
+use ark_std::{end_timer, log2, start_timer};
+use std::sync::{Arc, Mutex};
+use subroutines::{
+    pcs::prelude::{Commitment, PolynomialCommitmentScheme},
+    poly_iop::PolyIOP,
+    BatchProof,
+};
+use transcript::IOPTranscript;
+
+/// The commitments to the witness columns, in column order, together with a
+/// digest of the evaluations each of them commits to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PrecommittedWitnesses<E: Pairing> {
+    /// commitments to the witness columns
+    pub commitments: Vec<Commitment<E>>,
+    /// digests of the committed evaluations
+    pub digests: Vec<E::ScalarField>,
+}
+
+/// Commits to witness columns one at a time, in any order.
+///
+/// `commit_column` takes `&self`, so that columns can be committed from
+/// several threads as soon as each of them is ready.
+pub struct IncrementalCommitter<'a, E, PCS>
+where
+    E: Pairing,
+    PCS: PolynomialCommitmentScheme<E>,
+{
+    prover_param: &'a PCS::ProverParam,
+    #[allow(clippy::type_complexity)]
+    columns: Mutex<Vec<Option<(Commitment<E>, E::ScalarField)>>>,
+}
+
+impl<'a, E, PCS> IncrementalCommitter<'a, E, PCS>
+where
+    E: Pairing,
+    PCS: PolynomialCommitmentScheme<
+        E,
+        Polynomial = Arc<DenseMultilinearExtension<E::ScalarField>>,
+        Commitment = Commitment<E>,
+    >,
+{
+    /// Create a committer with no committed column.
+    pub fn new(prover_param: &'a PCS::ProverParam) -> Self {
+        Self {
+            prover_param,
+            columns: Mutex::new(vec![]),
+        }
+    }
+
+    /// Commit to the column `col_index` with evaluations `evals`.
+    ///
+    /// The commitment is computed right away on the calling thread. Each
+    /// column can only be committed once.
+    pub fn commit_column(
+        &self,
+        col_index: usize,
+        evals: &[E::ScalarField],
+    ) -> Result<(), HyperPlonkErrors> {
+        let start = start_timer!(|| format!("commit column {}", col_index));
+        if !evals.len().is_power_of_two() {
+            return Err(HyperPlonkErrors::InvalidProver(format!(
+                "Column {} length is not power of two: got {}",
+                col_index,
+                evals.len(),
+            )));
+        }
+        let poly = Arc::new(DenseMultilinearExtension::from_evaluations_slice(
+            log2(evals.len()) as usize,
+            evals,
+        ));
+        let commitment = PCS::commit(self.prover_param, &poly)?;
+        let digest = column_digest(evals)?;
+
+        let mut columns = self.columns.lock().map_err(|_| {
+            HyperPlonkErrors::InvalidProver("committer lock is poisoned".to_string())
+        })?;
+        if columns.len() <= col_index {
+            columns.resize_with(col_index + 1, || None);
+        }
+        if columns[col_index].is_some() {
+            return Err(HyperPlonkErrors::InvalidProver(format!(
+                "Column {} is committed twice",
+                col_index
+            )));
+        }
+        columns[col_index] = Some((commitment, digest));
+        end_timer!(start);
+        Ok(())
+    }
+
+    /// Output the commitments in column order.
+    ///
+    /// Fails if some column below the largest committed index has not been
+    /// committed.
+    pub fn finish(self) -> Result<PrecommittedWitnesses<E>, HyperPlonkErrors> {
+        let columns = self.columns.into_inner().map_err(|_| {
+            HyperPlonkErrors::InvalidProver("committer lock is poisoned".to_string())
+        })?;
+        let mut res = PrecommittedWitnesses {
+            commitments: Vec::with_capacity(columns.len()),
+            digests: Vec::with_capacity(columns.len()),
+        };
+        for (i, column) in columns.into_iter().enumerate() {
+            let (commitment, digest) = column.ok_or_else(|| {
+                HyperPlonkErrors::InvalidProver(format!("Column {} is not committed", i))
+            })?;
+            res.commitments.push(commitment);
+            res.digests.push(digest);
+        }
+        Ok(res)
+    }
+}
+
+/// A digest of the evaluations of a column.
+pub(crate) fn column_digest<F: PrimeField>(evals: &[F]) -> Result<F, HyperPlonkErrors> {
+    let mut bytes = Vec::new();
+    for eval in evals.iter() {
+        eval.serialize_compressed(&mut bytes)?;
+    }
+    let mut transcript = IOPTranscript::<F>::new(b"witness column digest");
+    transcript.append_message(b"evals", &bytes)?;
+    Ok(transcript.get_and_append_challenge(b"digest")?)
+}
+
+/// Generate a HyperPlonk proof for the witnesses output by the witness
+/// generator.
+///
+/// If the witnesses have been committed during their generation, the
+/// commitments are passed as `precommitted` and are not recomputed. They are
+/// checked against the witnesses first: every digest must match the
+/// corresponding witness column. The proof is identical to the one generated
+/// by `HyperPlonkSNARK::prove`.
+pub fn prove_with_generator<E, PCS>(
+    pk: &HyperPlonkProvingKey<E, PCS>,
+    pub_input: &[E::ScalarField],
+    witnesses: &[WitnessColumn<E::ScalarField>],
+    precommitted: Option<PrecommittedWitnesses<E>>,
+) -> Result<HyperPlonkProof<E, PolyIOP<E::ScalarField>, PCS>, HyperPlonkErrors>
+where
+    E: Pairing,
+    PCS: PolynomialCommitmentScheme<
+        E,
+        Polynomial = Arc<DenseMultilinearExtension<E::ScalarField>>,
+        Point = Vec<E::ScalarField>,
+        Evaluation = E::ScalarField,
+        Commitment = Commitment<E>,
+        BatchProof = BatchProof<E, PCS>,
+    >,
+{
+    let start = start_timer!(|| "hyperplonk proving with generator");
+    let mut stepper = prove_in_steps(pk, pub_input, witnesses)?;
+    if let Some(precommitted) = precommitted {
+        if precommitted.commitments.len() != witnesses.len()
+            || precommitted.digests.len() != witnesses.len()
+        {
+            return Err(HyperPlonkErrors::InvalidProver(format!(
+                "Number of precommitted columns is not correct: got {}, expect {}",
+                precommitted.commitments.len(),
+                witnesses.len()
+            )));
+        }
+        for (i, (w, digest)) in witnesses
+            .iter()
+            .zip(precommitted.digests.iter())
+            .enumerate()
+        {
+            if column_digest(w.coeff_ref())? != *digest {
+                return Err(HyperPlonkErrors::InvalidProver(format!(
+                    "Precommitted column {} does not match the witness",
+                    i
+                )));
+            }
+        }
+        stepper.set_witness_commitments(precommitted.commitments);
+    }
+    let proof = stepper.run()?;
+    end_timer!(start);
+    Ok(proof)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{custom_gate::CustomizedGates, mock::MockCircuit, HyperPlonkSNARK};
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_std::{test_rng, One};
+    use subroutines::pcs::prelude::MultilinearKzgPCS;
+
+    type Kzg = MultilinearKzgPCS<Bls12_381>;
+
+    #[test]
+    fn test_prove_with_precommitted_witnesses() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let nv = 5;
+        let pcs_srs = Kzg::gen_srs_for_testing(&mut rng, nv)?;
+
+        let gate = CustomizedGates::jellyfish_turbo_plonk_gate();
+        let circuit = MockCircuit::<Fr>::new(1 << nv, &gate);
+        let (pk, vk) =
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::preprocess(&circuit.index, &pcs_srs)?;
+
+        // commit to the columns from several threads, in reverse order
+        let committer = IncrementalCommitter::<Bls12_381, Kzg>::new(&*pk.pcs_param);
+        std::thread::scope(|s| {
+            let handles: Vec<_> = circuit
+                .witnesses
+                .iter()
+                .enumerate()
+                .rev()
+                .map(|(i, w)| {
+                    let committer = &committer;
+                    s.spawn(move || committer.commit_column(i, w.coeff_ref()))
+                })
+                .collect();
+            handles
+                .into_iter()
+                .try_for_each(|h| h.join().expect("committing thread panicked"))
+        })?;
+        let precommitted = committer.finish()?;
+
+        let proof = <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::prove(
+            &pk,
+            &circuit.public_inputs,
+            &circuit.witnesses,
+        )?;
+        assert_eq!(precommitted.commitments, proof.witness_commits);
+
+        let precommitted_proof = prove_with_generator(
+            &pk,
+            &circuit.public_inputs,
+            &circuit.witnesses,
+            Some(precommitted.clone()),
+        )?;
+        assert_eq!(precommitted_proof.witness_commits, proof.witness_commits);
+        assert_eq!(precommitted_proof.zero_check_proof, proof.zero_check_proof);
+        assert_eq!(
+            precommitted_proof.batch_openings.f_i_eval_at_point_i,
+            proof.batch_openings.f_i_eval_at_point_i
+        );
+        assert!(<PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::verify(
+            &vk,
+            &circuit.public_inputs,
+            &precommitted_proof,
+        )?);
+
+        let proof = prove_with_generator(&pk, &circuit.public_inputs, &circuit.witnesses, None)?;
+        assert_eq!(proof.witness_commits, precommitted.commitments);
+
+        // bad path: a precommitted column differs from the witness
+        let mut bad_witnesses = circuit.witnesses.clone();
+        bad_witnesses[1].0[0] += Fr::one();
+        assert!(prove_with_generator(
+            &pk,
+            &circuit.public_inputs,
+            &bad_witnesses,
+            Some(precommitted.clone()),
+        )
+        .is_err());
+
+        // bad path: a precommitted column is missing
+        let mut bad_precommitted = precommitted;
+        bad_precommitted.commitments.pop();
+        bad_precommitted.digests.pop();
+        assert!(prove_with_generator(
+            &pk,
+            &circuit.public_inputs,
+            &circuit.witnesses,
+            Some(bad_precommitted),
+        )
+        .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_incremental_committer_errors() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let pcs_srs = Kzg::gen_srs_for_testing(&mut rng, 3)?;
+        let (pcs_param, _) = Kzg::trim(&pcs_srs, None, Some(3))?;
+        let evals = vec![Fr::one(); 8];
+
+        // a column committed twice
+        let committer = IncrementalCommitter::<Bls12_381, Kzg>::new(&pcs_param);
+        committer.commit_column(0, &evals)?;
+        assert!(committer.commit_column(0, &evals).is_err());
+
+        // a column that is not a power of two
+        assert!(committer.commit_column(1, &evals[..5]).is_err());
+
+        // a gap in the committed columns
+        committer.commit_column(2, &evals)?;
+        assert!(committer.finish().is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,185 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! Runtime configuration of the prover.
+//!
+//! With the `parallel` feature, the hot loops of the prover, i.e., the sum
+//! check rounds and the eq table build, take their serial route when the
+//! current rayon pool has a single thread, e.g., in a container limited to
+//! one CPU. [`ProverConfig::force_serial`] forces that route without
+//! recompiling.
+//!
+//! [`ProverConfig::blinding_rows`] fills the rows the circuit reserves for
+//! it with random witness values, see [`crate::blinding`].
+
+use crate::{
+    blinding::blind_witnesses,
+    errors::HyperPlonkErrors,
+    structs::{HyperPlonkProof, HyperPlonkProvingKey},
+    utils::prover_sanity_check,
+    witness::WitnessColumn,
+    HyperPlonkSNARK,
+};
+use ark_ec::pairing::Pairing;
+use ark_poly::DenseMultilinearExtension;
+use ark_std::rand::RngCore;
+use std::sync::Arc;
+use subroutines::{
+    pcs::prelude::{Commitment, PolynomialCommitmentScheme},
+    poly_iop::PolyIOP,
+    BatchProof,
+};
+
+/// Configuration of [`prove_with_config`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ProverConfig {
+    /// Prove on a single thread, with the hot loops on their serial route.
+    /// The MSMs of the PCS are those of arkworks: they run on that thread
+    /// too.
+    pub force_serial: bool,
+    /// Number of rows at the end of the circuit that are filled with random
+    /// witness values before proving. The circuit must reserve them, and
+    /// the witnesses must leave them at zero.
+    pub blinding_rows: usize,
+}
+
+/// `HyperPlonkSNARK::prove` with the configuration `config`.
+///
+/// Apart from the blinding rows, which are sampled from `rng`, the proof
+/// does not depend on the configuration.
+pub fn prove_with_config<E, PCS, R>(
+    pk: &HyperPlonkProvingKey<E, PCS>,
+    pub_input: &[E::ScalarField],
+    witnesses: &[WitnessColumn<E::ScalarField>],
+    config: &ProverConfig,
+    rng: &mut R,
+) -> Result<HyperPlonkProof<E, PolyIOP<E::ScalarField>, PCS>, HyperPlonkErrors>
+where
+    E: Pairing,
+    PCS: PolynomialCommitmentScheme<
+        E,
+        Polynomial = Arc<DenseMultilinearExtension<E::ScalarField>>,
+        Point = Vec<E::ScalarField>,
+        Evaluation = E::ScalarField,
+        Commitment = Commitment<E>,
+        BatchProof = BatchProof<E, PCS>,
+    >,
+    HyperPlonkProvingKey<E, PCS>: Sync,
+    HyperPlonkProof<E, PolyIOP<E::ScalarField>, PCS>: Send,
+    R: RngCore,
+{
+    let blinded;
+    let witnesses = if config.blinding_rows == 0 {
+        witnesses
+    } else {
+        prover_sanity_check(&pk.params, pub_input, witnesses)?;
+        blinded = blind_witnesses(pk, witnesses, config.blinding_rows, rng)?;
+        &blinded
+    };
+    let prove =
+        || <PolyIOP<E::ScalarField> as HyperPlonkSNARK<E, PCS>>::prove(pk, pub_input, witnesses);
+    if config.force_serial {
+        util::run_serial(prove)
+    } else {
+        prove()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{custom_gate::CustomizedGates, mock::MockCircuit, streaming::serialize_streaming};
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_std::test_rng;
+    use subroutines::pcs::prelude::MultilinearKzgPCS;
+
+    type Kzg = MultilinearKzgPCS<Bls12_381>;
+
+    #[test]
+    fn test_force_serial() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let nv = 5;
+        let pcs_srs = Kzg::gen_srs_for_testing(&mut rng, nv)?;
+
+        let gate = CustomizedGates::vanilla_plonk_gate();
+        let circuit = MockCircuit::<Fr>::new(1 << nv, &gate);
+        let (pk, vk) =
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::preprocess(&circuit.index, &pcs_srs)?;
+
+        let prove = |force_serial| -> Result<Vec<u8>, HyperPlonkErrors> {
+            let proof = prove_with_config(
+                &pk,
+                &circuit.public_inputs,
+                &circuit.witnesses,
+                &ProverConfig {
+                    force_serial,
+                    ..ProverConfig::default()
+                },
+                &mut test_rng(),
+            )?;
+            assert!(<PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::verify(
+                &vk,
+                &circuit.public_inputs,
+                &proof
+            )?);
+            let mut bytes = vec![];
+            serialize_streaming(&proof, &mut bytes)?;
+            Ok(bytes)
+        };
+
+        // both routes produce the same proof, and the forced one takes the
+        // serial route
+        let proof = prove(false)?;
+        let count = util::serial_route_count();
+        assert_eq!(prove(true)?, proof);
+        if cfg!(feature = "parallel") {
+            assert!(util::serial_route_count() > count);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_deterministic_across_thread_counts() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let nv = 6;
+        let pcs_srs = Kzg::gen_srs_for_testing(&mut rng, nv)?;
+
+        for gate in [
+            CustomizedGates::vanilla_plonk_gate(),
+            CustomizedGates::jellyfish_turbo_plonk_gate(),
+        ] {
+            let circuit = MockCircuit::<Fr>::new(1 << nv, &gate);
+            let run = || -> Result<([u8; 32], Vec<u8>), HyperPlonkErrors> {
+                let (pk, vk) = <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::preprocess(
+                    &circuit.index,
+                    &pcs_srs,
+                )?;
+                let proof = <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::prove(
+                    &pk,
+                    &circuit.public_inputs,
+                    &circuit.witnesses,
+                )?;
+                assert!(<PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::verify(
+                    &vk,
+                    &circuit.public_inputs,
+                    &proof
+                )?);
+                let mut bytes = vec![];
+                serialize_streaming(&proof, &mut bytes)?;
+                Ok((vk.digest(), bytes))
+            };
+
+            // the keys and the proof do not depend on the number of threads
+            let expected = util::run_with_threads(1, run)?;
+            for num_threads in [4, 16] {
+                assert_eq!(util::run_with_threads(num_threads, run)?, expected);
+            }
+        }
+
+        Ok(())
+    }
+}
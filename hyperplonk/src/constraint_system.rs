@@ -0,0 +1,418 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! A builder of HyperPlonk indices, row by row.
+//!
+//! A [`ConstraintSystem`] collects the selectors of each row of the gate
+//! and the copy constraints between wires, i.e., cells of the witness
+//! columns, and [`ConstraintSystem::finalize`] turns them into the selector
+//! columns and the wire permutation of a [`HyperPlonkIndex`].
+//!
+//! Two monomials of the gate with the same witnesses and the same selector
+//! column are the same constraint, which the prover would compute twice.
+//! `finalize` reports them: with an error in strict mode, see
+//! [`ConstraintSystem::set_strict`], and otherwise as warnings returned by
+//! [`ConstraintSystem::finalize_with_warnings`].
+
+use crate::{
+    custom_gate::CustomizedGates,
+    errors::HyperPlonkErrors,
+    selectors::SelectorColumn,
+    structs::{HyperPlonkIndex, HyperPlonkParams},
+};
+use arithmetic::identity_permutation;
+use ark_ff::PrimeField;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+};
+use subroutines::poly_iop::prelude::PolyIOPErrors;
+
+/// A wire of the circuit: a witness column and a row.
+pub type Wire = (usize, usize);
+
+/// Builder of the index of a circuit for a gate.
+///
+/// The wires of a copy constraint are equal, and so are all the wires
+/// connected by copy constraints: each set of connected wires becomes a
+/// cycle of the permutation.
+#[derive(Clone, Debug)]
+pub struct ConstraintSystem<F: PrimeField> {
+    gate: CustomizedGates,
+    num_pub_input: usize,
+    num_rows: usize,
+    selectors: Vec<SelectorColumn<F>>,
+    copy_constraints: Vec<(Wire, Wire)>,
+    strict: bool,
+}
+
+impl<F: PrimeField> ConstraintSystem<F> {
+    /// An empty constraint system for `gate`, without public input.
+    pub fn new(gate: &CustomizedGates) -> Self {
+        Self {
+            gate: gate.clone(),
+            num_pub_input: 0,
+            num_rows: 0,
+            selectors: vec![SelectorColumn::default(); gate.num_selector_columns()],
+            copy_constraints: vec![],
+            strict: false,
+        }
+    }
+
+    /// In strict mode, `finalize` fails on duplicate monomials of the gate
+    /// instead of ignoring them, see [`Self::finalize_with_warnings`].
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Make the first `num_pub_input` wires of the first witness column the
+    /// public input.
+    pub fn set_num_pub_input(&mut self, num_pub_input: usize) {
+        self.num_pub_input = num_pub_input;
+    }
+
+    /// Number of rows added so far.
+    pub fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    /// Add a row of the gate with the selectors `selector_row`, and return
+    /// its index.
+    pub fn add_gate(&mut self, selector_row: &[F]) -> Result<usize, HyperPlonkErrors> {
+        if selector_row.len() != self.selectors.len() {
+            return Err(HyperPlonkErrors::InvalidParameters(format!(
+                "the gate has {} selectors, got {}",
+                self.selectors.len(),
+                selector_row.len()
+            )));
+        }
+        for (selector, &value) in self.selectors.iter_mut().zip(selector_row) {
+            selector.append(value);
+        }
+        self.num_rows += 1;
+        Ok(self.num_rows - 1)
+    }
+
+    /// Constrain the wires `wire_a` and `wire_b`, each given as a witness
+    /// column and a row, to be equal.
+    pub fn add_copy_constraint(&mut self, wire_a: Wire, wire_b: Wire) {
+        self.copy_constraints.push((wire_a, wire_b));
+    }
+
+    /// Build the index of a circuit with `2^num_vars` rows, the rows after
+    /// those added having all their selectors set to zero.
+    ///
+    /// Returns `HyperPlonkErrors::InconsistentCopyConstraint` if a copy
+    /// constraint refers to a wire outside of the circuit, and
+    /// `PolyIOPErrors::DuplicateConstraint` if a monomial of the gate
+    /// duplicates a previous one in strict mode.
+    pub fn finalize(self, num_vars: usize) -> Result<HyperPlonkIndex<F>, HyperPlonkErrors> {
+        self.finalize_with_warnings(num_vars)
+            .map(|(index, _)| index)
+    }
+
+    /// Same as [`Self::finalize`], and also returns the duplicate monomials
+    /// of the gate found out of strict mode, as the
+    /// `PolyIOPErrors::DuplicateConstraint` errors strict mode would have
+    /// returned.
+    pub fn finalize_with_warnings(
+        self,
+        num_vars: usize,
+    ) -> Result<(HyperPlonkIndex<F>, Vec<PolyIOPErrors>), HyperPlonkErrors> {
+        let num_rows = 1 << num_vars;
+        if self.num_rows > num_rows || self.num_pub_input > num_rows {
+            return Err(HyperPlonkErrors::InvalidParameters(format!(
+                "{} rows and {} public inputs do not fit in {} rows",
+                self.num_rows, self.num_pub_input, num_rows
+            )));
+        }
+        let num_witnesses = self.gate.num_witness_columns();
+
+        // the cells of the wires connected by copy constraints, where the
+        // cell of the wire (c, r) is c * num_rows + r
+        let mut parents = HashMap::new();
+        for &(wire_a, wire_b) in self.copy_constraints.iter() {
+            let mut cells = [0; 2];
+            for (cell, (column, row)) in cells.iter_mut().zip([wire_a, wire_b]) {
+                if column >= num_witnesses || row >= num_rows {
+                    return Err(HyperPlonkErrors::InconsistentCopyConstraint(format!(
+                        "the wire {:?} of the copy constraint between {:?} and {:?} is not in \
+                         the {} columns of {} rows",
+                        (column, row),
+                        wire_a,
+                        wire_b,
+                        num_witnesses,
+                        num_rows
+                    )));
+                }
+                *cell = find(&mut parents, column * num_rows + row);
+            }
+            if cells[0] != cells[1] {
+                parents.insert(cells[0].max(cells[1]), cells[0].min(cells[1]));
+            }
+        }
+
+        // each set of connected cells is a cycle, by increasing cell
+        let mut cycles: HashMap<usize, Vec<usize>> = HashMap::new();
+        let mut connected: Vec<usize> = parents.keys().copied().collect();
+        connected.sort_unstable();
+        for cell in connected {
+            let root = find(&mut parents, cell);
+            cycles.entry(root).or_insert_with(|| vec![root]).push(cell);
+        }
+        let mut permutation = identity_permutation(num_vars, num_witnesses);
+        for cycle in cycles.values() {
+            for (k, &cell) in cycle.iter().enumerate() {
+                permutation[cell] = F::from(cycle[(k + 1) % cycle.len()] as u64);
+            }
+        }
+
+        let selectors: Vec<SelectorColumn<F>> = self
+            .selectors
+            .into_iter()
+            .map(|mut selector| {
+                selector.0.resize(num_rows, F::zero());
+                selector
+            })
+            .collect();
+        let mut warnings = vec![];
+        for gate_index in duplicate_monomials(&self.gate, &selectors) {
+            let warning = PolyIOPErrors::DuplicateConstraint { gate_index };
+            if self.strict {
+                return Err(warning.into());
+            }
+            warnings.push(warning);
+        }
+        let index = HyperPlonkIndex {
+            params: HyperPlonkParams {
+                num_constraints: num_rows,
+                num_pub_input: self.num_pub_input,
+                gate_func: self.gate,
+                num_fixed_columns: 0,
+            },
+            permutation,
+            selectors,
+            fixed_columns: vec![],
+            tables: vec![],
+        };
+        Ok((index, warnings))
+    }
+}
+
+/// The monomials of `gate` with the same witnesses and the same selector
+/// column in `selectors` as a previous monomial, in increasing order.
+fn duplicate_monomials<F: PrimeField>(
+    gate: &CustomizedGates,
+    selectors: &[SelectorColumn<F>],
+) -> Vec<usize> {
+    // the monomials by witnesses and fingerprint of their selector column
+    let mut monomials: HashMap<(&[usize], Option<u64>), Vec<usize>> = HashMap::new();
+    let mut duplicates = vec![];
+    for (i, (_coeff, q, ws)) in gate.gates.iter().enumerate() {
+        let fingerprint = q.map(|q| {
+            let mut hasher = DefaultHasher::new();
+            selectors[q].0.hash(&mut hasher);
+            hasher.finish()
+        });
+        let same = monomials.entry((ws.as_slice(), fingerprint)).or_default();
+        let duplicate = same.iter().any(|&j| match (q, gate.gates[j].1) {
+            (Some(q), Some(other_q)) => selectors[*q] == selectors[other_q],
+            _ => true,
+        });
+        if duplicate {
+            duplicates.push(i);
+        } else {
+            same.push(i);
+        }
+    }
+    duplicates
+}
+
+/// The representative of the set of connected cells of `cell`, which is its
+/// smallest cell.
+fn find(parents: &mut HashMap<usize, usize>, cell: usize) -> usize {
+    let mut root = cell;
+    while let Some(&parent) = parents.get(&root) {
+        root = parent;
+    }
+    // path compression
+    let mut cell = cell;
+    while cell != root {
+        cell = parents.insert(cell, root).unwrap_or(root);
+    }
+    root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock::MockCircuit, witness::WitnessColumn, HyperPlonkSNARK};
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ff::{One, Zero};
+    use ark_std::test_rng;
+    use subroutines::{
+        pcs::{prelude::MultilinearKzgPCS, PolynomialCommitmentScheme},
+        poly_iop::PolyIOP,
+    };
+
+    type Kzg = MultilinearKzgPCS<Bls12_381>;
+
+    #[test]
+    fn test_constraint_system() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let nv = 3;
+        let pcs_srs = Kzg::gen_srs_for_testing(&mut rng, nv)?;
+
+        // x_{i+1} = x_i + x_i, with the vanilla gate
+        //     q_L w_1 + q_R w_2 + q_O w_3 + q_M w_1 w_2 + q_C = 0
+        let gate = CustomizedGates::vanilla_plonk_gate();
+        let one = Fr::one();
+        let mut cs = ConstraintSystem::<Fr>::new(&gate);
+        cs.set_num_pub_input(1);
+        let mut values = vec![Fr::from(3u64)];
+        for row in 0..6 {
+            assert_eq!(cs.add_gate(&[one, one, -one, Fr::zero(), Fr::zero()])?, row);
+            cs.add_copy_constraint((0, row), (1, row));
+            if row > 0 {
+                cs.add_copy_constraint((2, row - 1), (0, row));
+            }
+            values.push(values[row] + values[row]);
+        }
+        assert_eq!(cs.num_rows(), 6);
+        let index = cs.clone().finalize(nv)?;
+        assert_eq!(index.permutation.len(), 3 << nv);
+
+        // the wires (0, r) and (1, r) of the rows r > 0 are connected to
+        // (2, r - 1): the permutation is a single cycle through them
+        let cycle_of = |cell: usize| {
+            let mut cycle = vec![cell];
+            let mut next = index.permutation[cell];
+            while next != Fr::from(cell as u64) {
+                let target = (0..3 << nv).find(|&c| Fr::from(c as u64) == next).unwrap();
+                cycle.push(target);
+                next = index.permutation[target];
+            }
+            cycle.sort_unstable();
+            cycle
+        };
+        assert_eq!(cycle_of(1), vec![1, 9, 16]);
+        assert_eq!(cycle_of(16), vec![1, 9, 16]);
+        assert_eq!(cycle_of(0), vec![0, 8]);
+        // the padding rows are not wired
+        assert_eq!(cycle_of(7), vec![7]);
+
+        let witnesses = (0..3)
+            .map(|column| {
+                WitnessColumn(
+                    (0..1 << nv)
+                        .map(|row| match (column, row) {
+                            (_, row) if row >= 6 => Fr::zero(),
+                            (2, row) => values[row + 1],
+                            (_, row) => values[row],
+                        })
+                        .collect(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let circuit = MockCircuit {
+            public_inputs: vec![values[0]],
+            witnesses,
+            index,
+        };
+        assert!(circuit.is_satisfied());
+        let (pk, vk) =
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::preprocess(&circuit.index, &pcs_srs)?;
+        let proof = <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::prove(
+            &pk,
+            &circuit.public_inputs,
+            &circuit.witnesses,
+        )?;
+        assert!(<PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::verify(
+            &vk,
+            &circuit.public_inputs,
+            &proof
+        )?);
+
+        // bad path: a wire outside of the circuit
+        let mut bad_cs = cs.clone();
+        bad_cs.add_copy_constraint((0, 1), (3, 1));
+        assert!(matches!(
+            bad_cs.finalize(nv),
+            Err(HyperPlonkErrors::InconsistentCopyConstraint(_))
+        ));
+        let mut bad_cs = cs.clone();
+        bad_cs.add_copy_constraint((0, 1 << nv), (1, 1));
+        assert!(matches!(
+            bad_cs.finalize(nv),
+            Err(HyperPlonkErrors::InconsistentCopyConstraint(_))
+        ));
+        // bad path: too many rows, or a row of the wrong width
+        assert!(cs.clone().finalize(2).is_err());
+        assert!(cs.add_gate(&[one]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_duplicate_constraint() -> Result<(), HyperPlonkErrors> {
+        // q_1 w_1 + q_2 w_1 - w_2 = 0, where q_1 = q_2 on every row
+        let gate = CustomizedGates {
+            gates: vec![
+                (1, Some(0), vec![0]),
+                (1, Some(1), vec![0]),
+                (-1, None, vec![1]),
+            ],
+            lookups: vec![],
+        };
+        let one = Fr::one();
+        let mut cs = ConstraintSystem::<Fr>::new(&gate);
+        for _ in 0..3 {
+            cs.add_gate(&[one, one])?;
+        }
+
+        // permissive mode warns only
+        let (index, warnings) = cs.clone().finalize_with_warnings(2)?;
+        assert_eq!(index.selectors.len(), 2);
+        assert!(matches!(
+            warnings.as_slice(),
+            [PolyIOPErrors::DuplicateConstraint { gate_index: 1 }]
+        ));
+
+        cs.set_strict(true);
+        assert!(matches!(
+            cs.clone().finalize(2),
+            Err(HyperPlonkErrors::PolyIOPErrors(
+                PolyIOPErrors::DuplicateConstraint { gate_index: 1 }
+            ))
+        ));
+
+        // the selectors differ on a row
+        cs.add_gate(&[one, Fr::zero()])?;
+        let (_, warnings) = cs.clone().finalize_with_warnings(2)?;
+        assert!(warnings.is_empty());
+
+        // two monomials of the same witnesses without selector
+        let gate = CustomizedGates {
+            gates: vec![
+                (1, Some(0), vec![0]),
+                (-1, None, vec![1]),
+                (2, None, vec![1]),
+            ],
+            lookups: vec![],
+        };
+        let mut cs = ConstraintSystem::<Fr>::new(&gate);
+        cs.add_gate(&[one])?;
+        cs.set_strict(true);
+        assert!(matches!(
+            cs.finalize(1),
+            Err(HyperPlonkErrors::PolyIOPErrors(
+                PolyIOPErrors::DuplicateConstraint { gate_index: 2 }
+            ))
+        ));
+
+        Ok(())
+    }
+}
@@ -4,6 +4,8 @@
 // You should have received a copy of the MIT License
 // along with the HyperPlonk library. If not, see <https://mit-license.org/>.
 
+use crate::errors::HyperPlonkErrors;
+use ark_ff::PrimeField;
 use ark_std::cmp::max;
 
 /// Customized gate is a list of tuples of
@@ -22,15 +24,36 @@ use ark_std::cmp::max;
 ///         (1, Some(0), vec![0, 0, 0, 0, 0]),
 ///         (-1, None, vec![1])
 ///     ],
+///     lookups: vec![],
 /// };
 /// where id_qL = 0 // first selector
 /// id_W1 = 0 // first witness
 /// id_w2 = 1 // second witness
 ///
+/// A monomial may multiply any number of witnesses, e.g.,
+///     (1, Some(id_qM), vec![id_W1, id_W2, id_W3])
+/// for a triple product, and the number of wires of the gate is
+/// [`CustomizedGates::num_witness_columns`].
+///
+/// A gate may also constrain witnesses to lookup tables: each of its
+/// `lookups` is a pair `(witness_index, table_id)`, which requires every
+/// value of the witness column to be an entry of the table `table_id` of the
+/// index. See [`CustomizedGates::lookup_gate`].
+///
 /// NOTE: here coeff is a signed integer, instead of a field element
+/// How [`CustomizedGates::compose`] combines the polynomials of two gates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GateComposition {
+    /// the sum of the polynomials
+    Sum,
+    /// the product of the polynomials
+    Product,
+}
+
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct CustomizedGates {
     pub(crate) gates: Vec<(i64, Option<usize>, Vec<usize>)>,
+    pub(crate) lookups: Vec<(usize, usize)>,
 }
 
 impl CustomizedGates {
@@ -67,10 +90,160 @@ impl CustomizedGates {
                 }
             }
         }
+        // a looked up witness is a witness column as well
+        for &(witness, _table) in self.lookups.iter() {
+            res = max(res, witness);
+        }
         // add one here because index starts from 0
         res + 1
     }
 
+    /// The lookups of the gate, as pairs `(witness_index, table_id)`
+    pub fn lookups(&self) -> &[(usize, usize)] {
+        &self.lookups
+    }
+
+    /// The number of lookup tables the gate refers to, i.e., one more than
+    /// the largest table id of its lookups
+    pub fn num_tables(&self) -> usize {
+        self.lookups
+            .iter()
+            .map(|&(_witness, table)| table + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Add a lookup of the witness `witness` into the table `table_id`
+    pub fn with_lookup(mut self, witness: usize, table_id: usize) -> Self {
+        self.lookups.push((witness, table_id));
+        self
+    }
+
+    /// Evaluate the gate on the evaluations of its selectors and witnesses
+    /// at a same point.
+    pub fn evaluate<F: PrimeField>(
+        &self,
+        selector_evals: &[F],
+        witness_evals: &[F],
+    ) -> Result<F, HyperPlonkErrors> {
+        if selector_evals.len() < self.num_selector_columns()
+            || witness_evals.len() < self.num_witness_columns()
+        {
+            return Err(HyperPlonkErrors::InvalidParameters(format!(
+                "got {} selector and {} witness evaluations, expect {} and {}",
+                selector_evals.len(),
+                witness_evals.len(),
+                self.num_selector_columns(),
+                self.num_witness_columns()
+            )));
+        }
+        let mut res = F::zero();
+        for (coeff, selector, witnesses) in self.gates.iter() {
+            let mut cur_value = if *coeff < 0 {
+                -F::from(-*coeff as u64)
+            } else {
+                F::from(*coeff as u64)
+            };
+            cur_value *= match selector {
+                Some(s) => selector_evals[*s],
+                None => F::one(),
+            };
+            for &witness in witnesses.iter() {
+                cur_value *= witness_evals[witness]
+            }
+            res += cur_value;
+        }
+        Ok(res)
+    }
+
+    /// Compose the gate with `other` into a single gate, whose polynomial is
+    /// the sum or the product of theirs, on the same witness columns.
+    ///
+    /// The lookups of both gates are kept. For a sum, the selectors of
+    /// `other` come after those of `self`. For a product, the monomial of
+    /// each pair of monomials of the two gates has a selector if either of
+    /// them has one, which is the product of their selectors; see
+    /// [`CustomizedGates::compose_selectors`].
+    ///
+    /// Panics if a coefficient of a product does not fit in an `i64`.
+    pub fn compose(&self, other: &CustomizedGates, composition: GateComposition) -> Self {
+        let gates = match composition {
+            GateComposition::Sum => {
+                let shift = self.num_selector_columns();
+                self.gates
+                    .iter()
+                    .cloned()
+                    .chain(
+                        other
+                            .gates
+                            .iter()
+                            .map(|(coeff, q, ws)| (*coeff, q.map(|q| q + shift), ws.clone())),
+                    )
+                    .collect()
+            },
+            GateComposition::Product => {
+                let mut gates = vec![];
+                let mut num_selectors = 0;
+                for (coeff, q, ws) in self.gates.iter() {
+                    for (other_coeff, other_q, other_ws) in other.gates.iter() {
+                        let coeff = coeff
+                            .checked_mul(*other_coeff)
+                            .expect("the coefficient of the composed gate overflows");
+                        let q = if q.is_some() || other_q.is_some() {
+                            num_selectors += 1;
+                            Some(num_selectors - 1)
+                        } else {
+                            None
+                        };
+                        // witness list must be ordered
+                        let mut ws = [ws.as_slice(), other_ws.as_slice()].concat();
+                        ws.sort_unstable();
+                        gates.push((coeff, q, ws));
+                    }
+                }
+                gates
+            },
+        };
+        let mut lookups = self.lookups.clone();
+        for lookup in other.lookups.iter() {
+            if !lookups.contains(lookup) {
+                lookups.push(*lookup);
+            }
+        }
+        Self { gates, lookups }
+    }
+
+    /// The evaluations of the selectors of
+    /// [`self.compose(other, composition)`](CustomizedGates::compose), from
+    /// those of the selectors of `self` and of `other` at a same point.
+    pub fn compose_selectors<F: PrimeField>(
+        &self,
+        other: &CustomizedGates,
+        composition: GateComposition,
+        selector_evals: &[F],
+        other_selector_evals: &[F],
+    ) -> Vec<F> {
+        match composition {
+            GateComposition::Sum => [selector_evals, other_selector_evals].concat(),
+            GateComposition::Product => {
+                let mut res = vec![];
+                for (_coeff, q, _ws) in self.gates.iter() {
+                    for (_other_coeff, other_q, _other_ws) in other.gates.iter() {
+                        match (q, other_q) {
+                            (None, None) => (),
+                            (Some(q), None) => res.push(selector_evals[*q]),
+                            (None, Some(other_q)) => res.push(other_selector_evals[*other_q]),
+                            (Some(q), Some(other_q)) => {
+                                res.push(selector_evals[*q] * other_selector_evals[*other_q])
+                            },
+                        }
+                    }
+                }
+                res
+            },
+        }
+    }
+
     /// Return a vanilla plonk gate:
     /// ``` ignore
     ///   q_L w_1 + q_R w_2 + q_O w_3 + q_M w1w2 + q_C = 0
@@ -92,6 +265,7 @@ impl CustomizedGates {
                 (1, Some(3), vec![0, 1]),
                 (1, Some(4), vec![]),
             ],
+            lookups: vec![],
         }
     }
 
@@ -143,16 +317,24 @@ impl CustomizedGates {
                 (1, Some(11), vec![4]),
                 (1, Some(12), vec![]),
             ],
+            lookups: vec![],
         }
     }
 
     /// Generate a random gate for `num_witness` with a highest degree =
     /// `degree`
+    ///
+    /// Panics if `num_witness` or `degree` is zero.
     pub fn mock_gate(num_witness: usize, degree: usize) -> Self {
+        assert!(
+            num_witness > 0 && degree > 0,
+            "mock gate requires at least one witness and a positive degree"
+        );
         let mut gates = vec![];
 
+        // the high degree term uses the second witness, if there is one
         let mut high_degree_term = vec![0; degree - 1];
-        high_degree_term.push(1);
+        high_degree_term.push(ark_std::cmp::min(1, num_witness - 1));
 
         gates.push((1, Some(0), high_degree_term));
         for i in 0..num_witness {
@@ -160,7 +342,95 @@ impl CustomizedGates {
         }
         gates.push((1, Some(num_witness + 1), vec![]));
 
-        CustomizedGates { gates }
+        CustomizedGates {
+            gates,
+            lookups: vec![],
+        }
+    }
+
+    /// Return a gate over a single witness column:
+    /// ``` ignore
+    ///   q_1 w_1^degree - w_1 = 0
+    /// ```
+    /// which is
+    /// ``` ignore
+    ///     (1,    Some(id_q1),     vec![id_W1; degree]),
+    ///     (-1,   None,            vec![id_W1]),
+    /// ```
+    ///
+    /// Panics if `degree` is zero.
+    pub fn single_column_gate(degree: usize) -> Self {
+        assert!(degree > 0, "single column gate requires a positive degree");
+        Self {
+            gates: vec![(1, Some(0), vec![0; degree]), (-1, None, vec![0])],
+            lookups: vec![],
+        }
+    }
+
+    /// Return a gate that constrains the first witness to `[0, 2^k)`:
+    /// ``` ignore
+    ///   w_1 (w_1 - 1) ... (w_1 - (2^k - 1)) + q_C = 0
+    /// ```
+    /// expanded into one monomial per power of `w_1`. The constant selector
+    /// `q_C` is zero on every row of a range checked circuit, so that it is
+    /// described compactly rather than committed. The gate has degree `2^k`.
+    ///
+    /// Panics if `k` is larger than 4, where the coefficients no longer fit
+    /// in an `i64`.
+    pub fn range_check_gate(k: usize) -> Self {
+        assert!(k <= 4, "range check gate supports up to 4 bits");
+        // the coefficients of the falling factorial, by increasing degree
+        let mut coeffs = vec![1i64];
+        for i in 0..1i64 << k {
+            let mut next = vec![0; coeffs.len() + 1];
+            for (j, &c) in coeffs.iter().enumerate() {
+                next[j + 1] += c;
+                next[j] -= i * c;
+            }
+            coeffs = next;
+        }
+        let mut gates: Vec<_> = coeffs
+            .into_iter()
+            .enumerate()
+            .filter(|(_, c)| *c != 0)
+            .map(|(degree, c)| (c, None, vec![0; degree]))
+            .collect();
+        gates.push((1, Some(0), vec![]));
+        Self {
+            gates,
+            lookups: vec![],
+        }
+    }
+
+    /// Return a gate that constrains the first witness to be a bit:
+    /// ``` ignore
+    ///   w_1 (w_1 - 1) + q_C = 0
+    /// ```
+    /// i.e., the range check gate for `k = 1`.
+    pub fn boolean_gate() -> Self {
+        Self::range_check_gate(1)
+    }
+
+    /// Whether the gate is the boolean gate.
+    pub fn is_boolean_gate(&self) -> bool {
+        *self == Self::boolean_gate()
+    }
+
+    /// Return a gate that constrains the first witness to the entries of
+    /// the lookup table `table_id`:
+    /// ``` ignore
+    ///   w_1 \in T_{table_id}
+    ///   q_C = 0
+    /// ```
+    /// The constant selector `q_C` is zero on every row, so that it is
+    /// described compactly rather than committed. Other constraints are
+    /// combined with a lookup with [`Self::with_lookup`].
+    pub fn lookup_gate(table_id: usize) -> Self {
+        Self {
+            gates: vec![(1, Some(0), vec![])],
+            lookups: vec![],
+        }
+        .with_lookup(0, table_id)
     }
 
     /// Return a plonk gate where #selector > #witness * 2
@@ -188,6 +458,81 @@ impl CustomizedGates {
                 (1, Some(5), vec![1, 2]),
                 (1, Some(6), vec![]),
             ],
+            lookups: vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Fr;
+    use ark_std::{test_rng, UniformRand};
+
+    #[test]
+    fn test_compose() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let pairs = [
+            (
+                CustomizedGates::vanilla_plonk_gate(),
+                CustomizedGates::boolean_gate(),
+            ),
+            (
+                CustomizedGates::jellyfish_turbo_plonk_gate(),
+                CustomizedGates::mock_gate(2, 3),
+            ),
+            (
+                CustomizedGates::range_check_gate(2),
+                CustomizedGates::lookup_gate(1),
+            ),
+        ];
+        for (gate, other) in pairs.iter() {
+            for composition in [GateComposition::Sum, GateComposition::Product] {
+                let composed = gate.compose(other, composition);
+                assert_eq!(
+                    composed.num_witness_columns(),
+                    max(gate.num_witness_columns(), other.num_witness_columns())
+                );
+                match composition {
+                    GateComposition::Sum => {
+                        assert_eq!(composed.degree(), max(gate.degree(), other.degree()));
+                        assert_eq!(
+                            composed.num_selector_columns(),
+                            gate.num_selector_columns() + other.num_selector_columns()
+                        );
+                    },
+                    GateComposition::Product => {
+                        assert!(composed.degree() <= gate.degree() + other.degree())
+                    },
+                }
+                for lookup in gate.lookups().iter().chain(other.lookups()) {
+                    assert!(composed.lookups().contains(lookup));
+                }
+
+                for _ in 0..10 {
+                    let mut random =
+                        |n: usize| -> Vec<Fr> { (0..n).map(|_| Fr::rand(&mut rng)).collect() };
+                    let selectors = random(gate.num_selector_columns());
+                    let other_selectors = random(other.num_selector_columns());
+                    let witnesses = random(composed.num_witness_columns());
+                    let composed_selectors =
+                        gate.compose_selectors(other, composition, &selectors, &other_selectors);
+                    assert_eq!(composed_selectors.len(), composed.num_selector_columns());
+
+                    let value = gate.evaluate(&selectors, &witnesses)?;
+                    let other_value = other.evaluate(&other_selectors, &witnesses)?;
+                    let expected = match composition {
+                        GateComposition::Sum => value + other_value,
+                        GateComposition::Product => value * other_value,
+                    };
+                    assert_eq!(
+                        composed.evaluate(&composed_selectors, &witnesses)?,
+                        expected
+                    );
+                }
+            }
         }
+
+        Ok(())
     }
 }
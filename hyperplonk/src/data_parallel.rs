@@ -0,0 +1,363 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! Data-parallel proofs: a single proof for many instances of one circuit.
+//!
+//! The `N` instances of a circuit with `2^nv` rows are stacked into a circuit
+//! with `N * 2^nv` rows, `N` a power of two: row `r` of instance `k` is row
+//! `r * N + k` of the stacked circuit, i.e., the `log N` lowest variables
+//! index the instance. The selectors and the fixed columns are the same for
+//! every instance, and the permutation wires each instance within itself.
+//!
+//! The public inputs of the instances are therefore interleaved at the
+//! beginning of the first witness column: the public input polynomial of the
+//! stacked circuit is that of the instances, extended by the `log N`
+//! instance variables, and the usual public input check covers every
+//! instance.
+//!
+//! When there are fewer instances than the stacked circuit has room for, the
+//! last instance is repeated, as a dummy satisfied instance, both by the
+//! prover and the verifier.
+
+use crate::{
+    errors::HyperPlonkErrors,
+    selectors::SelectorColumn,
+    structs::{HyperPlonkIndex, HyperPlonkProof, HyperPlonkProvingKey, HyperPlonkVerifyingKey},
+    utils::cell_index,
+    witness::WitnessColumn,
+    HyperPlonkSNARK,
+};
+use ark_ec::pairing::Pairing;
+use ark_ff::PrimeField;
+use ark_poly::DenseMultilinearExtension;
+use std::sync::Arc;
+use subroutines::{
+    pcs::prelude::{Commitment, PolynomialCommitmentScheme},
+    poly_iop::PolyIOP,
+    BatchProof,
+};
+
+/// An instance of a circuit: its public input and its witness columns.
+pub type Instance<F> = (Vec<F>, Vec<WitnessColumn<F>>);
+
+/// The index of the circuit stacking `num_instances` instances of the
+/// circuit of `index`, where `num_instances` is a power of two.
+pub fn stack_index<F: PrimeField>(
+    index: &HyperPlonkIndex<F>,
+    num_instances: usize,
+) -> Result<HyperPlonkIndex<F>, HyperPlonkErrors> {
+    if !num_instances.is_power_of_two() {
+        return Err(HyperPlonkErrors::InvalidParameters(format!(
+            "Number of instances {} is not a power of two",
+            num_instances
+        )));
+    }
+    let num_rows = index.params.num_constraints;
+    let stacked_rows = num_rows * num_instances;
+
+    // cell (c, r) of instance k is cell (c, r * N + k) of the stacked circuit
+    let permutation = index
+        .permutation
+        .iter()
+        .map(cell_index)
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut stacked_permutation = vec![F::zero(); permutation.len() * num_instances];
+    for (cell, &target) in permutation.iter().enumerate() {
+        let (column, row) = (cell / num_rows, cell % num_rows);
+        let target = target as usize;
+        let (target_column, target_row) = (target / num_rows, target % num_rows);
+        for k in 0..num_instances {
+            stacked_permutation[column * stacked_rows + row * num_instances + k] =
+                F::from((target_column * stacked_rows + target_row * num_instances + k) as u64);
+        }
+    }
+
+    let mut params = index.params.clone();
+    params.num_constraints = stacked_rows;
+    params.num_pub_input *= num_instances;
+    Ok(HyperPlonkIndex {
+        params,
+        permutation: stacked_permutation,
+        selectors: repeat_columns(&index.selectors, num_instances),
+        fixed_columns: repeat_columns(&index.fixed_columns, num_instances),
+        tables: index.tables.clone(),
+    })
+}
+
+/// Preprocess the circuit of `index` for data-parallel proofs of up to
+/// `num_instances` instances, rounded up to a power of two.
+#[allow(clippy::type_complexity)]
+pub fn preprocess_data_parallel<E, PCS>(
+    index: &HyperPlonkIndex<E::ScalarField>,
+    num_instances: usize,
+    pcs_srs: &PCS::SRS,
+) -> Result<(HyperPlonkProvingKey<E, PCS>, HyperPlonkVerifyingKey<E, PCS>), HyperPlonkErrors>
+where
+    E: Pairing,
+    PCS: PolynomialCommitmentScheme<
+        E,
+        Polynomial = Arc<DenseMultilinearExtension<E::ScalarField>>,
+        Point = Vec<E::ScalarField>,
+        Evaluation = E::ScalarField,
+        Commitment = Commitment<E>,
+        BatchProof = BatchProof<E, PCS>,
+    >,
+{
+    let stacked_index = stack_index(index, num_instances.max(1).next_power_of_two())?;
+    <PolyIOP<E::ScalarField> as HyperPlonkSNARK<E, PCS>>::preprocess(&stacked_index, pcs_srs)
+}
+
+/// Prove all of `instances` at once with the key of the stacked circuit, as
+/// preprocessed by [`preprocess_data_parallel`].
+pub fn prove_data_parallel<E, PCS>(
+    pk: &HyperPlonkProvingKey<E, PCS>,
+    instances: &[Instance<E::ScalarField>],
+) -> Result<HyperPlonkProof<E, PolyIOP<E::ScalarField>, PCS>, HyperPlonkErrors>
+where
+    E: Pairing,
+    PCS: PolynomialCommitmentScheme<
+        E,
+        Polynomial = Arc<DenseMultilinearExtension<E::ScalarField>>,
+        Point = Vec<E::ScalarField>,
+        Evaluation = E::ScalarField,
+        Commitment = Commitment<E>,
+        BatchProof = BatchProof<E, PCS>,
+    >,
+{
+    let (_, first_witnesses) = instances
+        .first()
+        .ok_or_else(|| HyperPlonkErrors::InvalidParameters("No instance to prove".to_string()))?;
+    let num_rows = first_witnesses.first().map_or(0, |w| w.0.len());
+    let num_instances = num_instances(pk.params.num_constraints, num_rows, instances.len())?;
+    let num_columns = first_witnesses.len();
+    if instances
+        .iter()
+        .any(|(_, witnesses)| witnesses.len() != num_columns)
+    {
+        return Err(HyperPlonkErrors::InvalidParameters(
+            "The instances have different numbers of witness columns".to_string(),
+        ));
+    }
+
+    let padded = pad(instances, num_instances);
+    let pub_inputs: Vec<&[E::ScalarField]> = padded.iter().map(|(pi, _)| pi.as_slice()).collect();
+    let witnesses = (0..num_columns)
+        .map(|i| {
+            let columns: Vec<&[E::ScalarField]> =
+                padded.iter().map(|(_, w)| w[i].0.as_slice()).collect();
+            Ok(WitnessColumn(interleave(&columns)?))
+        })
+        .collect::<Result<Vec<_>, HyperPlonkErrors>>()?;
+    <PolyIOP<E::ScalarField> as HyperPlonkSNARK<E, PCS>>::prove(
+        pk,
+        &interleave(&pub_inputs)?,
+        &witnesses,
+    )
+}
+
+/// Verify a data-parallel proof of the instances with public inputs
+/// `pub_inputs` with the key of the stacked circuit.
+pub fn verify_data_parallel<E, PCS>(
+    vk: &HyperPlonkVerifyingKey<E, PCS>,
+    pub_inputs: &[Vec<E::ScalarField>],
+    proof: &HyperPlonkProof<E, PolyIOP<E::ScalarField>, PCS>,
+) -> Result<bool, HyperPlonkErrors>
+where
+    E: Pairing,
+    PCS: PolynomialCommitmentScheme<
+        E,
+        Polynomial = Arc<DenseMultilinearExtension<E::ScalarField>>,
+        Point = Vec<E::ScalarField>,
+        Evaluation = E::ScalarField,
+        Commitment = Commitment<E>,
+        BatchProof = BatchProof<E, PCS>,
+    >,
+{
+    let num_pub_input = pub_inputs.first().map_or(0, Vec::len);
+    if num_pub_input == 0 {
+        // without public input, the instances are not distinguished
+        return <PolyIOP<E::ScalarField> as HyperPlonkSNARK<E, PCS>>::verify(vk, &[], proof);
+    }
+    let num_instances = num_instances(vk.params.num_pub_input, num_pub_input, pub_inputs.len())?;
+    let padded = pad(pub_inputs, num_instances);
+    let pub_inputs: Vec<&[E::ScalarField]> = padded.iter().map(|pi| pi.as_slice()).collect();
+    <PolyIOP<E::ScalarField> as HyperPlonkSNARK<E, PCS>>::verify(
+        vk,
+        &interleave(&pub_inputs)?,
+        proof,
+    )
+}
+
+/// The number of instances of a stacked circuit of which a column of length
+/// `stacked_len` interleaves columns of length `len`, checking that there is
+/// room for `count` instances.
+fn num_instances(stacked_len: usize, len: usize, count: usize) -> Result<usize, HyperPlonkErrors> {
+    if len == 0 || !stacked_len.is_multiple_of(len) || count > stacked_len / len {
+        return Err(HyperPlonkErrors::InvalidParameters(format!(
+            "{} instances of length {} do not fit in a stacked circuit of length {}",
+            count, len, stacked_len
+        )));
+    }
+    Ok(stacked_len / len)
+}
+
+/// `items` followed by copies of its last item, up to `len` items.
+fn pad<T>(items: &[T], len: usize) -> Vec<&T> {
+    let last = &items[items.len() - 1];
+    items
+        .iter()
+        .chain(std::iter::repeat(last))
+        .take(len)
+        .collect()
+}
+
+/// Interleave the columns: row `r` of column `k` is row `r * N + k` of the
+/// output, for `N` columns of the same length.
+fn interleave<F: PrimeField>(columns: &[&[F]]) -> Result<Vec<F>, HyperPlonkErrors> {
+    let len = columns.first().map_or(0, |column| column.len());
+    if columns.iter().any(|column| column.len() != len) {
+        return Err(HyperPlonkErrors::InvalidParameters(
+            "The instances have columns of different lengths".to_string(),
+        ));
+    }
+    Ok((0..len)
+        .flat_map(|row| columns.iter().map(move |column| column[row]))
+        .collect())
+}
+
+/// Repeat every row of the columns `num_instances` times.
+fn repeat_columns<F: PrimeField>(
+    columns: &[SelectorColumn<F>],
+    num_instances: usize,
+) -> Vec<SelectorColumn<F>> {
+    columns
+        .iter()
+        .map(|column| {
+            SelectorColumn(
+                column
+                    .0
+                    .iter()
+                    .flat_map(|value| std::iter::repeat_n(*value, num_instances))
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{custom_gate::CustomizedGates, structs::HyperPlonkParams};
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ff::UniformRand;
+    use ark_std::{rand::RngCore, test_rng};
+    use subroutines::pcs::prelude::MultilinearKzgPCS;
+
+    type Kzg = MultilinearKzgPCS<Bls12_381>;
+
+    /// A chain of additions `w0 + w1 = w2`, where the output of each row is
+    /// wired to the left input of the next one, and the public input is the
+    /// left input of the first two rows.
+    fn chain_index(nv: usize) -> HyperPlonkIndex<Fr> {
+        let n = 1 << nv;
+        let column = |value: Fr| SelectorColumn(vec![value; n]);
+        let (one, zero) = (Fr::from(1u64), Fr::from(0u64));
+        let mut permutation: Vec<Fr> = (0..3 * n as u64).map(Fr::from).collect();
+        for row in 0..n - 1 {
+            // w2[row] <-> w0[row + 1]
+            permutation[2 * n + row] = Fr::from((row + 1) as u64);
+            permutation[row + 1] = Fr::from((2 * n + row) as u64);
+        }
+        HyperPlonkIndex {
+            params: HyperPlonkParams {
+                num_constraints: n,
+                num_pub_input: 2,
+                gate_func: CustomizedGates::vanilla_plonk_gate(),
+                num_fixed_columns: 0,
+            },
+            permutation,
+            selectors: vec![
+                column(one),
+                column(one),
+                column(-one),
+                column(zero),
+                column(zero),
+            ],
+            fixed_columns: vec![],
+            tables: vec![],
+        }
+    }
+
+    fn chain_instance<R: RngCore>(nv: usize, rng: &mut R) -> Instance<Fr> {
+        let n = 1 << nv;
+        let mut w0 = vec![Fr::rand(rng)];
+        let w1: Vec<Fr> = (0..n).map(|_| Fr::rand(rng)).collect();
+        let mut w2 = vec![];
+        for row in 0..n {
+            w2.push(w0[row] + w1[row]);
+            if row + 1 < n {
+                w0.push(w2[row]);
+            }
+        }
+        (
+            w0[..2].to_vec(),
+            vec![WitnessColumn(w0), WitnessColumn(w1), WitnessColumn(w2)],
+        )
+    }
+
+    #[test]
+    fn test_data_parallel_proof() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let nv = 3;
+        let num_instances = 5;
+        let padded_nv = nv + 3;
+        let pcs_srs = Kzg::gen_srs_for_testing(&mut rng, padded_nv)?;
+
+        let index = chain_index(nv);
+        let instances: Vec<_> = (0..num_instances)
+            .map(|_| chain_instance(nv, &mut rng))
+            .collect();
+        let pub_inputs: Vec<_> = instances.iter().map(|(pi, _)| pi.clone()).collect();
+
+        // each instance is satisfied on its own
+        let (pk, vk) =
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::preprocess(&index, &pcs_srs)?;
+        let (pi, witnesses) = &instances[0];
+        let proof = <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::prove(&pk, pi, witnesses)?;
+        assert!(<PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::verify(
+            &vk, pi, &proof
+        )?);
+
+        // the 5 instances are padded to 8 in a circuit with 3 more variables
+        let (pk, vk) = preprocess_data_parallel::<Bls12_381, Kzg>(&index, num_instances, &pcs_srs)?;
+        assert_eq!(pk.params.num_variables(), padded_nv);
+        let proof = prove_data_parallel(&pk, &instances)?;
+        assert!(verify_data_parallel(&vk, &pub_inputs, &proof)?);
+
+        // bad path: the public input of one instance is wrong
+        let mut bad_pub_inputs = pub_inputs.clone();
+        bad_pub_inputs[2][1] += Fr::from(1u64);
+        assert!(!verify_data_parallel(&vk, &bad_pub_inputs, &proof).unwrap_or(false));
+
+        // bad path: an instance is missing
+        assert!(!verify_data_parallel(&vk, &pub_inputs[..4], &proof).unwrap_or(false));
+
+        // bad path: one instance is not satisfied
+        let mut bad_instances = instances.clone();
+        bad_instances[3].1[1].0[4] += Fr::from(1u64);
+        let rejected = match prove_data_parallel(&pk, &bad_instances) {
+            Ok(proof) => !verify_data_parallel(&vk, &pub_inputs, &proof).unwrap_or(false),
+            Err(_) => true,
+        };
+        assert!(rejected);
+
+        // bad path: too many instances
+        let too_many = vec![instances[0].clone(); 9];
+        assert!(prove_data_parallel(&pk, &too_many).is_err());
+
+        Ok(())
+    }
+}
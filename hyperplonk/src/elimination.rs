@@ -0,0 +1,223 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! Elimination of the monomials of the gate whose selector is identically
+//! zero, before preprocessing.
+
+use crate::{custom_gate::CustomizedGates, sizing::estimate_proof_size, structs::HyperPlonkIndex};
+use ark_ec::pairing::Pairing;
+use ark_ff::PrimeField;
+
+/// What [`HyperPlonkIndex::eliminate_dead_gates`] removed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EliminationReport {
+    /// number of monomials removed from the gate
+    pub num_removed_gates: usize,
+    /// the selectors removed from the index, by their index in the original
+    /// index
+    pub removed_selectors: Vec<usize>,
+    /// how many bytes shorter a proof is, see
+    /// [`estimate_proof_size`](crate::sizing::estimate_proof_size)
+    pub proof_bytes_saved: usize,
+}
+
+impl<F: PrimeField> HyperPlonkIndex<F> {
+    /// The index without the monomials of the gate whose selector is zero on
+    /// every row, which do not contribute to the gate identity, and without
+    /// their selectors. The remaining selectors are renumbered in order.
+    ///
+    /// The witness columns are unchanged: a dead monomial is kept if it is
+    /// the only one of the last witness column, and so is the first
+    /// monomial if all of them are dead. A proof for the returned index
+    /// verifies if and only if a proof for `self` does.
+    pub fn eliminate_dead_gates<E: Pairing<ScalarField = F>>(
+        &self,
+    ) -> (HyperPlonkIndex<F>, EliminationReport) {
+        let gate = &self.params.gate_func;
+        let mut kept: Vec<bool> = gate
+            .gates
+            .iter()
+            .map(|(_coeff, q, _ws)| {
+                q.is_none_or(|q| self.selectors[q].0.iter().any(|value| !value.is_zero()))
+            })
+            .collect();
+        let last_witness = self.num_witness_columns() - 1;
+        if kept_gate(gate, &kept).num_witness_columns() <= last_witness {
+            if let Some(i) = gate
+                .gates
+                .iter()
+                .position(|(_coeff, _q, ws)| ws.last() == Some(&last_witness))
+            {
+                kept[i] = true;
+            }
+        }
+        if !kept.contains(&true) {
+            if let Some(first) = kept.first_mut() {
+                *first = true;
+            }
+        }
+
+        let mut gate_func = kept_gate(gate, &kept);
+        let mut alive = vec![false; self.selectors.len()];
+        for q in gate_func.gates.iter().filter_map(|(_coeff, q, _ws)| *q) {
+            alive[q] = true;
+        }
+        let mut new_ids = vec![0; self.selectors.len()];
+        let mut selectors = vec![];
+        let mut removed_selectors = vec![];
+        for (q, selector) in self.selectors.iter().enumerate() {
+            if alive[q] {
+                new_ids[q] = selectors.len();
+                selectors.push(selector.clone());
+            } else {
+                removed_selectors.push(q);
+            }
+        }
+        for (_coeff, q, _ws) in gate_func.gates.iter_mut() {
+            if let Some(q) = q {
+                *q = new_ids[*q];
+            }
+        }
+
+        let mut index = self.clone();
+        index.params.gate_func = gate_func;
+        index.selectors = selectors;
+        let report = EliminationReport {
+            num_removed_gates: kept.iter().filter(|&&k| !k).count(),
+            removed_selectors,
+            proof_bytes_saved: estimate_proof_size::<E>(self)
+                .proof_bytes
+                .saturating_sub(estimate_proof_size::<E>(&index).proof_bytes),
+        };
+        (index, report)
+    }
+}
+
+/// The gate with the monomials of `gate` that are `kept`.
+fn kept_gate(gate: &CustomizedGates, kept: &[bool]) -> CustomizedGates {
+    CustomizedGates {
+        gates: gate
+            .gates
+            .iter()
+            .zip(kept)
+            .filter(|(_monomial, &k)| k)
+            .map(|(monomial, _k)| monomial.clone())
+            .collect(),
+        lookups: gate.lookups.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        errors::HyperPlonkErrors, mock::MockCircuit, witness::WitnessColumn, HyperPlonkSNARK,
+    };
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ff::{One, Zero};
+    use ark_serialize::CanonicalSerialize;
+    use ark_std::test_rng;
+    use subroutines::{
+        pcs::{
+            prelude::{MultilinearKzgPCS, MultilinearUniversalParams},
+            PolynomialCommitmentScheme,
+        },
+        poly_iop::PolyIOP,
+    };
+
+    type Kzg = MultilinearKzgPCS<Bls12_381>;
+
+    /// Whether a proof for `index` with `witnesses` is generated and
+    /// verifies.
+    fn accepts(
+        index: &HyperPlonkIndex<Fr>,
+        public_inputs: &[Fr],
+        witnesses: &[WitnessColumn<Fr>],
+        pcs_srs: &MultilinearUniversalParams<Bls12_381>,
+    ) -> Result<bool, HyperPlonkErrors> {
+        let (pk, vk) =
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::preprocess(index, pcs_srs)?;
+        let proof = match <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::prove(
+            &pk,
+            public_inputs,
+            witnesses,
+        ) {
+            Ok(proof) => proof,
+            Err(_) => return Ok(false),
+        };
+        Ok(
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::verify(&vk, public_inputs, &proof)
+                .unwrap_or(false),
+        )
+    }
+
+    #[test]
+    fn test_eliminate_dead_gates() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let nv = 4;
+        let pcs_srs = Kzg::gen_srs_for_testing(&mut rng, nv)?;
+
+        // without dead gates, the index is unchanged
+        let gate = CustomizedGates::vanilla_plonk_gate();
+        let mut circuit = MockCircuit::<Fr>::new(1 << nv, &gate);
+        let (index, report) = circuit.index.eliminate_dead_gates::<Bls12_381>();
+        assert_eq!(index, circuit.index);
+        assert_eq!(report, EliminationReport::default());
+
+        // zero q_O and q_M, and move their terms to q_C
+        //     q_L w_1 + q_R w_2 + q_O w_3 + q_M w_1 w_2 + q_C = 0
+        for row in 0..1 << nv {
+            let w = |i: usize| circuit.witnesses[i].0[row];
+            let selectors = &mut circuit.index.selectors;
+            let moved = selectors[2].0[row] * w(2) + selectors[3].0[row] * w(0) * w(1);
+            selectors[4].0[row] += moved;
+            selectors[2].0[row] = Fr::zero();
+            selectors[3].0[row] = Fr::zero();
+        }
+        assert!(circuit.is_satisfied());
+
+        // q_M w_1 w_2 is removed, but not q_O w_3, the only monomial of w_3
+        let (index, report) = circuit.index.eliminate_dead_gates::<Bls12_381>();
+        assert_eq!(report.num_removed_gates, 1);
+        assert_eq!(report.removed_selectors, vec![3]);
+        assert_eq!(index.num_selector_columns(), 4);
+        assert_eq!(index.num_witness_columns(), 3);
+        assert_eq!(index.params.gate_func.degree(), gate.degree() - 1);
+        assert_eq!(index.selectors[3], circuit.index.selectors[4]);
+        // the zero check messages have one evaluation less in each round
+        assert_eq!(report.proof_bytes_saved, nv * Fr::zero().compressed_size());
+
+        // both indices accept the witnesses, and reject a wrong one
+        assert!(accepts(
+            &circuit.index,
+            &circuit.public_inputs,
+            &circuit.witnesses,
+            &pcs_srs
+        )?);
+        assert!(accepts(
+            &index,
+            &circuit.public_inputs,
+            &circuit.witnesses,
+            &pcs_srs
+        )?);
+        let mut bad_witnesses = circuit.witnesses.clone();
+        bad_witnesses[1].0[5] += Fr::one();
+        assert!(!accepts(
+            &circuit.index,
+            &circuit.public_inputs,
+            &bad_witnesses,
+            &pcs_srs
+        )?);
+        assert!(!accepts(
+            &index,
+            &circuit.public_inputs,
+            &bad_witnesses,
+            &pcs_srs
+        )?);
+
+        Ok(())
+    }
+}
@@ -8,13 +8,15 @@
 
 use arithmetic::ArithErrors;
 use ark_serialize::SerializationError;
-use ark_std::string::String;
+use ark_std::{string::String, vec::Vec};
+#[cfg(feature = "display")]
 use displaydoc::Display;
 use subroutines::{pcs::prelude::PCSError, poly_iop::prelude::PolyIOPErrors};
 use transcript::TranscriptError;
 
 /// A `enum` specifying the possible failure modes of hyperplonk.
-#[derive(Display, Debug)]
+#[derive(Debug)]
+#[cfg_attr(feature = "display", derive(Display))]
 pub enum HyperPlonkErrors {
     /// Invalid Prover: {0}
     InvalidProver(String),
@@ -24,6 +26,8 @@ pub enum HyperPlonkErrors {
     InvalidProof(String),
     /// Invalid parameters: {0}
     InvalidParameters(String),
+    /// Inconsistent copy constraint: {0}
+    InconsistentCopyConstraint(String),
     /// An error during (de)serialization: {0}
     SerializationError(SerializationError),
     /// PolyIOP error {0}
@@ -34,6 +38,25 @@ pub enum HyperPlonkErrors {
     TranscriptError(TranscriptError),
     /// Arithmetic Error: {0}
     ArithmeticErrors(ArithErrors),
+    /// Resource limit exceeded: {0} is {1}, the limit is {2}
+    ResourceLimitExceeded(&'static str, usize, usize),
+    /// Proving was cancelled
+    Cancelled,
+    /// Unsupported protocol version {proof}, the supported versions are {supported:?}
+    UnsupportedVersion {
+        /// the protocol version of the proof
+        proof: u16,
+        /// the protocol versions the verifier supports
+        supported: Vec<u16>,
+    },
+}
+
+/// Display as `Debug` without the `display` feature.
+#[cfg(not(feature = "display"))]
+impl ark_std::fmt::Display for HyperPlonkErrors {
+    fn fmt(&self, f: &mut ark_std::fmt::Formatter<'_>) -> ark_std::fmt::Result {
+        ark_std::fmt::Debug::fmt(self, f)
+    }
 }
 
 impl From<SerializationError> for HyperPlonkErrors {
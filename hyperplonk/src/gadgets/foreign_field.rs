@@ -0,0 +1,602 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! Foreign field arithmetic: addition and multiplication modulo a prime `p`
+//! other than the modulus of the native field, e.g., for 256-bit values.
+//!
+//! A foreign field element is split into `num_limbs` limbs of `limb_bits`
+//! bits, little endian, each of them a cell of the witness. The sum or the
+//! product `c` of `a` and `b` is proven with a quotient `q`, e.g.,
+//! `a * b = q * p + c` over the integers, column by column of the limb
+//! products: the `k`-th column
+//! ``` ignore
+//!     sum_{i + j = k} (a_i b_j - q_i p_j) - c_k + carry_{k - 1} = 2^limb_bits carry_k
+//! ```
+//! holds in the native field, and does not wrap around as all the limbs and
+//! carries are range checked, so that it holds over the integers. The range
+//! checks decompose the value into bits, as the library has no lookup
+//! argument.
+//!
+//! All the constraints use the single gate of [`ForeignFieldColumn::gate`]:
+//! ``` ignore
+//!     q_0 w_0 + q_1 w_1 + q_2 w_2 + q_3 w_3 + q_M w_0 w_1 + q_C = 0
+//! ```
+//! on rows `(x, y, acc_in, acc_out)` that accumulate a sum of terms.
+//!
+//! The results are reduced modulo `p` by the prover, but only range checked
+//! to `num_limbs * limb_bits` bits: the circuit proves that a result is
+//! congruent to the sum or the product, not that it is smaller than `p`.
+
+use crate::{
+    custom_gate::CustomizedGates,
+    errors::HyperPlonkErrors,
+    selectors::SelectorColumn,
+    structs::{HyperPlonkIndex, HyperPlonkParams},
+    witness::WitnessColumn,
+};
+use ark_ff::PrimeField;
+use ark_std::{log2, One, Zero};
+use num_bigint::{BigInt, BigUint};
+
+/// The number of witness columns of the gate.
+const NUM_WIRES: usize = 4;
+/// The number of selectors of the gate: one per wire, `q_M` and `q_C`.
+const NUM_SELECTORS: usize = 6;
+
+/// The limb decomposition of the elements of a foreign field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ForeignFieldConfig {
+    /// the prime modulus of the foreign field
+    pub modulus: BigUint,
+    /// the number of bits of a limb
+    pub limb_bits: usize,
+    /// the number of limbs of an element
+    pub num_limbs: usize,
+}
+
+/// A foreign field element of a [`ForeignFieldColumn`]: the variables of
+/// its limbs, least significant first.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ForeignFieldElement {
+    limbs: Vec<usize>,
+}
+
+/// A circuit built by [`ForeignFieldColumn::build`].
+pub struct ForeignFieldCircuit<F: PrimeField> {
+    /// the limbs of the exposed elements, padded with zeros to a power of
+    /// two
+    pub public_inputs: Vec<F>,
+    /// the witness columns, i.e., the wires of the gate
+    pub witnesses: Vec<WitnessColumn<F>>,
+    /// the index of the circuit, with the gate of
+    /// [`ForeignFieldColumn::gate`]
+    pub index: HyperPlonkIndex<F>,
+}
+
+/// A term of an integer expression over the variables.
+enum Term {
+    /// `coeff * x`
+    Linear(BigInt, usize),
+    /// `coeff * x * y`
+    Product(BigInt, usize, usize),
+}
+
+/// A row of the gate: the variables of its wires and its selectors.
+struct Row<F: PrimeField> {
+    wires: [usize; NUM_WIRES],
+    selectors: [F; NUM_SELECTORS],
+}
+
+/// Builder of a circuit of foreign field additions and multiplications.
+///
+/// The cells of the witness are variables, and the cells of a same variable
+/// are wired by the permutation when the circuit is built.
+pub struct ForeignFieldColumn<F: PrimeField> {
+    config: ForeignFieldConfig,
+    // the value of each variable; the variable 0 is the constant zero, which
+    // fills the unused wires
+    values: Vec<F>,
+    rows: Vec<Row<F>>,
+    // the variables of the public input
+    public: Vec<usize>,
+}
+
+impl<F: PrimeField> ForeignFieldColumn<F> {
+    /// A builder for the foreign field of `config`.
+    ///
+    /// Returns an error if an element of the foreign field does not fit in
+    /// the limbs, or if the columns of the limb products may wrap around
+    /// the native field.
+    pub fn new(config: ForeignFieldConfig) -> Result<Self, HyperPlonkErrors> {
+        if config.limb_bits == 0
+            || config.num_limbs == 0
+            || config.modulus <= BigUint::one()
+            || config.modulus.bits() > (config.limb_bits * config.num_limbs) as u64
+        {
+            return Err(HyperPlonkErrors::InvalidParameters(format!(
+                "a modulus of {} bits does not fit in {} limbs of {} bits",
+                config.modulus.bits(),
+                config.num_limbs,
+                config.limb_bits
+            )));
+        }
+        // a column of a multiplication is a sum of at most num_limbs
+        // products of limbs, as many products of limbs of the quotient and
+        // of the modulus, and of the carries, see `mul_carry_bits`
+        let column_bits = 2 * config.limb_bits + log2(config.num_limbs + 1) as usize + 4;
+        if column_bits >= F::MODULUS_BIT_SIZE as usize {
+            return Err(HyperPlonkErrors::InvalidParameters(format!(
+                "limbs of {} bits are too large for a native field of {} bits",
+                config.limb_bits,
+                F::MODULUS_BIT_SIZE
+            )));
+        }
+        Ok(Self {
+            config,
+            values: vec![F::zero()],
+            rows: vec![],
+            public: vec![],
+        })
+    }
+
+    /// The gate of the foreign field constraints:
+    /// ``` ignore
+    ///     q_0 w_0 + q_1 w_1 + q_2 w_2 + q_3 w_3 + q_M w_0 w_1 + q_C = 0
+    /// ```
+    pub fn gate() -> CustomizedGates {
+        CustomizedGates {
+            gates: vec![
+                (1, Some(0), vec![0]),
+                (1, Some(1), vec![1]),
+                (1, Some(2), vec![2]),
+                (1, Some(3), vec![3]),
+                (1, Some(4), vec![0, 1]),
+                (1, Some(5), vec![]),
+            ],
+            lookups: vec![],
+        }
+    }
+
+    /// The limb decomposition of the foreign field.
+    pub fn config(&self) -> &ForeignFieldConfig {
+        &self.config
+    }
+
+    /// Allocate the foreign field element `value`, which must be smaller
+    /// than the modulus, with range checked limbs.
+    pub fn alloc(&mut self, value: &BigUint) -> Result<ForeignFieldElement, HyperPlonkErrors> {
+        if *value >= self.config.modulus {
+            return Err(HyperPlonkErrors::InvalidParameters(
+                "the value is not reduced modulo the foreign modulus".to_string(),
+            ));
+        }
+        Ok(self.alloc_limbs(value))
+    }
+
+    /// The value of the element `x`.
+    pub fn value(&self, x: &ForeignFieldElement) -> BigUint {
+        x.limbs.iter().rev().fold(BigUint::zero(), |acc, &limb| {
+            (acc << self.config.limb_bits) + Into::<BigUint>::into(self.values[limb])
+        })
+    }
+
+    /// Constrain `a + b = c mod p`, and return `c`.
+    pub fn add(
+        &mut self,
+        a: &ForeignFieldElement,
+        b: &ForeignFieldElement,
+    ) -> Result<ForeignFieldElement, HyperPlonkErrors> {
+        let modulus = self.config.modulus.clone();
+        let sum = self.value(a) + self.value(b);
+        let quotient = if sum >= modulus {
+            BigUint::one()
+        } else {
+            BigUint::zero()
+        };
+        let c = self.alloc_limbs(&(sum - &quotient * &modulus));
+        let q = self.alloc_var(F::from(quotient));
+        self.range_check(q, 1);
+
+        let p_limbs = self.limbs(&modulus);
+        let columns = (0..self.config.num_limbs)
+            .map(|k| {
+                vec![
+                    Term::Linear(BigInt::one(), a.limbs[k]),
+                    Term::Linear(BigInt::one(), b.limbs[k]),
+                    Term::Linear(-BigInt::from(p_limbs[k].clone()), q),
+                    Term::Linear(-BigInt::one(), c.limbs[k]),
+                ]
+            })
+            .collect();
+        // the carries are -1, 0 or 1
+        self.assert_columns(columns, 2)?;
+        Ok(c)
+    }
+
+    /// Constrain `a * b = c mod p`, and return `c`.
+    pub fn mul(
+        &mut self,
+        a: &ForeignFieldElement,
+        b: &ForeignFieldElement,
+    ) -> Result<ForeignFieldElement, HyperPlonkErrors> {
+        let product = self.value(a) * self.value(b);
+        let c = &product % &self.config.modulus;
+        self.mul_with_result(a, b, &c)
+    }
+
+    /// Constrain `a * b = c mod p` for the claimed result `c`.
+    fn mul_with_result(
+        &mut self,
+        a: &ForeignFieldElement,
+        b: &ForeignFieldElement,
+        c: &BigUint,
+    ) -> Result<ForeignFieldElement, HyperPlonkErrors> {
+        let n = self.config.num_limbs;
+        let product = self.value(a) * self.value(b);
+        // both a and b are reduced, so that the quotient fits in the limbs
+        let q = self.alloc_limbs(&(product / &self.config.modulus));
+        let c = self.alloc_limbs(c);
+
+        let p_limbs = self.limbs(&self.config.modulus);
+        let mut columns: Vec<Vec<Term>> = (0..2 * n - 1).map(|_| vec![]).collect();
+        for i in 0..n {
+            for j in 0..n {
+                columns[i + j].push(Term::Product(BigInt::one(), a.limbs[i], b.limbs[j]));
+                if !p_limbs[j].is_zero() {
+                    columns[i + j]
+                        .push(Term::Linear(-BigInt::from(p_limbs[j].clone()), q.limbs[i]));
+                }
+            }
+        }
+        for (column, &limb) in columns.iter_mut().zip(c.limbs.iter()) {
+            column.push(Term::Linear(-BigInt::one(), limb));
+        }
+        self.assert_columns(columns, self.mul_carry_bits())?;
+        Ok(c)
+    }
+
+    /// Make the limbs of `x` public inputs, after those of the elements
+    /// exposed before.
+    pub fn expose(&mut self, x: &ForeignFieldElement) {
+        self.public.extend_from_slice(&x.limbs);
+    }
+
+    /// Build the circuit: the public input rows come first, then the rows
+    /// of the constraints, padded with empty rows to a power of two.
+    pub fn build(self) -> Result<ForeignFieldCircuit<F>, HyperPlonkErrors> {
+        let num_pub_input = self.public.len().next_power_of_two();
+        let mut rows: Vec<Row<F>> = Vec::with_capacity(num_pub_input + self.rows.len());
+        for i in 0..num_pub_input {
+            rows.push(Row {
+                wires: [self.public.get(i).copied().unwrap_or(0), 0, 0, 0],
+                selectors: [F::zero(); NUM_SELECTORS],
+            });
+        }
+        rows.extend(self.rows);
+        let num_rows = rows.len().next_power_of_two().max(2);
+        while rows.len() < num_rows {
+            rows.push(Row {
+                wires: [0; NUM_WIRES],
+                selectors: [F::zero(); NUM_SELECTORS],
+            });
+        }
+
+        let witnesses = (0..NUM_WIRES)
+            .map(|w| WitnessColumn(rows.iter().map(|row| self.values[row.wires[w]]).collect()))
+            .collect::<Vec<_>>();
+        let selectors = (0..NUM_SELECTORS)
+            .map(|s| SelectorColumn(rows.iter().map(|row| row.selectors[s]).collect()))
+            .collect();
+
+        // the cells of each variable form a cycle of the permutation; the
+        // cell of the wire w on the row i is w * num_rows + i
+        let mut cells = vec![vec![]; self.values.len()];
+        for w in 0..NUM_WIRES {
+            for (i, row) in rows.iter().enumerate() {
+                cells[row.wires[w]].push(w * num_rows + i);
+            }
+        }
+        let mut permutation = vec![F::zero(); NUM_WIRES * num_rows];
+        for cycle in cells.iter().filter(|cycle| !cycle.is_empty()) {
+            for (k, &cell) in cycle.iter().enumerate() {
+                permutation[cell] = F::from(cycle[(k + 1) % cycle.len()] as u64);
+            }
+        }
+
+        Ok(ForeignFieldCircuit {
+            public_inputs: witnesses[0].0[..num_pub_input].to_vec(),
+            witnesses,
+            index: HyperPlonkIndex {
+                params: HyperPlonkParams {
+                    num_constraints: num_rows,
+                    num_pub_input,
+                    gate_func: Self::gate(),
+                    num_fixed_columns: 0,
+                },
+                permutation,
+                selectors,
+                fixed_columns: vec![],
+                tables: vec![],
+            },
+        })
+    }
+
+    /// The number of bits of the carries of a multiplication, offset to be
+    /// non-negative: the `k`-th column is smaller than `(n + 1) 2^(2 B)` in
+    /// absolute value, for `n` limbs of `B` bits, and so is the carry times
+    /// `2^B`.
+    fn mul_carry_bits(&self) -> usize {
+        self.config.limb_bits + log2(self.config.num_limbs + 1) as usize + 1
+    }
+
+    /// The limbs of `value`, least significant first.
+    fn limbs(&self, value: &BigUint) -> Vec<BigUint> {
+        let mask = (BigUint::one() << self.config.limb_bits) - BigUint::one();
+        (0..self.config.num_limbs)
+            .map(|i| (value >> (i * self.config.limb_bits)) & &mask)
+            .collect()
+    }
+
+    fn alloc_var(&mut self, value: F) -> usize {
+        self.values.push(value);
+        self.values.len() - 1
+    }
+
+    /// Allocate the limbs of `value`, with range checks.
+    fn alloc_limbs(&mut self, value: &BigUint) -> ForeignFieldElement {
+        let limbs = self
+            .limbs(value)
+            .into_iter()
+            .map(|limb| {
+                let var = self.alloc_var(F::from(limb));
+                self.range_check(var, self.config.limb_bits);
+                var
+            })
+            .collect();
+        ForeignFieldElement { limbs }
+    }
+
+    /// Constrain `x < 2^bits` by its binary decomposition.
+    fn range_check(&mut self, x: usize, bits: usize) {
+        if bits == 1 {
+            self.assert_boolean(x);
+            return;
+        }
+        let value: BigUint = self.values[x].into();
+        let mut terms = vec![Term::Linear(-BigInt::one(), x)];
+        for i in 0..bits {
+            let bit = self.alloc_var(F::from(value.bit(i as u64) as u64));
+            self.assert_boolean(bit);
+            terms.push(Term::Linear(BigInt::one() << i, bit));
+        }
+        self.assert_zero(terms, BigInt::zero());
+    }
+
+    /// Constrain `sum_k columns[k] 2^(B k) = 0` over the integers, column by
+    /// column with range checked carries of `carry_bits` bits, offset by
+    /// `2^(carry_bits - 1)`.
+    fn assert_columns(
+        &mut self,
+        columns: Vec<Vec<Term>>,
+        carry_bits: usize,
+    ) -> Result<(), HyperPlonkErrors> {
+        let base = BigInt::one() << self.config.limb_bits;
+        let offset = BigInt::one() << (carry_bits - 1);
+        let num_columns = columns.len();
+        let mut carry: Option<(usize, BigInt)> = None;
+        for (k, mut terms) in columns.into_iter().enumerate() {
+            let mut sum = self.evaluate(&terms);
+            let mut constant = BigInt::zero();
+            if let Some((var, value)) = carry.take() {
+                sum += value;
+                terms.push(Term::Linear(BigInt::one(), var));
+                constant -= &offset;
+            }
+            if k + 1 < num_columns {
+                // exact for a correct result
+                let value = &sum / &base;
+                let shifted = (&value + &offset).to_biguint().ok_or_else(|| {
+                    HyperPlonkErrors::InvalidParameters(format!(
+                        "the carry of the column {} is out of range",
+                        k
+                    ))
+                })?;
+                let var = self.alloc_var(F::from(shifted));
+                self.range_check(var, carry_bits);
+                terms.push(Term::Linear(-base.clone(), var));
+                constant += &base * &offset;
+                carry = Some((var, value));
+            }
+            self.assert_zero(terms, constant);
+        }
+        Ok(())
+    }
+
+    /// The value of the sum of `terms`, over the integers.
+    fn evaluate(&self, terms: &[Term]) -> BigInt {
+        let value = |x: usize| BigInt::from(Into::<BigUint>::into(self.values[x]));
+        terms
+            .iter()
+            .map(|term| match term {
+                Term::Linear(coeff, x) => coeff * value(*x),
+                Term::Product(coeff, x, y) => coeff * value(*x) * value(*y),
+            })
+            .sum()
+    }
+
+    /// Constrain the sum of `terms` and `constant` to be zero, with a chain
+    /// of rows `(x, y, acc_in, acc_out)`, each of which adds a product or up
+    /// to two linear terms to the accumulator.
+    fn assert_zero(&mut self, terms: Vec<Term>, constant: BigInt) {
+        let mut products = vec![];
+        let mut linears = vec![];
+        for term in terms {
+            match term {
+                Term::Product(coeff, x, y) => products.push((coeff, x, y)),
+                Term::Linear(coeff, x) => linears.push((coeff, x)),
+            }
+        }
+        // the wires and selectors q_0, q_1 and q_M of each row
+        let mut slots: Vec<([usize; 2], [F; 3])> = products
+            .into_iter()
+            .map(|(coeff, x, y)| ([x, y], [F::zero(), F::zero(), to_field(&coeff)]))
+            .collect();
+        for pair in linears.chunks(2) {
+            let (coeff_1, y) = match pair.get(1) {
+                Some((coeff, y)) => (to_field(coeff), *y),
+                None => (F::zero(), 0),
+            };
+            slots.push(([pair[0].1, y], [to_field(&pair[0].0), coeff_1, F::zero()]));
+        }
+        if slots.is_empty() {
+            slots.push(([0, 0], [F::zero(); 3]));
+        }
+
+        let num_slots = slots.len();
+        let mut acc: Option<(usize, F)> = None;
+        let mut q_c = to_field(&constant);
+        for (i, ([x, y], [q_0, q_1, q_m])) in slots.into_iter().enumerate() {
+            let (acc_in, acc_value, q_2) = match acc {
+                Some((var, value)) => (var, value, F::one()),
+                None => (0, F::zero(), F::zero()),
+            };
+            let value = acc_value
+                + q_0 * self.values[x]
+                + q_1 * self.values[y]
+                + q_m * self.values[x] * self.values[y]
+                + q_c;
+            let (acc_out, q_3) = if i + 1 < num_slots {
+                let var = self.alloc_var(value);
+                acc = Some((var, value));
+                (var, -F::one())
+            } else {
+                (0, F::zero())
+            };
+            self.push_row([x, y, acc_in, acc_out], [q_0, q_1, q_2, q_3, q_m, q_c]);
+            q_c = F::zero();
+        }
+    }
+
+    /// Constrain `b * b - b = 0`.
+    fn assert_boolean(&mut self, b: usize) {
+        let mut selectors = [F::zero(); NUM_SELECTORS];
+        selectors[0] = -F::one();
+        selectors[4] = F::one();
+        self.push_row([b, b, 0, 0], selectors);
+    }
+
+    fn push_row(&mut self, wires: [usize; NUM_WIRES], selectors: [F; NUM_SELECTORS]) {
+        self.rows.push(Row { wires, selectors });
+    }
+}
+
+/// The integer `value` in the field.
+fn to_field<F: PrimeField>(value: &BigInt) -> F {
+    let magnitude = F::from(value.magnitude().clone());
+    if value.sign() == num_bigint::Sign::Minus {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HyperPlonkSNARK;
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_std::{rand::RngCore, test_rng};
+    use subroutines::{
+        pcs::prelude::{MultilinearKzgPCS, PolynomialCommitmentScheme},
+        poly_iop::PolyIOP,
+    };
+
+    type Kzg = MultilinearKzgPCS<Bls12_381>;
+
+    /// 4 limbs of 64 bits modulo the base field prime of secp256k1.
+    fn secp256k1_config() -> ForeignFieldConfig {
+        let modulus = (BigUint::one() << 256) - (BigUint::one() << 32) - BigUint::from(977u64);
+        ForeignFieldConfig {
+            modulus,
+            limb_bits: 64,
+            num_limbs: 4,
+        }
+    }
+
+    fn random_element<R: RngCore>(rng: &mut R, modulus: &BigUint) -> BigUint {
+        let words: Vec<u32> = (0..8).map(|_| rng.next_u32()).collect();
+        BigUint::from_slice(&words) % modulus
+    }
+
+    /// Whether the circuit proves and verifies.
+    fn prove_and_verify(
+        circuit: &ForeignFieldCircuit<Fr>,
+        pcs_srs: &<Kzg as PolynomialCommitmentScheme<Bls12_381>>::SRS,
+    ) -> Result<bool, HyperPlonkErrors> {
+        let (pk, vk) =
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::preprocess(&circuit.index, pcs_srs)?;
+        let proof = match <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::prove(
+            &pk,
+            &circuit.public_inputs,
+            &circuit.witnesses,
+        ) {
+            Ok(proof) => proof,
+            Err(_) => return Ok(false),
+        };
+        Ok(<PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::verify(
+            &vk,
+            &circuit.public_inputs,
+            &proof,
+        )
+        .unwrap_or(false))
+    }
+
+    #[test]
+    fn test_foreign_field_mul() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let config = secp256k1_config();
+        let modulus = config.modulus.clone();
+        let nv = 12;
+        let pcs_srs = Kzg::gen_srs_for_testing(&mut rng, nv)?;
+
+        let a = random_element(&mut rng, &modulus);
+        let b = random_element(&mut rng, &modulus);
+        let mut builder = ForeignFieldColumn::<Fr>::new(config.clone())?;
+        let a_var = builder.alloc(&a)?;
+        let b_var = builder.alloc(&b)?;
+        let product = builder.mul(&a_var, &b_var)?;
+        let sum = builder.add(&a_var, &b_var)?;
+        let expected = &a * &b % &modulus;
+        assert_eq!(builder.value(&product), expected);
+        assert_eq!(builder.value(&sum), (&a + &b) % &modulus);
+        builder.expose(&product);
+        let limbs: Vec<Fr> = builder.limbs(&expected).into_iter().map(Fr::from).collect();
+        let circuit = builder.build()?;
+        assert!(circuit.index.num_variables() <= nv);
+        // the public input is the product
+        assert_eq!(circuit.public_inputs, limbs);
+        assert!(prove_and_verify(&circuit, &pcs_srs)?);
+
+        // an incorrect product is rejected
+        let mut builder = ForeignFieldColumn::<Fr>::new(config.clone())?;
+        let a_var = builder.alloc(&a)?;
+        let b_var = builder.alloc(&b)?;
+        let wrong = (&a * &b + BigUint::one()) % &modulus;
+        builder.mul_with_result(&a_var, &b_var, &wrong)?;
+        assert!(!prove_and_verify(&builder.build()?, &pcs_srs)?);
+
+        // an unreduced value, and limbs too large for the native field
+        let mut builder = ForeignFieldColumn::<Fr>::new(config)?;
+        assert!(builder.alloc(&modulus).is_err());
+        assert!(ForeignFieldColumn::<Fr>::new(ForeignFieldConfig {
+            modulus,
+            limb_bits: 128,
+            num_limbs: 2,
+        })
+        .is_err());
+        Ok(())
+    }
+}
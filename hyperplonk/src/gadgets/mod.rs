@@ -0,0 +1,10 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! Gadgets: builders of the columns, gates and copy constraints of common
+//! sub-circuits.
+
+pub mod foreign_field;
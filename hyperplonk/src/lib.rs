@@ -9,18 +9,45 @@
 use ark_ec::pairing::Pairing;
 use errors::HyperPlonkErrors;
 use subroutines::{pcs::prelude::PolynomialCommitmentScheme, poly_iop::prelude::PermutationCheck};
-use witness::WitnessColumn;
+use witness::WitnessSource;
 
+pub mod artifacts;
+#[cfg(feature = "parallel")]
+pub mod batch;
+pub mod blinding;
+pub mod committer;
+pub mod config;
+pub mod constraint_system;
 mod custom_gate;
+pub mod data_parallel;
+mod elimination;
 mod errors;
+pub mod gadgets;
+pub mod limits;
+pub mod mask_bundle;
 mod mock;
 pub mod prelude;
+pub mod profile;
+pub mod public_input;
+#[cfg(any(test, feature = "reference"))]
+pub mod reference;
+pub mod schedule;
 mod selectors;
+pub mod sizing;
 mod snark;
+mod statistics;
+pub mod stepper;
+pub mod streaming;
 mod structs;
 mod utils;
+pub mod versions;
+mod vk_compression;
+mod vk_digest;
 mod witness;
 
+pub use schedule::{transcript_schedule, TranscriptEvent};
+pub use versions::{PROTOCOL_VERSION, SUPPORTED_VERSIONS};
+
 /// A trait for HyperPlonk SNARKs.
 /// A HyperPlonk is derived from ZeroChecks and PermutationChecks.
 pub trait HyperPlonkSNARK<E, PCS>: PermutationCheck<E, PCS>
@@ -37,7 +64,9 @@ where
     ///
     /// Inputs:
     /// - `index`: HyperPlonk index
-    /// - `pcs_srs`: Polynomial commitment structured reference string
+    /// - `pcs_srs`: Polynomial commitment structured reference string, either
+    ///   the full SRS of a setup or one trimmed from it to fewer variables,
+    ///   as long as it supports `sizing::required_srs_num_vars` variables
     ///
     /// Outputs:
     /// - The HyperPlonk proving key, which includes the preprocessed
@@ -54,14 +83,19 @@ where
     /// Inputs:
     /// - `pk`: circuit proving key
     /// - `pub_input`: online public input
-    /// - `witness`: witness assignment
+    /// - `witness`: witness assignment, e.g., a slice of `WitnessColumn`s,
+    ///   or a [`WitnessSource`] that streams the columns
     ///
     /// Outputs:
     /// - The HyperPlonk SNARK proof.
-    fn prove(
+    ///
+    /// With the `parallel` feature, the witness polynomials are committed to
+    /// in parallel on the current rayon thread pool, which requires the PCS
+    /// prover parameters to be `Sync` and the commitments to be `Send`.
+    fn prove<W: WitnessSource<E::ScalarField> + ?Sized>(
         pk: &Self::ProvingKey,
         pub_input: &[E::ScalarField],
-        witnesses: &[WitnessColumn<E::ScalarField>],
+        witnesses: &W,
     ) -> Result<Self::Proof, HyperPlonkErrors>;
 
     /// Verify the HyperPlonk proof.
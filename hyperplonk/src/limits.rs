@@ -0,0 +1,353 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! Resource limits and cancellation for untrusted proving requests.
+//!
+//! When the index and the witnesses come from semi-trusted users, the
+//! circuit shape is checked against [`ResourceLimits`] before anything is
+//! allocated, and a running proof can be aborted with a
+//! [`CancellationToken`].
+
+use crate::{
+    errors::HyperPlonkErrors,
+    sizing::committed_selectors,
+    stepper::prove_in_steps,
+    structs::{HyperPlonkIndex, HyperPlonkParams, HyperPlonkProof, HyperPlonkProvingKey},
+    witness::WitnessColumn,
+    HyperPlonkSNARK,
+};
+use ark_ec::pairing::Pairing;
+use ark_poly::DenseMultilinearExtension;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use subroutines::{
+    pcs::prelude::{Commitment, MultilinearKzgPCS, PolynomialCommitmentScheme},
+    poly_iop::{prelude::check_num_variables, PolyIOP},
+    BatchProof,
+};
+
+/// Number of tables of `2^nv` field elements, besides the witnesses and the
+/// permutation polynomials, numerators and denominators of the permutation
+/// check, that the prover holds at the same time: frac(x), prod(x), and the
+/// eq tables of the sum checks.
+const PROVER_WORKING_TABLES: usize = 4;
+
+/// Limits on the circuits accepted by [`preprocess_with_limits`] and
+/// [`prove_with_limits`].
+///
+/// The default has no limit, apart from the bound on the number of
+/// variables of the sum check.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// maximum number of variables
+    pub max_num_vars: usize,
+    /// maximum degree of the gate
+    pub max_gate_degree: usize,
+    /// maximum number of witness columns
+    pub max_witness_columns: usize,
+    /// maximum estimate of the memory used by the prover, in bytes
+    pub max_memory_bytes_estimate: usize,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        Self {
+            max_num_vars: usize::MAX,
+            max_gate_degree: usize::MAX,
+            max_witness_columns: usize::MAX,
+            max_memory_bytes_estimate: usize::MAX,
+        }
+    }
+}
+
+impl ResourceLimits {
+    /// Check the circuit shape `params` against the limits, without
+    /// allocating anything.
+    pub fn check<E: Pairing>(&self, params: &HyperPlonkParams) -> Result<(), HyperPlonkErrors> {
+        let num_vars = params.num_variables();
+        check_num_variables(num_vars)?;
+        check_limit("num_vars", num_vars, self.max_num_vars)?;
+        check_limit(
+            "gate_degree",
+            params.gate_func.degree(),
+            self.max_gate_degree,
+        )?;
+        check_limit(
+            "witness_columns",
+            params.num_witness_columns(),
+            self.max_witness_columns,
+        )?;
+        check_limit(
+            "memory_bytes_estimate",
+            estimate_memory_bytes::<E>(params),
+            self.max_memory_bytes_estimate,
+        )
+    }
+}
+
+fn check_limit(name: &'static str, value: usize, limit: usize) -> Result<(), HyperPlonkErrors> {
+    if value > limit {
+        return Err(HyperPlonkErrors::ResourceLimitExceeded(name, value, limit));
+    }
+    Ok(())
+}
+
+/// An estimate of the memory used to prove a circuit of shape `params`, in
+/// bytes: the proving key, as reported by the sizing module, together with
+/// the tables the prover builds.
+///
+/// `params` must have at most `MAX_NUM_VARIABLES` variables.
+pub fn estimate_memory_bytes<E: Pairing>(params: &HyperPlonkParams) -> usize {
+    let proving_key = HyperPlonkProvingKey::<E, MultilinearKzgPCS<E>>::expected_size_for_params(
+        params,
+        &committed_selectors::<E>(params),
+        params.gate_func.num_tables(),
+    );
+    // the multiplicity and fractional polynomials of each lookup
+    let num_tables = params.num_witness_columns()
+        + 3 * params.num_permutation_columns()
+        + 3 * params.gate_func.lookups().len()
+        + PROVER_WORKING_TABLES;
+    proving_key.uncompressed_bytes
+        + (num_tables << params.num_variables()) * std::mem::size_of::<E::ScalarField>()
+}
+
+/// A token to cancel a running proof from another thread.
+///
+/// Cloning the token yields a handle to the same cancellation state.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Create a token that is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel the proofs using this token. They stop at their next step.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Return `HyperPlonkErrors::Cancelled` if the token has been cancelled.
+    pub(crate) fn check(&self) -> Result<(), HyperPlonkErrors> {
+        if self.is_cancelled() {
+            return Err(HyperPlonkErrors::Cancelled);
+        }
+        Ok(())
+    }
+}
+
+/// `HyperPlonkSNARK::preprocess`, after checking the index against `limits`.
+#[allow(clippy::type_complexity)]
+pub fn preprocess_with_limits<E, PCS>(
+    index: &HyperPlonkIndex<E::ScalarField>,
+    pcs_srs: &PCS::SRS,
+    limits: &ResourceLimits,
+) -> Result<
+    (
+        <PolyIOP<E::ScalarField> as HyperPlonkSNARK<E, PCS>>::ProvingKey,
+        <PolyIOP<E::ScalarField> as HyperPlonkSNARK<E, PCS>>::VerifyingKey,
+    ),
+    HyperPlonkErrors,
+>
+where
+    E: Pairing,
+    PCS: PolynomialCommitmentScheme<
+        E,
+        Polynomial = Arc<DenseMultilinearExtension<E::ScalarField>>,
+        Point = Vec<E::ScalarField>,
+        Evaluation = E::ScalarField,
+        Commitment = Commitment<E>,
+        BatchProof = BatchProof<E, PCS>,
+    >,
+{
+    limits.check::<E>(&index.params)?;
+    <PolyIOP<E::ScalarField> as HyperPlonkSNARK<E, PCS>>::preprocess(index, pcs_srs)
+}
+
+/// `HyperPlonkSNARK::prove`, after checking the proving key against
+/// `limits`.
+///
+/// If `cancellation` is given, the proof is aborted with
+/// `HyperPlonkErrors::Cancelled` at the first step after it is cancelled.
+pub fn prove_with_limits<E, PCS>(
+    pk: &HyperPlonkProvingKey<E, PCS>,
+    pub_input: &[E::ScalarField],
+    witnesses: &[WitnessColumn<E::ScalarField>],
+    limits: &ResourceLimits,
+    cancellation: Option<CancellationToken>,
+) -> Result<HyperPlonkProof<E, PolyIOP<E::ScalarField>, PCS>, HyperPlonkErrors>
+where
+    E: Pairing,
+    PCS: PolynomialCommitmentScheme<
+        E,
+        Polynomial = Arc<DenseMultilinearExtension<E::ScalarField>>,
+        Point = Vec<E::ScalarField>,
+        Evaluation = E::ScalarField,
+        Commitment = Commitment<E>,
+        BatchProof = BatchProof<E, PCS>,
+    >,
+{
+    limits.check::<E>(&pk.params)?;
+    let mut stepper = prove_in_steps(pk, pub_input, witnesses)?;
+    if let Some(cancellation) = cancellation {
+        stepper.set_cancellation_token(cancellation);
+    }
+    stepper.run()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{custom_gate::CustomizedGates, mock::MockCircuit, stepper::StepResult};
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_std::test_rng;
+
+    type Kzg = MultilinearKzgPCS<Bls12_381>;
+
+    #[test]
+    fn test_resource_limits() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let nv = 4;
+        let pcs_srs = Kzg::gen_srs_for_testing(&mut rng, nv)?;
+
+        let gate = CustomizedGates::vanilla_plonk_gate();
+        let circuit = MockCircuit::<Fr>::new(1 << nv, &gate);
+        let params = &circuit.index.params;
+        let memory = estimate_memory_bytes::<Bls12_381>(params);
+        let limits = ResourceLimits {
+            max_num_vars: nv,
+            max_gate_degree: gate.degree(),
+            max_witness_columns: params.num_witness_columns(),
+            max_memory_bytes_estimate: memory,
+        };
+        let (pk, vk) = preprocess_with_limits::<Bls12_381, Kzg>(&circuit.index, &pcs_srs, &limits)?;
+        let proof = prove_with_limits(
+            &pk,
+            &circuit.public_inputs,
+            &circuit.witnesses,
+            &limits,
+            None,
+        )?;
+        assert!(<PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::verify(
+            &vk,
+            &circuit.public_inputs,
+            &proof
+        )?);
+
+        // each limit, one below what the circuit needs
+        let tight_limits = [
+            (
+                "num_vars",
+                ResourceLimits {
+                    max_num_vars: nv - 1,
+                    ..limits
+                },
+            ),
+            (
+                "gate_degree",
+                ResourceLimits {
+                    max_gate_degree: gate.degree() - 1,
+                    ..limits
+                },
+            ),
+            (
+                "witness_columns",
+                ResourceLimits {
+                    max_witness_columns: params.num_witness_columns() - 1,
+                    ..limits
+                },
+            ),
+            (
+                "memory_bytes_estimate",
+                ResourceLimits {
+                    max_memory_bytes_estimate: memory - 1,
+                    ..limits
+                },
+            ),
+        ];
+        for (name, tight_limits) in tight_limits.iter() {
+            assert!(matches!(
+                preprocess_with_limits::<Bls12_381, Kzg>(&circuit.index, &pcs_srs, tight_limits),
+                Err(HyperPlonkErrors::ResourceLimitExceeded(n, _, _)) if n == *name
+            ));
+            assert!(matches!(
+                prove_with_limits(
+                    &pk,
+                    &circuit.public_inputs,
+                    &circuit.witnesses,
+                    tight_limits,
+                    None,
+                ),
+                Err(HyperPlonkErrors::ResourceLimitExceeded(n, _, _)) if n == *name
+            ));
+        }
+
+        // a huge index is rejected without allocating anything
+        let mut huge_params = params.clone();
+        huge_params.num_constraints = 1 << 30;
+        assert!(matches!(
+            limits.check::<Bls12_381>(&huge_params),
+            Err(HyperPlonkErrors::ResourceLimitExceeded("num_vars", 30, _))
+        ));
+        assert!(ResourceLimits::default()
+            .check::<Bls12_381>(&huge_params)
+            .is_ok());
+        let mut huge_gate = params.clone();
+        huge_gate.gate_func = CustomizedGates::mock_gate(2, 64);
+        assert!(matches!(
+            limits.check::<Bls12_381>(&huge_gate),
+            Err(HyperPlonkErrors::ResourceLimitExceeded("gate_degree", _, _))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cancellation() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let nv = 4;
+        let pcs_srs = Kzg::gen_srs_for_testing(&mut rng, nv)?;
+
+        let gate = CustomizedGates::vanilla_plonk_gate();
+        let circuit = MockCircuit::<Fr>::new(1 << nv, &gate);
+        let (pk, _) =
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::preprocess(&circuit.index, &pcs_srs)?;
+
+        // cancelled in the middle of the gate zero check
+        let token = CancellationToken::new();
+        let mut stepper = prove_in_steps(&pk, &circuit.public_inputs, &circuit.witnesses)?;
+        stepper.set_cancellation_token(token.clone());
+        for _ in 0..circuit.num_witness_columns() + 2 {
+            assert!(matches!(stepper.step()?, StepResult::Continue));
+        }
+        token.cancel();
+        assert!(matches!(stepper.step(), Err(HyperPlonkErrors::Cancelled)));
+        assert!(matches!(stepper.step(), Err(HyperPlonkErrors::Cancelled)));
+
+        // cancelled before the proof starts
+        assert!(matches!(
+            prove_with_limits(
+                &pk,
+                &circuit.public_inputs,
+                &circuit.witnesses,
+                &ResourceLimits::default(),
+                Some(token),
+            ),
+            Err(HyperPlonkErrors::Cancelled)
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,322 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! Mask bundles: the masks of the zk sub-protocols of one proof, committed
+//! and opened as a single polynomial.
+//!
+//! A zk proof masks several sub-protocols, e.g., the gate zero check, the
+//! permutation check and the batch opening, each with a multilinear mask.
+//! Rather than committing to and opening every mask on its own, the prover
+//! packs the masks into one polynomial, the bundle, commits to it once, and
+//! adds one opening claim of the bundle per mask to the batch opening that
+//! the proof already has.
+//!
+//! The bundle of `k` masks with at most `n` variables has `n + log k`
+//! variables, `k` being rounded up to a power of two. Its evaluations are
+//! those of the first mask, then of the second one, and so on: the `i`-th
+//! mask starts at the offset `i 2^n`, so that the masks are separated by the
+//! last `log k` variables, i.e., `bundle(x, <i>) = m_i(x)` for the binary
+//! decomposition `<i>` of `i`. A mask with fewer than `n` variables is
+//! extended to not depend on the other ones. The evaluation of `m_i` at `r`
+//! is thus the evaluation of the bundle at the vertex-shifted point `(r, 0,
+//! ..., 0, <i>)`, which the verifier derives from the [`MaskBundleLayout`]
+//! alone.
+//!
+//! The batch opening only takes polynomials with the same number of
+//! variables, so the bundle must have the number of variables of the other
+//! opened polynomials.
+
+use crate::errors::HyperPlonkErrors;
+use arithmetic::{gen_eval_point, get_batched_nv, merge_polynomials};
+use ark_ff::PrimeField;
+use ark_poly::{DenseMultilinearExtension, MultilinearExtension};
+use ark_std::{log2, vec::Vec};
+use std::sync::Arc;
+
+/// The shape of a [`MaskBundle`], which is all the verifier needs to derive
+/// the opening points of the masks.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MaskBundleLayout {
+    /// number of variables of each mask, in the order of the bundle
+    mask_num_vars: Vec<usize>,
+}
+
+impl MaskBundleLayout {
+    /// The layout of a bundle of masks with `mask_num_vars` variables.
+    pub fn new(mask_num_vars: Vec<usize>) -> Result<Self, HyperPlonkErrors> {
+        if mask_num_vars.is_empty() {
+            return Err(HyperPlonkErrors::InvalidParameters(
+                "a mask bundle needs at least one mask".to_string(),
+            ));
+        }
+        Ok(Self { mask_num_vars })
+    }
+
+    /// Number of masks of the bundle.
+    pub fn num_masks(&self) -> usize {
+        self.mask_num_vars.len()
+    }
+
+    /// Number of variables of each slot of the bundle, i.e., of the largest
+    /// mask.
+    pub fn slot_num_vars(&self) -> usize {
+        self.mask_num_vars.iter().copied().max().unwrap_or(0)
+    }
+
+    /// Number of variables of the bundle.
+    pub fn num_vars(&self) -> usize {
+        get_batched_nv(self.slot_num_vars(), self.num_masks())
+    }
+
+    /// The point at which the bundle evaluates to the `index`-th mask at
+    /// `point`.
+    pub fn point<F: PrimeField>(
+        &self,
+        index: usize,
+        point: &[F],
+    ) -> Result<Vec<F>, HyperPlonkErrors> {
+        match self.mask_num_vars.get(index) {
+            None => Err(HyperPlonkErrors::InvalidParameters(format!(
+                "mask {} is not in a bundle of {} masks",
+                index,
+                self.num_masks()
+            ))),
+            Some(&nv) if nv != point.len() => Err(HyperPlonkErrors::InvalidParameters(format!(
+                "mask {} has {} variables, but the point has {} coordinates",
+                index,
+                nv,
+                point.len()
+            ))),
+            Some(_) => {
+                let mut padded = point.to_vec();
+                padded.resize(self.slot_num_vars(), F::zero());
+                Ok(gen_eval_point(
+                    index,
+                    log2(self.num_masks()) as usize,
+                    &padded,
+                ))
+            },
+        }
+    }
+}
+
+/// The masks of one proof, packed into a single polynomial, see the module
+/// documentation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MaskBundle<F: PrimeField> {
+    layout: MaskBundleLayout,
+    masks: Vec<Arc<DenseMultilinearExtension<F>>>,
+    polynomial: Arc<DenseMultilinearExtension<F>>,
+}
+
+impl<F: PrimeField> MaskBundle<F> {
+    /// Pack `masks`, in this order, into a bundle.
+    pub fn pack(masks: Vec<Arc<DenseMultilinearExtension<F>>>) -> Result<Self, HyperPlonkErrors> {
+        let layout = MaskBundleLayout::new(masks.iter().map(|mask| mask.num_vars).collect())?;
+        let slot_num_vars = layout.slot_num_vars();
+        let slots: Vec<_> = masks
+            .iter()
+            .map(|mask| {
+                // repeat the evaluations, so that the slot does not depend on
+                // the variables that the mask does not have
+                let evaluations = mask
+                    .evaluations
+                    .iter()
+                    .copied()
+                    .cycle()
+                    .take(1 << slot_num_vars)
+                    .collect();
+                Arc::new(DenseMultilinearExtension::from_evaluations_vec(
+                    slot_num_vars,
+                    evaluations,
+                ))
+            })
+            .collect();
+        let polynomial = merge_polynomials(&slots)?;
+
+        Ok(Self {
+            layout,
+            masks,
+            polynomial,
+        })
+    }
+
+    /// The layout of the bundle.
+    pub fn layout(&self) -> &MaskBundleLayout {
+        &self.layout
+    }
+
+    /// The packed polynomial, to commit to and to open.
+    pub fn polynomial(&self) -> &Arc<DenseMultilinearExtension<F>> {
+        &self.polynomial
+    }
+
+    /// The opening claim of the bundle for the evaluation of the `index`-th
+    /// mask at `point`: the point of the bundle and the evaluation.
+    pub fn claim(&self, index: usize, point: &[F]) -> Result<(Vec<F>, F), HyperPlonkErrors> {
+        let bundle_point = self.layout.point(index, point)?;
+        let evaluation = self.masks[index]
+            .evaluate(point)
+            .expect("the layout checked the number of variables of the point");
+        Ok((bundle_point, evaluation))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ff::UniformRand;
+    use ark_serialize::{CanonicalSerialize, Compress};
+    use ark_std::{rand::RngCore, test_rng};
+    use subroutines::pcs::prelude::{BatchProof, MultilinearKzgPCS, PolynomialCommitmentScheme};
+    use transcript::IOPTranscript;
+
+    type Kzg = MultilinearKzgPCS<Bls12_381>;
+
+    fn size<T: CanonicalSerialize>(x: &T) -> usize {
+        x.serialized_size(Compress::Yes)
+    }
+
+    fn batch_size(proof: &BatchProof<Bls12_381, Kzg>) -> usize {
+        size(&proof.sum_check_proof().proofs)
+            + size(&proof.f_i_eval_at_point_i)
+            + size(proof.g_prime_proof())
+    }
+
+    fn rand_point<R: RngCore>(nv: usize, rng: &mut R) -> Vec<Fr> {
+        (0..nv).map(|_| Fr::rand(rng)).collect()
+    }
+
+    fn rand_mle<R: RngCore>(nv: usize, rng: &mut R) -> Arc<DenseMultilinearExtension<Fr>> {
+        Arc::new(DenseMultilinearExtension::rand(nv, rng))
+    }
+
+    #[test]
+    fn test_mask_bundle_points() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        for mask_num_vars in [vec![3], vec![4, 4, 3], vec![2, 5, 1, 5, 1]] {
+            let masks: Vec<_> = mask_num_vars
+                .iter()
+                .map(|&nv| rand_mle(nv, &mut rng))
+                .collect();
+            let bundle = MaskBundle::pack(masks.clone())?;
+            let layout = MaskBundleLayout::new(mask_num_vars.clone())?;
+            assert_eq!(bundle.layout(), &layout);
+            assert_eq!(bundle.polynomial().num_vars, layout.num_vars());
+
+            for (i, mask) in masks.iter().enumerate() {
+                let point = rand_point(mask.num_vars, &mut rng);
+                let (bundle_point, evaluation) = bundle.claim(i, &point)?;
+                // the verifier derives the point from the layout
+                assert_eq!(bundle_point, layout.point(i, &point)?);
+                assert_eq!(Some(evaluation), mask.evaluate(&point));
+                assert_eq!(
+                    bundle.polynomial().evaluate(&bundle_point),
+                    Some(evaluation)
+                );
+            }
+
+            // a mask out of the bundle, or a point of the wrong size
+            let num_masks = mask_num_vars.len();
+            assert!(layout.point::<Fr>(num_masks, &[]).is_err());
+            let point = rand_point(mask_num_vars[0] + 1, &mut rng);
+            assert!(layout.point(0, &point).is_err());
+        }
+        assert!(MaskBundle::<Fr>::pack(vec![]).is_err());
+        Ok(())
+    }
+
+    /// The masks of the gate zero check, the permutation check and the batch
+    /// opening of a proof with 6 variables, bundled into the batch opening
+    /// of the witnesses or committed and opened independently.
+    #[test]
+    fn test_mask_bundle_proof_size() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let nv = 6;
+        let pcs_srs = Kzg::gen_srs_for_testing(&mut rng, nv)?;
+        let (ck, vk) = Kzg::trim(&pcs_srs, None, Some(nv))?;
+
+        let witnesses: Vec<_> = (0..2).map(|_| rand_mle(nv, &mut rng)).collect();
+        let witness_points: Vec<_> = (0..2).map(|_| rand_point(nv, &mut rng)).collect();
+        let witness_evals: Vec<_> = witnesses
+            .iter()
+            .zip(witness_points.iter())
+            .map(|(w, p)| w.evaluate(p).unwrap())
+            .collect();
+        let witness_commitments = witnesses
+            .iter()
+            .map(|w| Kzg::commit(&ck, w))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // 3 masks, so that the bundle has the 6 variables of the witnesses
+        let masks: Vec<_> = [4, 4, 3].iter().map(|&n| rand_mle(n, &mut rng)).collect();
+        let mask_points: Vec<_> = masks
+            .iter()
+            .map(|mask| rand_point(mask.num_vars, &mut rng))
+            .collect();
+
+        // independent masks: a commitment and an opening each
+        let mut independent_size = 0;
+        for (mask, point) in masks.iter().zip(mask_points.iter()) {
+            let commitment = Kzg::commit(&ck, mask)?;
+            let (proof, eval) = Kzg::open(&ck, mask, point)?;
+            assert!(Kzg::verify(&vk, &commitment, point, &eval, &proof)?);
+            independent_size += size(&commitment) + size(&proof) + size(&eval);
+        }
+
+        // bundled masks: a commitment and a claim in the batch opening each
+        let bundle = MaskBundle::pack(masks)?;
+        assert_eq!(bundle.layout().num_vars(), nv);
+        let bundle_commitment = Kzg::commit(&ck, bundle.polynomial())?;
+        let mut polys = witnesses.clone();
+        let mut points = witness_points.clone();
+        let mut evals = witness_evals.clone();
+        let mut commitments = witness_commitments;
+        for (i, point) in mask_points.iter().enumerate() {
+            let (bundle_point, eval) = bundle.claim(i, point)?;
+            polys.push(bundle.polynomial().clone());
+            points.push(bundle_point);
+            evals.push(eval);
+            commitments.push(bundle_commitment);
+        }
+        let mut transcript = IOPTranscript::<Fr>::new(b"mask bundle");
+        let batch_proof = Kzg::multi_open(&ck, &polys, &points, &evals, &mut transcript)?;
+        // the verifier derives the points of the masks from the layout
+        let layout = MaskBundleLayout::new(vec![4, 4, 3])?;
+        let mut verifier_points = witness_points.clone();
+        for (i, point) in mask_points.iter().enumerate() {
+            verifier_points.push(layout.point(i, point)?);
+        }
+        let mut transcript = IOPTranscript::<Fr>::new(b"mask bundle");
+        assert!(Kzg::batch_verify(
+            &vk,
+            &commitments,
+            &verifier_points,
+            &batch_proof,
+            &mut transcript
+        )?);
+
+        // the batch of the witnesses alone
+        let mut transcript = IOPTranscript::<Fr>::new(b"mask bundle");
+        let witness_batch_proof = Kzg::multi_open(
+            &ck,
+            &witnesses,
+            &witness_points,
+            &witness_evals,
+            &mut transcript,
+        )?;
+        let independent_total = batch_size(&witness_batch_proof) + independent_size;
+        let bundled_total = batch_size(&batch_proof) + size(&bundle_commitment);
+        assert!(
+            bundled_total < independent_total,
+            "bundled: {} bytes, independent: {} bytes",
+            bundled_total,
+            independent_total
+        );
+        Ok(())
+    }
+}
@@ -4,15 +4,12 @@
 // You should have received a copy of the MIT License
 // along with the HyperPlonk library. If not, see <https://mit-license.org/>.
 
-use arithmetic::identity_permutation;
 use ark_ff::PrimeField;
-use ark_std::{log2, test_rng};
+use ark_std::{log2, rand::RngCore, test_rng};
 
 use crate::{
-    custom_gate::CustomizedGates,
-    selectors::SelectorColumn,
-    structs::{HyperPlonkIndex, HyperPlonkParams},
-    witness::WitnessColumn,
+    constraint_system::ConstraintSystem, custom_gate::CustomizedGates, errors::HyperPlonkErrors,
+    selectors::SelectorColumn, structs::HyperPlonkIndex, witness::WitnessColumn,
 };
 
 pub struct MockCircuit<F: PrimeField> {
@@ -41,49 +38,69 @@ impl<F: PrimeField> MockCircuit<F> {
 impl<F: PrimeField> MockCircuit<F> {
     /// Generate a mock plonk circuit for the input constraint size.
     pub fn new(num_constraints: usize, gate: &CustomizedGates) -> MockCircuit<F> {
+        let (selectors, witnesses) = Self::random_columns(num_constraints, gate, &[]);
+        let pub_input_len = ark_std::cmp::min(4, num_constraints);
+        Self::from_columns(gate, selectors, witnesses, pub_input_len)
+    }
+
+    /// Generate a mock plonk circuit for the input constraint size, whose
+    /// public input is `public`: the first `public.len()` values of the first
+    /// witness are fixed, and the other wires are completed at random so that
+    /// the gate is satisfied.
+    ///
+    /// Panics if the length of `public` is not a power of two no larger than
+    /// `num_constraints`, or if a public value cannot satisfy the gate.
+    pub fn new_with_public_inputs(
+        num_constraints: usize,
+        gate: &CustomizedGates,
+        public: &[F],
+    ) -> MockCircuit<F> {
+        assert!(
+            public.len().is_power_of_two() && public.len() <= num_constraints,
+            "the public input length {} is not a power of two up to {}",
+            public.len(),
+            num_constraints
+        );
+        let (selectors, witnesses) = Self::random_columns(num_constraints, gate, public);
+        Self::from_columns(gate, selectors, witnesses, public.len())
+    }
+
+    /// Random selector and witness columns that satisfy `gate`, where the
+    /// first witness starts with `public`.
+    fn random_columns(
+        num_constraints: usize,
+        gate: &CustomizedGates,
+        public: &[F],
+    ) -> (Vec<SelectorColumn<F>>, Vec<WitnessColumn<F>>) {
         let mut rng = test_rng();
-        let nv = log2(num_constraints);
         let num_selectors = gate.num_selector_columns();
+        assert!(
+            num_selectors > 0,
+            "mock circuits require at least one selector"
+        );
         let num_witnesses = gate.num_witness_columns();
-        let log_n_wires = log2(num_witnesses);
-        let merged_nv = nv + log_n_wires;
 
         let mut selectors: Vec<SelectorColumn<F>> = vec![SelectorColumn::default(); num_selectors];
         let mut witnesses: Vec<WitnessColumn<F>> = vec![WitnessColumn::default(); num_witnesses];
 
-        for _cs_counter in 0..num_constraints {
+        for cs_counter in 0..num_constraints {
             let mut cur_selectors: Vec<F> = (0..(num_selectors - 1))
                 .map(|_| F::rand(&mut rng))
                 .collect();
-            let cur_witness: Vec<F> = (0..num_witnesses).map(|_| F::rand(&mut rng)).collect();
-            let mut last_selector = F::zero();
-            for (index, (coeff, q, wit)) in gate.gates.iter().enumerate() {
-                if index != num_selectors - 1 {
-                    let mut cur_monomial = if *coeff < 0 {
-                        -F::from((-coeff) as u64)
-                    } else {
-                        F::from(*coeff as u64)
-                    };
-                    cur_monomial = match q {
-                        Some(p) => cur_monomial * cur_selectors[*p],
-                        None => cur_monomial,
-                    };
-                    for wit_index in wit.iter() {
-                        cur_monomial *= cur_witness[*wit_index];
-                    }
-                    last_selector += cur_monomial;
-                } else {
-                    let mut cur_monomial = if *coeff < 0 {
-                        -F::from((-coeff) as u64)
-                    } else {
-                        F::from(*coeff as u64)
-                    };
-                    for wit_index in wit.iter() {
-                        cur_monomial *= cur_witness[*wit_index];
-                    }
-                    last_selector /= -cur_monomial;
-                }
+            let mut cur_witness: Vec<F> = (0..num_witnesses).map(|_| F::rand(&mut rng)).collect();
+            if let Some(value) = public.get(cs_counter) {
+                cur_witness[0] = *value;
             }
+            // a public value may zero the monomial of the last selector, in
+            // which case the other selectors are turned off on this row
+            let last_selector = solve_last_selector(gate, &cur_selectors, &cur_witness)
+                .or_else(|| {
+                    cur_selectors = vec![F::zero(); num_selectors - 1];
+                    solve_last_selector(gate, &cur_selectors, &cur_witness)
+                })
+                .unwrap_or_else(|| {
+                    panic!("the public input {} cannot satisfy the gate", cs_counter)
+                });
             cur_selectors.push(last_selector);
             for i in 0..num_selectors {
                 selectors[i].append(cur_selectors[i]);
@@ -92,21 +109,104 @@ impl<F: PrimeField> MockCircuit<F> {
                 witnesses[i].append(cur_witness[i]);
             }
         }
-        let pub_input_len = ark_std::cmp::min(4, num_constraints);
-        let public_inputs = witnesses[0].0[0..pub_input_len].to_vec();
+        (selectors, witnesses)
+    }
 
-        let params = HyperPlonkParams {
-            num_constraints,
-            num_pub_input: public_inputs.len(),
-            gate_func: gate.clone(),
-        };
+    /// Build a mock circuit for `gate` from explicit witness values, whose
+    /// public input is the first `num_pub_input` values of the first
+    /// witness.
+    ///
+    /// All the selectors are one, except the last one that is solved for on
+    /// each row so that the gate is satisfied. Returns an error if the
+    /// witnesses do not match the gate, or if a row cannot be satisfied: the
+    /// monomial of the last selector is zero but not the others.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn from_witnesses(
+        gate: &CustomizedGates,
+        witnesses: Vec<WitnessColumn<F>>,
+        num_pub_input: usize,
+    ) -> Result<MockCircuit<F>, HyperPlonkErrors> {
+        let num_selectors = gate.num_selector_columns();
+        if num_selectors == 0 || witnesses.len() != gate.num_witness_columns() {
+            return Err(HyperPlonkErrors::InvalidParameters(format!(
+                "a mock circuit for the gate takes {} witnesses and at least one selector, got {} witnesses and {} selectors",
+                gate.num_witness_columns(),
+                witnesses.len(),
+                num_selectors
+            )));
+        }
+        let num_constraints = witnesses[0].0.len();
+        if !num_constraints.is_power_of_two()
+            || witnesses.iter().any(|w| w.0.len() != num_constraints)
+            || num_pub_input > num_constraints
+        {
+            return Err(HyperPlonkErrors::InvalidParameters(
+                "the witnesses must have the same power of two length, at least the public input length"
+                    .to_string(),
+            ));
+        }
 
-        let permutation = identity_permutation(merged_nv as usize, 1);
-        let index = HyperPlonkIndex {
-            params,
-            permutation,
+        let mut selectors: Vec<SelectorColumn<F>> = vec![SelectorColumn::default(); num_selectors];
+        for row in 0..num_constraints {
+            let mut cur_selectors = vec![F::one(); num_selectors - 1];
+            let cur_witness: Vec<F> = witnesses.iter().map(|w| w.0[row]).collect();
+            let last_selector = solve_last_selector(gate, &cur_selectors, &cur_witness)
+                .ok_or_else(|| {
+                    HyperPlonkErrors::InvalidParameters(format!(
+                        "row {} cannot satisfy the gate",
+                        row
+                    ))
+                })?;
+            cur_selectors.push(last_selector);
+            for (selector, value) in selectors.iter_mut().zip(cur_selectors) {
+                selector.append(value);
+            }
+        }
+        Ok(Self::from_columns(
+            gate,
             selectors,
-        };
+            witnesses,
+            num_pub_input,
+        ))
+    }
+
+    /// Generate a mock circuit for `CustomizedGates::range_check_gate(k)`,
+    /// whose witness takes random values in `[0, 2^k)`.
+    pub fn new_with_range_check(num_constraints: usize, k: usize) -> MockCircuit<F> {
+        let mut rng = test_rng();
+        let gate = CustomizedGates::range_check_gate(k);
+        let witness = WitnessColumn(
+            (0..num_constraints)
+                .map(|_| F::from(rng.next_u64() % (1 << k)))
+                .collect(),
+        );
+        let selector = SelectorColumn(vec![F::zero(); num_constraints]);
+        let pub_input_len = ark_std::cmp::min(4, num_constraints);
+        Self::from_columns(&gate, vec![selector], vec![witness], pub_input_len)
+    }
+
+    /// Build the circuit without copy constraints from its columns.
+    fn from_columns(
+        gate: &CustomizedGates,
+        selectors: Vec<SelectorColumn<F>>,
+        witnesses: Vec<WitnessColumn<F>>,
+        pub_input_len: usize,
+    ) -> MockCircuit<F> {
+        let num_constraints = witnesses[0].0.len();
+        let public_inputs = witnesses[0].0[0..pub_input_len].to_vec();
+
+        let mut cs = ConstraintSystem::new(gate);
+        cs.set_num_pub_input(pub_input_len);
+        let mut selector_row = Vec::with_capacity(selectors.len());
+        for row in 0..num_constraints {
+            selector_row.clear();
+            selector_row.extend(selectors.iter().map(|selector| selector.0[row]));
+            cs.add_gate(&selector_row)
+                .expect("the selectors do not match the gate");
+        }
+        let index = cs
+            .finalize(log2(num_constraints) as usize)
+            .expect("the public input fits in the circuit");
 
         Self {
             public_inputs,
@@ -116,9 +216,51 @@ impl<F: PrimeField> MockCircuit<F> {
     }
 
     pub fn is_satisfied(&self) -> bool {
-        for current_row in 0..self.num_variables() {
+        self.check_satisfied().is_ok()
+    }
+
+    /// Check that the public input matches the first wires and that every
+    /// row satisfies the gate, and report the first row that does not.
+    pub fn check_satisfied(&self) -> Result<(), HyperPlonkErrors> {
+        if self.public_inputs.len() != self.index.params.num_pub_input {
+            return Err(HyperPlonkErrors::InvalidParameters(format!(
+                "public input length {} does not match the circuit, expect {}",
+                self.public_inputs.len(),
+                self.index.params.num_pub_input
+            )));
+        }
+        for (i, (public, wire)) in self
+            .public_inputs
+            .iter()
+            .zip(self.witnesses[0].0.iter())
+            .enumerate()
+        {
+            if public != wire {
+                return Err(HyperPlonkErrors::InvalidParameters(format!(
+                    "public input {} does not match the witness",
+                    i
+                )));
+            }
+        }
+        self.is_satisfied_verbose().map_err(|violation| {
+            HyperPlonkErrors::InvalidParameters(if self.index.params.gate_func.is_boolean_gate() {
+                format!(
+                    "row {} is not boolean: witness {}",
+                    violation.row, violation.wire_values[0]
+                )
+            } else {
+                format!("row {} does not satisfy the gate", violation.row)
+            })
+        })
+    }
+
+    /// Check that every gate is satisfied, and report the first one that is
+    /// not with the values of its wires.
+    pub fn is_satisfied_verbose(&self) -> Result<(), GateViolation<F>> {
+        let gate = &self.index.params.gate_func;
+        for current_row in 0..self.index.params.num_constraints {
             let mut cur = F::zero();
-            for (coeff, q, wit) in self.index.params.gate_func.gates.iter() {
+            for (coeff, q, wit) in gate.gates.iter() {
                 let mut cur_monomial = if *coeff < 0 {
                     -F::from((-coeff) as u64)
                 } else {
@@ -134,11 +276,66 @@ impl<F: PrimeField> MockCircuit<F> {
                 cur += cur_monomial;
             }
             if !cur.is_zero() {
-                return false;
+                return Err(GateViolation {
+                    gate_index: current_row,
+                    row: current_row,
+                    wire_values: self.witnesses.iter().map(|w| w.0[current_row]).collect(),
+                    expected: F::zero(),
+                    actual: cur,
+                });
             }
         }
 
-        true
+        Ok(())
+    }
+}
+
+/// A gate of a mock circuit that is not satisfied.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GateViolation<F: PrimeField> {
+    /// Index of the gate in the circuit. Mock circuits have one gate per
+    /// row, so it is also the row.
+    pub gate_index: usize,
+    /// Row of the witness columns that the gate reads.
+    pub row: usize,
+    /// Values of the witnesses on the row, one per witness column.
+    pub wire_values: Vec<F>,
+    /// Value the gate identity should evaluate to.
+    pub expected: F,
+    /// Value the gate identity evaluates to.
+    pub actual: F,
+}
+
+/// The value of the last selector that satisfies `gate` on a row with the
+/// other `selectors` and `witness`, if any.
+fn solve_last_selector<F: PrimeField>(
+    gate: &CustomizedGates,
+    selectors: &[F],
+    witness: &[F],
+) -> Option<F> {
+    // the monomial of the last selector is solved for so that the gate is
+    // satisfied; all other monomials are summed up
+    let mut sum = F::zero();
+    let mut last_monomial = F::zero();
+    for (coeff, q, wit) in gate.gates.iter() {
+        let mut cur_monomial = if *coeff < 0 {
+            -F::from((-coeff) as u64)
+        } else {
+            F::from(*coeff as u64)
+        };
+        for wit_index in wit.iter() {
+            cur_monomial *= witness[*wit_index];
+        }
+        match q {
+            Some(p) if *p == selectors.len() => last_monomial = cur_monomial,
+            Some(p) => sum += cur_monomial * selectors[*p],
+            None => sum += cur_monomial,
+        }
+    }
+    match (last_monomial.is_zero(), sum.is_zero()) {
+        (false, _) => Some(-sum / last_monomial),
+        (true, true) => Some(F::one()),
+        (true, false) => None,
     }
 }
 
@@ -147,6 +344,14 @@ mod test {
     use super::*;
     use crate::{errors::HyperPlonkErrors, HyperPlonkSNARK};
     use ark_bls12_381::{Bls12_381, Fr};
+    use ark_std::{
+        rand::{rngs::StdRng, SeedableRng},
+        One, UniformRand, Zero,
+    };
+    use proptest::{
+        prelude::*,
+        test_runner::{Config, RngAlgorithm, TestRng, TestRunner},
+    };
     use subroutines::{
         pcs::{
             prelude::{MultilinearKzgPCS, MultilinearUniversalParams},
@@ -171,7 +376,11 @@ mod test {
             let circuit = MockCircuit::<Fr>::new(1 << i, &jf_gate);
             assert!(circuit.is_satisfied());
 
-            for num_witness in 2..10 {
+            let single_column_gate = CustomizedGates::single_column_gate(5);
+            let circuit = MockCircuit::<Fr>::new(1 << i, &single_column_gate);
+            assert!(circuit.is_satisfied());
+
+            for num_witness in 1..10 {
                 for degree in CUSTOM_DEGREE {
                     let mock_gate = CustomizedGates::mock_gate(num_witness, degree);
                     let circuit = MockCircuit::<Fr>::new(1 << i, &mock_gate);
@@ -227,7 +436,7 @@ mod test {
             test_mock_circuit_zkp_helper(nv, &tubro_gate, &pcs_srs)?;
         }
         let nv = 5;
-        for num_witness in 2..10 {
+        for num_witness in 1..10 {
             for degree in CUSTOM_DEGREE {
                 let mock_gate = CustomizedGates::mock_gate(num_witness, degree);
                 test_mock_circuit_zkp_helper(nv, &mock_gate, &pcs_srs)?;
@@ -237,6 +446,83 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_mock_circuit_three_wire_gate() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let pcs_srs = MultilinearKzgPCS::<Bls12_381>::gen_srs_for_testing(&mut rng, 8)?;
+        // q_M w_1 w_2 w_3 + q_O w_4 = 0
+        let gate = CustomizedGates {
+            gates: vec![(1, Some(0), vec![0, 1, 2]), (1, Some(1), vec![3])],
+            lookups: vec![],
+        };
+        assert_eq!(gate.num_witness_columns(), 4);
+        assert_eq!(gate.degree(), 4);
+        let circuit = MockCircuit::<Fr>::new(1 << 4, &gate);
+        assert!(circuit.is_satisfied());
+        test_mock_circuit_zkp_helper(4, &gate, &pcs_srs)
+    }
+
+    #[test]
+    fn test_mock_circuit_range_check() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let pcs_srs = MultilinearKzgPCS::<Bls12_381>::gen_srs_for_testing(&mut rng, 8)?;
+        for k in 0..=4 {
+            let gate = CustomizedGates::range_check_gate(k);
+            assert_eq!(gate.degree(), 1 << k);
+            assert_eq!(gate.num_witness_columns(), 1);
+            for value in 0..1u64 << (k + 1) {
+                let eval = gate.evaluate(&[Fr::zero()], &[Fr::from(value)])?;
+                assert_eq!(eval.is_zero(), value < 1 << k);
+            }
+
+            let circuit = MockCircuit::<Fr>::new_with_range_check(1 << 4, k);
+            assert!(circuit.is_satisfied());
+            let (pk, vk) = <PolyIOP<Fr> as HyperPlonkSNARK<
+                Bls12_381,
+                MultilinearKzgPCS<Bls12_381>,
+            >>::preprocess(&circuit.index, &pcs_srs)?;
+            assert!(!vk.selectors[0].is_committed());
+            let proof = <PolyIOP<Fr> as HyperPlonkSNARK<
+                Bls12_381,
+                MultilinearKzgPCS<Bls12_381>,
+            >>::prove(&pk, &circuit.public_inputs, &circuit.witnesses)?;
+            assert!(<PolyIOP<Fr> as HyperPlonkSNARK<
+                Bls12_381,
+                MultilinearKzgPCS<Bls12_381>,
+            >>::verify(&vk, &circuit.public_inputs, &proof)?);
+
+            // a value out of range does not satisfy the gate
+            let mut bad_circuit = circuit;
+            bad_circuit.witnesses[0].0[5] = Fr::from(1u64 << k);
+            assert!(!bad_circuit.is_satisfied());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_mock_circuit_boolean() -> Result<(), HyperPlonkErrors> {
+        let gate = CustomizedGates::boolean_gate();
+        assert!(gate.is_boolean_gate());
+        assert!(!CustomizedGates::range_check_gate(2).is_boolean_gate());
+        assert!(!CustomizedGates::vanilla_plonk_gate().is_boolean_gate());
+
+        let circuit = MockCircuit::<Fr>::new_with_range_check(1 << 3, 1);
+        assert_eq!(circuit.index.params.gate_func, gate);
+        circuit.check_satisfied()?;
+
+        for value in [2u64, 3, 1 << 20] {
+            let mut bad_circuit = MockCircuit::<Fr>::new_with_range_check(1 << 3, 1);
+            bad_circuit.witnesses[0].0[6] = Fr::from(value);
+            assert!(!bad_circuit.is_satisfied());
+            let err = bad_circuit.check_satisfied().unwrap_err().to_string();
+            assert!(err.contains("row 6 is not boolean"), "{}", err);
+        }
+        let mut bad_circuit = MockCircuit::<Fr>::new_with_range_check(1 << 3, 1);
+        bad_circuit.witnesses[0].0[2] = -Fr::one();
+        assert!(!bad_circuit.is_satisfied());
+        Ok(())
+    }
+
     #[test]
     fn test_mock_circuit_e2e() -> Result<(), HyperPlonkErrors> {
         let mut rng = test_rng();
@@ -262,4 +548,183 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn test_mock_circuit_from_witnesses() -> Result<(), HyperPlonkErrors> {
+        let gate = CustomizedGates::vanilla_plonk_gate();
+        let circuit = MockCircuit::<Fr>::new(1 << 3, &gate);
+        let rebuilt = MockCircuit::from_witnesses(&gate, circuit.witnesses.clone(), 4)?;
+        assert!(rebuilt.is_satisfied());
+        assert_eq!(rebuilt.public_inputs, circuit.public_inputs);
+        assert_eq!(rebuilt.index.params, circuit.index.params);
+        assert_eq!(rebuilt.index.permutation, circuit.index.permutation);
+
+        // bad path: wrong number of witnesses, wrong lengths
+        assert!(MockCircuit::from_witnesses(&gate, circuit.witnesses[1..].to_vec(), 4).is_err());
+        let mut witnesses = circuit.witnesses.clone();
+        witnesses[1].0.pop();
+        assert!(MockCircuit::from_witnesses(&gate, witnesses, 4).is_err());
+        assert!(MockCircuit::from_witnesses(&gate, circuit.witnesses.clone(), 16).is_err());
+        // bad path: a row that cannot be satisfied, q_c is the last selector
+        let gate = CustomizedGates {
+            gates: vec![(1, None, vec![0]), (1, Some(0), vec![1])],
+            lookups: vec![],
+        };
+        let witnesses = vec![
+            WitnessColumn(vec![Fr::one(); 2]),
+            WitnessColumn(vec![Fr::zero(); 2]),
+        ];
+        assert!(MockCircuit::from_witnesses(&gate, witnesses, 1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_mock_circuit_gate_violation() {
+        let gate = CustomizedGates::vanilla_plonk_gate();
+        let circuit = MockCircuit::<Fr>::new(1 << 3, &gate);
+        assert_eq!(circuit.is_satisfied_verbose(), Ok(()));
+
+        let mut bad_circuit = circuit;
+        bad_circuit.witnesses[2].0[5] += Fr::one();
+        let violation = bad_circuit.is_satisfied_verbose().unwrap_err();
+        assert_eq!(violation.gate_index, 5);
+        assert_eq!(violation.row, 5);
+        let wire_values: Vec<Fr> = bad_circuit.witnesses.iter().map(|w| w.0[5]).collect();
+        assert_eq!(violation.wire_values, wire_values);
+        assert_eq!(violation.expected, Fr::zero());
+        // q_O w_3 is off by the output selector
+        assert_eq!(violation.actual, bad_circuit.index.selectors[2].0[5]);
+        assert!(!bad_circuit.is_satisfied());
+        let err = bad_circuit.check_satisfied().unwrap_err().to_string();
+        assert!(err.contains("row 5 does not satisfy the gate"), "{}", err);
+
+        // the first violated gate is reported
+        bad_circuit.witnesses[0].0[3] += Fr::one();
+        assert_eq!(
+            bad_circuit.is_satisfied_verbose().unwrap_err().gate_index,
+            3
+        );
+    }
+
+    #[test]
+    fn test_mock_circuit_public_inputs() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let pcs_srs = MultilinearKzgPCS::<Bls12_381>::gen_srs_for_testing(&mut rng, 8)?;
+        let public = [Fr::from(3u64), Fr::zero(), Fr::one(), -Fr::from(7u64)];
+        for gate in [
+            CustomizedGates::vanilla_plonk_gate(),
+            CustomizedGates::jellyfish_turbo_plonk_gate(),
+            CustomizedGates::mock_gate(2, 3),
+            // q_1 w_2 + q_2 w_1: the zero public value turns q_1 off
+            CustomizedGates {
+                gates: vec![(1, Some(0), vec![1]), (1, Some(1), vec![0])],
+                lookups: vec![],
+            },
+        ] {
+            let circuit = MockCircuit::<Fr>::new_with_public_inputs(1 << 4, &gate, &public);
+            circuit.check_satisfied()?;
+            assert_eq!(circuit.public_inputs, public);
+            assert_eq!(circuit.index.params.num_pub_input, public.len());
+
+            let (pk, vk) = <PolyIOP<Fr> as HyperPlonkSNARK<
+                Bls12_381,
+                MultilinearKzgPCS<Bls12_381>,
+            >>::preprocess(&circuit.index, &pcs_srs)?;
+            let proof = <PolyIOP<Fr> as HyperPlonkSNARK<
+                Bls12_381,
+                MultilinearKzgPCS<Bls12_381>,
+            >>::prove(&pk, &public, &circuit.witnesses)?;
+            assert!(<PolyIOP<Fr> as HyperPlonkSNARK<
+                Bls12_381,
+                MultilinearKzgPCS<Bls12_381>,
+            >>::verify(&vk, &public, &proof)?);
+
+            // bad path: the verifier is given a wrong public input
+            let mut bad_public = public.to_vec();
+            bad_public[2] += Fr::one();
+            assert!(
+                !<PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, MultilinearKzgPCS<Bls12_381>>>::verify(
+                    &vk,
+                    &bad_public,
+                    &proof
+                )
+                .unwrap_or(false)
+            );
+
+            // bad path: the public input does not match the wires
+            let mut bad_circuit = circuit;
+            bad_circuit.public_inputs = bad_public;
+            let err = bad_circuit.check_satisfied().unwrap_err().to_string();
+            assert!(err.contains("public input 2"), "{}", err);
+        }
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_mock_circuit_public_inputs_too_long() {
+        let gate = CustomizedGates::vanilla_plonk_gate();
+        MockCircuit::<Fr>::new_with_public_inputs(1 << 2, &gate, &[Fr::one(); 8]);
+    }
+
+    /// Circuits of 1 to 4 variables, for a gate among a few shapes, with
+    /// witnesses derived from a seed. Proptest shrinks them toward fewer
+    /// variables and smaller gates.
+    fn circuit_shape() -> impl Strategy<Value = (usize, CustomizedGates, u64)> {
+        let gate = prop_oneof![
+            (1..4usize, 1..4usize).prop_map(|(num_witness, degree)| {
+                CustomizedGates::mock_gate(num_witness, degree)
+            }),
+            Just(CustomizedGates::vanilla_plonk_gate()),
+            Just(CustomizedGates::jellyfish_turbo_plonk_gate()),
+        ];
+        (1..=4usize, gate, any::<u64>())
+    }
+
+    #[test]
+    fn test_mock_circuit_shapes() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let pcs_srs = MultilinearKzgPCS::<Bls12_381>::gen_srs_for_testing(&mut rng, 8)?;
+        let mut runner = TestRunner::new_with_rng(
+            Config {
+                cases: 8,
+                failure_persistence: None,
+                ..Config::default()
+            },
+            TestRng::deterministic_rng(RngAlgorithm::ChaCha),
+        );
+        let result = runner.run(&circuit_shape(), |(nv, gate, seed)| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let witnesses = (0..gate.num_witness_columns())
+                .map(|_| WitnessColumn((0..1 << nv).map(|_| Fr::rand(&mut rng)).collect()))
+                .collect();
+            let fail = |e: HyperPlonkErrors| TestCaseError::fail(e.to_string());
+            let circuit =
+                MockCircuit::from_witnesses(&gate, witnesses, 1 << (nv - 1)).map_err(fail)?;
+            prop_assert!(circuit.is_satisfied());
+
+            let (pk, vk) = <PolyIOP<Fr> as HyperPlonkSNARK<
+                Bls12_381,
+                MultilinearKzgPCS<Bls12_381>,
+            >>::preprocess(&circuit.index, &pcs_srs)
+            .map_err(fail)?;
+            let proof =
+                <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, MultilinearKzgPCS<Bls12_381>>>::prove(
+                    &pk,
+                    &circuit.public_inputs,
+                    &circuit.witnesses,
+                )
+                .map_err(fail)?;
+            prop_assert!(<PolyIOP<Fr> as HyperPlonkSNARK<
+                Bls12_381,
+                MultilinearKzgPCS<Bls12_381>,
+            >>::verify(&vk, &circuit.public_inputs, &proof,)
+            .map_err(fail)?);
+            Ok(())
+        });
+        if let Err(e) = result {
+            panic!("{}", e);
+        }
+        Ok(())
+    }
 }
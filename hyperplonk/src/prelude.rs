@@ -5,6 +5,30 @@
 // along with the HyperPlonk library. If not, see <https://mit-license.org/>.
 
 pub use crate::{
-    custom_gate::CustomizedGates, errors::HyperPlonkErrors, mock::MockCircuit,
-    selectors::SelectorColumn, witness::WitnessColumn, HyperPlonkSNARK,
+    artifacts::{analyze, commit_artifacts, CircuitArtifacts},
+    blinding::recommended_blinding_rows,
+    committer::{prove_with_generator, IncrementalCommitter, PrecommittedWitnesses},
+    config::{prove_with_config, ProverConfig},
+    constraint_system::{ConstraintSystem, Wire},
+    custom_gate::{CustomizedGates, GateComposition},
+    data_parallel::{
+        preprocess_data_parallel, prove_data_parallel, stack_index, verify_data_parallel, Instance,
+    },
+    elimination::EliminationReport,
+    errors::HyperPlonkErrors,
+    gadgets::foreign_field::{ForeignFieldColumn, ForeignFieldConfig},
+    limits::{prove_with_limits, CancellationToken, ResourceLimits},
+    mask_bundle::{MaskBundle, MaskBundleLayout},
+    mock::{GateViolation, MockCircuit},
+    profile::{prove_with_profile, ProofProfile},
+    public_input::{decode_bytes, encode_bytes},
+    schedule::{transcript_schedule, TranscriptEvent},
+    selectors::{SelectorColumn, SelectorDescription},
+    statistics::IndexStatistics,
+    stepper::{prove_in_steps, ProverStepper, StepResult},
+    streaming::{serialize_streaming, verify_streaming},
+    structs::EvaluationClaim,
+    vk_compression::CompressedVerifierKey,
+    witness::{WitnessColumn, WitnessSource},
+    HyperPlonkSNARK, PROTOCOL_VERSION, SUPPORTED_VERSIONS,
 };
@@ -0,0 +1,226 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! The wall-clock time of the phases of the HyperPlonk prover.
+//!
+//! [`prove_with_profile`] drives a
+//! [`ProverStepper`](crate::stepper::ProverStepper) and measures every step
+//! with [`Instant`], so that the profile only costs two clock reads per step.
+
+use crate::{
+    errors::HyperPlonkErrors,
+    stepper::{prove_in_steps, StepResult},
+    structs::{HyperPlonkProof, HyperPlonkProvingKey},
+    witness::WitnessSource,
+};
+use ark_ec::pairing::Pairing;
+use ark_poly::DenseMultilinearExtension;
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use subroutines::{
+    pcs::prelude::{Commitment, PolynomialCommitmentScheme},
+    poly_iop::PolyIOP,
+    BatchProof,
+};
+
+/// The phases of the prover that a [`ProofProfile`] distinguishes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ProfiledPhase {
+    /// the witness polynomials, the gate identity, and the polynomials of
+    /// the permutation check
+    MleComputation,
+    /// the commitments to the witnesses, to frac(x) and to prod(x)
+    Commitment,
+    /// the rounds of the gate and permutation zero checks
+    SumCheck,
+    /// the lookup check, with its commitments and its sum check
+    LookupCheck,
+    /// the evaluations and the batch opening
+    Opening,
+}
+
+/// The wall-clock time spent in each phase of a proof.
+///
+/// `total` is the time of the whole proof, which is slightly more than the
+/// sum of the phases.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ProofProfile {
+    /// building the polynomials: the witnesses, the gate identity, and
+    /// frac(x) and prod(x) of the permutation check
+    pub mle_computation: Duration,
+    /// the KZG commitments to the witnesses, to frac(x) and to prod(x)
+    pub commitment: Duration,
+    /// the rounds of the gate and permutation zero checks
+    pub sum_check: Duration,
+    /// the lookup check, if the gate has lookups
+    pub lookup_check: Duration,
+    /// the evaluations at the opening points and the KZG batch opening
+    pub opening: Duration,
+    /// the whole proof
+    pub total: Duration,
+}
+
+impl ProofProfile {
+    /// The phases, with their names.
+    fn phases(&self) -> [(&'static str, Duration); 5] {
+        [
+            ("mle_computation", self.mle_computation),
+            ("commitment", self.commitment),
+            ("sum_check", self.sum_check),
+            ("lookup_check", self.lookup_check),
+            ("opening", self.opening),
+        ]
+    }
+
+    fn record(&mut self, phase: ProfiledPhase, elapsed: Duration) {
+        let time = match phase {
+            ProfiledPhase::MleComputation => &mut self.mle_computation,
+            ProfiledPhase::Commitment => &mut self.commitment,
+            ProfiledPhase::SumCheck => &mut self.sum_check,
+            ProfiledPhase::LookupCheck => &mut self.lookup_check,
+            ProfiledPhase::Opening => &mut self.opening,
+        };
+        *time += elapsed;
+    }
+
+    /// The profile as a JSON object, with the times in microseconds, e.g.
+    /// `{"mle_computation_us":12,...,"total_us":80}`.
+    pub fn to_json(&self) -> String {
+        let fields = self
+            .phases()
+            .iter()
+            .chain([("total", self.total)].iter())
+            .map(|(name, time)| format!("\"{}_us\":{}", name, time.as_micros()))
+            .collect::<Vec<_>>();
+        format!("{{{}}}", fields.join(","))
+    }
+}
+
+/// A table of the phases, with their times and their shares of the total.
+impl fmt::Display for ProofProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<16} {:>12} {:>7}", "phase", "time (us)", "share")?;
+        let total = self.total.as_secs_f64();
+        for (name, time) in self.phases() {
+            let share = if total > 0.0 {
+                100.0 * time.as_secs_f64() / total
+            } else {
+                0.0
+            };
+            writeln!(f, "{:<16} {:>12} {:>6.1}%", name, time.as_micros(), share)?;
+        }
+        write!(f, "{:<16} {:>12}", "total", self.total.as_micros())
+    }
+}
+
+/// `HyperPlonkSNARK::prove`, which also outputs the time spent in each
+/// phase of the prover. The proof is the same.
+#[allow(clippy::type_complexity)]
+pub fn prove_with_profile<E, PCS, W>(
+    pk: &HyperPlonkProvingKey<E, PCS>,
+    pub_input: &[E::ScalarField],
+    witnesses: &W,
+) -> Result<
+    (
+        HyperPlonkProof<E, PolyIOP<E::ScalarField>, PCS>,
+        ProofProfile,
+    ),
+    HyperPlonkErrors,
+>
+where
+    E: Pairing,
+    W: WitnessSource<E::ScalarField> + ?Sized,
+    PCS: PolynomialCommitmentScheme<
+        E,
+        Polynomial = Arc<DenseMultilinearExtension<E::ScalarField>>,
+        Point = Vec<E::ScalarField>,
+        Evaluation = E::ScalarField,
+        Commitment = Commitment<E>,
+        BatchProof = BatchProof<E, PCS>,
+    >,
+{
+    let mut profile = ProofProfile::default();
+    let start = Instant::now();
+    let mut stepper = prove_in_steps(pk, pub_input, witnesses)?;
+    profile.record(ProfiledPhase::MleComputation, start.elapsed());
+
+    // the witnesses are committed to in parallel, as in `ProverStepper::run`
+    let step = Instant::now();
+    stepper.commit_witnesses()?;
+    profile.record(ProfiledPhase::Commitment, step.elapsed());
+
+    loop {
+        let phase = stepper.profiled_phase();
+        let step = Instant::now();
+        let result = stepper.step()?;
+        profile.record(phase, step.elapsed());
+        if let StepResult::Done(proof) = result {
+            profile.total = start.elapsed();
+            return Ok((proof, profile));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{custom_gate::CustomizedGates, mock::MockCircuit, HyperPlonkSNARK};
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_std::test_rng;
+    use subroutines::pcs::prelude::MultilinearKzgPCS;
+
+    type Kzg = MultilinearKzgPCS<Bls12_381>;
+
+    #[test]
+    fn test_prove_with_profile() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let nv = 4;
+        let pcs_srs = Kzg::gen_srs_for_testing(&mut rng, nv)?;
+
+        for circuit in [
+            MockCircuit::<Fr>::new(1 << nv, &CustomizedGates::vanilla_plonk_gate()),
+            MockCircuit::new_with_range_check(1 << nv, 2),
+        ] {
+            let (pk, vk) = <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::preprocess(
+                &circuit.index,
+                &pcs_srs,
+            )?;
+            let (proof, profile) =
+                prove_with_profile(&pk, &circuit.public_inputs, &circuit.witnesses)?;
+            assert_eq!(
+                proof,
+                <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::prove(
+                    &pk,
+                    &circuit.public_inputs,
+                    &circuit.witnesses,
+                )?
+            );
+            assert!(<PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::verify(
+                &vk,
+                &circuit.public_inputs,
+                &proof,
+            )?);
+
+            let phases = profile.phases();
+            assert!(phases.iter().map(|(_, time)| *time).sum::<Duration>() <= profile.total);
+            if circuit.index.params.gate_func.lookups().is_empty() {
+                assert_eq!(profile.lookup_check, Duration::ZERO);
+            }
+
+            let json = profile.to_json();
+            assert!(json.starts_with("{\"mle_computation_us\":"));
+            assert!(json.ends_with(&format!("\"total_us\":{}}}", profile.total.as_micros())));
+            assert_eq!(json.matches("_us\":").count(), phases.len() + 1);
+            // a header, one line per phase and the total
+            assert_eq!(profile.to_string().lines().count(), phases.len() + 2);
+        }
+
+        Ok(())
+    }
+}
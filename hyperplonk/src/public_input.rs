@@ -0,0 +1,265 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! Encoding of byte strings as public inputs.
+//!
+//! [`encode_bytes`] packs a byte string into field elements with a fixed
+//! encoding, so that the prover and the verifier agree on the public input
+//! whatever language they are written in. The encoding of `data` under the
+//! domain `domain` consists of, in order:
+//! - the domain tag: the first `min(L, 32)` bytes of the SHA-256 hash of
+//!   `"hyperplonk public input v1"`, the length of `domain` on 8 bytes in
+//!   little-endian and `domain`, read as a little-endian integer;
+//! - the length of `data` in bytes;
+//! - the limbs of `data`: its consecutive chunks of `L` bytes, the last one
+//!   possibly shorter, each read as a little-endian integer;
+//! - zeros, up to the next power of two number of elements, as the public
+//!   input must have a power of two length.
+//!
+//! `L` is the number of bytes that always fit below the modulus, i.e., `(b -
+//! 1) / 8` for a `b`-bit modulus, which is 31 for the scalar field of
+//! BLS12-381. The encoding is pinned by `test_vectors/public_input.txt`.
+//!
+//! The encoding is a `Vec` of field elements, which `HyperPlonkSNARK::prove`
+//! and `HyperPlonkSNARK::verify` take directly as public input.
+
+use crate::errors::HyperPlonkErrors;
+use ark_ff::{BigInteger, PrimeField};
+use ark_std::{cmp::min, vec::Vec};
+use sha2::{Digest, Sha256};
+
+/// Domain separator of the hash of the domain tag.
+const DOMAIN_SEPARATOR: &[u8] = b"hyperplonk public input v1";
+
+/// Number of bytes of a limb for the field `F`.
+fn limb_bytes<F: PrimeField>() -> usize {
+    (F::MODULUS_BIT_SIZE as usize - 1) / 8
+}
+
+/// The first element of the encoding under `domain`.
+fn domain_tag<F: PrimeField>(domain: &[u8]) -> F {
+    let hash = Sha256::new()
+        .chain_update(DOMAIN_SEPARATOR)
+        .chain_update((domain.len() as u64).to_le_bytes())
+        .chain_update(domain)
+        .finalize();
+    F::from_le_bytes_mod_order(&hash[..min(limb_bytes::<F>(), hash.len())])
+}
+
+/// Encode `data` under the domain `domain` as public input, see the module
+/// documentation.
+pub fn encode_bytes<F: PrimeField>(domain: &[u8], data: &[u8]) -> Vec<F> {
+    let mut elements = vec![domain_tag(domain), F::from(data.len() as u64)];
+    elements.extend(
+        data.chunks(limb_bytes::<F>())
+            .map(F::from_le_bytes_mod_order),
+    );
+    elements.resize(elements.len().next_power_of_two(), F::zero());
+    elements
+}
+
+/// Decode the data encoded by [`encode_bytes`] under the domain `domain`.
+///
+/// Any input that is not exactly the encoding of some data under `domain`
+/// is rejected.
+pub fn decode_bytes<F: PrimeField>(
+    domain: &[u8],
+    elements: &[F],
+) -> Result<Vec<u8>, HyperPlonkErrors> {
+    let invalid = |msg: &str| {
+        Err(HyperPlonkErrors::InvalidParameters(format!(
+            "invalid public input encoding: {}",
+            msg
+        )))
+    };
+    let limb_bytes = limb_bytes::<F>();
+    if elements.len() < 2 {
+        return invalid("too short");
+    }
+    if elements[0] != domain_tag(domain) {
+        return invalid("wrong domain");
+    }
+    let len = match to_bytes(elements[1], 8) {
+        Some(bytes) => u64::from_le_bytes(bytes.try_into().expect("8 bytes")) as usize,
+        None => return invalid("the length does not fit on 8 bytes"),
+    };
+    let num_limbs = len.div_ceil(limb_bytes);
+    if elements.len() != (2 + num_limbs).next_power_of_two() {
+        return invalid("wrong number of elements");
+    }
+
+    let mut data = Vec::with_capacity(num_limbs * limb_bytes);
+    for &limb in elements[2..2 + num_limbs].iter() {
+        match to_bytes(limb, limb_bytes) {
+            Some(bytes) => data.extend_from_slice(&bytes),
+            None => return invalid("a limb does not fit"),
+        }
+    }
+    if data[len..].iter().any(|&b| b != 0) || elements[2 + num_limbs..].iter().any(|e| !e.is_zero())
+    {
+        return invalid("non-zero padding");
+    }
+    data.truncate(len);
+    Ok(data)
+}
+
+/// The `len` little-endian bytes of `x`, if it is smaller than `2^(8 len)`.
+fn to_bytes<F: PrimeField>(x: F, len: usize) -> Option<Vec<u8>> {
+    let mut bytes = x.into_bigint().to_bytes_le();
+    if bytes[len..].iter().any(|&b| b != 0) {
+        return None;
+    }
+    bytes.truncate(len);
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        custom_gate::CustomizedGates,
+        selectors::SelectorColumn,
+        structs::{HyperPlonkIndex, HyperPlonkParams},
+        witness::WitnessColumn,
+        HyperPlonkSNARK,
+    };
+    use arithmetic::identity_permutation;
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ff::{One, Zero};
+    use ark_serialize::CanonicalSerialize;
+    use ark_std::test_rng;
+    use subroutines::{
+        pcs::prelude::{MultilinearKzgPCS, PolynomialCommitmentScheme},
+        poly_iop::PolyIOP,
+    };
+
+    type Kzg = MultilinearKzgPCS<Bls12_381>;
+
+    const TEST_VECTORS: &str = include_str!("../test_vectors/public_input.txt");
+
+    fn test_vector(name: &str) -> &'static str {
+        TEST_VECTORS
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .find_map(|line| line.strip_prefix(name)?.trim().strip_prefix('='))
+            .map(str::trim)
+            .expect("missing test vector")
+    }
+
+    fn to_hex(elements: &[Fr]) -> String {
+        let mut bytes = vec![];
+        for element in elements.iter() {
+            element
+                .serialize_compressed(&mut bytes)
+                .expect("serialization into a vector cannot fail");
+        }
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn data(len: usize) -> Vec<u8> {
+        (0..len).map(|i| i as u8).collect()
+    }
+
+    #[test]
+    fn test_public_input_test_vectors() -> Result<(), HyperPlonkErrors> {
+        assert_eq!(limb_bytes::<Fr>(), 31);
+        assert_eq!(to_hex(&encode_bytes::<Fr>(b"", b"")), test_vector("empty"));
+        for len in [40, 62, 100] {
+            let encoded = encode_bytes::<Fr>(b"hyperplonk test", &data(len));
+            assert_eq!(to_hex(&encoded), test_vector(&format!("bytes_{}", len)));
+            assert_eq!(decode_bytes(b"hyperplonk test", &encoded)?, data(len));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_bytes() -> Result<(), HyperPlonkErrors> {
+        for len in 0..100 {
+            let encoded = encode_bytes::<Fr>(b"domain", &data(len));
+            assert!(encoded.len().is_power_of_two());
+            assert_eq!(decode_bytes(b"domain", &encoded)?, data(len));
+        }
+
+        let encoded = encode_bytes::<Fr>(b"domain", &data(40));
+        // another domain
+        assert!(decode_bytes(b"other domain", &encoded).is_err());
+        assert_ne!(encode_bytes::<Fr>(b"other domain", &data(40)), encoded);
+        // a wrong length
+        let mut bad = encoded.clone();
+        bad[1] = Fr::from(100u64);
+        assert!(decode_bytes(b"domain", &bad).is_err());
+        // a limb that does not fit on 31 bytes
+        let mut bad = encoded.clone();
+        bad[2] = -Fr::one();
+        assert!(decode_bytes(b"domain", &bad).is_err());
+        // non-zero bytes past the end of the data
+        let mut bad = encoded.clone();
+        bad[3] += Fr::from(1u64 << 40) * Fr::from(1u64 << 40);
+        assert!(decode_bytes(b"domain", &bad).is_err());
+        // a truncated or extended encoding
+        assert!(decode_bytes(b"domain", &encoded[..2]).is_err());
+        let mut bad = encoded;
+        bad.resize(8, Fr::zero());
+        assert!(decode_bytes(b"domain", &bad).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_encoded_public_input_e2e() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let nv = 3;
+        let pcs_srs = Kzg::gen_srs_for_testing(&mut rng, nv)?;
+
+        // a transaction hash and some metadata
+        let domain = b"hyperplonk test transaction";
+        let tx = [data(32), b"metadata".to_vec()].concat();
+        let pub_input = encode_bytes::<Fr>(domain, &tx);
+
+        // the gate q_0 w_0 - w_1, where the public input is the beginning of
+        // w_0
+        let mut w_0 = pub_input.clone();
+        w_0.resize(1 << nv, Fr::one());
+        let witnesses = vec![WitnessColumn(w_0.clone()), WitnessColumn(w_0)];
+        let index = HyperPlonkIndex {
+            params: HyperPlonkParams {
+                num_constraints: 1 << nv,
+                num_pub_input: pub_input.len(),
+                gate_func: CustomizedGates {
+                    gates: vec![(1, Some(0), vec![0]), (-1, None, vec![1])],
+                    lookups: vec![],
+                },
+                num_fixed_columns: 0,
+            },
+            permutation: identity_permutation(nv, 2),
+            selectors: vec![SelectorColumn(vec![Fr::one(); 1 << nv])],
+            fixed_columns: vec![],
+            tables: vec![],
+        };
+        let (pk, vk) =
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::preprocess(&index, &pcs_srs)?;
+        let proof =
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::prove(&pk, &pub_input, &witnesses)?;
+
+        // the verifier encodes the bytes on its side
+        assert!(<PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::verify(
+            &vk,
+            &encode_bytes(domain, &tx),
+            &proof,
+        )?);
+        // and other bytes do not verify
+        let mut other_tx = tx.clone();
+        other_tx[0] ^= 1;
+        assert!(!matches!(
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::verify(
+                &vk,
+                &encode_bytes(domain, &other_tx),
+                &proof,
+            ),
+            Ok(true)
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,418 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! Deliberately naive reference implementations.
+//!
+//! The functions in this module trade all efficiency for being obviously
+//! correct: every quantity is computed straight from its definition over the
+//! boolean hypercube. They serve as the oracle of the differential tests,
+//! which check the optimized prover and verifier against them.
+//!
+//! Only compiled for tests or with the `reference` feature.
+
+use crate::{custom_gate::CustomizedGates, selectors::SelectorColumn, witness::WitnessColumn};
+use arithmetic::VirtualPolynomial;
+use ark_ff::PrimeField;
+
+/// Evaluate the multilinear extension of `evals` at `point` as
+/// `\sum_{b \in {0,1}^n} evals[b] * eq(b, point)`.
+///
+/// The `i`-th variable is the `i`-th least significant bit of `b`, as in
+/// arkworks' `DenseMultilinearExtension`.
+pub fn naive_mle_evaluate<F: PrimeField>(evals: &[F], point: &[F]) -> F {
+    assert_eq!(evals.len(), 1 << point.len());
+    let mut res = F::zero();
+    for (b, eval) in evals.iter().enumerate() {
+        let mut eq = F::one();
+        for (i, p) in point.iter().enumerate() {
+            if (b >> i) & 1 == 1 {
+                eq *= p;
+            } else {
+                eq *= F::one() - p;
+            }
+        }
+        res += eq * eval;
+    }
+    res
+}
+
+/// Evaluate the virtual polynomial at `point`, evaluating each multilinear
+/// polynomial with [`naive_mle_evaluate`].
+pub fn naive_virtual_evaluate<F: PrimeField>(poly: &VirtualPolynomial<F>, point: &[F]) -> F {
+    let mle_evals: Vec<F> = poly
+        .flattened_ml_extensions
+        .iter()
+        .map(|mle| naive_mle_evaluate(&mle.evaluations, point))
+        .collect();
+    poly.products
+        .iter()
+        .map(|(coeff, product)| product.iter().fold(*coeff, |acc, &j| acc * mle_evals[j]))
+        .sum()
+}
+
+/// The sum of the virtual polynomial over the boolean hypercube, computed
+/// point by point.
+pub fn brute_force_sum<F: PrimeField>(poly: &VirtualPolynomial<F>) -> F {
+    (0..1 << poly.aux_info.num_variables)
+        .map(|b| {
+            poly.products
+                .iter()
+                .map(|(coeff, product)| {
+                    product.iter().fold(*coeff, |acc, &j| {
+                        acc * poly.flattened_ml_extensions[j].evaluations[b]
+                    })
+                })
+                .sum::<F>()
+        })
+        .sum()
+}
+
+/// The product of all the values.
+pub fn naive_grand_product<F: PrimeField>(values: &[F]) -> F {
+    values.iter().fold(F::one(), |acc, v| acc * v)
+}
+
+/// Check the gate identity row by row.
+///
+/// Returns the index of the first row that violates the gate, if any.
+pub fn naive_gate_check<F: PrimeField>(
+    gate: &CustomizedGates,
+    selectors: &[SelectorColumn<F>],
+    witnesses: &[WitnessColumn<F>],
+) -> Result<(), usize> {
+    let num_rows = witnesses.first().map_or(0, |w| w.0.len());
+    for row in 0..num_rows {
+        let mut res = F::zero();
+        for (coeff, selector, wires) in gate.gates.iter() {
+            let mut term = if *coeff < 0 {
+                -F::from((-coeff) as u64)
+            } else {
+                F::from(*coeff as u64)
+            };
+            if let Some(s) = selector {
+                term *= selectors[*s].0[row];
+            }
+            for &w in wires.iter() {
+                term *= witnesses[w].0[row];
+            }
+            res += term;
+        }
+        if !res.is_zero() {
+            return Err(row);
+        }
+    }
+    Ok(())
+}
+
+/// Check the copy constraints cell by cell: the cell `i` of the concatenated
+/// columns must hold the same value as the cell `permutation[i]`.
+///
+/// Returns the index of the first violated cell, if any.
+pub fn naive_copy_check<F: PrimeField>(columns: &[Vec<F>], permutation: &[F]) -> Result<(), usize> {
+    let cells: Vec<F> = columns.concat();
+    for (i, sigma) in permutation.iter().take(cells.len()).enumerate() {
+        let j = (0..cells.len())
+            .find(|&j| F::from(j as u64) == *sigma)
+            .ok_or(i)?;
+        if cells[i] != cells[j] {
+            return Err(i);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod differential {
+    //! Differential tests of the optimized implementation against the
+    //! reference one, over randomized inputs.
+    //!
+    //! Each property is checked over a number of random cases. On failure the
+    //! case is shrunk to a minimal failing case before being reported, so
+    //! that the report is as small as possible.
+
+    use super::*;
+    use crate::{errors::HyperPlonkErrors, mock::MockCircuit, HyperPlonkSNARK};
+    use arithmetic::evaluate_opt;
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_poly::{DenseMultilinearExtension, MultilinearExtension};
+    use ark_std::{
+        rand::{rngs::StdRng, SeedableRng},
+        test_rng, UniformRand,
+    };
+    use std::sync::Arc;
+    use subroutines::{
+        pcs::{prelude::MultilinearKzgPCS, PolynomialCommitmentScheme},
+        poly_iop::{
+            prelude::{compute_frac_poly, compute_product_poly, SumCheck},
+            PolyIOP,
+        },
+    };
+
+    const NUM_CASES: u64 = 16;
+    const MAX_NUM_VARS: usize = 6;
+
+    /// The parameters of a random case.
+    #[derive(Clone, Debug, PartialEq)]
+    struct Case {
+        nv: usize,
+        max_multiplicands: usize,
+        num_products: usize,
+        seed: u64,
+    }
+
+    impl Case {
+        fn generate(seed: u64) -> Self {
+            let mut rng = StdRng::seed_from_u64(seed);
+            Self {
+                nv: 1 + (u64::rand(&mut rng) as usize) % MAX_NUM_VARS,
+                max_multiplicands: 1 + (u64::rand(&mut rng) as usize) % 4,
+                num_products: 1 + (u64::rand(&mut rng) as usize) % 4,
+                seed,
+            }
+        }
+
+        /// Strictly smaller cases, smallest first.
+        fn shrink(&self) -> Vec<Self> {
+            let mut res = vec![];
+            if self.nv > 1 {
+                res.push(Self {
+                    nv: 1,
+                    ..self.clone()
+                });
+                res.push(Self {
+                    nv: self.nv - 1,
+                    ..self.clone()
+                });
+            }
+            if self.max_multiplicands > 1 {
+                res.push(Self {
+                    max_multiplicands: self.max_multiplicands - 1,
+                    ..self.clone()
+                });
+            }
+            if self.num_products > 1 {
+                res.push(Self {
+                    num_products: self.num_products - 1,
+                    ..self.clone()
+                });
+            }
+            res
+        }
+
+        fn rng(&self) -> StdRng {
+            StdRng::seed_from_u64(self.seed)
+        }
+
+        fn virtual_poly(&self) -> VirtualPolynomial<Fr> {
+            let (poly, _) = VirtualPolynomial::<Fr>::rand(
+                self.nv,
+                (1, self.max_multiplicands + 1),
+                self.num_products,
+                &mut self.rng(),
+            )
+            .unwrap();
+            poly
+        }
+    }
+
+    /// Check `property` on `NUM_CASES` random cases. On failure, returns the
+    /// minimal failing case found by greedily shrinking the first failure.
+    fn check_property(
+        property: impl Fn(&Case) -> Result<(), String>,
+    ) -> Result<(), (Case, String)> {
+        for seed in 0..NUM_CASES {
+            let case = Case::generate(seed);
+            if let Err(e) = property(&case) {
+                let mut failure = (case, e);
+                while let Some(smaller) = failure
+                    .0
+                    .shrink()
+                    .into_iter()
+                    .find_map(|c| property(&c).err().map(|e| (c, e)))
+                {
+                    failure = smaller;
+                }
+                return Err(failure);
+            }
+        }
+        Ok(())
+    }
+
+    fn assert_property(property: impl Fn(&Case) -> Result<(), String>) {
+        if let Err((case, e)) = check_property(property) {
+            panic!("property failed on minimal case {:?}: {}", case, e);
+        }
+    }
+
+    /// The sum check proves the brute-force sum, and its subclaim matches the
+    /// naive evaluation of the polynomial. `claimed_sum` is the
+    /// implementation under test.
+    fn sum_check_property(
+        case: &Case,
+        claimed_sum: impl Fn(&VirtualPolynomial<Fr>) -> Fr,
+    ) -> Result<(), String> {
+        let poly = case.virtual_poly();
+        let sum = claimed_sum(&poly);
+        if sum != brute_force_sum(&poly) {
+            return Err(format!("claimed sum {} is not the brute-force sum", sum));
+        }
+
+        let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+        let proof = <PolyIOP<Fr> as SumCheck<Fr>>::prove(&poly, &mut transcript)
+            .map_err(|e| e.to_string())?;
+        let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+        let subclaim =
+            <PolyIOP<Fr> as SumCheck<Fr>>::verify(sum, &proof, &poly.aux_info, &mut transcript)
+                .map_err(|e| e.to_string())?;
+        if subclaim.expected_evaluation != naive_virtual_evaluate(&poly, &subclaim.point) {
+            return Err("subclaim does not match the naive evaluation".to_string());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_sum_check_against_reference() {
+        assert_property(|case| {
+            sum_check_property(case, |poly| {
+                <PolyIOP<Fr> as SumCheck<Fr>>::extract_sum(
+                    &<PolyIOP<Fr> as SumCheck<Fr>>::prove(
+                        poly,
+                        &mut <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript(),
+                    )
+                    .unwrap(),
+                )
+            })
+        });
+    }
+
+    #[test]
+    fn test_mle_evaluation_against_reference() {
+        assert_property(|case| {
+            let mut rng = case.rng();
+            let mle = DenseMultilinearExtension::<Fr>::rand(case.nv, &mut rng);
+            let point: Vec<Fr> = (0..case.nv).map(|_| Fr::rand(&mut rng)).collect();
+            if evaluate_opt(&mle, &point) != naive_mle_evaluate(&mle.evaluations, &point) {
+                return Err("evaluate_opt does not match the naive evaluation".to_string());
+            }
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_grand_product_against_reference() {
+        assert_property(|case| {
+            let mut rng = case.rng();
+            let fx = Arc::new(DenseMultilinearExtension::<Fr>::rand(case.nv, &mut rng));
+            let gx = Arc::new(DenseMultilinearExtension::<Fr>::rand(case.nv, &mut rng));
+            let frac = compute_frac_poly(std::slice::from_ref(&fx), std::slice::from_ref(&gx))
+                .map_err(|e| e.to_string())?;
+            let prod = compute_product_poly(&frac).map_err(|e| e.to_string())?;
+            // prod(1, ..., 1, 0) is the product of frac(x) over the hypercube
+            let expected =
+                naive_grand_product(&fx.evaluations) / naive_grand_product(&gx.evaluations);
+            if prod.evaluations[(1 << case.nv) - 2] != expected {
+                return Err("prod(1, ..., 1, 0) is not the grand product".to_string());
+            }
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_hyperplonk_against_reference() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let pcs_srs = MultilinearKzgPCS::<Bls12_381>::gen_srs_for_testing(&mut rng, 4)?;
+
+        for nv in 1..=4 {
+            for gate in [
+                CustomizedGates::vanilla_plonk_gate(),
+                CustomizedGates::single_column_gate(3),
+            ] {
+                let circuit = MockCircuit::<Fr>::new(1 << nv, &gate);
+                let (pk, vk) = <PolyIOP<Fr> as HyperPlonkSNARK<
+                    Bls12_381,
+                    MultilinearKzgPCS<Bls12_381>,
+                >>::preprocess(&circuit.index, &pcs_srs)?;
+
+                // both agree that the honest circuit is satisfied
+                let columns: Vec<Vec<Fr>> = circuit.witnesses.iter().map(|w| w.0.clone()).collect();
+                assert_eq!(
+                    naive_gate_check(&gate, &circuit.index.selectors, &circuit.witnesses),
+                    Ok(())
+                );
+                assert_eq!(
+                    naive_copy_check(&columns, &circuit.index.permutation),
+                    Ok(())
+                );
+                let proof = <PolyIOP<Fr> as HyperPlonkSNARK<
+                    Bls12_381,
+                    MultilinearKzgPCS<Bls12_381>,
+                >>::prove(
+                    &pk, &circuit.public_inputs, &circuit.witnesses
+                )?;
+                assert!(<PolyIOP<Fr> as HyperPlonkSNARK<
+                    Bls12_381,
+                    MultilinearKzgPCS<Bls12_381>,
+                >>::verify(
+                    &vk, &circuit.public_inputs, &proof
+                )?);
+
+                // both agree that a perturbed witness is not
+                let mut bad_witnesses = circuit.witnesses.clone();
+                let last_row = (1 << nv) - 1;
+                bad_witnesses[0].0[last_row] += Fr::from(1u64);
+                assert_eq!(
+                    naive_gate_check(&gate, &circuit.index.selectors, &bad_witnesses),
+                    Err(last_row)
+                );
+                // the prover does not check the gate, so proving succeeds and
+                // only the verifier rejects the proof; on small circuits the
+                // perturbed row is also a public input
+                let mut bad_pub_input = circuit.public_inputs.clone();
+                if let Some(input) = bad_pub_input.get_mut(last_row) {
+                    *input += Fr::from(1u64);
+                }
+                let bad_proof = <PolyIOP<Fr> as HyperPlonkSNARK<
+                    Bls12_381,
+                    MultilinearKzgPCS<Bls12_381>,
+                >>::prove(&pk, &bad_pub_input, &bad_witnesses)?;
+                assert!(!matches!(
+                    <PolyIOP<Fr> as HyperPlonkSNARK<
+                        Bls12_381,
+                        MultilinearKzgPCS<Bls12_381>,
+                    >>::verify(&vk, &bad_pub_input, &bad_proof),
+                    Ok(true)
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The harness catches an off-by-one in the implementation under test,
+    /// and shrinks it to the smallest number of variables.
+    #[test]
+    fn test_harness_catches_off_by_one() {
+        let off_by_one_sum = |poly: &VirtualPolynomial<Fr>| {
+            // skips the last point of the hypercube
+            (0..(1 << poly.aux_info.num_variables) - 1)
+                .map(|b| {
+                    poly.products
+                        .iter()
+                        .map(|(coeff, product)| {
+                            product.iter().fold(*coeff, |acc, &j| {
+                                acc * poly.flattened_ml_extensions[j].evaluations[b]
+                            })
+                        })
+                        .sum::<Fr>()
+                })
+                .sum::<Fr>()
+        };
+        let (case, _) = check_property(|case| sum_check_property(case, off_by_one_sum))
+            .expect_err("the off-by-one is not caught");
+        assert_eq!(case.nv, 1);
+        assert_eq!(case.max_multiplicands, 1);
+        assert_eq!(case.num_products, 1);
+    }
+}
@@ -0,0 +1,365 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! The transcript schedule of the HyperPlonk verifier: every message it
+//! absorbs and every challenge it squeezes, in order.
+//!
+//! The schedule is not written down separately: [`transcript_schedule`] runs
+//! the verifier on a recording transcript against a placeholder proof of the
+//! shape the verifying key expects, and skips the checks of the opened
+//! evaluations, which do not touch the transcript. A change of the
+//! transcript of `verify` is therefore a change of the schedule.
+//!
+//! The placeholder witness commitments are distinct, as are those of an
+//! honest proof with high probability. The batch opening absorbs each
+//! distinct opening claim once, so that a proof with two identical witness
+//! columns absorbs fewer evaluations than the schedule shows.
+
+use crate::{
+    errors::HyperPlonkErrors,
+    snark::{num_opened_evaluations, verify_iop_with, VerifierMode},
+    structs::{HyperPlonkProof, HyperPlonkVerifyingKey},
+    versions::TRANSCRIPT_LABEL,
+};
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup};
+use ark_std::Zero;
+use std::fmt;
+use subroutines::{
+    pcs::prelude::{
+        BatchProof, Commitment, MultilinearKzgPCS, MultilinearKzgProof, PolynomialCommitmentScheme,
+    },
+    poly_iop::{
+        prelude::{IOPProof, IOPProverMessage, LookupCheckProof, ProductCheckProof},
+        PolyIOP,
+    },
+};
+use transcript::{TranscriptOp, LOOKUP_TAG, OPENING_TAG, PERM_CHECK_TAG, ZERO_CHECK_TAG};
+
+/// An operation of the verifier on the transcript.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TranscriptEvent {
+    /// A message of `len` bytes absorbed under `label`, which is the `role`
+    /// of the message in the protocol.
+    Absorb {
+        label: String,
+        role: String,
+        len: usize,
+    },
+    /// A challenge squeezed under `label`, for `purpose`.
+    Challenge { label: String, purpose: String },
+}
+
+impl fmt::Display for TranscriptEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Absorb { label, role, len } => {
+                write!(f, "absorb {:?} ({} bytes): {}", label, len, role)
+            },
+            Self::Challenge { label, purpose } => write!(f, "challenge {:?}: {}", label, purpose),
+        }
+    }
+}
+
+/// The transcript schedule of `verify` for the proofs under `vk`, of the
+/// protocol version of `vk`. It starts with the label of the transcript,
+/// which Merlin absorbs as its domain separator.
+pub fn transcript_schedule<E: Pairing>(
+    vk: &HyperPlonkVerifyingKey<E, MultilinearKzgPCS<E>>,
+) -> Result<Vec<TranscriptEvent>, HyperPlonkErrors> {
+    let pub_input = vec![E::ScalarField::zero(); vk.params.num_pub_input];
+    proof_schedule(vk, &pub_input, &placeholder_proof(vk))
+}
+
+/// The transcript schedule of the verification of `proof`, without its
+/// checks.
+fn proof_schedule<E: Pairing>(
+    vk: &HyperPlonkVerifyingKey<E, MultilinearKzgPCS<E>>,
+    pub_input: &[E::ScalarField],
+    proof: &HyperPlonkProof<E, PolyIOP<E::ScalarField>, MultilinearKzgPCS<E>>,
+) -> Result<Vec<TranscriptEvent>, HyperPlonkErrors> {
+    let (comms, points, mut transcript) =
+        verify_iop_with(vk, pub_input, proof, VerifierMode::Schedule)?;
+    // the pairing check comes after the last challenge, and fails for the
+    // placeholder proof
+    MultilinearKzgPCS::<E>::batch_verify(
+        &vk.pcs_param,
+        &comms,
+        &points,
+        &proof.batch_openings,
+        &mut transcript,
+    )?;
+    let ops = transcript.recorded_ops().ok_or_else(|| {
+        HyperPlonkErrors::InvalidVerifier("the transcript is not recorded".to_string())
+    })?;
+
+    let mut events = vec![TranscriptEvent::Absorb {
+        label: "dom-sep".to_string(),
+        role: "transcript label".to_string(),
+        len: TRANSCRIPT_LABEL.len(),
+    }];
+    let mut classifier = Classifier::default();
+    let mut previous = None;
+    for op in ops.iter() {
+        events.push(classifier.classify(op, previous));
+        previous = Some(op);
+    }
+    Ok(events)
+}
+
+/// A proof of the shape that `vk` expects, whose sum checks and batch
+/// opening sum check pass with all-zero messages and evaluations.
+fn placeholder_proof<E: Pairing>(
+    vk: &HyperPlonkVerifyingKey<E, MultilinearKzgPCS<E>>,
+) -> HyperPlonkProof<E, PolyIOP<E::ScalarField>, MultilinearKzgPCS<E>> {
+    let num_vars = vk.params.num_variables();
+    let num_witnesses = vk.params.num_witness_columns();
+    let num_lookups = vk.params.gate_func.lookups().len();
+    // distinct multiples of the generator
+    let commitment = |i: usize| {
+        Commitment((E::G1Affine::generator() * E::ScalarField::from(i as u64 + 1)).into_affine())
+    };
+    let sum_check_proof = |max_degree: usize| IOPProof {
+        point: vec![E::ScalarField::zero(); num_vars],
+        proofs: vec![
+            IOPProverMessage {
+                evaluations: vec![E::ScalarField::zero(); max_degree + 1],
+            };
+            num_vars
+        ],
+    };
+
+    HyperPlonkProof {
+        protocol_version: vk.protocol_version,
+        witness_commits: (0..num_witnesses).map(commitment).collect(),
+        batch_openings: BatchProof::new(
+            sum_check_proof(2),
+            vec![E::ScalarField::zero(); num_opened_evaluations(vk)],
+            MultilinearKzgProof {
+                proofs: vec![E::G1Affine::zero(); num_vars],
+            },
+        ),
+        // the zero checks multiply by eq(x, r), of degree 1
+        zero_check_proof: sum_check_proof(vk.params.gate_func.degree() + 1),
+        perm_check_proof: ProductCheckProof {
+            zero_check_proof: sum_check_proof(vk.params.num_permutation_columns() + 2),
+            prod_x_comm: commitment(num_witnesses),
+            frac_comm: commitment(num_witnesses + 1),
+        },
+        // the fractions sum to zero, and the sum check multiplies by
+        // eq(x, r) the products of degree 2
+        lookup_check_proof: (num_lookups > 0).then(|| LookupCheckProof {
+            sum_check_proof: sum_check_proof(3),
+            multiplicity_comms: (0..num_lookups)
+                .map(|l| commitment(num_witnesses + 2 + l))
+                .collect(),
+            input_frac_comms: (0..num_lookups)
+                .map(|l| commitment(num_witnesses + 2 + num_lookups + l))
+                .collect(),
+            table_frac_comms: (0..num_lookups)
+                .map(|l| commitment(num_witnesses + 2 + 2 * num_lookups + l))
+                .collect(),
+        }),
+    }
+}
+
+/// The sum checks of a proof, in the order of the transcript, apart from the
+/// lookup sum check which comes after the permutation zero check if the gate
+/// has lookups.
+const SUM_CHECKS: [&str; 3] = [
+    "gate zero check",
+    "permutation zero check",
+    "batch opening sum check",
+];
+
+/// Names the operations of the verifier, from their labels and from the
+/// operations before them.
+#[derive(Default)]
+struct Classifier {
+    witness_commits: usize,
+    evals: usize,
+    // the tag of the last sub-protocol
+    tag: Option<u8>,
+    // the zero checks and the sum checks other than the lookup sum check
+    // started so far, and the current sum check
+    zero_checks: usize,
+    sum_checks: usize,
+    sum_check: &'static str,
+    round: usize,
+    // the label and the number of the consecutive challenges so far
+    run: Option<(&'static [u8], usize)>,
+}
+
+impl Classifier {
+    fn classify(&mut self, op: &TranscriptOp, previous: Option<&TranscriptOp>) -> TranscriptEvent {
+        match op {
+            TranscriptOp::Message(label, msg) => {
+                let role = match (*label, previous) {
+                    (label, Some(TranscriptOp::Challenge(challenge))) if label == *challenge => {
+                        "challenge absorbed back".to_string()
+                    },
+                    _ => {
+                        self.run = None;
+                        self.message_role(label, msg)
+                    },
+                };
+                TranscriptEvent::Absorb {
+                    label: String::from_utf8_lossy(label).into_owned(),
+                    role,
+                    len: msg.len(),
+                }
+            },
+            TranscriptOp::Challenge(label) => {
+                let index = match self.run {
+                    Some((run, n)) if run == *label => n,
+                    _ => 0,
+                };
+                self.run = Some((*label, index + 1));
+                TranscriptEvent::Challenge {
+                    label: String::from_utf8_lossy(label).into_owned(),
+                    purpose: self.challenge_purpose(label, index),
+                }
+            },
+        }
+    }
+
+    fn message_role(&mut self, label: &[u8], msg: &[u8]) -> String {
+        match label {
+            b"protocol version" => "protocol version".to_string(),
+            b"w" => {
+                self.witness_commits += 1;
+                format!("witness commitment {}", self.witness_commits - 1)
+            },
+            b"sub-protocol" => {
+                self.tag = msg.first().copied();
+                match msg {
+                    [ZERO_CHECK_TAG] => "zero check tag",
+                    [PERM_CHECK_TAG] => "permutation check tag",
+                    [OPENING_TAG] => "opening tag",
+                    [LOOKUP_TAG] => "lookup tag",
+                    _ => "unknown tag",
+                }
+                .to_string()
+            },
+            b"aux info" => {
+                self.sum_check = if self.tag == Some(LOOKUP_TAG) {
+                    "lookup sum check"
+                } else {
+                    self.sum_checks += 1;
+                    SUM_CHECKS
+                        .get(self.sum_checks - 1)
+                        .copied()
+                        .unwrap_or("sum check")
+                };
+                self.round = 0;
+                format!("aux info of the {}", self.sum_check)
+            },
+            b"prover msg" => format!("round {} message of the {}", self.round, self.sum_check),
+            b"frac(x)" => "commitment to frac(x)".to_string(),
+            b"prod(x)" => "commitment to prod(x)".to_string(),
+            b"m(x)" => "commitment to m(x)".to_string(),
+            b"a(x)" => "commitment to a(x)".to_string(),
+            b"b(x)" => "commitment to b(x)".to_string(),
+            b"eval" => {
+                self.evals += 1;
+                format!("opened evaluation {}", self.evals - 1)
+            },
+            _ => "unknown".to_string(),
+        }
+    }
+
+    fn challenge_purpose(&mut self, label: &[u8], index: usize) -> String {
+        match label {
+            b"0check r" => {
+                if index == 0 {
+                    self.zero_checks += 1;
+                }
+                let zero_check = SUM_CHECKS[..2]
+                    .get(self.zero_checks - 1)
+                    .unwrap_or(&"zero check");
+                format!("point r[{}] of the {}", index, zero_check)
+            },
+            b"Internal round" => {
+                self.round += 1;
+                format!(
+                    "round {} challenge of the {}",
+                    self.round - 1,
+                    self.sum_check
+                )
+            },
+            b"gamma" | b"alpha" if self.tag == Some(LOOKUP_TAG) => {
+                format!("lookup check challenge {}", String::from_utf8_lossy(label))
+            },
+            b"r" if self.tag == Some(LOOKUP_TAG) => format!("lookup check point r[{}]", index),
+            b"beta" | b"gamma" => format!(
+                "permutation check challenge {}",
+                String::from_utf8_lossy(label)
+            ),
+            b"alpha" => "product check challenge alpha".to_string(),
+            b"r_pi" => format!("public input point r_pi[{}]", index),
+            b"t" => format!("batching point t[{}]", index),
+            _ => "unknown".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{custom_gate::CustomizedGates, mock::MockCircuit, HyperPlonkSNARK};
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_std::test_rng;
+
+    type Kzg = MultilinearKzgPCS<Bls12_381>;
+
+    const VANILLA_NV4: &str = include_str!("../test_vectors/transcript_schedule_vanilla_nv4.txt");
+
+    #[test]
+    fn test_transcript_schedule() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let nv = 4;
+        let pcs_srs = Kzg::gen_srs_for_testing(&mut rng, nv)?;
+        let gate = CustomizedGates::vanilla_plonk_gate();
+        let circuit = MockCircuit::<Fr>::new(1 << nv, &gate);
+        let (pk, vk) =
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::preprocess(&circuit.index, &pcs_srs)?;
+
+        // any change of the transcript of `verify` fails here: it is a new
+        // protocol version, see `versions`
+        let schedule = transcript_schedule(&vk)?;
+        let rendered: Vec<String> = schedule.iter().map(|event| event.to_string()).collect();
+        assert_eq!(
+            rendered,
+            VANILLA_NV4
+                .lines()
+                .filter(|line| !line.starts_with('#'))
+                .collect::<Vec<_>>(),
+            "transcript schedule changed"
+        );
+
+        // the schedule of an honest proof is the same
+        let proof = <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::prove(
+            &pk,
+            &circuit.public_inputs,
+            &circuit.witnesses,
+        )?;
+        assert!(<PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::verify(
+            &vk,
+            &circuit.public_inputs,
+            &proof
+        )?);
+        assert_eq!(
+            proof_schedule(&vk, &circuit.public_inputs, &proof)?,
+            schedule
+        );
+
+        // a verifying key of another shape has another schedule
+        let circuit = MockCircuit::<Fr>::new(1 << (nv - 1), &gate);
+        let (_, small_vk) =
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::preprocess(&circuit.index, &pcs_srs)?;
+        assert_ne!(transcript_schedule(&small_vk)?, schedule);
+        Ok(())
+    }
+}
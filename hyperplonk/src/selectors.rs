@@ -5,8 +5,13 @@
 // along with the HyperPlonk library. If not, see <https://mit-license.org/>.
 
 use crate::{build_mle, errors::HyperPlonkErrors};
+use arithmetic::evaluate_opt;
 use ark_ff::PrimeField;
 use ark_poly::DenseMultilinearExtension;
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, Read, SerializationError, Valid, Validate,
+    Write,
+};
 use ark_std::log2;
 use std::sync::Arc;
 
@@ -77,3 +82,314 @@ impl<F: PrimeField> SelectorRow<F> {
         build_mle!(matrix)
     }
 }
+
+/// How the verifier obtains the evaluation of a selector polynomial.
+///
+/// A selector with exploitable structure is described in the keys by its
+/// structure, which the verifier evaluates directly instead of checking an
+/// opening; the prover neither commits to it nor opens it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SelectorDescription<F: PrimeField, C> {
+    /// The selector is opened against its commitment.
+    Committed(C),
+    /// The non-zero entries `(row, value)` of the selector, by increasing
+    /// row.
+    Sparse(Vec<(usize, F)>),
+    /// The selector repeats `values` every `period` rows, where `period` is a
+    /// power of two and the length of `values`.
+    Periodic { period: usize, values: Vec<F> },
+}
+
+impl<F: PrimeField, C: CanonicalSerialize> SelectorDescription<F, C> {
+    /// The commitment of a committed selector.
+    pub fn commitment(&self) -> Option<&C> {
+        match self {
+            Self::Committed(commitment) => Some(commitment),
+            _ => None,
+        }
+    }
+
+    /// Whether the selector is committed, i.e., is opened by the proof.
+    pub fn is_committed(&self) -> bool {
+        matches!(self, Self::Committed(_))
+    }
+
+    /// The smallest compact description of the selector with evaluations
+    /// `evals`, if it serializes in fewer bytes than a commitment,
+    /// uncompressed.
+    pub(crate) fn compact(evals: &[F]) -> Option<Self>
+    where
+        C: Default,
+    {
+        let max_bytes = Self::Committed(C::default()).serialized_size(Compress::No);
+        let sparse = Self::Sparse(
+            evals
+                .iter()
+                .enumerate()
+                .filter(|(_, value)| !value.is_zero())
+                .map(|(row, value)| (row, *value))
+                .collect(),
+        );
+        let periodic = (0..log2(evals.len()))
+            .map(|k| 1 << k)
+            .find(|&period| {
+                evals
+                    .iter()
+                    .enumerate()
+                    .all(|(row, value)| *value == evals[row & (period - 1)])
+            })
+            .map(|period| Self::Periodic {
+                period,
+                values: evals[..period].to_vec(),
+            });
+        [Some(sparse), periodic]
+            .into_iter()
+            .flatten()
+            .map(|description| (description.serialized_size(Compress::No), description))
+            .filter(|(size, _)| *size < max_bytes)
+            .min_by_key(|(size, _)| *size)
+            .map(|(_, description)| description)
+    }
+
+    /// Check that a compact description is well formed for `num_vars`
+    /// variables.
+    pub(crate) fn check(&self, num_vars: usize) -> Result<(), HyperPlonkErrors> {
+        match self {
+            Self::Committed(_) => Ok(()),
+            Self::Sparse(entries) => {
+                let mut next_row = 0;
+                for &(row, _) in entries.iter() {
+                    if row < next_row || row >= 1 << num_vars {
+                        return Err(HyperPlonkErrors::InvalidParameters(format!(
+                            "Sparse selector row {} is out of order or out of range",
+                            row
+                        )));
+                    }
+                    next_row = row + 1;
+                }
+                Ok(())
+            },
+            Self::Periodic { period, values } => {
+                if !period.is_power_of_two() || *period > 1 << num_vars || values.len() != *period {
+                    return Err(HyperPlonkErrors::InvalidParameters(format!(
+                        "Periodic selector has period {} and {} values for {} variables",
+                        period,
+                        values.len(),
+                        num_vars
+                    )));
+                }
+                Ok(())
+            },
+        }
+    }
+
+    /// The evaluations over the boolean hypercube of a compact description;
+    /// `None` for a committed selector.
+    ///
+    /// The description must have been checked for `num_vars` variables.
+    pub(crate) fn evaluations(&self, num_vars: usize) -> Option<Vec<F>> {
+        match self {
+            Self::Committed(_) => None,
+            Self::Sparse(entries) => {
+                let mut evals = vec![F::zero(); 1 << num_vars];
+                for &(row, value) in entries.iter() {
+                    evals[row] = value;
+                }
+                Some(evals)
+            },
+            Self::Periodic { period, values } => Some(
+                (0..1 << num_vars)
+                    .map(|row| values[row & (period - 1)])
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Evaluate a compact description at `point`, without building its
+    /// polynomial; `None` for a committed selector.
+    ///
+    /// The description must have been checked for `point.len()` variables.
+    pub(crate) fn evaluate(&self, point: &[F]) -> Option<F> {
+        match self {
+            Self::Committed(_) => None,
+            // each row contributes its value times eq(row, point)
+            Self::Sparse(entries) => Some(
+                entries
+                    .iter()
+                    .map(|&(row, value)| {
+                        point.iter().enumerate().fold(value, |acc, (j, &x)| {
+                            if (row >> j) & 1 == 1 {
+                                acc * x
+                            } else {
+                                acc * (F::one() - x)
+                            }
+                        })
+                    })
+                    .sum(),
+            ),
+            // the selector only depends on the low log(period) variables
+            Self::Periodic { period, values } => {
+                let k = log2(*period) as usize;
+                let poly = DenseMultilinearExtension::from_evaluations_slice(k, values);
+                Some(evaluate_opt(&poly, &point[..k]))
+            },
+        }
+    }
+}
+
+// A description is serialized as a tag byte, `0` for a committed selector,
+// `1` for a sparse one and `2` for a periodic one, followed by the
+// commitment, the entries, or the period and the values.
+impl<F: PrimeField, C: CanonicalSerialize> CanonicalSerialize for SelectorDescription<F, C> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        match self {
+            Self::Committed(commitment) => {
+                0u8.serialize_with_mode(&mut writer, compress)?;
+                commitment.serialize_with_mode(&mut writer, compress)
+            },
+            Self::Sparse(entries) => {
+                1u8.serialize_with_mode(&mut writer, compress)?;
+                entries.serialize_with_mode(&mut writer, compress)
+            },
+            Self::Periodic { period, values } => {
+                2u8.serialize_with_mode(&mut writer, compress)?;
+                period.serialize_with_mode(&mut writer, compress)?;
+                values.serialize_with_mode(&mut writer, compress)
+            },
+        }
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        1 + match self {
+            Self::Committed(commitment) => commitment.serialized_size(compress),
+            Self::Sparse(entries) => entries.serialized_size(compress),
+            Self::Periodic { period, values } => {
+                period.serialized_size(compress) + values.serialized_size(compress)
+            },
+        }
+    }
+}
+
+// Only the commitment is checked: a compact description can only be checked
+// against a number of variables, by `SelectorDescription::check`.
+impl<F: PrimeField, C: Valid> Valid for SelectorDescription<F, C> {
+    fn check(&self) -> Result<(), SerializationError> {
+        match self {
+            Self::Committed(commitment) => commitment.check(),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<F: PrimeField, C: CanonicalDeserialize> CanonicalDeserialize for SelectorDescription<F, C> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        match u8::deserialize_with_mode(&mut reader, compress, validate)? {
+            0 => Ok(Self::Committed(C::deserialize_with_mode(
+                &mut reader,
+                compress,
+                validate,
+            )?)),
+            1 => Ok(Self::Sparse(Vec::deserialize_with_mode(
+                &mut reader,
+                compress,
+                validate,
+            )?)),
+            2 => Ok(Self::Periodic {
+                period: usize::deserialize_with_mode(&mut reader, compress, validate)?,
+                values: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+            }),
+            _ => Err(SerializationError::InvalidData),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ff::UniformRand;
+    use ark_std::test_rng;
+    use subroutines::pcs::prelude::Commitment;
+
+    type Description = SelectorDescription<Fr, Commitment<Bls12_381>>;
+
+    #[test]
+    fn test_compact_selector_descriptions() {
+        let mut rng = test_rng();
+        let nv = 4;
+        let point: Vec<Fr> = (0..nv).map(|_| Fr::rand(&mut rng)).collect();
+
+        // a selector with two non-zero rows
+        let mut evals = vec![Fr::from(0u64); 1 << nv];
+        evals[3] = Fr::from(5u64);
+        evals[12] = Fr::from(7u64);
+        let description = Description::compact(&evals).unwrap();
+        assert_eq!(
+            description,
+            Description::Sparse(vec![(3, Fr::from(5u64)), (12, Fr::from(7u64))])
+        );
+        description.check(nv).unwrap();
+        assert_eq!(description.evaluations(nv), Some(evals.clone()));
+        let poly = DenseMultilinearExtension::from_evaluations_slice(nv, &evals);
+        assert_eq!(
+            description.evaluate(&point),
+            Some(evaluate_opt(&poly, &point))
+        );
+
+        // a selector that alternates between two values
+        let evals: Vec<Fr> = (0..1 << nv).map(|i| Fr::from(1 + (i % 2) as u64)).collect();
+        let description = Description::compact(&evals).unwrap();
+        assert_eq!(
+            description,
+            Description::Periodic {
+                period: 2,
+                values: evals[..2].to_vec()
+            }
+        );
+        description.check(nv).unwrap();
+        assert_eq!(description.evaluations(nv), Some(evals.clone()));
+        let poly = DenseMultilinearExtension::from_evaluations_slice(nv, &evals);
+        assert_eq!(
+            description.evaluate(&point),
+            Some(evaluate_opt(&poly, &point))
+        );
+
+        // a random selector is committed
+        let evals: Vec<Fr> = (0..1 << nv).map(|_| Fr::rand(&mut rng)).collect();
+        assert_eq!(Description::compact(&evals), None);
+        assert_eq!(
+            Description::Committed(Commitment::default()).evaluate(&point),
+            None
+        );
+
+        // malformed descriptions
+        assert!(Description::Sparse(vec![(1 << nv, Fr::from(1u64))])
+            .check(nv)
+            .is_err());
+        assert!(
+            Description::Sparse(vec![(2, Fr::from(1u64)), (2, Fr::from(1u64))])
+                .check(nv)
+                .is_err()
+        );
+        assert!(Description::Periodic {
+            period: 3,
+            values: vec![Fr::from(1u64); 3]
+        }
+        .check(nv)
+        .is_err());
+        assert!(Description::Periodic {
+            period: 2,
+            values: vec![Fr::from(1u64); 3]
+        }
+        .check(nv)
+        .is_err());
+    }
+}
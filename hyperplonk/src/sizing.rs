@@ -0,0 +1,642 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! Sizes of the SRS, of the HyperPlonk keys and of the proofs, computed
+//! structurally from the circuit shape, without generating anything.
+
+use crate::{
+    custom_gate::CustomizedGates,
+    selectors::SelectorDescription,
+    structs::{HyperPlonkIndex, HyperPlonkParams, HyperPlonkProvingKey, HyperPlonkVerifyingKey},
+};
+use ark_ec::{pairing::Pairing, AffineRepr};
+use ark_ff::{PrimeField, Zero};
+use ark_serialize::{CanonicalSerialize, Compress};
+use subroutines::pcs::prelude::{
+    Commitment, MultilinearKzgPCS, MultilinearProverParam, MultilinearUniversalParams,
+    MultilinearVerifierParam, SrsSizeReport,
+};
+
+/// Number of variables the SRS must support on top of those of the circuit.
+///
+/// It is zero: every polynomial that HyperPlonk commits to or opens has the
+/// number of variables of the circuit. The product check of the permutation
+/// argument builds `frac(x)` and `prod(x)` over the rows, and the batch
+/// opening reduces its claims to a single polynomial over the rows too. The
+/// identity oracle, which spans all the permutation columns, is evaluated
+/// by the verifier and never committed. The lookup tables are repeated to
+/// the rows, and so are the polynomials of the lookup argument. Blinding
+/// rows are rows of the circuit, and the masked sum check is not part of a
+/// HyperPlonk proof. A circuit with as many variables as the SRS is
+/// therefore supported.
+pub const SRS_NUM_VARS_OVERHEAD: usize = 0;
+
+/// Number of bytes of a serialized length, i.e., of a `usize` field or of the
+/// length prefix of a vector.
+const LENGTH_BYTES: usize = 8;
+
+/// Number of bytes of a serialized cell index of the compact permutation,
+/// or row index of a sparse selector.
+const INDEX_BYTES: usize = 8;
+
+/// Number of bytes of the tag of a serialized selector description.
+const TAG_BYTES: usize = 1;
+
+/// Number of bytes of the serialized protocol version of a proof.
+const VERSION_BYTES: usize = 2;
+
+/// The number of field and group elements in, and the serialized size of, a
+/// HyperPlonk key.
+///
+/// The instance parameters are not counted: the size is that of the
+/// preprocessed oracles, the compact permutation, the selector descriptions,
+/// the commitments and the PCS parameters, each serialized as with
+/// `CanonicalSerialize`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct KeySizeReport {
+    /// number of field elements
+    pub num_scalars: usize,
+    /// number of G1 elements
+    pub num_g1: usize,
+    /// number of G2 elements
+    pub num_g2: usize,
+    /// serialized size in bytes with compressed encoding
+    pub compressed_bytes: usize,
+    /// serialized size in bytes with uncompressed encoding
+    pub uncompressed_bytes: usize,
+}
+
+impl KeySizeReport {
+    /// Report for the PCS parameters `pcs_param`, together with
+    /// `num_scalars` field elements, `num_g1` G1 elements, `num_indices`
+    /// indices, `num_lengths` lengths and `num_tags` selector tags.
+    fn new<E: Pairing>(
+        pcs_param: SrsSizeReport,
+        num_scalars: usize,
+        num_g1: usize,
+        num_indices: usize,
+        num_lengths: usize,
+        num_tags: usize,
+    ) -> Self {
+        let bytes = |compress| {
+            num_scalars * E::ScalarField::zero().serialized_size(compress)
+                + num_g1 * E::G1Affine::zero().serialized_size(compress)
+                + num_indices * INDEX_BYTES
+                + num_lengths * LENGTH_BYTES
+                + num_tags * TAG_BYTES
+        };
+        Self {
+            num_scalars,
+            num_g1: pcs_param.num_g1 + num_g1,
+            num_g2: pcs_param.num_g2,
+            compressed_bytes: pcs_param.compressed_bytes + bytes(Compress::Yes),
+            uncompressed_bytes: pcs_param.uncompressed_bytes + bytes(Compress::No),
+        }
+    }
+}
+
+/// The number of field elements, G1 elements, indices and lengths in the
+/// selector descriptions `selectors`, each of which also has a tag.
+fn selector_counts<F: PrimeField, C>(
+    selectors: &[SelectorDescription<F, C>],
+) -> (usize, usize, usize, usize) {
+    selectors.iter().fold(
+        (0, 0, 0, 0),
+        |(num_scalars, num_g1, num_indices, num_lengths), selector| match selector {
+            SelectorDescription::Committed(_) => {
+                (num_scalars, num_g1 + 1, num_indices, num_lengths)
+            },
+            // the rows and the values of the entries, and their number
+            SelectorDescription::Sparse(entries) => (
+                num_scalars + entries.len(),
+                num_g1,
+                num_indices + entries.len(),
+                num_lengths + 1,
+            ),
+            // the values, their number and the period
+            SelectorDescription::Periodic { values, .. } => (
+                num_scalars + values.len(),
+                num_g1,
+                num_indices,
+                num_lengths + 2,
+            ),
+        },
+    )
+}
+
+/// The descriptions of the selectors of `index`, with a placeholder
+/// commitment for the committed ones.
+fn expected_selectors<E: Pairing>(
+    index: &HyperPlonkIndex<E::ScalarField>,
+) -> Vec<SelectorDescription<E::ScalarField, Commitment<E>>> {
+    index
+        .selectors
+        .iter()
+        .map(|column| {
+            SelectorDescription::compact(&column.0)
+                .unwrap_or_else(|| SelectorDescription::Committed(Commitment::default()))
+        })
+        .collect()
+}
+
+/// The descriptions of the selectors of a circuit with parameters `params`
+/// when all of them are committed.
+pub(crate) fn committed_selectors<E: Pairing>(
+    params: &HyperPlonkParams,
+) -> Vec<SelectorDescription<E::ScalarField, Commitment<E>>> {
+    vec![SelectorDescription::Committed(Commitment::default()); params.num_selector_columns()]
+}
+
+impl<E: Pairing> HyperPlonkProvingKey<E, MultilinearKzgPCS<E>> {
+    /// The expected size of the proving key preprocessed from `index`.
+    pub fn expected_size(index: &HyperPlonkIndex<E::ScalarField>) -> KeySizeReport {
+        Self::expected_size_for_params(
+            &index.params,
+            &expected_selectors(index),
+            index.tables.len(),
+        )
+    }
+
+    pub(crate) fn expected_size_for_params(
+        params: &HyperPlonkParams,
+        selectors: &[SelectorDescription<E::ScalarField, Commitment<E>>],
+        num_tables: usize,
+    ) -> KeySizeReport {
+        let num_vars = params.num_variables();
+        let num_oracles = params.num_selector_columns() + params.num_fixed_columns + num_tables;
+        let num_commitments =
+            params.num_permutation_columns() + params.num_fixed_columns + num_tables;
+        let (num_scalars, num_g1, num_indices, num_lengths) = selector_counts(selectors);
+        // each oracle is serialized with its evaluations and num_vars, the
+        // permutation is only stored in compact form, and there are three
+        // vectors of oracles, the permutation, the selector descriptions and
+        // three vectors of commitments
+        KeySizeReport::new::<E>(
+            MultilinearProverParam::<E>::expected_size(num_vars),
+            (num_oracles << num_vars) + num_scalars,
+            num_commitments + num_g1,
+            (params.num_permutation_columns() << num_vars) + num_indices,
+            2 * num_oracles + 8 + num_lengths,
+            selectors.len(),
+        )
+    }
+}
+
+impl<E: Pairing> HyperPlonkVerifyingKey<E, MultilinearKzgPCS<E>> {
+    /// The expected size of the verifying key preprocessed from `index`.
+    pub fn expected_size(index: &HyperPlonkIndex<E::ScalarField>) -> KeySizeReport {
+        Self::expected_size_for_params(
+            &index.params,
+            &expected_selectors(index),
+            index.tables.len(),
+        )
+    }
+
+    pub(crate) fn expected_size_for_params(
+        params: &HyperPlonkParams,
+        selectors: &[SelectorDescription<E::ScalarField, Commitment<E>>],
+        num_tables: usize,
+    ) -> KeySizeReport {
+        let num_vars = params.num_variables();
+        let num_commitments =
+            params.num_permutation_columns() + params.num_fixed_columns + num_tables;
+        let (num_scalars, num_g1, num_indices, num_lengths) = selector_counts(selectors);
+        // the selector descriptions and three vectors of commitments
+        KeySizeReport::new::<E>(
+            MultilinearVerifierParam::<E>::expected_size(num_vars),
+            num_scalars,
+            num_commitments + num_g1,
+            num_indices,
+            4 + num_lengths,
+            selectors.len(),
+        )
+    }
+}
+
+/// The structure and the serialized size of a HyperPlonk proof.
+///
+/// The size is that of the proof written by
+/// [`serialize_streaming`](crate::streaming::serialize_streaming).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ProofSizeEstimate {
+    /// serialized size in bytes
+    pub proof_bytes: usize,
+    /// number of sum check rounds, over all the sum checks and zero checks
+    pub num_sumcheck_rounds: usize,
+    /// number of evaluations opened by the PCS batch opening
+    pub num_pcs_openings: usize,
+    /// number of commitments in the proof
+    pub num_commitments: usize,
+}
+
+/// The expected size of a proof for the circuit of `index`.
+pub fn estimate_proof_size<E: Pairing>(
+    index: &HyperPlonkIndex<E::ScalarField>,
+) -> ProofSizeEstimate {
+    let num_committed_selectors = expected_selectors::<E>(index)
+        .iter()
+        .filter(|selector| selector.is_committed())
+        .count();
+    expected_proof_size::<E>(&index.params, num_committed_selectors)
+}
+
+/// The expected size of a proof for a circuit with parameters `params` and
+/// `num_committed_selectors` committed selectors.
+pub(crate) fn expected_proof_size<E: Pairing>(
+    params: &HyperPlonkParams,
+    num_committed_selectors: usize,
+) -> ProofSizeEstimate {
+    let num_vars = params.num_variables();
+    let num_lookups = params.gate_func.lookups().len();
+    let num_perm_columns = params.num_permutation_columns();
+    // the gate zero check, the permutation zero check, the lookup sum check
+    // and the batch opening sum check, whose prover messages have one
+    // evaluation more than their degree, the zero checks being multiplied by
+    // eq(x, r)
+    let mut message_lens = vec![params.gate_func.degree() + 2, num_perm_columns + 3, 3];
+    if num_lookups > 0 {
+        message_lens.push(4);
+    }
+    // the same opened evaluations as `snark::num_opened_evaluations`
+    let num_pcs_openings = 8
+        + 2 * num_perm_columns
+        + params.num_witness_columns()
+        + num_committed_selectors
+        + 5 * num_lookups;
+    // the witnesses, frac(x) and prod(x), and m(x), a(x) and b(x) for each
+    // lookup
+    let num_commitments = params.num_witness_columns() + 2 + 3 * num_lookups;
+    // the prover messages, the opened evaluations, the point of the batch
+    // opening and the opening proof of g', which has one G1 element per
+    // variable
+    let num_scalars = num_vars * message_lens.iter().sum::<usize>() + num_pcs_openings + num_vars;
+    let num_g1 = num_commitments + num_vars;
+    ProofSizeEstimate {
+        proof_bytes: VERSION_BYTES
+            + num_scalars * E::ScalarField::zero().compressed_size()
+            + num_g1 * E::G1Affine::zero().compressed_size()
+            + LENGTH_BYTES,
+        num_sumcheck_rounds: num_vars * message_lens.len(),
+        num_pcs_openings,
+        num_commitments,
+    }
+}
+
+/// The sizes of everything needed to prove and verify a circuit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SizingReport {
+    /// the smallest SRS supporting the circuit
+    pub srs: SrsSizeReport,
+    /// the proving key
+    pub proving_key: KeySizeReport,
+    /// the verifying key
+    pub verifying_key: KeySizeReport,
+    /// a proof
+    pub proof: ProofSizeEstimate,
+}
+
+/// The number of variables the SRS must support to preprocess a circuit with
+/// parameters `params`.
+#[allow(clippy::identity_op)]
+pub fn required_srs_num_vars(params: &HyperPlonkParams) -> usize {
+    params.num_variables() + SRS_NUM_VARS_OVERHEAD
+}
+
+/// The sizes of the SRS, of the keys and of a proof for a circuit with `2^nv` constraints
+/// of the given gate, no fixed columns, committed selectors, and only the
+/// lookup tables the gate refers to.
+pub fn report<E: Pairing>(gate: &CustomizedGates, nv: usize) -> SizingReport {
+    let params = HyperPlonkParams {
+        num_constraints: 1 << nv,
+        num_pub_input: 0,
+        gate_func: gate.clone(),
+        num_fixed_columns: 0,
+    };
+    SizingReport {
+        srs: MultilinearUniversalParams::<E>::expected_size(required_srs_num_vars(&params)),
+        proving_key: HyperPlonkProvingKey::<E, MultilinearKzgPCS<E>>::expected_size_for_params(
+            &params,
+            &committed_selectors::<E>(&params),
+            gate.num_tables(),
+        ),
+        verifying_key: HyperPlonkVerifyingKey::<E, MultilinearKzgPCS<E>>::expected_size_for_params(
+            &params,
+            &committed_selectors::<E>(&params),
+            gate.num_tables(),
+        ),
+        proof: expected_proof_size::<E>(&params, params.num_selector_columns()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{prove_with_config, ProverConfig},
+        errors::HyperPlonkErrors,
+        mock::MockCircuit,
+        selectors::SelectorColumn,
+        streaming::serialize_streaming,
+        structs::HyperPlonkIndex,
+        witness::WitnessColumn,
+        HyperPlonkSNARK,
+    };
+    use arithmetic::random_permutation;
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ff::{One, UniformRand};
+    use ark_poly::DenseMultilinearExtension;
+    use ark_std::{rand::RngCore, test_rng};
+    use std::sync::Arc;
+    use subroutines::{pcs::PolynomialCommitmentScheme, poly_iop::PolyIOP};
+
+    type Kzg = MultilinearKzgPCS<Bls12_381>;
+
+    fn bytes<T: CanonicalSerialize>(items: &[T], compress: Compress) -> usize {
+        LENGTH_BYTES
+            + items
+                .iter()
+                .map(|item| item.serialized_size(compress))
+                .sum::<usize>()
+    }
+
+    fn oracles_bytes(oracles: &[Arc<DenseMultilinearExtension<Fr>>], compress: Compress) -> usize {
+        LENGTH_BYTES
+            + oracles
+                .iter()
+                .map(|oracle| oracle.serialized_size(compress))
+                .sum::<usize>()
+    }
+
+    fn pk_bytes(pk: &HyperPlonkProvingKey<Bls12_381, Kzg>, compress: Compress) -> usize {
+        pk.permutation.serialized_size(compress)
+            + oracles_bytes(&pk.selector_oracles, compress)
+            + oracles_bytes(&pk.fixed_oracles, compress)
+            + bytes(&pk.permutation_commitments, compress)
+            + bytes(&pk.selectors, compress)
+            + bytes(&pk.fixed_commitments, compress)
+            + oracles_bytes(&pk.table_oracles, compress)
+            + bytes(&pk.table_commitments, compress)
+            + pk.pcs_param.serialized_size(compress)
+    }
+
+    fn vk_bytes(vk: &HyperPlonkVerifyingKey<Bls12_381, Kzg>, compress: Compress) -> usize {
+        bytes(&vk.perm_commitments, compress)
+            + bytes(&vk.selectors, compress)
+            + bytes(&vk.fixed_commitments, compress)
+            + bytes(&vk.table_commitments, compress)
+            + vk.pcs_param.serialized_size(compress)
+    }
+
+    #[test]
+    fn test_expected_key_sizes() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let pcs_srs = Kzg::gen_srs_for_testing(&mut rng, 5)?;
+
+        for nv in 1..=5 {
+            for gate in [
+                CustomizedGates::vanilla_plonk_gate(),
+                CustomizedGates::jellyfish_turbo_plonk_gate(),
+                CustomizedGates::single_column_gate(3),
+            ] {
+                let circuit = MockCircuit::<Fr>::new(1 << nv, &gate);
+                let (pk, vk) = <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::preprocess(
+                    &circuit.index,
+                    &pcs_srs,
+                )?;
+                let pk_size = HyperPlonkProvingKey::<Bls12_381, Kzg>::expected_size(&circuit.index);
+                let vk_size =
+                    HyperPlonkVerifyingKey::<Bls12_381, Kzg>::expected_size(&circuit.index);
+                assert_eq!(pk_bytes(&pk, Compress::Yes), pk_size.compressed_bytes);
+                assert_eq!(pk_bytes(&pk, Compress::No), pk_size.uncompressed_bytes);
+                assert_eq!(vk_bytes(&vk, Compress::Yes), vk_size.compressed_bytes);
+                assert_eq!(vk_bytes(&vk, Compress::No), vk_size.uncompressed_bytes);
+
+                // the report assumes committed selectors, while the
+                // selectors of the smallest circuits are described compactly
+                let report = report::<Bls12_381>(&gate, nv);
+                if vk.selectors.iter().all(|selector| selector.is_committed()) {
+                    assert_eq!(report.proving_key, pk_size);
+                    assert_eq!(report.verifying_key, vk_size);
+                } else {
+                    assert!(vk_size.uncompressed_bytes < report.verifying_key.uncompressed_bytes);
+                }
+                assert_eq!(
+                    report.srs,
+                    MultilinearUniversalParams::<Bls12_381>::expected_size(nv)
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_expected_key_sizes_with_fixed_columns() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let nv = 3;
+        let pcs_srs = Kzg::gen_srs_for_testing(&mut rng, nv)?;
+
+        let gate = CustomizedGates::vanilla_plonk_gate();
+        let mut circuit = MockCircuit::<Fr>::new(1 << nv, &gate);
+        circuit.index.params.num_fixed_columns = 2;
+        circuit.index.fixed_columns = vec![circuit.index.selectors[0].clone(); 2];
+        circuit.index.permutation =
+            arithmetic::identity_permutation(nv, circuit.index.num_permutation_columns());
+
+        let (pk, vk) =
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::preprocess(&circuit.index, &pcs_srs)?;
+        let pk_size = HyperPlonkProvingKey::<Bls12_381, Kzg>::expected_size(&circuit.index);
+        let vk_size = HyperPlonkVerifyingKey::<Bls12_381, Kzg>::expected_size(&circuit.index);
+        assert_eq!(pk_bytes(&pk, Compress::Yes), pk_size.compressed_bytes);
+        assert_eq!(vk_bytes(&vk, Compress::Yes), vk_size.compressed_bytes);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_compact_permutation_shrinks_proving_key() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let nv = 5;
+        let pcs_srs = Kzg::gen_srs_for_testing(&mut rng, nv)?;
+
+        let gate = CustomizedGates::jellyfish_turbo_plonk_gate();
+        let circuit = MockCircuit::<Fr>::new(1 << nv, &gate);
+        let (mut pk, _) =
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::preprocess(&circuit.index, &pcs_srs)?;
+        let pk_size = report::<Bls12_381>(&gate, nv).proving_key;
+        assert_eq!(pk_bytes(&pk, Compress::No), pk_size.uncompressed_bytes);
+
+        // the key used to store the permutation polynomials instead of the
+        // compact permutation
+        let permutation_oracles = pk.permutation_oracles();
+        let stored_bytes = pk_size.uncompressed_bytes
+            - pk.permutation.serialized_size(Compress::No)
+            + oracles_bytes(&permutation_oracles, Compress::No);
+        let num_cells = circuit.index.num_permutation_columns() << nv;
+        assert_eq!(pk.permutation.len(), num_cells);
+        assert_eq!(
+            stored_bytes - pk_size.uncompressed_bytes,
+            num_cells * (Fr::zero().serialized_size(Compress::No) - INDEX_BYTES)
+                + permutation_oracles.len() * 2 * LENGTH_BYTES
+        );
+
+        // the cache brings the tables back in memory, but not into the
+        // reported size
+        pk.cache_permutation_oracles();
+        assert_eq!(pk.permutation_oracles(), permutation_oracles);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_expected_proof_size() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let pcs_srs = Kzg::gen_srs_for_testing(&mut rng, 5)?;
+
+        for nv in 3..=5 {
+            for circuit in [
+                MockCircuit::<Fr>::new(1 << nv, &CustomizedGates::vanilla_plonk_gate()),
+                MockCircuit::new(1 << nv, &CustomizedGates::jellyfish_turbo_plonk_gate()),
+                MockCircuit::new_with_range_check(1 << nv, 2),
+            ] {
+                let (pk, vk) = <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::preprocess(
+                    &circuit.index,
+                    &pcs_srs,
+                )?;
+                let proof = <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::prove(
+                    &pk,
+                    &circuit.public_inputs,
+                    &circuit.witnesses,
+                )?;
+                let mut bytes = vec![];
+                serialize_streaming(&proof, &mut bytes)?;
+
+                let estimate = estimate_proof_size::<Bls12_381>(&circuit.index);
+                assert_eq!(estimate.proof_bytes, bytes.len());
+                assert_eq!(
+                    estimate.num_pcs_openings,
+                    proof.batch_openings.f_i_eval_at_point_i.len()
+                );
+                let lookup_rounds = proof
+                    .lookup_check_proof
+                    .as_ref()
+                    .map_or(0, |lookup| lookup.sum_check_proof.proofs.len());
+                assert_eq!(
+                    estimate.num_sumcheck_rounds,
+                    proof.zero_check_proof.proofs.len()
+                        + proof.perm_check_proof.zero_check_proof.proofs.len()
+                        + lookup_rounds
+                        + proof.batch_openings.sum_check_proof().proofs.len()
+                );
+
+                let report = report::<Bls12_381>(&circuit.index.params.gate_func, nv);
+                if vk.selectors.iter().all(|selector| selector.is_committed()) {
+                    assert_eq!(report.proof, estimate);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// A circuit with copy constraints between all of its cells: the gate
+    /// `q_0 w_0 - w_1` with `q_0 = 1`, a random permutation, and the same
+    /// value in every cell.
+    fn copy_circuit<R: RngCore>(nv: usize, rng: &mut R) -> MockCircuit<Fr> {
+        let value = Fr::rand(rng);
+        let gate_func = CustomizedGates {
+            gates: vec![(1, Some(0), vec![0]), (-1, None, vec![1])],
+            lookups: vec![],
+        };
+        MockCircuit {
+            public_inputs: vec![value; 2],
+            witnesses: vec![WitnessColumn(vec![value; 1 << nv]); 2],
+            index: HyperPlonkIndex {
+                params: HyperPlonkParams {
+                    num_constraints: 1 << nv,
+                    num_pub_input: 2,
+                    gate_func,
+                    num_fixed_columns: 0,
+                },
+                permutation: random_permutation(nv, 2, rng),
+                selectors: vec![SelectorColumn(vec![Fr::one(); 1 << nv])],
+                fixed_columns: vec![],
+                tables: vec![],
+            },
+        }
+    }
+
+    /// A vanilla circuit whose last `rows` rows are reserved for blinding.
+    fn blinded_circuit(nv: usize, rows: usize) -> MockCircuit<Fr> {
+        let mut circuit = MockCircuit::new(1 << nv, &CustomizedGates::vanilla_plonk_gate());
+        for row in (1 << nv) - rows..1 << nv {
+            for selector in circuit.index.selectors.iter_mut() {
+                selector.0[row] = Fr::zero();
+            }
+            for witness in circuit.witnesses.iter_mut() {
+                witness.0[row] = Fr::zero();
+            }
+        }
+        circuit
+    }
+
+    #[test]
+    fn test_srs_boundary() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let srs_nv = 3;
+        let pcs_srs = Kzg::gen_srs_for_testing(&mut rng, srs_nv)?;
+        let max_nv = srs_nv - SRS_NUM_VARS_OVERHEAD;
+
+        let blinding = ProverConfig {
+            blinding_rows: 2,
+            ..ProverConfig::default()
+        };
+        let circuits = |nv: usize, rng: &mut _| {
+            [
+                (
+                    MockCircuit::new(1 << nv, &CustomizedGates::vanilla_plonk_gate()),
+                    ProverConfig::default(),
+                ),
+                (copy_circuit(nv, rng), ProverConfig::default()),
+                (blinded_circuit(nv, 2), blinding),
+            ]
+        };
+
+        // the largest circuits the SRS supports are proven and verified
+        for (circuit, config) in circuits(max_nv, &mut rng) {
+            assert_eq!(required_srs_num_vars(&circuit.index.params), srs_nv);
+            let (pk, vk) = <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::preprocess(
+                &circuit.index,
+                &pcs_srs,
+            )?;
+            let proof = prove_with_config(
+                &pk,
+                &circuit.public_inputs,
+                &circuit.witnesses,
+                &config,
+                &mut rng,
+            )?;
+            assert!(<PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::verify(
+                &vk,
+                &circuit.public_inputs,
+                &proof,
+            )?);
+        }
+
+        // one more variable is rejected by the preprocessing
+        for (circuit, _) in circuits(max_nv + 1, &mut rng) {
+            assert!(matches!(
+                <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::preprocess(
+                    &circuit.index,
+                    &pcs_srs,
+                ),
+                Err(HyperPlonkErrors::InvalidParameters(_))
+            ));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,208 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! Aggregate statistics of a circuit, for optimizing it.
+
+use crate::{structs::HyperPlonkIndex, utils::cell_index};
+use ark_ff::PrimeField;
+use std::{collections::HashSet, fmt};
+
+/// Statistics of a [`HyperPlonkIndex`], see
+/// [`HyperPlonkIndex::statistics`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IndexStatistics {
+    /// number of gates, i.e., of rows of the circuit
+    pub num_gates: usize,
+    /// number of distinct gate polynomials, i.e., of distinct rows of
+    /// selector values
+    pub num_distinct_gates: usize,
+    /// the degree of the gate
+    pub max_degree: usize,
+    /// the number of gates of each degree: the degree of a gate is that of
+    /// its monomials whose selector is not zero on its row
+    pub degree_distribution: Vec<usize>,
+    /// number of copy constraints, i.e., of the equalities between wires
+    /// that the permutation enforces
+    pub num_copy_constraints: usize,
+    /// number of selectors that are not zero on every row
+    pub num_nonzero_selectors: usize,
+    /// number of evaluations of the selectors, fixed columns, permutation
+    /// and lookup tables of the index
+    pub num_mle_coefficients: usize,
+}
+
+impl<F: PrimeField> HyperPlonkIndex<F> {
+    /// Aggregate statistics of the circuit.
+    pub fn statistics(&self) -> IndexStatistics {
+        let gate = &self.params.gate_func;
+        let num_gates = self.params.num_constraints;
+        let selector_row = |row: usize| -> Vec<F> {
+            self.selectors
+                .iter()
+                .map(|selector| selector.0.get(row).copied().unwrap_or_else(F::zero))
+                .collect()
+        };
+
+        let max_degree = gate.degree();
+        let mut degree_distribution = vec![0; max_degree + 1];
+        let mut distinct_gates = HashSet::new();
+        for row in 0..num_gates {
+            let selectors = selector_row(row);
+            let degree = gate
+                .gates
+                .iter()
+                .filter(|(_coeff, q, _ws)| q.is_none_or(|q| !selectors[q].is_zero()))
+                .map(|(_coeff, q, ws)| ws.len() + (q.is_some() as usize))
+                .max()
+                .unwrap_or(0);
+            degree_distribution[degree] += 1;
+            distinct_gates.insert(selectors);
+        }
+
+        // a cycle of k wires of the permutation is k - 1 copy constraints
+        let permutation: Vec<Option<usize>> = self
+            .permutation
+            .iter()
+            .map(|value| cell_index(value).ok().map(|cell| cell as usize))
+            .collect();
+        let mut visited = vec![false; permutation.len()];
+        let mut num_copy_constraints = 0;
+        for start in 0..permutation.len() {
+            let mut cycle_len = 0usize;
+            let mut cell = Some(start);
+            while let Some(c) = cell.filter(|&c| c < visited.len() && !visited[c]) {
+                visited[c] = true;
+                cycle_len += 1;
+                cell = permutation[c];
+            }
+            num_copy_constraints += cycle_len.saturating_sub(1);
+        }
+
+        IndexStatistics {
+            num_gates,
+            num_distinct_gates: distinct_gates.len(),
+            max_degree,
+            degree_distribution,
+            num_copy_constraints,
+            num_nonzero_selectors: self
+                .selectors
+                .iter()
+                .filter(|selector| selector.0.iter().any(|value| !value.is_zero()))
+                .count(),
+            num_mle_coefficients: self
+                .selectors
+                .iter()
+                .chain(self.fixed_columns.iter())
+                .map(|column| column.0.len())
+                .sum::<usize>()
+                + self.permutation.len()
+                + self
+                    .tables
+                    .iter()
+                    .map(|table| table.evaluations.len())
+                    .sum::<usize>(),
+        }
+    }
+}
+
+/// A table of the statistics, with a line per degree of gates.
+impl fmt::Display for IndexStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<24} {:>12}", "gates", self.num_gates)?;
+        writeln!(
+            f,
+            "{:<24} {:>12}",
+            "distinct gates", self.num_distinct_gates
+        )?;
+        writeln!(f, "{:<24} {:>12}", "max degree", self.max_degree)?;
+        for (degree, &count) in self.degree_distribution.iter().enumerate() {
+            if count > 0 {
+                writeln!(f, "{:<24} {:>12}", format!("  of degree {}", degree), count)?;
+            }
+        }
+        writeln!(
+            f,
+            "{:<24} {:>12}",
+            "copy constraints", self.num_copy_constraints
+        )?;
+        writeln!(
+            f,
+            "{:<24} {:>12}",
+            "nonzero selectors", self.num_nonzero_selectors
+        )?;
+        write!(
+            f,
+            "{:<24} {:>12}",
+            "mle coefficients", self.num_mle_coefficients
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        constraint_system::ConstraintSystem, custom_gate::CustomizedGates, errors::HyperPlonkErrors,
+    };
+    use ark_bls12_381::Fr;
+    use ark_ff::{One, Zero};
+
+    #[test]
+    fn test_statistics() -> Result<(), HyperPlonkErrors> {
+        let nv = 3;
+        let (one, zero) = (Fr::one(), Fr::zero());
+
+        // the vanilla gate
+        //     q_L w_1 + q_R w_2 + q_O w_3 + q_M w_1 w_2 + q_C = 0
+        // with 3 additions, 2 multiplications and 3 padding rows
+        let mut cs = ConstraintSystem::<Fr>::new(&CustomizedGates::vanilla_plonk_gate());
+        for _ in 0..3 {
+            cs.add_gate(&[one, one, -one, zero, zero])?;
+        }
+        for _ in 0..2 {
+            cs.add_gate(&[zero, zero, -one, one, zero])?;
+        }
+        cs.add_copy_constraint((0, 0), (1, 0));
+        cs.add_copy_constraint((2, 0), (0, 1));
+        cs.add_copy_constraint((2, 1), (0, 2));
+        // a cycle of three wires
+        cs.add_copy_constraint((0, 3), (1, 3));
+        cs.add_copy_constraint((2, 4), (1, 3));
+        let vanilla = cs.finalize(nv)?.statistics();
+        assert_eq!(vanilla.num_gates, 8);
+        assert_eq!(vanilla.num_distinct_gates, 3);
+        assert_eq!(vanilla.max_degree, 3);
+        assert_eq!(vanilla.degree_distribution, vec![3, 0, 3, 2]);
+        assert_eq!(vanilla.num_copy_constraints, 5);
+        // q_C is zero
+        assert_eq!(vanilla.num_nonzero_selectors, 4);
+        // 5 selectors and 3 permutation columns
+        assert_eq!(vanilla.num_mle_coefficients, 8 << nv);
+
+        // the gate
+        //     q_0 w_1^4 w_2 + q_1 w_1 + q_2 w_2 + q_3 = 0
+        // with 4 rows w_1^4 w_2 = 1 and 4 padding rows
+        let mut cs = ConstraintSystem::<Fr>::new(&CustomizedGates::mock_gate(2, 5));
+        for _ in 0..4 {
+            cs.add_gate(&[one, zero, zero, -one])?;
+        }
+        cs.add_copy_constraint((1, 0), (1, 1));
+        let high_degree = cs.finalize(nv)?.statistics();
+        assert_eq!(high_degree.num_gates, vanilla.num_gates);
+        assert_eq!(high_degree.num_distinct_gates, 2);
+        assert_eq!(high_degree.max_degree, 6);
+        assert_eq!(high_degree.degree_distribution, vec![4, 0, 0, 0, 0, 0, 4]);
+        assert_eq!(high_degree.num_copy_constraints, 1);
+        assert_eq!(high_degree.num_nonzero_selectors, 2);
+        // 4 selectors and 2 permutation columns
+        assert_eq!(high_degree.num_mle_coefficients, 6 << nv);
+
+        // a line per statistic, and a line per degree of gates
+        assert_eq!(vanilla.to_string().lines().count(), 6 + 3);
+        assert_eq!(high_degree.to_string().lines().count(), 6 + 2);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,1400 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! A HyperPlonk prover that runs in bounded-size steps.
+//!
+//! [`prove_in_steps`] returns a [`ProverStepper`]; every call to
+//! [`ProverStepper::step`] executes one unit of work (e.g. one commitment or
+//! one sum check round) and returns. This allows the caller to yield to an
+//! executor or to report progress in between steps. The proof
+//! is identical to the one generated by `HyperPlonkSNARK::prove`, which is
+//! itself implemented on top of the stepper.
+//!
+//! A very long proof can be checkpointed in between two steps with
+//! [`ProverStepper::checkpoint`], and resumed with [`ProverStepper::resume`],
+//! e.g. after the prover process was restarted.
+
+use crate::{
+    errors::HyperPlonkErrors,
+    limits::CancellationToken,
+    profile::ProfiledPhase,
+    structs::{HyperPlonkProof, HyperPlonkProvingKey},
+    utils::{
+        build_f, prover_sanity_check, pub_input_challenge, read_mles, write_mles, PcsAccumulator,
+    },
+    versions,
+    witness::{read_columns, WitnessSource},
+    PROTOCOL_VERSION,
+};
+use ark_ec::pairing::Pairing;
+use ark_ff::PrimeField;
+use ark_poly::DenseMultilinearExtension;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, Write};
+use ark_std::{cfg_iter, end_timer, log2, start_timer, One, Zero};
+#[cfg(feature = "parallel")]
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use std::sync::Arc;
+use subroutines::{
+    pcs::prelude::{Commitment, PolynomialCommitmentScheme},
+    poly_iop::{
+        prelude::{
+            build_lookup_check_poly, build_prod_check_poly, compute_frac_poly,
+            compute_lookup_frac_polys, compute_multiplicity_poly, compute_product_poly,
+            computer_nums_and_denoms, zero_check_rounds, IOPProof, LookupCheckProof,
+            ProductCheckProof, SumCheckRounds,
+        },
+        PolyIOP,
+    },
+    BatchProof, MultiOpenRounds,
+};
+use transcript::{IOPTranscript, LookupChallenge};
+
+type Mle<F> = Arc<DenseMultilinearExtension<F>>;
+
+/// Version of the layout of the checkpoints.
+const CHECKPOINT_FORMAT: u8 = 3;
+
+/// The outcome of a single [`ProverStepper::step`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum StepResult<P> {
+    /// More steps are required.
+    Continue,
+    /// The prover is finished and outputs the proof.
+    Done(P),
+}
+
+/// The phases of the HyperPlonk prover, in execution order.
+enum Phase<F: PrimeField> {
+    /// 1. commit to the next witness polynomial
+    CommitWitness,
+    /// 2. build the gate identity and initialize its zero check
+    ZeroCheckInit,
+    /// 2. one round of the gate identity zero check
+    ZeroCheckRound(SumCheckRounds<F>),
+    /// 3. build the fractional and product polynomials of the permutation
+    ///    check
+    PermCheckInit,
+    /// 3. commit to the fractional polynomial
+    CommitFrac,
+    /// 3. commit to the product polynomial
+    CommitProd,
+    /// 3. initialize the zero check of the product check
+    ProdCheckInit,
+    /// 3. one round of the product check zero check
+    ProdCheckRound(SumCheckRounds<F>),
+    /// 3'. commit to the next multiplicity or fractional polynomial of the
+    ///     lookups, if the gate has lookups
+    LookupCommit,
+    /// 3'. build the lookup check polynomial and initialize its sum check
+    LookupCheckInit,
+    /// 3'. one round of the lookup check sum check
+    LookupCheckRound(SumCheckRounds<F>),
+    /// 4. evaluate all the polynomials at their opening points
+    Evaluations,
+    /// 5. initialize the sum check of the deferred batch opening
+    OpeningInit,
+    /// 5. one round of the sum check of the batch opening
+    OpeningRound(MultiOpenRounds<F>),
+    /// 5. open the batched polynomial and output the proof
+    Opening(MultiOpenRounds<F>),
+    /// the proof has been output
+    Finished,
+}
+
+/// A HyperPlonk prover that is driven one step at a time.
+///
+/// Created by [`prove_in_steps`].
+pub struct ProverStepper<'a, E, PCS>
+where
+    E: Pairing,
+    PCS: PolynomialCommitmentScheme<E>,
+{
+    pk: &'a HyperPlonkProvingKey<E, PCS>,
+    protocol_version: u16,
+    transcript: IOPTranscript<E::ScalarField>,
+    phase: Phase<E::ScalarField>,
+    num_steps: usize,
+
+    witness_polys: Vec<Mle<E::ScalarField>>,
+    witness_commits: Vec<PCS::Commitment>,
+    precommitted_witnesses: Option<Vec<PCS::Commitment>>,
+    zero_check_proof: Option<IOPProof<E::ScalarField>>,
+
+    permutation_oracles: Vec<Mle<E::ScalarField>>,
+    numerators: Vec<Mle<E::ScalarField>>,
+    denominators: Vec<Mle<E::ScalarField>>,
+    frac_poly: Option<Mle<E::ScalarField>>,
+    prod_x: Option<Mle<E::ScalarField>>,
+    frac_comm: Option<PCS::Commitment>,
+    prod_x_comm: Option<PCS::Commitment>,
+    perm_check_proof: Option<ProductCheckProof<E, PCS, PolyIOP<E::ScalarField>>>,
+
+    // the multiplicity polynomials of the lookups, followed by the
+    // fractional polynomials `a(x)` and `b(x)` of each lookup, in the order
+    // of their commitments
+    lookup_polys: Vec<Mle<E::ScalarField>>,
+    lookup_comms: Vec<PCS::Commitment>,
+    lookup_gamma: Option<E::ScalarField>,
+    lookup_check_proof: Option<LookupCheckProof<E, PCS>>,
+
+    pcs_acc: PcsAccumulator<E, PCS>,
+    cancellation: Option<CancellationToken>,
+}
+
+/// Start generating a HyperPlonk proof for `pk`, `pub_input` and `witnesses`
+/// in steps.
+///
+/// The witnesses are read from `witnesses` and the inputs are sanity
+/// checked eagerly; no other work is done until the first call to
+/// [`ProverStepper::step`].
+pub fn prove_in_steps<'a, E, PCS, W>(
+    pk: &'a HyperPlonkProvingKey<E, PCS>,
+    pub_input: &[E::ScalarField],
+    witnesses: &W,
+) -> Result<ProverStepper<'a, E, PCS>, HyperPlonkErrors>
+where
+    E: Pairing,
+    W: WitnessSource<E::ScalarField> + ?Sized,
+    PCS: PolynomialCommitmentScheme<
+        E,
+        Polynomial = Arc<DenseMultilinearExtension<E::ScalarField>>,
+        Point = Vec<E::ScalarField>,
+        Evaluation = E::ScalarField,
+        Commitment = Commitment<E>,
+        BatchProof = BatchProof<E, PCS>,
+    >,
+{
+    let witnesses = read_columns(witnesses, pk.params.num_constraints);
+    prover_sanity_check(&pk.params, pub_input, &witnesses)?;
+
+    let witness_polys = witnesses
+        .into_iter()
+        .map(|w| Arc::new(DenseMultilinearExtension::from(w)))
+        .collect();
+
+    Ok(ProverStepper {
+        pk,
+        protocol_version: PROTOCOL_VERSION,
+        transcript: versions::transcript::<E::ScalarField>(PROTOCOL_VERSION)?,
+        phase: Phase::CommitWitness,
+        num_steps: 0,
+        witness_polys,
+        witness_commits: vec![],
+        precommitted_witnesses: None,
+        zero_check_proof: None,
+        permutation_oracles: vec![],
+        numerators: vec![],
+        denominators: vec![],
+        frac_poly: None,
+        prod_x: None,
+        frac_comm: None,
+        prod_x_comm: None,
+        perm_check_proof: None,
+        lookup_polys: vec![],
+        lookup_comms: vec![],
+        lookup_gamma: None,
+        lookup_check_proof: None,
+        pcs_acc: PcsAccumulator::<E, PCS>::new(pk.params.num_variables()),
+        cancellation: None,
+    })
+}
+
+impl<'a, E, PCS> ProverStepper<'a, E, PCS>
+where
+    E: Pairing,
+    PCS: PolynomialCommitmentScheme<
+        E,
+        Polynomial = Arc<DenseMultilinearExtension<E::ScalarField>>,
+        Point = Vec<E::ScalarField>,
+        Evaluation = E::ScalarField,
+        Commitment = Commitment<E>,
+        BatchProof = BatchProof<E, PCS>,
+    >,
+{
+    /// Total number of steps needed to generate a proof with `num_vars`
+    /// variables, `num_witnesses` witness columns and `num_lookups` lookups:
+    /// one per witness commitment, one per sum check round of each of the
+    /// two zero checks and of the batch opening, and eight for the remaining
+    /// phases. The lookups take one step per commitment, three per lookup,
+    /// one per round of their sum check, and one to initialize it.
+    pub fn expected_num_steps(num_vars: usize, num_witnesses: usize, num_lookups: usize) -> usize {
+        let lookup_steps = if num_lookups == 0 {
+            0
+        } else {
+            3 * num_lookups + num_vars + 1
+        };
+        num_witnesses + 3 * num_vars + 8 + lookup_steps
+    }
+
+    /// Number of steps executed so far.
+    pub fn num_steps(&self) -> usize {
+        self.num_steps
+    }
+
+    /// Use `commitments` as the witness commitments instead of committing
+    /// to the witness polynomials. The caller is responsible for checking
+    /// that they commit to the witnesses.
+    pub(crate) fn set_witness_commitments(&mut self, commitments: Vec<PCS::Commitment>) {
+        self.precommitted_witnesses = Some(commitments);
+    }
+
+    /// Generate the proof in the older protocol `version`, e.g., for
+    /// verifiers that do not support the current version yet. It must be
+    /// set before the first step.
+    pub fn set_protocol_version(&mut self, version: u16) -> Result<(), HyperPlonkErrors> {
+        if self.num_steps != 0 {
+            return Err(HyperPlonkErrors::InvalidProver(
+                "the protocol version must be set before the first step".to_string(),
+            ));
+        }
+        let recording = self.transcript.is_recording();
+        self.transcript = versions::transcript(version)?;
+        if recording {
+            self.transcript.start_recording();
+        }
+        self.protocol_version = version;
+        Ok(())
+    }
+
+    /// Allow [`Self::checkpoint`]. It must be enabled before the first
+    /// step, as the transcript is then recorded from its initial state.
+    pub fn enable_checkpoints(&mut self) -> Result<(), HyperPlonkErrors> {
+        if self.num_steps != 0 {
+            return Err(HyperPlonkErrors::InvalidProver(
+                "the checkpoints must be enabled before the first step".to_string(),
+            ));
+        }
+        self.transcript.start_recording();
+        Ok(())
+    }
+
+    /// Write the state of the prover in between two steps, from which
+    /// [`Self::resume`] continues and generates the same proof.
+    ///
+    /// The checkpoint holds the transcript operations, the witness, the
+    /// commitments and proofs generated so far, and the tables that the
+    /// remaining steps still need, e.g. the partially folded tables of the
+    /// current sum check. The tables are written in their canonical
+    /// serialization, without any further compression. The cancellation
+    /// token is not part of the checkpoint.
+    pub fn checkpoint<W: Write>(&self, mut writer: W) -> Result<(), HyperPlonkErrors> {
+        if !self.transcript.is_recording() {
+            return Err(HyperPlonkErrors::InvalidProver(
+                "the checkpoints are not enabled".to_string(),
+            ));
+        }
+        CHECKPOINT_FORMAT.serialize_compressed(&mut writer)?;
+        self.protocol_version.serialize_compressed(&mut writer)?;
+        // binds the checkpoint to the wiring of the circuit of the key
+        self.pk
+            .permutation_commitments
+            .serialize_compressed(&mut writer)?;
+        self.num_steps.serialize_compressed(&mut writer)?;
+        match &self.phase {
+            Phase::CommitWitness => 0u8.serialize_compressed(&mut writer)?,
+            Phase::ZeroCheckInit => 1u8.serialize_compressed(&mut writer)?,
+            Phase::ZeroCheckRound(rounds) => {
+                2u8.serialize_compressed(&mut writer)?;
+                rounds.serialize_compressed(&mut writer)?;
+            },
+            Phase::PermCheckInit => 3u8.serialize_compressed(&mut writer)?,
+            Phase::CommitFrac => 4u8.serialize_compressed(&mut writer)?,
+            Phase::CommitProd => 5u8.serialize_compressed(&mut writer)?,
+            Phase::ProdCheckInit => 6u8.serialize_compressed(&mut writer)?,
+            Phase::ProdCheckRound(rounds) => {
+                7u8.serialize_compressed(&mut writer)?;
+                rounds.serialize_compressed(&mut writer)?;
+            },
+            Phase::Evaluations => 8u8.serialize_compressed(&mut writer)?,
+            Phase::Opening(rounds) => {
+                9u8.serialize_compressed(&mut writer)?;
+                rounds.serialize_compressed(&mut writer)?;
+            },
+            Phase::LookupCommit => 10u8.serialize_compressed(&mut writer)?,
+            Phase::LookupCheckInit => 11u8.serialize_compressed(&mut writer)?,
+            Phase::LookupCheckRound(rounds) => {
+                12u8.serialize_compressed(&mut writer)?;
+                rounds.serialize_compressed(&mut writer)?;
+            },
+            Phase::OpeningInit => 13u8.serialize_compressed(&mut writer)?,
+            Phase::OpeningRound(rounds) => {
+                14u8.serialize_compressed(&mut writer)?;
+                rounds.serialize_compressed(&mut writer)?;
+            },
+            Phase::Finished => {
+                return Err(HyperPlonkErrors::InvalidProver(
+                    "the proof has already been generated".to_string(),
+                ))
+            },
+        }
+        self.transcript.write_log(&mut writer)?;
+
+        write_mles(&self.witness_polys, &mut writer)?;
+        self.witness_commits.serialize_compressed(&mut writer)?;
+        self.precommitted_witnesses
+            .serialize_compressed(&mut writer)?;
+        self.zero_check_proof.serialize_compressed(&mut writer)?;
+
+        // the permutation oracles are rebuilt from the key
+        (!self.permutation_oracles.is_empty()).serialize_compressed(&mut writer)?;
+        write_mles(&self.numerators, &mut writer)?;
+        write_mles(&self.denominators, &mut writer)?;
+        write_mles(
+            &[self.frac_poly.clone(), self.prod_x.clone()]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>(),
+            &mut writer,
+        )?;
+        self.frac_comm.serialize_compressed(&mut writer)?;
+        self.prod_x_comm.serialize_compressed(&mut writer)?;
+        match &self.perm_check_proof {
+            Some(proof) => {
+                true.serialize_compressed(&mut writer)?;
+                proof.zero_check_proof.serialize_compressed(&mut writer)?;
+                proof.prod_x_comm.serialize_compressed(&mut writer)?;
+                proof.frac_comm.serialize_compressed(&mut writer)?;
+            },
+            None => false.serialize_compressed(&mut writer)?,
+        }
+
+        write_mles(&self.lookup_polys, &mut writer)?;
+        self.lookup_comms.serialize_compressed(&mut writer)?;
+        self.lookup_gamma.serialize_compressed(&mut writer)?;
+        match &self.lookup_check_proof {
+            Some(proof) => {
+                true.serialize_compressed(&mut writer)?;
+                proof.sum_check_proof.serialize_compressed(&mut writer)?;
+                proof.multiplicity_comms.serialize_compressed(&mut writer)?;
+                proof.input_frac_comms.serialize_compressed(&mut writer)?;
+                proof.table_frac_comms.serialize_compressed(&mut writer)?;
+            },
+            None => false.serialize_compressed(&mut writer)?,
+        }
+
+        self.pcs_acc.write(&mut writer)
+    }
+
+    /// Restore a prover from a checkpoint written by [`Self::checkpoint`]
+    /// for the same key `pk`. The remaining steps generate the proof that
+    /// the checkpointed prover would have generated.
+    pub fn resume<R: Read>(
+        pk: &'a HyperPlonkProvingKey<E, PCS>,
+        mut reader: R,
+    ) -> Result<Self, HyperPlonkErrors> {
+        let format = u8::deserialize_compressed(&mut reader)?;
+        if format != CHECKPOINT_FORMAT {
+            return Err(HyperPlonkErrors::InvalidParameters(format!(
+                "unknown checkpoint format {}",
+                format
+            )));
+        }
+        let protocol_version = u16::deserialize_compressed(&mut reader)?;
+        let permutation_commitments = Vec::<Commitment<E>>::deserialize_compressed(&mut reader)?;
+        if permutation_commitments != pk.permutation_commitments {
+            return Err(HyperPlonkErrors::InvalidParameters(
+                "the checkpoint is for another proving key".to_string(),
+            ));
+        }
+        let num_steps = usize::deserialize_compressed(&mut reader)?;
+        let phase = match u8::deserialize_compressed(&mut reader)? {
+            0 => Phase::CommitWitness,
+            1 => Phase::ZeroCheckInit,
+            2 => Phase::ZeroCheckRound(SumCheckRounds::deserialize_compressed(&mut reader)?),
+            3 => Phase::PermCheckInit,
+            4 => Phase::CommitFrac,
+            5 => Phase::CommitProd,
+            6 => Phase::ProdCheckInit,
+            7 => Phase::ProdCheckRound(SumCheckRounds::deserialize_compressed(&mut reader)?),
+            8 => Phase::Evaluations,
+            9 => Phase::Opening(MultiOpenRounds::deserialize_compressed(&mut reader)?),
+            10 => Phase::LookupCommit,
+            11 => Phase::LookupCheckInit,
+            12 => Phase::LookupCheckRound(SumCheckRounds::deserialize_compressed(&mut reader)?),
+            13 => Phase::OpeningInit,
+            14 => Phase::OpeningRound(MultiOpenRounds::deserialize_compressed(&mut reader)?),
+            tag => {
+                return Err(HyperPlonkErrors::InvalidParameters(format!(
+                    "unknown prover phase {} in the checkpoint",
+                    tag
+                )))
+            },
+        };
+        let mut transcript = versions::transcript::<E::ScalarField>(protocol_version)?;
+        transcript.start_recording();
+        transcript.replay_log(&mut reader)?;
+
+        let num_vars = pk.params.num_variables();
+        let witness_polys = read_mles(&mut reader, num_vars)?;
+        if witness_polys.len() != pk.params.num_witness_columns() {
+            return Err(HyperPlonkErrors::InvalidParameters(format!(
+                "the checkpoint has {} witness columns, the key has {}",
+                witness_polys.len(),
+                pk.params.num_witness_columns()
+            )));
+        }
+        let witness_commits = Vec::deserialize_compressed(&mut reader)?;
+        let precommitted_witnesses = Option::deserialize_compressed(&mut reader)?;
+        let zero_check_proof = Option::deserialize_compressed(&mut reader)?;
+
+        let permutation_oracles = if bool::deserialize_compressed(&mut reader)? {
+            pk.permutation_oracles()
+        } else {
+            vec![]
+        };
+        let numerators = read_mles(&mut reader, num_vars)?;
+        let denominators = read_mles(&mut reader, num_vars)?;
+        let (frac_poly, prod_x) = match read_mles(&mut reader, num_vars)?.as_slice() {
+            [] => (None, None),
+            [frac_poly, prod_x] => (Some(frac_poly.clone()), Some(prod_x.clone())),
+            _ => {
+                return Err(HyperPlonkErrors::InvalidParameters(
+                    "the checkpoint has only one of frac(x) and prod(x)".to_string(),
+                ))
+            },
+        };
+        let frac_comm = Option::deserialize_compressed(&mut reader)?;
+        let prod_x_comm = Option::deserialize_compressed(&mut reader)?;
+        let perm_check_proof = if bool::deserialize_compressed(&mut reader)? {
+            Some(ProductCheckProof {
+                zero_check_proof: IOPProof::deserialize_compressed(&mut reader)?,
+                prod_x_comm: Commitment::deserialize_compressed(&mut reader)?,
+                frac_comm: Commitment::deserialize_compressed(&mut reader)?,
+            })
+        } else {
+            None
+        };
+        let lookup_polys = read_mles(&mut reader, num_vars)?;
+        let lookup_comms = Vec::deserialize_compressed(&mut reader)?;
+        let lookup_gamma = Option::deserialize_compressed(&mut reader)?;
+        let lookup_check_proof = if bool::deserialize_compressed(&mut reader)? {
+            Some(LookupCheckProof {
+                sum_check_proof: IOPProof::deserialize_compressed(&mut reader)?,
+                multiplicity_comms: Vec::deserialize_compressed(&mut reader)?,
+                input_frac_comms: Vec::deserialize_compressed(&mut reader)?,
+                table_frac_comms: Vec::deserialize_compressed(&mut reader)?,
+            })
+        } else {
+            None
+        };
+
+        Ok(ProverStepper {
+            pk,
+            protocol_version,
+            transcript,
+            phase,
+            num_steps,
+            witness_polys,
+            witness_commits,
+            precommitted_witnesses,
+            zero_check_proof,
+            permutation_oracles,
+            numerators,
+            denominators,
+            frac_poly,
+            prod_x,
+            frac_comm,
+            prod_x_comm,
+            perm_check_proof,
+            lookup_polys,
+            lookup_comms,
+            lookup_gamma,
+            lookup_check_proof,
+            pcs_acc: PcsAccumulator::read(&mut reader)?,
+            cancellation: None,
+        })
+    }
+
+    /// Abort the proof once `token` is cancelled: every step after the
+    /// cancellation returns `HyperPlonkErrors::Cancelled`.
+    pub fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancellation = Some(token);
+    }
+
+    /// Execute one bounded-size unit of work.
+    ///
+    /// Returns [`StepResult::Done`] with the proof once the last step has
+    /// been executed; calling `step` afterwards is an error.
+    #[allow(clippy::type_complexity)]
+    pub fn step(
+        &mut self,
+    ) -> Result<StepResult<HyperPlonkProof<E, PolyIOP<E::ScalarField>, PCS>>, HyperPlonkErrors>
+    {
+        if let Some(token) = &self.cancellation {
+            token.check()?;
+        }
+        let phase = std::mem::replace(&mut self.phase, Phase::Finished);
+        self.num_steps += 1;
+        self.phase = match phase {
+            Phase::CommitWitness => self.commit_witness()?,
+            Phase::ZeroCheckInit => self.zero_check_init()?,
+            Phase::ZeroCheckRound(rounds) => self.zero_check_round(rounds)?,
+            Phase::PermCheckInit => self.perm_check_init()?,
+            Phase::CommitFrac => self.commit_frac()?,
+            Phase::CommitProd => self.commit_prod()?,
+            Phase::ProdCheckInit => self.prod_check_init()?,
+            Phase::ProdCheckRound(rounds) => self.prod_check_round(rounds)?,
+            Phase::LookupCommit => self.lookup_commit()?,
+            Phase::LookupCheckInit => self.lookup_check_init()?,
+            Phase::LookupCheckRound(rounds) => self.lookup_check_round(rounds)?,
+            Phase::Evaluations => self.evaluations()?,
+            Phase::OpeningInit => self.opening_init()?,
+            Phase::OpeningRound(rounds) => self.opening_round(rounds)?,
+            Phase::Opening(rounds) => return self.opening(rounds).map(StepResult::Done),
+            Phase::Finished => {
+                return Err(HyperPlonkErrors::InvalidProver(
+                    "the proof has already been generated".to_string(),
+                ))
+            },
+        };
+        Ok(StepResult::Continue)
+    }
+
+    /// Execute all the remaining steps and output the proof.
+    ///
+    /// With no step in between, the witness commitments do not need to be
+    /// one step each: if none has been computed yet, they are all computed
+    /// at once, in parallel, before the steps run.
+    pub fn run(
+        mut self,
+    ) -> Result<HyperPlonkProof<E, PolyIOP<E::ScalarField>, PCS>, HyperPlonkErrors> {
+        self.commit_witnesses()?;
+        loop {
+            if let StepResult::Done(proof) = self.step()? {
+                return Ok(proof);
+            }
+        }
+    }
+
+    /// Compute all the witness commitments at once, in parallel, if none
+    /// has been computed yet.
+    pub(crate) fn commit_witnesses(&mut self) -> Result<(), HyperPlonkErrors> {
+        if self.witness_commits.is_empty() && self.precommitted_witnesses.is_none() {
+            if let Some(token) = &self.cancellation {
+                token.check()?;
+            }
+            let step = start_timer!(|| "commit witnesses");
+            let pcs_param = &*self.pk.pcs_param;
+            self.precommitted_witnesses = Some(
+                cfg_iter!(self.witness_polys)
+                    .map(|poly| PCS::commit(pcs_param, poly))
+                    .collect::<Result<Vec<_>, _>>()?,
+            );
+            end_timer!(step);
+        }
+        Ok(())
+    }
+
+    /// The profiled phase that the next step belongs to.
+    pub(crate) fn profiled_phase(&self) -> ProfiledPhase {
+        match self.phase {
+            Phase::CommitWitness | Phase::CommitFrac | Phase::CommitProd => {
+                ProfiledPhase::Commitment
+            },
+            Phase::ZeroCheckInit | Phase::PermCheckInit | Phase::ProdCheckInit => {
+                ProfiledPhase::MleComputation
+            },
+            Phase::ZeroCheckRound(_) | Phase::ProdCheckRound(_) => ProfiledPhase::SumCheck,
+            Phase::LookupCommit | Phase::LookupCheckInit | Phase::LookupCheckRound(_) => {
+                ProfiledPhase::LookupCheck
+            },
+            Phase::Evaluations
+            | Phase::OpeningInit
+            | Phase::OpeningRound(_)
+            | Phase::Opening(_)
+            | Phase::Finished => ProfiledPhase::Opening,
+        }
+    }
+
+    // =======================================================================
+    // 1. Commit Witness polynomials `w_i(x)` and append commitment to
+    // transcript
+    // =======================================================================
+    fn commit_witness(&mut self) -> Result<Phase<E::ScalarField>, HyperPlonkErrors> {
+        let step = start_timer!(|| "commit witness");
+        let index = self.witness_commits.len();
+        let w_com = match &self.precommitted_witnesses {
+            Some(commitments) => commitments[index],
+            None => PCS::commit(&*self.pk.pcs_param, &self.witness_polys[index])?,
+        };
+        self.transcript.append_serializable_element(b"w", &w_com)?;
+        self.witness_commits.push(w_com);
+        end_timer!(step);
+
+        if self.witness_commits.len() < self.witness_polys.len() {
+            Ok(Phase::CommitWitness)
+        } else {
+            Ok(Phase::ZeroCheckInit)
+        }
+    }
+
+    // =======================================================================
+    // 2 Run ZeroCheck on
+    //
+    //     `f(q_0(x),...q_l(x), w_0(x),...w_d(x))`
+    //
+    // where `f` is the constraint polynomial, and obtain a ZeroCheckSubClaim
+    // =======================================================================
+    fn zero_check_init(&mut self) -> Result<Phase<E::ScalarField>, HyperPlonkErrors> {
+        let step = start_timer!(|| "build f and init ZeroCheck");
+        let fx = build_f(
+            &self.pk.params.gate_func,
+            self.pk.params.num_variables(),
+            &self.pk.selector_oracles,
+            &self.witness_polys,
+        )?;
+        let rounds = zero_check_rounds(&fx, &mut self.transcript)?;
+        end_timer!(step);
+        Ok(Phase::ZeroCheckRound(rounds))
+    }
+
+    fn zero_check_round(
+        &mut self,
+        mut rounds: SumCheckRounds<E::ScalarField>,
+    ) -> Result<Phase<E::ScalarField>, HyperPlonkErrors> {
+        rounds.prove_round(&mut self.transcript)?;
+        if !rounds.is_finished() {
+            return Ok(Phase::ZeroCheckRound(rounds));
+        }
+        self.zero_check_proof = Some(rounds.finish());
+        Ok(Phase::PermCheckInit)
+    }
+
+    // =======================================================================
+    // 3. Run permutation check on `\{w_i(x)\}` and `permutation_oracle`, and
+    // obtain a PermCheckSubClaim.
+    // =======================================================================
+    fn perm_check_init(&mut self) -> Result<Phase<E::ScalarField>, HyperPlonkErrors> {
+        let step = start_timer!(|| "build frac(x) and prod(x)");
+        let beta = self.transcript.get_perm_check_challenge(b"beta")?;
+        let gamma = self.transcript.get_perm_check_challenge(b"gamma")?;
+        // the permutation polynomials are only built for this proof, unless
+        // they are cached in the key
+        self.permutation_oracles = self.pk.permutation_oracles();
+        // the witness columns followed by the fixed columns
+        let perm_columns = [
+            self.witness_polys.as_slice(),
+            self.pk.fixed_oracles.as_slice(),
+        ]
+        .concat();
+        let (numerators, denominators) = computer_nums_and_denoms(
+            &beta,
+            &gamma,
+            &perm_columns,
+            &perm_columns,
+            &self.permutation_oracles,
+        )?;
+
+        let frac_poly = compute_frac_poly(&numerators, &denominators)?;
+        let prod_x = compute_product_poly(&frac_poly)?;
+
+        self.numerators = numerators;
+        self.denominators = denominators;
+        self.frac_poly = Some(frac_poly);
+        self.prod_x = Some(prod_x);
+        end_timer!(step);
+        Ok(Phase::CommitFrac)
+    }
+
+    fn commit_frac(&mut self) -> Result<Phase<E::ScalarField>, HyperPlonkErrors> {
+        let frac_poly = self.frac_poly.as_ref().ok_or_else(|| {
+            HyperPlonkErrors::InvalidProver("frac(x) has not been built".to_string())
+        })?;
+        self.frac_comm = Some(PCS::commit(&*self.pk.pcs_param, frac_poly)?);
+        Ok(Phase::CommitProd)
+    }
+
+    fn commit_prod(&mut self) -> Result<Phase<E::ScalarField>, HyperPlonkErrors> {
+        let prod_x = self.prod_x.as_ref().ok_or_else(|| {
+            HyperPlonkErrors::InvalidProver("prod(x) has not been built".to_string())
+        })?;
+        self.prod_x_comm = Some(PCS::commit(&*self.pk.pcs_param, prod_x)?);
+        Ok(Phase::ProdCheckInit)
+    }
+
+    fn prod_check_init(&mut self) -> Result<Phase<E::ScalarField>, HyperPlonkErrors> {
+        let (frac_poly, prod_x, frac_comm, prod_x_comm) = self.perm_check_polys()?;
+
+        self.transcript
+            .append_serializable_element(b"frac(x)", &frac_comm)?;
+        self.transcript
+            .append_serializable_element(b"prod(x)", &prod_x_comm)?;
+        let alpha = self.transcript.get_perm_check_challenge(b"alpha")?;
+
+        let q_x = build_prod_check_poly(
+            &self.numerators,
+            &self.denominators,
+            &frac_poly,
+            &prod_x,
+            &alpha,
+        )?;
+        let rounds = zero_check_rounds(&q_x, &mut self.transcript)?;
+        Ok(Phase::ProdCheckRound(rounds))
+    }
+
+    fn prod_check_round(
+        &mut self,
+        mut rounds: SumCheckRounds<E::ScalarField>,
+    ) -> Result<Phase<E::ScalarField>, HyperPlonkErrors> {
+        rounds.prove_round(&mut self.transcript)?;
+        if !rounds.is_finished() {
+            return Ok(Phase::ProdCheckRound(rounds));
+        }
+        let (_, _, frac_comm, prod_x_comm) = self.perm_check_polys()?;
+        self.perm_check_proof = Some(ProductCheckProof {
+            zero_check_proof: rounds.finish(),
+            prod_x_comm,
+            frac_comm,
+        });
+        // the numerators and denominators are no longer needed
+        self.numerators = vec![];
+        self.denominators = vec![];
+        if self.pk.params.gate_func.lookups().is_empty() {
+            Ok(Phase::Evaluations)
+        } else {
+            Ok(Phase::LookupCommit)
+        }
+    }
+
+    // =======================================================================
+    // 3'. Run the lookup check on the looked up witnesses `w_j(x)` and their
+    // tables `t_k(x)`: commit to the multiplicities `m_l(x)`, then to `a_l(x)`
+    // and `b_l(x)` of each lookup, one commitment per step
+    // =======================================================================
+    fn lookup_commit(&mut self) -> Result<Phase<E::ScalarField>, HyperPlonkErrors> {
+        let step = start_timer!(|| "commit lookup polynomial");
+        let lookups = self.pk.params.gate_func.lookups();
+        let num_lookups = lookups.len();
+        let index = self.lookup_comms.len();
+        if index == self.lookup_polys.len() {
+            if index < num_lookups {
+                let (witness, table_id) = lookups[index];
+                let mx = compute_multiplicity_poly(
+                    &self.witness_polys[witness],
+                    &self.pk.table_oracles[table_id],
+                )?;
+                self.lookup_polys.push(mx);
+            } else {
+                // a(x) and b(x) of a lookup are built together
+                let l = (index - num_lookups) / 2;
+                let (witness, table_id) = lookups[l];
+                let (ax, bx) = compute_lookup_frac_polys(
+                    &self.lookup_gamma()?,
+                    &self.witness_polys[witness],
+                    &self.pk.table_oracles[table_id],
+                    &self.lookup_polys[l],
+                )?;
+                self.lookup_polys.extend([ax, bx]);
+            }
+        }
+        let label: &'static [u8] = if index < num_lookups {
+            b"m(x)"
+        } else {
+            [b"a(x)", b"b(x)"][(index - num_lookups) % 2]
+        };
+        let comm = PCS::commit(&*self.pk.pcs_param, &self.lookup_polys[index])?;
+        self.transcript.append_serializable_element(label, &comm)?;
+        self.lookup_comms.push(comm);
+        if self.lookup_comms.len() == num_lookups {
+            self.lookup_gamma = Some(self.transcript.get_lookup_challenges(b"gamma", 1)?[0].0);
+        }
+        end_timer!(step);
+
+        if self.lookup_comms.len() < 3 * num_lookups {
+            Ok(Phase::LookupCommit)
+        } else {
+            Ok(Phase::LookupCheckInit)
+        }
+    }
+
+    fn lookup_check_init(&mut self) -> Result<Phase<E::ScalarField>, HyperPlonkErrors> {
+        let step = start_timer!(|| "build the lookup check polynomial");
+        let gamma = self.lookup_gamma()?;
+        let alpha = self.transcript.get_lookup_challenges(b"alpha", 1)?[0];
+        let r: Vec<_> = self
+            .transcript
+            .get_lookup_challenges(b"r", self.pk.params.num_variables())?
+            .into_iter()
+            .map(|c| c.0)
+            .collect();
+
+        let (fxs, txs): (Vec<_>, Vec<_>) = self
+            .pk
+            .params
+            .gate_func
+            .lookups()
+            .iter()
+            .map(|&(witness, table_id)| {
+                (
+                    self.witness_polys[witness].clone(),
+                    self.pk.table_oracles[table_id].clone(),
+                )
+            })
+            .unzip();
+        let (mxs, fracs) = self.lookup_polys.split_at(fxs.len());
+        let axs: Vec<_> = fracs.iter().step_by(2).cloned().collect();
+        let bxs: Vec<_> = fracs.iter().skip(1).step_by(2).cloned().collect();
+        let q_x = build_lookup_check_poly(&fxs, &txs, mxs, &axs, &bxs, &gamma, &alpha, &r)?;
+        let rounds = SumCheckRounds::new(&q_x, &mut self.transcript)?;
+        end_timer!(step);
+        Ok(Phase::LookupCheckRound(rounds))
+    }
+
+    fn lookup_check_round(
+        &mut self,
+        mut rounds: SumCheckRounds<E::ScalarField>,
+    ) -> Result<Phase<E::ScalarField>, HyperPlonkErrors> {
+        rounds.prove_round(&mut self.transcript)?;
+        if !rounds.is_finished() {
+            return Ok(Phase::LookupCheckRound(rounds));
+        }
+        let num_lookups = self.pk.params.gate_func.lookups().len();
+        let (multiplicity_comms, frac_comms) = self.lookup_comms.split_at(num_lookups);
+        self.lookup_check_proof = Some(LookupCheckProof {
+            sum_check_proof: rounds.finish(),
+            multiplicity_comms: multiplicity_comms.to_vec(),
+            input_frac_comms: frac_comms.iter().step_by(2).copied().collect(),
+            table_frac_comms: frac_comms.iter().skip(1).step_by(2).copied().collect(),
+        });
+        Ok(Phase::Evaluations)
+    }
+
+    // =======================================================================
+    // 4. Generate evaluations and corresponding proofs
+    // - permcheck
+    //  1. (deferred) batch opening prod(x) at
+    //   - [perm_check_point]
+    //   - [perm_check_point[2..n], 0]
+    //   - [perm_check_point[2..n], 1]
+    //   - [1,...1, 0]
+    //  2. (deferred) batch opening frac(x) at
+    //   - [perm_check_point]
+    //   - [perm_check_point[2..n], 0]
+    //   - [perm_check_point[2..n], 1]
+    //  3. (deferred) batch opening s_id(x) at
+    //   - [perm_check_point]
+    //  4. (deferred) batch opening perms(x) at
+    //   - [perm_check_point]
+    //  5. (deferred) batch opening witness_i(x) at
+    //   - [perm_check_point]
+    //  6. (deferred) batch opening fixed_i(x) at
+    //   - [perm_check_point]
+    //
+    // - zero check evaluations and proofs
+    //   - 4.3.1. (deferred) wi_poly(zero_check_point)
+    //   - 4.3.2. (deferred) selector_poly(zero_check_point)
+    //
+    // - 4.4. (deferred) public input consistency checks
+    //   - pi_poly(r_pi) where r_pi is sampled from transcript
+    //
+    // - 4.5. (deferred) lookup check evaluations, for each lookup
+    //   - m(x), a(x), b(x), w_j(x) and t_k(x) at lookup_check_point
+    // =======================================================================
+    fn evaluations(&mut self) -> Result<Phase<E::ScalarField>, HyperPlonkErrors> {
+        let step = start_timer!(|| "opening and evaluations");
+        let pk = self.pk;
+        let num_vars = pk.params.num_variables();
+        // the preprocessed commitments are not part of the proof, the
+        // verifier takes them from the verifying key
+        if cfg!(debug_assertions) {
+            check_preprocessed_commitments(pk)?;
+        }
+        // online public input of length 2^\ell
+        let ell = log2(pk.params.num_pub_input) as usize;
+
+        let (frac_poly, prod_x, _, _) = self.perm_check_polys()?;
+        let (zero_check_proof, perm_check_proof) =
+            match (&self.zero_check_proof, &self.perm_check_proof) {
+                (Some(zero_check_proof), Some(perm_check_proof)) => {
+                    (zero_check_proof, perm_check_proof)
+                },
+                _ => {
+                    return Err(HyperPlonkErrors::InvalidProver(
+                        "the PolyIOP proofs have not been generated".to_string(),
+                    ))
+                },
+            };
+        let perm_check_point = &perm_check_proof.zero_check_proof.point;
+        let pcs_acc = &mut self.pcs_acc;
+
+        // (perm_check_point[2..n], 0)
+        let perm_check_point_0 = [
+            &[E::ScalarField::zero()],
+            &perm_check_point[0..num_vars - 1],
+        ]
+        .concat();
+        // (perm_check_point[2..n], 1)
+        let perm_check_point_1 =
+            [&[E::ScalarField::one()], &perm_check_point[0..num_vars - 1]].concat();
+        // (1, ..., 1, 0)
+        let prod_final_query_point = [
+            vec![E::ScalarField::zero()],
+            vec![E::ScalarField::one(); num_vars - 1],
+        ]
+        .concat();
+
+        // prod(x)'s points
+        pcs_acc.insert_poly_and_points(&prod_x, &perm_check_proof.prod_x_comm, perm_check_point);
+        pcs_acc.insert_poly_and_points(&prod_x, &perm_check_proof.prod_x_comm, &perm_check_point_0);
+        pcs_acc.insert_poly_and_points(&prod_x, &perm_check_proof.prod_x_comm, &perm_check_point_1);
+        pcs_acc.insert_poly_and_points(
+            &prod_x,
+            &perm_check_proof.prod_x_comm,
+            &prod_final_query_point,
+        );
+
+        // frac(x)'s points
+        pcs_acc.insert_poly_and_points(&frac_poly, &perm_check_proof.frac_comm, perm_check_point);
+        pcs_acc.insert_poly_and_points(
+            &frac_poly,
+            &perm_check_proof.frac_comm,
+            &perm_check_point_0,
+        );
+        pcs_acc.insert_poly_and_points(
+            &frac_poly,
+            &perm_check_proof.frac_comm,
+            &perm_check_point_1,
+        );
+
+        // perms(x)'s points, which the accumulator now holds until the
+        // opening
+        for (perm, pcom) in std::mem::take(&mut self.permutation_oracles)
+            .iter()
+            .zip(pk.permutation_commitments.iter())
+        {
+            pcs_acc.insert_poly_and_points(perm, pcom, perm_check_point);
+        }
+
+        // witnesses' points
+        // TODO: refactor so it remains correct even if the order changed
+        for (wpoly, wcom) in self.witness_polys.iter().zip(self.witness_commits.iter()) {
+            pcs_acc.insert_poly_and_points(wpoly, wcom, perm_check_point);
+        }
+        // fixed columns' points, opened against their preprocessed commitments
+        for (fpoly, fcom) in pk.fixed_oracles.iter().zip(pk.fixed_commitments.iter()) {
+            pcs_acc.insert_poly_and_points(fpoly, fcom, perm_check_point);
+        }
+        for (wpoly, wcom) in self.witness_polys.iter().zip(self.witness_commits.iter()) {
+            pcs_acc.insert_poly_and_points(wpoly, wcom, &zero_check_proof.point);
+        }
+
+        //   - 4.3.2. (deferred) selector_poly(zero_check_point), for the
+        //     committed selectors only: the verifier evaluates the others
+        pk.selector_oracles
+            .iter()
+            .zip(pk.selectors.iter())
+            .filter_map(|(poly, selector)| Some((poly, selector.commitment()?)))
+            .for_each(|(poly, com)| {
+                pcs_acc.insert_poly_and_points(poly, com, &zero_check_proof.point)
+            });
+
+        // - 4.4. public input consistency checks
+        //   - pi_poly(r_pi) where r_pi is sampled from transcript
+        let r_pi = pub_input_challenge(&mut self.transcript, ell)?;
+        // padded with zeros
+        let r_pi_padded = [r_pi, vec![E::ScalarField::zero(); num_vars - ell]].concat();
+        // Evaluate witness_poly[0] at r_pi||0s which is equal to public_input evaluated
+        // at r_pi. Assumes that public_input is a power of 2
+        pcs_acc.insert_poly_and_points(
+            &self.witness_polys[0],
+            &self.witness_commits[0],
+            &r_pi_padded,
+        );
+
+        // - 4.5. lookup check evaluations
+        if let Some(lookup_check_proof) = &self.lookup_check_proof {
+            let lookup_check_point = &lookup_check_proof.sum_check_proof.point;
+            let num_lookups = pk.params.gate_func.lookups().len();
+            let comms = [
+                &lookup_check_proof.multiplicity_comms,
+                &lookup_check_proof.input_frac_comms,
+                &lookup_check_proof.table_frac_comms,
+            ];
+            for (l, &(witness, table_id)) in pk.params.gate_func.lookups().iter().enumerate() {
+                // m(x), a(x) and b(x) of the lookup
+                let polys = [l, num_lookups + 2 * l, num_lookups + 2 * l + 1];
+                for (&i, comms) in polys.iter().zip(comms.iter()) {
+                    pcs_acc.insert_poly_and_points(
+                        &self.lookup_polys[i],
+                        &comms[l],
+                        lookup_check_point,
+                    );
+                }
+                pcs_acc.insert_poly_and_points(
+                    &self.witness_polys[witness],
+                    &self.witness_commits[witness],
+                    lookup_check_point,
+                );
+                pcs_acc.insert_poly_and_points(
+                    &pk.table_oracles[table_id],
+                    &pk.table_commitments[table_id],
+                    lookup_check_point,
+                );
+            }
+        }
+        end_timer!(step);
+        Ok(Phase::OpeningInit)
+    }
+
+    // =======================================================================
+    // 5. deferred batch opening
+    // =======================================================================
+    fn opening_init(&mut self) -> Result<Phase<E::ScalarField>, HyperPlonkErrors> {
+        let step = start_timer!(|| "init deferred batch openings");
+        // the accumulated claims are no longer needed once merged
+        let pcs_acc = std::mem::replace(
+            &mut self.pcs_acc,
+            PcsAccumulator::new(self.pk.params.num_variables()),
+        );
+        let rounds = pcs_acc.multi_open_rounds(&mut self.transcript)?;
+        end_timer!(step);
+        Ok(Phase::OpeningRound(rounds))
+    }
+
+    fn opening_round(
+        &mut self,
+        mut rounds: MultiOpenRounds<E::ScalarField>,
+    ) -> Result<Phase<E::ScalarField>, HyperPlonkErrors> {
+        rounds.prove_round(&mut self.transcript)?;
+        if rounds.is_finished() {
+            Ok(Phase::Opening(rounds))
+        } else {
+            Ok(Phase::OpeningRound(rounds))
+        }
+    }
+
+    fn opening(
+        &mut self,
+        rounds: MultiOpenRounds<E::ScalarField>,
+    ) -> Result<HyperPlonkProof<E, PolyIOP<E::ScalarField>, PCS>, HyperPlonkErrors> {
+        let step = start_timer!(|| "open the batched polynomial");
+        let batch_openings = rounds.finish::<E, PCS>(&*self.pk.pcs_param)?;
+        end_timer!(step);
+
+        match (self.zero_check_proof.take(), self.perm_check_proof.take()) {
+            (Some(zero_check_proof), Some(perm_check_proof)) => Ok(HyperPlonkProof {
+                protocol_version: self.protocol_version,
+                witness_commits: std::mem::take(&mut self.witness_commits),
+                batch_openings,
+                zero_check_proof,
+                perm_check_proof,
+                lookup_check_proof: self.lookup_check_proof.take(),
+            }),
+            _ => Err(HyperPlonkErrors::InvalidProver(
+                "the PolyIOP proofs have not been generated".to_string(),
+            )),
+        }
+    }
+
+    /// The challenge `gamma` of the lookup check.
+    fn lookup_gamma(&self) -> Result<LookupChallenge<E::ScalarField>, HyperPlonkErrors> {
+        self.lookup_gamma.map(LookupChallenge).ok_or_else(|| {
+            HyperPlonkErrors::InvalidProver(
+                "the multiplicities have not been committed".to_string(),
+            )
+        })
+    }
+
+    /// The polynomials and commitments of the permutation check.
+    #[allow(clippy::type_complexity)]
+    fn perm_check_polys(
+        &self,
+    ) -> Result<
+        (
+            Mle<E::ScalarField>,
+            Mle<E::ScalarField>,
+            PCS::Commitment,
+            PCS::Commitment,
+        ),
+        HyperPlonkErrors,
+    > {
+        match (
+            &self.frac_poly,
+            &self.prod_x,
+            self.frac_comm,
+            self.prod_x_comm,
+        ) {
+            (Some(frac_poly), Some(prod_x), Some(frac_comm), Some(prod_x_comm)) => {
+                Ok((frac_poly.clone(), prod_x.clone(), frac_comm, prod_x_comm))
+            },
+            _ => Err(HyperPlonkErrors::InvalidProver(
+                "the permutation check polynomials have not been committed".to_string(),
+            )),
+        }
+    }
+}
+
+/// Recompute the commitments to the preprocessed oracles of `pk`, and check
+/// that they match the commitments in `pk`. A selector described compactly
+/// must instead match its description.
+fn check_preprocessed_commitments<E, PCS>(
+    pk: &HyperPlonkProvingKey<E, PCS>,
+) -> Result<(), HyperPlonkErrors>
+where
+    E: Pairing,
+    PCS: PolynomialCommitmentScheme<E, Polynomial = Arc<DenseMultilinearExtension<E::ScalarField>>>,
+{
+    if pk.selector_oracles.len() != pk.selectors.len() {
+        return Err(HyperPlonkErrors::InvalidProver(format!(
+            "Number of selector descriptions is not correct: got {}, expect {}",
+            pk.selectors.len(),
+            pk.selector_oracles.len()
+        )));
+    }
+    for (i, (oracle, selector)) in pk
+        .selector_oracles
+        .iter()
+        .zip(pk.selectors.iter())
+        .enumerate()
+    {
+        let matches = match selector.commitment() {
+            Some(commitment) => PCS::commit(&*pk.pcs_param, oracle)? == *commitment,
+            None => {
+                selector.check(oracle.num_vars).is_ok()
+                    && selector.evaluations(oracle.num_vars).as_ref() == Some(&oracle.evaluations)
+            },
+        };
+        if !matches {
+            return Err(HyperPlonkErrors::InvalidProver(format!(
+                "The selector {} does not match the proving key",
+                i
+            )));
+        }
+    }
+
+    let permutation_oracles = pk.permutation_oracles();
+    for (name, oracles, commitments) in [
+        (
+            "permutation",
+            &permutation_oracles,
+            &pk.permutation_commitments,
+        ),
+        ("fixed", &pk.fixed_oracles, &pk.fixed_commitments),
+        ("table", &pk.table_oracles, &pk.table_commitments),
+    ] {
+        if oracles.len() != commitments.len() {
+            return Err(HyperPlonkErrors::InvalidProver(format!(
+                "Number of {} commitments is not correct: got {}, expect {}",
+                name,
+                commitments.len(),
+                oracles.len()
+            )));
+        }
+        for (i, (oracle, commitment)) in oracles.iter().zip(commitments.iter()).enumerate() {
+            if PCS::commit(&*pk.pcs_param, oracle)? != *commitment {
+                return Err(HyperPlonkErrors::InvalidProver(format!(
+                    "The {} commitment {} does not match the proving key",
+                    name, i
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        custom_gate::CustomizedGates,
+        mock::MockCircuit,
+        selectors::SelectorColumn,
+        structs::{HyperPlonkIndex, HyperPlonkParams},
+        witness::WitnessColumn,
+        HyperPlonkSNARK,
+    };
+    use arithmetic::identity_permutation;
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_std::test_rng;
+    use subroutines::pcs::prelude::MultilinearKzgPCS;
+
+    #[test]
+    fn test_prover_stepper() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let nv = 6;
+        let pcs_srs = MultilinearKzgPCS::<Bls12_381>::gen_srs_for_testing(&mut rng, nv)?;
+
+        for gate in [
+            CustomizedGates::vanilla_plonk_gate(),
+            CustomizedGates::single_column_gate(5),
+        ] {
+            let circuit = MockCircuit::<Fr>::new(1 << nv, &gate);
+            let (pk, vk) = <PolyIOP<Fr> as HyperPlonkSNARK<
+                Bls12_381,
+                MultilinearKzgPCS<Bls12_381>,
+            >>::preprocess(&circuit.index, &pcs_srs)?;
+
+            let mut stepper = prove_in_steps(&pk, &circuit.public_inputs, &circuit.witnesses)?;
+            let proof = loop {
+                if let StepResult::Done(proof) = stepper.step()? {
+                    break proof;
+                }
+            };
+            assert_eq!(
+                stepper.num_steps(),
+                ProverStepper::<Bls12_381, MultilinearKzgPCS<Bls12_381>>::expected_num_steps(
+                    nv,
+                    circuit.num_witness_columns(),
+                    0
+                )
+            );
+            // the stepper cannot be driven past the end
+            assert!(stepper.step().is_err());
+
+            let expected = <PolyIOP<Fr> as HyperPlonkSNARK<
+                Bls12_381,
+                MultilinearKzgPCS<Bls12_381>,
+            >>::prove(&pk, &circuit.public_inputs, &circuit.witnesses)?;
+            assert_eq!(proof.witness_commits, expected.witness_commits);
+            assert_eq!(proof.zero_check_proof, expected.zero_check_proof);
+            assert_eq!(
+                proof.perm_check_proof.zero_check_proof,
+                expected.perm_check_proof.zero_check_proof
+            );
+            assert_eq!(
+                proof.perm_check_proof.prod_x_comm,
+                expected.perm_check_proof.prod_x_comm
+            );
+            assert_eq!(
+                proof.perm_check_proof.frac_comm,
+                expected.perm_check_proof.frac_comm
+            );
+            assert_eq!(
+                proof.batch_openings.f_i_eval_at_point_i,
+                expected.batch_openings.f_i_eval_at_point_i
+            );
+            assert!(<PolyIOP<Fr> as HyperPlonkSNARK<
+                Bls12_381,
+                MultilinearKzgPCS<Bls12_381>,
+            >>::verify(&vk, &circuit.public_inputs, &proof)?);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_prover_checkpoints() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let nv = 4;
+        let pcs_srs = MultilinearKzgPCS::<Bls12_381>::gen_srs_for_testing(&mut rng, nv)?;
+        let circuit = MockCircuit::<Fr>::new(1 << nv, &CustomizedGates::vanilla_plonk_gate());
+        let (pk, _) =
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, MultilinearKzgPCS<Bls12_381>>>::preprocess(
+                &circuit.index,
+                &pcs_srs,
+            )?;
+        let expected =
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, MultilinearKzgPCS<Bls12_381>>>::prove(
+                &pk,
+                &circuit.public_inputs,
+                &circuit.witnesses,
+            )?;
+
+        let num_witnesses = circuit.num_witness_columns();
+        let num_steps =
+            ProverStepper::<Bls12_381, MultilinearKzgPCS<Bls12_381>>::expected_num_steps(
+                nv,
+                num_witnesses,
+                0,
+            );
+        // before the first step, after the witness commitments, after two
+        // rounds of each zero check and of the batch opening, and before the
+        // final opening
+        for steps in [
+            0,
+            num_witnesses,
+            num_witnesses + 3,
+            num_witnesses + nv + 7,
+            num_steps - nv + 1,
+            num_steps - 1,
+        ] {
+            let mut stepper = prove_in_steps(&pk, &circuit.public_inputs, &circuit.witnesses)?;
+            stepper.enable_checkpoints()?;
+            for _ in 0..steps {
+                stepper.step()?;
+            }
+            let mut checkpoint = vec![];
+            stepper.checkpoint(&mut checkpoint)?;
+            drop(stepper);
+
+            // a truncated checkpoint is rejected
+            assert!(ProverStepper::resume(&pk, &checkpoint[..checkpoint.len() - 1]).is_err());
+
+            let resumed = ProverStepper::resume(&pk, checkpoint.as_slice())?;
+            assert_eq!(resumed.num_steps(), steps);
+            assert_eq!(resumed.run()?, expected);
+        }
+
+        // bad path: the checkpoints are not enabled, or enabled too late
+        let mut stepper = prove_in_steps(&pk, &circuit.public_inputs, &circuit.witnesses)?;
+        assert!(stepper.checkpoint(vec![]).is_err());
+        stepper.step()?;
+        assert!(stepper.enable_checkpoints().is_err());
+        Ok(())
+    }
+    #[test]
+    fn test_prover_stepper_lookup() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let nv = 3;
+        let pcs_srs = MultilinearKzgPCS::<Bls12_381>::gen_srs_for_testing(&mut rng, nv)?;
+        let table: Vec<Fr> = [3u64, 5, 7, 11].into_iter().map(Fr::from).collect();
+        let index = HyperPlonkIndex {
+            params: HyperPlonkParams {
+                num_constraints: 1 << nv,
+                num_pub_input: 2,
+                gate_func: CustomizedGates::lookup_gate(0),
+                num_fixed_columns: 0,
+            },
+            permutation: identity_permutation(nv, 1),
+            selectors: vec![SelectorColumn(vec![Fr::zero(); 1 << nv])],
+            fixed_columns: vec![],
+            tables: vec![Arc::new(DenseMultilinearExtension::from_evaluations_vec(
+                2, table,
+            ))],
+        };
+        let (pk, vk) =
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, MultilinearKzgPCS<Bls12_381>>>::preprocess(
+                &index, &pcs_srs,
+            )?;
+        let witnesses = vec![WitnessColumn(
+            [3u64, 5, 5, 7, 11, 11, 11, 3]
+                .into_iter()
+                .map(Fr::from)
+                .collect(),
+        )];
+        let pi = witnesses[0].0[..2].to_vec();
+
+        let num_steps =
+            ProverStepper::<Bls12_381, MultilinearKzgPCS<Bls12_381>>::expected_num_steps(nv, 1, 1);
+        let mut stepper = prove_in_steps(&pk, &pi, &witnesses)?;
+        let proof = loop {
+            if let StepResult::Done(proof) = stepper.step()? {
+                break proof;
+            }
+        };
+        assert_eq!(stepper.num_steps(), num_steps);
+        assert!(proof.lookup_check_proof.is_some());
+        assert!(<PolyIOP<Fr> as HyperPlonkSNARK<
+            Bls12_381,
+            MultilinearKzgPCS<Bls12_381>,
+        >>::verify(&vk, &pi, &proof)?);
+
+        // the permutation check ends after 2 * nv + 6 steps; resume in
+        // between two lookup commitments and during the lookup sum check
+        for steps in [2 * nv + 8, 2 * nv + 12] {
+            let mut stepper = prove_in_steps(&pk, &pi, &witnesses)?;
+            stepper.enable_checkpoints()?;
+            for _ in 0..steps {
+                stepper.step()?;
+            }
+            let mut checkpoint = vec![];
+            stepper.checkpoint(&mut checkpoint)?;
+            let resumed = ProverStepper::resume(&pk, checkpoint.as_slice())?;
+            assert_eq!(resumed.run()?, proof);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,492 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! Verifying a proof while it is being read.
+//!
+//! [`verify_streaming`] reads a proof written by [`serialize_streaming`] and
+//! verifies it as it goes: every sum check round is checked as soon as its
+//! prover message has been read, and then dropped. Apart from the public
+//! input, the verifier only keeps one round message, the commitments and
+//! the data of the final PCS batch verification. An invalid round is
+//! rejected right away, before the rest of the proof is read.
+//!
+//! The streaming format has no length prefix, since all the lengths are
+//! derived from the verifying key. It consists of, in order and with
+//! compressed encoding:
+//! - the protocol version, on 2 bytes in little-endian;
+//! - the witness commitments;
+//! - the prover messages of the gate zero check;
+//! - the commitments to frac(x) and prod(x);
+//! - the prover messages of the permutation zero check;
+//! - if the gate has lookups, the commitments to the multiplicities m(x),
+//!   then to a(x) and b(x) for each lookup, and the prover messages of the
+//!   lookup sum check;
+//! - the opened evaluations;
+//! - the point and the prover messages of the batch opening sum check;
+//! - the opening proof of g'.
+
+use crate::{
+    errors::HyperPlonkErrors,
+    snark::{
+        check_evaluations, check_verifying_key, num_opened_evaluations, perm_check_aux_info,
+        zero_check_aux_info, IopClaims, LookupClaims, VerifierMode,
+    },
+    structs::{HyperPlonkProof, HyperPlonkVerifyingKey},
+    versions::{self, check_protocol_version},
+};
+use arithmetic::VPAuxInfo;
+use ark_ec::pairing::Pairing;
+use ark_ff::{PrimeField, Zero};
+use ark_poly::DenseMultilinearExtension;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{
+    end_timer,
+    io::{Read, Write},
+    start_timer,
+};
+use std::sync::Arc;
+use subroutines::{
+    pcs::prelude::{Commitment, PolynomialCommitmentScheme},
+    poly_iop::{
+        prelude::{
+            lookup_check_aux_info, prod_boundary_opening, IOPProof, IOPProverMessage,
+            LookupCheckSubClaim, SumCheckVerifierRounds, ZeroCheck, ZeroCheckVerifierRounds,
+        },
+        PolyIOP,
+    },
+    BatchProof,
+};
+use transcript::IOPTranscript;
+
+/// Number of evaluations in a prover message of the batch opening sum check,
+/// whose polynomial has degree 2.
+const BATCH_SUM_CHECK_MESSAGE_LEN: usize = 3;
+
+/// Write `proof` in the streaming format read by [`verify_streaming`].
+pub fn serialize_streaming<E, PCS, W>(
+    proof: &HyperPlonkProof<E, PolyIOP<E::ScalarField>, PCS>,
+    mut writer: W,
+) -> Result<(), HyperPlonkErrors>
+where
+    E: Pairing,
+    PCS: PolynomialCommitmentScheme<
+        E,
+        Polynomial = Arc<DenseMultilinearExtension<E::ScalarField>>,
+        Commitment = Commitment<E>,
+        BatchProof = BatchProof<E, PCS>,
+    >,
+    W: Write,
+{
+    proof.protocol_version.serialize_compressed(&mut writer)?;
+    for w_com in proof.witness_commits.iter() {
+        w_com.serialize_compressed(&mut writer)?;
+    }
+    write_messages(&proof.zero_check_proof.proofs, &mut writer)?;
+
+    proof
+        .perm_check_proof
+        .frac_comm
+        .serialize_compressed(&mut writer)?;
+    proof
+        .perm_check_proof
+        .prod_x_comm
+        .serialize_compressed(&mut writer)?;
+    write_messages(&proof.perm_check_proof.zero_check_proof.proofs, &mut writer)?;
+
+    if let Some(lookup_check_proof) = &proof.lookup_check_proof {
+        for m_com in lookup_check_proof.multiplicity_comms.iter() {
+            m_com.serialize_compressed(&mut writer)?;
+        }
+        for (a_com, b_com) in lookup_check_proof
+            .input_frac_comms
+            .iter()
+            .zip(lookup_check_proof.table_frac_comms.iter())
+        {
+            a_com.serialize_compressed(&mut writer)?;
+            b_com.serialize_compressed(&mut writer)?;
+        }
+        write_messages(&lookup_check_proof.sum_check_proof.proofs, &mut writer)?;
+    }
+
+    for eval in proof.batch_openings.f_i_eval_at_point_i.iter() {
+        eval.serialize_compressed(&mut writer)?;
+    }
+    let sum_check_proof = proof.batch_openings.sum_check_proof();
+    for x in sum_check_proof.point.iter() {
+        x.serialize_compressed(&mut writer)?;
+    }
+    write_messages(&sum_check_proof.proofs, &mut writer)?;
+    proof
+        .batch_openings
+        .g_prime_proof()
+        .serialize_compressed(&mut writer)?;
+    Ok(())
+}
+
+/// Verify a proof read from `reader` in the streaming format written by
+/// [`serialize_streaming`].
+///
+/// The result is the same as `HyperPlonkSNARK::verify` on the deserialized
+/// proof. The reader is not read further than the first round that fails.
+pub fn verify_streaming<E, PCS, R>(
+    vk: &HyperPlonkVerifyingKey<E, PCS>,
+    pub_input: &[E::ScalarField],
+    mut reader: R,
+) -> Result<bool, HyperPlonkErrors>
+where
+    E: Pairing,
+    PCS: PolynomialCommitmentScheme<
+        E,
+        Polynomial = Arc<DenseMultilinearExtension<E::ScalarField>>,
+        Point = Vec<E::ScalarField>,
+        Evaluation = E::ScalarField,
+        Commitment = Commitment<E>,
+        BatchProof = BatchProof<E, PCS>,
+    >,
+    R: Read,
+{
+    let start = start_timer!(|| "hyperplonk streaming verification");
+    check_verifying_key(vk, pub_input)?;
+    let num_vars = vk.params.num_variables();
+    let protocol_version: u16 = read(&mut reader)?;
+    check_protocol_version(vk, protocol_version)?;
+    let mut transcript = versions::transcript::<E::ScalarField>(protocol_version)?;
+
+    // =======================================================================
+    // 1. gate zero check
+    // =======================================================================
+    let step = start_timer!(|| "verify zero check");
+    let num_witnesses = vk.params.num_witness_columns();
+    let mut witness_commits = Vec::with_capacity(num_witnesses);
+    for _ in 0..num_witnesses {
+        let w_com: Commitment<E> = read(&mut reader)?;
+        transcript.append_serializable_element(b"w", &w_com)?;
+        witness_commits.push(w_com);
+    }
+    let zero_check_sub_claim =
+        verify_zero_check(&zero_check_aux_info(vk), &mut reader, &mut transcript)?;
+    end_timer!(step);
+
+    // =======================================================================
+    // 2. permutation check, with the transcript of `PermutationCheck::verify`
+    // =======================================================================
+    let step = start_timer!(|| "verify permutation check");
+    let beta = transcript.get_perm_check_challenge(b"beta")?;
+    let gamma = transcript.get_perm_check_challenge(b"gamma")?;
+    let frac_comm: Commitment<E> = read(&mut reader)?;
+    let prod_x_comm: Commitment<E> = read(&mut reader)?;
+    transcript.append_serializable_element(b"frac(x)", &frac_comm)?;
+    transcript.append_serializable_element(b"prod(x)", &prod_x_comm)?;
+    let alpha = transcript.get_perm_check_challenge(b"alpha")?;
+    let perm_zero_check_sub_claim =
+        verify_zero_check(&perm_check_aux_info(vk), &mut reader, &mut transcript)?;
+    end_timer!(step);
+
+    // =======================================================================
+    // 2'. lookup check, with the transcript of `LookupCheck::verify`
+    // =======================================================================
+    let num_lookups = vk.params.gate_func.lookups().len();
+    let lookup = if num_lookups == 0 {
+        None
+    } else {
+        let step = start_timer!(|| "verify lookup check");
+        let lookup = verify_lookup_check(vk, num_lookups, &mut reader, &mut transcript)?;
+        end_timer!(step);
+        Some(lookup)
+    };
+
+    // =======================================================================
+    // 3. check the subclaims against the evaluations
+    // =======================================================================
+    let evals = (0..num_opened_evaluations(vk))
+        .map(|_| read(&mut reader))
+        .collect::<Result<Vec<E::ScalarField>, _>>()?;
+    let claims = IopClaims {
+        zero_check_point: zero_check_sub_claim.point,
+        zero_check_eval: zero_check_sub_claim.expected_evaluation,
+        zero_check_r: zero_check_sub_claim.init_challenge,
+        prod_x_comm,
+        frac_comm,
+        perm_check_point: perm_zero_check_sub_claim.point,
+        perm_check_eval: perm_zero_check_sub_claim.expected_evaluation,
+        alpha,
+        beta,
+        gamma,
+        required_openings: vec![prod_boundary_opening(num_vars)],
+        lookup,
+    };
+    let (comms, points) = check_evaluations(
+        vk,
+        pub_input,
+        &witness_commits,
+        &evals,
+        claims,
+        &mut transcript,
+        VerifierMode::Verify,
+    )?;
+
+    // =======================================================================
+    // 4. PCS batch verification
+    //
+    // The batch opening sum check only has `num_vars` rounds of 3
+    // evaluations, so it is read entirely and verified by the PCS.
+    // =======================================================================
+    let step = start_timer!(|| "PCS batch verify");
+    let point = (0..num_vars)
+        .map(|_| read(&mut reader))
+        .collect::<Result<Vec<E::ScalarField>, _>>()?;
+    let proofs = (0..num_vars)
+        .map(|_| read_message(BATCH_SUM_CHECK_MESSAGE_LEN, &mut reader))
+        .collect::<Result<Vec<_>, _>>()?;
+    let g_prime_proof: PCS::Proof = read(&mut reader)?;
+    let batch_openings = BatchProof::new(IOPProof { point, proofs }, evals, g_prime_proof);
+    let res = PCS::batch_verify(
+        &vk.pcs_param,
+        &comms,
+        &points,
+        &batch_openings,
+        &mut transcript,
+    )?;
+    end_timer!(step);
+
+    end_timer!(start);
+    Ok(res)
+}
+
+/// Run the zero check verifier for `aux_info` on the prover messages read
+/// from `reader`, one round at a time.
+fn verify_zero_check<F: PrimeField, R: Read>(
+    aux_info: &VPAuxInfo<F>,
+    reader: &mut R,
+    transcript: &mut IOPTranscript<F>,
+) -> Result<<PolyIOP<F> as ZeroCheck<F>>::ZeroCheckSubClaim, HyperPlonkErrors> {
+    let mut verifier = ZeroCheckVerifierRounds::new(aux_info, transcript)?;
+    while !verifier.is_finished() {
+        let prover_msg = read_message(verifier.message_len(), reader)?;
+        verifier.verify_round(&prover_msg, transcript)?;
+    }
+    Ok(verifier.finish()?)
+}
+
+/// Read the commitments and the sum check of the `num_lookups` lookups of
+/// the gate of `vk` from `reader`, and verify the sum check one round at a
+/// time.
+fn verify_lookup_check<E, PCS, R>(
+    vk: &HyperPlonkVerifyingKey<E, PCS>,
+    num_lookups: usize,
+    reader: &mut R,
+    transcript: &mut IOPTranscript<E::ScalarField>,
+) -> Result<LookupClaims<E>, HyperPlonkErrors>
+where
+    E: Pairing,
+    PCS: PolynomialCommitmentScheme<E, Commitment = Commitment<E>>,
+    R: Read,
+{
+    let mut multiplicity_comms = Vec::with_capacity(num_lookups);
+    for _ in 0..num_lookups {
+        let m_com: Commitment<E> = read(reader)?;
+        transcript.append_serializable_element(b"m(x)", &m_com)?;
+        multiplicity_comms.push(m_com);
+    }
+    let gamma = transcript.get_lookup_challenges(b"gamma", 1)?[0];
+    let mut input_frac_comms = Vec::with_capacity(num_lookups);
+    let mut table_frac_comms = Vec::with_capacity(num_lookups);
+    for _ in 0..num_lookups {
+        let a_com: Commitment<E> = read(reader)?;
+        let b_com: Commitment<E> = read(reader)?;
+        transcript.append_serializable_element(b"a(x)", &a_com)?;
+        transcript.append_serializable_element(b"b(x)", &b_com)?;
+        input_frac_comms.push(a_com);
+        table_frac_comms.push(b_com);
+    }
+    let aux_info = lookup_check_aux_info(vk.params.num_variables());
+    let alpha = transcript.get_lookup_challenges(b"alpha", 1)?[0];
+    let r = transcript
+        .get_lookup_challenges(b"r", aux_info.num_variables)?
+        .into_iter()
+        .map(|c| c.0)
+        .collect();
+
+    let mut verifier = SumCheckVerifierRounds::new(E::ScalarField::zero(), &aux_info, transcript)?;
+    while !verifier.is_finished() {
+        let prover_msg = read_message(verifier.message_len(), reader)?;
+        verifier.verify_round(&prover_msg, transcript)?;
+    }
+    Ok(LookupClaims {
+        sub_claim: LookupCheckSubClaim {
+            sum_check_sub_claim: verifier.finish()?,
+            gamma,
+            alpha,
+            r,
+        },
+        multiplicity_comms,
+        input_frac_comms,
+        table_frac_comms,
+    })
+}
+
+fn read<T: CanonicalDeserialize, R: Read>(reader: &mut R) -> Result<T, HyperPlonkErrors> {
+    Ok(T::deserialize_compressed(reader)?)
+}
+
+fn read_message<F: PrimeField, R: Read>(
+    len: usize,
+    reader: &mut R,
+) -> Result<IOPProverMessage<F>, HyperPlonkErrors> {
+    let evaluations = (0..len)
+        .map(|_| read(reader))
+        .collect::<Result<Vec<F>, _>>()?;
+    Ok(IOPProverMessage { evaluations })
+}
+
+fn write_messages<F: PrimeField, W: Write>(
+    prover_msgs: &[IOPProverMessage<F>],
+    writer: &mut W,
+) -> Result<(), HyperPlonkErrors> {
+    for prover_msg in prover_msgs.iter() {
+        for eval in prover_msg.evaluations.iter() {
+            eval.serialize_compressed(&mut *writer)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        custom_gate::CustomizedGates, mock::MockCircuit, HyperPlonkSNARK, PROTOCOL_VERSION,
+    };
+    use ark_bls12_381::{Bls12_381, Fq, Fr, G1Affine};
+    use ark_std::{test_rng, One};
+    use subroutines::pcs::prelude::MultilinearKzgPCS;
+
+    type Kzg = MultilinearKzgPCS<Bls12_381>;
+
+    /// A reader returning at most one byte per call, that counts the bytes
+    /// read.
+    struct ByteReader<'a> {
+        bytes: &'a [u8],
+        pos: usize,
+    }
+
+    impl<'a> Read for ByteReader<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> ark_std::io::Result<usize> {
+            if buf.is_empty() || self.pos == self.bytes.len() {
+                return Ok(0);
+            }
+            buf[0] = self.bytes[self.pos];
+            self.pos += 1;
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn test_verify_streaming() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let nv = 5;
+        let pcs_srs = Kzg::gen_srs_for_testing(&mut rng, nv)?;
+
+        let gate = CustomizedGates::jellyfish_turbo_plonk_gate();
+        let circuit = MockCircuit::<Fr>::new(1 << nv, &gate);
+        let (pk, vk) =
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::preprocess(&circuit.index, &pcs_srs)?;
+        let proof = <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::prove(
+            &pk,
+            &circuit.public_inputs,
+            &circuit.witnesses,
+        )?;
+        let mut bytes = vec![];
+        serialize_streaming(&proof, &mut bytes)?;
+
+        // read one byte at a time
+        let mut reader = ByteReader {
+            bytes: &bytes,
+            pos: 0,
+        };
+        assert!(verify_streaming(&vk, &circuit.public_inputs, &mut reader)?);
+        assert_eq!(reader.pos, bytes.len());
+
+        // bad path: a truncated proof
+        assert!(verify_streaming(&vk, &circuit.public_inputs, &bytes[..bytes.len() - 1]).is_err());
+
+        // bad path: round 3 of the gate zero check is corrupted
+        let mut bad_proof = proof;
+        bad_proof.zero_check_proof.proofs[3].evaluations[0] += Fr::one();
+        let mut bad_bytes = vec![];
+        serialize_streaming(&bad_proof, &mut bad_bytes)?;
+        let mut reader = ByteReader {
+            bytes: &bad_bytes,
+            pos: 0,
+        };
+        assert!(verify_streaming(&vk, &circuit.public_inputs, &mut reader).is_err());
+        // the verifier stopped right after round 3
+        let field_size = Fr::one().compressed_size();
+        let round_end = proof_offset_after_zero_check_round(&vk, field_size, 3);
+        assert_eq!(reader.pos, round_end);
+        assert!(reader.pos < bad_bytes.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_streaming_bad_group_elements() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let nv = 4;
+        let pcs_srs = Kzg::gen_srs_for_testing(&mut rng, nv)?;
+
+        let gate = CustomizedGates::vanilla_plonk_gate();
+        let circuit = MockCircuit::<Fr>::new(1 << nv, &gate);
+        let (pk, vk) =
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::preprocess(&circuit.index, &pcs_srs)?;
+        let proof = <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::prove(
+            &pk,
+            &circuit.public_inputs,
+            &circuit.witnesses,
+        )?;
+
+        // a point on the curve outside the prime order subgroup, and a point
+        // whose compressed encoding is the x-coordinate of no point
+        let on_curve = |x: u64| G1Affine::get_point_from_x_unchecked(Fq::from(x), false);
+        let non_subgroup = (1u64..)
+            .filter_map(on_curve)
+            .find(|p| !p.is_in_correct_subgroup_assuming_on_curve())
+            .unwrap();
+        let off_curve_x = (1u64..).find(|&x| on_curve(x).is_none()).unwrap();
+        let off_curve = G1Affine::new_unchecked(Fq::from(off_curve_x), Fq::one());
+
+        for point in [non_subgroup, off_curve] {
+            let mut bad_proof = proof.clone();
+            bad_proof.witness_commits[1] = Commitment(point);
+            let mut bytes = vec![];
+            serialize_streaming(&bad_proof, &mut bytes)?;
+            assert!(verify_streaming(&vk, &circuit.public_inputs, &bytes[..]).is_err());
+
+            let mut bad_proof = proof.clone();
+            bad_proof.perm_check_proof.frac_comm = Commitment(point);
+            let mut bytes = vec![];
+            serialize_streaming(&bad_proof, &mut bytes)?;
+            assert!(verify_streaming(&vk, &circuit.public_inputs, &bytes[..]).is_err());
+        }
+
+        Ok(())
+    }
+
+    /// The offset in the streaming format of the end of round `round` of the
+    /// gate zero check.
+    fn proof_offset_after_zero_check_round(
+        vk: &HyperPlonkVerifyingKey<Bls12_381, Kzg>,
+        field_size: usize,
+        round: usize,
+    ) -> usize {
+        let commitment_size = Commitment::<Bls12_381>::default().compressed_size();
+        let message_len = vk.params.gate_func.degree() + 2;
+        let version_size = PROTOCOL_VERSION.compressed_size();
+        version_size
+            + vk.params.num_witness_columns() * commitment_size
+            + (round + 1) * message_len * field_size
+    }
+}
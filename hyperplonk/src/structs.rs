@@ -6,7 +6,13 @@
 
 //! Main module for the HyperPlonk PolyIOP.
 
-use crate::{custom_gate::CustomizedGates, prelude::HyperPlonkErrors, selectors::SelectorColumn};
+use crate::{
+    custom_gate::CustomizedGates,
+    prelude::HyperPlonkErrors,
+    selectors::{SelectorColumn, SelectorDescription},
+    utils::build_permutation_oracles,
+};
+use arithmetic::eq_eval;
 use ark_ec::pairing::Pairing;
 use ark_ff::PrimeField;
 use ark_poly::DenseMultilinearExtension;
@@ -14,14 +20,20 @@ use ark_std::log2;
 use std::sync::Arc;
 use subroutines::{
     pcs::PolynomialCommitmentScheme,
-    poly_iop::prelude::{PermutationCheck, ZeroCheck},
+    poly_iop::prelude::{LookupCheckProof, OpeningRole, PermutationCheck, ZeroCheck},
 };
 
 /// The proof for the HyperPlonk PolyIOP, consists of the following:
+///   - the version of the protocol the proof was generated with
 ///   - the commitments to all witness MLEs
 ///   - a batch opening to all the MLEs at certain index
 ///   - the zero-check proof for checking custom gate-satisfiability
 ///   - the permutation-check proof for checking the copy constraints
+///   - the lookup-check proof for checking the lookups, if the gate has any
+///
+/// The commitments to the preprocessed polynomials, i.e., the selectors, the
+/// permutations and the fixed columns, are not part of the proof: the
+/// verifier always takes them from the verifying key.
 #[derive(Clone, Debug, PartialEq)]
 pub struct HyperPlonkProof<E, PC, PCS>
 where
@@ -29,6 +41,8 @@ where
     PC: PermutationCheck<E, PCS>,
     PCS: PolynomialCommitmentScheme<E>,
 {
+    /// The version of the protocol the proof was generated with
+    pub protocol_version: u16,
     // PCS commit for witnesses
     pub witness_commits: Vec<PCS::Commitment>,
     pub batch_openings: PCS::BatchProof,
@@ -39,12 +53,15 @@ where
     pub zero_check_proof: <PC as ZeroCheck<E::ScalarField>>::ZeroCheckProof,
     // the permutation check proof for copy constraints
     pub perm_check_proof: PC::PermutationProof,
+    // the lookup check proof for the lookups of the gate, if any
+    pub lookup_check_proof: Option<LookupCheckProof<E, PCS>>,
 }
 
 /// The HyperPlonk instance parameters, consists of the following:
 ///   - the number of constraints
 ///   - number of public input columns
 ///   - the customized gate function
+///   - number of fixed columns wired by the permutation
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct HyperPlonkParams {
     /// the number of constraints
@@ -55,6 +72,11 @@ pub struct HyperPlonkParams {
     pub num_pub_input: usize,
     /// customized gate function
     pub gate_func: CustomizedGates,
+    /// number of fixed columns
+    // fixed columns are preprocessed constants that take part in the
+    // permutation argument after the witness columns, so that witness cells
+    // can be wired to circuit constants.
+    pub num_fixed_columns: usize,
 }
 
 impl HyperPlonkParams {
@@ -73,9 +95,15 @@ impl HyperPlonkParams {
         self.gate_func.num_witness_columns()
     }
 
+    /// number of columns in the permutation argument, i.e., the witness
+    /// columns followed by the fixed columns
+    pub fn num_permutation_columns(&self) -> usize {
+        self.num_witness_columns() + self.num_fixed_columns
+    }
+
     /// evaluate the identical polynomial
     pub fn eval_id_oracle<F: PrimeField>(&self, point: &[F]) -> Result<F, HyperPlonkErrors> {
-        let len = self.num_variables() + (log2(self.num_witness_columns()) as usize);
+        let len = self.num_variables() + (log2(self.num_permutation_columns()) as usize);
         if point.len() != len {
             return Err(HyperPlonkErrors::InvalidParameters(format!(
                 "ID oracle point length = {}, expected {}",
@@ -96,13 +124,18 @@ impl HyperPlonkParams {
 
 /// The HyperPlonk index, consists of the following:
 ///   - HyperPlonk parameters
-///   - the wire permutation
+///   - the wire permutation over the witness and fixed columns
 ///   - the selector vectors
+///   - the fixed column vectors
+///   - the lookup tables, each of which has at most as many variables as
+///     the circuit, and is repeated to fill the rows of the circuit
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct HyperPlonkIndex<F: PrimeField> {
     pub params: HyperPlonkParams,
     pub permutation: Vec<F>,
     pub selectors: Vec<SelectorColumn<F>>,
+    pub fixed_columns: Vec<SelectorColumn<F>>,
+    pub tables: Vec<Arc<DenseMultilinearExtension<F>>>,
 }
 
 impl<F: PrimeField> HyperPlonkIndex<F> {
@@ -120,41 +153,156 @@ impl<F: PrimeField> HyperPlonkIndex<F> {
     pub fn num_witness_columns(&self) -> usize {
         self.params.num_witness_columns()
     }
+
+    /// number of fixed columns
+    pub fn num_fixed_columns(&self) -> usize {
+        self.params.num_fixed_columns
+    }
+
+    /// number of columns in the permutation argument
+    pub fn num_permutation_columns(&self) -> usize {
+        self.params.num_permutation_columns()
+    }
 }
 
 /// The HyperPlonk proving key, consists of the following:
 ///   - the hyperplonk instance parameters
 ///   - the preprocessed polynomials output by the indexer
-///   - the commitment to the selectors and permutations
+///   - the commitment to the selectors, permutations, fixed columns and
+///     lookup tables
 ///   - the parameters for polynomial commitment
+///
+/// All the large components are reference counted: cloning the key is cheap,
+/// and proving only borrows them. The key can therefore be shared by several
+/// threads proving concurrently, e.g., behind an `Arc`.
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct HyperPlonkProvingKey<E: Pairing, PCS: PolynomialCommitmentScheme<E>> {
     /// Hyperplonk instance parameters
     pub params: HyperPlonkParams,
-    /// The preprocessed permutation polynomials
-    pub permutation_oracles: Vec<Arc<DenseMultilinearExtension<E::ScalarField>>>,
+    /// The wire permutation: the index of the cell each cell is mapped to,
+    /// column after column. The permutation polynomials are built from it
+    /// by `permutation_oracles`.
+    pub permutation: Arc<Vec<u64>>,
+    /// The permutation polynomials, if they have been cached with
+    /// `cache_permutation_oracles`
+    pub permutation_oracles_cache: Option<Vec<Arc<DenseMultilinearExtension<E::ScalarField>>>>,
     /// The preprocessed selector polynomials
     pub selector_oracles: Vec<Arc<DenseMultilinearExtension<E::ScalarField>>>,
-    /// Commitments to the preprocessed selector polynomials
-    pub selector_commitments: Vec<PCS::Commitment>,
+    /// How the verifier evaluates each selector polynomial: against its
+    /// commitment, or from a compact description
+    pub selectors: Vec<SelectorDescription<E::ScalarField, PCS::Commitment>>,
     /// Commitments to the preprocessed permutation polynomials
     pub permutation_commitments: Vec<PCS::Commitment>,
+    /// The preprocessed fixed polynomials
+    pub fixed_oracles: Vec<Arc<DenseMultilinearExtension<E::ScalarField>>>,
+    /// Commitments to the preprocessed fixed polynomials
+    pub fixed_commitments: Vec<PCS::Commitment>,
+    /// The lookup tables, repeated to the number of variables of the circuit
+    pub table_oracles: Vec<Arc<DenseMultilinearExtension<E::ScalarField>>>,
+    /// Commitments to the lookup tables
+    pub table_commitments: Vec<PCS::Commitment>,
     /// The parameters for PCS commitment
-    pub pcs_param: PCS::ProverParam,
+    pub pcs_param: Arc<PCS::ProverParam>,
+}
+
+impl<E: Pairing, PCS: PolynomialCommitmentScheme<E>> HyperPlonkProvingKey<E, PCS> {
+    /// The permutation polynomials: the cached ones if any, otherwise they
+    /// are built from the compact permutation.
+    pub fn permutation_oracles(&self) -> Vec<Arc<DenseMultilinearExtension<E::ScalarField>>> {
+        match &self.permutation_oracles_cache {
+            Some(oracles) => oracles.clone(),
+            None => build_permutation_oracles(self.params.num_variables(), &self.permutation),
+        }
+    }
+
+    /// Build the permutation polynomials once and keep them in the key, so
+    /// that the proofs do not build them again. This trades the memory of
+    /// the tables for the time to build them at every proof.
+    pub fn cache_permutation_oracles(&mut self) {
+        self.permutation_oracles_cache = Some(build_permutation_oracles(
+            self.params.num_variables(),
+            &self.permutation,
+        ));
+    }
 }
 
 /// The HyperPlonk verifying key, consists of the following:
+///   - the version of the protocol
 ///   - the hyperplonk instance parameters
 ///   - the commitments to the preprocessed polynomials output by the indexer
 ///   - the parameters for polynomial commitment
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct HyperPlonkVerifyingKey<E: Pairing, PCS: PolynomialCommitmentScheme<E>> {
+    /// The version of the protocol the key was generated for: the verifier
+    /// accepts the proofs of the supported versions up to this one
+    pub protocol_version: u16,
     /// Hyperplonk instance parameters
     pub params: HyperPlonkParams,
     /// The parameters for PCS commitment
     pub pcs_param: PCS::VerifierParam,
-    /// A commitment to the preprocessed selector polynomials
-    pub selector_commitments: Vec<PCS::Commitment>,
+    /// The preprocessed selector polynomials: their commitments, or compact
+    /// descriptions the verifier evaluates itself
+    pub selectors: Vec<SelectorDescription<E::ScalarField, PCS::Commitment>>,
     /// Permutation oracles' commitments
     pub perm_commitments: Vec<PCS::Commitment>,
+    /// Commitments to the preprocessed fixed polynomials
+    pub fixed_commitments: Vec<PCS::Commitment>,
+    /// Commitments to the lookup tables
+    pub table_commitments: Vec<PCS::Commitment>,
+}
+
+impl<E: Pairing, PCS: PolynomialCommitmentScheme<E>> HyperPlonkVerifyingKey<E, PCS> {
+    /// The expected evaluation of `f(x) * eq(x, r)` at `point`, where `f` is
+    /// the gate identity and `r` is the zero check challenge
+    /// `zero_check_r`, computed from the evaluation `claims` at `point`.
+    ///
+    /// The evaluations of the witnesses and of the committed selectors are
+    /// looked up in `claims` by their `OpeningRole::Witness` and
+    /// `OpeningRole::Selector` roles; the other selectors are evaluated from
+    /// their description. A missing claim is an error.
+    pub fn expected_gate_evaluation(
+        &self,
+        claims: &[EvaluationClaim<E::ScalarField>],
+        zero_check_r: &[E::ScalarField],
+        point: &[E::ScalarField],
+    ) -> Result<E::ScalarField, HyperPlonkErrors> {
+        let lookup = |role: OpeningRole| {
+            claims
+                .iter()
+                .find(|claim| claim.role == role && claim.point == point)
+                .map(|claim| claim.evaluation)
+                .ok_or_else(|| {
+                    HyperPlonkErrors::InvalidProof(format!("missing evaluation claim {:?}", role))
+                })
+        };
+        let selector_evals = self
+            .selectors
+            .iter()
+            .enumerate()
+            .map(|(i, selector)| match selector.evaluate(point) {
+                Some(eval) => Ok(eval),
+                None => lookup(OpeningRole::Selector(i)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let witness_evals = (0..self.params.num_witness_columns())
+            .map(|i| lookup(OpeningRole::Witness(i)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let gate_eval = self
+            .params
+            .gate_func
+            .evaluate(&selector_evals, &witness_evals)?;
+        Ok(gate_eval * eq_eval(point, zero_check_r)?)
+    }
+}
+
+/// A claim that the polynomial of `role` evaluates to `evaluation` at
+/// `point`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EvaluationClaim<F: PrimeField> {
+    /// the polynomial the claim is on
+    pub role: OpeningRole,
+    /// the evaluation point
+    pub point: Vec<F>,
+    /// the claimed evaluation
+    pub evaluation: F,
 }
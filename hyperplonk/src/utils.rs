@@ -12,9 +12,16 @@ use arithmetic::{evaluate_opt, VirtualPolynomial};
 use ark_ec::pairing::Pairing;
 use ark_ff::PrimeField;
 use ark_poly::DenseMultilinearExtension;
-use std::{borrow::Borrow, sync::Arc};
-use subroutines::pcs::{prelude::Commitment, PolynomialCommitmentScheme};
-use transcript::IOPTranscript;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use ark_std::cfg_chunks;
+#[cfg(feature = "parallel")]
+use rayon::{iter::ParallelIterator, slice::ParallelSlice};
+use std::sync::Arc;
+use subroutines::pcs::{
+    prelude::{Commitment, MultiOpenRounds},
+    PolynomialCommitmentScheme,
+};
+use transcript::{IOPTranscript, PermCheckChallenge};
 
 /// An accumulator structure that holds a polynomial and
 /// its opening points
@@ -73,21 +80,95 @@ where
         self.commitments.push(*commit);
     }
 
-    /// Batch open all the points over a merged polynomial.
-    /// A simple wrapper of PCS::multi_open
-    pub(super) fn multi_open(
+    /// Initialize the batch opening of all the points over a merged
+    /// polynomial, which [`MultiOpenRounds`] then proves round by round.
+    pub(super) fn multi_open_rounds(
         &self,
-        prover_param: impl Borrow<PCS::ProverParam>,
         transcript: &mut IOPTranscript<E::ScalarField>,
-    ) -> Result<PCS::BatchProof, HyperPlonkErrors> {
-        Ok(PCS::multi_open(
-            prover_param.borrow(),
-            self.polynomials.as_ref(),
-            self.points.as_ref(),
-            self.evals.as_ref(),
+    ) -> Result<MultiOpenRounds<E::ScalarField>, HyperPlonkErrors> {
+        Ok(MultiOpenRounds::new(
+            &self.polynomials,
+            &self.points,
+            &self.evals,
             transcript,
         )?)
     }
+
+    /// Write the accumulator. A polynomial inserted at several points is
+    /// written once.
+    pub(super) fn write<W: Write>(&self, mut writer: W) -> Result<(), HyperPlonkErrors> {
+        let mut distinct: Vec<PCS::Polynomial> = vec![];
+        let mut indices = Vec::with_capacity(self.polynomials.len());
+        for poly in self.polynomials.iter() {
+            match distinct.iter().position(|p| Arc::ptr_eq(p, poly)) {
+                Some(i) => indices.push(i),
+                None => {
+                    indices.push(distinct.len());
+                    distinct.push(poly.clone());
+                },
+            }
+        }
+        self.num_var.serialize_compressed(&mut writer)?;
+        write_mles(&distinct, &mut writer)?;
+        indices.serialize_compressed(&mut writer)?;
+        self.commitments.serialize_compressed(&mut writer)?;
+        self.points.serialize_compressed(&mut writer)?;
+        self.evals.serialize_compressed(&mut writer)?;
+        Ok(())
+    }
+
+    /// Read an accumulator written by [`Self::write`].
+    pub(super) fn read<R: Read>(mut reader: R) -> Result<Self, HyperPlonkErrors> {
+        let num_var = usize::deserialize_compressed(&mut reader)?;
+        let distinct = read_mles(&mut reader, num_var)?;
+        let indices = Vec::<usize>::deserialize_compressed(&mut reader)?;
+        let commitments = Vec::deserialize_compressed(&mut reader)?;
+        let points = Vec::<Vec<E::ScalarField>>::deserialize_compressed(&mut reader)?;
+        let evals = Vec::deserialize_compressed(&mut reader)?;
+        if indices.iter().any(|&i| i >= distinct.len())
+            || commitments.len() != indices.len()
+            || points.len() != indices.len()
+            || evals.len() != indices.len()
+            || points.iter().any(|point| point.len() != num_var)
+        {
+            return Err(SerializationError::InvalidData.into());
+        }
+        Ok(Self {
+            num_var,
+            polynomials: indices.iter().map(|&i| distinct[i].clone()).collect(),
+            commitments,
+            points,
+            evals,
+        })
+    }
+}
+
+/// Write a list of MLEs.
+pub(crate) fn write_mles<F: PrimeField, W: Write>(
+    mles: &[Arc<DenseMultilinearExtension<F>>],
+    mut writer: W,
+) -> Result<(), HyperPlonkErrors> {
+    mles.len().serialize_compressed(&mut writer)?;
+    for mle in mles.iter() {
+        mle.as_ref().serialize_compressed(&mut writer)?;
+    }
+    Ok(())
+}
+
+/// Read a list of MLEs written by [`write_mles`], each of which must have
+/// `num_vars` variables.
+pub(crate) fn read_mles<F: PrimeField, R: Read>(
+    reader: R,
+    num_vars: usize,
+) -> Result<Vec<Arc<DenseMultilinearExtension<F>>>, HyperPlonkErrors> {
+    let mles = Vec::<DenseMultilinearExtension<F>>::deserialize_compressed(reader)?;
+    if mles
+        .iter()
+        .any(|mle| mle.num_vars != num_vars || mle.evaluations.len() != 1 << num_vars)
+    {
+        return Err(SerializationError::InvalidData.into());
+    }
+    Ok(mles.into_iter().map(Arc::new).collect())
 }
 
 /// Build MLE from matrix of witnesses.
@@ -119,6 +200,59 @@ macro_rules! build_mle {
     }};
 }
 
+/// Sample the point `r_pi` at which the public input is opened.
+pub(crate) fn pub_input_challenge<F: PrimeField>(
+    transcript: &mut IOPTranscript<F>,
+    ell: usize,
+) -> Result<Vec<F>, HyperPlonkErrors> {
+    Ok(transcript
+        .get_opening_challenges(b"r_pi", ell)?
+        .into_iter()
+        .map(|r| r.0)
+        .collect())
+}
+
+/// The compact form of the permutation polynomials `oracles`: the index of
+/// the cell each cell is mapped to, column after column.
+pub(crate) fn compact_permutation<F: PrimeField>(
+    oracles: &[Arc<DenseMultilinearExtension<F>>],
+) -> Result<Vec<u64>, HyperPlonkErrors> {
+    oracles
+        .iter()
+        .flat_map(|oracle| oracle.evaluations.iter())
+        .map(cell_index)
+        .collect()
+}
+
+/// The cell index encoded by the permutation value `value`.
+pub(crate) fn cell_index<F: PrimeField>(value: &F) -> Result<u64, HyperPlonkErrors> {
+    let bigint = value.into_bigint();
+    let limbs = bigint.as_ref();
+    if limbs[1..].iter().any(|&limb| limb != 0) {
+        return Err(HyperPlonkErrors::InvalidParameters(format!(
+            "Permutation value {} is not a cell index",
+            value
+        )));
+    }
+    Ok(limbs[0])
+}
+
+/// Build the permutation polynomials with `num_vars` variables from the
+/// compact `permutation`, one column at a time in parallel.
+pub(crate) fn build_permutation_oracles<F: PrimeField>(
+    num_vars: usize,
+    permutation: &[u64],
+) -> Vec<Arc<DenseMultilinearExtension<F>>> {
+    cfg_chunks!(permutation, 1 << num_vars)
+        .map(|chunk| {
+            Arc::new(DenseMultilinearExtension::from_evaluations_vec(
+                num_vars,
+                chunk.iter().map(|&index| F::from(index)).collect(),
+            ))
+        })
+        .collect()
+}
+
 /// Sanity-check for HyperPlonk SNARK proving
 pub(crate) fn prover_sanity_check<F: PrimeField>(
     params: &HyperPlonkParams,
@@ -150,6 +284,15 @@ pub(crate) fn prover_sanity_check<F: PrimeField>(
         )));
     }
 
+    // number of witness columns
+    if witnesses.len() != params.num_witness_columns() {
+        return Err(HyperPlonkErrors::InvalidProver(format!(
+            "Number of witness columns is not correct: got {}, expect {}",
+            witnesses.len(),
+            params.num_witness_columns()
+        )));
+    }
+
     // witnesses length
     for (i, w) in witnesses.iter().enumerate() {
         if w.0.len() != params.num_constraints {
@@ -229,30 +372,6 @@ pub(crate) fn build_f<F: PrimeField>(
     Ok(res)
 }
 
-pub(crate) fn eval_f<F: PrimeField>(
-    gates: &CustomizedGates,
-    selector_evals: &[F],
-    witness_evals: &[F],
-) -> Result<F, HyperPlonkErrors> {
-    let mut res = F::zero();
-    for (coeff, selector, witnesses) in gates.gates.iter() {
-        let mut cur_value = if *coeff < 0 {
-            -F::from(-*coeff as u64)
-        } else {
-            F::from(*coeff as u64)
-        };
-        cur_value *= match selector {
-            Some(s) => selector_evals[*s],
-            None => F::one(),
-        };
-        for &witness in witnesses.iter() {
-            cur_value *= witness_evals[witness]
-        }
-        res += cur_value;
-    }
-    Ok(res)
-}
-
 // check perm check subclaim:
 // proof.witness_perm_check_eval ?= perm_check_sub_claim.expected_eval
 // Q(x) := prod(x) - p1(x) * p2(x)
@@ -272,11 +391,12 @@ pub(crate) fn eval_perm_gate<F: PrimeField>(
     witness_perm_evals: &[F],
     id_evals: &[F],
     perm_evals: &[F],
-    alpha: F,
-    beta: F,
-    gamma: F,
+    alpha: PermCheckChallenge<F>,
+    beta: PermCheckChallenge<F>,
+    gamma: PermCheckChallenge<F>,
     x1: F,
 ) -> Result<F, HyperPlonkErrors> {
+    let (alpha, beta, gamma) = (alpha.0, beta.0, gamma.0);
     let p1_eval = frac_evals[1] + x1 * (prod_evals[1] - frac_evals[1]);
     let p2_eval = frac_evals[2] + x1 * (prod_evals[2] - frac_evals[2]);
     let mut f_prod_eval = F::one();
@@ -339,8 +459,14 @@ mod test {
         // ]
         let gates = CustomizedGates {
             gates: vec![(1, Some(0), vec![0, 0, 0, 0, 0]), (-1, None, vec![1])],
+            lookups: vec![],
         };
-        let f = build_f(&gates, num_vars, &[ql.clone()], &[w1.clone(), w2.clone()])?;
+        let f = build_f(
+            &gates,
+            num_vars,
+            std::slice::from_ref(&ql),
+            &[w1.clone(), w2.clone()],
+        )?;
 
         // Sanity check on build_f
         // f(0, 0) = 0
@@ -352,12 +478,12 @@ mod test {
         // f(1, 1) = 5 * 2^5 + (-1) * 2 = 158
         assert_eq!(f.evaluate(&[F::one(), F::one()])?, F::from(158u64));
 
-        // test eval_f
+        // test CustomizedGates::evaluate
         {
             let point = [F::zero(), F::zero()];
             let selector_evals = ql.evaluate(&point).unwrap();
             let witness_evals = [w1.evaluate(&point).unwrap(), w2.evaluate(&point).unwrap()];
-            let eval_f = eval_f(&gates, &[selector_evals], &witness_evals)?;
+            let eval_f = gates.evaluate(&[selector_evals], &witness_evals)?;
             // f(0, 0) = 0
             assert_eq!(eval_f, F::zero());
         }
@@ -365,7 +491,7 @@ mod test {
             let point = [F::zero(), F::one()];
             let selector_evals = ql.evaluate(&point).unwrap();
             let witness_evals = [w1.evaluate(&point).unwrap(), w2.evaluate(&point).unwrap()];
-            let eval_f = eval_f(&gates, &[selector_evals], &witness_evals)?;
+            let eval_f = gates.evaluate(&[selector_evals], &witness_evals)?;
             // f(0, 1) = 2 * 0^5 + (-1) * 1 = -1
             assert_eq!(eval_f, -F::one());
         }
@@ -373,7 +499,7 @@ mod test {
             let point = [F::one(), F::zero()];
             let selector_evals = ql.evaluate(&point).unwrap();
             let witness_evals = [w1.evaluate(&point).unwrap(), w2.evaluate(&point).unwrap()];
-            let eval_f = eval_f(&gates, &[selector_evals], &witness_evals)?;
+            let eval_f = gates.evaluate(&[selector_evals], &witness_evals)?;
             // f(1, 0) = 0 * 1^5 + (-1) * 1 = -1
             assert_eq!(eval_f, -F::one());
         }
@@ -381,7 +507,7 @@ mod test {
             let point = [F::one(), F::one()];
             let selector_evals = ql.evaluate(&point).unwrap();
             let witness_evals = [w1.evaluate(&point).unwrap(), w2.evaluate(&point).unwrap()];
-            let eval_f = eval_f(&gates, &[selector_evals], &witness_evals)?;
+            let eval_f = gates.evaluate(&[selector_evals], &witness_evals)?;
             // f(1, 1) = 5 * 2^5 + (-1) * 2 = 158
             assert_eq!(eval_f, F::from(158u64));
         }
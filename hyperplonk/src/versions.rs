@@ -0,0 +1,319 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! Versions of the HyperPlonk protocol.
+//!
+//! A proof records the version of the protocol it was generated with, and
+//! the verifier runs the transcript of that version. Each supported version
+//! has its own module below, which is frozen once the version has shipped:
+//! a change of the transcript or of its labels is a new version, in a new
+//! module.
+//!
+//! The preprocessing is the same in every version, so that a verifying key
+//! accepts the proofs of every supported version up to its own.
+
+use crate::{errors::HyperPlonkErrors, structs::HyperPlonkVerifyingKey};
+use ark_ec::pairing::Pairing;
+use ark_ff::PrimeField;
+use subroutines::pcs::PolynomialCommitmentScheme;
+use transcript::IOPTranscript;
+
+/// The version of the protocol of the keys and proofs generated by this
+/// library.
+pub const PROTOCOL_VERSION: u16 = v2::VERSION;
+
+/// The versions of the protocol whose proofs can be verified, oldest first.
+pub const SUPPORTED_VERSIONS: [u16; 2] = [v1::VERSION, v2::VERSION];
+
+/// The label of the HyperPlonk transcripts, which Merlin absorbs as the
+/// domain separator.
+pub(crate) const TRANSCRIPT_LABEL: &[u8] = b"hyperplonk";
+
+/// A new HyperPlonk transcript for the protocol `version`.
+pub(crate) fn transcript<F: PrimeField>(
+    version: u16,
+) -> Result<IOPTranscript<F>, HyperPlonkErrors> {
+    new_transcript(version, false)
+}
+
+/// A new HyperPlonk transcript for the protocol `version` that records its
+/// operations from its creation on, except for the label.
+pub(crate) fn recorded_transcript<F: PrimeField>(
+    version: u16,
+) -> Result<IOPTranscript<F>, HyperPlonkErrors> {
+    new_transcript(version, true)
+}
+
+fn new_transcript<F: PrimeField>(
+    version: u16,
+    record: bool,
+) -> Result<IOPTranscript<F>, HyperPlonkErrors> {
+    match version {
+        v1::VERSION => v1::transcript(record),
+        v2::VERSION => v2::transcript(record),
+        _ => Err(HyperPlonkErrors::UnsupportedVersion {
+            proof: version,
+            supported: SUPPORTED_VERSIONS.to_vec(),
+        }),
+    }
+}
+
+/// Check that a proof of the protocol `version` can be verified with `vk`.
+pub(crate) fn check_protocol_version<E, PCS>(
+    vk: &HyperPlonkVerifyingKey<E, PCS>,
+    version: u16,
+) -> Result<(), HyperPlonkErrors>
+where
+    E: Pairing,
+    PCS: PolynomialCommitmentScheme<E>,
+{
+    if !SUPPORTED_VERSIONS.contains(&version) {
+        return Err(HyperPlonkErrors::UnsupportedVersion {
+            proof: version,
+            supported: SUPPORTED_VERSIONS.to_vec(),
+        });
+    }
+    if !SUPPORTED_VERSIONS.contains(&vk.protocol_version) {
+        return Err(HyperPlonkErrors::InvalidVerifier(format!(
+            "Verifying key has unsupported protocol version {}",
+            vk.protocol_version
+        )));
+    }
+    if version > vk.protocol_version {
+        return Err(HyperPlonkErrors::InvalidProof(format!(
+            "Proof has protocol version {}, newer than the verifying key version {}",
+            version, vk.protocol_version
+        )));
+    }
+    Ok(())
+}
+
+/// Version 1, the protocol of the proofs generated before the versions:
+/// the challenges of the sub-protocols are derived without tags, the
+/// version is not absorbed, and the batch opening batches every claim
+/// without absorbing the evaluations, see [`IOPTranscript::is_legacy`].
+mod v1 {
+    use super::*;
+
+    pub(super) const VERSION: u16 = 1;
+
+    pub(super) fn transcript<F: PrimeField>(
+        record: bool,
+    ) -> Result<IOPTranscript<F>, HyperPlonkErrors> {
+        let mut transcript = IOPTranscript::new_untagged(TRANSCRIPT_LABEL);
+        if record {
+            transcript.start_recording();
+        }
+        Ok(transcript)
+    }
+}
+
+/// Version 2: the version is absorbed as a single byte at the beginning of
+/// the transcript, and the tag of the sub-protocol before each challenge of
+/// the zero check, the permutation check and the opening. The batch opening
+/// merges the identical claims and absorbs the evaluations of the others
+/// before its challenges.
+mod v2 {
+    use super::*;
+
+    pub(super) const VERSION: u16 = 2;
+
+    pub(super) fn transcript<F: PrimeField>(
+        record: bool,
+    ) -> Result<IOPTranscript<F>, HyperPlonkErrors> {
+        let mut transcript = IOPTranscript::new(TRANSCRIPT_LABEL);
+        if record {
+            transcript.start_recording();
+        }
+        transcript.append_message(b"protocol version", &[VERSION as u8])?;
+        Ok(transcript)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        custom_gate::CustomizedGates,
+        mock::MockCircuit,
+        stepper::prove_in_steps,
+        streaming::{serialize_streaming, verify_streaming},
+        structs::HyperPlonkProof,
+        HyperPlonkSNARK,
+    };
+    use crate::{selectors::SelectorDescription, structs::HyperPlonkParams};
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use ark_std::test_rng;
+    use subroutines::{
+        pcs::prelude::{BatchProof, Commitment, MultilinearKzgPCS},
+        poly_iop::{
+            prelude::{IOPProof, IOPProverMessage, ProductCheckProof},
+            PolyIOP,
+        },
+    };
+
+    type Kzg = MultilinearKzgPCS<Bls12_381>;
+
+    const GOLDEN_V1: &str = include_str!("../test_vectors/proof_v1.txt");
+
+    /// The value `name` of the golden version 1 proof.
+    fn golden_value(name: &str) -> &'static str {
+        GOLDEN_V1
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .find_map(|line| line.strip_prefix(name)?.trim().strip_prefix('='))
+            .map(str::trim)
+            .expect("missing golden value")
+    }
+
+    /// Deserialize the value `name` of the golden version 1 proof.
+    fn golden<T: CanonicalDeserialize>(name: &str) -> T {
+        let hex = golden_value(name);
+        let bytes: Vec<u8> = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).expect("invalid hex"))
+            .collect();
+        T::deserialize_compressed(&bytes[..]).expect("invalid golden value")
+    }
+
+    /// Deserialize the sum check proof `name` of the golden version 1 proof.
+    fn golden_sum_check(name: &str) -> IOPProof<Fr> {
+        let (point, messages): (Vec<Fr>, Vec<Vec<Fr>>) = golden(name);
+        IOPProof {
+            point,
+            proofs: messages
+                .into_iter()
+                .map(|evaluations| IOPProverMessage { evaluations })
+                .collect(),
+        }
+    }
+
+    fn assert_unsupported(res: Result<bool, HyperPlonkErrors>, version: u16) {
+        match res {
+            Err(HyperPlonkErrors::UnsupportedVersion { proof, supported }) => {
+                assert_eq!(proof, version);
+                assert_eq!(supported, SUPPORTED_VERSIONS.to_vec());
+            },
+            res => panic!("expected an unsupported version error, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_golden_v1_proof() -> Result<(), HyperPlonkErrors> {
+        let selector_commitments: Vec<Commitment<Bls12_381>> = golden("selector_commitments");
+        let vk = HyperPlonkVerifyingKey::<Bls12_381, Kzg> {
+            protocol_version: PROTOCOL_VERSION,
+            params: HyperPlonkParams {
+                num_constraints: golden_value("num_constraints").parse().unwrap(),
+                num_pub_input: golden_value("num_pub_input").parse().unwrap(),
+                gate_func: CustomizedGates::vanilla_plonk_gate(),
+                num_fixed_columns: 0,
+            },
+            pcs_param: golden("pcs_param"),
+            selectors: selector_commitments
+                .into_iter()
+                .map(SelectorDescription::Committed)
+                .collect(),
+            perm_commitments: golden("perm_commitments"),
+            fixed_commitments: vec![],
+            table_commitments: vec![],
+        };
+        let public_inputs: Vec<Fr> = golden("public_inputs");
+        let proof = HyperPlonkProof::<Bls12_381, PolyIOP<Fr>, Kzg> {
+            protocol_version: v1::VERSION,
+            witness_commits: golden("witness_commits"),
+            batch_openings: BatchProof::new(
+                golden_sum_check("batch_sum_check_proof"),
+                golden("batch_evals"),
+                golden("batch_g_prime_proof"),
+            ),
+            zero_check_proof: golden_sum_check("zero_check_proof"),
+            perm_check_proof: ProductCheckProof {
+                zero_check_proof: golden_sum_check("perm_zero_check_proof"),
+                prod_x_comm: golden("prod_x_comm"),
+                frac_comm: golden("frac_comm"),
+            },
+            lookup_check_proof: None,
+        };
+
+        let verify = |proof: &HyperPlonkProof<Bls12_381, PolyIOP<Fr>, Kzg>| {
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::verify(&vk, &public_inputs, proof)
+        };
+        assert!(verify(&proof)?);
+
+        // the proof only verifies with the version 1 transcript
+        let mut relabelled = proof.clone();
+        relabelled.protocol_version = v2::VERSION;
+        assert!(!matches!(verify(&relabelled), Ok(true)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_protocol_versions() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let nv = 4;
+        let pcs_srs = Kzg::gen_srs_for_testing(&mut rng, nv)?;
+        let gate = CustomizedGates::vanilla_plonk_gate();
+        let circuit = MockCircuit::<Fr>::new(1 << nv, &gate);
+        let (pk, mut vk) =
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::preprocess(&circuit.index, &pcs_srs)?;
+        assert_eq!(vk.protocol_version, PROTOCOL_VERSION);
+        let verify = |vk: &HyperPlonkVerifyingKey<Bls12_381, Kzg>,
+                      proof: &HyperPlonkProof<Bls12_381, PolyIOP<Fr>, Kzg>| {
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::verify(
+                vk,
+                &circuit.public_inputs,
+                proof,
+            )
+        };
+
+        let proof = <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::prove(
+            &pk,
+            &circuit.public_inputs,
+            &circuit.witnesses,
+        )?;
+        assert_eq!(proof.protocol_version, PROTOCOL_VERSION);
+        assert!(verify(&vk, &proof)?);
+
+        // a version 1 proof verifies through the version 1 transcript
+        let mut stepper = prove_in_steps(&pk, &circuit.public_inputs, &circuit.witnesses)?;
+        stepper.set_protocol_version(v1::VERSION)?;
+        let v1_proof = stepper.run()?;
+        assert_eq!(v1_proof.protocol_version, v1::VERSION);
+        assert!(verify(&vk, &v1_proof)?);
+        let mut bytes = vec![];
+        serialize_streaming(&v1_proof, &mut bytes)?;
+        assert!(verify_streaming(&vk, &circuit.public_inputs, &bytes[..])?);
+
+        // the versions have different transcripts
+        assert_ne!(v1_proof.zero_check_proof, proof.zero_check_proof);
+        let mut relabelled = v1_proof.clone();
+        relabelled.protocol_version = v2::VERSION;
+        assert!(!matches!(verify(&vk, &relabelled), Ok(true)));
+
+        // an unknown version is rejected, by the prover and the verifier
+        let mut stepper = prove_in_steps(&pk, &circuit.public_inputs, &circuit.witnesses)?;
+        assert!(stepper.set_protocol_version(99).is_err());
+        let mut v99_proof = proof.clone();
+        v99_proof.protocol_version = 99;
+        assert_unsupported(verify(&vk, &v99_proof), 99);
+        let mut bytes = vec![];
+        serialize_streaming(&v99_proof, &mut bytes)?;
+        assert_unsupported(
+            verify_streaming(&vk, &circuit.public_inputs, &bytes[..]),
+            99,
+        );
+        // the version is the first field of the streaming format
+        assert_eq!(bytes[..99u16.compressed_size()], 99u16.to_le_bytes());
+
+        // a key of version 1 does not accept the proofs of version 2
+        vk.protocol_version = v1::VERSION;
+        assert!(verify(&vk, &v1_proof)?);
+        assert!(verify(&vk, &proof).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,301 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! Compressed verifying keys.
+//!
+//! Circuits built from templates often have selector, permutation, fixed or
+//! table columns with the same values, and therefore the same commitments. A
+//! [`CompressedVerifierKey`] stores each distinct commitment once, and refers
+//! to it by index. Selectors that are zero everywhere are not committed in
+//! the first place: the preprocessing describes them as empty sparse
+//! selectors.
+
+use crate::{
+    artifacts::{deserialize_params, params_serialized_size, serialize_params},
+    errors::HyperPlonkErrors,
+    selectors::SelectorDescription,
+    structs::{HyperPlonkParams, HyperPlonkVerifyingKey},
+};
+use ark_ec::pairing::Pairing;
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, Read, SerializationError, Valid, Validate,
+    Write,
+};
+use subroutines::pcs::PolynomialCommitmentScheme;
+
+/// A verifying key where each distinct commitment is stored once.
+///
+/// It is obtained with [`HyperPlonkVerifyingKey::compress`], and turned back
+/// into the verifying key with [`CompressedVerifierKey::expand`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompressedVerifierKey<E: Pairing, PCS: PolynomialCommitmentScheme<E>> {
+    /// The version of the protocol the key was generated for
+    pub protocol_version: u16,
+    /// Hyperplonk instance parameters
+    pub params: HyperPlonkParams,
+    /// The parameters for PCS commitment
+    pub pcs_param: PCS::VerifierParam,
+    /// The distinct commitments of the key, by order of first occurrence
+    pub commitments: Vec<PCS::Commitment>,
+    /// The selectors, where a committed selector holds the index of its
+    /// commitment in `commitments`
+    pub selectors: Vec<SelectorDescription<E::ScalarField, usize>>,
+    /// The indices of the permutation commitments in `commitments`
+    pub perm_commitments: Vec<usize>,
+    /// The indices of the fixed commitments in `commitments`
+    pub fixed_commitments: Vec<usize>,
+    /// The indices of the table commitments in `commitments`
+    pub table_commitments: Vec<usize>,
+}
+
+impl<E: Pairing, PCS: PolynomialCommitmentScheme<E>> HyperPlonkVerifyingKey<E, PCS> {
+    /// Compress the verifying key, storing each distinct commitment once.
+    pub fn compress(&self) -> CompressedVerifierKey<E, PCS> {
+        let mut commitments: Vec<PCS::Commitment> = Vec::new();
+        let mut index_of = |commitment: &PCS::Commitment| {
+            commitments
+                .iter()
+                .position(|c| c == commitment)
+                .unwrap_or_else(|| {
+                    commitments.push(commitment.clone());
+                    commitments.len() - 1
+                })
+        };
+        let selectors = self
+            .selectors
+            .iter()
+            .map(|selector| match selector {
+                SelectorDescription::Committed(commitment) => {
+                    SelectorDescription::Committed(index_of(commitment))
+                },
+                SelectorDescription::Sparse(entries) => {
+                    SelectorDescription::Sparse(entries.clone())
+                },
+                SelectorDescription::Periodic { period, values } => SelectorDescription::Periodic {
+                    period: *period,
+                    values: values.clone(),
+                },
+            })
+            .collect();
+        let perm_commitments = self.perm_commitments.iter().map(&mut index_of).collect();
+        let fixed_commitments = self.fixed_commitments.iter().map(&mut index_of).collect();
+        let table_commitments = self.table_commitments.iter().map(&mut index_of).collect();
+
+        CompressedVerifierKey {
+            protocol_version: self.protocol_version,
+            params: self.params.clone(),
+            pcs_param: self.pcs_param.clone(),
+            commitments,
+            selectors,
+            perm_commitments,
+            fixed_commitments,
+            table_commitments,
+        }
+    }
+}
+
+impl<E: Pairing, PCS: PolynomialCommitmentScheme<E>> CompressedVerifierKey<E, PCS> {
+    /// Reconstruct the verifying key. Fails if an index is out of the range
+    /// of `commitments`.
+    pub fn expand(&self) -> Result<HyperPlonkVerifyingKey<E, PCS>, HyperPlonkErrors> {
+        let lookup = |index: &usize| {
+            self.commitments.get(*index).cloned().ok_or_else(|| {
+                HyperPlonkErrors::InvalidParameters(format!(
+                    "commitment index {} out of range for {} commitments",
+                    index,
+                    self.commitments.len()
+                ))
+            })
+        };
+        let selectors = self
+            .selectors
+            .iter()
+            .map(|selector| {
+                Ok(match selector {
+                    SelectorDescription::Committed(index) => {
+                        SelectorDescription::Committed(lookup(index)?)
+                    },
+                    SelectorDescription::Sparse(entries) => {
+                        SelectorDescription::Sparse(entries.clone())
+                    },
+                    SelectorDescription::Periodic { period, values } => {
+                        SelectorDescription::Periodic {
+                            period: *period,
+                            values: values.clone(),
+                        }
+                    },
+                })
+            })
+            .collect::<Result<_, HyperPlonkErrors>>()?;
+
+        Ok(HyperPlonkVerifyingKey {
+            protocol_version: self.protocol_version,
+            params: self.params.clone(),
+            pcs_param: self.pcs_param.clone(),
+            selectors,
+            perm_commitments: self
+                .perm_commitments
+                .iter()
+                .map(lookup)
+                .collect::<Result<_, _>>()?,
+            fixed_commitments: self
+                .fixed_commitments
+                .iter()
+                .map(lookup)
+                .collect::<Result<_, _>>()?,
+            table_commitments: self
+                .table_commitments
+                .iter()
+                .map(lookup)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    fn indices(&self) -> impl Iterator<Item = &usize> {
+        self.selectors
+            .iter()
+            .filter_map(|selector| selector.commitment())
+            .chain(self.perm_commitments.iter())
+            .chain(self.fixed_commitments.iter())
+            .chain(self.table_commitments.iter())
+    }
+}
+
+// The key is serialized as the protocol version, the instance parameters,
+// see `serialize_params`, the PCS parameters, the distinct commitments, the
+// selector descriptions and the three vectors of indices.
+
+impl<E: Pairing, PCS: PolynomialCommitmentScheme<E>> CanonicalSerialize
+    for CompressedVerifierKey<E, PCS>
+{
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.protocol_version
+            .serialize_with_mode(&mut writer, compress)?;
+        serialize_params(&self.params, &mut writer, compress)?;
+        self.pcs_param.serialize_with_mode(&mut writer, compress)?;
+        self.commitments
+            .serialize_with_mode(&mut writer, compress)?;
+        self.selectors.serialize_with_mode(&mut writer, compress)?;
+        self.perm_commitments
+            .serialize_with_mode(&mut writer, compress)?;
+        self.fixed_commitments
+            .serialize_with_mode(&mut writer, compress)?;
+        self.table_commitments
+            .serialize_with_mode(&mut writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.protocol_version.serialized_size(compress)
+            + params_serialized_size(&self.params, compress)
+            + self.pcs_param.serialized_size(compress)
+            + self.commitments.serialized_size(compress)
+            + self.selectors.serialized_size(compress)
+            + self.perm_commitments.serialized_size(compress)
+            + self.fixed_commitments.serialized_size(compress)
+            + self.table_commitments.serialized_size(compress)
+    }
+}
+
+impl<E: Pairing, PCS: PolynomialCommitmentScheme<E>> Valid for CompressedVerifierKey<E, PCS> {
+    fn check(&self) -> Result<(), SerializationError> {
+        self.pcs_param.check()?;
+        self.commitments.check()?;
+        if self.indices().any(|index| *index >= self.commitments.len()) {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(())
+    }
+}
+
+impl<E: Pairing, PCS: PolynomialCommitmentScheme<E>> CanonicalDeserialize
+    for CompressedVerifierKey<E, PCS>
+{
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let res = Self {
+            protocol_version: u16::deserialize_with_mode(&mut reader, compress, validate)?,
+            params: deserialize_params(&mut reader, compress, validate)?,
+            pcs_param: PCS::VerifierParam::deserialize_with_mode(&mut reader, compress, validate)?,
+            commitments: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+            selectors: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+            perm_commitments: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+            fixed_commitments: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+            table_commitments: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+        };
+        if validate == Validate::Yes {
+            Valid::check(&res)?;
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{custom_gate::CustomizedGates, mock::MockCircuit, HyperPlonkSNARK};
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_std::test_rng;
+    use subroutines::{pcs::prelude::MultilinearKzgPCS, poly_iop::PolyIOP};
+
+    type Kzg = MultilinearKzgPCS<Bls12_381>;
+
+    #[test]
+    fn test_compressed_verifier_key() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let nv = 3;
+        let pcs_srs = Kzg::gen_srs_for_testing(&mut rng, nv)?;
+
+        let gate = CustomizedGates::vanilla_plonk_gate();
+        let circuit = MockCircuit::<Fr>::new(1 << nv, &gate);
+        let (_, vk) =
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::preprocess(&circuit.index, &pcs_srs)?;
+        let compressed = vk.compress();
+        assert_eq!(compressed.expand()?, vk);
+
+        // three selector columns with the same values share a commitment
+        let mut index = circuit.index.clone();
+        index.selectors[1] = index.selectors[0].clone();
+        index.selectors[2] = index.selectors[0].clone();
+        let (_, redundant_vk) =
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::preprocess(&index, &pcs_srs)?;
+        let redundant = redundant_vk.compress();
+        assert_eq!(
+            redundant.commitments.len(),
+            compressed.commitments.len() - 2
+        );
+        assert_eq!(redundant.expand()?, redundant_vk);
+        assert!(redundant.compressed_size() < compressed.compressed_size());
+
+        // serialization round trip
+        let mut bytes = Vec::new();
+        redundant.serialize_compressed(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), redundant.compressed_size());
+        let deserialized =
+            CompressedVerifierKey::<Bls12_381, Kzg>::deserialize_compressed(bytes.as_slice())
+                .unwrap();
+        assert_eq!(deserialized, redundant);
+
+        // an index out of range is rejected
+        let mut bad = redundant;
+        bad.perm_commitments[0] = bad.commitments.len();
+        assert!(bad.expand().is_err());
+        let mut bytes = Vec::new();
+        bad.serialize_compressed(&mut bytes).unwrap();
+        assert!(
+            CompressedVerifierKey::<Bls12_381, Kzg>::deserialize_compressed(bytes.as_slice())
+                .is_err()
+        );
+
+        Ok(())
+    }
+}
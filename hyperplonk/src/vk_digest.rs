@@ -0,0 +1,254 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! Digest of a verifying key.
+//!
+//! The digest identifies a verifying key in external systems, e.g., key
+//! registries, which may store it for a long time. It is therefore computed
+//! with a fixed, named hash function over a canonical serialization of the
+//! key, independently of the hash used by the transcript.
+
+use crate::structs::HyperPlonkVerifyingKey;
+use ark_ec::pairing::Pairing;
+use ark_serialize::CanonicalSerialize;
+use digest::{consts::U32, Digest, OutputSizeUser};
+use sha2::Sha256;
+use subroutines::pcs::PolynomialCommitmentScheme;
+
+/// Domain separator at the beginning of the canonical serialization.
+const DOMAIN_SEPARATOR: &[u8] = b"hyperplonk verifying key v1";
+
+impl<E: Pairing, PCS: PolynomialCommitmentScheme<E>> HyperPlonkVerifyingKey<E, PCS> {
+    /// The canonical serialization of the verifying key, which is hashed by
+    /// [`Self::digest`]. It consists of, in order:
+    /// - the bytes of `"hyperplonk verifying key v1"`;
+    /// - the number of constraints, of public inputs and of fixed columns;
+    /// - the number of gates, then for each gate its coefficient, a `0` byte
+    ///   if it has no selector or a `1` byte followed by the selector index,
+    ///   and the list of its witness indices;
+    /// - the commitments of the committed selectors, the permutation and the
+    ///   fixed commitments;
+    /// - the PCS verifier parameters;
+    /// - only if some selectors are described compactly, the list of their
+    ///   indices, each followed by its description;
+    /// - only if the gate has lookups, the list of the compactly described
+    ///   selectors above even if it is empty, the list of the lookups, each
+    ///   as its witness index and its table id, and the table commitments.
+    ///
+    /// Integers are encoded on 8 bytes in little-endian, lists are prefixed
+    /// with their length, and commitments, selector descriptions and PCS
+    /// parameters are serialized with compressed `CanonicalSerialize`. The
+    /// serialization of a key whose selectors are all committed does not
+    /// depend on the selector descriptions, nor does that of a key without
+    /// lookups depend on its tables, which the verifier never uses. Neither
+    /// does it depend on the protocol version, since the preprocessing is
+    /// the same in every version.
+    pub fn to_canonical_bytes(&self) -> Vec<u8> {
+        let mut bytes = DOMAIN_SEPARATOR.to_vec();
+        push_u64(&mut bytes, self.params.num_constraints);
+        push_u64(&mut bytes, self.params.num_pub_input);
+        push_u64(&mut bytes, self.params.num_fixed_columns);
+        push_u64(&mut bytes, self.params.gate_func.gates.len());
+        for (coeff, selector, witnesses) in self.params.gate_func.gates.iter() {
+            bytes.extend_from_slice(&coeff.to_le_bytes());
+            match selector {
+                None => bytes.push(0),
+                Some(selector) => {
+                    bytes.push(1);
+                    push_u64(&mut bytes, *selector);
+                },
+            }
+            push_u64(&mut bytes, witnesses.len());
+            for &witness in witnesses.iter() {
+                push_u64(&mut bytes, witness);
+            }
+        }
+
+        let selector_commitments: Vec<_> = self
+            .selectors
+            .iter()
+            .filter_map(|selector| selector.commitment())
+            .cloned()
+            .collect();
+        selector_commitments
+            .serialize_compressed(&mut bytes)
+            .expect("serialization into a vector cannot fail");
+        self.perm_commitments
+            .serialize_compressed(&mut bytes)
+            .expect("serialization into a vector cannot fail");
+        self.fixed_commitments
+            .serialize_compressed(&mut bytes)
+            .expect("serialization into a vector cannot fail");
+        self.pcs_param
+            .serialize_compressed(&mut bytes)
+            .expect("serialization into a vector cannot fail");
+
+        let compact_selectors: Vec<_> = self
+            .selectors
+            .iter()
+            .enumerate()
+            .filter(|(_, selector)| !selector.is_committed())
+            .collect();
+        let lookups = self.params.gate_func.lookups();
+        if !compact_selectors.is_empty() || !lookups.is_empty() {
+            push_u64(&mut bytes, compact_selectors.len());
+            for (index, selector) in compact_selectors {
+                push_u64(&mut bytes, index);
+                selector
+                    .serialize_compressed(&mut bytes)
+                    .expect("serialization into a vector cannot fail");
+            }
+        }
+        if !lookups.is_empty() {
+            push_u64(&mut bytes, lookups.len());
+            for &(witness, table_id) in lookups.iter() {
+                push_u64(&mut bytes, witness);
+                push_u64(&mut bytes, table_id);
+            }
+            self.table_commitments
+                .serialize_compressed(&mut bytes)
+                .expect("serialization into a vector cannot fail");
+        }
+        bytes
+    }
+
+    /// The digest of the verifying key with the hash function `H`.
+    pub fn digest_with<H: Digest + OutputSizeUser<OutputSize = U32>>(&self) -> [u8; 32] {
+        H::digest(self.to_canonical_bytes()).into()
+    }
+
+    /// The digest of the verifying key, with SHA-256.
+    ///
+    /// The hash function is part of the definition of the digest and will
+    /// not change: the digests of the test vectors in
+    /// `test_vectors/vk_digest.txt` must stay the same.
+    pub fn digest(&self) -> [u8; 32] {
+        self.digest_with::<Sha256>()
+    }
+}
+
+fn push_u64(bytes: &mut Vec<u8>, x: usize) {
+    bytes.extend_from_slice(&(x as u64).to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        custom_gate::CustomizedGates, errors::HyperPlonkErrors, mock::MockCircuit,
+        selectors::SelectorDescription, structs::HyperPlonkParams, HyperPlonkSNARK,
+        PROTOCOL_VERSION,
+    };
+    use ark_bls12_381::{Bls12_381, Fr, G1Affine, G2Affine};
+    use ark_ec::AffineRepr;
+    use ark_std::test_rng;
+    use subroutines::{
+        pcs::prelude::{Commitment, MultilinearKzgPCS, MultilinearVerifierParam},
+        poly_iop::PolyIOP,
+    };
+
+    type Kzg = MultilinearKzgPCS<Bls12_381>;
+
+    const TEST_VECTORS: &str = include_str!("../test_vectors/vk_digest.txt");
+
+    fn test_vector(name: &str) -> &'static str {
+        TEST_VECTORS
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .find_map(|line| line.strip_prefix(name)?.trim().strip_prefix('='))
+            .map(str::trim)
+            .expect("missing test vector")
+    }
+
+    fn to_hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// The verifying key of the test vectors.
+    fn golden_vk() -> HyperPlonkVerifyingKey<Bls12_381, Kzg> {
+        let g1 = G1Affine::generator();
+        let g2 = G2Affine::generator();
+        HyperPlonkVerifyingKey {
+            protocol_version: PROTOCOL_VERSION,
+            params: HyperPlonkParams {
+                num_constraints: 4,
+                num_pub_input: 2,
+                gate_func: CustomizedGates {
+                    gates: vec![(1, Some(0), vec![0]), (-1, None, vec![1])],
+                    lookups: vec![],
+                },
+                num_fixed_columns: 0,
+            },
+            pcs_param: MultilinearVerifierParam {
+                num_vars: 2,
+                g: g1,
+                h: g2,
+                h_mask: vec![g2, g2],
+            },
+            selectors: vec![SelectorDescription::Committed(Commitment(g1))],
+            perm_commitments: vec![Commitment(g1), Commitment(G1Affine::zero())],
+            fixed_commitments: vec![],
+            table_commitments: vec![],
+        }
+    }
+
+    #[test]
+    fn test_vk_digest_test_vectors() {
+        let vk = golden_vk();
+        assert_eq!(
+            to_hex(&vk.to_canonical_bytes()),
+            test_vector("canonical_bytes")
+        );
+        assert_eq!(to_hex(&vk.digest()), test_vector("sha256"));
+        assert_eq!(vk.digest(), vk.digest_with::<Sha256>());
+    }
+
+    #[test]
+    fn test_vk_digest_binds_the_key() -> Result<(), HyperPlonkErrors> {
+        let mut rng = test_rng();
+        let nv = 3;
+        let pcs_srs = Kzg::gen_srs_for_testing(&mut rng, nv)?;
+
+        let gate = CustomizedGates::vanilla_plonk_gate();
+        let circuit = MockCircuit::<Fr>::new(1 << nv, &gate);
+        let (_, vk) =
+            <PolyIOP<Fr> as HyperPlonkSNARK<Bls12_381, Kzg>>::preprocess(&circuit.index, &pcs_srs)?;
+        let digest = vk.digest();
+
+        // any change to a selector commitment changes the digest
+        for i in 0..vk.selectors.len() {
+            let commitment = vk.selectors[i].commitment().unwrap();
+            let mut bad_vk = vk.clone();
+            bad_vk.selectors[i] = SelectorDescription::Committed(Commitment(
+                (commitment.0 + G1Affine::generator()).into(),
+            ));
+            assert_ne!(bad_vk.digest(), digest);
+        }
+        let mut bad_vk = vk.clone();
+        bad_vk.selectors.swap(0, 1);
+        assert_ne!(bad_vk.digest(), digest);
+
+        // as does describing a selector compactly, and changing or moving
+        // the description
+        let mut compact_vk = vk.clone();
+        compact_vk.selectors[1] = SelectorDescription::Sparse(vec![(2, Fr::from(3u64))]);
+        let compact_digest = compact_vk.digest();
+        assert_ne!(compact_digest, digest);
+        let mut bad_vk = compact_vk.clone();
+        bad_vk.selectors[1] = SelectorDescription::Sparse(vec![(3, Fr::from(3u64))]);
+        assert_ne!(bad_vk.digest(), compact_digest);
+        let mut bad_vk = compact_vk;
+        bad_vk.selectors.swap(1, 2);
+        assert_ne!(bad_vk.digest(), compact_digest);
+
+        // and so does a change to the gate
+        let mut bad_vk = vk;
+        bad_vk.params.gate_func.gates[0].0 = -1;
+        assert_ne!(bad_vk.digest(), digest);
+
+        Ok(())
+    }
+}
@@ -59,6 +59,82 @@ impl<F: PrimeField> WitnessColumn<F> {
     }
 }
 
+/// The witness columns given to the prover, read one column at a time.
+///
+/// The prover reads each column once, into the multilinear polynomial it
+/// proves with, so a source that streams its columns, e.g., from disk, is
+/// never held in memory next to that polynomial. Columns held in memory,
+/// as `WitnessColumn`s or as multilinear polynomials, are sources as well.
+pub trait WitnessSource<F: PrimeField> {
+    /// The number of witness columns.
+    fn num_columns(&self) -> usize;
+
+    /// The values of the column `idx`, in row order.
+    fn column(&self, idx: usize) -> Box<dyn Iterator<Item = F> + '_>;
+}
+
+impl<F: PrimeField> WitnessSource<F> for [WitnessColumn<F>] {
+    fn num_columns(&self) -> usize {
+        self.len()
+    }
+
+    fn column(&self, idx: usize) -> Box<dyn Iterator<Item = F> + '_> {
+        Box::new(self[idx].0.iter().copied())
+    }
+}
+
+impl<F: PrimeField> WitnessSource<F> for Vec<WitnessColumn<F>> {
+    fn num_columns(&self) -> usize {
+        self.len()
+    }
+
+    fn column(&self, idx: usize) -> Box<dyn Iterator<Item = F> + '_> {
+        self.as_slice().column(idx)
+    }
+}
+
+impl<F: PrimeField, const N: usize> WitnessSource<F> for [WitnessColumn<F>; N] {
+    fn num_columns(&self) -> usize {
+        N
+    }
+
+    fn column(&self, idx: usize) -> Box<dyn Iterator<Item = F> + '_> {
+        self.as_slice().column(idx)
+    }
+}
+
+impl<F: PrimeField> WitnessSource<F> for [Arc<DenseMultilinearExtension<F>>] {
+    fn num_columns(&self) -> usize {
+        self.len()
+    }
+
+    fn column(&self, idx: usize) -> Box<dyn Iterator<Item = F> + '_> {
+        Box::new(self[idx].evaluations.iter().copied())
+    }
+}
+
+impl<F: PrimeField> WitnessSource<F> for Vec<Arc<DenseMultilinearExtension<F>>> {
+    fn num_columns(&self) -> usize {
+        self.len()
+    }
+
+    fn column(&self, idx: usize) -> Box<dyn Iterator<Item = F> + '_> {
+        self.as_slice().column(idx)
+    }
+}
+
+/// Read the columns of `source`, reading at most `max_len + 1` values of
+/// each, so that a column that is too long is detected without reading all
+/// of it.
+pub(crate) fn read_columns<F: PrimeField, W: WitnessSource<F> + ?Sized>(
+    source: &W,
+    max_len: usize,
+) -> Vec<WitnessColumn<F>> {
+    (0..source.num_columns())
+        .map(|idx| WitnessColumn(source.column(idx).take(max_len + 1).collect()))
+        .collect()
+}
+
 impl<F: PrimeField> From<&WitnessColumn<F>> for DenseMultilinearExtension<F> {
     fn from(witness: &WitnessColumn<F>) -> Self {
         let nv = witness.get_nv();
@@ -66,6 +142,13 @@ impl<F: PrimeField> From<&WitnessColumn<F>> for DenseMultilinearExtension<F> {
     }
 }
 
+impl<F: PrimeField> From<WitnessColumn<F>> for DenseMultilinearExtension<F> {
+    fn from(witness: WitnessColumn<F>) -> Self {
+        let nv = witness.get_nv();
+        Self::from_evaluations_vec(nv, witness.0)
+    }
+}
+
 impl<F: PrimeField> WitnessRow<F> {
     /// Build MLE from matrix of witnesses.
     ///
@@ -0,0 +1,26 @@
+#!/usr/bin/env bash
+
+# Check the dependencies of the verifier-only build of hyperplonk, i.e.,
+# without the default features, and that a proof still verifies in it.
+
+#Fail out on error
+set -e
+
+# crates that the verifier-only build must not depend on
+FORBIDDEN="rayon rand_chacha displaydoc itertools crossbeam-deque"
+
+DEPS=$(cargo tree -p hyperplonk --no-default-features -e normal --prefix none \
+    | sed -e 's/ (\*)$//' -e 's/ (proc-macro)$//' | sort -u)
+
+echo "dependencies of the verifier-only build:"
+echo "$DEPS"
+
+for dep in $FORBIDDEN; do
+    if echo "$DEPS" | grep -q "^$dep v"; then
+        echo "error: the verifier-only build depends on $dep"
+        exit 1
+    fi
+done
+
+cargo build -p hyperplonk --no-default-features
+cargo test -p hyperplonk --no-default-features --lib -- test_hyperplonk_e2e
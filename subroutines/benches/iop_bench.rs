@@ -12,9 +12,11 @@ use std::{marker::PhantomData, sync::Arc, time::Instant};
 use subroutines::{
     pcs::{prelude::MultilinearKzgPCS, PolynomialCommitmentScheme},
     poly_iop::prelude::{
-        PermutationCheck, PolyIOP, PolyIOPErrors, ProductCheck, SumCheck, ZeroCheck,
+        PermutationCheck, PolyIOP, PolyIOPErrors, ProductCheck, RandomMaskPolynomial, SumCheck,
+        ZeroCheck, ZkSumCheck,
     },
 };
+use transcript::{Blake3Transcript, IOPTranscript, Transcript};
 
 type Kzg = MultilinearKzgPCS<Bls12_381>;
 
@@ -23,11 +25,91 @@ fn main() -> Result<(), PolyIOPErrors> {
     println!("\n\n");
     bench_sum_check()?;
     println!("\n\n");
+    bench_partial_sum_check()?;
+    println!("\n\n");
+    bench_transcripts()?;
+    println!("\n\n");
     bench_prod_check()?;
     println!("\n\n");
     bench_zero_check()
 }
 
+/// Sum check of `f * g + h` where `f` and `g` only depend on the first and
+/// the second half of the variables, against the same polynomial with all
+/// the mles over all the variables.
+fn bench_partial_sum_check() -> Result<(), PolyIOPErrors> {
+    let mut rng = test_rng();
+    for nv in [10, 16, 20] {
+        let repetition = if nv < 20 { 10 } else { 2 };
+
+        let half = nv / 2;
+        let f = Arc::new(DenseMultilinearExtension::rand(half, &mut rng));
+        let g = Arc::new(DenseMultilinearExtension::rand(nv - half, &mut rng));
+        let h = Arc::new(DenseMultilinearExtension::rand(nv, &mut rng));
+        let mut poly = VirtualPolynomial::new(nv);
+        poly.add_partial_mle_list(
+            [(f, (0..half).collect()), (g, (half..nv).collect())],
+            Fr::from(1u64),
+        )?;
+        poly.add_mle_list([h], Fr::from(1u64))?;
+        let lifted = poly.lift();
+
+        for (poly, name) in [(&poly, "half-dimension"), (&lifted, "lifted")] {
+            let start = Instant::now();
+            for _ in 0..repetition {
+                let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+                let _proof = <PolyIOP<Fr> as SumCheck<Fr>>::prove(poly, &mut transcript)?;
+            }
+            println!(
+                "sum check proving time for {} variables with {} mles: {} ns",
+                nv,
+                name,
+                start.elapsed().as_nanos() / repetition as u128
+            );
+        }
+        println!("====================================");
+    }
+    Ok(())
+}
+
+/// Zk sum check proving with the Merlin transcript against the BLAKE3 one.
+fn bench_transcripts() -> Result<(), PolyIOPErrors> {
+    let mut rng = test_rng();
+    for nv in [10, 15, 20] {
+        let repetition = if nv < 20 { 10 } else { 2 };
+
+        let (poly, _) = VirtualPolynomial::rand(nv, (2, 3), 2, &mut rng)?;
+        let (mask, _) = RandomMaskPolynomial::rand(nv, 3, &mut rng);
+        let merlin = bench_zk_sum_check_prover::<IOPTranscript<Fr>>(&poly, &mask, repetition)?;
+        let blake3 = bench_zk_sum_check_prover::<Blake3Transcript<Fr>>(&poly, &mask, repetition)?;
+        println!(
+            "zk sum check proving time for {} variables: {} ns with merlin, {} ns with blake3",
+            nv, merlin, blake3
+        );
+        println!("====================================");
+    }
+    Ok(())
+}
+
+/// The average proving time of the zk sum check over the transcript `T`.
+fn bench_zk_sum_check_prover<T: Transcript<Fr>>(
+    poly: &VirtualPolynomial<Fr>,
+    mask: &RandomMaskPolynomial<Fr>,
+    repetition: usize,
+) -> Result<u128, PolyIOPErrors> {
+    let start = Instant::now();
+    for _ in 0..repetition {
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr, T>>::init_transcript();
+        let _proof = <PolyIOP<Fr> as ZkSumCheck<Fr, T>>::prove_bound(
+            poly,
+            mask,
+            &mask.evaluations,
+            &mut transcript,
+        )?;
+    }
+    Ok(start.elapsed().as_nanos() / repetition as u128)
+}
+
 fn bench_sum_check() -> Result<(), PolyIOPErrors> {
     let mut rng = test_rng();
     for degree in 2..4 {
@@ -181,6 +263,8 @@ fn bench_permutation_check() -> Result<(), PolyIOPErrors> {
             let poly_info = VPAuxInfo {
                 max_degree: 2,
                 num_variables: nv,
+                per_variable_degree: vec![],
+                is_sparse: false,
                 phantom: PhantomData,
             };
 
@@ -252,6 +336,8 @@ fn bench_prod_check() -> Result<(), PolyIOPErrors> {
             let poly_info = VPAuxInfo {
                 max_degree: 2,
                 num_variables: nv,
+                per_variable_degree: vec![],
+                is_sparse: false,
                 phantom: PhantomData,
             };
 
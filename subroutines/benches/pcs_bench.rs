@@ -7,17 +7,74 @@
 use ark_bls12_381::{Bls12_381, Fr};
 use ark_ff::UniformRand;
 use ark_poly::{DenseMultilinearExtension, MultilinearExtension};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::{sync::Arc, test_rng};
 use std::time::Instant;
 use subroutines::pcs::{
-    prelude::{MultilinearKzgPCS, PCSError, PolynomialCommitmentScheme},
+    prelude::{
+        MultilinearKzgPCS, MultilinearUniversalParams, PCSError, PolynomialCommitmentScheme,
+        SrsValidation,
+    },
     StructuredReferenceString,
 };
 
 fn main() -> Result<(), PCSError> {
+    bench_srs_loading()?;
     bench_pcs()
 }
 
+/// Loading an SRS for circuits of up to 2^20 gates: with a subgroup check
+/// per element, with the batch subgroup check, and without checks; and
+/// validating it, on a sample and in full.
+fn bench_srs_loading() -> Result<(), PCSError> {
+    let mut rng = test_rng();
+
+    for nv in [12, 16, 20] {
+        let srs = MultilinearKzgPCS::<Bls12_381>::gen_srs_for_testing(&mut rng, nv)?;
+        let mut bytes = Vec::new();
+        srs.serialize_compressed(&mut bytes)?;
+
+        let start = Instant::now();
+        MultilinearUniversalParams::<Bls12_381>::deserialize_compressed(&bytes[..])?;
+        println!(
+            "SRS loading with per element checks for {} variables: {} ms",
+            nv,
+            start.elapsed().as_millis()
+        );
+
+        let start = Instant::now();
+        MultilinearUniversalParams::<Bls12_381>::deserialize_checked(&bytes[..], &mut rng)?;
+        println!(
+            "SRS loading with batch checks for {} variables: {} ms",
+            nv,
+            start.elapsed().as_millis()
+        );
+
+        let start = Instant::now();
+        MultilinearUniversalParams::<Bls12_381>::deserialize_trusted(&bytes[..])?;
+        println!(
+            "SRS loading without checks for {} variables: {} ms",
+            nv,
+            start.elapsed().as_millis()
+        );
+
+        for mode in [SrsValidation::Sampled, SrsValidation::Full] {
+            let start = Instant::now();
+            srs.validate(mode, &mut rng)?;
+            println!(
+                "{:?} SRS validation for {} variables: {} ms",
+                mode,
+                nv,
+                start.elapsed().as_millis()
+            );
+        }
+
+        println!("====================================");
+    }
+
+    Ok(())
+}
+
 fn bench_pcs() -> Result<(), PCSError> {
     let mut rng = test_rng();
 
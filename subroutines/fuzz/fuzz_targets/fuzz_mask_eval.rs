@@ -0,0 +1,68 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! `RandomMaskPolynomial::eval` on masks and points of arbitrary shapes: it
+//! must return an error for a malformed input, and never panic.
+//!
+//! The input is read byte by byte: the number of variables, then for each
+//! variable the number of evaluations and the evaluations, then the number
+//! of coordinates of the point and its coordinates. A field element is read
+//! from 8 bytes, so that small values, such as the evaluation points of the
+//! univariate polynomials, are common.
+
+#![no_main]
+
+use ark_bls12_381::Fr;
+use libfuzzer_sys::fuzz_target;
+use subroutines::RandomMaskPolynomial;
+
+/// A reader of the fuzzer input, which yields zeros once it is exhausted.
+struct Input<'a>(&'a [u8]);
+
+impl Input<'_> {
+    fn byte(&mut self) -> u8 {
+        match self.0.split_first() {
+            Some((&b, rest)) => {
+                self.0 = rest;
+                b
+            },
+            None => 0,
+        }
+    }
+
+    /// A length below 16.
+    fn len(&mut self) -> usize {
+        (self.byte() % 16) as usize
+    }
+
+    fn field(&mut self) -> Fr {
+        let mut bytes = [0u8; 8];
+        for b in bytes.iter_mut() {
+            *b = self.byte();
+        }
+        Fr::from(u64::from_le_bytes(bytes))
+    }
+
+    fn fields(&mut self) -> Vec<Fr> {
+        let len = self.len();
+        (0..len).map(|_| self.field()).collect()
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    let mut input = Input(data);
+    let num_vars = input.len();
+    let mask = RandomMaskPolynomial {
+        const_term: input.field(),
+        evaluations: (0..num_vars).map(|_| input.fields()).collect(),
+        tag: [0u8; 32],
+    };
+    let point = input.fields();
+
+    let well_formed = point.len() == mask.evaluations.len()
+        && mask.evaluations.iter().all(|evals| !evals.is_empty());
+    assert_eq!(mask.eval(&point).is_ok(), well_formed);
+});
@@ -0,0 +1,67 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! The zk sum check verifier on arbitrary proofs and claimed sums: it must
+//! return, with `Ok` or with an error, and never panic.
+//!
+//! The input is the claimed sum, on 32 bytes reduced modulo the field order,
+//! followed by a compressed `IOPProof`.
+
+#![no_main]
+
+use arithmetic::VPAuxInfo;
+use ark_bls12_381::Fr;
+use ark_ff::PrimeField;
+use ark_serialize::CanonicalDeserialize;
+use libfuzzer_sys::fuzz_target;
+use std::marker::PhantomData;
+use subroutines::{IOPProof, PolyIOP, ZkSumCheck};
+
+const NUM_VARIABLES: usize = 4;
+const MAX_DEGREE: usize = 3;
+const MASK_DEGREE: usize = 3;
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 32 {
+        return;
+    }
+    let (sum_bytes, proof_bytes) = data.split_at(32);
+    let claimed_sum = Fr::from_le_bytes_mod_order(sum_bytes);
+    let proof = match IOPProof::<Fr>::deserialize_compressed(proof_bytes) {
+        Ok(proof) => proof,
+        Err(_) => return,
+    };
+    let aux_info = VPAuxInfo {
+        max_degree: MAX_DEGREE,
+        num_variables: NUM_VARIABLES,
+        per_variable_degree: vec![],
+        is_sparse: false,
+        phantom: PhantomData::<Fr>,
+    };
+
+    let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr>>::init_transcript();
+    #[allow(deprecated)]
+    let _ = <PolyIOP<Fr> as ZkSumCheck<Fr>>::verify(
+        claimed_sum,
+        &proof,
+        &aux_info,
+        &mut transcript,
+        NUM_VARIABLES,
+        MASK_DEGREE,
+    );
+
+    // the claimed sum is split between the polynomial and the mask
+    let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr>>::init_transcript();
+    let _ = <PolyIOP<Fr> as ZkSumCheck<Fr>>::verify_bound(
+        claimed_sum,
+        -claimed_sum,
+        &proof,
+        &b"mask commitment".to_vec(),
+        &aux_info,
+        &mut transcript,
+        MASK_DEGREE,
+    );
+});
@@ -9,11 +9,13 @@
 use arithmetic::ArithErrors;
 use ark_serialize::SerializationError;
 use ark_std::string::String;
+#[cfg(feature = "display")]
 use displaydoc::Display;
 use transcript::TranscriptError;
 
 /// A `enum` specifying the possible failure modes of the PCS.
-#[derive(Display, Debug)]
+#[derive(Debug)]
+#[cfg_attr(feature = "display", derive(Display))]
 pub enum PCSError {
     /// Invalid Prover: {0}
     InvalidProver(String),
@@ -29,6 +31,23 @@ pub enum PCSError {
     TranscriptError(TranscriptError),
     /// ArithErrors error {0}
     ArithErrors(ArithErrors),
+    /// Inconsistent SRS: element {index} of level {level}
+    InconsistentSrs {
+        /// the level `i` of the first inconsistent element, i.e. its table
+        /// `powers_of_g[i]`, or `powers_of_g[i + 1]` and `h_mask[i]`
+        level: usize,
+        /// the index `y` of the first inconsistent element, i.e. of the
+        /// elements `2y` and `2y + 1` of its table and `y` of the next one
+        index: usize,
+    },
+}
+
+/// Display as `Debug` without the `display` feature.
+#[cfg(not(feature = "display"))]
+impl ark_std::fmt::Display for PCSError {
+    fn fmt(&self, f: &mut ark_std::fmt::Formatter<'_>) -> ark_std::fmt::Result {
+        ark_std::fmt::Debug::fmt(self, f)
+    }
 }
 
 impl From<SerializationError> for PCSError {
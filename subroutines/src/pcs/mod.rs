@@ -22,7 +22,8 @@ use transcript::IOPTranscript;
 /// This trait defines APIs for polynomial commitment schemes.
 /// Note that for our usage of PCS, we do not require the hiding property.
 pub trait PolynomialCommitmentScheme<E: Pairing> {
-    /// Prover parameters
+    /// Prover parameters. They are `Sync`, as the HyperPlonk prover
+    /// commits to several polynomials in parallel with the same parameters.
     type ProverParam: Clone + Sync;
     /// Verifier parameters
     type VerifierParam: Clone + CanonicalSerialize + CanonicalDeserialize;
@@ -16,14 +16,28 @@ use crate::{
         prelude::{Commitment, PCSError},
         PolynomialCommitmentScheme,
     },
-    poly_iop::{prelude::SumCheck, PolyIOP},
+    poly_iop::{
+        prelude::{PolyIOPErrors, SumCheck, SumCheckRounds},
+        PolyIOP,
+    },
     IOPProof,
 };
 use arithmetic::{build_eq_x_r_vec, DenseMultilinearExtension, VPAuxInfo, VirtualPolynomial};
 use ark_ec::{pairing::Pairing, scalar_mul::variable_base::VariableBaseMSM, CurveGroup};
-
-use ark_std::{end_timer, log2, start_timer, One, Zero};
-use std::{collections::BTreeMap, iter, marker::PhantomData, ops::Deref, sync::Arc};
+use ark_ff::PrimeField;
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, Read, SerializationError, Valid, Validate,
+    Write,
+};
+use ark_std::{end_timer, log2, start_timer, Zero};
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap},
+    hash::{Hash, Hasher},
+    iter,
+    marker::PhantomData,
+    ops::Deref,
+    sync::Arc,
+};
 use transcript::IOPTranscript;
 
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
@@ -40,7 +54,64 @@ where
     pub(crate) g_prime_proof: PCS::Proof,
 }
 
+impl<E, PCS> BatchProof<E, PCS>
+where
+    E: Pairing,
+    PCS: PolynomialCommitmentScheme<E>,
+{
+    /// Assemble a batch proof from its parts, e.g., after reading them from
+    /// a stream.
+    pub fn new(
+        sum_check_proof: IOPProof<E::ScalarField>,
+        f_i_eval_at_point_i: Vec<E::ScalarField>,
+        g_prime_proof: PCS::Proof,
+    ) -> Self {
+        Self {
+            sum_check_proof,
+            f_i_eval_at_point_i,
+            g_prime_proof,
+        }
+    }
+
+    /// The sum check proof proving tilde g's sum.
+    pub fn sum_check_proof(&self) -> &IOPProof<E::ScalarField> {
+        &self.sum_check_proof
+    }
+
+    /// The proof for g'(a_2).
+    pub fn g_prime_proof(&self) -> &PCS::Proof {
+        &self.g_prime_proof
+    }
+}
+
+/// Canonical deduplication of opening claims.
+///
+/// Claims `i` and `j` are identical if they open the same polynomial (as
+/// decided by `same_poly`) at the same point. The `i`-th output is the index
+/// of the first claim identical to claim `i`; the deduplicated claims are
+/// therefore the ones with `output[i] == i`, in their original order.
+///
+/// The prover compares polynomials, by their hashes first, and the verifier
+/// compares commitments, so that both sides agree on the deduplicated claims.
+fn dedup_claims<P: PartialEq>(
+    points: &[P],
+    same_poly: impl Fn(usize, usize) -> bool,
+) -> Vec<usize> {
+    let mut representatives: Vec<usize> = Vec::with_capacity(points.len());
+    for (i, point) in points.iter().enumerate() {
+        let representative = representatives
+            .iter()
+            .enumerate()
+            .find(|&(j, &rep)| j == rep && points[j] == *point && same_poly(i, j))
+            .map_or(i, |(j, _)| j);
+        representatives.push(representative);
+    }
+    representatives
+}
+
 /// Steps:
+/// 0. deduplicate identical claims and append their evaluations to the
+///    transcript
 /// 1. get challenge point t from transcript
 /// 2. build eq(t,i) for i in [0..k]
 /// 3. build \tilde g_i(b) = eq(t, i) * f_i(b)
@@ -66,113 +137,320 @@ where
 {
     let open_timer = start_timer!(|| format!("multi open {} points", points.len()));
 
-    // TODO: sanity checks
-    let num_var = polynomials[0].num_vars;
-    let k = polynomials.len();
-    let ell = log2(k) as usize;
-
-    // challenge point t
-    let t = transcript.get_and_append_challenge_vectors("t".as_ref(), ell)?;
-
-    // eq(t, i) for i in [0..k]
-    let eq_t_i_list = build_eq_x_r_vec(t.as_ref())?;
-
-    // \tilde g_i(b) = eq(t, i) * f_i(b)
-    let timer = start_timer!(|| format!("compute tilde g for {} points", points.len()));
-    // combine the polynomials that have same opening point first to reduce the
-    // cost of sum check later.
-    let point_indices = points
-        .iter()
-        .fold(BTreeMap::<_, _>::new(), |mut indices, point| {
-            let idx = indices.len();
-            indices.entry(point).or_insert(idx);
-            indices
-        });
-    let deduped_points =
-        BTreeMap::from_iter(point_indices.iter().map(|(point, idx)| (*idx, *point)))
-            .into_values()
-            .collect::<Vec<_>>();
-    let merged_tilde_gs = polynomials
-        .iter()
-        .zip(points.iter())
-        .zip(eq_t_i_list.iter())
-        .fold(
-            iter::repeat_with(DenseMultilinearExtension::zero)
-                .map(Arc::new)
-                .take(point_indices.len())
-                .collect::<Vec<_>>(),
-            |mut merged_tilde_gs, ((poly, point), coeff)| {
-                *Arc::make_mut(&mut merged_tilde_gs[point_indices[point]]) +=
-                    (*coeff, poly.deref());
-                merged_tilde_gs
-            },
-        );
+    let mut rounds = MultiOpenRounds::new(polynomials, points, evals, transcript)?;
+    let timer = start_timer!(|| format!("sum check prove of {} variables", rounds.num_rounds()));
+    while !rounds.is_finished() {
+        rounds.prove_round(transcript)?;
+    }
     end_timer!(timer);
+    let proof = rounds.finish(prover_param);
+
+    end_timer!(open_timer);
+    proof
+}
+
+/// A batch opening that is driven one sum check round at a time.
+///
+/// `PCS::multi_open` is equivalent to calling [`MultiOpenRounds::new`], then
+/// [`MultiOpenRounds::prove_round`] until the prover is finished, and finally
+/// [`MultiOpenRounds::finish`]; the transcript must not be touched in
+/// between. Like [`SumCheckRounds`], the rounds can be serialized between two
+/// rounds.
+pub struct MultiOpenRounds<F: PrimeField> {
+    /// \tilde g_i, merged over the claims with the same point
+    merged_tilde_gs: Vec<Arc<DenseMultilinearExtension<F>>>,
+    /// the distinct points, in the order of `merged_tilde_gs`
+    deduped_points: Vec<Vec<F>>,
+    /// f_i(point_i) for all the claims, duplicates included
+    evals: Vec<F>,
+    /// the sum check on \sum_i \tilde eq_i * \tilde g_i
+    rounds: SumCheckRounds<F>,
+}
+
+impl<F: PrimeField> MultiOpenRounds<F> {
+    /// Steps 0-4 of the batch opening of `polynomials` at `points`, where
+    /// they evaluate to `evals`, and the initialization of the sum check.
+    pub fn new(
+        polynomials: &[Arc<DenseMultilinearExtension<F>>],
+        points: &[Vec<F>],
+        evals: &[F],
+        transcript: &mut IOPTranscript<F>,
+    ) -> Result<Self, PCSError> {
+        if polynomials.is_empty()
+            || polynomials.len() != points.len()
+            || polynomials.len() != evals.len()
+        {
+            return Err(PCSError::InvalidParameters(format!(
+                "polynomials ({}), points ({}) and evaluations ({}) do not match",
+                polynomials.len(),
+                points.len(),
+                evals.len()
+            )));
+        }
+        let num_var = polynomials[0].num_vars;
+
+        // the same claim may be inserted several times; keep the first
+        // occurrence, except for the legacy proofs, which batch every claim
+        let mut claims = vec![];
+        if transcript.is_legacy() {
+            claims.extend(0..polynomials.len());
+        } else {
+            // hash each polynomial once, so that distinct polynomials are
+            // told apart without comparing their evaluations
+            let digests: Vec<u64> = polynomials
+                .iter()
+                .map(|poly| {
+                    let mut hasher = DefaultHasher::new();
+                    poly.hash(&mut hasher);
+                    hasher.finish()
+                })
+                .collect();
+            let representatives = dedup_claims(points, |i, j| {
+                Arc::ptr_eq(&polynomials[i], &polynomials[j])
+                    || (digests[i] == digests[j] && polynomials[i] == polynomials[j])
+            });
+            for (i, &rep) in representatives.iter().enumerate() {
+                if evals[i] != evals[rep] {
+                    return Err(PCSError::InvalidProver(format!(
+                        "claims {} and {} are identical but have different evaluations",
+                        rep, i
+                    )));
+                }
+                if rep == i {
+                    claims.push(i);
+                }
+            }
+            // bind the evaluations before deriving t, so that they cannot be
+            // adapted to the batching coefficients
+            for &i in claims.iter() {
+                transcript.append_field_element(b"eval", &evals[i])?;
+            }
+        }
+        let polynomials: Vec<_> = claims.iter().map(|&i| polynomials[i].clone()).collect();
+        let points: Vec<_> = claims.iter().map(|&i| &points[i]).collect();
+
+        let k = polynomials.len();
+        let ell = log2(k) as usize;
+
+        // challenge point t
+        let t = batching_challenge(transcript, ell)?;
+
+        // eq(t, i) for i in [0..k]
+        let eq_t_i_list = build_eq_x_r_vec(t.as_ref())?;
 
-    let timer = start_timer!(|| format!("compute tilde eq for {} points", points.len()));
-    let tilde_eqs: Vec<_> = deduped_points
-        .iter()
-        .map(|point| {
-            let eq_b_zi = build_eq_x_r_vec(point).unwrap();
-            Arc::new(DenseMultilinearExtension::from_evaluations_vec(
-                num_var, eq_b_zi,
-            ))
+        // \tilde g_i(b) = eq(t, i) * f_i(b)
+        let timer = start_timer!(|| format!("compute tilde g for {} points", points.len()));
+        // combine the polynomials that have same opening point first to reduce the
+        // cost of sum check later.
+        let point_indices = points
+            .iter()
+            .fold(BTreeMap::<_, _>::new(), |mut indices, point| {
+                let idx = indices.len();
+                indices.entry(*point).or_insert(idx);
+                indices
+            });
+        let deduped_points = BTreeMap::from_iter(
+            point_indices
+                .iter()
+                .map(|(point, idx)| (*idx, (*point).clone())),
+        )
+        .into_values()
+        .collect::<Vec<_>>();
+        let merged_tilde_gs = polynomials
+            .iter()
+            .zip(points.iter())
+            .zip(eq_t_i_list.iter())
+            .fold(
+                iter::repeat_with(DenseMultilinearExtension::zero)
+                    .map(Arc::new)
+                    .take(point_indices.len())
+                    .collect::<Vec<_>>(),
+                |mut merged_tilde_gs, ((poly, point), coeff)| {
+                    *Arc::make_mut(&mut merged_tilde_gs[point_indices[*point]]) +=
+                        (*coeff, poly.deref());
+                    merged_tilde_gs
+                },
+            );
+        end_timer!(timer);
+
+        let timer = start_timer!(|| format!("compute tilde eq for {} points", points.len()));
+        let tilde_eqs: Vec<_> = deduped_points
+            .iter()
+            .map(|point| {
+                let eq_b_zi = build_eq_x_r_vec(point).unwrap();
+                Arc::new(DenseMultilinearExtension::from_evaluations_vec(
+                    num_var, eq_b_zi,
+                ))
+            })
+            .collect();
+        end_timer!(timer);
+
+        // built the virtual polynomial for SumCheck
+        let step = start_timer!(|| "add mle");
+        let mut sum_check_vp = VirtualPolynomial::new(num_var);
+        for (merged_tilde_g, tilde_eq) in merged_tilde_gs.iter().zip(tilde_eqs) {
+            sum_check_vp.add_mle_list([merged_tilde_g.clone(), tilde_eq], F::one())?;
+        }
+        end_timer!(step);
+
+        let rounds = SumCheckRounds::new(&sum_check_vp, transcript).map_err(sum_check_failed)?;
+
+        Ok(Self {
+            merged_tilde_gs,
+            deduped_points,
+            evals: evals.to_vec(),
+            rounds,
         })
-        .collect();
-    end_timer!(timer);
+    }
 
-    // built the virtual polynomial for SumCheck
-    let timer = start_timer!(|| format!("sum check prove of {} variables", num_var));
+    /// Total number of rounds of the sum check.
+    pub fn num_rounds(&self) -> usize {
+        self.rounds.num_rounds()
+    }
 
-    let step = start_timer!(|| "add mle");
-    let mut sum_check_vp = VirtualPolynomial::new(num_var);
-    for (merged_tilde_g, tilde_eq) in merged_tilde_gs.iter().zip(tilde_eqs.into_iter()) {
-        sum_check_vp.add_mle_list([merged_tilde_g.clone(), tilde_eq], E::ScalarField::one())?;
+    /// Whether all the rounds of the sum check have been proven.
+    pub fn is_finished(&self) -> bool {
+        self.rounds.is_finished()
     }
-    end_timer!(step);
 
-    let proof = match <PolyIOP<E::ScalarField> as SumCheck<E::ScalarField>>::prove(
-        &sum_check_vp,
-        transcript,
-    ) {
-        Ok(p) => p,
-        Err(_e) => {
-            // cannot wrap IOPError with PCSError due to cyclic dependency
+    /// Prove the next round of the sum check.
+    pub fn prove_round(&mut self, transcript: &mut IOPTranscript<F>) -> Result<(), PCSError> {
+        self.rounds
+            .prove_round(transcript)
+            .map_err(sum_check_failed)
+    }
+
+    /// Steps 6-7 of the batch opening: open g'(X) at the point a2 of the sum
+    /// check. Must be called after the last round.
+    pub fn finish<E, PCS>(
+        self,
+        prover_param: &PCS::ProverParam,
+    ) -> Result<BatchProof<E, PCS>, PCSError>
+    where
+        E: Pairing<ScalarField = F>,
+        PCS: PolynomialCommitmentScheme<
+            E,
+            Polynomial = Arc<DenseMultilinearExtension<F>>,
+            Point = Vec<F>,
+            Evaluation = F,
+        >,
+    {
+        if !self.is_finished() {
             return Err(PCSError::InvalidProver(
-                "Sumcheck in batch proving Failed".to_string(),
+                "the sum check of the batch opening is not finished".to_string(),
             ));
-        },
-    };
-
-    end_timer!(timer);
+        }
+        let proof = self.rounds.finish();
+        let num_var = proof.point.len();
+
+        // a2 := sumcheck's point
+        let a2 = &proof.point[..num_var];
+
+        // build g'(X) = \sum_i=1..k \tilde eq_i(a2) * \tilde g_i(X) where (a2) is the
+        // sumcheck's point \tilde eq_i(a2) = eq(a2, point_i)
+        let step = start_timer!(|| "evaluate at a2");
+        let mut g_prime = Arc::new(DenseMultilinearExtension::zero());
+        for (merged_tilde_g, point) in self.merged_tilde_gs.iter().zip(self.deduped_points.iter()) {
+            let eq_i_a2 = eq_eval(a2, point)?;
+            *Arc::make_mut(&mut g_prime) += (eq_i_a2, merged_tilde_g.deref());
+        }
+        end_timer!(step);
+
+        let step = start_timer!(|| "pcs open");
+        let (g_prime_proof, _g_prime_eval) =
+            PCS::open(prover_param, &g_prime, a2.to_vec().as_ref())?;
+        // assert_eq!(g_prime_eval, tilde_g_eval);
+        end_timer!(step);
+
+        Ok(BatchProof {
+            sum_check_proof: proof,
+            f_i_eval_at_point_i: self.evals,
+            g_prime_proof,
+        })
+    }
+}
 
-    // a2 := sumcheck's point
-    let a2 = &proof.point[..num_var];
+// The merged \tilde g_i are serialized through the polynomials they point
+// to, as in `VirtualPolynomial`.
+impl<F: PrimeField> CanonicalSerialize for MultiOpenRounds<F> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.merged_tilde_gs
+            .len()
+            .serialize_with_mode(&mut writer, compress)?;
+        for merged_tilde_g in self.merged_tilde_gs.iter() {
+            merged_tilde_g
+                .as_ref()
+                .serialize_with_mode(&mut writer, compress)?;
+        }
+        self.deduped_points
+            .serialize_with_mode(&mut writer, compress)?;
+        self.evals.serialize_with_mode(&mut writer, compress)?;
+        self.rounds.serialize_with_mode(&mut writer, compress)
+    }
 
-    // build g'(X) = \sum_i=1..k \tilde eq_i(a2) * \tilde g_i(X) where (a2) is the
-    // sumcheck's point \tilde eq_i(a2) = eq(a2, point_i)
-    let step = start_timer!(|| "evaluate at a2");
-    let mut g_prime = Arc::new(DenseMultilinearExtension::zero());
-    for (merged_tilde_g, point) in merged_tilde_gs.iter().zip(deduped_points.iter()) {
-        let eq_i_a2 = eq_eval(a2, point)?;
-        *Arc::make_mut(&mut g_prime) += (eq_i_a2, merged_tilde_g.deref());
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.merged_tilde_gs.len().serialized_size(compress)
+            + self
+                .merged_tilde_gs
+                .iter()
+                .map(|merged_tilde_g| merged_tilde_g.as_ref().serialized_size(compress))
+                .sum::<usize>()
+            + self.deduped_points.serialized_size(compress)
+            + self.evals.serialized_size(compress)
+            + self.rounds.serialized_size(compress)
     }
-    end_timer!(step);
+}
 
-    let step = start_timer!(|| "pcs open");
-    let (g_prime_proof, _g_prime_eval) = PCS::open(prover_param, &g_prime, a2.to_vec().as_ref())?;
-    // assert_eq!(g_prime_eval, tilde_g_eval);
-    end_timer!(step);
+impl<F: PrimeField> Valid for MultiOpenRounds<F> {
+    fn check(&self) -> Result<(), SerializationError> {
+        if self.merged_tilde_gs.len() != self.deduped_points.len() {
+            return Err(SerializationError::InvalidData);
+        }
+        for merged_tilde_g in self.merged_tilde_gs.iter() {
+            merged_tilde_g.check()?;
+        }
+        self.rounds.check()
+    }
+}
 
-    let step = start_timer!(|| "evaluate fi(pi)");
-    end_timer!(step);
-    end_timer!(open_timer);
+impl<F: PrimeField> CanonicalDeserialize for MultiOpenRounds<F> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let merged_tilde_gs = Vec::<DenseMultilinearExtension<F>>::deserialize_with_mode(
+            &mut reader,
+            compress,
+            validate,
+        )?
+        .into_iter()
+        .map(Arc::new)
+        .collect();
+        let deduped_points = Vec::deserialize_with_mode(&mut reader, compress, validate)?;
+        let evals = Vec::deserialize_with_mode(&mut reader, compress, validate)?;
+        let rounds = SumCheckRounds::deserialize_with_mode(&mut reader, compress, validate)?;
+        let rounds = Self {
+            merged_tilde_gs,
+            deduped_points,
+            evals,
+            rounds,
+        };
+        if validate == Validate::Yes {
+            rounds.check()?;
+        }
+        Ok(rounds)
+    }
+}
 
-    Ok(BatchProof {
-        sum_check_proof: proof,
-        f_i_eval_at_point_i: evals.to_vec(),
-        g_prime_proof,
-    })
+/// The sum check of a batch opening failed.
+fn sum_check_failed(_e: PolyIOPErrors) -> PCSError {
+    // cannot wrap IOPError with PCSError due to cyclic dependency
+    PCSError::InvalidProver("Sumcheck in batch proving Failed".to_string())
 }
 
 /// Steps:
@@ -199,14 +477,83 @@ where
 {
     let open_timer = start_timer!(|| "batch verification");
 
-    // TODO: sanity checks
+    let (g_prime_commit, a2, tilde_g_eval) =
+        batch_verify_reduce::<E, PCS>(f_i_commitments, points, proof, transcript)?;
 
-    let k = f_i_commitments.len();
+    // verify commitment
+    let res = PCS::verify(
+        verifier_param,
+        &g_prime_commit,
+        &a2,
+        &tilde_g_eval,
+        &proof.g_prime_proof,
+    )?;
+
+    end_timer!(open_timer);
+    Ok(res)
+}
+
+/// Steps 1-3 of `batch_verify_internal`: reduce the batch opening to a single
+/// opening claim, i.e., the commitment to g', the point a2 and the expected
+/// evaluation g'(a2).
+#[allow(clippy::type_complexity)]
+pub(crate) fn batch_verify_reduce<E, PCS>(
+    f_i_commitments: &[Commitment<E>],
+    points: &[PCS::Point],
+    proof: &BatchProof<E, PCS>,
+    transcript: &mut IOPTranscript<E::ScalarField>,
+) -> Result<(Commitment<E>, Vec<E::ScalarField>, E::ScalarField), PCSError>
+where
+    E: Pairing,
+    PCS: PolynomialCommitmentScheme<
+        E,
+        Polynomial = Arc<DenseMultilinearExtension<E::ScalarField>>,
+        Point = Vec<E::ScalarField>,
+        Evaluation = E::ScalarField,
+        Commitment = Commitment<E>,
+    >,
+{
+    if f_i_commitments.is_empty()
+        || f_i_commitments.len() != points.len()
+        || f_i_commitments.len() != proof.f_i_eval_at_point_i.len()
+    {
+        return Err(PCSError::InvalidProof(format!(
+            "commitments ({}), points ({}) and evaluations ({}) do not match",
+            f_i_commitments.len(),
+            points.len(),
+            proof.f_i_eval_at_point_i.len()
+        )));
+    }
+
+    // deduplicate the claims exactly as the prover did; identical claims must
+    // come with identical evaluations
+    let mut claims = vec![];
+    if transcript.is_legacy() {
+        claims.extend(0..f_i_commitments.len());
+    } else {
+        let representatives = dedup_claims(points, |i, j| f_i_commitments[i] == f_i_commitments[j]);
+        for (i, &rep) in representatives.iter().enumerate() {
+            if proof.f_i_eval_at_point_i[i] != proof.f_i_eval_at_point_i[rep] {
+                return Err(PCSError::InvalidProof(format!(
+                    "claims {} and {} are identical but have different evaluations",
+                    rep, i
+                )));
+            }
+            if rep == i {
+                claims.push(i);
+            }
+        }
+        for &i in claims.iter() {
+            transcript.append_field_element(b"eval", &proof.f_i_eval_at_point_i[i])?;
+        }
+    }
+
+    let k = claims.len();
     let ell = log2(k) as usize;
     let num_var = proof.sum_check_proof.point.len();
 
     // challenge point t
-    let t = transcript.get_and_append_challenge_vectors("t".as_ref(), ell)?;
+    let t = batching_challenge(transcript, ell)?;
 
     // sum check point (a2)
     let a2 = &proof.sum_check_proof.point[..num_var];
@@ -218,9 +565,9 @@ where
     let mut scalars = vec![];
     let mut bases = vec![];
 
-    for (i, point) in points.iter().enumerate() {
-        let eq_i_a2 = eq_eval(a2, point)?;
-        scalars.push(eq_i_a2 * eq_t_list[i]);
+    for (&i, eq_t_i) in claims.iter().zip(eq_t_list.iter()) {
+        let eq_i_a2 = eq_eval(a2, &points[i])?;
+        scalars.push(eq_i_a2 * eq_t_i);
         bases.push(f_i_commitments[i].0);
     }
     let g_prime_commit = E::G1::msm_unchecked(&bases, &scalars);
@@ -228,12 +575,14 @@ where
 
     // ensure \sum_i eq(t, <i>) * f_i_evals matches the sum via SumCheck
     let mut sum = E::ScalarField::zero();
-    for (i, &e) in eq_t_list.iter().enumerate().take(k) {
+    for (&i, &e) in claims.iter().zip(eq_t_list.iter()) {
         sum += e * proof.f_i_eval_at_point_i[i];
     }
     let aux_info = VPAuxInfo {
         max_degree: 2,
         num_variables: num_var,
+        per_variable_degree: vec![],
+        is_sparse: false,
         phantom: PhantomData,
     };
     let subclaim = match <PolyIOP<E::ScalarField> as SumCheck<E::ScalarField>>::verify(
@@ -252,17 +601,24 @@ where
     };
     let tilde_g_eval = subclaim.expected_evaluation;
 
-    // verify commitment
-    let res = PCS::verify(
-        verifier_param,
-        &Commitment(g_prime_commit.into_affine()),
-        a2.to_vec().as_ref(),
-        &tilde_g_eval,
-        &proof.g_prime_proof,
-    )?;
+    Ok((
+        Commitment(g_prime_commit.into_affine()),
+        a2.to_vec(),
+        tilde_g_eval,
+    ))
+}
 
-    end_timer!(open_timer);
-    Ok(res)
+/// Sample the challenge point `t` that batches the claims, as an opening
+/// challenge.
+fn batching_challenge<F: PrimeField>(
+    transcript: &mut IOPTranscript<F>,
+    ell: usize,
+) -> Result<Vec<F>, PCSError> {
+    Ok(transcript
+        .get_opening_challenges(b"t", ell)?
+        .into_iter()
+        .map(|t| t.0)
+        .collect())
 }
 
 #[cfg(test)]
@@ -348,4 +704,93 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_multi_open_duplicate_claims() -> Result<(), PCSError> {
+        let mut rng = test_rng();
+        let nv = 4;
+        let ml_params = MultilinearUniversalParams::<E>::gen_srs_for_testing(&mut rng, nv)?;
+        let (ml_ck, ml_vk) = ml_params.trim(nv)?;
+
+        let f = Arc::new(DenseMultilinearExtension::rand(nv, &mut rng));
+        let g = Arc::new(DenseMultilinearExtension::rand(nv, &mut rng));
+        // a copy of `f` that is not the same `Arc`
+        let f_copy = Arc::new(f.deref().clone());
+        let p = (0..nv).map(|_| Fr::rand(&mut rng)).collect::<Vec<Fr>>();
+        let q = (0..nv).map(|_| Fr::rand(&mut rng)).collect::<Vec<Fr>>();
+
+        // (f, p) is claimed three times, (f, q) and (g, p) once each
+        let polys = vec![f.clone(), g.clone(), f.clone(), f.clone(), f_copy];
+        let points = vec![p.clone(), p.clone(), p.clone(), q, p];
+        let evals = polys
+            .iter()
+            .zip(points.iter())
+            .map(|(f, p)| f.evaluate(p).unwrap())
+            .collect::<Vec<_>>();
+        let commitments = polys
+            .iter()
+            .map(|poly| MultilinearKzgPCS::commit(&ml_ck, poly).unwrap())
+            .collect::<Vec<_>>();
+
+        let mut transcript = IOPTranscript::new("test transcript".as_ref());
+        transcript.append_field_element("init".as_ref(), &Fr::zero())?;
+        let batch_proof = multi_open_internal::<E, MultilinearKzgPCS<E>>(
+            &ml_ck,
+            &polys,
+            &points,
+            &evals,
+            &mut transcript,
+        )?;
+
+        // good path: duplicated claims are only counted once
+        let mut transcript = IOPTranscript::new("test transcript".as_ref());
+        transcript.append_field_element("init".as_ref(), &Fr::zero())?;
+        assert!(batch_verify_internal::<E, MultilinearKzgPCS<E>>(
+            &ml_vk,
+            &commitments,
+            &points,
+            &batch_proof,
+            &mut transcript
+        )?);
+
+        // bad path: a false claim is cancelled against a duplicated true
+        // claim. Without deduplication the batching coefficients eq(t, i)
+        // would be derived from the transcript before the evaluations, and
+        // the evaluations of (f, p) at index 0 and 2 can be shifted by
+        // d * eq(t, 2) and -d * eq(t, 0) without changing the batched sum.
+        let d = Fr::rand(&mut rng);
+        let mut transcript = IOPTranscript::<Fr>::new("test transcript".as_ref());
+        transcript.append_field_element("init".as_ref(), &Fr::zero())?;
+        let t = batching_challenge(&mut transcript, log2(polys.len()) as usize)?;
+        let eq_t_list = build_eq_x_r_vec(&t)?;
+        let mut bad_proof = batch_proof.clone();
+        bad_proof.f_i_eval_at_point_i[0] += d * eq_t_list[2];
+        bad_proof.f_i_eval_at_point_i[2] -= d * eq_t_list[0];
+        let mut transcript = IOPTranscript::new("test transcript".as_ref());
+        transcript.append_field_element("init".as_ref(), &Fr::zero())?;
+        assert!(batch_verify_internal::<E, MultilinearKzgPCS<E>>(
+            &ml_vk,
+            &commitments,
+            &points,
+            &bad_proof,
+            &mut transcript
+        )
+        .is_err());
+
+        // bad path: the prover refuses inconsistent duplicated claims
+        let mut bad_evals = evals;
+        bad_evals[4] += d;
+        let mut transcript = IOPTranscript::new("test transcript".as_ref());
+        transcript.append_field_element("init".as_ref(), &Fr::zero())?;
+        assert!(multi_open_internal::<E, MultilinearKzgPCS<E>>(
+            &ml_ck,
+            &polys,
+            &points,
+            &bad_evals,
+            &mut transcript,
+        )
+        .is_err());
+
+        Ok(())
+    }
 }
@@ -25,16 +25,17 @@ use ark_poly::{DenseMultilinearExtension, MultilinearExtension};
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::{
     borrow::Borrow, end_timer, format, marker::PhantomData, rand::Rng, start_timer,
-    string::ToString, sync::Arc, vec, vec::Vec, One, Zero,
+    string::ToString, vec, vec::Vec, One, UniformRand, Zero,
 };
-use std::ops::Mul;
+use std::{ops::Mul, sync::Arc};
 // use batching::{batch_verify_internal, multi_open_internal};
 use srs::{MultilinearProverParam, MultilinearUniversalParams, MultilinearVerifierParam};
 use transcript::IOPTranscript;
 
-use self::batching::{batch_verify_internal, multi_open_internal};
+use self::batching::{batch_verify_internal, batch_verify_reduce, multi_open_internal};
 
 /// KZG Polynomial Commitment Scheme on multilinear polynomials.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct MultilinearKzgPCS<E: Pairing> {
     #[doc(hidden)]
     phantom: PhantomData<E>,
@@ -47,6 +48,41 @@ pub struct MultilinearKzgProof<E: Pairing> {
     pub proofs: Vec<E::G1Affine>,
 }
 
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug, PartialEq, Eq)]
+/// proof of openings of a single polynomial at several points, see
+/// [`MultilinearKzgPCS::open_at_points`]
+pub struct MultilinearKzgMultiOpenProof<E: Pairing> {
+    /// The proof of opening at each point
+    pub proofs: Vec<MultilinearKzgProof<E>>,
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug, PartialEq, Eq)]
+/// proof of opening of a hiding commitment, see
+/// [`MultilinearKzgPCS::open_hiding`]
+pub struct MultilinearKzgHidingProof<E: Pairing> {
+    /// The proof of opening, with each quotient blinded
+    pub proof: MultilinearKzgProof<E>,
+    /// The blinding of the commitment, less those of the quotients
+    pub blinding_proof: E::G1Affine,
+}
+
+/// A pairing check that has been prepared but not yet evaluated:
+///
+///   \prod_i e(pi_i, h^{t_i - a_i}) * e(g^value / comm, h) == 1
+///
+/// Deferring the check allows the caller to fold the pairing work of many
+/// openings into a single multi-pairing, see
+/// [`MultilinearKzgPairingCheck::batch_check`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MultilinearKzgPairingCheck<E: Pairing> {
+    /// the quotient evaluations paired with `h^{t_i - a_i}`
+    pub(crate) quotients: Vec<(E::G1Affine, E::G2Affine)>,
+    /// `g^value / comm`
+    pub(crate) eval_term: E::G1Affine,
+    /// generator of G2
+    pub(crate) h: E::G2Affine,
+}
+
 impl<E: Pairing> PolynomialCommitmentScheme<E> for MultilinearKzgPCS<E> {
     // Parameters
     type ProverParam = MultilinearProverParam<E>;
@@ -190,6 +226,521 @@ impl<E: Pairing> PolynomialCommitmentScheme<E> for MultilinearKzgPCS<E> {
     }
 }
 
+impl<E: Pairing> MultilinearKzgPCS<E> {
+    /// Same as `batch_verify`, except that the final pairing check is returned
+    /// to the caller instead of being evaluated.
+    ///
+    /// All the non-pairing checks (i.e., the sum check of the batch opening)
+    /// are still performed, and an error is returned if they fail.
+    pub fn batch_verify_deferred(
+        verifier_param: &MultilinearVerifierParam<E>,
+        commitments: &[Commitment<E>],
+        points: &[Vec<E::ScalarField>],
+        batch_proof: &BatchProof<E, Self>,
+        transcript: &mut IOPTranscript<E::ScalarField>,
+    ) -> Result<MultilinearKzgPairingCheck<E>, PCSError> {
+        let (g_prime_commit, a2, tilde_g_eval) =
+            batch_verify_reduce::<E, Self>(commitments, points, batch_proof, transcript)?;
+        prepare_verify_internal(
+            verifier_param,
+            &g_prime_commit,
+            &a2,
+            &tilde_g_eval,
+            &batch_proof.g_prime_proof,
+        )
+    }
+
+    /// Open `polynomial` at each of `points`, e.g., at `0`, `1` and the point
+    /// of a sum check. Returns the proof along with the evaluations.
+    ///
+    /// The proof holds the quotients of each opening, which
+    /// [`Self::verify_at_points`] checks with `num_var + 1` pairings in
+    /// total, instead of `num_var + 1` per point.
+    pub fn open_at_points(
+        prover_param: &MultilinearProverParam<E>,
+        polynomial: &DenseMultilinearExtension<E::ScalarField>,
+        points: &[Vec<E::ScalarField>],
+    ) -> Result<(MultilinearKzgMultiOpenProof<E>, Vec<E::ScalarField>), PCSError> {
+        let open_timer = start_timer!(|| format!("open at {} points", points.len()));
+        if points.is_empty() {
+            return Err(PCSError::InvalidParameters(
+                "no point to open at".to_string(),
+            ));
+        }
+        let (proofs, evals) = points
+            .iter()
+            .map(|point| open_internal(prover_param, polynomial, point))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .unzip();
+        end_timer!(open_timer);
+        Ok((MultilinearKzgMultiOpenProof { proofs }, evals))
+    }
+
+    /// Verifies that `evals[j]` is the evaluation at `points[j]` of the
+    /// polynomial committed inside `commitment`, for all `j`.
+    ///
+    /// The openings are combined with the powers of a challenge `gamma`
+    /// derived from all of them, so this function takes `num_var + 1`
+    /// pairings and `num_var + 1` MSMs.
+    pub fn verify_at_points(
+        verifier_param: &MultilinearVerifierParam<E>,
+        commitment: &Commitment<E>,
+        points: &[Vec<E::ScalarField>],
+        evals: &[E::ScalarField],
+        proof: &MultilinearKzgMultiOpenProof<E>,
+    ) -> Result<bool, PCSError> {
+        let verify_timer = start_timer!(|| format!("verify at {} points", points.len()));
+        if points.is_empty() || points.len() != evals.len() || points.len() != proof.proofs.len() {
+            return Err(PCSError::InvalidParameters(format!(
+                "points ({}), evaluations ({}) and proofs ({}) do not match",
+                points.len(),
+                evals.len(),
+                proof.proofs.len()
+            )));
+        }
+        let num_var = points[0].len();
+        if num_var > verifier_param.num_vars {
+            return Err(PCSError::InvalidParameters(format!(
+                "point length ({}) exceeds param limit ({})",
+                num_var, verifier_param.num_vars
+            )));
+        }
+        if points.iter().any(|point| point.len() != num_var)
+            || proof
+                .proofs
+                .iter()
+                .any(|proof| proof.proofs.len() != num_var)
+        {
+            return Err(PCSError::InvalidParameters(
+                "points or proofs of different lengths".to_string(),
+            ));
+        }
+
+        let mut transcript =
+            IOPTranscript::<E::ScalarField>::new(b"multilinear kzg multi-point opening");
+        transcript.append_serializable_element(b"commitment", commitment)?;
+        for ((point, eval), proof) in points.iter().zip(evals.iter()).zip(proof.proofs.iter()) {
+            transcript.append_serializable_element(b"point", point)?;
+            transcript.append_field_element(b"eval", eval)?;
+            transcript.append_serializable_element(b"proof", proof)?;
+        }
+        let gamma = transcript.get_and_append_challenge(b"gamma")?;
+        let mut powers = vec![E::ScalarField::one()];
+        for _ in 1..points.len() {
+            let next = powers[powers.len() - 1] * gamma;
+            powers.push(next);
+        }
+
+        let commitments = vec![*commitment; points.len()];
+        let res = check_combined_openings(
+            verifier_param,
+            &commitments,
+            points,
+            evals,
+            &proof.proofs,
+            &powers,
+        )?;
+        end_timer!(verify_timer);
+        Ok(res)
+    }
+
+    /// Verifies that `evals[j]` is the evaluation at `points[j]` of the
+    /// polynomial committed inside `commitments[j]`, given its opening proof
+    /// `proofs[j]`, for all `j`.
+    ///
+    /// The openings are combined with random scalars drawn from `transcript`
+    /// once it has absorbed all of them, so this function takes a pairing per
+    /// variable of the parameters used by the points, plus one, instead of
+    /// `num_var + 1` per opening.
+    pub fn batch_verify_openings(
+        verifier_param: &MultilinearVerifierParam<E>,
+        commitments: &[Commitment<E>],
+        points: &[Vec<E::ScalarField>],
+        evals: &[E::ScalarField],
+        proofs: &[MultilinearKzgProof<E>],
+        transcript: &mut IOPTranscript<E::ScalarField>,
+    ) -> Result<bool, PCSError> {
+        let verify_timer = start_timer!(|| format!("batch verify {} openings", points.len()));
+        if commitments.len() != points.len()
+            || points.len() != evals.len()
+            || points.len() != proofs.len()
+        {
+            return Err(PCSError::InvalidParameters(format!(
+                "commitments ({}), points ({}), evaluations ({}) and proofs ({}) do not match",
+                commitments.len(),
+                points.len(),
+                evals.len(),
+                proofs.len()
+            )));
+        }
+
+        for (((commitment, point), eval), proof) in commitments
+            .iter()
+            .zip(points.iter())
+            .zip(evals.iter())
+            .zip(proofs.iter())
+        {
+            transcript.append_serializable_element(b"commitment", commitment)?;
+            transcript.append_serializable_element(b"point", point)?;
+            transcript.append_field_element(b"eval", eval)?;
+            transcript.append_serializable_element(b"proof", proof)?;
+        }
+        let randomizers = transcript.get_n_challenges(points.len(), b"batch openings")?;
+
+        let res = check_combined_openings(
+            verifier_param,
+            commitments,
+            points,
+            evals,
+            proofs,
+            &randomizers,
+        )?;
+        end_timer!(verify_timer);
+        Ok(res)
+    }
+
+    /// Open the linear combination `\sum_i alpha_i * poly_i` of `polys` at
+    /// `point`, with a single proof for the whole combination. Returns the
+    /// proof along with the evaluation of the combination.
+    ///
+    /// The polynomials must have the same number of variables.
+    pub fn open_lc(
+        prover_param: &MultilinearProverParam<E>,
+        polys: &[(E::ScalarField, &DenseMultilinearExtension<E::ScalarField>)],
+        point: &[E::ScalarField],
+    ) -> Result<(MultilinearKzgProof<E>, E::ScalarField), PCSError> {
+        let open_timer = start_timer!(|| format!("open combination of {} mles", polys.len()));
+        let num_vars = match polys.first() {
+            Some((_, poly)) => poly.num_vars,
+            None => {
+                return Err(PCSError::InvalidParameters(
+                    "no polynomial to combine".to_string(),
+                ))
+            },
+        };
+        if polys.iter().any(|(_, poly)| poly.num_vars != num_vars) {
+            return Err(PCSError::InvalidParameters(
+                "polynomials of different numbers of variables".to_string(),
+            ));
+        }
+
+        let mut evaluations = vec![E::ScalarField::zero(); 1 << num_vars];
+        for (alpha, poly) in polys.iter() {
+            for (acc, eval) in evaluations.iter_mut().zip(poly.evaluations.iter()) {
+                *acc += *alpha * eval;
+            }
+        }
+        let combination = DenseMultilinearExtension::from_evaluations_vec(num_vars, evaluations);
+        let res = open_internal(prover_param, &combination, point);
+        end_timer!(open_timer);
+        res
+    }
+
+    /// Verifies that `value` is the evaluation at `point` of the linear
+    /// combination `\sum_i alpha_i * poly_i` of the polynomials committed
+    /// inside `terms`, given a proof generated by [`Self::open_lc`].
+    ///
+    /// The commitment to the combination is the same combination of the
+    /// commitments, so this function takes a MSM of the size of `terms` on
+    /// top of [`PolynomialCommitmentScheme::verify`].
+    pub fn verify_lc(
+        verifier_param: &MultilinearVerifierParam<E>,
+        terms: &[(E::ScalarField, Commitment<E>)],
+        point: &[E::ScalarField],
+        value: &E::ScalarField,
+        proof: &MultilinearKzgProof<E>,
+    ) -> Result<bool, PCSError> {
+        if terms.is_empty() {
+            return Err(PCSError::InvalidParameters(
+                "no commitment to combine".to_string(),
+            ));
+        }
+        let (alphas, bases): (Vec<_>, Vec<_>) = terms
+            .iter()
+            .map(|(alpha, commitment)| (*alpha, commitment.0))
+            .unzip();
+        let commitment = Commitment(E::G1::msm_unchecked(&bases, &alphas).into_affine());
+        verify_internal(verifier_param, &commitment, point, value, proof)
+    }
+
+    /// Generate a hiding commitment for a polynomial: the commitment to
+    /// `poly(X) + blinding * Y`, where `Y` is a variable of the SRS that
+    /// `poly` does not use. Hence the parameters must support one more
+    /// variable than `poly`.
+    ///
+    /// With `blinding` uniformly random, the commitment is uniformly random
+    /// too, whatever `poly`.
+    pub fn commit_hiding(
+        prover_param: &MultilinearProverParam<E>,
+        poly: &Arc<DenseMultilinearExtension<E::ScalarField>>,
+        blinding: &E::ScalarField,
+    ) -> Result<Commitment<E>, PCSError> {
+        let blinding_level = hiding_level(prover_param.num_vars, poly.num_vars)?;
+        let commitment = Self::commit(prover_param, poly)?;
+        let blinded = variable_base(prover_param, blinding_level) * blinding + commitment.0;
+        Ok(Commitment(blinded.into_affine()))
+    }
+
+    /// On input a polynomial `p`, the blinding of its hiding commitment and
+    /// a point `point`, outputs a proof for the same, along with the
+    /// evaluation.
+    ///
+    /// Each quotient `pi_i` is blinded as `pi_i * g^{t_Y s_i}` for a random
+    /// `s_i`, so the proof reveals nothing but the evaluation, and the
+    /// blinding proof `g^{blinding - \sum_i s_i (t_i - point_i)}` makes up
+    /// for it in the pairing check.
+    pub fn open_hiding<R: Rng>(
+        prover_param: &MultilinearProverParam<E>,
+        polynomial: &DenseMultilinearExtension<E::ScalarField>,
+        point: &[E::ScalarField],
+        blinding: &E::ScalarField,
+        rng: &mut R,
+    ) -> Result<(MultilinearKzgHidingProof<E>, E::ScalarField), PCSError> {
+        let open_timer =
+            start_timer!(|| format!("open hiding mle with {} variable", polynomial.num_vars));
+        let blinding_level = hiding_level(prover_param.num_vars, polynomial.num_vars)?;
+        let (proof, eval) = open_internal(prover_param, polynomial, point)?;
+
+        let base = variable_base(prover_param, blinding_level);
+        let s: Vec<_> = (0..point.len())
+            .map(|_| E::ScalarField::rand(rng))
+            .collect();
+        let proofs: Vec<E::G1> = proof
+            .proofs
+            .iter()
+            .zip(s.iter())
+            .map(|(pi, s_i)| base * s_i + pi)
+            .collect();
+        let offset: E::ScalarField = s
+            .iter()
+            .zip(point.iter())
+            .map(|(s_i, z_i)| *s_i * z_i)
+            .sum();
+        let mut blinding_proof = prover_param.g.mul(*blinding + offset);
+        for (i, s_i) in s.iter().enumerate() {
+            blinding_proof -= variable_base(prover_param, blinding_level + 1 + i) * s_i;
+        }
+        end_timer!(open_timer);
+
+        Ok((
+            MultilinearKzgHidingProof {
+                proof: MultilinearKzgProof {
+                    proofs: E::G1::normalize_batch(&proofs),
+                },
+                blinding_proof: blinding_proof.into_affine(),
+            },
+            eval,
+        ))
+    }
+
+    /// Verifies that `value` is the evaluation at `point` of the polynomial
+    /// committed inside the hiding commitment `commitment`.
+    ///
+    /// This function takes `num_var + 2` pairings, one more than
+    /// [`PolynomialCommitmentScheme::verify`].
+    pub fn verify_hiding(
+        verifier_param: &MultilinearVerifierParam<E>,
+        commitment: &Commitment<E>,
+        point: &[E::ScalarField],
+        value: &E::ScalarField,
+        proof: &MultilinearKzgHidingProof<E>,
+    ) -> Result<bool, PCSError> {
+        let verify_timer = start_timer!(|| "verify hiding");
+        let blinding_level = hiding_level(verifier_param.num_vars, point.len())?;
+        if proof.proof.proofs.len() != point.len() {
+            return Err(PCSError::InvalidProof(format!(
+                "{} quotients for a point of length {}",
+                proof.proof.proofs.len(),
+                point.len()
+            )));
+        }
+        let mut check =
+            prepare_verify_internal(verifier_param, commitment, point, value, &proof.proof)?;
+        check
+            .quotients
+            .push((proof.blinding_proof, verifier_param.h_mask[blinding_level]));
+        let res = check.check();
+        end_timer!(verify_timer);
+        Ok(res)
+    }
+}
+
+/// Check the openings `proofs[j]` of `commitments[j]` at `points[j]` to
+/// `evals[j]`, scaled by `randomizers[j]`, with a single multi-pairing:
+///
+///   \prod_k e(\prod_{j,i} pi_{j,i}^{r_j}, h^{t_k})
+///     * e(\prod_j (g^{v_j} / comm_j / \prod_i pi_{j,i}^{z_{j,i}})^{r_j}, h) == 1
+///
+/// where the first product is over the quotients `pi_{j,i}` of the variables
+/// `i` at level `k` of the parameters. The inputs must have the same length.
+fn check_combined_openings<E: Pairing>(
+    verifier_param: &MultilinearVerifierParam<E>,
+    commitments: &[Commitment<E>],
+    points: &[Vec<E::ScalarField>],
+    evals: &[E::ScalarField],
+    proofs: &[MultilinearKzgProof<E>],
+    randomizers: &[E::ScalarField],
+) -> Result<bool, PCSError> {
+    let prepare_inputs_timer = start_timer!(|| "prepare pairing inputs");
+
+    // the bases and the scalars of the MSM paired with h^{t_k}, at level k
+    let mut levels =
+        vec![(Vec::<E::G1Affine>::new(), Vec::<E::ScalarField>::new()); verifier_param.num_vars];
+    let mut bases = vec![verifier_param.g];
+    let mut scalars: Vec<E::ScalarField> = vec![evals
+        .iter()
+        .zip(randomizers.iter())
+        .map(|(eval, r)| *eval * r)
+        .sum()];
+    for (((commitment, point), proof), r) in commitments
+        .iter()
+        .zip(points.iter())
+        .zip(proofs.iter())
+        .zip(randomizers.iter())
+    {
+        if point.len() > verifier_param.num_vars || proof.proofs.len() != point.len() {
+            return Err(PCSError::InvalidParameters(format!(
+                "an opening of {} quotients at a point of length {}, for params of {} variables",
+                proof.proofs.len(),
+                point.len(),
+                verifier_param.num_vars
+            )));
+        }
+        let ignored = verifier_param.num_vars - point.len();
+        bases.push(commitment.0);
+        scalars.push(-*r);
+        for (i, (z, pi)) in point.iter().zip(proof.proofs.iter()).enumerate() {
+            levels[ignored + i].0.push(*pi);
+            levels[ignored + i].1.push(*r);
+            bases.push(*pi);
+            scalars.push(-(*z * r));
+        }
+    }
+
+    let mut g1 = vec![];
+    let mut g2 = vec![];
+    for (k, (level_bases, level_scalars)) in levels.iter().enumerate() {
+        if !level_bases.is_empty() {
+            g1.push(E::G1::msm_unchecked(level_bases, level_scalars));
+            g2.push(E::G2Prepared::from(verifier_param.h_mask[k]));
+        }
+    }
+    g1.push(E::G1::msm_unchecked(&bases, &scalars));
+    g2.push(E::G2Prepared::from(verifier_param.h));
+    let g1: Vec<_> = E::G1::normalize_batch(&g1)
+        .into_iter()
+        .map(E::G1Prepared::from)
+        .collect();
+    end_timer!(prepare_inputs_timer);
+
+    Ok(E::multi_pairing(g1, g2) == ark_ec::pairing::PairingOutput(E::TargetField::one()))
+}
+
+/// The level of the SRS of the variable `Y` that blinds the hiding
+/// commitments to polynomials with `num_vars` variables, i.e., the variable
+/// just before theirs.
+fn hiding_level(param_num_vars: usize, num_vars: usize) -> Result<usize, PCSError> {
+    if num_vars >= param_num_vars {
+        return Err(PCSError::InvalidParameters(format!(
+            "hiding commitments to {} variables require params of at least {} variables, got {}",
+            num_vars,
+            num_vars + 1,
+            param_num_vars
+        )));
+    }
+    Ok(param_num_vars - num_vars - 1)
+}
+
+/// `g^{t_level}`, the commitment to the first variable of the polynomials of
+/// `num_vars - level` variables.
+fn variable_base<E: Pairing>(prover_param: &MultilinearProverParam<E>, level: usize) -> E::G1 {
+    prover_param.powers_of_g[level]
+        .evals
+        .iter()
+        .skip(1)
+        .step_by(2)
+        .fold(E::G1::zero(), |acc, p| acc + p)
+}
+
+impl<E: Pairing> MultilinearKzgPairingCheck<E> {
+    /// Evaluate the pairing check.
+    pub fn check(&self) -> bool {
+        let pairing_product_timer = start_timer!(|| "pairing product");
+
+        let mut pairings: Vec<_> = self
+            .quotients
+            .iter()
+            .map(|&(p, h)| (E::G1Prepared::from(p), E::G2Prepared::from(h)))
+            .collect();
+        pairings.push((
+            E::G1Prepared::from(self.eval_term),
+            E::G2Prepared::from(self.h),
+        ));
+
+        let ps = pairings.iter().map(|(p, _)| p.clone());
+        let hs = pairings.iter().map(|(_, h)| h.clone());
+
+        let res = E::multi_pairing(ps, hs) == ark_ec::pairing::PairingOutput(E::TargetField::one());
+        end_timer!(pairing_product_timer);
+        res
+    }
+
+    /// Evaluate a list of pairing checks with a single multi-pairing.
+    ///
+    /// Each check is scaled by a randomizer `r_i` derived from a transcript
+    /// over all the checks, so the result is deterministic and the
+    /// `e(., h)` terms of all checks merge into a single pairing. Returns
+    /// `true` iff (with overwhelming probability) every check passes.
+    ///
+    /// All checks must come from the same verifier parameters.
+    pub fn batch_check(checks: &[Self]) -> Result<bool, PCSError> {
+        let timer = start_timer!(|| format!("batch check {} pairing checks", checks.len()));
+        if checks.is_empty() {
+            return Ok(true);
+        }
+        let h = checks[0].h;
+        if checks.iter().any(|c| c.h != h) {
+            return Err(PCSError::InvalidParameters(
+                "pairing checks use different verifier parameters".to_string(),
+            ));
+        }
+
+        let mut transcript =
+            IOPTranscript::<E::ScalarField>::new(b"multilinear kzg batch pairing check");
+        for check in checks.iter() {
+            for (p, h) in check.quotients.iter() {
+                transcript.append_serializable_element(b"quotient", p)?;
+                transcript.append_serializable_element(b"h", h)?;
+            }
+            transcript.append_serializable_element(b"eval term", &check.eval_term)?;
+        }
+        // the first check does not need to be randomized
+        let mut randomizers = vec![E::ScalarField::one()];
+        randomizers.extend(transcript.get_and_append_challenge_vectors(b"r", checks.len() - 1)?);
+
+        let mut g1 = vec![];
+        let mut g2 = vec![];
+        for (check, r) in checks.iter().zip(randomizers.iter()) {
+            for &(p, h) in check.quotients.iter() {
+                g1.push(p * r);
+                g2.push(E::G2Prepared::from(h));
+            }
+        }
+        let eval_bases: Vec<_> = checks.iter().map(|c| c.eval_term).collect();
+        g1.push(E::G1::msm_unchecked(&eval_bases, &randomizers));
+        g2.push(E::G2Prepared::from(h));
+
+        let g1: Vec<_> = E::G1::normalize_batch(&g1)
+            .into_iter()
+            .map(E::G1Prepared::from)
+            .collect();
+        let res = E::multi_pairing(g1, g2) == ark_ec::pairing::PairingOutput(E::TargetField::one());
+        end_timer!(timer);
+        Ok(res)
+    }
+}
+
 /// On input a polynomial `p` and a point `point`, outputs a proof for the
 /// same. This function does not need to take the evaluation value as an
 /// input.
@@ -277,6 +828,22 @@ fn verify_internal<E: Pairing>(
     proof: &MultilinearKzgProof<E>,
 ) -> Result<bool, PCSError> {
     let verify_timer = start_timer!(|| "verify");
+    let res = prepare_verify_internal(verifier_param, commitment, point, value, proof)?.check();
+    end_timer!(verify_timer);
+    Ok(res)
+}
+
+/// Prepares the pairing check that `value` is the evaluation at `x` of the
+/// polynomial committed inside `comm`, without evaluating it.
+///
+/// This function takes num_var number of MSM.
+fn prepare_verify_internal<E: Pairing>(
+    verifier_param: &MultilinearVerifierParam<E>,
+    commitment: &Commitment<E>,
+    point: &[E::ScalarField],
+    value: &E::ScalarField,
+    proof: &MultilinearKzgProof<E>,
+) -> Result<MultilinearKzgPairingCheck<E>, PCSError> {
     let num_var = point.len();
 
     if num_var > verifier_param.num_vars {
@@ -302,30 +869,16 @@ fn verify_internal<E: Pairing>(
     let h_vec: Vec<E::G2Affine> = E::G2::normalize_batch(&h_vec);
     end_timer!(prepare_inputs_timer);
 
-    let pairing_product_timer = start_timer!(|| "pairing product");
-
-    let mut pairings: Vec<_> = proof
-        .proofs
-        .iter()
-        .map(|&x| E::G1Prepared::from(x))
-        .zip(h_vec.into_iter().take(num_var).map(E::G2Prepared::from))
-        .collect();
-
-    pairings.push((
-        E::G1Prepared::from(
-            (verifier_param.g.mul(*value) - commitment.0.into_group()).into_affine(),
-        ),
-        E::G2Prepared::from(verifier_param.h),
-    ));
-
-    let ps = pairings.iter().map(|(p, _)| p.clone());
-    let hs = pairings.iter().map(|(_, h)| h.clone());
-
-    let res = E::multi_pairing(ps, hs) == ark_ec::pairing::PairingOutput(E::TargetField::one());
-
-    end_timer!(pairing_product_timer);
-    end_timer!(verify_timer);
-    Ok(res)
+    Ok(MultilinearKzgPairingCheck {
+        quotients: proof
+            .proofs
+            .iter()
+            .copied()
+            .zip(h_vec.into_iter().take(num_var))
+            .collect(),
+        eval_term: (verifier_param.g.mul(*value) - commitment.0.into_group()).into_affine(),
+        h: verifier_param.h,
+    })
 }
 
 #[cfg(test)]
@@ -380,6 +933,245 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_batch_pairing_check() -> Result<(), PCSError> {
+        let mut rng = test_rng();
+        let nv = 8;
+        let params = MultilinearKzgPCS::<E>::gen_srs_for_testing(&mut rng, nv)?;
+        let (ck, vk) = MultilinearKzgPCS::trim(&params, None, Some(nv))?;
+
+        let mut checks = vec![];
+        for _ in 0..4 {
+            let poly = Arc::new(DenseMultilinearExtension::rand(nv, &mut rng));
+            let point: Vec<_> = (0..nv).map(|_| Fr::rand(&mut rng)).collect();
+            let com = MultilinearKzgPCS::commit(&ck, &poly)?;
+            let (proof, value) = MultilinearKzgPCS::open(&ck, &poly, &point)?;
+            let check = prepare_verify_internal(&vk, &com, &point, &value, &proof)?;
+            assert!(check.check());
+            checks.push(check);
+        }
+        assert!(MultilinearKzgPairingCheck::batch_check(&checks)?);
+        assert!(MultilinearKzgPairingCheck::<E>::batch_check(&[])?);
+
+        // a single wrong evaluation invalidates the whole batch
+        let poly = Arc::new(DenseMultilinearExtension::rand(nv, &mut rng));
+        let point: Vec<_> = (0..nv).map(|_| Fr::rand(&mut rng)).collect();
+        let com = MultilinearKzgPCS::commit(&ck, &poly)?;
+        let (proof, _value) = MultilinearKzgPCS::open(&ck, &poly, &point)?;
+        let value = Fr::rand(&mut rng);
+        let bad_check = prepare_verify_internal(&vk, &com, &point, &value, &proof)?;
+        assert!(!bad_check.check());
+        checks.insert(2, bad_check);
+        assert!(!MultilinearKzgPairingCheck::batch_check(&checks)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_at_points() -> Result<(), PCSError> {
+        let mut rng = test_rng();
+        let nv = 6;
+        let params = MultilinearKzgPCS::<E>::gen_srs_for_testing(&mut rng, nv + 2)?;
+        let (ck, vk) = MultilinearKzgPCS::trim(&params, None, Some(nv))?;
+        let poly = Arc::new(DenseMultilinearExtension::rand(nv, &mut rng));
+        let com = MultilinearKzgPCS::commit(&ck, &poly)?;
+
+        let zero = vec![Fr::zero(); nv];
+        let one = vec![Fr::one(); nv];
+        let r: Vec<_> = (0..nv).map(|_| Fr::rand(&mut rng)).collect();
+        for points in [vec![zero.clone(), r.clone()], vec![zero, one, r]] {
+            let (proof, evals) = MultilinearKzgPCS::open_at_points(&ck, &poly, &points)?;
+            for (point, eval) in points.iter().zip(evals.iter()) {
+                assert_eq!(poly.evaluate(point), Some(*eval));
+            }
+            assert!(MultilinearKzgPCS::verify_at_points(
+                &vk, &com, &points, &evals, &proof
+            )?);
+
+            // a single wrong evaluation
+            let mut wrong = evals.clone();
+            wrong[1] += Fr::one();
+            assert!(!MultilinearKzgPCS::verify_at_points(
+                &vk, &com, &points, &wrong, &proof
+            )?);
+            // swapped openings
+            let mut swapped = proof.clone();
+            swapped.proofs.swap(0, 1);
+            assert!(!MultilinearKzgPCS::verify_at_points(
+                &vk, &com, &points, &evals, &swapped
+            )?);
+            // missing an opening
+            assert!(
+                MultilinearKzgPCS::verify_at_points(&vk, &com, &points[1..], &evals, &proof)
+                    .is_err()
+            );
+        }
+        assert!(MultilinearKzgPCS::open_at_points(&ck, &poly, &[]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_batch_verify_openings() -> Result<(), PCSError> {
+        let mut rng = test_rng();
+        let nv = 8;
+        let params = MultilinearKzgPCS::<E>::gen_srs_for_testing(&mut rng, nv)?;
+        let (ck, vk) = MultilinearKzgPCS::trim(&params, None, Some(nv))?;
+
+        // polynomials of different sizes, at different points
+        let (mut commitments, mut points, mut evals, mut proofs) = (vec![], vec![], vec![], vec![]);
+        for num_vars in [nv, nv, 5, 1] {
+            let poly = Arc::new(DenseMultilinearExtension::rand(num_vars, &mut rng));
+            let point: Vec<_> = (0..num_vars).map(|_| Fr::rand(&mut rng)).collect();
+            let (proof, eval) = MultilinearKzgPCS::open(&ck, &poly, &point)?;
+            commitments.push(MultilinearKzgPCS::commit(&ck, &poly)?);
+            points.push(point);
+            evals.push(eval);
+            proofs.push(proof);
+        }
+        let verify = |evals: &[Fr], proofs: &[MultilinearKzgProof<E>]| -> Result<bool, PCSError> {
+            let mut transcript = IOPTranscript::new(b"test");
+            transcript.append_message(b"statement", b"batch openings")?;
+            MultilinearKzgPCS::batch_verify_openings(
+                &vk,
+                &commitments,
+                &points,
+                evals,
+                proofs,
+                &mut transcript,
+            )
+        };
+        assert!(verify(&evals, &proofs)?);
+
+        // a single wrong evaluation, or swapped proofs, invalidate the batch
+        let mut wrong = evals.clone();
+        wrong[2] += Fr::one();
+        assert!(!verify(&wrong, &proofs)?);
+        let mut swapped = proofs.clone();
+        swapped.swap(0, 1);
+        assert!(!verify(&evals, &swapped)?);
+        assert!(verify(&evals[1..], &proofs).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_lc() -> Result<(), PCSError> {
+        let mut rng = test_rng();
+        let nv = 8;
+        let params = MultilinearKzgPCS::<E>::gen_srs_for_testing(&mut rng, nv)?;
+        let (ck, vk) = MultilinearKzgPCS::trim(&params, None, Some(nv))?;
+
+        let polys: Vec<_> = (0..4)
+            .map(|_| Arc::new(DenseMultilinearExtension::rand(nv, &mut rng)))
+            .collect();
+        let alphas: Vec<_> = (0..polys.len()).map(|_| Fr::rand(&mut rng)).collect();
+        let point: Vec<_> = (0..nv).map(|_| Fr::rand(&mut rng)).collect();
+        let terms: Vec<_> = alphas
+            .iter()
+            .zip(polys.iter())
+            .map(|(alpha, poly)| Ok((*alpha, MultilinearKzgPCS::commit(&ck, poly)?)))
+            .collect::<Result<_, PCSError>>()?;
+
+        let combination: Vec<_> = alphas
+            .iter()
+            .copied()
+            .zip(polys.iter().map(|p| &**p))
+            .collect();
+        let (proof, value) = MultilinearKzgPCS::open_lc(&ck, &combination, &point)?;
+        let expected: Fr = alphas
+            .iter()
+            .zip(polys.iter())
+            .map(|(alpha, poly)| *alpha * poly.evaluate(&point).unwrap())
+            .sum();
+        assert_eq!(value, expected);
+        assert!(MultilinearKzgPCS::verify_lc(
+            &vk, &terms, &point, &value, &proof
+        )?);
+        assert!(!MultilinearKzgPCS::verify_lc(
+            &vk,
+            &terms,
+            &point,
+            &(value + Fr::one()),
+            &proof
+        )?);
+        // the proof is bound to the coefficients
+        let mut other = terms.clone();
+        other[0].0 += Fr::one();
+        assert!(!MultilinearKzgPCS::verify_lc(
+            &vk, &other, &point, &value, &proof
+        )?);
+
+        // a single proof instead of one per polynomial
+        let separate: Vec<_> = polys
+            .iter()
+            .map(|poly| Ok(MultilinearKzgPCS::open(&ck, poly, &point)?.0))
+            .collect::<Result<_, PCSError>>()?;
+        assert_eq!(
+            separate.compressed_size(),
+            // the length prefix of the list
+            8 + polys.len() * proof.compressed_size()
+        );
+        assert!(MultilinearKzgPCS::open_lc(&ck, &[], &point).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_hiding() -> Result<(), PCSError> {
+        let mut rng = test_rng();
+        let nv = 6;
+        let params = MultilinearKzgPCS::<E>::gen_srs_for_testing(&mut rng, nv + 1)?;
+        let (ck, vk) = MultilinearKzgPCS::trim(&params, None, Some(nv + 1))?;
+        let poly = Arc::new(DenseMultilinearExtension::rand(nv, &mut rng));
+
+        // different blindings yield different commitments, and no blinding
+        // the plain one
+        let (r1, r2) = (Fr::rand(&mut rng), Fr::rand(&mut rng));
+        let com = MultilinearKzgPCS::commit_hiding(&ck, &poly, &r1)?;
+        assert_ne!(com, MultilinearKzgPCS::commit_hiding(&ck, &poly, &r2)?);
+        assert_eq!(com, MultilinearKzgPCS::commit_hiding(&ck, &poly, &r1)?);
+        assert_eq!(
+            MultilinearKzgPCS::commit_hiding(&ck, &poly, &Fr::zero())?,
+            MultilinearKzgPCS::commit(&ck, &poly)?
+        );
+
+        let point: Vec<_> = (0..nv).map(|_| Fr::rand(&mut rng)).collect();
+        let (proof, value) = MultilinearKzgPCS::open_hiding(&ck, &poly, &point, &r1, &mut rng)?;
+        assert_eq!(poly.evaluate(&point), Some(value));
+        assert!(MultilinearKzgPCS::verify_hiding(
+            &vk, &com, &point, &value, &proof
+        )?);
+        // the quotients are blinded
+        let (plain, _) = MultilinearKzgPCS::open(&ck, &poly, &point)?;
+        assert!(plain
+            .proofs
+            .iter()
+            .all(|pi| !proof.proof.proofs.contains(pi)));
+
+        // a wrong evaluation, a wrong blinding or a plain proof fail
+        assert!(!MultilinearKzgPCS::verify_hiding(
+            &vk,
+            &com,
+            &point,
+            &(value + Fr::one()),
+            &proof
+        )?);
+        let (wrong, _) = MultilinearKzgPCS::open_hiding(&ck, &poly, &point, &r2, &mut rng)?;
+        assert!(!MultilinearKzgPCS::verify_hiding(
+            &vk, &com, &point, &value, &wrong
+        )?);
+        assert!(!MultilinearKzgPCS::verify(
+            &vk,
+            &com,
+            &point,
+            &value,
+            &proof.proof
+        )?);
+
+        // the params must support one more variable than the polynomial
+        let (ck, _) = MultilinearKzgPCS::trim(&params, None, Some(nv))?;
+        assert!(MultilinearKzgPCS::commit_hiding(&ck, &poly, &r1).is_err());
+        Ok(())
+    }
+
     #[test]
     fn setup_commit_verify_constant_polynomial() {
         let mut rng = test_rng();
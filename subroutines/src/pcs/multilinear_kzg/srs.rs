@@ -10,15 +10,19 @@ use crate::pcs::{
     prelude::PCSError,
     StructuredReferenceString,
 };
-use ark_ec::{pairing::Pairing, scalar_mul::fixed_base::FixedBase, AffineRepr, CurveGroup};
-use ark_ff::{Field, PrimeField, Zero};
+use ark_ec::{
+    pairing::{Pairing, PairingOutput},
+    scalar_mul::fixed_base::FixedBase,
+    AffineRepr, CurveGroup,
+};
+use ark_ff::{Field, One, PrimeField, Zero};
 use ark_poly::DenseMultilinearExtension;
-use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Read, SerializationError};
 use ark_std::{
     collections::LinkedList, end_timer, format, rand::Rng, start_timer, string::ToString, vec::Vec,
     UniformRand,
 };
-use core::iter::FromIterator;
+use core::iter::{once, FromIterator};
 
 /// Evaluations over {0,1}^n for G1 or G2
 #[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug)]
@@ -52,7 +56,7 @@ pub struct MultilinearProverParam<E: Pairing> {
 }
 
 /// Verifier Parameters
-#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug)]
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug, PartialEq, Eq)]
 pub struct MultilinearVerifierParam<E: Pairing> {
     /// number of variables
     pub num_vars: usize,
@@ -64,6 +68,289 @@ pub struct MultilinearVerifierParam<E: Pairing> {
     pub h_mask: Vec<E::G2Affine>,
 }
 
+/// How much of an SRS [`MultilinearUniversalParams::validate`] checks.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SrsValidation {
+    /// about the square root of the number of elements, sampled at random
+    #[default]
+    Sampled,
+    /// every element
+    Full,
+}
+
+/// Number of random linear combinations checked by [`batch_check_subgroup`]:
+/// the smallest prime dividing a cofactor of BLS12-381 is 3, and `3^{-81}`
+/// is below `2^{-128}`.
+const SUBGROUP_CHECK_ROUNDS: usize = 81;
+
+/// Check that all the `points`, which must be on the curve, are in the prime
+/// order subgroup.
+///
+/// Instead of checking the points one by one, check that random linear
+/// combinations of them, with 64-bit coefficients, are in the subgroup.
+/// Each of the 81 rounds costs a multi-scalar multiplication and misses a
+/// point outside the subgroup with probability at most `1/q`, for the
+/// smallest prime `q` dividing the cofactor.
+///
+/// Points decoded from the compressed encoding are always on the curve.
+pub fn batch_check_subgroup<G: CurveGroup, R: Rng>(
+    points: &[G::Affine],
+    rng: &mut R,
+) -> Result<(), PCSError> {
+    let start = start_timer!(|| format!("batch subgroup check of {} points", points.len()));
+    for _ in 0..SUBGROUP_CHECK_ROUNDS {
+        let coeffs: Vec<G::ScalarField> = (0..points.len())
+            .map(|_| G::ScalarField::from(rng.next_u64()))
+            .collect();
+        let combination = G::msm_unchecked(points, &coeffs);
+        if !combination.mul_bigint(G::ScalarField::MODULUS).is_zero() {
+            return Err(SerializationError::InvalidData.into());
+        }
+    }
+    end_timer!(start);
+    Ok(())
+}
+
+/// Number of bytes of a serialized length, i.e., of a `usize` field or of the
+/// length prefix of a vector.
+const LENGTH_BYTES: usize = 8;
+
+/// The number of group elements in, and the serialized size of, a structured
+/// reference string or of the parameters trimmed from it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SrsSizeReport {
+    /// number of G1 elements
+    pub num_g1: usize,
+    /// number of G2 elements
+    pub num_g2: usize,
+    /// serialized size in bytes with compressed encoding
+    pub compressed_bytes: usize,
+    /// serialized size in bytes with uncompressed encoding
+    pub uncompressed_bytes: usize,
+}
+
+impl SrsSizeReport {
+    /// Report for `num_g1` G1 elements and `num_g2` G2 elements, serialized
+    /// along with `num_lengths` lengths.
+    pub fn new<E: Pairing>(num_g1: usize, num_g2: usize, num_lengths: usize) -> Self {
+        let bytes = |compress| {
+            num_g1 * E::G1Affine::zero().serialized_size(compress)
+                + num_g2 * E::G2Affine::zero().serialized_size(compress)
+                + num_lengths * LENGTH_BYTES
+        };
+        Self {
+            num_g1,
+            num_g2,
+            compressed_bytes: bytes(Compress::Yes),
+            uncompressed_bytes: bytes(Compress::No),
+        }
+    }
+}
+
+impl<E: Pairing> MultilinearUniversalParams<E> {
+    /// The expected size of the SRS for `num_vars` variables, computed
+    /// without generating it.
+    ///
+    /// The SRS consists of `2^{num_vars} + ... + 2 + 1` evaluations of
+    /// `g` plus `g` itself in G1, and `h` with the `num_vars` masks in G2.
+    pub fn expected_size(num_vars: usize) -> SrsSizeReport {
+        // num_vars, the lengths of powers_of_g, of each of its
+        // num_vars + 1 entries, and of h_mask
+        SrsSizeReport::new::<E>(1 << (num_vars + 1), num_vars + 1, num_vars + 4)
+    }
+
+    /// The SRS for `max_num_vars` variables contained in this one.
+    ///
+    /// It is a subset of the group elements of this SRS, so it needs no new
+    /// setup, and it can be used in place of this SRS for any number of
+    /// variables up to `max_num_vars`: its `trim` gives the same parameters.
+    /// This lets a single large setup serve circuits of any smaller size
+    /// with an SRS of their own size.
+    pub fn trim_universal(&self, max_num_vars: usize) -> Result<Self, PCSError> {
+        let (prover_param, _) = self.trim(max_num_vars)?;
+        let to_reduce = self.prover_param.num_vars - max_num_vars;
+        Ok(Self {
+            prover_param,
+            h_mask: self.h_mask[to_reduce..].to_vec(),
+        })
+    }
+
+    /// Check that the SRS is well formed, e.g. after loading the output of a
+    /// ceremony and before spending time on preprocessing with it.
+    ///
+    /// The table `powers_of_g[i]` holds `g^{eq(t_i.., x)}` for `x` in
+    /// `{0,1}^{nv - i}`, where the least significant bit of `x` stands for
+    /// `t_i`, and `h_mask[i]` is `h^{t_i}`. So for each level `i < nv` and
+    /// index `y`, the elements `a = powers_of_g[i][2y]`,
+    /// `b = powers_of_g[i][2y + 1]` and `c = powers_of_g[i + 1][y]` satisfy
+    /// `a + b = c` and `e(b, h) = e(c, h^{t_i})`.
+    ///
+    /// These relations are checked for every `(i, y)` with
+    /// [`SrsValidation::Full`], or for about the square root of their number,
+    /// sampled with `rng`, with [`SrsValidation::Sampled`]. They are checked
+    /// in a random linear combination, with a single multi-pairing, and one
+    /// by one only if it fails, so that the error is
+    /// `PCSError::InconsistentSrs` for the first inconsistent `(i, y)`.
+    ///
+    /// The group elements must be in the prime order subgroups, as checked
+    /// by [`MultilinearUniversalParams::deserialize_checked`].
+    pub fn validate<R: Rng>(&self, mode: SrsValidation, rng: &mut R) -> Result<(), PCSError> {
+        let start = start_timer!(|| format!("{:?} SRS validation", mode));
+        let pp = &self.prover_param;
+        let nv = pp.num_vars;
+        if pp.powers_of_g.len() != nv + 1
+            || self.h_mask.len() != nv
+            || pp
+                .powers_of_g
+                .iter()
+                .enumerate()
+                .any(|(i, powers)| powers.evals.len() != 1 << (nv - i))
+            || pp.powers_of_g[nv].evals[0] != pp.g
+        {
+            return Err(PCSError::InvalidParameters(format!(
+                "the SRS tables do not match its {} variables",
+                nv
+            )));
+        }
+
+        // level i has 2^{nv - i - 1} pairs of elements
+        let num_pairs = (1 << nv) - 1;
+        let mut pairs: Vec<(usize, usize)> = match mode {
+            SrsValidation::Full => (0..nv)
+                .flat_map(|i| (0..1 << (nv - i - 1)).map(move |y| (i, y)))
+                .collect(),
+            SrsValidation::Sampled if num_pairs == 0 => vec![],
+            SrsValidation::Sampled => {
+                let mut num_samples = 1;
+                while num_samples * num_samples < num_pairs {
+                    num_samples += 1;
+                }
+                (0..num_samples)
+                    .map(|_| {
+                        let mut k = rng.gen_range(0..num_pairs);
+                        let mut i = 0;
+                        while k >= 1 << (nv - i - 1) {
+                            k -= 1 << (nv - i - 1);
+                            i += 1;
+                        }
+                        (i, k)
+                    })
+                    .collect()
+            },
+        };
+        pairs.sort_unstable();
+        pairs.dedup();
+
+        let coeffs: Vec<E::ScalarField> = pairs
+            .iter()
+            .map(|_| E::ScalarField::from(rng.next_u64()))
+            .collect();
+        if !self.check_pairs(&pairs, &coeffs) {
+            let (level, index) = pairs
+                .iter()
+                .copied()
+                .find(|&pair| !self.check_pairs(&[pair], &[E::ScalarField::one()]))
+                .unwrap_or(pairs[0]);
+            return Err(PCSError::InconsistentSrs { level, index });
+        }
+        end_timer!(start);
+        Ok(())
+    }
+
+    /// Whether the relations of [`MultilinearUniversalParams::validate`]
+    /// hold for the `pairs` `(i, y)`, in the linear combination with
+    /// coefficients `coeffs`.
+    fn check_pairs(&self, pairs: &[(usize, usize)], coeffs: &[E::ScalarField]) -> bool {
+        let powers = &self.prover_param.powers_of_g;
+        // sum of r (a + b - c), sum of r b, and sum of r c for each level
+        let mut sum = E::G1::zero();
+        let mut b_sum = E::G1::zero();
+        let mut c_sums = vec![E::G1::zero(); self.prover_param.num_vars];
+        for (&(i, y), &r) in pairs.iter().zip(coeffs) {
+            let a = powers[i].evals[2 * y];
+            let b = powers[i].evals[2 * y + 1];
+            let c = powers[i + 1].evals[y];
+            sum += (a.into_group() + b - c) * r;
+            b_sum += b * r;
+            c_sums[i] += c * r;
+        }
+        if !sum.is_zero() {
+            return false;
+        }
+        // e(sum of r b, h) * prod_i e(-(sum of r c), h^{t_i}) = 1
+        let g1: Vec<E::G1> = once(b_sum).chain(c_sums.into_iter().map(|c| -c)).collect();
+        let g2: Vec<E::G2Affine> = once(self.prover_param.h)
+            .chain(self.h_mask.iter().copied())
+            .collect();
+        E::multi_pairing(g1, g2) == PairingOutput(E::TargetField::one())
+    }
+
+    /// Read an SRS in compressed encoding, and check that its group elements
+    /// are in the prime order subgroups with [`batch_check_subgroup`].
+    ///
+    /// This accepts the same SRS as `deserialize_compressed`, which checks
+    /// the group elements one by one, but does the checks in multi-scalar
+    /// multiplications over all of them.
+    pub fn deserialize_checked<R: Read, G: Rng>(reader: R, rng: &mut G) -> Result<Self, PCSError> {
+        let srs = Self::deserialize_trusted(reader)?;
+        srs.prover_param.check_subgroups(rng)?;
+        batch_check_subgroup::<E::G2, _>(&srs.h_mask, rng)?;
+        Ok(srs)
+    }
+
+    /// Read an SRS in compressed encoding without checking that its group
+    /// elements are in the prime order subgroups.
+    ///
+    /// Only for an SRS from a trusted source, such as a file written by this
+    /// process: the security of the commitments relies on these checks.
+    pub fn deserialize_trusted<R: Read>(reader: R) -> Result<Self, PCSError> {
+        Ok(Self::deserialize_compressed_unchecked(reader)?)
+    }
+}
+
+impl<E: Pairing> MultilinearProverParam<E> {
+    /// The expected size of the prover parameters trimmed to `num_vars`
+    /// variables.
+    pub fn expected_size(num_vars: usize) -> SrsSizeReport {
+        // num_vars, the lengths of powers_of_g and of each of its
+        // num_vars + 1 entries
+        SrsSizeReport::new::<E>(1 << (num_vars + 1), 1, num_vars + 3)
+    }
+
+    /// Read prover parameters in compressed encoding, and check that their
+    /// group elements are in the prime order subgroups with
+    /// [`batch_check_subgroup`].
+    pub fn deserialize_checked<R: Read, G: Rng>(reader: R, rng: &mut G) -> Result<Self, PCSError> {
+        let pp = Self::deserialize_trusted(reader)?;
+        pp.check_subgroups(rng)?;
+        Ok(pp)
+    }
+
+    /// Read prover parameters in compressed encoding without checking that
+    /// their group elements are in the prime order subgroups, see
+    /// [`MultilinearUniversalParams::deserialize_trusted`].
+    pub fn deserialize_trusted<R: Read>(reader: R) -> Result<Self, PCSError> {
+        Ok(Self::deserialize_compressed_unchecked(reader)?)
+    }
+
+    fn check_subgroups<G: Rng>(&self, rng: &mut G) -> Result<(), PCSError> {
+        for powers in self.powers_of_g.iter() {
+            batch_check_subgroup::<E::G1, _>(&powers.evals, rng)?;
+        }
+        batch_check_subgroup::<E::G1, _>(&[self.g], rng)?;
+        batch_check_subgroup::<E::G2, _>(&[self.h], rng)
+    }
+}
+
+impl<E: Pairing> MultilinearVerifierParam<E> {
+    /// The expected size of the verifier parameters trimmed to `num_vars`
+    /// variables.
+    pub fn expected_size(num_vars: usize) -> SrsSizeReport {
+        // num_vars and the length of h_mask
+        SrsSizeReport::new::<E>(1, num_vars + 1, 2)
+    }
+}
+
 impl<E: Pairing> StructuredReferenceString<E> for MultilinearUniversalParams<E> {
     type ProverParam = MultilinearProverParam<E>;
     type VerifierParam = MultilinearVerifierParam<E>;
@@ -153,11 +440,7 @@ impl<E: Pairing> StructuredReferenceString<E> for MultilinearUniversalParams<E>
             eq_arr.push_front(remove_dummy_variable(&base, i)?);
             if i != 0 {
                 let mul = eq.pop_back().unwrap().evaluations;
-                base = base
-                    .into_iter()
-                    .zip(mul.into_iter())
-                    .map(|(a, b)| a * b)
-                    .collect();
+                base = base.into_iter().zip(mul).map(|(a, b)| a * b).collect();
             }
         }
 
@@ -237,10 +520,186 @@ fn remove_dummy_variable<F: Field>(poly: &[F], pad: usize) -> Result<Vec<F>, PCS
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ark_bls12_381::Bls12_381;
-    use ark_std::test_rng;
+    use ark_bls12_381::{Bls12_381, G1Affine, G1Projective};
+    use ark_ec::short_weierstrass::{Affine, SWCurveConfig};
+    use ark_std::{test_rng, One};
     type E = Bls12_381;
 
+    /// A point on the curve outside the prime order subgroup.
+    fn non_subgroup_point<P: SWCurveConfig>() -> Affine<P> {
+        (1u64..)
+            .filter_map(|x| Affine::<P>::get_point_from_x_unchecked(x.into(), false))
+            .find(|p| !p.is_in_correct_subgroup_assuming_on_curve())
+            .unwrap()
+    }
+
+    /// A point off the curve, whose compressed encoding is the x-coordinate
+    /// of no point.
+    fn off_curve_point<P: SWCurveConfig>() -> Affine<P> {
+        let x = (1u64..)
+            .map(P::BaseField::from)
+            .find(|&x| Affine::<P>::get_point_from_x_unchecked(x, false).is_none())
+            .unwrap();
+        Affine::new_unchecked(x, P::BaseField::one())
+    }
+
+    fn to_bytes<T: CanonicalSerialize>(t: &T) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        t.serialize_compressed(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_batch_check_subgroup() -> Result<(), PCSError> {
+        let mut rng = test_rng();
+        let mut points: Vec<G1Affine> = (0..100)
+            .map(|_| G1Projective::rand(&mut rng).into_affine())
+            .collect();
+        batch_check_subgroup::<G1Projective, _>(&points, &mut rng)?;
+        batch_check_subgroup::<G1Projective, _>(&[], &mut rng)?;
+
+        points[42] = non_subgroup_point();
+        assert!(batch_check_subgroup::<G1Projective, _>(&points, &mut rng).is_err());
+        // two points whose components outside the subgroup may cancel out
+        points[43] = (-points[42].into_group()).into_affine();
+        assert!(batch_check_subgroup::<G1Projective, _>(&points, &mut rng).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_checked() -> Result<(), PCSError> {
+        let mut rng = test_rng();
+        let srs = MultilinearUniversalParams::<E>::gen_srs_for_testing(&mut rng, 4)?;
+        let bytes = to_bytes(&srs);
+        let checked = MultilinearUniversalParams::<E>::deserialize_checked(&bytes[..], &mut rng)?;
+        assert_eq!(to_bytes(&checked), bytes);
+        checked.validate(SrsValidation::Full, &mut rng)?;
+        let (ck, vk) = srs.trim(3)?;
+        let checked =
+            MultilinearProverParam::<E>::deserialize_checked(&to_bytes(&ck)[..], &mut rng)?;
+        assert_eq!(to_bytes(&checked), to_bytes(&ck));
+
+        // bad path: elements outside the prime order subgroups, which only
+        // the trusted loaders accept
+        let mut bad_srs = [srs.clone(), srs.clone(), srs.clone()];
+        bad_srs[0].prover_param.powers_of_g[1].evals[2] = non_subgroup_point();
+        bad_srs[1].prover_param.g = non_subgroup_point();
+        bad_srs[2].h_mask[0] = non_subgroup_point();
+        for (i, srs) in bad_srs.iter().enumerate() {
+            let bytes = to_bytes(srs);
+            assert!(MultilinearUniversalParams::<E>::deserialize_compressed(&bytes[..]).is_err());
+            assert!(
+                MultilinearUniversalParams::<E>::deserialize_checked(&bytes[..], &mut rng).is_err()
+            );
+            assert!(MultilinearUniversalParams::<E>::deserialize_trusted(&bytes[..]).is_ok());
+
+            // the prover parameters do not include the masks
+            let (ck, _) = srs.trim(4)?;
+            let bytes = to_bytes(&ck);
+            let checked = MultilinearProverParam::<E>::deserialize_checked(&bytes[..], &mut rng);
+            assert_eq!(checked.is_err(), i < 2);
+            assert!(MultilinearProverParam::<E>::deserialize_trusted(&bytes[..]).is_ok());
+        }
+        let mut bad_vk = vk.clone();
+        bad_vk.h_mask[1] = non_subgroup_point();
+        let bytes = to_bytes(&bad_vk);
+        assert!(MultilinearVerifierParam::<E>::deserialize_compressed(&bytes[..]).is_err());
+
+        // bad path: elements off the curve, which no loader accepts
+        let mut bad_srs = srs;
+        bad_srs.prover_param.powers_of_g[0].evals[0] = off_curve_point();
+        let bytes = to_bytes(&bad_srs);
+        assert!(
+            MultilinearUniversalParams::<E>::deserialize_checked(&bytes[..], &mut rng).is_err()
+        );
+        assert!(MultilinearUniversalParams::<E>::deserialize_trusted(&bytes[..]).is_err());
+        let mut bad_vk = vk;
+        bad_vk.h_mask[0] = off_curve_point();
+        let bytes = to_bytes(&bad_vk);
+        assert!(MultilinearVerifierParam::<E>::deserialize_compressed(&bytes[..]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate() -> Result<(), PCSError> {
+        let mut rng = test_rng();
+        let nv = 4;
+        let srs = MultilinearUniversalParams::<E>::gen_srs_for_testing(&mut rng, nv)?;
+        for mode in [SrsValidation::Sampled, SrsValidation::Full] {
+            srs.validate(mode, &mut rng)?;
+            srs.trim_universal(1)?.validate(mode, &mut rng)?;
+        }
+
+        // bad path: an element of the first level, which only one pair uses
+        let mut bad_srs = srs.clone();
+        let g = bad_srs.prover_param.g;
+        let evals = &mut bad_srs.prover_param.powers_of_g[0].evals;
+        evals[5] = (evals[5] + g).into_affine();
+        assert!(matches!(
+            bad_srs.validate(SrsValidation::Full, &mut rng),
+            Err(PCSError::InconsistentSrs { level: 0, index: 2 })
+        ));
+        // an element of the second level, which pairs of the first and the
+        // second levels use: the first level comes first
+        let mut bad_srs = srs.clone();
+        let evals = &mut bad_srs.prover_param.powers_of_g[2].evals;
+        evals[3] = (evals[3] + g).into_affine();
+        assert!(matches!(
+            bad_srs.validate(SrsValidation::Full, &mut rng),
+            Err(PCSError::InconsistentSrs { level: 1, index: 3 })
+        ));
+        // a mask, which all the pairs of its level use
+        let mut bad_srs = srs.clone();
+        bad_srs.h_mask[2] = bad_srs.h_mask[1];
+        assert!(matches!(
+            bad_srs.validate(SrsValidation::Full, &mut rng),
+            Err(PCSError::InconsistentSrs { level: 2, index: 0 })
+        ));
+        // the generator of G2, which all the pairs use, so that any sample
+        // fails
+        let mut bad_srs = srs.clone();
+        bad_srs.prover_param.h = bad_srs.h_mask[0];
+        assert!(matches!(
+            bad_srs.validate(SrsValidation::Sampled, &mut rng),
+            Err(PCSError::InconsistentSrs { .. })
+        ));
+        // tables of the wrong shape
+        let mut bad_srs = srs;
+        bad_srs.h_mask.pop();
+        assert!(matches!(
+            bad_srs.validate(SrsValidation::Sampled, &mut rng),
+            Err(PCSError::InvalidParameters(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_trim_universal() -> Result<(), PCSError> {
+        let mut rng = test_rng();
+        let srs = MultilinearUniversalParams::<E>::gen_srs_for_testing(&mut rng, 6)?;
+        for max_nv in 1..=6 {
+            let trimmed = srs.trim_universal(max_nv)?;
+            assert_eq!(
+                trimmed.serialized_size(Compress::Yes),
+                MultilinearUniversalParams::<E>::expected_size(max_nv).compressed_bytes
+            );
+            for nv in 1..=max_nv {
+                let (ck, vk) = trimmed.trim(nv)?;
+                let (full_ck, full_vk) = srs.trim(nv)?;
+                assert_eq!(to_bytes(&ck), to_bytes(&full_ck));
+                assert_eq!(to_bytes(&vk), to_bytes(&full_vk));
+            }
+            assert!(trimmed.trim(max_nv + 1).is_err());
+        }
+        assert_eq!(to_bytes(&srs.trim_universal(6)?), to_bytes(&srs));
+        assert!(srs.trim_universal(7).is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn test_srs_gen() -> Result<(), PCSError> {
         let mut rng = test_rng();
@@ -250,4 +709,33 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_expected_size() -> Result<(), PCSError> {
+        let mut rng = test_rng();
+        let srs = MultilinearUniversalParams::<E>::gen_srs_for_testing(&mut rng, 6)?;
+        for compress in [Compress::Yes, Compress::No] {
+            let bytes = |report: SrsSizeReport| match compress {
+                Compress::Yes => report.compressed_bytes,
+                Compress::No => report.uncompressed_bytes,
+            };
+            assert_eq!(
+                srs.serialized_size(compress),
+                bytes(MultilinearUniversalParams::<E>::expected_size(6))
+            );
+            for nv in 1..=6 {
+                let (ck, vk) = srs.trim(nv)?;
+                assert_eq!(
+                    ck.serialized_size(compress),
+                    bytes(MultilinearProverParam::<E>::expected_size(nv))
+                );
+                assert_eq!(
+                    vk.serialized_size(compress),
+                    bytes(MultilinearVerifierParam::<E>::expected_size(nv))
+                );
+            }
+        }
+
+        Ok(())
+    }
 }
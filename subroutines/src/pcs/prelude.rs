@@ -8,9 +8,13 @@
 pub use crate::pcs::{
     errors::PCSError,
     multilinear_kzg::{
-        batching::BatchProof,
-        srs::{MultilinearProverParam, MultilinearUniversalParams, MultilinearVerifierParam},
-        MultilinearKzgPCS, MultilinearKzgProof,
+        batching::{BatchProof, MultiOpenRounds},
+        srs::{
+            batch_check_subgroup, MultilinearProverParam, MultilinearUniversalParams,
+            MultilinearVerifierParam, SrsSizeReport, SrsValidation,
+        },
+        MultilinearKzgHidingProof, MultilinearKzgMultiOpenProof, MultilinearKzgPCS,
+        MultilinearKzgPairingCheck, MultilinearKzgProof,
     },
     structs::Commitment,
     univariate_kzg::{
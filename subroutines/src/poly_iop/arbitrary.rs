@@ -0,0 +1,107 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! `arbitrary::Arbitrary` for the sum check proofs and subclaims.
+//!
+//! The generated values are structurally valid: a proof has between 1 and
+//! `MAX_NUM_VARIABLES` rounds, a point with one coordinate per round, and
+//! messages of a same degree between 1 and `MAX_DEGREE`. Their field
+//! elements are arbitrary, read from the unstructured bytes and reduced
+//! modulo the field order, so that they satisfy no relation with each
+//! other; a test that needs one, such as a non-zero claimed sum, filters the
+//! generated values.
+
+use crate::poly_iop::{
+    prelude::{MAX_DEGREE, MAX_NUM_VARIABLES},
+    structs::{IOPProof, IOPProverMessage},
+    zk_sum_check::ZkSumCheckSubClaim,
+};
+use arbitrary::{Arbitrary, Result, Unstructured};
+use ark_ff::PrimeField;
+
+/// A field element from the bytes of `u`, padded with zeros once they are
+/// exhausted.
+fn arbitrary_field<F: PrimeField>(u: &mut Unstructured) -> Result<F> {
+    let mut bytes = vec![0u8; (F::MODULUS_BIT_SIZE as usize).div_ceil(8)];
+    u.fill_buffer(&mut bytes)?;
+    Ok(F::from_le_bytes_mod_order(&bytes))
+}
+
+fn arbitrary_fields<F: PrimeField>(u: &mut Unstructured, len: usize) -> Result<Vec<F>> {
+    (0..len).map(|_| arbitrary_field(u)).collect()
+}
+
+fn arbitrary_message<F: PrimeField>(
+    u: &mut Unstructured,
+    degree: usize,
+) -> Result<IOPProverMessage<F>> {
+    Ok(IOPProverMessage {
+        evaluations: arbitrary_fields(u, degree + 1)?,
+    })
+}
+
+impl<'a, F: PrimeField> Arbitrary<'a> for IOPProverMessage<F> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let degree = u.int_in_range(1..=MAX_DEGREE)?;
+        arbitrary_message(u, degree)
+    }
+}
+
+impl<'a, F: PrimeField> Arbitrary<'a> for IOPProof<F> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let num_rounds = u.int_in_range(1..=MAX_NUM_VARIABLES)?;
+        let degree = u.int_in_range(1..=MAX_DEGREE)?;
+        Ok(Self {
+            point: arbitrary_fields(u, num_rounds)?,
+            proofs: (0..num_rounds)
+                .map(|_| arbitrary_message(u, degree))
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+impl<'a, F: PrimeField> Arbitrary<'a> for ZkSumCheckSubClaim<F> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let num_vars = u.int_in_range(1..=MAX_NUM_VARIABLES)?;
+        Ok(Self {
+            point: arbitrary_fields(u, num_vars)?,
+            expected_evaluation: arbitrary_field(u)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poly_iop::strategies::{check, fail};
+    use ark_bls12_381::Fr;
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use proptest::{collection::vec, prelude::*};
+
+    #[test]
+    fn test_arbitrary_proof_round_trip() {
+        check(vec(any::<u8>(), 0..1024), |bytes| {
+            let mut u = Unstructured::new(&bytes);
+            let proof = IOPProof::<Fr>::arbitrary(&mut u).map_err(fail)?;
+            prop_assert!((1..=MAX_NUM_VARIABLES).contains(&proof.proofs.len()));
+            prop_assert_eq!(proof.point.len(), proof.proofs.len());
+            let len = proof.proofs[0].evaluations.len();
+            prop_assert!((2..=MAX_DEGREE + 1).contains(&len));
+            prop_assert!(proof.proofs.iter().all(|msg| msg.evaluations.len() == len));
+
+            let mut serialized = Vec::new();
+            proof.serialize_compressed(&mut serialized).map_err(fail)?;
+            prop_assert_eq!(
+                IOPProof::<Fr>::deserialize_compressed(serialized.as_slice()).map_err(fail)?,
+                proof
+            );
+
+            let sub_claim = ZkSumCheckSubClaim::<Fr>::arbitrary(&mut u).map_err(fail)?;
+            prop_assert!((1..=MAX_NUM_VARIABLES).contains(&sub_claim.point.len()));
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,348 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! A compact encoding of the sum check proofs.
+//!
+//! It is denser than the canonical serialization of `IOPProof` in three
+//! ways:
+//! - the lengths are varints, and all the prover messages share one length;
+//! - the evaluation at 1 of the message of every round but the first is left
+//!   out: the message of the previous round evaluated at the coordinate of
+//!   the point is the claimed sum of the round, that is the sum of the
+//!   evaluations at 0 and 1;
+//! - an evaluation whose value or opposite is less than 2^63 is a zigzag
+//!   varint, flagged in a bitmap, instead of a full field element.
+//!
+//! The bytes are, in order: the number of rounds and the length of the
+//! messages, as varints; the bitmap of the stored evaluations, one bit per
+//! evaluation from the least significant bit; the stored evaluations, round
+//! by round; and the point, with compressed encoding.
+//!
+//! The evaluations of a proof are random field elements but for a few, so
+//! that it is mostly the left out evaluations and lengths that make the
+//! encoding smaller: by about one evaluation per message. This is about 19%
+//! for the messages of 5 evaluations of a vanilla plonk zero check, short of
+//! a 20% reduction. The point is made of challenges, which are random too;
+//! leaving it out would need the transcript to decode the proof.
+
+use crate::poly_iop::{
+    errors::PolyIOPErrors,
+    structs::{IOPProof, IOPProverMessage},
+    sum_check::{interpolate_uni_poly, MAX_DEGREE, MAX_NUM_VARIABLES},
+};
+use ark_ff::{BigInteger, PrimeField};
+use std::marker::PhantomData;
+
+/// The compact encoding of an `IOPProof`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompactProof<F: PrimeField> {
+    bytes: Vec<u8>,
+    phantom: PhantomData<F>,
+}
+
+impl<F: PrimeField> CompactProof<F> {
+    /// Encode `proof`. Returns an error if its messages do not have a same
+    /// length of at least 2, one per coordinate of the point, or if a round
+    /// is not consistent with the previous one, since its evaluation at 1
+    /// could not be recovered.
+    pub fn from_iop_proof(proof: &IOPProof<F>) -> Result<Self, PolyIOPErrors> {
+        let num_rounds = proof.point.len();
+        let message_len = proof.proofs.first().map_or(0, |m| m.evaluations.len());
+        if proof.proofs.len() != num_rounds
+            || (num_rounds > 0 && message_len < 2)
+            || proof
+                .proofs
+                .iter()
+                .any(|m| m.evaluations.len() != message_len)
+        {
+            return Err(PolyIOPErrors::InvalidProof(format!(
+                "a compact proof needs one message of a same length of at least 2 per round, \
+                 got {} messages for {} rounds",
+                proof.proofs.len(),
+                num_rounds
+            )));
+        }
+        for round in 1..num_rounds {
+            let claimed_sum =
+                interpolate_uni_poly(&proof.proofs[round - 1].evaluations, proof.point[round - 1])?;
+            let evaluations = &proof.proofs[round].evaluations;
+            if evaluations[0] + evaluations[1] != claimed_sum {
+                return Err(PolyIOPErrors::InvalidProof(format!(
+                    "round {} is not consistent with the previous round",
+                    round
+                )));
+            }
+        }
+
+        let stored: Vec<F> = proof
+            .proofs
+            .iter()
+            .enumerate()
+            .flat_map(|(round, m)| {
+                m.evaluations
+                    .iter()
+                    .enumerate()
+                    .filter(move |(i, _)| round == 0 || *i != 1)
+                    .map(|(_, e)| *e)
+            })
+            .collect();
+        let small: Vec<Option<u64>> = stored.iter().map(to_zigzag).collect();
+
+        let mut bytes = vec![];
+        write_varint(&mut bytes, num_rounds as u64);
+        write_varint(&mut bytes, message_len as u64);
+        let mut bitmap = vec![0u8; stored.len().div_ceil(8)];
+        for (i, z) in small.iter().enumerate() {
+            if z.is_some() {
+                bitmap[i / 8] |= 1 << (i % 8);
+            }
+        }
+        bytes.extend_from_slice(&bitmap);
+        for (e, z) in stored.iter().zip(small) {
+            match z {
+                Some(z) => write_varint(&mut bytes, z),
+                None => e.serialize_compressed(&mut bytes)?,
+            }
+        }
+        for x in proof.point.iter() {
+            x.serialize_compressed(&mut bytes)?;
+        }
+        Ok(Self {
+            bytes,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Decode the proof, with the left out evaluations recomputed.
+    pub fn to_iop_proof(&self) -> Result<IOPProof<F>, PolyIOPErrors> {
+        let mut reader = self.bytes.as_slice();
+        let num_rounds = read_varint(&mut reader)? as usize;
+        let message_len = read_varint(&mut reader)? as usize;
+        if num_rounds > MAX_NUM_VARIABLES
+            || (num_rounds > 0 && !(2..=MAX_DEGREE + 1).contains(&message_len))
+        {
+            return Err(PolyIOPErrors::InvalidProof(format!(
+                "invalid compact proof of {} rounds with messages of length {}",
+                num_rounds, message_len
+            )));
+        }
+        let num_stored = match num_rounds {
+            0 => 0,
+            _ => message_len + (num_rounds - 1) * (message_len - 1),
+        };
+        let bitmap = take(&mut reader, num_stored.div_ceil(8))?;
+        let mut stored = Vec::with_capacity(num_stored);
+        for i in 0..num_stored {
+            if (bitmap[i / 8] >> (i % 8)) & 1 == 1 {
+                stored.push(from_zigzag(read_varint(&mut reader)?));
+            } else {
+                stored.push(F::deserialize_compressed(&mut reader)?);
+            }
+        }
+        let point = (0..num_rounds)
+            .map(|_| F::deserialize_compressed(&mut reader))
+            .collect::<Result<Vec<F>, _>>()?;
+        if !reader.is_empty() {
+            return Err(PolyIOPErrors::InvalidProof(format!(
+                "{} trailing bytes after the compact proof",
+                reader.len()
+            )));
+        }
+
+        let mut stored = stored.into_iter();
+        let mut proofs: Vec<IOPProverMessage<F>> = Vec::with_capacity(num_rounds);
+        for round in 0..num_rounds {
+            let evaluations = if round == 0 {
+                stored.by_ref().take(message_len).collect()
+            } else {
+                let claimed_sum =
+                    interpolate_uni_poly(&proofs[round - 1].evaluations, point[round - 1])?;
+                let mut evaluations: Vec<F> = stored.by_ref().take(message_len - 1).collect();
+                evaluations.insert(1, claimed_sum - evaluations[0]);
+                evaluations
+            };
+            proofs.push(IOPProverMessage { evaluations });
+        }
+        Ok(IOPProof { point, proofs })
+    }
+
+    /// The bytes of the compact encoding.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// A compact proof from its bytes, which are only checked when decoded
+    /// by [`CompactProof::to_iop_proof`].
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self {
+            bytes,
+            phantom: PhantomData,
+        }
+    }
+}
+
+/// The zigzag encoding of `x` if its value or opposite is less than 2^63.
+fn to_zigzag<F: PrimeField>(x: &F) -> Option<u64> {
+    let small = |v: F| {
+        let v = v.into_bigint();
+        (v.num_bits() <= 63).then_some(v.as_ref()[0])
+    };
+    small(*x)
+        .map(|v| v << 1)
+        .or_else(|| small(-*x).map(|v| (v << 1) - 1))
+}
+
+fn from_zigzag<F: PrimeField>(z: u64) -> F {
+    if z.is_multiple_of(2) {
+        F::from(z >> 1)
+    } else {
+        -F::from((z >> 1) + 1)
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        bytes.push(v as u8 | 0x80);
+        v >>= 7;
+    }
+    bytes.push(v as u8);
+}
+
+fn read_varint(reader: &mut &[u8]) -> Result<u64, PolyIOPErrors> {
+    let mut v = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = take(reader, 1)?[0];
+        if shift == 63 && byte > 1 {
+            break;
+        }
+        v |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(v);
+        }
+    }
+    Err(PolyIOPErrors::InvalidProof(
+        "varint overflows 64 bits".to_string(),
+    ))
+}
+
+fn take<'a>(reader: &mut &'a [u8], len: usize) -> Result<&'a [u8], PolyIOPErrors> {
+    if reader.len() < len {
+        return Err(PolyIOPErrors::InvalidProof(
+            "truncated compact proof".to_string(),
+        ));
+    }
+    let (head, rest) = reader.split_at(len);
+    *reader = rest;
+    Ok(head)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poly_iop::{prelude::SumCheck, PolyIOP};
+    use arithmetic::VirtualPolynomial;
+    use ark_ff::{One, Zero};
+    use ark_poly::DenseMultilinearExtension;
+    use ark_secp256k1::Fr;
+    use ark_serialize::CanonicalSerialize;
+    use ark_std::{test_rng, UniformRand};
+    use std::sync::Arc;
+
+    fn canonical_size<T: CanonicalSerialize>(x: &T) -> usize {
+        x.compressed_size()
+    }
+
+    #[test]
+    fn test_round_trip() -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        for nv in 1..6 {
+            let (poly, sum) = VirtualPolynomial::<Fr>::rand(nv, (2, 4), 2, &mut rng)?;
+            let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+            let proof = <PolyIOP<Fr> as SumCheck<Fr>>::prove(&poly, &mut transcript)?;
+
+            let compact = CompactProof::from_iop_proof(&proof)?;
+            assert!(compact.as_bytes().len() < canonical_size(&proof));
+            let decoded = CompactProof::from_bytes(compact.as_bytes().to_vec()).to_iop_proof()?;
+            assert_eq!(decoded, proof);
+            let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+            <PolyIOP<Fr> as SumCheck<Fr>>::verify(sum, &decoded, &poly.aux_info, &mut transcript)?;
+        }
+        assert_eq!(
+            CompactProof::from_iop_proof(&IOPProof::<Fr>::default())?.to_iop_proof()?,
+            IOPProof::default()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_small_evaluations() -> Result<(), PolyIOPErrors> {
+        // the first message of a sum check over small values is small
+        let nv = 3;
+        let evals: Vec<Fr> = (0..1u64 << nv).map(|i| Fr::from(i % 3)).collect();
+        let mle = Arc::new(DenseMultilinearExtension::from_evaluations_vec(nv, evals));
+        let mut poly = VirtualPolynomial::new(nv);
+        poly.add_mle_list(vec![mle.clone(), mle], -Fr::one())?;
+        let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+        let proof = <PolyIOP<Fr> as SumCheck<Fr>>::prove(&poly, &mut transcript)?;
+        assert!(proof.proofs[0]
+            .evaluations
+            .iter()
+            .all(|e| to_zigzag(e).is_some()));
+
+        let compact = CompactProof::from_iop_proof(&proof)?;
+        // 3 evaluations of the first message are varints of 1 byte
+        assert!(compact.as_bytes().len() <= canonical_size(&proof) - 3 * 31);
+        assert_eq!(compact.to_iop_proof()?, proof);
+
+        for x in [Fr::zero(), Fr::one(), -Fr::one(), Fr::from(u64::MAX >> 1)] {
+            assert_eq!(from_zigzag::<Fr>(to_zigzag(&x).unwrap()), x);
+            assert_eq!(from_zigzag::<Fr>(to_zigzag(&-x).unwrap()), -x);
+        }
+        assert_eq!(to_zigzag(&Fr::from(1u64 << 63)), None);
+        assert_eq!(to_zigzag(&Fr::rand(&mut test_rng())), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_malformed() -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        let (poly, _) = VirtualPolynomial::<Fr>::rand(4, (2, 3), 2, &mut rng)?;
+        let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+        let proof = <PolyIOP<Fr> as SumCheck<Fr>>::prove(&poly, &mut transcript)?;
+
+        // an inconsistent round, messages of different lengths
+        let mut bad_proof = proof.clone();
+        bad_proof.proofs[2].evaluations[1] += Fr::one();
+        assert!(CompactProof::from_iop_proof(&bad_proof).is_err());
+        let mut bad_proof = proof.clone();
+        bad_proof.proofs[3].evaluations.pop();
+        assert!(CompactProof::from_iop_proof(&bad_proof).is_err());
+        let mut bad_proof = proof.clone();
+        bad_proof.point.pop();
+        assert!(CompactProof::from_iop_proof(&bad_proof).is_err());
+
+        // truncated, trailing bytes, too many rounds
+        let bytes = CompactProof::from_iop_proof(&proof)?.as_bytes().to_vec();
+        for len in [0, 1, 2, bytes.len() - 1] {
+            assert!(CompactProof::<Fr>::from_bytes(bytes[..len].to_vec())
+                .to_iop_proof()
+                .is_err());
+        }
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(CompactProof::<Fr>::from_bytes(trailing)
+            .to_iop_proof()
+            .is_err());
+        let mut too_long = bytes;
+        too_long[0] = MAX_NUM_VARIABLES as u8 + 1;
+        assert!(CompactProof::<Fr>::from_bytes(too_long)
+            .to_iop_proof()
+            .is_err());
+        // a varint longer than 64 bits
+        assert!(read_varint(&mut [0xff; 10].as_slice()).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,334 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! Composition of sub-protocols on a single transcript.
+//!
+//! An [`IOPComposer`] runs sum checks, product checks and permutation checks
+//! one after the other on its transcript. Before each of them, it absorbs
+//! the index of the step and the kind of the sub-protocol, so that two
+//! sub-protocols never derive their challenges from the same transcript
+//! state, whatever their labels. The verifier replays the same steps in the
+//! same order with its own composer.
+
+use crate::{
+    pcs::PolynomialCommitmentScheme,
+    poly_iop::{
+        errors::PolyIOPErrors,
+        perm_check::{PermutationCheck, PermutationCheckSubClaim},
+        prod_check::{ProductCheck, ProductCheckProof, ProductCheckSubClaim},
+        structs::IOPProof,
+        sum_check::{SumCheck, SumCheckSubClaim},
+        PolyIOP,
+    },
+};
+use arithmetic::{VPAuxInfo, VirtualPolynomial};
+use ark_ec::pairing::Pairing;
+use ark_ff::PrimeField;
+use ark_poly::DenseMultilinearExtension;
+use ark_serialize::CanonicalSerialize;
+use std::sync::Arc;
+use transcript::IOPTranscript;
+
+/// The label under which a composer absorbs each step.
+const STEP_LABEL: &[u8] = b"composed step";
+
+/// The kinds of the sub-protocols, absorbed with the index of each step.
+const SUM_CHECK: u8 = 1;
+const PRODUCT_CHECK: u8 = 2;
+const PERMUTATION_CHECK: u8 = 3;
+
+/// A transcript shared by a sequence of sub-protocols.
+///
+/// The prover calls the `run_*` methods, and the verifier the `verify_*`
+/// methods in the same order, each with a composer created with the same
+/// label. Both sides collect the serialized proofs of the steps, so the
+/// blobs output by their [`IOPComposer::finalize`] are equal if the
+/// verifier replayed the prover's steps.
+#[derive(Clone)]
+pub struct IOPComposer<F: PrimeField> {
+    transcript: IOPTranscript<F>,
+    num_steps: u64,
+    proof_bytes: Vec<u8>,
+}
+
+impl<F: PrimeField> IOPComposer<F> {
+    /// A composer on a new transcript with the label `label`.
+    pub fn new(label: &'static [u8]) -> Self {
+        Self::from_transcript(IOPTranscript::new(label))
+    }
+
+    /// A composer on `transcript`, e.g., after the commitments of the
+    /// protocol that the sub-protocols are part of have been absorbed.
+    pub fn from_transcript(transcript: IOPTranscript<F>) -> Self {
+        Self {
+            transcript,
+            num_steps: 0,
+            proof_bytes: vec![],
+        }
+    }
+
+    /// Number of sub-protocols run or verified so far.
+    pub fn num_steps(&self) -> usize {
+        self.num_steps as usize
+    }
+
+    /// The transcript, to absorb messages between the steps.
+    pub fn transcript(&mut self) -> &mut IOPTranscript<F> {
+        &mut self.transcript
+    }
+
+    /// The serialized proofs of the steps, in order, and the transcript
+    /// after the last step.
+    pub fn finalize(self) -> (Vec<u8>, IOPTranscript<F>) {
+        (self.proof_bytes, self.transcript)
+    }
+
+    /// Absorb the next step, of the sub-protocol `kind`.
+    fn begin_step(&mut self, kind: u8) -> Result<(), PolyIOPErrors> {
+        let mut step = self.num_steps.to_le_bytes().to_vec();
+        step.push(kind);
+        self.transcript.append_message(STEP_LABEL, &step)?;
+        self.num_steps += 1;
+        Ok(())
+    }
+
+    /// Prove the sum of `poly` over the boolean hypercube.
+    pub fn run_sumcheck(
+        &mut self,
+        poly: &VirtualPolynomial<F>,
+    ) -> Result<IOPProof<F>, PolyIOPErrors> {
+        self.begin_step(SUM_CHECK)?;
+        let proof = <PolyIOP<F> as SumCheck<F>>::prove(poly, &mut self.transcript)?;
+        proof.serialize_compressed(&mut self.proof_bytes)?;
+        Ok(proof)
+    }
+
+    /// Verify the sum check `proof` of `claimed_sum`.
+    pub fn verify_sumcheck(
+        &mut self,
+        claimed_sum: F,
+        proof: &IOPProof<F>,
+        aux_info: &VPAuxInfo<F>,
+    ) -> Result<SumCheckSubClaim<F>, PolyIOPErrors> {
+        self.begin_step(SUM_CHECK)?;
+        let subclaim = <PolyIOP<F> as SumCheck<F>>::verify(
+            claimed_sum,
+            proof,
+            aux_info,
+            &mut self.transcript,
+        )?;
+        proof.serialize_compressed(&mut self.proof_bytes)?;
+        Ok(subclaim)
+    }
+
+    /// Prove that the products of `fxs` and of `gxs` over the boolean
+    /// hypercube are equal, and return the proof with `prod(x)` and
+    /// `frac(x)`.
+    #[allow(clippy::type_complexity)]
+    pub fn run_product_check<E, PCS>(
+        &mut self,
+        pcs_param: &PCS::ProverParam,
+        fxs: &[Arc<DenseMultilinearExtension<F>>],
+        gxs: &[Arc<DenseMultilinearExtension<F>>],
+    ) -> Result<
+        (
+            ProductCheckProof<E, PCS, PolyIOP<F>>,
+            Arc<DenseMultilinearExtension<F>>,
+            Arc<DenseMultilinearExtension<F>>,
+        ),
+        PolyIOPErrors,
+    >
+    where
+        E: Pairing<ScalarField = F>,
+        PCS: PolynomialCommitmentScheme<E, Polynomial = Arc<DenseMultilinearExtension<F>>>,
+    {
+        self.begin_step(PRODUCT_CHECK)?;
+        let (proof, prod_x, frac_x) =
+            <PolyIOP<F> as ProductCheck<E, PCS>>::prove(pcs_param, fxs, gxs, &mut self.transcript)?;
+        self.append_product_check_proof(&proof)?;
+        Ok((proof, prod_x, frac_x))
+    }
+
+    /// Verify the product check `proof`.
+    pub fn verify_product_check<E, PCS>(
+        &mut self,
+        proof: &ProductCheckProof<E, PCS, PolyIOP<F>>,
+        aux_info: &VPAuxInfo<F>,
+    ) -> Result<ProductCheckSubClaim<F, PolyIOP<F>>, PolyIOPErrors>
+    where
+        E: Pairing<ScalarField = F>,
+        PCS: PolynomialCommitmentScheme<E, Polynomial = Arc<DenseMultilinearExtension<F>>>,
+    {
+        self.begin_step(PRODUCT_CHECK)?;
+        let subclaim =
+            <PolyIOP<F> as ProductCheck<E, PCS>>::verify(proof, aux_info, &mut self.transcript)?;
+        self.append_product_check_proof(proof)?;
+        Ok(subclaim)
+    }
+
+    /// Prove that `gxs` is the permutation of `fxs` under `perms`, and
+    /// return the proof with `prod(x)` and `frac(x)`.
+    #[allow(clippy::type_complexity)]
+    pub fn run_permutation_check<E, PCS>(
+        &mut self,
+        pcs_param: &PCS::ProverParam,
+        fxs: &[Arc<DenseMultilinearExtension<F>>],
+        gxs: &[Arc<DenseMultilinearExtension<F>>],
+        perms: &[Arc<DenseMultilinearExtension<F>>],
+    ) -> Result<
+        (
+            ProductCheckProof<E, PCS, PolyIOP<F>>,
+            Arc<DenseMultilinearExtension<F>>,
+            Arc<DenseMultilinearExtension<F>>,
+        ),
+        PolyIOPErrors,
+    >
+    where
+        E: Pairing<ScalarField = F>,
+        PCS: PolynomialCommitmentScheme<E, Polynomial = Arc<DenseMultilinearExtension<F>>>,
+    {
+        self.begin_step(PERMUTATION_CHECK)?;
+        let (proof, prod_x, frac_x) = <PolyIOP<F> as PermutationCheck<E, PCS>>::prove(
+            pcs_param,
+            fxs,
+            gxs,
+            perms,
+            &mut self.transcript,
+        )?;
+        self.append_product_check_proof(&proof)?;
+        Ok((proof, prod_x, frac_x))
+    }
+
+    /// Verify the permutation check `proof`.
+    pub fn verify_permutation_check<E, PCS>(
+        &mut self,
+        proof: &ProductCheckProof<E, PCS, PolyIOP<F>>,
+        aux_info: &VPAuxInfo<F>,
+    ) -> Result<PermutationCheckSubClaim<E, PCS, PolyIOP<F>>, PolyIOPErrors>
+    where
+        E: Pairing<ScalarField = F>,
+        PCS: PolynomialCommitmentScheme<E, Polynomial = Arc<DenseMultilinearExtension<F>>>,
+    {
+        self.begin_step(PERMUTATION_CHECK)?;
+        let subclaim = <PolyIOP<F> as PermutationCheck<E, PCS>>::verify(
+            proof,
+            aux_info,
+            &mut self.transcript,
+        )?;
+        self.append_product_check_proof(proof)?;
+        Ok(subclaim)
+    }
+
+    /// Append the zero check proof and the commitments of `proof` to the
+    /// proof bytes.
+    fn append_product_check_proof<E, PCS>(
+        &mut self,
+        proof: &ProductCheckProof<E, PCS, PolyIOP<F>>,
+    ) -> Result<(), PolyIOPErrors>
+    where
+        E: Pairing<ScalarField = F>,
+        PCS: PolynomialCommitmentScheme<E>,
+    {
+        proof
+            .zero_check_proof
+            .serialize_compressed(&mut self.proof_bytes)?;
+        proof
+            .prod_x_comm
+            .serialize_compressed(&mut self.proof_bytes)?;
+        proof
+            .frac_comm
+            .serialize_compressed(&mut self.proof_bytes)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pcs::prelude::MultilinearKzgPCS;
+    use arithmetic::identity_permutation_mles;
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_poly::MultilinearExtension;
+    use ark_std::test_rng;
+    use std::marker::PhantomData;
+
+    type Kzg = MultilinearKzgPCS<Bls12_381>;
+
+    #[test]
+    fn test_composer() -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        let nv = 3;
+        let srs = Kzg::gen_srs_for_testing(&mut rng, nv)?;
+        let (pcs_param, _) = Kzg::trim(&srs, None, Some(nv))?;
+
+        let (poly, sum) = VirtualPolynomial::<Fr>::rand(nv, (2, 3), 2, &mut rng)?;
+        let w1 = Arc::new(DenseMultilinearExtension::<Fr>::rand(nv, &mut rng));
+        let w2 = Arc::new(DenseMultilinearExtension::<Fr>::rand(nv, &mut rng));
+        let mut perms = identity_permutation_mles(nv, 2);
+        perms.reverse();
+        let aux_info = VPAuxInfo {
+            max_degree: 3,
+            num_variables: nv,
+            per_variable_degree: vec![],
+            is_sparse: false,
+            phantom: PhantomData,
+        };
+
+        let mut prover = IOPComposer::<Fr>::new(b"composer test");
+        let sum_proof = prover.run_sumcheck(&poly)?;
+        let (prod_proof, prod_x, _) = prover.run_product_check::<Bls12_381, Kzg>(
+            &pcs_param,
+            &[w1.clone(), w2.clone()],
+            &[w2.clone(), w1.clone()],
+        )?;
+        let (perm_proof, perm_prod_x, _) = prover.run_permutation_check::<Bls12_381, Kzg>(
+            &pcs_param,
+            &[w1.clone(), w2.clone()],
+            &[w2, w1],
+            &perms,
+        )?;
+        assert_eq!(prover.num_steps(), 3);
+        let (prover_bytes, mut prover_transcript) = prover.finalize();
+
+        let mut verifier = IOPComposer::<Fr>::new(b"composer test");
+        let subclaim = verifier.verify_sumcheck(sum, &sum_proof, &poly.aux_info)?;
+        assert_eq!(
+            poly.evaluate(&subclaim.point)?,
+            subclaim.expected_evaluation
+        );
+        let subclaim = verifier.verify_product_check::<Bls12_381, Kzg>(&prod_proof, &aux_info)?;
+        for (_, point, eval) in subclaim.required_openings.iter() {
+            assert_eq!(prod_x.evaluate(point).unwrap(), *eval);
+        }
+        let subclaim =
+            verifier.verify_permutation_check::<Bls12_381, Kzg>(&perm_proof, &aux_info)?;
+        for (_, point, eval) in subclaim.product_check_sub_claim.required_openings.iter() {
+            assert_eq!(perm_prod_x.evaluate(point).unwrap(), *eval);
+        }
+        let (verifier_bytes, mut verifier_transcript) = verifier.finalize();
+
+        // both sides end in the same state, with the same proofs
+        assert_eq!(prover_bytes, verifier_bytes);
+        assert_eq!(
+            prover_transcript.get_and_append_challenge(b"end")?,
+            verifier_transcript.get_and_append_challenge(b"end")?
+        );
+
+        // the steps are domain separated: the same sum check as a second
+        // step is a different proof, which does not verify as a first step
+        let mut prover = IOPComposer::<Fr>::new(b"composer test");
+        prover.run_sumcheck(&poly)?;
+        let second_proof = prover.run_sumcheck(&poly)?;
+        assert_ne!(second_proof, sum_proof);
+        let mut verifier = IOPComposer::<Fr>::new(b"composer test");
+        assert!(verifier
+            .verify_sumcheck(sum, &second_proof, &poly.aux_info)
+            .map_or(true, |subclaim| poly.evaluate(&subclaim.point).unwrap()
+                != subclaim.expected_evaluation));
+
+        Ok(())
+    }
+}
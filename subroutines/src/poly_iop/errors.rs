@@ -6,14 +6,21 @@
 
 //! Error module.
 
-use crate::pcs::prelude::PCSError;
+use crate::{
+    pcs::prelude::PCSError,
+    poly_iop::{proof_format::SUPPORTED_PROOF_FORMATS, structs::VerifierTrace},
+};
 use arithmetic::ArithErrors;
+use ark_ff::PrimeField;
 use ark_std::string::String;
+#[cfg(feature = "display")]
 use displaydoc::Display;
+use std::any::Any;
 use transcript::TranscriptError;
 
 /// A `enum` specifying the possible failure modes of the PolyIOP.
-#[derive(Display, Debug)]
+#[derive(Debug)]
+#[cfg_attr(feature = "display", derive(Display))]
 pub enum PolyIOPErrors {
     /// Invalid Prover: {0}
     InvalidProver(String),
@@ -23,18 +30,155 @@ pub enum PolyIOPErrors {
     InvalidProof(String),
     /// Invalid parameters: {0}
     InvalidParameters(String),
-    /// Invalid challenge: {0}
-    InvalidChallenge(String),
+    /// Invalid challenge in round {round}: {value}
+    InvalidChallenge {
+        /// the round of the sum check that sampled the challenge, or 0 for
+        /// a challenge sampled outside of a sum check
+        round: usize,
+        /// why the challenge is rejected, with its value
+        value: String,
+    },
+    /// Invalid number of variables {0}: must be between 1 and {1}
+    InvalidNumVariables(usize, usize),
+    /// Invalid degree {0}: must be between 1 and {1}
+    InvalidDegree(usize, usize),
+    /// Sum check degree mismatch: claimed {claimed}, actual {actual}
+    SumCheckDegreeMismatch {
+        /// the max degree of the auxiliary information
+        claimed: usize,
+        /// the degree of the polynomial or of the prover message
+        actual: usize,
+    },
+    /// The random mask has already been used in another proof
+    MaskReused,
+    /// The monomial {gate_index} of the gate duplicates a previous one
+    DuplicateConstraint {
+        /// the index of the duplicate monomial in the gate
+        gate_index: usize,
+    },
+    /// Sum check round {round} is not consistent with the claimed sum
+    InconsistentRound {
+        /// the failing round, starting from 0
+        round: usize,
+        /// the `VerifierTrace` up to the failing round, see
+        /// `PolyIOPErrors::verifier_trace`
+        trace: Box<dyn Any + Send + Sync>,
+    },
+    /// Proof {index} of the batch failed to verify: {source}
+    BatchProofFailed {
+        /// the index of the failing proof in the batch
+        index: usize,
+        /// the error of that proof
+        source: Box<PolyIOPErrors>,
+    },
+    /// Unsupported proof format version {version}, supported: {supported:?}
+    UnsupportedVersion {
+        /// the version of the proof format
+        version: u32,
+        /// the supported versions, oldest first
+        supported: Vec<u32>,
+    },
     /// Should not arrive to this point
     ShouldNotArrive,
     /// An error during (de)serialization: {0}
     SerializationErrors(ark_serialize::SerializationError),
-    /// Transcript Error: {0}
-    TranscriptErrors(TranscriptError),
+    /// Transcript error: {0}
+    TranscriptError(TranscriptErrorKind),
     /// Arithmetic Error: {0}
     ArithmeticErrors(ArithErrors),
-    /// PCS error {0}
-    PCSErrors(PCSError),
+    /// Commitment error: {0}
+    CommitmentError(CommitmentErrorKind),
+}
+
+/// The cause of a transcript failure.
+#[derive(Debug)]
+#[cfg_attr(feature = "display", derive(Display))]
+pub enum TranscriptErrorKind {
+    /// invalid transcript: {0}
+    InvalidTranscript(String),
+    /// serialization: {0}
+    Serialization(ark_serialize::SerializationError),
+}
+
+/// The cause of a failure of the polynomial commitment scheme.
+#[derive(Debug)]
+#[cfg_attr(feature = "display", derive(Display))]
+pub enum CommitmentErrorKind {
+    /// committing to {0}: {1}
+    Commit(&'static str, PCSError),
+    /// PCS: {0}
+    Pcs(PCSError),
+}
+
+/// Display as `Debug` without the `display` feature.
+#[cfg(not(feature = "display"))]
+impl ark_std::fmt::Display for PolyIOPErrors {
+    fn fmt(&self, f: &mut ark_std::fmt::Formatter<'_>) -> ark_std::fmt::Result {
+        ark_std::fmt::Debug::fmt(self, f)
+    }
+}
+
+#[cfg(not(feature = "display"))]
+impl ark_std::fmt::Display for TranscriptErrorKind {
+    fn fmt(&self, f: &mut ark_std::fmt::Formatter<'_>) -> ark_std::fmt::Result {
+        ark_std::fmt::Debug::fmt(self, f)
+    }
+}
+
+#[cfg(not(feature = "display"))]
+impl ark_std::fmt::Display for CommitmentErrorKind {
+    fn fmt(&self, f: &mut ark_std::fmt::Formatter<'_>) -> ark_std::fmt::Result {
+        ark_std::fmt::Debug::fmt(self, f)
+    }
+}
+
+impl PolyIOPErrors {
+    /// The error of a sum check whose round `round` failed, with the trace
+    /// of the verifier up to that round.
+    pub(crate) fn inconsistent_round<F: PrimeField>(round: usize, trace: VerifierTrace<F>) -> Self {
+        Self::InconsistentRound {
+            round,
+            trace: Box::new(trace),
+        }
+    }
+
+    /// The error of a sum check whose auxiliary information claims the max
+    /// degree `claimed`, for a polynomial or a prover message of degree
+    /// `actual`.
+    pub(crate) fn degree_mismatch(claimed: usize, actual: usize) -> Self {
+        Self::SumCheckDegreeMismatch { claimed, actual }
+    }
+
+    /// The error of a proof in the format `version`, which has no codec.
+    pub(crate) fn unsupported_version(version: u32) -> Self {
+        Self::UnsupportedVersion {
+            version,
+            supported: SUPPORTED_PROOF_FORMATS.to_vec(),
+        }
+    }
+
+    /// The error of the commitment to the polynomial `poly`.
+    pub(crate) fn commit_failed(poly: &'static str, source: PCSError) -> Self {
+        Self::CommitmentError(CommitmentErrorKind::Commit(poly, source))
+    }
+
+    /// The error of proof `index` of a batch.
+    pub(crate) fn batch_proof_failed(index: usize, source: Self) -> Self {
+        Self::BatchProofFailed {
+            index,
+            source: Box::new(source),
+        }
+    }
+
+    /// The trace of the verifier attached to a failed sum check over `F`,
+    /// if any.
+    pub fn verifier_trace<F: PrimeField>(&self) -> Option<&VerifierTrace<F>> {
+        match self {
+            Self::InconsistentRound { trace, .. } => trace.downcast_ref::<VerifierTrace<F>>(),
+            Self::BatchProofFailed { source, .. } => source.verifier_trace(),
+            _ => None,
+        }
+    }
 }
 
 impl From<ark_serialize::SerializationError> for PolyIOPErrors {
@@ -45,7 +189,10 @@ impl From<ark_serialize::SerializationError> for PolyIOPErrors {
 
 impl From<TranscriptError> for PolyIOPErrors {
     fn from(e: TranscriptError) -> Self {
-        Self::TranscriptErrors(e)
+        Self::TranscriptError(match e {
+            TranscriptError::InvalidTranscript(msg) => TranscriptErrorKind::InvalidTranscript(msg),
+            TranscriptError::SerializationError(e) => TranscriptErrorKind::Serialization(e),
+        })
     }
 }
 
@@ -57,6 +204,72 @@ impl From<ArithErrors> for PolyIOPErrors {
 
 impl From<PCSError> for PolyIOPErrors {
     fn from(e: PCSError) -> Self {
-        Self::PCSErrors(e)
+        Self::CommitmentError(CommitmentErrorKind::Pcs(e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_structured_errors() {
+        let err = PolyIOPErrors::from(TranscriptError::InvalidTranscript("empty".to_string()));
+        assert!(matches!(
+            &err,
+            PolyIOPErrors::TranscriptError(TranscriptErrorKind::InvalidTranscript(msg))
+                if msg == "empty"
+        ));
+        let err = PolyIOPErrors::from(TranscriptError::SerializationError(
+            ark_serialize::SerializationError::InvalidData,
+        ));
+        assert!(matches!(
+            err,
+            PolyIOPErrors::TranscriptError(TranscriptErrorKind::Serialization(_))
+        ));
+
+        let err = PolyIOPErrors::from(PCSError::InvalidParameters("too large".to_string()));
+        assert!(matches!(
+            err,
+            PolyIOPErrors::CommitmentError(CommitmentErrorKind::Pcs(_))
+        ));
+        let err = PolyIOPErrors::commit_failed(
+            "prod(x)",
+            PCSError::InvalidParameters("too large".to_string()),
+        );
+        assert!(matches!(
+            err,
+            PolyIOPErrors::CommitmentError(CommitmentErrorKind::Commit("prod(x)", _))
+        ));
+    }
+
+    #[cfg(feature = "display")]
+    #[test]
+    fn test_structured_errors_display() {
+        assert_eq!(
+            PolyIOPErrors::degree_mismatch(3, 4).to_string(),
+            "Sum check degree mismatch: claimed 3, actual 4"
+        );
+        assert_eq!(
+            PolyIOPErrors::InvalidChallenge {
+                round: 2,
+                value: "1 is 0 or 1".to_string(),
+            }
+            .to_string(),
+            "Invalid challenge in round 2: 1 is 0 or 1"
+        );
+        assert_eq!(
+            PolyIOPErrors::from(TranscriptError::InvalidTranscript("empty".to_string()))
+                .to_string(),
+            "Transcript error: invalid transcript: empty"
+        );
+        assert_eq!(
+            PolyIOPErrors::commit_failed(
+                "prod(x)",
+                PCSError::InvalidParameters("too large".to_string()),
+            )
+            .to_string(),
+            "Commitment error: committing to prod(x): Invalid parameters: too large"
+        );
     }
 }
@@ -0,0 +1,972 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! Main module for the grand product check, which proves the product of the
+//! evaluations of a virtual polynomial over the boolean hypercube without
+//! committing to any auxiliary polynomial.
+//!
+//! The evaluations are multiplied pairwise in a binary tree. Layer `k` of the
+//! tree has `2^k` entries, entry `y` being the product of the entries `2y`
+//! and `2y + 1` of layer `k + 1`, and layer `num_vars` holds the evaluations
+//! of the polynomial. A claim on the multilinear extension of layer `k` at a
+//! random point is reduced with a sum check to a claim on layer `k + 1`, and
+//! the claim on the last layer to a claim on the polynomial at a random
+//! point, as in [Tha13](https://eprint.iacr.org/2013/351).
+//!
+//! The product is not reduced with the logarithmic derivative, as in the
+//! lookup check. The logarithmic derivative turns `\prod_x (X - f(x))` into
+//! `\sum_x 1 / (X - f(x))`, which is a sum check on a polynomial only once
+//! the prover commits to the inverses `1 / (X - f(x))`, and it compares two
+//! such products at a random `X` rather than proving the value of the
+//! product of `f(x)`. The product tree needs no commitment, so the check is
+//! an IOP of sum checks only, ending in a single claim on `f(x)`, at the
+//! cost of `num_vars` sum checks, `O(num_vars^2)` field elements, instead of
+//! one.
+
+use std::{fmt::Debug, marker::PhantomData, sync::Arc};
+
+use crate::poly_iop::{
+    errors::PolyIOPErrors,
+    structs::{IOPProof, RandomMaskPolynomial},
+    sum_check::{check_num_variables, SumCheck},
+    zk_sum_check::{prove_with_rho, verify_with_rho, ZkConfig, ZkSumCheck},
+    PolyIOP,
+};
+use arithmetic::{eq_eval, evaluate_opt, VPAuxInfo, VirtualPolynomial};
+use ark_ff::PrimeField;
+use ark_poly::DenseMultilinearExtension;
+use ark_std::{cfg_into_iter, end_timer, start_timer};
+#[cfg(feature = "parallel")]
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use transcript::IOPTranscript;
+
+/// A grand product subclaim for `f(x)`: the polynomial evaluates to
+/// `expected_evaluation` at `point`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProductSubClaim<F: PrimeField> {
+    /// the evaluation point
+    pub point: Vec<F>,
+    /// the expected evaluation
+    pub expected_evaluation: F,
+}
+
+/// A grand product proof for a polynomial with `num_vars` variables.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GrandProductProof<F: PrimeField> {
+    /// the sum checks reducing layers `1..num_vars` of the tree
+    pub layer_proofs: Vec<IOPProof<F>>,
+    /// the evaluations of the even and odd halves of layers `1..=num_vars`
+    /// at the points of the above sum checks
+    pub layer_evals: Vec<(F, F)>,
+    /// the sum check reducing the last layer to the polynomial
+    pub proof: IOPProof<F>,
+}
+
+/// A GrandProductCheck for `f(x)` proves that the product of `f(x)` over
+/// `x \in {0,1}^num_vars` is a claimed value.
+/// It is derived from SumCheck.
+pub trait GrandProductCheck<F: PrimeField>: SumCheck<F> {
+    type ProductSubClaim: Clone + Debug + Default + PartialEq;
+    type GrandProductProof: Clone + Debug + Default + PartialEq;
+
+    /// Initialize the system with a transcript
+    ///
+    /// This function is optional -- in the case where a GrandProductCheck is
+    /// an building block for a more complex protocol, the transcript
+    /// may be initialized by this complex protocol, and passed to the
+    /// GrandProductCheck prover/verifier.
+    fn init_transcript() -> Self::Transcript;
+
+    /// Extract the product of the evaluations from the proof.
+    fn extract_product(proof: &Self::GrandProductProof) -> F;
+
+    /// Prove the product of the evaluations of `poly` over
+    /// {0,1}^`num_vars`.
+    fn prove(
+        poly: &Self::VirtualPolynomial,
+        transcript: &mut Self::Transcript,
+    ) -> Result<Self::GrandProductProof, PolyIOPErrors>;
+
+    /// Verify the claimed product using the proof.
+    fn verify(
+        claimed_product: F,
+        proof: &Self::GrandProductProof,
+        aux_info: &Self::VPAuxInfo,
+        transcript: &mut Self::Transcript,
+    ) -> Result<Self::ProductSubClaim, PolyIOPErrors>;
+}
+
+impl<F: PrimeField> GrandProductCheck<F> for PolyIOP<F> {
+    type ProductSubClaim = ProductSubClaim<F>;
+    type GrandProductProof = GrandProductProof<F>;
+
+    fn init_transcript() -> IOPTranscript<F> {
+        IOPTranscript::<F>::new(b"Initializing GrandProductCheck transcript")
+    }
+
+    fn extract_product(proof: &Self::GrandProductProof) -> F {
+        proof
+            .layer_evals
+            .first()
+            .map_or_else(F::one, |(even, odd)| *even * odd)
+    }
+
+    fn prove(
+        poly: &VirtualPolynomial<F>,
+        transcript: &mut IOPTranscript<F>,
+    ) -> Result<Self::GrandProductProof, PolyIOPErrors> {
+        let start = start_timer!(|| "grand product check prove");
+
+        let layers = prove_layers(poly, None, transcript)?;
+        let f_hat = poly.build_f_hat(&layers.point)?;
+        let proof = <Self as SumCheck<F>>::prove(&f_hat, transcript)?;
+
+        end_timer!(start);
+        Ok(GrandProductProof {
+            layer_proofs: layers.layer_proofs,
+            layer_evals: layers.layer_evals,
+            proof,
+        })
+    }
+
+    fn verify(
+        claimed_product: F,
+        proof: &Self::GrandProductProof,
+        fx_aux_info: &VPAuxInfo<F>,
+        transcript: &mut IOPTranscript<F>,
+    ) -> Result<Self::ProductSubClaim, PolyIOPErrors> {
+        let start = start_timer!(|| "grand product check verify");
+
+        let (claim, point, _) = verify_layers(
+            claimed_product,
+            &proof.layer_proofs,
+            &proof.layer_evals,
+            None,
+            fx_aux_info.num_variables,
+            transcript,
+        )?;
+
+        // hat_fx's max degree is increased by eq(x, r).degree() which is 1
+        let mut hat_fx_aux_info = fx_aux_info.clone();
+        hat_fx_aux_info.max_degree += 1;
+        let sum_subclaim =
+            <Self as SumCheck<F>>::verify(claim, &proof.proof, &hat_fx_aux_info, transcript)?;
+
+        let eq_x_r_eval = eq_eval(&sum_subclaim.point, &point)?;
+        let expected_evaluation = sum_subclaim.expected_evaluation / eq_x_r_eval;
+
+        end_timer!(start);
+        Ok(ProductSubClaim {
+            point: sum_subclaim.point,
+            expected_evaluation,
+        })
+    }
+}
+
+/// A zero knowledge grand product subclaim for `f(x)`, blinded with `R(x)`
+/// and masked with `g(x)`:
+/// `f(point) + blinding_coefficient * R(point)
+///     + mask_coefficient * g(mask_point) = expected_evaluation`,
+/// and `g(x)` evaluates at each point of `mask_evaluations` to the value
+/// next to it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ZkProductSubClaim<F: PrimeField> {
+    /// the evaluation point of the polynomial and of the blinding polynomial
+    pub point: Vec<F>,
+    /// the expected evaluation
+    pub expected_evaluation: F,
+    /// the coefficient of the blinding polynomial
+    pub blinding_coefficient: F,
+    /// the evaluation point of the mask polynomial
+    pub mask_point: Vec<F>,
+    /// the coefficient of the mask polynomial
+    pub mask_coefficient: F,
+    /// the evaluations of the mask polynomial sent for the layer sum checks,
+    /// with their points
+    pub mask_evaluations: Vec<(Vec<F>, F)>,
+}
+
+/// A zero knowledge grand product proof for a polynomial with `num_vars`
+/// variables.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ZkGrandProductProof<F: PrimeField> {
+    /// the zk sum checks reducing layers `1..=num_vars` of the blinded tree
+    pub layer_proofs: Vec<IOPProof<F>>,
+    /// the evaluations of the even and odd halves of layers
+    /// `1..=num_vars + 1` of the blinded tree at the points of the above sum
+    /// checks
+    pub layer_evals: Vec<(F, F)>,
+    /// the sums of the masks of the layer sum checks
+    pub layer_mask_sums: Vec<F>,
+    /// the evaluations of the masks of the layer sum checks at their points
+    pub layer_mask_evals: Vec<F>,
+    /// the sum of the mask polynomial over the boolean hypercube
+    pub mask_sum: F,
+    /// the zk sum check reducing the last layer to the polynomial
+    pub proof: IOPProof<F>,
+}
+
+/// A ZkGrandProductCheck for `f(x)` is a GrandProductCheck on the product
+/// tree of `W(x, t) = (1 - t) f(x) + t R(x)` for a random blinding
+/// polynomial `R(x)`, whose sum checks are all zk sum checks.
+///
+/// As `t` is the last variable of `W`, layer `1` of the tree is the product
+/// of `f(x)`, which is the claimed product, and the one of `R(x)`. The other
+/// layer evaluations are at a random `t`, where they mix the partial
+/// products of `f(x)` with the ones of `R(x)`.
+///
+/// The sum check of layer `k` is masked with `rho` times the restriction of
+/// the mask polynomial `g(x, t)` to its first `k` variables, the others
+/// being 0, and the last sum check with `rho` times `g(x, t)`. The sums of
+/// these masks are sent with the proof and added to the claims of the sum
+/// checks, and so are their evaluations at the points of the layer sum
+/// checks, which the verifier returns as claims on `g(x, t)`.
+pub trait ZkGrandProductCheck<F: PrimeField>: ZkSumCheck<F> {
+    type ZkProductSubClaim: Clone + Debug + Default + PartialEq;
+    type ZkGrandProductProof: Clone + Debug + Default + PartialEq;
+
+    /// Initialize the system with a transcript
+    ///
+    /// This function is optional -- in the case where a ZkGrandProductCheck
+    /// is an building block for a more complex protocol, the transcript
+    /// may be initialized by this complex protocol, and passed to the
+    /// ZkGrandProductCheck prover/verifier.
+    fn init_transcript() -> Self::Transcript;
+
+    /// Extract the product of the evaluations from the proof.
+    fn extract_product(proof: &Self::ZkGrandProductProof) -> F;
+
+    /// Prove the product of the evaluations of `poly` over
+    /// {0,1}^`num_vars`, blinding the product tree with `blinding`, a random
+    /// MLE with `num_vars` variables, and masking the sum checks with `rho`
+    /// times `mask_poly`, which has `num_vars + 1` variables.
+    ///
+    /// `rho` must be sampled after `blinding` and `mask_poly` are fixed, see
+    /// `ZkSumCheck::prove_bound`.
+    fn prove(
+        poly: &Self::VirtualPolynomial,
+        blinding: &Self::MultilinearExtension,
+        mask_poly: &Self::RandomMaskPolynomial,
+        rho: &F,
+        transcript: &mut Self::Transcript,
+    ) -> Result<Self::ZkGrandProductProof, PolyIOPErrors>;
+
+    /// Verify the claimed product using the proof, for a mask polynomial
+    /// with `mask_poly_nv` variables, one more than the polynomial, of degree
+    /// `mask_poly_degree`.
+    fn verify(
+        claimed_product: F,
+        proof: &Self::ZkGrandProductProof,
+        aux_info: &Self::VPAuxInfo,
+        rho: &F,
+        transcript: &mut Self::Transcript,
+        mask_poly_nv: Self::MPNumV,
+        mask_poly_degree: Self::MPDeg,
+    ) -> Result<Self::ZkProductSubClaim, PolyIOPErrors>;
+}
+
+impl<F: PrimeField> ZkGrandProductCheck<F> for PolyIOP<F> {
+    type ZkProductSubClaim = ZkProductSubClaim<F>;
+    type ZkGrandProductProof = ZkGrandProductProof<F>;
+
+    fn init_transcript() -> IOPTranscript<F> {
+        IOPTranscript::<F>::new(b"Initializing ZkGrandProductCheck transcript")
+    }
+
+    fn extract_product(proof: &Self::ZkGrandProductProof) -> F {
+        proof
+            .layer_evals
+            .first()
+            .map_or_else(F::one, |(product, _)| *product)
+    }
+
+    fn prove(
+        poly: &VirtualPolynomial<F>,
+        blinding: &Arc<DenseMultilinearExtension<F>>,
+        mask_poly: &RandomMaskPolynomial<F>,
+        rho: &F,
+        transcript: &mut IOPTranscript<F>,
+    ) -> Result<Self::ZkGrandProductProof, PolyIOPErrors> {
+        let start = start_timer!(|| "zk grand product check prove");
+
+        check_num_variables(poly.aux_info.num_variables)?;
+        let blinded = blind(poly, blinding)?;
+        ZkConfig {
+            mask_degree: mask_poly
+                .evaluations
+                .first()
+                .map_or(0, |evals| evals.len().saturating_sub(1)),
+        }
+        .check_mask(&blinded.aux_info, mask_poly)?;
+        let layers = prove_layers(&blinded, Some((mask_poly, rho)), transcript)?;
+
+        let mask_sum = mask_sum(mask_poly);
+        transcript.append_field_element(b"mask sum", &mask_sum)?;
+        let f_hat = blinded.build_f_hat(&layers.point)?;
+        let proof = prove_with_rho(&f_hat, mask_poly, rho, transcript)?;
+
+        end_timer!(start);
+        Ok(ZkGrandProductProof {
+            layer_proofs: layers.layer_proofs,
+            layer_evals: layers.layer_evals,
+            layer_mask_sums: layers.layer_mask_sums,
+            layer_mask_evals: layers.layer_mask_evals,
+            mask_sum,
+            proof,
+        })
+    }
+
+    fn verify(
+        claimed_product: F,
+        proof: &Self::ZkGrandProductProof,
+        fx_aux_info: &VPAuxInfo<F>,
+        rho: &F,
+        transcript: &mut IOPTranscript<F>,
+        mask_poly_nv: usize,
+        mask_poly_degree: usize,
+    ) -> Result<Self::ZkProductSubClaim, PolyIOPErrors> {
+        let start = start_timer!(|| "zk grand product check verify");
+
+        let num_vars = fx_aux_info.num_variables;
+        check_num_variables(num_vars)?;
+        if mask_poly_nv != num_vars + 1 {
+            return Err(PolyIOPErrors::InvalidParameters(format!(
+                "zk grand product check: mask of {} variables for {} variables",
+                mask_poly_nv, num_vars
+            )));
+        }
+        // layer 1 of the blinded tree is the product of f(x) and the one of
+        // R(x), whose product is the root
+        let blinding_product = match proof.layer_evals.first() {
+            Some(&(product, blinding_product)) if product == claimed_product => blinding_product,
+            _ => {
+                return Err(PolyIOPErrors::InvalidProof(
+                    "zk grand product check: the proof is not for the claimed product".to_string(),
+                ))
+            },
+        };
+        let (claim, point, mask_evaluations) = verify_layers(
+            claimed_product * blinding_product,
+            &proof.layer_proofs,
+            &proof.layer_evals,
+            Some(LayerMasks {
+                rho: *rho,
+                degree: mask_poly_degree,
+                sums: &proof.layer_mask_sums,
+                evals: &proof.layer_mask_evals,
+            }),
+            num_vars + 1,
+            transcript,
+        )?;
+
+        transcript.append_field_element(b"mask sum", &proof.mask_sum)?;
+        // hat_wx's max degree is increased by eq(x, r).degree() which is 1
+        let hat_wx_aux_info = VPAuxInfo {
+            max_degree: fx_aux_info.max_degree.max(1) + 1,
+            num_variables: num_vars + 1,
+            per_variable_degree: vec![],
+            is_sparse: false,
+            phantom: PhantomData,
+        };
+        let sum_subclaim = verify_with_rho(
+            claim + *rho * proof.mask_sum,
+            &proof.proof,
+            &hat_wx_aux_info,
+            transcript,
+            mask_poly_nv,
+            mask_poly_degree,
+        )?;
+
+        // the subclaim is on eq((x, t), r) * ((1 - t) f(x) + t R(x)) +
+        // rho * g(x, t)
+        let t = sum_subclaim.point[num_vars];
+        let eq_x_r_eval = eq_eval(&sum_subclaim.point, &point)?;
+        let scale = (eq_x_r_eval * (F::one() - t)).inverse().ok_or_else(|| {
+            PolyIOPErrors::InvalidProof(
+                "zk grand product check: eq(x, r) or 1 - t is zero".to_string(),
+            )
+        })?;
+
+        end_timer!(start);
+        Ok(ZkProductSubClaim {
+            point: sum_subclaim.point[..num_vars].to_vec(),
+            expected_evaluation: sum_subclaim.expected_evaluation * scale,
+            blinding_coefficient: t * eq_x_r_eval * scale,
+            mask_point: sum_subclaim.point,
+            mask_coefficient: *rho * scale,
+            mask_evaluations,
+        })
+    }
+}
+
+/// The reduction of the product of a polynomial by [`prove_layers`].
+#[derive(Default)]
+struct LayerProofs<F: PrimeField> {
+    /// the layer sum checks
+    layer_proofs: Vec<IOPProof<F>>,
+    /// the layer evaluations
+    layer_evals: Vec<(F, F)>,
+    /// the sums of the masks of the layer sum checks, if they are masked
+    layer_mask_sums: Vec<F>,
+    /// the evaluations of the masks at the points of the layer sum checks,
+    /// if they are masked
+    layer_mask_evals: Vec<F>,
+    /// the point of the claim on the polynomial
+    point: Vec<F>,
+}
+
+/// The masks of the layer sum checks of a zk grand product check, as seen by
+/// the verifier, see [`ZkGrandProductCheck`].
+struct LayerMasks<'a, F: PrimeField> {
+    /// the coefficient of the masks
+    rho: F,
+    /// the degree of the masks in each variable
+    degree: usize,
+    /// the sums of the masks
+    sums: &'a [F],
+    /// the evaluations of the masks at the points of the sum checks
+    evals: &'a [F],
+}
+
+/// Reduce the product of the evaluations of `poly` to a claim on the
+/// multilinear extension of its evaluations, masking the layer sum checks
+/// with `rho` times the restrictions of `mask_poly` if `mask` is given.
+fn prove_layers<F: PrimeField>(
+    poly: &VirtualPolynomial<F>,
+    mask: Option<(&RandomMaskPolynomial<F>, &F)>,
+    transcript: &mut IOPTranscript<F>,
+) -> Result<LayerProofs<F>, PolyIOPErrors> {
+    let num_vars = poly.aux_info.num_variables;
+    check_num_variables(num_vars)?;
+    let layers = build_layers(poly);
+    transcript.append_field_element(b"product", &layers[0][0])?;
+
+    let mut res = LayerProofs {
+        layer_proofs: Vec::with_capacity(num_vars.saturating_sub(1)),
+        layer_evals: Vec::with_capacity(num_vars),
+        ..Default::default()
+    };
+    for k in 0..num_vars {
+        let (even, odd) = split_layer(&layers[k + 1], k);
+        let (even_eval, odd_eval) = if k == 0 {
+            (even.evaluations[0], odd.evaluations[0])
+        } else {
+            let mut layer_poly = VirtualPolynomial::new(k);
+            layer_poly.add_mle_list([Arc::new(even.clone()), Arc::new(odd.clone())], F::one())?;
+            let f_hat = layer_poly.build_f_hat(&res.point)?;
+            let proof = match mask {
+                None => <PolyIOP<F> as SumCheck<F>>::prove(&f_hat, transcript)?,
+                Some((mask_poly, rho)) => {
+                    let layer_mask = restrict_mask(mask_poly, k);
+                    let mask_sum = mask_sum(&layer_mask);
+                    transcript.append_field_element(b"mask sum", &mask_sum)?;
+                    let proof = prove_with_rho(&f_hat, &layer_mask, rho, transcript)?;
+                    let mask_eval = layer_mask.eval(&proof.point)?;
+                    transcript.append_field_element(b"mask eval", &mask_eval)?;
+                    res.layer_mask_sums.push(mask_sum);
+                    res.layer_mask_evals.push(mask_eval);
+                    proof
+                },
+            };
+            res.point = proof.point.clone();
+            res.layer_proofs.push(proof);
+            (
+                evaluate_opt(&even, &res.point),
+                evaluate_opt(&odd, &res.point),
+            )
+        };
+        transcript.append_field_element(b"product layer even", &even_eval)?;
+        transcript.append_field_element(b"product layer odd", &odd_eval)?;
+        res.layer_evals.push((even_eval, odd_eval));
+
+        let lambda = transcript.get_and_append_challenge(b"product layer")?;
+        res.point.insert(0, lambda);
+    }
+
+    Ok(res)
+}
+
+/// Verify the reduction of `claimed_product` by [`prove_layers`] for a
+/// polynomial with `num_vars` variables, with the layer sum checks masked
+/// with `masks` if given.
+///
+/// Returns the claimed evaluation of the multilinear extension of the
+/// evaluations of the polynomial, the point, and the claimed evaluations of
+/// the mask polynomial, which has `num_vars` variables.
+#[allow(clippy::type_complexity)]
+fn verify_layers<F: PrimeField>(
+    claimed_product: F,
+    layer_proofs: &[IOPProof<F>],
+    layer_evals: &[(F, F)],
+    masks: Option<LayerMasks<F>>,
+    num_vars: usize,
+    transcript: &mut IOPTranscript<F>,
+) -> Result<(F, Vec<F>, Vec<(Vec<F>, F)>), PolyIOPErrors> {
+    check_num_variables(num_vars)?;
+    if layer_evals.len() != num_vars || layer_proofs.len() != num_vars - 1 {
+        return Err(PolyIOPErrors::InvalidProof(format!(
+            "grand product check: {} layer proofs and {} layer evaluations for {} variables",
+            layer_proofs.len(),
+            layer_evals.len(),
+            num_vars
+        )));
+    }
+    if let Some(masks) = &masks {
+        if masks.sums.len() != num_vars - 1 || masks.evals.len() != num_vars - 1 {
+            return Err(PolyIOPErrors::InvalidProof(format!(
+                "grand product check: {} mask sums and {} mask evaluations for {} variables",
+                masks.sums.len(),
+                masks.evals.len(),
+                num_vars
+            )));
+        }
+    }
+    transcript.append_field_element(b"product", &claimed_product)?;
+
+    let mut claim = claimed_product;
+    let mut point = vec![];
+    let mut mask_evaluations = vec![];
+    for (k, &(even_eval, odd_eval)) in layer_evals.iter().enumerate() {
+        let expected = if k == 0 {
+            claim
+        } else {
+            // the layer sum check is on eq(x, r) * even(x) * odd(x)
+            let aux_info = VPAuxInfo {
+                max_degree: 3,
+                num_variables: k,
+                per_variable_degree: vec![],
+                is_sparse: false,
+                phantom: PhantomData,
+            };
+            let (subclaim_point, evaluation) = match &masks {
+                None => {
+                    let subclaim = <PolyIOP<F> as SumCheck<F>>::verify(
+                        claim,
+                        &layer_proofs[k - 1],
+                        &aux_info,
+                        transcript,
+                    )?;
+                    (subclaim.point, subclaim.expected_evaluation)
+                },
+                Some(masks) => {
+                    // the mask is the restriction of the mask polynomial to
+                    // its first k variables
+                    let (mask_sum, mask_eval) = (masks.sums[k - 1], masks.evals[k - 1]);
+                    transcript.append_field_element(b"mask sum", &mask_sum)?;
+                    let subclaim = verify_with_rho(
+                        claim + masks.rho * mask_sum,
+                        &layer_proofs[k - 1],
+                        &aux_info,
+                        transcript,
+                        k,
+                        masks.degree,
+                    )?;
+                    transcript.append_field_element(b"mask eval", &mask_eval)?;
+                    let mut mask_point = subclaim.point.clone();
+                    mask_point.resize(num_vars, F::zero());
+                    mask_evaluations.push((mask_point, mask_eval));
+                    (
+                        subclaim.point,
+                        subclaim.expected_evaluation - masks.rho * mask_eval,
+                    )
+                },
+            };
+            let eq_x_r_eval = eq_eval(&subclaim_point, &point)?;
+            point = subclaim_point;
+            evaluation / eq_x_r_eval
+        };
+        if even_eval * odd_eval != expected {
+            return Err(PolyIOPErrors::InvalidProof(format!(
+                "grand product check: layer {} evaluates to {}, expected {}",
+                k,
+                even_eval * odd_eval,
+                expected
+            )));
+        }
+        transcript.append_field_element(b"product layer even", &even_eval)?;
+        transcript.append_field_element(b"product layer odd", &odd_eval)?;
+
+        let lambda = transcript.get_and_append_challenge(b"product layer")?;
+        claim = even_eval + lambda * (odd_eval - even_eval);
+        point.insert(0, lambda);
+    }
+
+    Ok((claim, point, mask_evaluations))
+}
+
+/// The polynomial `W(x, t) = (1 - t) f(x) + t R(x)` with `t` as its last
+/// variable, for `f(x) = poly` and `R(x) = blinding`.
+fn blind<F: PrimeField>(
+    poly: &VirtualPolynomial<F>,
+    blinding: &DenseMultilinearExtension<F>,
+) -> Result<VirtualPolynomial<F>, PolyIOPErrors> {
+    let poly = poly.lift();
+    let num_vars = poly.aux_info.num_variables;
+    if blinding.num_vars != num_vars {
+        return Err(PolyIOPErrors::InvalidParameters(format!(
+            "blinding polynomial of {} variables for {} variables",
+            blinding.num_vars, num_vars
+        )));
+    }
+    let zeros = vec![F::zero(); 1 << num_vars];
+    let extend = |low: &[F], high: &[F]| {
+        Arc::new(DenseMultilinearExtension::from_evaluations_vec(
+            num_vars + 1,
+            [low, high].concat(),
+        ))
+    };
+
+    // (1 - t) m(x) and m(x) for each MLE m(x) of f(x), so that each product
+    // of f(x) vanishes at t = 1 without increasing its degree
+    let (vanishing, constant): (Vec<_>, Vec<_>) = poly
+        .flattened_ml_extensions
+        .iter()
+        .map(|mle| {
+            (
+                extend(&mle.evaluations, &zeros),
+                extend(&mle.evaluations, &mle.evaluations),
+            )
+        })
+        .unzip();
+    let mut res = VirtualPolynomial::new(num_vars + 1);
+    for (coefficient, product) in poly.products.iter() {
+        res.add_mle_list(
+            product.iter().enumerate().map(|(j, &i)| {
+                if j == 0 {
+                    vanishing[i].clone()
+                } else {
+                    constant[i].clone()
+                }
+            }),
+            *coefficient,
+        )?;
+    }
+    res.add_mle_list([extend(&zeros, &blinding.evaluations)], F::one())?;
+    Ok(res)
+}
+
+/// The restriction of `mask_poly` to its first `num_vars` variables, the
+/// others being 0.
+fn restrict_mask<F: PrimeField>(
+    mask_poly: &RandomMaskPolynomial<F>,
+    num_vars: usize,
+) -> RandomMaskPolynomial<F> {
+    let (kept, fixed) = mask_poly.evaluations.split_at(num_vars);
+    RandomMaskPolynomial {
+        const_term: mask_poly.const_term + fixed.iter().map(|evals| evals[0]).sum::<F>(),
+        evaluations: kept.to_vec(),
+        tag: mask_poly.tag,
+    }
+}
+
+/// The sum of `mask_poly` over the boolean hypercube, which has as many
+/// variables as the mask.
+fn mask_sum<F: PrimeField>(mask_poly: &RandomMaskPolynomial<F>) -> F {
+    let num_vars = mask_poly.evaluations.len();
+    let sum: F = mask_poly
+        .evaluations
+        .iter()
+        .map(|evals| evals[0] + evals[1])
+        .sum();
+    sum * F::from(1u64 << (num_vars - 1)) + F::from(1u64 << num_vars) * mask_poly.const_term
+}
+
+/// Build the layers of the product tree of `poly`, from the root to the
+/// evaluations of `poly` over the boolean hypercube.
+fn build_layers<F: PrimeField>(poly: &VirtualPolynomial<F>) -> Vec<Vec<F>> {
+    let poly = poly.lift();
+    let num_vars = poly.aux_info.num_variables;
+
+    let mut layer: Vec<F> = cfg_into_iter!(0..1usize << num_vars)
+        .map(|x| {
+            poly.products
+                .iter()
+                .map(|(coefficient, product)| {
+                    product.iter().fold(*coefficient, |acc, &i| {
+                        acc * poly.flattened_ml_extensions[i][x]
+                    })
+                })
+                .sum()
+        })
+        .collect();
+
+    let mut layers = Vec::with_capacity(num_vars + 1);
+    for _ in 0..num_vars {
+        let next: Vec<F> = cfg_into_iter!(0..layer.len() / 2)
+            .map(|y| layer[2 * y] * layer[2 * y + 1])
+            .collect();
+        layers.push(layer);
+        layer = next;
+    }
+    layers.push(layer);
+    layers.reverse();
+    layers
+}
+
+/// Split layer `k + 1` of the product tree into the multilinear extensions
+/// of its even and odd entries, whose product is layer `k`.
+fn split_layer<F: PrimeField>(
+    layer: &[F],
+    k: usize,
+) -> (DenseMultilinearExtension<F>, DenseMultilinearExtension<F>) {
+    let (even, odd): (Vec<F>, Vec<F>) = layer.chunks(2).map(|pair| (pair[0], pair[1])).unzip();
+    (
+        DenseMultilinearExtension::from_evaluations_vec(k, even),
+        DenseMultilinearExtension::from_evaluations_vec(k, odd),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::{GrandProductCheck, ZkGrandProductCheck};
+    use crate::poly_iop::{errors::PolyIOPErrors, structs::RandomMaskPolynomial, PolyIOP};
+    use arithmetic::{evaluate_opt, VirtualPolynomial};
+    use ark_bls12_381::Fr;
+    use ark_ff::{One, UniformRand};
+    use ark_poly::{DenseMultilinearExtension, MultilinearExtension};
+    use ark_std::test_rng;
+    use std::sync::Arc;
+
+    fn test_grand_product(
+        nv: usize,
+        num_multiplicands_range: (usize, usize),
+        num_products: usize,
+    ) -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        let (poly, _sum) =
+            VirtualPolynomial::<Fr>::rand(nv, num_multiplicands_range, num_products, &mut rng)?;
+        let product = super::build_layers(&poly)[0][0];
+        let mut point = vec![Fr::one(); nv];
+        let mut expected = Fr::one();
+        for x in 0..1usize << nv {
+            for (i, p) in point.iter_mut().enumerate() {
+                *p = Fr::from(((x >> i) & 1) as u64);
+            }
+            expected *= poly.evaluate(&point)?;
+        }
+        assert_eq!(product, expected);
+
+        let mut transcript = <PolyIOP<Fr> as GrandProductCheck<Fr>>::init_transcript();
+        let proof = <PolyIOP<Fr> as GrandProductCheck<Fr>>::prove(&poly, &mut transcript)?;
+        assert_eq!(
+            <PolyIOP<Fr> as GrandProductCheck<Fr>>::extract_product(&proof),
+            product
+        );
+
+        let mut transcript = <PolyIOP<Fr> as GrandProductCheck<Fr>>::init_transcript();
+        let subclaim = <PolyIOP<Fr> as GrandProductCheck<Fr>>::verify(
+            product,
+            &proof,
+            &poly.aux_info,
+            &mut transcript,
+        )?;
+        assert!(
+            poly.evaluate(&subclaim.point)? == subclaim.expected_evaluation,
+            "wrong subclaim"
+        );
+
+        // a wrong claimed product is rejected
+        let mut transcript = <PolyIOP<Fr> as GrandProductCheck<Fr>>::init_transcript();
+        assert!(<PolyIOP<Fr> as GrandProductCheck<Fr>>::verify(
+            product + Fr::one(),
+            &proof,
+            &poly.aux_info,
+            &mut transcript,
+        )
+        .is_err());
+
+        // a tampered layer evaluation is rejected
+        let mut bad_proof = proof.clone();
+        bad_proof.layer_evals[nv - 1].0 += Fr::one();
+        let mut transcript = <PolyIOP<Fr> as GrandProductCheck<Fr>>::init_transcript();
+        assert!(<PolyIOP<Fr> as GrandProductCheck<Fr>>::verify(
+            product,
+            &bad_proof,
+            &poly.aux_info,
+            &mut transcript,
+        )
+        .is_err());
+
+        Ok(())
+    }
+
+    fn test_zk_productcheck(
+        nv: usize,
+        num_multiplicands_range: (usize, usize),
+        num_products: usize,
+    ) -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        let (poly, _sum) =
+            VirtualPolynomial::<Fr>::rand(nv, num_multiplicands_range, num_products, &mut rng)?;
+        let product = super::build_layers(&poly)[0][0];
+        let blinding = Arc::new(DenseMultilinearExtension::rand(nv, &mut rng));
+        let mask_degree = num_multiplicands_range.1 + 1;
+        let (mask, mask_sum) = RandomMaskPolynomial::rand(nv + 1, mask_degree, &mut rng);
+        let rho = Fr::rand(&mut rng);
+
+        let mut transcript = <PolyIOP<Fr> as ZkGrandProductCheck<Fr>>::init_transcript();
+        let proof = <PolyIOP<Fr> as ZkGrandProductCheck<Fr>>::prove(
+            &poly,
+            &blinding,
+            &mask,
+            &rho,
+            &mut transcript,
+        )?;
+        assert_eq!(proof.mask_sum, mask_sum);
+        assert_eq!(
+            <PolyIOP<Fr> as ZkGrandProductCheck<Fr>>::extract_product(&proof),
+            product
+        );
+
+        let mut transcript = <PolyIOP<Fr> as ZkGrandProductCheck<Fr>>::init_transcript();
+        let subclaim = <PolyIOP<Fr> as ZkGrandProductCheck<Fr>>::verify(
+            product,
+            &proof,
+            &poly.aux_info,
+            &rho,
+            &mut transcript,
+            nv + 1,
+            mask_degree,
+        )?;
+        let res = poly.evaluate(&subclaim.point)?
+            + subclaim.blinding_coefficient * blinding.evaluate(&subclaim.point).unwrap()
+            + subclaim.mask_coefficient * mask.eval(&subclaim.mask_point)?;
+        assert!(res == subclaim.expected_evaluation, "wrong subclaim");
+        assert_eq!(subclaim.mask_evaluations.len(), nv);
+        for (point, eval) in subclaim.mask_evaluations.iter() {
+            assert_eq!(mask.eval(point)?, *eval, "wrong mask evaluation");
+        }
+
+        // a wrong claimed product is rejected
+        let mut transcript = <PolyIOP<Fr> as ZkGrandProductCheck<Fr>>::init_transcript();
+        assert!(<PolyIOP<Fr> as ZkGrandProductCheck<Fr>>::verify(
+            product + Fr::one(),
+            &proof,
+            &poly.aux_info,
+            &rho,
+            &mut transcript,
+            nv + 1,
+            mask_degree,
+        )
+        .is_err());
+
+        // a wrong mask sum is rejected
+        let mut bad_proof = proof.clone();
+        bad_proof.mask_sum += Fr::one();
+        let mut transcript = <PolyIOP<Fr> as ZkGrandProductCheck<Fr>>::init_transcript();
+        assert!(<PolyIOP<Fr> as ZkGrandProductCheck<Fr>>::verify(
+            product,
+            &bad_proof,
+            &poly.aux_info,
+            &rho,
+            &mut transcript,
+            nv + 1,
+            mask_degree,
+        )
+        .is_err());
+
+        // a wrong layer mask sum is rejected
+        let mut bad_proof = proof.clone();
+        bad_proof.layer_mask_sums[nv - 1] += Fr::one();
+        let mut transcript = <PolyIOP<Fr> as ZkGrandProductCheck<Fr>>::init_transcript();
+        assert!(<PolyIOP<Fr> as ZkGrandProductCheck<Fr>>::verify(
+            product,
+            &bad_proof,
+            &poly.aux_info,
+            &rho,
+            &mut transcript,
+            nv + 1,
+            mask_degree,
+        )
+        .is_err());
+
+        // a wrong layer mask evaluation is rejected, or returned as a wrong
+        // claim on the mask
+        let mut bad_proof = proof.clone();
+        bad_proof.layer_mask_evals[nv - 1] += Fr::one();
+        let mut transcript = <PolyIOP<Fr> as ZkGrandProductCheck<Fr>>::init_transcript();
+        if let Ok(subclaim) = <PolyIOP<Fr> as ZkGrandProductCheck<Fr>>::verify(
+            product,
+            &bad_proof,
+            &poly.aux_info,
+            &rho,
+            &mut transcript,
+            nv + 1,
+            mask_degree,
+        ) {
+            let (point, eval) = &subclaim.mask_evaluations[nv - 1];
+            assert_ne!(mask.eval(point)?, *eval);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_zk_layer_evals_are_blinded() -> Result<(), PolyIOPErrors> {
+        let nv = 4;
+        let mut rng = test_rng();
+        let (poly, _sum) = VirtualPolynomial::<Fr>::rand(nv, (2, 3), 2, &mut rng)?;
+        let layers = super::build_layers(&poly);
+        let blinding = Arc::new(DenseMultilinearExtension::rand(nv, &mut rng));
+        let (mask, _mask_sum) = RandomMaskPolynomial::rand(nv + 1, 4, &mut rng);
+        let rho = Fr::rand(&mut rng);
+
+        let mut transcript = <PolyIOP<Fr> as GrandProductCheck<Fr>>::init_transcript();
+        let proof = <PolyIOP<Fr> as GrandProductCheck<Fr>>::prove(&poly, &mut transcript)?;
+        let mut transcript = <PolyIOP<Fr> as ZkGrandProductCheck<Fr>>::init_transcript();
+        let zk_proof = <PolyIOP<Fr> as ZkGrandProductCheck<Fr>>::prove(
+            &poly,
+            &blinding,
+            &mask,
+            &rho,
+            &mut transcript,
+        )?;
+
+        // layer k + 1 of the blinded tree stacks layer k of the tree of f(x)
+        // over the one of R(x), so its point is the point of layer k and t
+        for k in 1..nv {
+            let (even, odd) = super::split_layer(&layers[k + 1], k);
+            let point = &proof.layer_proofs[k - 1].point;
+            assert_eq!(
+                proof.layer_evals[k],
+                (evaluate_opt(&even, point), evaluate_opt(&odd, point))
+            );
+
+            let zk_point = &zk_proof.layer_proofs[k].point;
+            let (zk_even, zk_odd) = zk_proof.layer_evals[k + 1];
+            let real_point = &zk_point[..k];
+            assert_ne!(zk_even, evaluate_opt(&even, real_point));
+            assert_ne!(zk_odd, evaluate_opt(&odd, real_point));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_trivial_polynomial() -> Result<(), PolyIOPErrors> {
+        test_grand_product(1, (2, 3), 1)
+    }
+
+    #[test]
+    fn test_normal_polynomial() -> Result<(), PolyIOPErrors> {
+        test_grand_product(5, (2, 4), 3)
+    }
+
+    #[test]
+    fn test_zk_trivial_polynomial() -> Result<(), PolyIOPErrors> {
+        test_zk_productcheck(1, (2, 3), 1)
+    }
+
+    #[test]
+    fn test_zk_normal_polynomial() -> Result<(), PolyIOPErrors> {
+        test_zk_productcheck(5, (2, 4), 3)
+    }
+
+    #[test]
+    fn zero_polynomial_should_error() {
+        assert!(test_grand_product(0, (2, 3), 1).is_err());
+    }
+}
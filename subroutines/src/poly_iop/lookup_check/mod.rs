@@ -0,0 +1,415 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! Main module for the Lookup Check protocol
+
+use crate::{
+    pcs::PolynomialCommitmentScheme,
+    poly_iop::{
+        errors::PolyIOPErrors,
+        lookup_check::util::{
+            build_lookup_check_poly, compute_lookup_frac_polys, compute_multiplicity_poly,
+        },
+        structs::IOPProof,
+        sum_check::{SumCheck, SumCheckSubClaim},
+        PolyIOP,
+    },
+};
+use arithmetic::VPAuxInfo;
+use ark_ec::pairing::Pairing;
+use ark_ff::PrimeField;
+use ark_poly::DenseMultilinearExtension;
+use ark_std::{end_timer, start_timer, Zero};
+use std::sync::Arc;
+use transcript::{IOPTranscript, LookupChallenge};
+
+pub mod util;
+
+/// A lookup check proves that for two lists of n-variate multilinear
+/// polynomials `(f1, ..., fk)` and `(t1, ..., tk)`, every evaluation of `fl`
+/// on the boolean hypercube is an evaluation of the table `tl` on the boolean
+/// hypercube.
+///
+/// A LookupCheck is derived from SumCheck, with the logarithmic derivative
+/// argument of [Hab22](https://eprint.iacr.org/2022/1530.pdf):
+/// `fl` is in `tl` iff there is a multiplicity polynomial `ml` with
+/// \sum_x 1 / (gamma + fl(x)) = \sum_x ml(x) / (gamma + tl(x))
+/// for a random `gamma`.
+///
+/// Prover steps:
+/// 1. build the multiplicity polynomials `ml(x)`, push their commitments to
+///    the transcript, and generate `gamma`
+/// 2. build `al(x) = 1 / (gamma + fl(x))` and `bl(x) = ml(x) / (gamma +
+///    tl(x))`, push their commitments to the transcript, and generate `alpha`
+///    and `r`
+/// 3. generate the sumcheck proof that the virtual polynomial
+///
+///    ```text
+///    Q(x) = \sum_l al(x) - bl(x)
+///         + eq(x, r) * (alpha^{2l} * (al(x) * (gamma + fl(x)) - 1)
+///                     + alpha^{2l+1} * (bl(x) * (gamma + tl(x)) - ml(x)))
+///    ```
+///
+///    sums to zero
+///
+/// Verifier steps:
+/// 1. Extract commitments of `ml(x)`, `al(x)` and `bl(x)` from the proof,
+///    push them to the transcript, and generate the same challenges
+/// 2. `verify` to verify the sumcheck proof and generate the subclaim for
+///    polynomial evaluations
+pub trait LookupCheck<E, PCS>: SumCheck<E::ScalarField>
+where
+    E: Pairing,
+    PCS: PolynomialCommitmentScheme<E>,
+{
+    type LookupCheckSubClaim;
+    type LookupCheckProof;
+
+    /// Initialize the system with a transcript
+    ///
+    /// This function is optional -- in the case where a LookupCheck is
+    /// an building block for a more complex protocol, the transcript
+    /// may be initialized by this complex protocol, and passed to the
+    /// LookupCheck prover/verifier.
+    fn init_transcript() -> Self::Transcript;
+
+    /// Proves that every evaluation of `fl` on the boolean hypercube is an
+    /// evaluation of `tl` on the boolean hypercube, for each `l`.
+    ///
+    /// Inputs:
+    /// - pcs_param: PCS committing key
+    /// - fxs: the list of looked up multilinear polynomials
+    /// - txs: the list of table multilinear polynomials
+    /// - transcript: the IOP transcript
+    ///
+    /// Outputs
+    /// - the lookup check proof
+    /// - the multiplicity polynomials `ml(x)`
+    /// - the fractional polynomials `al(x)`
+    /// - the fractional polynomials `bl(x)`
+    ///
+    /// Cost: O(N)
+    #[allow(clippy::type_complexity)]
+    fn prove(
+        pcs_param: &PCS::ProverParam,
+        fxs: &[Self::MultilinearExtension],
+        txs: &[Self::MultilinearExtension],
+        transcript: &mut IOPTranscript<E::ScalarField>,
+    ) -> Result<
+        (
+            Self::LookupCheckProof,
+            Vec<Self::MultilinearExtension>,
+            Vec<Self::MultilinearExtension>,
+            Vec<Self::MultilinearExtension>,
+        ),
+        PolyIOPErrors,
+    >;
+
+    /// Verify that every evaluation of `fl` on the boolean hypercube is an
+    /// evaluation of `tl`, up to the evaluations of `fl`, `tl`, `ml`, `al`
+    /// and `bl` at the subclaim point, see
+    /// [`util::eval_lookup_check_poly`].
+    fn verify(
+        proof: &Self::LookupCheckProof,
+        aux_info: &VPAuxInfo<E::ScalarField>,
+        transcript: &mut Self::Transcript,
+    ) -> Result<Self::LookupCheckSubClaim, PolyIOPErrors>;
+}
+
+/// A lookup check subclaim consists of
+/// - A sum check IOP subclaim for the virtual polynomial
+/// - The random challenges `gamma`, `alpha` and `r`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LookupCheckSubClaim<F: PrimeField> {
+    // the SubClaim from the SumCheck
+    pub sum_check_sub_claim: SumCheckSubClaim<F>,
+    pub gamma: LookupChallenge<F>,
+    pub alpha: LookupChallenge<F>,
+    pub r: Vec<F>,
+}
+
+/// A lookup check proof consists of
+/// - a sumcheck proof
+/// - the commitments of the multiplicity polynomials
+/// - the commitments of the fractional polynomials `al(x)` and `bl(x)`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LookupCheckProof<E: Pairing, PCS: PolynomialCommitmentScheme<E>> {
+    pub sum_check_proof: IOPProof<E::ScalarField>,
+    pub multiplicity_comms: Vec<PCS::Commitment>,
+    pub input_frac_comms: Vec<PCS::Commitment>,
+    pub table_frac_comms: Vec<PCS::Commitment>,
+}
+
+impl<E, PCS> LookupCheck<E, PCS> for PolyIOP<E::ScalarField>
+where
+    E: Pairing,
+    PCS: PolynomialCommitmentScheme<E, Polynomial = Arc<DenseMultilinearExtension<E::ScalarField>>>,
+{
+    type LookupCheckSubClaim = LookupCheckSubClaim<E::ScalarField>;
+    type LookupCheckProof = LookupCheckProof<E, PCS>;
+
+    fn init_transcript() -> Self::Transcript {
+        IOPTranscript::<E::ScalarField>::new(b"Initializing LookupCheck transcript")
+    }
+
+    fn prove(
+        pcs_param: &PCS::ProverParam,
+        fxs: &[Self::MultilinearExtension],
+        txs: &[Self::MultilinearExtension],
+        transcript: &mut IOPTranscript<E::ScalarField>,
+    ) -> Result<
+        (
+            Self::LookupCheckProof,
+            Vec<Self::MultilinearExtension>,
+            Vec<Self::MultilinearExtension>,
+            Vec<Self::MultilinearExtension>,
+        ),
+        PolyIOPErrors,
+    > {
+        let start = start_timer!(|| "lookup_check prove");
+
+        if fxs.is_empty() {
+            return Err(PolyIOPErrors::InvalidParameters("fxs is empty".to_string()));
+        }
+        if fxs.len() != txs.len() {
+            return Err(PolyIOPErrors::InvalidParameters(
+                "fxs and txs have different number of polynomials".to_string(),
+            ));
+        }
+        let num_vars = fxs[0].num_vars;
+        for poly in fxs.iter().chain(txs.iter()) {
+            if poly.num_vars != num_vars {
+                return Err(PolyIOPErrors::InvalidParameters(
+                    "fx and tx have different number of variables".to_string(),
+                ));
+            }
+        }
+
+        // commit the multiplicities and generate gamma
+        let mxs = fxs
+            .iter()
+            .zip(txs.iter())
+            .map(|(fx, tx)| compute_multiplicity_poly(fx, tx))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut multiplicity_comms = Vec::with_capacity(mxs.len());
+        for mx in mxs.iter() {
+            let comm =
+                PCS::commit(pcs_param, mx).map_err(|e| PolyIOPErrors::commit_failed("m(x)", e))?;
+            transcript.append_serializable_element(b"m(x)", &comm)?;
+            multiplicity_comms.push(comm);
+        }
+        let gamma = transcript.get_lookup_challenges(b"gamma", 1)?[0];
+
+        // commit the fractions and generate alpha and r
+        let mut axs = Vec::with_capacity(fxs.len());
+        let mut bxs = Vec::with_capacity(fxs.len());
+        let mut input_frac_comms = Vec::with_capacity(fxs.len());
+        let mut table_frac_comms = Vec::with_capacity(fxs.len());
+        for ((fx, tx), mx) in fxs.iter().zip(txs.iter()).zip(mxs.iter()) {
+            let (ax, bx) = compute_lookup_frac_polys(&gamma, fx, tx, mx)?;
+            let a_comm =
+                PCS::commit(pcs_param, &ax).map_err(|e| PolyIOPErrors::commit_failed("a(x)", e))?;
+            let b_comm =
+                PCS::commit(pcs_param, &bx).map_err(|e| PolyIOPErrors::commit_failed("b(x)", e))?;
+            transcript.append_serializable_element(b"a(x)", &a_comm)?;
+            transcript.append_serializable_element(b"b(x)", &b_comm)?;
+            input_frac_comms.push(a_comm);
+            table_frac_comms.push(b_comm);
+            axs.push(ax);
+            bxs.push(bx);
+        }
+        let alpha = transcript.get_lookup_challenges(b"alpha", 1)?[0];
+        let r: Vec<_> = transcript
+            .get_lookup_challenges(b"r", num_vars)?
+            .into_iter()
+            .map(|c| c.0)
+            .collect();
+
+        // build the sum check proof
+        let q_x = build_lookup_check_poly(fxs, txs, &mxs, &axs, &bxs, &gamma, &alpha, &r)?;
+        let sum_check_proof = <Self as SumCheck<E::ScalarField>>::prove(&q_x, transcript)?;
+
+        end_timer!(start);
+
+        Ok((
+            LookupCheckProof {
+                sum_check_proof,
+                multiplicity_comms,
+                input_frac_comms,
+                table_frac_comms,
+            },
+            mxs,
+            axs,
+            bxs,
+        ))
+    }
+
+    fn verify(
+        proof: &Self::LookupCheckProof,
+        aux_info: &VPAuxInfo<E::ScalarField>,
+        transcript: &mut Self::Transcript,
+    ) -> Result<Self::LookupCheckSubClaim, PolyIOPErrors> {
+        let start = start_timer!(|| "lookup_check verify");
+
+        let num_lookups = proof.multiplicity_comms.len();
+        if num_lookups == 0
+            || proof.input_frac_comms.len() != num_lookups
+            || proof.table_frac_comms.len() != num_lookups
+        {
+            return Err(PolyIOPErrors::InvalidProof(format!(
+                "lookup check proof has {}, {} and {} commitments",
+                num_lookups,
+                proof.input_frac_comms.len(),
+                proof.table_frac_comms.len()
+            )));
+        }
+
+        // update transcript and generate challenges
+        for comm in proof.multiplicity_comms.iter() {
+            transcript.append_serializable_element(b"m(x)", comm)?;
+        }
+        let gamma = transcript.get_lookup_challenges(b"gamma", 1)?[0];
+        for (a_comm, b_comm) in proof
+            .input_frac_comms
+            .iter()
+            .zip(proof.table_frac_comms.iter())
+        {
+            transcript.append_serializable_element(b"a(x)", a_comm)?;
+            transcript.append_serializable_element(b"b(x)", b_comm)?;
+        }
+        let alpha = transcript.get_lookup_challenges(b"alpha", 1)?[0];
+        let r: Vec<_> = transcript
+            .get_lookup_challenges(b"r", aux_info.num_variables)?
+            .into_iter()
+            .map(|c| c.0)
+            .collect();
+
+        // the fractions sum to zero
+        let sum_check_sub_claim = <Self as SumCheck<E::ScalarField>>::verify(
+            E::ScalarField::zero(),
+            &proof.sum_check_proof,
+            aux_info,
+            transcript,
+        )?;
+
+        end_timer!(start);
+
+        Ok(LookupCheckSubClaim {
+            sum_check_sub_claim,
+            gamma,
+            alpha,
+            r,
+        })
+    }
+}
+
+/// The aux info of the virtual polynomial of a lookup check over `num_vars`
+/// variables.
+pub fn lookup_check_aux_info<F: PrimeField>(num_vars: usize) -> VPAuxInfo<F> {
+    VPAuxInfo {
+        max_degree: 3,
+        num_variables: num_vars,
+        per_variable_degree: vec![],
+        is_sparse: false,
+        phantom: Default::default(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{lookup_check_aux_info, util::eval_lookup_check_poly, LookupCheck};
+    use crate::{
+        pcs::{prelude::MultilinearKzgPCS, PolynomialCommitmentScheme},
+        poly_iop::{errors::PolyIOPErrors, PolyIOP},
+    };
+    use arithmetic::eq_eval;
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_poly::{DenseMultilinearExtension, MultilinearExtension};
+    use ark_std::{rand::RngCore, test_rng};
+    use std::sync::Arc;
+
+    type Kzg = MultilinearKzgPCS<Bls12_381>;
+
+    fn test_lookup_check(nv: usize) -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        let srs = Kzg::gen_srs_for_testing(&mut rng, nv)?;
+        let (pcs_param, _) = Kzg::trim(&srs, None, Some(nv))?;
+
+        // a table of 4 entries, repeated to fill the hypercube
+        let table = [
+            Fr::from(3u64),
+            Fr::from(5u64),
+            Fr::from(7u64),
+            Fr::from(11u64),
+        ];
+        let tx = Arc::new(DenseMultilinearExtension::from_evaluations_vec(
+            nv,
+            (0..1 << nv).map(|i| table[i % table.len()]).collect(),
+        ));
+        let fx = Arc::new(DenseMultilinearExtension::from_evaluations_vec(
+            nv,
+            (0..1 << nv)
+                .map(|_| table[rng.next_u32() as usize % table.len()])
+                .collect(),
+        ));
+        let gx = Arc::new(DenseMultilinearExtension::rand(nv, &mut rng));
+        let fxs = vec![fx, tx.clone()];
+        let txs = vec![tx.clone(), tx];
+
+        let mut transcript = <PolyIOP<Fr> as LookupCheck<Bls12_381, Kzg>>::init_transcript();
+        transcript.append_message(b"testing", b"initializing transcript for testing")?;
+        let (proof, mxs, axs, bxs) = <PolyIOP<Fr> as LookupCheck<Bls12_381, Kzg>>::prove(
+            &pcs_param,
+            &fxs,
+            &txs,
+            &mut transcript,
+        )?;
+
+        let mut transcript = <PolyIOP<Fr> as LookupCheck<Bls12_381, Kzg>>::init_transcript();
+        transcript.append_message(b"testing", b"initializing transcript for testing")?;
+        let subclaim = <PolyIOP<Fr> as LookupCheck<Bls12_381, Kzg>>::verify(
+            &proof,
+            &lookup_check_aux_info(nv),
+            &mut transcript,
+        )?;
+        let point = &subclaim.sum_check_sub_claim.point;
+        let evals: Vec<_> = (0..fxs.len())
+            .map(|l| {
+                [&fxs[l], &txs[l], &mxs[l], &axs[l], &bxs[l]].map(|p| p.evaluate(point).unwrap())
+            })
+            .collect();
+        assert_eq!(
+            eval_lookup_check_poly(
+                &evals,
+                &subclaim.gamma,
+                &subclaim.alpha,
+                eq_eval(point, &subclaim.r)?
+            ),
+            subclaim.sum_check_sub_claim.expected_evaluation
+        );
+
+        // a value that is not in the table has no multiplicity
+        let mut transcript = <PolyIOP<Fr> as LookupCheck<Bls12_381, Kzg>>::init_transcript();
+        assert!(<PolyIOP<Fr> as LookupCheck<Bls12_381, Kzg>>::prove(
+            &pcs_param,
+            &[gx],
+            &txs[..1],
+            &mut transcript,
+        )
+        .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_trivial_polynomial() -> Result<(), PolyIOPErrors> {
+        test_lookup_check(2)
+    }
+    #[test]
+    fn test_normal_polynomial() -> Result<(), PolyIOPErrors> {
+        test_lookup_check(10)
+    }
+}
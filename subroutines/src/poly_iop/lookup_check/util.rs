@@ -0,0 +1,167 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! This module implements useful functions for the lookup check protocol.
+
+use crate::poly_iop::errors::PolyIOPErrors;
+use arithmetic::{build_eq_x_r, VirtualPolynomial};
+use ark_ff::{batch_inversion, PrimeField};
+use ark_poly::DenseMultilinearExtension;
+use ark_std::{end_timer, start_timer};
+use std::{collections::HashMap, sync::Arc};
+use transcript::LookupChallenge;
+
+/// Compute the multiplicity polynomial `m(x)` of the input `fx` in the table
+/// `tx`: `m(x)` is the number of `y` with `fx(y) = tx(x)` if `x` is the first
+/// row of the table with this value, and 0 otherwise.
+///
+/// Returns an error if an evaluation of `fx` is not in the table.
+pub fn compute_multiplicity_poly<F: PrimeField>(
+    fx: &Arc<DenseMultilinearExtension<F>>,
+    tx: &Arc<DenseMultilinearExtension<F>>,
+) -> Result<Arc<DenseMultilinearExtension<F>>, PolyIOPErrors> {
+    let start = start_timer!(|| "compute m(x)");
+
+    let mut first_rows = HashMap::new();
+    for (row, value) in tx.evaluations.iter().enumerate() {
+        first_rows.entry(*value).or_insert(row);
+    }
+    let mut counts = vec![0u64; tx.evaluations.len()];
+    for (row, value) in fx.evaluations.iter().enumerate() {
+        match first_rows.get(value) {
+            Some(&table_row) => counts[table_row] += 1,
+            None => {
+                return Err(PolyIOPErrors::InvalidProver(format!(
+                    "the input at row {} is not in the table",
+                    row
+                )))
+            },
+        }
+    }
+
+    end_timer!(start);
+    Ok(Arc::new(DenseMultilinearExtension::from_evaluations_vec(
+        tx.num_vars,
+        counts.into_iter().map(F::from).collect(),
+    )))
+}
+
+/// Compute the fractional polynomials `a(x) = 1 / (gamma + fx(x))` and
+/// `b(x) = mx(x) / (gamma + tx(x))` on the boolean hypercube.
+///
+/// Returns an error if `gamma + fx(x)` or `gamma + tx(x)` is zero on the
+/// boolean hypercube, which only happens with negligible probability.
+#[allow(clippy::type_complexity)]
+pub fn compute_lookup_frac_polys<F: PrimeField>(
+    gamma: &LookupChallenge<F>,
+    fx: &Arc<DenseMultilinearExtension<F>>,
+    tx: &Arc<DenseMultilinearExtension<F>>,
+    mx: &Arc<DenseMultilinearExtension<F>>,
+) -> Result<
+    (
+        Arc<DenseMultilinearExtension<F>>,
+        Arc<DenseMultilinearExtension<F>>,
+    ),
+    PolyIOPErrors,
+> {
+    let start = start_timer!(|| "compute a(x) and b(x)");
+
+    let mut a_evals: Vec<F> = fx.evaluations.iter().map(|f| gamma.0 + f).collect();
+    let mut b_evals: Vec<F> = tx.evaluations.iter().map(|t| gamma.0 + t).collect();
+    if a_evals.iter().chain(b_evals.iter()).any(|x| x.is_zero()) {
+        return Err(PolyIOPErrors::InvalidChallenge {
+            round: 0,
+            value: format!(
+                "gamma {} is the opposite of an input or table value",
+                gamma.0
+            ),
+        });
+    }
+    batch_inversion(&mut a_evals);
+    batch_inversion(&mut b_evals);
+    for (b_eval, m) in b_evals.iter_mut().zip(mx.evaluations.iter()) {
+        *b_eval *= m;
+    }
+
+    end_timer!(start);
+    Ok((
+        Arc::new(DenseMultilinearExtension::from_evaluations_vec(
+            fx.num_vars,
+            a_evals,
+        )),
+        Arc::new(DenseMultilinearExtension::from_evaluations_vec(
+            tx.num_vars,
+            b_evals,
+        )),
+    ))
+}
+
+/// Build the virtual polynomial
+///    Q(x) = \sum_l a_l(x) - b_l(x)
+///         + eq(x, r) * (alpha^{2l} * (a_l(x) * (gamma + f_l(x)) - 1)
+///                     + alpha^{2l+1} * (b_l(x) * (gamma + t_l(x)) - m_l(x)))
+/// whose sum check is the core of the lookup check.
+///
+/// Cost: O(N)
+#[allow(clippy::too_many_arguments)]
+pub fn build_lookup_check_poly<F: PrimeField>(
+    fxs: &[Arc<DenseMultilinearExtension<F>>],
+    txs: &[Arc<DenseMultilinearExtension<F>>],
+    mxs: &[Arc<DenseMultilinearExtension<F>>],
+    axs: &[Arc<DenseMultilinearExtension<F>>],
+    bxs: &[Arc<DenseMultilinearExtension<F>>],
+    gamma: &LookupChallenge<F>,
+    alpha: &LookupChallenge<F>,
+    r: &[F],
+) -> Result<VirtualPolynomial<F>, PolyIOPErrors> {
+    let eq_x_r = build_eq_x_r(r)?;
+    let mut q_x = VirtualPolynomial::new(r.len());
+    let mut coeff = F::one();
+    for ((((fx, tx), mx), ax), bx) in fxs
+        .iter()
+        .zip(txs.iter())
+        .zip(mxs.iter())
+        .zip(axs.iter())
+        .zip(bxs.iter())
+    {
+        q_x.add_mle_list([ax.clone()], F::one())?;
+        q_x.add_mle_list([bx.clone()], -F::one())?;
+
+        // alpha^{2l} * eq(x, r) * (a(x) * (gamma + f(x)) - 1)
+        q_x.add_mle_list([ax.clone(), eq_x_r.clone()], coeff * gamma.0)?;
+        q_x.add_mle_list([ax.clone(), fx.clone(), eq_x_r.clone()], coeff)?;
+        q_x.add_mle_list([eq_x_r.clone()], -coeff)?;
+        coeff *= alpha.0;
+
+        // alpha^{2l+1} * eq(x, r) * (b(x) * (gamma + t(x)) - m(x))
+        q_x.add_mle_list([bx.clone(), eq_x_r.clone()], coeff * gamma.0)?;
+        q_x.add_mle_list([bx.clone(), tx.clone(), eq_x_r.clone()], coeff)?;
+        q_x.add_mle_list([mx.clone(), eq_x_r.clone()], -coeff)?;
+        coeff *= alpha.0;
+    }
+    Ok(q_x)
+}
+
+/// Evaluate `Q(x)` of [`build_lookup_check_poly`] at a point from the
+/// evaluations of `(f_l, t_l, m_l, a_l, b_l)` and of `eq(x, r)` at this
+/// point.
+pub fn eval_lookup_check_poly<F: PrimeField>(
+    evals: &[[F; 5]],
+    gamma: &LookupChallenge<F>,
+    alpha: &LookupChallenge<F>,
+    eq_x_r_eval: F,
+) -> F {
+    let mut res = F::zero();
+    let mut coeff = F::one();
+    for [f, t, m, a, b] in evals.iter() {
+        res += *a - b;
+        res += eq_x_r_eval * coeff * (*a * (gamma.0 + f) - F::one());
+        coeff *= alpha.0;
+        res += eq_x_r_eval * coeff * (*b * (gamma.0 + t) - m);
+        coeff *= alpha.0;
+    }
+    res
+}
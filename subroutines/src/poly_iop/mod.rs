@@ -7,10 +7,23 @@
 use ark_ff::PrimeField;
 use std::marker::PhantomData;
 
+#[cfg(any(test, feature = "arbitrary"))]
+mod arbitrary;
+mod compact;
+mod composer;
 mod errors;
+mod grand_product;
+mod lookup_check;
 mod perm_check;
 pub mod prelude;
 mod prod_check;
+mod proof_format;
+mod protocols;
+mod security;
+#[cfg(any(test, feature = "serde"))]
+mod serde_hex;
+#[cfg(test)]
+mod strategies;
 mod structs;
 mod sum_check;
 mod utils;
@@ -25,11 +38,17 @@ mod zk_sum_check;
 /// An PolyIOP may be instantiated with one of the following:
 /// - SumCheck protocol.
 /// - ZeroCheck protocol.
+/// - GrandProductCheck protocol.
 /// - PermutationCheck protocol.
+/// - LookupCheck protocol.
 ///
 /// Those individual protocol may have similar or identical APIs.
 /// The systematic way to invoke specific protocol is, for example
 ///     `<PolyIOP<F> as SumCheck<F>>::prove()`
+/// or, equivalently, through the inherent wrappers
+///     `SumCheckProtocol::<F>::prove()`
+/// and likewise `ZkSumCheckProtocol`, `ZeroCheckProtocol`,
+/// `PermCheckProtocol` and `LookupCheckProtocol`.
 pub struct PolyIOP<F: PrimeField> {
     /// Associated field
     #[doc(hidden)]
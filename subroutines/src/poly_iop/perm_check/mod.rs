@@ -15,7 +15,7 @@ use ark_ec::pairing::Pairing;
 use ark_poly::DenseMultilinearExtension;
 use ark_std::{end_timer, start_timer};
 use std::sync::Arc;
-use transcript::IOPTranscript;
+use transcript::{IOPTranscript, PermCheckChallenge};
 
 /// A permutation subclaim consists of
 /// - the SubClaim from the ProductCheck
@@ -30,7 +30,10 @@ where
     /// the SubClaim from the ProductCheck
     pub product_check_sub_claim: PC::ProductCheckSubClaim,
     /// Challenges beta and gamma
-    pub challenges: (E::ScalarField, E::ScalarField),
+    pub challenges: (
+        PermCheckChallenge<E::ScalarField>,
+        PermCheckChallenge<E::ScalarField>,
+    ),
 }
 
 pub mod util;
@@ -149,8 +152,8 @@ where
         }
 
         // generate challenge `beta` and `gamma` from current transcript
-        let beta = transcript.get_and_append_challenge(b"beta")?;
-        let gamma = transcript.get_and_append_challenge(b"gamma")?;
+        let beta = transcript.get_perm_check_challenge(b"beta")?;
+        let gamma = transcript.get_perm_check_challenge(b"gamma")?;
         let (numerators, denominators) = computer_nums_and_denoms(&beta, &gamma, fxs, gxs, perms)?;
 
         // invoke product check on numerator and denominator
@@ -172,8 +175,8 @@ where
     ) -> Result<Self::PermutationCheckSubClaim, PolyIOPErrors> {
         let start = start_timer!(|| "Permutation check verify");
 
-        let beta = transcript.get_and_append_challenge(b"beta")?;
-        let gamma = transcript.get_and_append_challenge(b"gamma")?;
+        let beta = transcript.get_perm_check_challenge(b"beta")?;
+        let gamma = transcript.get_perm_check_challenge(b"gamma")?;
 
         // invoke the zero check on the iop_proof
         let product_check_sub_claim =
@@ -221,6 +224,8 @@ mod test {
         let poly_info = VPAuxInfo {
             max_degree: fxs.len() + 1,
             num_variables: nv,
+            per_variable_degree: vec![],
+            is_sparse: false,
             phantom: PhantomData,
         };
 
@@ -248,13 +253,15 @@ mod test {
         )?;
 
         // check product subclaim
-        if evaluate_opt(
-            &prod_x,
-            &perm_check_sub_claim.product_check_sub_claim.final_query.0,
-        ) != perm_check_sub_claim.product_check_sub_claim.final_query.1
+        for (_, point, eval) in perm_check_sub_claim
+            .product_check_sub_claim
+            .required_openings
+            .iter()
         {
-            return Err(PolyIOPErrors::InvalidVerifier("wrong subclaim".to_string()));
-        };
+            if evaluate_opt(&prod_x, point) != *eval {
+                return Err(PolyIOPErrors::InvalidVerifier("wrong subclaim".to_string()));
+            }
+        }
 
         Ok(())
     }
@@ -12,6 +12,7 @@ use ark_ff::PrimeField;
 use ark_poly::DenseMultilinearExtension;
 use ark_std::{end_timer, start_timer};
 use std::sync::Arc;
+use transcript::PermCheckChallenge;
 
 /// Returns the evaluations of two list of MLEs:
 /// - numerators = (a1, ..., ak)
@@ -27,9 +28,9 @@ use std::sync::Arc;
 ///
 /// The caller is responsible for sanity-check
 #[allow(clippy::type_complexity)]
-pub(super) fn computer_nums_and_denoms<F: PrimeField>(
-    beta: &F,
-    gamma: &F,
+pub fn computer_nums_and_denoms<F: PrimeField>(
+    beta: &PermCheckChallenge<F>,
+    gamma: &PermCheckChallenge<F>,
     fxs: &[Arc<DenseMultilinearExtension<F>>],
     gxs: &[Arc<DenseMultilinearExtension<F>>],
     perms: &[Arc<DenseMultilinearExtension<F>>],
@@ -54,8 +55,8 @@ pub(super) fn computer_nums_and_denoms<F: PrimeField>(
             .iter()
             .zip(gxs[l].iter().zip(s_ids[l].iter().zip(perms[l].iter())))
         {
-            let numerator = f_ev + *beta * s_id_ev + gamma;
-            let denominator = g_ev + *beta * perm_ev + gamma;
+            let numerator = f_ev + beta.0 * s_id_ev + gamma.0;
+            let denominator = g_ev + beta.0 * perm_ev + gamma.0;
 
             numerator_evals.push(numerator);
             denominator_evals.push(denominator);
@@ -6,8 +6,51 @@
 
 #![allow(unused_imports)]
 
+#[cfg(feature = "mask-generator")]
+pub use crate::poly_iop::zk_sum_check::mask::MaskGenerator;
 pub use crate::poly_iop::{
-    errors::PolyIOPErrors, perm_check::PermutationCheck, prod_check::ProductCheck,
-    structs::IOPProof, sum_check::SumCheck, utils::*, zero_check::ZeroCheck, PolyIOP,
-    zk_sum_check::ZkSumCheck, structs::RandomMaskPolynomial
+    compact::CompactProof,
+    composer::IOPComposer,
+    errors::{CommitmentErrorKind, PolyIOPErrors, TranscriptErrorKind},
+    grand_product::{
+        GrandProductCheck, GrandProductProof, ProductSubClaim, ZkGrandProductCheck,
+        ZkGrandProductProof, ZkProductSubClaim,
+    },
+    lookup_check::{
+        lookup_check_aux_info,
+        util::{
+            build_lookup_check_poly, compute_lookup_frac_polys, compute_multiplicity_poly,
+            eval_lookup_check_poly,
+        },
+        LookupCheck, LookupCheckProof, LookupCheckSubClaim,
+    },
+    perm_check::{util::computer_nums_and_denoms, PermutationCheck},
+    prod_check::{
+        prod_boundary_opening,
+        util::{build_prod_check_poly, compute_frac_poly, compute_product_poly},
+        OpeningRole, ProductCheck, ProductCheckProof,
+    },
+    proof_format::{ProofSerializer, PROOF_FORMAT_VERSION, SUPPORTED_PROOF_FORMATS},
+    protocols::{
+        LookupCheckProtocol, PermCheckProtocol, SumCheckProtocol, ZeroCheckProtocol,
+        ZkSumCheckProtocol,
+    },
+    security::{min_field_bits_for_security, soundness_error},
+    structs::{
+        IOPProof, IOPProverMessage, IOPVerifierState, RandomMaskPolynomial, RoundTrace,
+        VerifierTrace,
+    },
+    sum_check::{
+        check_aux_info, check_num_variables, check_proof_shape, SumCheck, SumCheckRounds,
+        SumCheckVerifierRounds, MAX_DEGREE, MAX_NUM_VARIABLES,
+    },
+    utils::*,
+    zero_check::{zero_check_rounds, ZeroCheck, ZeroCheckVerifierRounds},
+    zk_sum_check::{
+        accumulator::AccumulatedChecks,
+        batch::{BatchSumCheck, BatchSumCheckProof, BatchSumCheckSubClaim},
+        mask::{prove_zk, verify_zk, MaskHandle, MaskTag},
+        ZkConfig, ZkSumCheck, ZkSumCheckVerifier,
+    },
+    PolyIOP,
 };
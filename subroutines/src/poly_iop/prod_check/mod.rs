@@ -17,13 +17,13 @@ use crate::{
 };
 use arithmetic::VPAuxInfo;
 use ark_ec::pairing::Pairing;
-use ark_ff::{One, PrimeField, Zero};
+use ark_ff::PrimeField;
 use ark_poly::DenseMultilinearExtension;
 use ark_std::{end_timer, start_timer};
 use std::sync::Arc;
-use transcript::IOPTranscript;
+use transcript::{IOPTranscript, PermCheckChallenge};
 
-mod util;
+pub mod util;
 
 /// A product-check proves that two lists of n-variate multilinear polynomials
 /// `(f1, f2, ..., fk)` and `(g1, ..., gk)` satisfy:
@@ -111,23 +111,51 @@ where
     ) -> Result<Self::ProductCheckSubClaim, PolyIOPErrors>;
 }
 
+/// The role of an opening, i.e., the polynomial it opens: the opening that
+/// a product check subclaim requires, or the opening of a selector or a
+/// witness that a gate identity is evaluated on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OpeningRole {
+    /// The opening of `prod(x)` at `(1, ..., 1, 0)`, which carries the claim
+    /// that the grand product is 1.
+    ProdBoundary,
+    /// The opening of the selector of the given index.
+    Selector(usize),
+    /// The opening of the witness column of the given index.
+    Witness(usize),
+}
+
+/// The opening of `prod(x)` at `(1, ..., 1, 0)` with evaluation 1, for
+/// `num_vars` variables.
+///
+/// The point is reversed because Arkworks' MLE uses big-endian format for
+/// points.
+pub fn prod_boundary_opening<F: PrimeField>(num_vars: usize) -> (OpeningRole, Vec<F>, F) {
+    let mut point = vec![F::one(); num_vars];
+    point[0] = F::zero();
+    (OpeningRole::ProdBoundary, point, F::one())
+}
+
 /// A product check subclaim consists of
 /// - A zero check IOP subclaim for the virtual polynomial
 /// - The random challenge `alpha`
-/// - A final query for `prod(1, ..., 1, 0) = 1`.
-// Note that this final query is in fact a constant that
-// is independent from the proof. So we should avoid
-// (de)serialize it.
+/// - The openings the verifier must find among the opening claims, starting
+///   with `prod(1, ..., 1, 0) = 1`.
+// Note that these openings are in fact constants that
+// are independent from the proof. So we should avoid
+// (de)serialize them.
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct ProductCheckSubClaim<F: PrimeField, ZC: ZeroCheck<F>> {
     // the SubClaim from the ZeroCheck
     pub zero_check_sub_claim: ZC::ZeroCheckSubClaim,
-    // final query which consists of
-    // - the vector `(1, ..., 1, 0)` (needs to be reversed because Arkwork's MLE uses big-endian
-    //   format for points)
-    // The expected final query evaluation is 1
-    pub final_query: (Vec<F>, F),
-    pub alpha: F,
+    // the required openings, each of which consists of
+    // - its role, which tells the polynomial to open
+    // - the point
+    // - the expected evaluation
+    // A caller that does not open every one of them, with the expected
+    // evaluation, has not checked the product.
+    pub required_openings: Vec<(OpeningRole, Vec<F>, F)>,
+    pub alpha: PermCheckChallenge<F>,
 }
 
 /// A product check proof consists of
@@ -195,11 +223,13 @@ where
         let prod_x = compute_product_poly(&frac_poly)?;
 
         // generate challenge
-        let frac_comm = PCS::commit(pcs_param, &frac_poly)?;
-        let prod_x_comm = PCS::commit(pcs_param, &prod_x)?;
+        let frac_comm = PCS::commit(pcs_param, &frac_poly)
+            .map_err(|e| PolyIOPErrors::commit_failed("frac(x)", e))?;
+        let prod_x_comm = PCS::commit(pcs_param, &prod_x)
+            .map_err(|e| PolyIOPErrors::commit_failed("prod(x)", e))?;
         transcript.append_serializable_element(b"frac(x)", &frac_comm)?;
         transcript.append_serializable_element(b"prod(x)", &prod_x_comm)?;
-        let alpha = transcript.get_and_append_challenge(b"alpha")?;
+        let alpha = transcript.get_perm_check_challenge(b"alpha")?;
 
         // build the zero-check proof
         let (zero_check_proof, _) =
@@ -228,7 +258,7 @@ where
         // update transcript and generate challenge
         transcript.append_serializable_element(b"frac(x)", &proof.frac_comm)?;
         transcript.append_serializable_element(b"prod(x)", &proof.prod_x_comm)?;
-        let alpha = transcript.get_and_append_challenge(b"alpha")?;
+        let alpha = transcript.get_perm_check_challenge(b"alpha")?;
 
         // invoke the zero check on the iop_proof
         // the virtual poly info for Q(x)
@@ -238,17 +268,14 @@ where
             transcript,
         )?;
 
-        // the final query is on prod_x
-        let mut final_query = vec![E::ScalarField::one(); aux_info.num_variables];
-        // the point has to be reversed because Arkworks uses big-endian.
-        final_query[0] = E::ScalarField::zero();
-        let final_eval = E::ScalarField::one();
+        // the boundary query is on prod_x
+        let required_openings = vec![prod_boundary_opening(aux_info.num_variables)];
 
         end_timer!(start);
 
         Ok(ProductCheckSubClaim {
             zero_check_sub_claim,
-            final_query: (final_query, final_eval),
+            required_openings,
             alpha,
         })
     }
@@ -259,7 +286,11 @@ mod test {
     use super::ProductCheck;
     use crate::{
         pcs::{prelude::MultilinearKzgPCS, PolynomialCommitmentScheme},
-        poly_iop::{errors::PolyIOPErrors, PolyIOP},
+        poly_iop::{
+            errors::PolyIOPErrors,
+            strategies::{check, fail, poly_shape, PolyShape},
+            PolyIOP,
+        },
     };
     use arithmetic::VPAuxInfo;
     use ark_bls12_381::{Bls12_381, Fr};
@@ -323,6 +354,8 @@ mod test {
         let aux_info = VPAuxInfo {
             max_degree: fs.len() + 1,
             num_variables: fs[0].num_vars,
+            per_variable_degree: vec![],
+            is_sparse: false,
             phantom: PhantomData,
         };
         let prod_subclaim = <PolyIOP<E::ScalarField> as ProductCheck<E, PCS>>::verify(
@@ -330,11 +363,9 @@ mod test {
             &aux_info,
             &mut transcript,
         )?;
-        assert_eq!(
-            prod_x.evaluate(&prod_subclaim.final_query.0).unwrap(),
-            prod_subclaim.final_query.1,
-            "different product"
-        );
+        for (_, point, eval) in prod_subclaim.required_openings.iter() {
+            assert_eq!(prod_x.evaluate(point).unwrap(), *eval, "different product");
+        }
         check_frac_poly::<E>(&frac_poly, fs, gs);
 
         // bad path
@@ -355,9 +386,10 @@ mod test {
             &aux_info,
             &mut transcript,
         )?;
+        let (_, point, eval) = &bad_subclaim.required_openings[0];
         assert_ne!(
-            prod_x_bad.evaluate(&bad_subclaim.final_query.0).unwrap(),
-            bad_subclaim.final_query.1,
+            prod_x_bad.evaluate(point).unwrap(),
+            *eval,
             "can't detect wrong proof"
         );
         // the frac_poly should still be computed correctly
@@ -403,4 +435,38 @@ mod test {
     fn test_normal_polynomial() -> Result<(), PolyIOPErrors> {
         test_product_check(10)
     }
+
+    #[test]
+    fn test_product_check_shapes() -> Result<(), PolyIOPErrors> {
+        let max_nv = 6;
+        let srs = MultilinearKzgPCS::<Bls12_381>::gen_srs_for_testing(&mut test_rng(), max_nv)?;
+        // the multiplicands of a single product are the fractions
+        check(poly_shape(max_nv, 3, 1), |shape| {
+            let (poly, _) = shape.polynomial::<Fr>();
+            let fs = poly.flattened_ml_extensions.clone();
+            let gs: Vec<_> = fs
+                .iter()
+                .rev()
+                .map(|f| {
+                    let mut g = f.as_ref().clone();
+                    g.evaluations.reverse();
+                    Arc::new(g)
+                })
+                .collect();
+            let other_shape = PolyShape {
+                seed: shape.seed ^ 1,
+                ..shape.clone()
+            };
+            let hs = other_shape.polynomial::<Fr>().0.flattened_ml_extensions;
+
+            let (pcs_param, _) =
+                MultilinearKzgPCS::<Bls12_381>::trim(&srs, None, Some(shape.num_vars))
+                    .map_err(fail)?;
+            test_product_check_helper::<Bls12_381, MultilinearKzgPCS<Bls12_381>>(
+                &fs, &gs, &hs, &pcs_param,
+            )
+            .map_err(fail)
+        });
+        Ok(())
+    }
 }
@@ -12,14 +12,14 @@ use ark_ff::{batch_inversion, PrimeField};
 use ark_poly::DenseMultilinearExtension;
 use ark_std::{end_timer, start_timer};
 use std::sync::Arc;
-use transcript::IOPTranscript;
+use transcript::{IOPTranscript, PermCheckChallenge};
 
 /// Compute multilinear fractional polynomial s.t. frac(x) = f1(x) * ... * fk(x)
 /// / (g1(x) * ... * gk(x)) for all x \in {0,1}^n
 ///
 /// The caller needs to sanity-check that the number of polynomials and
 /// variables match in fxs and gxs; and gi(x) has no zero entries.
-pub(super) fn compute_frac_poly<F: PrimeField>(
+pub fn compute_frac_poly<F: PrimeField>(
     fxs: &[Arc<DenseMultilinearExtension<F>>],
     gxs: &[Arc<DenseMultilinearExtension<F>>],
 ) -> Result<Arc<DenseMultilinearExtension<F>>, PolyIOPErrors> {
@@ -62,7 +62,7 @@ pub(super) fn compute_frac_poly<F: PrimeField>(
 ///
 /// The caller needs to check num_vars matches in f and g
 /// Cost: linear in N.
-pub(super) fn compute_product_poly<F: PrimeField>(
+pub fn compute_product_poly<F: PrimeField>(
     frac_poly: &Arc<DenseMultilinearExtension<F>>,
 ) -> Result<Arc<DenseMultilinearExtension<F>>, PolyIOPErrors> {
     let start = start_timer!(|| "compute evaluations of prod polynomial");
@@ -124,10 +124,30 @@ pub(super) fn prove_zero_check<F: PrimeField>(
     gxs: &[Arc<DenseMultilinearExtension<F>>],
     frac_poly: &Arc<DenseMultilinearExtension<F>>,
     prod_x: &Arc<DenseMultilinearExtension<F>>,
-    alpha: &F,
+    alpha: &PermCheckChallenge<F>,
     transcript: &mut IOPTranscript<F>,
 ) -> Result<(IOPProof<F>, VirtualPolynomial<F>), PolyIOPErrors> {
     let start = start_timer!(|| "zerocheck in product check");
+
+    let q_x = build_prod_check_poly(fxs, gxs, frac_poly, prod_x, alpha)?;
+    let iop_proof = <PolyIOP<F> as ZeroCheck<F>>::prove(&q_x, transcript)?;
+
+    end_timer!(start);
+    Ok((iop_proof, q_x))
+}
+
+/// Build the virtual polynomial
+///    Q(x) = prod(x) - p1(x) * p2(x) + alpha * [frac(x) * g1(x) * ... * gk(x)
+/// - f1(x) * ... * fk(x)] whose zero check is the core of the product check.
+///
+/// Cost: O(N)
+pub fn build_prod_check_poly<F: PrimeField>(
+    fxs: &[Arc<DenseMultilinearExtension<F>>],
+    gxs: &[Arc<DenseMultilinearExtension<F>>],
+    frac_poly: &Arc<DenseMultilinearExtension<F>>,
+    prod_x: &Arc<DenseMultilinearExtension<F>>,
+    alpha: &PermCheckChallenge<F>,
+) -> Result<VirtualPolynomial<F>, PolyIOPErrors> {
     let num_vars = frac_poly.num_vars;
 
     // compute p1(x) = (1-x1) * frac(x2, ..., xn, 0) + x1 * prod(x2, ..., xn, 0)
@@ -164,16 +184,13 @@ pub(super) fn prove_zero_check<F: PrimeField>(
     // + alpha * frac(x) * g1(x) * ... * gk(x)
     let mut mle_list = gxs.to_vec();
     mle_list.push(frac_poly.clone());
-    q_x.add_mle_list(mle_list, *alpha)?;
+    q_x.add_mle_list(mle_list, alpha.0)?;
 
     //   prod(x)
     // - p1(x) * p2(x)
     // + alpha * frac(x) * g1(x) * ... * gk(x)
     // - alpha * f1(x) * ... * fk(x)]
-    q_x.add_mle_list(fxs.to_vec(), -*alpha)?;
+    q_x.add_mle_list(fxs.to_vec(), -alpha.0)?;
 
-    let iop_proof = <PolyIOP<F> as ZeroCheck<F>>::prove(&q_x, transcript)?;
-
-    end_timer!(start);
-    Ok((iop_proof, q_x))
+    Ok(q_x)
 }
@@ -0,0 +1,160 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! A versioned binary format for storing sum check proofs.
+//!
+//! The bytes of a proof are a version tag, on 4 bytes in little-endian,
+//! followed by the encoding of the proof by the codec of that version. A
+//! codec is frozen once its version has shipped, so that stored proofs stay
+//! readable: a change of the encoding is a new version, with a new codec.
+//!
+//! Version 0 is the compressed canonical serialization of `IOPProof`.
+
+use crate::poly_iop::{errors::PolyIOPErrors, structs::IOPProof};
+use ark_ff::PrimeField;
+
+/// The version of the proofs written by [`ProofSerializer::serialize`] by
+/// default.
+pub const PROOF_FORMAT_VERSION: u32 = v0::VERSION;
+
+/// The versions of the proof format that can be read and written, oldest
+/// first.
+pub const SUPPORTED_PROOF_FORMATS: [u32; 1] = [v0::VERSION];
+
+/// Reads and writes sum check proofs in the versioned binary format.
+pub struct ProofSerializer;
+
+impl ProofSerializer {
+    /// The bytes of `proof` in the format `version`. Returns
+    /// `PolyIOPErrors::UnsupportedVersion` if `version` is not supported.
+    pub fn serialize<F: PrimeField>(
+        proof: &IOPProof<F>,
+        version: u32,
+    ) -> Result<Vec<u8>, PolyIOPErrors> {
+        let mut bytes = version.to_le_bytes().to_vec();
+        match version {
+            v0::VERSION => v0::write(proof, &mut bytes)?,
+            _ => return Err(PolyIOPErrors::unsupported_version(version)),
+        }
+        Ok(bytes)
+    }
+
+    /// The proof in `bytes`, with the version of its format. Returns an
+    /// error if the version is not supported, or if the bytes are not
+    /// exactly a proof in that version.
+    pub fn deserialize<F: PrimeField>(bytes: &[u8]) -> Result<(IOPProof<F>, u32), PolyIOPErrors> {
+        if bytes.len() < 4 {
+            return Err(PolyIOPErrors::InvalidProof(format!(
+                "{} bytes is too short for the version tag",
+                bytes.len()
+            )));
+        }
+        let (tag, mut reader) = bytes.split_at(4);
+        let version = u32::from_le_bytes([tag[0], tag[1], tag[2], tag[3]]);
+        let proof = match version {
+            v0::VERSION => v0::read(&mut reader)?,
+            _ => return Err(PolyIOPErrors::unsupported_version(version)),
+        };
+        if !reader.is_empty() {
+            return Err(PolyIOPErrors::InvalidProof(format!(
+                "{} trailing bytes after the proof",
+                reader.len()
+            )));
+        }
+        Ok((proof, version))
+    }
+}
+
+/// Version 0: the compressed canonical serialization, with validation.
+mod v0 {
+    use super::*;
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+
+    pub(super) const VERSION: u32 = 0;
+
+    pub(super) fn write<F: PrimeField>(
+        proof: &IOPProof<F>,
+        bytes: &mut Vec<u8>,
+    ) -> Result<(), PolyIOPErrors> {
+        Ok(proof.serialize_compressed(bytes)?)
+    }
+
+    pub(super) fn read<F: PrimeField>(reader: &mut &[u8]) -> Result<IOPProof<F>, PolyIOPErrors> {
+        Ok(IOPProof::deserialize_compressed(reader)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poly_iop::structs::IOPProverMessage;
+    use ark_secp256k1::Fr;
+    use ark_serialize::CanonicalSerialize;
+    use ark_std::{test_rng, UniformRand};
+
+    fn proof() -> IOPProof<Fr> {
+        let mut rng = test_rng();
+        IOPProof {
+            point: (0..3).map(|_| Fr::rand(&mut rng)).collect(),
+            proofs: (0..3)
+                .map(|_| IOPProverMessage {
+                    evaluations: (0..4).map(|_| Fr::rand(&mut rng)).collect(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_v0_round_trip() -> Result<(), PolyIOPErrors> {
+        let proof = proof();
+        let bytes = ProofSerializer::serialize(&proof, PROOF_FORMAT_VERSION)?;
+        assert_eq!(bytes[..4], [0, 0, 0, 0]);
+        let mut canonical = vec![];
+        proof.serialize_compressed(&mut canonical)?;
+        assert_eq!(bytes[4..], canonical[..]);
+        assert_eq!(ProofSerializer::deserialize(&bytes)?, (proof, 0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_tampered_version() -> Result<(), PolyIOPErrors> {
+        let mut bytes = ProofSerializer::serialize(&proof(), 0)?;
+        for i in 0..4 {
+            bytes[i] ^= 1;
+            assert!(matches!(
+                ProofSerializer::deserialize::<Fr>(&bytes),
+                Err(PolyIOPErrors::UnsupportedVersion { .. })
+            ));
+            bytes[i] ^= 1;
+        }
+        ProofSerializer::deserialize::<Fr>(&bytes)?;
+
+        // bad path: truncated, trailing bytes
+        assert!(ProofSerializer::deserialize::<Fr>(&bytes[..3]).is_err());
+        assert!(ProofSerializer::deserialize::<Fr>(&bytes[..bytes.len() - 1]).is_err());
+        bytes.push(0);
+        assert!(ProofSerializer::deserialize::<Fr>(&bytes).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        // there is no codec for version 1 yet
+        assert!(matches!(
+            ProofSerializer::serialize(&proof(), 1),
+            Err(PolyIOPErrors::UnsupportedVersion { version: 1, .. })
+        ));
+        let mut bytes = ProofSerializer::serialize(&proof(), 0).unwrap();
+        bytes[0] = 1;
+        match ProofSerializer::deserialize::<Fr>(&bytes) {
+            Err(PolyIOPErrors::UnsupportedVersion { version, supported }) => {
+                assert_eq!(version, 1);
+                assert_eq!(supported, SUPPORTED_PROOF_FORMATS.to_vec());
+            },
+            _ => panic!("version 1 is not supported"),
+        }
+    }
+}
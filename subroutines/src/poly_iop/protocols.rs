@@ -0,0 +1,723 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! Inherent entry points for each protocol implemented by [`PolyIOP`].
+//!
+//! `SumCheckProtocol::<F>::prove(...)` is equivalent to
+//! `<PolyIOP<F> as SumCheck<F>>::prove(...)`, and likewise for the other
+//! protocols. The traits remain the API for code that is generic over the
+//! IOP.
+
+use crate::{
+    pcs::PolynomialCommitmentScheme,
+    poly_iop::{
+        errors::PolyIOPErrors,
+        lookup_check::{LookupCheck, LookupCheckProof, LookupCheckSubClaim},
+        perm_check::{PermutationCheck, PermutationCheckSubClaim},
+        prod_check::ProductCheckProof,
+        structs::{IOPProof, RandomMaskPolynomial, VerifierTrace},
+        sum_check::{SumCheck, SumCheckSubClaim},
+        zero_check::{ZeroCheck, ZeroCheckSubClaim},
+        zk_sum_check::{prove_with_rho, verify_with_rho, ZkSumCheck, ZkSumCheckSubClaim},
+        PolyIOP,
+    },
+};
+use arithmetic::{VPAuxInfo, VirtualPolynomial};
+use ark_ec::pairing::Pairing;
+use ark_ff::PrimeField;
+use ark_poly::DenseMultilinearExtension;
+use ark_serialize::CanonicalSerialize;
+use std::{marker::PhantomData, sync::Arc};
+use transcript::IOPTranscript;
+
+/// The sum check protocol.
+///
+/// ```
+/// use arithmetic::VirtualPolynomial;
+/// use ark_bls12_381::Fr;
+/// use ark_std::test_rng;
+/// use subroutines::poly_iop::prelude::{PolyIOPErrors, SumCheckProtocol};
+///
+/// # fn main() -> Result<(), PolyIOPErrors> {
+/// let mut rng = test_rng();
+/// let (poly, sum) = VirtualPolynomial::<Fr>::rand(4, (2, 3), 2, &mut rng)?;
+///
+/// let mut transcript = SumCheckProtocol::<Fr>::init_transcript();
+/// let proof = SumCheckProtocol::prove(&poly, &mut transcript)?;
+///
+/// let mut transcript = SumCheckProtocol::<Fr>::init_transcript();
+/// let subclaim = SumCheckProtocol::verify(sum, &proof, &poly.aux_info, &mut transcript)?;
+/// assert_eq!(poly.evaluate(&subclaim.point)?, subclaim.expected_evaluation);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SumCheckProtocol<F: PrimeField>(PhantomData<F>);
+
+impl<F: PrimeField> SumCheckProtocol<F> {
+    /// See [`SumCheck::init_transcript`].
+    pub fn init_transcript() -> IOPTranscript<F> {
+        <PolyIOP<F> as SumCheck<F>>::init_transcript()
+    }
+
+    /// See [`SumCheck::extract_sum`].
+    pub fn extract_sum(proof: &IOPProof<F>) -> F {
+        <PolyIOP<F> as SumCheck<F>>::extract_sum(proof)
+    }
+
+    /// See [`SumCheck::prove`].
+    pub fn prove(
+        poly: &VirtualPolynomial<F>,
+        transcript: &mut IOPTranscript<F>,
+    ) -> Result<IOPProof<F>, PolyIOPErrors> {
+        <PolyIOP<F> as SumCheck<F>>::prove(poly, transcript)
+    }
+
+    /// See [`SumCheck::verify`].
+    pub fn verify(
+        claimed_sum: F,
+        proof: &IOPProof<F>,
+        aux_info: &VPAuxInfo<F>,
+        transcript: &mut IOPTranscript<F>,
+    ) -> Result<SumCheckSubClaim<F>, PolyIOPErrors> {
+        <PolyIOP<F> as SumCheck<F>>::verify(claimed_sum, proof, aux_info, transcript)
+    }
+
+    /// See [`SumCheck::verify_traced`].
+    pub fn verify_traced(
+        claimed_sum: F,
+        proof: &IOPProof<F>,
+        aux_info: &VPAuxInfo<F>,
+        transcript: &mut IOPTranscript<F>,
+    ) -> Result<(SumCheckSubClaim<F>, VerifierTrace<F>), PolyIOPErrors> {
+        <PolyIOP<F> as SumCheck<F>>::verify_traced(claimed_sum, proof, aux_info, transcript)
+    }
+}
+
+/// The zero-knowledge sum check protocol, which masks the polynomial with
+/// `rho` times a random mask polynomial, where `rho` is sampled from the
+/// transcript after absorbing a commitment to the mask.
+///
+/// ```
+/// use arithmetic::VirtualPolynomial;
+/// use ark_bls12_381::Fr;
+/// use ark_std::test_rng;
+/// use subroutines::poly_iop::prelude::{PolyIOPErrors, RandomMaskPolynomial, ZkSumCheckProtocol};
+///
+/// # fn main() -> Result<(), PolyIOPErrors> {
+/// let mut rng = test_rng();
+/// let (poly, sum) = VirtualPolynomial::<Fr>::rand(4, (2, 3), 2, &mut rng)?;
+/// let (mask, mask_sum) = RandomMaskPolynomial::rand(4, 2, &mut rng);
+/// // any serializable commitment to the mask
+/// let mask_commitment = mask.evaluations.clone();
+///
+/// let mut transcript = ZkSumCheckProtocol::<Fr>::init_transcript();
+/// let (proof, rho) =
+///     ZkSumCheckProtocol::prove_bound(&poly, &mask, &mask_commitment, &mut transcript)?;
+///
+/// let mut transcript = ZkSumCheckProtocol::<Fr>::init_transcript();
+/// let (subclaim, verifier_rho) = ZkSumCheckProtocol::verify_bound(
+///     sum,
+///     mask_sum,
+///     &proof,
+///     &mask_commitment,
+///     &poly.aux_info,
+///     &mut transcript,
+///     2,
+/// )?;
+/// assert_eq!(rho, verifier_rho);
+/// assert_eq!(
+///     poly.evaluate(&subclaim.point)? + rho * mask.eval(&subclaim.point)?,
+///     subclaim.expected_evaluation
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ZkSumCheckProtocol<F: PrimeField>(PhantomData<F>);
+
+impl<F: PrimeField> ZkSumCheckProtocol<F> {
+    /// See [`ZkSumCheck::init_transcript`].
+    pub fn init_transcript() -> IOPTranscript<F> {
+        <PolyIOP<F> as ZkSumCheck<F>>::init_transcript()
+    }
+
+    /// See [`ZkSumCheck::init_transcript_with_domain`].
+    pub fn init_transcript_with_domain(domain: Option<&[u8]>) -> IOPTranscript<F> {
+        <PolyIOP<F> as ZkSumCheck<F>>::init_transcript_with_domain(domain)
+    }
+
+    /// See [`ZkSumCheck::extract_sum`].
+    pub fn extract_sum(proof: &IOPProof<F>) -> F {
+        <PolyIOP<F> as ZkSumCheck<F>>::extract_sum(proof)
+    }
+
+    /// See [`ZkSumCheck::prove`].
+    #[deprecated(note = "rho must come from the transcript, use `prove_bound`")]
+    pub fn prove(
+        poly: &VirtualPolynomial<F>,
+        mask_poly: &RandomMaskPolynomial<F>,
+        rho: &F,
+        transcript: &mut IOPTranscript<F>,
+    ) -> Result<IOPProof<F>, PolyIOPErrors> {
+        prove_with_rho(poly, mask_poly, rho, transcript)
+    }
+
+    /// See [`ZkSumCheck::verify`].
+    #[deprecated(note = "rho must come from the transcript, use `verify_bound`")]
+    pub fn verify(
+        claimed_sum: F,
+        proof: &IOPProof<F>,
+        aux_info: &VPAuxInfo<F>,
+        transcript: &mut IOPTranscript<F>,
+        mask_poly_nv: usize,
+        mask_poly_degree: usize,
+    ) -> Result<ZkSumCheckSubClaim<F>, PolyIOPErrors> {
+        verify_with_rho(
+            claimed_sum,
+            proof,
+            aux_info,
+            transcript,
+            mask_poly_nv,
+            mask_poly_degree,
+        )
+    }
+
+    /// See [`ZkSumCheck::batch_verify`].
+    pub fn batch_verify(
+        sums: &[F],
+        proofs: &[IOPProof<F>],
+        aux_info: &VPAuxInfo<F>,
+        transcript: &mut IOPTranscript<F>,
+        mask_poly_nv: usize,
+        mask_poly_degree: usize,
+    ) -> Result<Vec<ZkSumCheckSubClaim<F>>, PolyIOPErrors> {
+        <PolyIOP<F> as ZkSumCheck<F>>::batch_verify(
+            sums,
+            proofs,
+            aux_info,
+            transcript,
+            mask_poly_nv,
+            mask_poly_degree,
+        )
+    }
+
+    /// See [`ZkSumCheck::prove_bound`].
+    pub fn prove_bound<C: CanonicalSerialize>(
+        poly: &VirtualPolynomial<F>,
+        mask_poly: &RandomMaskPolynomial<F>,
+        mask_commitment: &C,
+        transcript: &mut IOPTranscript<F>,
+    ) -> Result<(IOPProof<F>, F), PolyIOPErrors> {
+        <PolyIOP<F> as ZkSumCheck<F>>::prove_bound(poly, mask_poly, mask_commitment, transcript)
+    }
+
+    /// See [`ZkSumCheck::verify_bound`].
+    pub fn verify_bound<C: CanonicalSerialize>(
+        sum: F,
+        mask_sum: F,
+        proof: &IOPProof<F>,
+        mask_commitment: &C,
+        aux_info: &VPAuxInfo<F>,
+        transcript: &mut IOPTranscript<F>,
+        mask_poly_degree: usize,
+    ) -> Result<(ZkSumCheckSubClaim<F>, F), PolyIOPErrors> {
+        <PolyIOP<F> as ZkSumCheck<F>>::verify_bound(
+            sum,
+            mask_sum,
+            proof,
+            mask_commitment,
+            aux_info,
+            transcript,
+            mask_poly_degree,
+        )
+    }
+
+    /// See [`ZkSumCheck::verify_traced`].
+    pub fn verify_traced<C: CanonicalSerialize>(
+        sum: F,
+        mask_sum: F,
+        proof: &IOPProof<F>,
+        mask_commitment: &C,
+        aux_info: &VPAuxInfo<F>,
+        transcript: &mut IOPTranscript<F>,
+        mask_poly_degree: usize,
+    ) -> Result<(ZkSumCheckSubClaim<F>, F, VerifierTrace<F>), PolyIOPErrors> {
+        <PolyIOP<F> as ZkSumCheck<F>>::verify_traced(
+            sum,
+            mask_sum,
+            proof,
+            mask_commitment,
+            aux_info,
+            transcript,
+            mask_poly_degree,
+        )
+    }
+}
+
+/// The zero check protocol.
+///
+/// ```
+/// use arithmetic::VirtualPolynomial;
+/// use ark_bls12_381::Fr;
+/// use ark_std::test_rng;
+/// use subroutines::poly_iop::prelude::{PolyIOPErrors, ZeroCheckProtocol};
+///
+/// # fn main() -> Result<(), PolyIOPErrors> {
+/// let mut rng = test_rng();
+/// let poly = VirtualPolynomial::<Fr>::rand_zero(4, (2, 3), 2, &mut rng)?;
+///
+/// let mut transcript = ZeroCheckProtocol::<Fr>::init_transcript();
+/// transcript.append_message(b"testing", b"initializing transcript for testing")?;
+/// let proof = ZeroCheckProtocol::prove(&poly, &mut transcript)?;
+///
+/// let mut transcript = ZeroCheckProtocol::<Fr>::init_transcript();
+/// transcript.append_message(b"testing", b"initializing transcript for testing")?;
+/// let subclaim = ZeroCheckProtocol::verify(&proof, &poly.aux_info, &mut transcript)?;
+/// assert_eq!(poly.evaluate(&subclaim.point)?, subclaim.expected_evaluation);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ZeroCheckProtocol<F: PrimeField>(PhantomData<F>);
+
+impl<F: PrimeField> ZeroCheckProtocol<F> {
+    /// See [`ZeroCheck::init_transcript`].
+    pub fn init_transcript() -> IOPTranscript<F> {
+        <PolyIOP<F> as ZeroCheck<F>>::init_transcript()
+    }
+
+    /// See [`ZeroCheck::prove`].
+    pub fn prove(
+        poly: &VirtualPolynomial<F>,
+        transcript: &mut IOPTranscript<F>,
+    ) -> Result<IOPProof<F>, PolyIOPErrors> {
+        <PolyIOP<F> as ZeroCheck<F>>::prove(poly, transcript)
+    }
+
+    /// See [`ZeroCheck::verify`].
+    pub fn verify(
+        proof: &IOPProof<F>,
+        fx_aux_info: &VPAuxInfo<F>,
+        transcript: &mut IOPTranscript<F>,
+    ) -> Result<ZeroCheckSubClaim<F>, PolyIOPErrors> {
+        <PolyIOP<F> as ZeroCheck<F>>::verify(proof, fx_aux_info, transcript)
+    }
+}
+
+/// The permutation check protocol, with the product polynomial committed
+/// with `PCS`.
+///
+/// ```
+/// use arithmetic::{evaluate_opt, identity_permutation_mles, VPAuxInfo};
+/// use ark_bls12_381::{Bls12_381, Fr};
+/// use ark_poly::{DenseMultilinearExtension, MultilinearExtension};
+/// use ark_std::test_rng;
+/// use std::{marker::PhantomData, sync::Arc};
+/// use subroutines::{
+///     pcs::{prelude::MultilinearKzgPCS, PolynomialCommitmentScheme},
+///     poly_iop::prelude::{PermCheckProtocol, PolyIOPErrors},
+/// };
+///
+/// type Perm = PermCheckProtocol<Bls12_381, MultilinearKzgPCS<Bls12_381>>;
+///
+/// # fn main() -> Result<(), PolyIOPErrors> {
+/// let mut rng = test_rng();
+/// let nv = 3;
+/// let srs = MultilinearKzgPCS::<Bls12_381>::gen_srs_for_testing(&mut rng, nv)?;
+/// let (pcs_param, _) = MultilinearKzgPCS::<Bls12_381>::trim(&srs, None, Some(nv))?;
+///
+/// // (w1, w2) is a permutation of (w2, w1) under the swapped identity
+/// let w1 = Arc::new(DenseMultilinearExtension::<Fr>::rand(nv, &mut rng));
+/// let w2 = Arc::new(DenseMultilinearExtension::<Fr>::rand(nv, &mut rng));
+/// let mut perms = identity_permutation_mles(nv, 2);
+/// perms.reverse();
+///
+/// let mut transcript = Perm::init_transcript();
+/// transcript.append_message(b"testing", b"initializing transcript for testing")?;
+/// let (proof, prod_x, _frac_poly) = Perm::prove(
+///     &pcs_param,
+///     &[w1.clone(), w2.clone()],
+///     &[w2, w1],
+///     &perms,
+///     &mut transcript,
+/// )?;
+///
+/// let aux_info = VPAuxInfo {
+///     max_degree: 3,
+///     num_variables: nv,
+///     per_variable_degree: vec![],
+///     is_sparse: false,
+///     phantom: PhantomData,
+/// };
+/// let mut transcript = Perm::init_transcript();
+/// transcript.append_message(b"testing", b"initializing transcript for testing")?;
+/// let subclaim = Perm::verify(&proof, &aux_info, &mut transcript)?;
+/// let (_, point, eval) = &subclaim.product_check_sub_claim.required_openings[0];
+/// assert_eq!(evaluate_opt(&prod_x, point), *eval);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PermCheckProtocol<E: Pairing, PCS>(PhantomData<(E, PCS)>);
+
+impl<E, PCS> PermCheckProtocol<E, PCS>
+where
+    E: Pairing,
+    PCS: PolynomialCommitmentScheme<E, Polynomial = Arc<DenseMultilinearExtension<E::ScalarField>>>,
+{
+    /// See [`PermutationCheck::init_transcript`].
+    pub fn init_transcript() -> IOPTranscript<E::ScalarField> {
+        <PolyIOP<E::ScalarField> as PermutationCheck<E, PCS>>::init_transcript()
+    }
+
+    /// See [`PermutationCheck::prove`].
+    #[allow(clippy::type_complexity)]
+    pub fn prove(
+        pcs_param: &PCS::ProverParam,
+        fxs: &[Arc<DenseMultilinearExtension<E::ScalarField>>],
+        gxs: &[Arc<DenseMultilinearExtension<E::ScalarField>>],
+        perms: &[Arc<DenseMultilinearExtension<E::ScalarField>>],
+        transcript: &mut IOPTranscript<E::ScalarField>,
+    ) -> Result<
+        (
+            ProductCheckProof<E, PCS, PolyIOP<E::ScalarField>>,
+            Arc<DenseMultilinearExtension<E::ScalarField>>,
+            Arc<DenseMultilinearExtension<E::ScalarField>>,
+        ),
+        PolyIOPErrors,
+    > {
+        <PolyIOP<E::ScalarField> as PermutationCheck<E, PCS>>::prove(
+            pcs_param, fxs, gxs, perms, transcript,
+        )
+    }
+
+    /// See [`PermutationCheck::verify`].
+    pub fn verify(
+        proof: &ProductCheckProof<E, PCS, PolyIOP<E::ScalarField>>,
+        aux_info: &VPAuxInfo<E::ScalarField>,
+        transcript: &mut IOPTranscript<E::ScalarField>,
+    ) -> Result<PermutationCheckSubClaim<E, PCS, PolyIOP<E::ScalarField>>, PolyIOPErrors> {
+        <PolyIOP<E::ScalarField> as PermutationCheck<E, PCS>>::verify(proof, aux_info, transcript)
+    }
+}
+
+/// The lookup check protocol, with the multiplicity and fractional
+/// polynomials committed with `PCS`.
+///
+/// ```
+/// use arithmetic::eq_eval;
+/// use ark_bls12_381::{Bls12_381, Fr};
+/// use ark_poly::{DenseMultilinearExtension, MultilinearExtension};
+/// use ark_std::test_rng;
+/// use std::sync::Arc;
+/// use subroutines::{
+///     pcs::{prelude::MultilinearKzgPCS, PolynomialCommitmentScheme},
+///     poly_iop::prelude::{
+///         eval_lookup_check_poly, lookup_check_aux_info, LookupCheckProtocol, PolyIOPErrors,
+///     },
+/// };
+///
+/// type Lookup = LookupCheckProtocol<Bls12_381, MultilinearKzgPCS<Bls12_381>>;
+///
+/// # fn main() -> Result<(), PolyIOPErrors> {
+/// let mut rng = test_rng();
+/// let nv = 3;
+/// let srs = MultilinearKzgPCS::<Bls12_381>::gen_srs_for_testing(&mut rng, nv)?;
+/// let (pcs_param, _) = MultilinearKzgPCS::<Bls12_381>::trim(&srs, None, Some(nv))?;
+///
+/// // the reversed table looks up in the table
+/// let t = Arc::new(DenseMultilinearExtension::<Fr>::rand(nv, &mut rng));
+/// let mut f = t.as_ref().clone();
+/// f.evaluations.reverse();
+/// let f = Arc::new(f);
+///
+/// let mut transcript = Lookup::init_transcript();
+/// let (proof, ms, as_, bs) =
+///     Lookup::prove(&pcs_param, &[f.clone()], &[t.clone()], &mut transcript)?;
+///
+/// let mut transcript = Lookup::init_transcript();
+/// let subclaim = Lookup::verify(&proof, &lookup_check_aux_info(nv), &mut transcript)?;
+/// let point = &subclaim.sum_check_sub_claim.point;
+/// let evals = [[&f, &t, &ms[0], &as_[0], &bs[0]].map(|p| p.evaluate(point).unwrap())];
+/// let eq = eq_eval(point, &subclaim.r)?;
+/// assert_eq!(
+///     eval_lookup_check_poly(&evals, &subclaim.gamma, &subclaim.alpha, eq),
+///     subclaim.sum_check_sub_claim.expected_evaluation
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LookupCheckProtocol<E: Pairing, PCS>(PhantomData<(E, PCS)>);
+
+impl<E, PCS> LookupCheckProtocol<E, PCS>
+where
+    E: Pairing,
+    PCS: PolynomialCommitmentScheme<E, Polynomial = Arc<DenseMultilinearExtension<E::ScalarField>>>,
+{
+    /// See [`LookupCheck::init_transcript`].
+    pub fn init_transcript() -> IOPTranscript<E::ScalarField> {
+        <PolyIOP<E::ScalarField> as LookupCheck<E, PCS>>::init_transcript()
+    }
+
+    /// See [`LookupCheck::prove`].
+    #[allow(clippy::type_complexity)]
+    pub fn prove(
+        pcs_param: &PCS::ProverParam,
+        fxs: &[Arc<DenseMultilinearExtension<E::ScalarField>>],
+        txs: &[Arc<DenseMultilinearExtension<E::ScalarField>>],
+        transcript: &mut IOPTranscript<E::ScalarField>,
+    ) -> Result<
+        (
+            LookupCheckProof<E, PCS>,
+            Vec<Arc<DenseMultilinearExtension<E::ScalarField>>>,
+            Vec<Arc<DenseMultilinearExtension<E::ScalarField>>>,
+            Vec<Arc<DenseMultilinearExtension<E::ScalarField>>>,
+        ),
+        PolyIOPErrors,
+    > {
+        <PolyIOP<E::ScalarField> as LookupCheck<E, PCS>>::prove(pcs_param, fxs, txs, transcript)
+    }
+
+    /// See [`LookupCheck::verify`].
+    pub fn verify(
+        proof: &LookupCheckProof<E, PCS>,
+        aux_info: &VPAuxInfo<E::ScalarField>,
+        transcript: &mut IOPTranscript<E::ScalarField>,
+    ) -> Result<LookupCheckSubClaim<E::ScalarField>, PolyIOPErrors> {
+        <PolyIOP<E::ScalarField> as LookupCheck<E, PCS>>::verify(proof, aux_info, transcript)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pcs::prelude::MultilinearKzgPCS;
+    use arithmetic::{identity_permutation_mles, random_permutation_mles};
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_poly::MultilinearExtension;
+    use ark_std::test_rng;
+
+    type Kzg = MultilinearKzgPCS<Bls12_381>;
+
+    #[test]
+    fn test_sum_check_protocol_matches_trait() -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        let (poly, sum) = VirtualPolynomial::<Fr>::rand(5, (2, 4), 3, &mut rng)?;
+
+        let mut transcript = SumCheckProtocol::<Fr>::init_transcript();
+        let proof = SumCheckProtocol::prove(&poly, &mut transcript)?;
+        let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+        assert_eq!(
+            proof,
+            <PolyIOP<Fr> as SumCheck<Fr>>::prove(&poly, &mut transcript)?
+        );
+        assert_eq!(SumCheckProtocol::extract_sum(&proof), sum);
+
+        let mut transcript = SumCheckProtocol::<Fr>::init_transcript();
+        let subclaim = SumCheckProtocol::verify(sum, &proof, &poly.aux_info, &mut transcript)?;
+        let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+        assert_eq!(
+            subclaim,
+            <PolyIOP<Fr> as SumCheck<Fr>>::verify(sum, &proof, &poly.aux_info, &mut transcript)?
+        );
+        assert_eq!(
+            poly.evaluate(&subclaim.point)?,
+            subclaim.expected_evaluation
+        );
+
+        // bad path: a wrong sum
+        let mut transcript = SumCheckProtocol::<Fr>::init_transcript();
+        assert!(SumCheckProtocol::verify(
+            sum + Fr::from(1u64),
+            &proof,
+            &poly.aux_info,
+            &mut transcript
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_zk_sum_check_protocol_matches_trait() -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        let nv = 5;
+        let (poly, sum) = VirtualPolynomial::<Fr>::rand(nv, (2, 4), 3, &mut rng)?;
+        let (mask, mask_sum) = RandomMaskPolynomial::rand(nv, 3, &mut rng);
+        let mask_commitment = mask.evaluations.clone();
+
+        let mut transcript = ZkSumCheckProtocol::<Fr>::init_transcript();
+        let (proof, rho) =
+            ZkSumCheckProtocol::prove_bound(&poly, &mask, &mask_commitment, &mut transcript)?;
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr>>::init_transcript();
+        assert_eq!(
+            (proof.clone(), rho),
+            <PolyIOP<Fr> as ZkSumCheck<Fr>>::prove_bound(
+                &poly,
+                &mask,
+                &mask_commitment,
+                &mut transcript
+            )?
+        );
+        assert_eq!(
+            ZkSumCheckProtocol::extract_sum(&proof),
+            sum + rho * mask_sum
+        );
+
+        let mut transcript = ZkSumCheckProtocol::<Fr>::init_transcript();
+        let (subclaim, _) = ZkSumCheckProtocol::verify_bound(
+            sum,
+            mask_sum,
+            &proof,
+            &mask_commitment,
+            &poly.aux_info,
+            &mut transcript,
+            3,
+        )?;
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr>>::init_transcript();
+        assert_eq!(
+            (subclaim.clone(), rho),
+            <PolyIOP<Fr> as ZkSumCheck<Fr>>::verify_bound(
+                sum,
+                mask_sum,
+                &proof,
+                &mask_commitment,
+                &poly.aux_info,
+                &mut transcript,
+                3
+            )?
+        );
+        assert_eq!(
+            poly.evaluate(&subclaim.point)? + rho * mask.eval(&subclaim.point)?,
+            subclaim.expected_evaluation
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_zero_check_protocol_matches_trait() -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        let poly = VirtualPolynomial::<Fr>::rand_zero(5, (2, 4), 3, &mut rng)?;
+
+        let mut transcript = ZeroCheckProtocol::<Fr>::init_transcript();
+        transcript.append_message(b"testing", b"initializing transcript for testing")?;
+        let proof = ZeroCheckProtocol::prove(&poly, &mut transcript)?;
+        let mut transcript = <PolyIOP<Fr> as ZeroCheck<Fr>>::init_transcript();
+        transcript.append_message(b"testing", b"initializing transcript for testing")?;
+        assert_eq!(
+            proof,
+            <PolyIOP<Fr> as ZeroCheck<Fr>>::prove(&poly, &mut transcript)?
+        );
+
+        let mut transcript = ZeroCheckProtocol::<Fr>::init_transcript();
+        transcript.append_message(b"testing", b"initializing transcript for testing")?;
+        let subclaim = ZeroCheckProtocol::verify(&proof, &poly.aux_info, &mut transcript)?;
+        let mut transcript = <PolyIOP<Fr> as ZeroCheck<Fr>>::init_transcript();
+        transcript.append_message(b"testing", b"initializing transcript for testing")?;
+        assert_eq!(
+            subclaim,
+            <PolyIOP<Fr> as ZeroCheck<Fr>>::verify(&proof, &poly.aux_info, &mut transcript)?
+        );
+        assert_eq!(
+            poly.evaluate(&subclaim.point)?,
+            subclaim.expected_evaluation
+        );
+
+        // bad path: a polynomial that is not zero on the hypercube
+        let (poly, _) = VirtualPolynomial::<Fr>::rand(5, (2, 4), 3, &mut rng)?;
+        let mut transcript = ZeroCheckProtocol::<Fr>::init_transcript();
+        transcript.append_message(b"testing", b"initializing transcript for testing")?;
+        let proof = ZeroCheckProtocol::prove(&poly, &mut transcript)?;
+        let mut transcript = ZeroCheckProtocol::<Fr>::init_transcript();
+        transcript.append_message(b"testing", b"initializing transcript for testing")?;
+        assert!(ZeroCheckProtocol::verify(&proof, &poly.aux_info, &mut transcript).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_perm_check_protocol_matches_trait() -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        let nv = 4;
+        let srs = Kzg::gen_srs_for_testing(&mut rng, nv)?;
+        let (pcs_param, _) = Kzg::trim(&srs, None, Some(nv))?;
+        let ws = vec![
+            Arc::new(DenseMultilinearExtension::<Fr>::rand(nv, &mut rng)),
+            Arc::new(DenseMultilinearExtension::<Fr>::rand(nv, &mut rng)),
+        ];
+        let aux_info = VPAuxInfo {
+            max_degree: ws.len() + 1,
+            num_variables: nv,
+            per_variable_degree: vec![],
+            is_sparse: false,
+            phantom: PhantomData,
+        };
+        let id_perms = identity_permutation_mles(nv, 2);
+
+        let mut transcript = PermCheckProtocol::<Bls12_381, Kzg>::init_transcript();
+        transcript.append_message(b"testing", b"initializing transcript for testing")?;
+        let (proof, prod_x, frac_poly) = PermCheckProtocol::<Bls12_381, Kzg>::prove(
+            &pcs_param,
+            &ws,
+            &ws,
+            &id_perms,
+            &mut transcript,
+        )?;
+        let mut transcript = <PolyIOP<Fr> as PermutationCheck<Bls12_381, Kzg>>::init_transcript();
+        transcript.append_message(b"testing", b"initializing transcript for testing")?;
+        let (trait_proof, trait_prod_x, trait_frac_poly) =
+            <PolyIOP<Fr> as PermutationCheck<Bls12_381, Kzg>>::prove(
+                &pcs_param,
+                &ws,
+                &ws,
+                &id_perms,
+                &mut transcript,
+            )?;
+        assert_eq!(proof.zero_check_proof, trait_proof.zero_check_proof);
+        assert_eq!(proof.prod_x_comm, trait_proof.prod_x_comm);
+        assert_eq!(proof.frac_comm, trait_proof.frac_comm);
+        assert_eq!(prod_x, trait_prod_x);
+        assert_eq!(frac_poly, trait_frac_poly);
+
+        let mut transcript = PermCheckProtocol::<Bls12_381, Kzg>::init_transcript();
+        transcript.append_message(b"testing", b"initializing transcript for testing")?;
+        let subclaim =
+            PermCheckProtocol::<Bls12_381, Kzg>::verify(&proof, &aux_info, &mut transcript)?;
+        let mut transcript = <PolyIOP<Fr> as PermutationCheck<Bls12_381, Kzg>>::init_transcript();
+        transcript.append_message(b"testing", b"initializing transcript for testing")?;
+        let trait_subclaim = <PolyIOP<Fr> as PermutationCheck<Bls12_381, Kzg>>::verify(
+            &proof,
+            &aux_info,
+            &mut transcript,
+        )?;
+        assert_eq!(subclaim.challenges, trait_subclaim.challenges);
+        assert_eq!(
+            subclaim.product_check_sub_claim,
+            trait_subclaim.product_check_sub_claim
+        );
+        let (_, point, eval) = &subclaim.product_check_sub_claim.required_openings[0];
+        assert_eq!(prod_x.evaluate(point), Some(*eval));
+
+        // bad path: a random permutation
+        let perms = random_permutation_mles(nv, 2, &mut rng);
+        let mut transcript = PermCheckProtocol::<Bls12_381, Kzg>::init_transcript();
+        transcript.append_message(b"testing", b"initializing transcript for testing")?;
+        let (proof, prod_x, _) = PermCheckProtocol::<Bls12_381, Kzg>::prove(
+            &pcs_param,
+            &ws,
+            &ws,
+            &perms,
+            &mut transcript,
+        )?;
+        let mut transcript = PermCheckProtocol::<Bls12_381, Kzg>::init_transcript();
+        transcript.append_message(b"testing", b"initializing transcript for testing")?;
+        let subclaim =
+            PermCheckProtocol::<Bls12_381, Kzg>::verify(&proof, &aux_info, &mut transcript)?;
+        let (_, point, eval) = &subclaim.product_check_sub_claim.required_openings[0];
+        assert_ne!(prod_x.evaluate(point), Some(*eval));
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! Soundness error of the sum checks, for choosing the field size.
+//!
+//! A sum check over `num_vars` variables on a polynomial of degree at most
+//! `max_degree` in each variable is sound except with probability
+//! `num_vars * max_degree / |F|`: a cheating prover has to send a wrong
+//! univariate polynomial in some round, which agrees with the correct one
+//! on at most `max_degree` points. A proof with several sum checks is sound
+//! except with the sum of their errors.
+
+use ark_std::log2;
+
+/// The soundness error of `num_sum_checks` sum checks over `num_vars`
+/// variables, on polynomials of degree at most `max_degree` in each
+/// variable, in a field of at least `2^field_bits` elements, i.e.,
+/// `num_sum_checks * num_vars * max_degree / 2^field_bits`.
+///
+/// The degree of a virtual polynomial is the number of multilinear
+/// extensions in its largest product, plus one for the `eq(x, r)` factor of
+/// a zero check.
+pub fn soundness_error(
+    num_vars: usize,
+    num_sum_checks: usize,
+    field_bits: usize,
+    max_degree: usize,
+) -> f64 {
+    let num_bad_points = num_sum_checks
+        .saturating_mul(num_vars)
+        .saturating_mul(max_degree);
+    num_bad_points as f64 * 2f64.powi(-(field_bits as i32))
+}
+
+/// The smallest `field_bits` such that the [`soundness_error`] of
+/// `num_sum_checks` sum checks over `num_vars` variables, on polynomials of
+/// degree at most `max_degree`, is at most `2^-security_bits`.
+pub fn min_field_bits_for_security(
+    security_bits: u32,
+    num_vars: usize,
+    max_degree: usize,
+    num_sum_checks: usize,
+) -> usize {
+    let num_bad_points = num_sum_checks
+        .saturating_mul(num_vars)
+        .saturating_mul(max_degree);
+    (security_bits + log2(num_bad_points)) as usize
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_bls12_381::Fr;
+    use ark_ff::PrimeField;
+
+    #[test]
+    fn test_soundness_error() {
+        // a zero check of a vanilla gate, of degree 3 + 1, over 2^20 rows
+        assert_eq!(soundness_error(20, 1, 254, 4), 80.0 * 2f64.powi(-254));
+        assert_eq!(soundness_error(16, 2, 134, 2), 2f64.powi(-128));
+        assert_eq!(soundness_error(20, 0, 64, 4), 0.0);
+        // more sum checks, variables or degree are less sound
+        assert!(soundness_error(20, 2, 128, 4) > soundness_error(20, 1, 128, 4));
+        assert!(soundness_error(21, 1, 128, 4) > soundness_error(20, 1, 128, 4));
+        assert!(soundness_error(20, 1, 128, 5) > soundness_error(20, 1, 128, 4));
+    }
+
+    #[test]
+    fn test_min_field_bits_for_security() {
+        // (security bits, num_vars, max_degree, num_sum_checks, field bits)
+        let cases = [
+            // one zero check of a vanilla gate over 2^20 rows
+            (128, 20, 4, 1, 135),
+            // the gate, permutation, lookup and opening sum checks
+            (128, 20, 4, 4, 137),
+            // the permutation zero check of 5 columns over 2^30 rows
+            (100, 30, 6, 3, 110),
+            (80, 10, 32, 2, 90),
+            // a power of two number of bad points is met exactly
+            (128, 16, 2, 2, 134),
+            (128, 1, 1, 1, 128),
+        ];
+        for (security_bits, num_vars, max_degree, num_sum_checks, field_bits) in cases {
+            assert_eq!(
+                min_field_bits_for_security(security_bits, num_vars, max_degree, num_sum_checks),
+                field_bits
+            );
+            let target = 2f64.powi(-(security_bits as i32));
+            assert!(soundness_error(num_vars, num_sum_checks, field_bits, max_degree) <= target);
+            assert!(soundness_error(num_vars, num_sum_checks, field_bits - 1, max_degree) > target);
+        }
+
+        // the scalar field of BLS12-381 has more than 2^254 elements, which
+        // is plenty for 128 bits of security
+        let field_bits = Fr::MODULUS_BIT_SIZE as usize - 1;
+        assert!(min_field_bits_for_security(128, 32, 32, 8) <= field_bits);
+    }
+}
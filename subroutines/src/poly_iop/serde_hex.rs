@@ -0,0 +1,136 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! `serde` for the sum check proofs, with the `serde` feature.
+//!
+//! A field element is the lowercase hex string of its compressed canonical
+//! serialization, that is of its little-endian bytes. An `IOPProof` is
+//! represented as
+//!
+//! ```json
+//! {
+//!   "point": ["<hex>", ...],
+//!   "proofs": [{ "evaluations": ["<hex>", ...] }, ...]
+//! }
+//! ```
+//!
+//! with one coordinate of the point and one prover message per round.
+//! Deserialization rejects a string that is not hex, or whose bytes are not
+//! exactly the canonical serialization of a field element.
+
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+/// Serialize field elements as hex strings.
+#[allow(clippy::ptr_arg)]
+pub(crate) fn serialize<F, S>(elements: &Vec<F>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    F: CanonicalSerialize,
+    S: Serializer,
+{
+    serializer.collect_seq(elements.iter().map(to_hex))
+}
+
+/// Deserialize field elements from hex strings.
+pub(crate) fn deserialize<'de, F, D>(deserializer: D) -> Result<Vec<F>, D::Error>
+where
+    F: CanonicalDeserialize,
+    D: Deserializer<'de>,
+{
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|s| from_hex(s).map_err(D::Error::custom))
+        .collect()
+}
+
+fn to_hex<F: CanonicalSerialize>(element: &F) -> String {
+    let mut bytes = Vec::with_capacity(element.compressed_size());
+    element
+        .serialize_compressed(&mut bytes)
+        .expect("serializing to a vector does not fail");
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex<F: CanonicalDeserialize>(s: &str) -> Result<F, String> {
+    if !s.len().is_multiple_of(2) || !s.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("invalid hex string {:?}", s));
+    }
+    let bytes = (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16))
+        .collect::<Result<Vec<u8>, _>>()
+        .map_err(|e| e.to_string())?;
+    let mut reader = bytes.as_slice();
+    let element = F::deserialize_compressed(&mut reader).map_err(|e| e.to_string())?;
+    if !reader.is_empty() {
+        return Err(format!("{} trailing bytes in {:?}", reader.len(), s));
+    }
+    Ok(element)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::poly_iop::{
+        prelude::{RandomMaskPolynomial, ZkSumCheck},
+        structs::{IOPProof, IOPProverMessage},
+        PolyIOP,
+    };
+    use arithmetic::VirtualPolynomial;
+    use ark_secp256k1::Fr;
+    use ark_std::{test_rng, One};
+
+    #[test]
+    fn test_proof_json_round_trip() {
+        let mut rng = test_rng();
+        let nv = 4;
+        let (poly, sum) = VirtualPolynomial::<Fr>::rand(nv, (2, 3), 2, &mut rng).unwrap();
+        let (mask, mask_sum) = RandomMaskPolynomial::<Fr>::rand(nv, 3, &mut rng);
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr>>::init_transcript();
+        let (proof, _) = <PolyIOP<Fr> as ZkSumCheck<Fr>>::prove_bound(
+            &poly,
+            &mask,
+            &mask.evaluations,
+            &mut transcript,
+        )
+        .unwrap();
+
+        let json = serde_json::to_string(&proof).unwrap();
+        let deserialized: IOPProof<Fr> = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, proof);
+
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr>>::init_transcript();
+        <PolyIOP<Fr> as ZkSumCheck<Fr>>::verify_bound(
+            sum,
+            mask_sum,
+            &deserialized,
+            &mask.evaluations,
+            &poly.aux_info,
+            &mut transcript,
+            mask.evaluations[0].len() - 1,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_field_element_json() {
+        let message = IOPProverMessage {
+            evaluations: vec![Fr::one(), -Fr::one()],
+        };
+        let json = serde_json::to_string(&message).unwrap();
+        let one = format!("01{}", "00".repeat(31));
+        assert!(json.starts_with(&format!("{{\"evaluations\":[\"{}\",", one)));
+        assert_eq!(
+            serde_json::from_str::<IOPProverMessage<Fr>>(&json).unwrap(),
+            message
+        );
+
+        // bad path: not hex, odd length, trailing bytes, not canonical
+        for bad in ["zz", "0", &format!("{}00", one), &"ff".repeat(32)] {
+            let json = format!("{{\"evaluations\":[\"{}\"]}}", bad);
+            assert!(serde_json::from_str::<IOPProverMessage<Fr>>(&json).is_err());
+        }
+    }
+}
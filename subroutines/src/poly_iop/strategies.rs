@@ -0,0 +1,200 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! Proptest strategies for the test suites of the PIOPs.
+//!
+//! A strategy generates the shape of a virtual polynomial: its number of
+//! variables, the number of multiplicands of each product, and a seed from
+//! which the evaluations are derived. Proptest shrinks the shape toward
+//! fewer variables and fewer, smaller products, so that a failure is
+//! reported on a counterexample small enough to debug.
+//!
+//! The runner is seeded deterministically and does not persist failures: a
+//! failure in CI reproduces with the same counterexample on any machine.
+
+use arithmetic::{random_zero_mle_list, VirtualPolynomial};
+use ark_ff::PrimeField;
+use ark_std::{
+    fmt::Debug,
+    rand::{Rng, SeedableRng},
+};
+use proptest::{
+    collection::vec,
+    prelude::*,
+    test_runner::{Config, RngAlgorithm, TestRng, TestRunner},
+};
+use rand_chacha::ChaCha20Rng;
+
+/// Number of cases of a randomized test.
+const NUM_CASES: u32 = 16;
+
+/// The shape of a virtual polynomial.
+#[derive(Clone, Debug)]
+pub(crate) struct PolyShape {
+    pub(crate) num_vars: usize,
+    /// number of multiplicands of each product
+    pub(crate) degrees: Vec<usize>,
+    pub(crate) seed: u64,
+}
+
+/// Shapes with 1 to `max_num_vars` variables and 1 to `max_products`
+/// products of 1 to `max_degree` multiplicands.
+pub(crate) fn poly_shape(
+    max_num_vars: usize,
+    max_degree: usize,
+    max_products: usize,
+) -> impl Strategy<Value = PolyShape> {
+    (
+        1..=max_num_vars,
+        vec(1..=max_degree, 1..=max_products),
+        any::<u64>(),
+    )
+        .prop_map(|(num_vars, degrees, seed)| PolyShape {
+            num_vars,
+            degrees,
+            seed,
+        })
+}
+
+impl PolyShape {
+    /// The maximal number of multiplicands of a product.
+    pub(crate) fn max_degree(&self) -> usize {
+        self.degrees.iter().copied().max().unwrap_or(0)
+    }
+
+    /// A rng derived from the seed of the shape.
+    pub(crate) fn rng(&self) -> ChaCha20Rng {
+        ChaCha20Rng::seed_from_u64(self.seed)
+    }
+
+    /// A virtual polynomial of this shape with random evaluations, and its
+    /// sum.
+    pub(crate) fn polynomial<F: PrimeField>(&self) -> (VirtualPolynomial<F>, F) {
+        let mut rng = self.rng();
+        let products: Vec<(F, Vec<Vec<F>>)> = self
+            .degrees
+            .iter()
+            .map(|&degree| {
+                let multiplicands = (0..degree)
+                    .map(|_| (0..1 << self.num_vars).map(|_| F::rand(&mut rng)).collect())
+                    .collect();
+                (F::rand(&mut rng), multiplicands)
+            })
+            .collect();
+        VirtualPolynomial::from_products(self.num_vars, &products)
+            .expect("the shape has valid products")
+    }
+
+    /// A virtual polynomial of this shape that is zero over the boolean
+    /// hypercube.
+    pub(crate) fn zero_polynomial<F: PrimeField>(&self) -> VirtualPolynomial<F> {
+        let mut rng = self.rng();
+        let mut poly = VirtualPolynomial::new(self.num_vars);
+        for &degree in self.degrees.iter() {
+            let product = random_zero_mle_list(self.num_vars, degree, &mut rng);
+            poly.add_mle_list(product, F::from(rng.gen::<u64>()))
+                .expect("the shape has valid products");
+        }
+        poly
+    }
+}
+
+/// A runner with a fixed seed.
+pub(crate) fn runner() -> TestRunner {
+    TestRunner::new_with_rng(
+        Config {
+            cases: NUM_CASES,
+            failure_persistence: None,
+            ..Config::default()
+        },
+        TestRng::deterministic_rng(RngAlgorithm::ChaCha),
+    )
+}
+
+/// Run `test` on the values of `strategy`, and panic with the minimal
+/// failing value if it fails.
+pub(crate) fn check<S, T>(strategy: S, test: T)
+where
+    S: Strategy,
+    S::Value: Debug,
+    T: Fn(S::Value) -> Result<(), TestCaseError>,
+{
+    if let Err(e) = runner().run(&strategy, test) {
+        panic!("{}", e);
+    }
+}
+
+/// Turn an error of the code under test into a test failure.
+pub(crate) fn fail<E: Debug>(e: E) -> TestCaseError {
+    TestCaseError::fail(format!("{:?}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poly_iop::{prelude::SumCheck, PolyIOP};
+    use ark_bls12_381::Fr;
+    use proptest::test_runner::TestError;
+
+    #[test]
+    fn test_shapes() {
+        check(poly_shape(4, 3, 3), |shape| {
+            let (poly, sum) = shape.polynomial::<Fr>();
+            prop_assert_eq!(poly.aux_info.num_variables, shape.num_vars);
+            prop_assert_eq!(poly.aux_info.max_degree, shape.max_degree());
+            prop_assert_eq!(poly.products.len(), shape.degrees.len());
+            prop_assert_eq!(shape.polynomial::<Fr>().1, sum);
+
+            let zero_poly = shape.zero_polynomial::<Fr>();
+            prop_assert_eq!(zero_poly.aux_info.max_degree, shape.max_degree());
+            for i in 0..1 << shape.num_vars {
+                let point: Vec<Fr> = (0..shape.num_vars)
+                    .map(|j| Fr::from(((i >> j) & 1) as u64))
+                    .collect();
+                prop_assert_eq!(zero_poly.evaluate(&point).map_err(fail)?, Fr::from(0u64));
+            }
+            Ok(())
+        });
+    }
+
+    /// A sum check against a sum computed with an injected bug shrinks to a
+    /// small counterexample.
+    #[test]
+    fn test_shrinking() {
+        // the bug: the last product is dropped from the sum when there are
+        // several products of degree at least 2
+        let buggy_sum = |shape: &PolyShape| {
+            let mut shape = shape.clone();
+            if shape.degrees.len() >= 2 && shape.max_degree() >= 2 {
+                shape.degrees.pop();
+            }
+            shape.polynomial::<Fr>().1
+        };
+        let result = runner().run(&poly_shape(10, 6, 4), |shape| {
+            let (poly, _) = shape.polynomial::<Fr>();
+            let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+            let proof =
+                <PolyIOP<Fr> as SumCheck<Fr>>::prove(&poly, &mut transcript).map_err(fail)?;
+            let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+            <PolyIOP<Fr> as SumCheck<Fr>>::verify(
+                buggy_sum(&shape),
+                &proof,
+                &poly.aux_info,
+                &mut transcript,
+            )
+            .map_err(fail)?;
+            Ok(())
+        });
+        match result {
+            Err(TestError::Fail(_, shape)) => {
+                assert!(shape.num_vars <= 3, "did not shrink: {:?}", shape);
+                assert_eq!(shape.degrees.len(), 2);
+                assert_eq!(shape.max_degree(), 2);
+            },
+            result => panic!("the bug was not detected: {:?}", result),
+        }
+    }
+}
@@ -6,28 +6,54 @@
 
 //! This module defines structs that are shared by all sub protocols.
 
+use crate::poly_iop::errors::PolyIOPErrors;
 use arithmetic::VirtualPolynomial;
 use ark_ff::PrimeField;
-use ark_serialize::CanonicalSerialize;
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, Read, SerializationError, Valid, Validate,
+    Write,
+};
 
 /// An IOP proof is a collections of
 /// - messages from prover to verifier at each round through the interactive
 ///   protocol.
 /// - a point that is generated by the transcript for evaluation
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
+///
+/// With the `serde` feature, it implements `serde::Serialize` and
+/// `serde::Deserialize`, see the `serde_hex` module for its representation.
+#[derive(Clone, Debug, Default, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound = "")
+)]
 pub struct IOPProof<F: PrimeField> {
+    #[cfg_attr(
+        any(test, feature = "serde"),
+        serde(with = "crate::poly_iop::serde_hex")
+    )]
     pub point: Vec<F>,
     pub proofs: Vec<IOPProverMessage<F>>,
 }
 
 /// A message from the prover to the verifier at a given round
 /// is a list of evaluations.
-#[derive(Clone, Debug, Default, PartialEq, Eq, CanonicalSerialize)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound = "")
+)]
 pub struct IOPProverMessage<F: PrimeField> {
-    pub(crate) evaluations: Vec<F>,
+    #[cfg_attr(
+        any(test, feature = "serde"),
+        serde(with = "crate::poly_iop::serde_hex")
+    )]
+    pub evaluations: Vec<F>,
 }
 
 /// Prover State of a PolyIOP.
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
 pub struct IOPProverState<F: PrimeField> {
     /// sampled randomness given by the verifier
     pub challenges: Vec<F>,
@@ -41,10 +67,14 @@ pub struct IOPProverState<F: PrimeField> {
 }
 
 /// Prover State of a PolyIOP
+#[derive(Clone, Debug)]
 pub struct IOPVerifierState<F: PrimeField> {
     pub(crate) round: usize,
     pub(crate) num_vars: usize,
     pub(crate) max_degree: usize,
+    /// the degree of each round, empty if every round has degree
+    /// `max_degree`
+    pub(crate) per_variable_degree: Vec<usize>,
     pub(crate) finished: bool,
     /// a list storing the univariate polynomial in evaluation form sent by the
     /// prover at each round
@@ -53,11 +83,126 @@ pub struct IOPVerifierState<F: PrimeField> {
     pub(crate) challenges: Vec<F>,
 }
 
+/// A round of a sum check, as seen by the verifier.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RoundTrace<F: PrimeField> {
+    /// the prover message absorbed in this round
+    pub message: Vec<F>,
+    /// the challenge sampled in this round
+    pub challenge: F,
+    /// the claimed sum of the next round, i.e. the message evaluated at the
+    /// challenge
+    pub claimed_sum: F,
+}
+
+/// The claimed sums derived by a sum check verifier, round by round.
+///
+/// It is attached to `PolyIOPErrors::InconsistentRound` when a round fails,
+/// in which case the last round is the failing one, and is returned by the
+/// traced verifiers on success.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VerifierTrace<F: PrimeField> {
+    /// the asserted sum, i.e. the claimed sum of the first round
+    pub asserted_sum: F,
+    /// the rounds, in order
+    pub rounds: Vec<RoundTrace<F>>,
+}
+
+impl<F: PrimeField> VerifierTrace<F> {
+    /// The claimed sums `s_0, s_1, ...`: the asserted sum followed by the
+    /// claimed sum derived in each round.
+    pub fn claimed_sums(&self) -> Vec<F> {
+        ark_std::iter::once(self.asserted_sum)
+            .chain(self.rounds.iter().map(|round| round.claimed_sum))
+            .collect()
+    }
+
+    /// The last claimed sum, which is the expected evaluation of the
+    /// subclaim after a successful verification.
+    pub fn final_sum(&self) -> F {
+        self.rounds
+            .last()
+            .map_or(self.asserted_sum, |round| round.claimed_sum)
+    }
+}
+
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct RandomMaskPolynomial<F: PrimeField> {
     // Constant term of random mask polynomial
     pub const_term: F,
     // For each uni-polynomial g_i, `evaluations[i][j]` is g_i(j),
     // j\in \{0, 1, \cdots, d\}
-    pub evaluations: Vec<Vec<F>>
+    pub evaluations: Vec<Vec<F>>,
+    // Random tag of the mask, absorbed into the transcript by `prove_zk`
+    pub tag: [u8; 32],
+}
+
+// The mask is serialized as its constant term, its evaluations and its tag,
+// so that a prover can checkpoint it.
+impl<F: PrimeField> CanonicalSerialize for RandomMaskPolynomial<F> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.const_term.serialize_with_mode(&mut writer, compress)?;
+        self.evaluations
+            .serialize_with_mode(&mut writer, compress)?;
+        writer.write_all(&self.tag)?;
+        Ok(())
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.const_term.serialized_size(compress)
+            + self.evaluations.serialized_size(compress)
+            + self.tag.len()
+    }
+}
+
+impl<F: PrimeField> Valid for RandomMaskPolynomial<F> {
+    /// All the univariate polynomials have the same degree.
+    fn check(&self) -> Result<(), SerializationError> {
+        match self.evaluations.first() {
+            Some(first)
+                if first.is_empty()
+                    || self
+                        .evaluations
+                        .iter()
+                        .any(|evals| evals.len() != first.len()) =>
+            {
+                Err(SerializationError::InvalidData)
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<F: PrimeField> CanonicalDeserialize for RandomMaskPolynomial<F> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let const_term = F::deserialize_with_mode(&mut reader, compress, validate)?;
+        let evaluations = Vec::deserialize_with_mode(&mut reader, compress, validate)?;
+        let mut tag = [0u8; 32];
+        reader.read_exact(&mut tag)?;
+        let mask = Self {
+            const_term,
+            evaluations,
+            tag,
+        };
+        if validate == Validate::Yes {
+            mask.check()?;
+        }
+        Ok(mask)
+    }
+}
+
+impl<F: PrimeField> RandomMaskPolynomial<F> {
+    /// Deserialize a mask from its compressed canonical serialization,
+    /// checking that all its univariate polynomials have the same degree.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PolyIOPErrors> {
+        Ok(Self::deserialize_compressed(bytes)?)
+    }
 }
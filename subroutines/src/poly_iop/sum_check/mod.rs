@@ -8,12 +8,13 @@
 
 use crate::poly_iop::{
     errors::PolyIOPErrors,
-    structs::{IOPProof, IOPProverState, IOPVerifierState},
+    structs::{IOPProof, IOPProverMessage, IOPProverState, IOPVerifierState, VerifierTrace},
     PolyIOP,
 };
 use arithmetic::{VPAuxInfo, VirtualPolynomial};
 use ark_ff::PrimeField;
 use ark_poly::DenseMultilinearExtension;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::{end_timer, start_timer};
 use std::{fmt::Debug, sync::Arc};
 use transcript::IOPTranscript;
@@ -21,6 +22,103 @@ use transcript::IOPTranscript;
 mod prover;
 mod verifier;
 
+pub(crate) use verifier::interpolate_uni_poly;
+
+/// The maximum number of variables of a polynomial the sum check, and every
+/// PolyIOP built on it, accepts. Defaults to 32, and can be overridden by
+/// setting `HYPERPLONK_MAX_NUM_VARIABLES` at build time.
+pub const MAX_NUM_VARIABLES: usize = parse_limit(option_env!("HYPERPLONK_MAX_NUM_VARIABLES"), 32);
+
+/// The maximum degree of a polynomial the sum check, and every PolyIOP built
+/// on it, accepts. Defaults to 128, and can be overridden by setting
+/// `HYPERPLONK_MAX_DEGREE` at build time.
+pub const MAX_DEGREE: usize = parse_limit(option_env!("HYPERPLONK_MAX_DEGREE"), 128);
+
+const fn parse_limit(value: Option<&str>, default: usize) -> usize {
+    let bytes = match value {
+        Some(value) => value.as_bytes(),
+        None => return default,
+    };
+    assert!(!bytes.is_empty(), "limit must be a decimal number");
+    let mut res = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        assert!(bytes[i].is_ascii_digit(), "limit must be a decimal number");
+        res = res * 10 + (bytes[i] - b'0') as usize;
+        i += 1;
+    }
+    res
+}
+
+/// Check that the number of variables is between 1 and [`MAX_NUM_VARIABLES`].
+pub fn check_num_variables(num_variables: usize) -> Result<(), PolyIOPErrors> {
+    if num_variables == 0 || num_variables > MAX_NUM_VARIABLES {
+        return Err(PolyIOPErrors::InvalidNumVariables(
+            num_variables,
+            MAX_NUM_VARIABLES,
+        ));
+    }
+    Ok(())
+}
+
+/// Check that the auxiliary information describes a polynomial within the
+/// bounds of the sum check, i.e., with between 1 and [`MAX_NUM_VARIABLES`]
+/// variables and a degree between 1 and [`MAX_DEGREE`].
+pub fn check_aux_info<F: PrimeField>(aux_info: &VPAuxInfo<F>) -> Result<(), PolyIOPErrors> {
+    check_num_variables(aux_info.num_variables)?;
+    if aux_info.max_degree == 0 || aux_info.max_degree > MAX_DEGREE {
+        return Err(PolyIOPErrors::InvalidDegree(
+            aux_info.max_degree,
+            MAX_DEGREE,
+        ));
+    }
+    if !aux_info.per_variable_degree.is_empty() {
+        if aux_info.per_variable_degree.len() != aux_info.num_variables {
+            return Err(PolyIOPErrors::InvalidParameters(format!(
+                "{} variable degrees for {} variables",
+                aux_info.per_variable_degree.len(),
+                aux_info.num_variables
+            )));
+        }
+        if let Some(&degree) = aux_info
+            .per_variable_degree
+            .iter()
+            .find(|&&degree| degree == 0 || degree > aux_info.max_degree)
+        {
+            return Err(PolyIOPErrors::InvalidDegree(degree, aux_info.max_degree));
+        }
+    }
+    Ok(())
+}
+
+/// Check that `proof` has the shape of a sum check proof for a polynomial
+/// described by `aux_info`: one message per variable, each with
+/// `aux_info.round_degree(round) + 1` evaluations.
+pub fn check_proof_shape<F: PrimeField>(
+    proof: &IOPProof<F>,
+    aux_info: &VPAuxInfo<F>,
+) -> Result<(), PolyIOPErrors> {
+    if proof.proofs.len() != aux_info.num_variables {
+        return Err(PolyIOPErrors::InvalidProof(format!(
+            "incorrect number of rounds: {} vs {}",
+            proof.proofs.len(),
+            aux_info.num_variables
+        )));
+    }
+    if let Some((round, msg)) = proof
+        .proofs
+        .iter()
+        .enumerate()
+        .find(|(round, msg)| msg.evaluations.len() != aux_info.round_degree(*round) + 1)
+    {
+        return Err(PolyIOPErrors::degree_mismatch(
+            aux_info.round_degree(round),
+            msg.evaluations.len().saturating_sub(1),
+        ));
+    }
+    Ok(())
+}
+
 /// Trait for doing sum check protocols.
 pub trait SumCheck<F: PrimeField> {
     type VirtualPolynomial;
@@ -57,6 +155,19 @@ pub trait SumCheck<F: PrimeField> {
         aux_info: &Self::VPAuxInfo,
         transcript: &mut Self::Transcript,
     ) -> Result<Self::SumCheckSubClaim, PolyIOPErrors>;
+
+    /// Same as `verify`, and also return the claimed sum derived in each
+    /// round.
+    ///
+    /// If a round is not consistent with its claimed sum, the trace up to
+    /// that round is attached to the error, see
+    /// `PolyIOPErrors::verifier_trace`.
+    fn verify_traced(
+        sum: F,
+        proof: &Self::SumCheckProof,
+        aux_info: &Self::VPAuxInfo,
+        transcript: &mut Self::Transcript,
+    ) -> Result<(Self::SumCheckSubClaim, VerifierTrace<F>), PolyIOPErrors>;
 }
 
 /// Trait for sum check protocol prover side APIs.
@@ -90,7 +201,9 @@ pub trait SumCheckVerifier<F: PrimeField> {
     type SumCheckSubClaim;
 
     /// Initialize the verifier's state.
-    fn verifier_init(index_info: &Self::VPAuxInfo) -> Self;
+    fn verifier_init(index_info: &Self::VPAuxInfo) -> Result<Self, PolyIOPErrors>
+    where
+        Self: Sized;
 
     /// Run verifier for the current round, given a prover message.
     ///
@@ -129,6 +242,186 @@ pub struct SumCheckSubClaim<F: PrimeField> {
     pub expected_evaluation: F,
 }
 
+/// A sum check prover that is driven one round at a time.
+///
+/// `SumCheck::prove` is equivalent to calling [`SumCheckRounds::new`],
+/// then [`SumCheckRounds::prove_round`] until the prover is finished, and
+/// finally [`SumCheckRounds::finish`]; the transcript must not be touched in
+/// between. This allows a caller to interleave the rounds with other work.
+///
+/// The rounds can be serialized between two rounds, e.g. to resume a long
+/// proof in another process, with the transcript restored alongside.
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct SumCheckRounds<F: PrimeField> {
+    /// the underlying sum check prover state
+    prover_state: IOPProverState<F>,
+    /// the challenge of the previous round
+    challenge: Option<F>,
+    /// the prover messages generated so far
+    prover_msgs: Vec<IOPProverMessage<F>>,
+}
+
+impl<F: PrimeField> SumCheckRounds<F> {
+    /// Initialize the prover for the sum of `poly` over {0,1}^`num_vars`,
+    /// and append the auxiliary information of `poly` to the transcript.
+    pub fn new(
+        poly: &VirtualPolynomial<F>,
+        transcript: &mut IOPTranscript<F>,
+    ) -> Result<Self, PolyIOPErrors> {
+        transcript.append_message(b"aux info", &poly.aux_info.transcript_bytes())?;
+        let prover_state = IOPProverState::prover_init(poly)?;
+
+        Ok(Self {
+            prover_state,
+            challenge: None,
+            prover_msgs: Vec::with_capacity(poly.aux_info.num_variables),
+        })
+    }
+
+    /// Total number of rounds of this sum check.
+    pub fn num_rounds(&self) -> usize {
+        self.prover_state.poly.aux_info.num_variables
+    }
+
+    /// Whether all the rounds have been proven.
+    pub fn is_finished(&self) -> bool {
+        self.prover_msgs.len() == self.num_rounds()
+    }
+
+    /// Prove the next round: generate the prover message, append it to the
+    /// transcript and sample the challenge for the round.
+    pub fn prove_round(&mut self, transcript: &mut IOPTranscript<F>) -> Result<(), PolyIOPErrors> {
+        if self.is_finished() {
+            return Err(PolyIOPErrors::InvalidProver(
+                "all the rounds have been proven".to_string(),
+            ));
+        }
+        let prover_msg =
+            IOPProverState::prove_round_and_update_state(&mut self.prover_state, &self.challenge)?;
+        transcript.append_serializable_element(b"prover msg", &prover_msg)?;
+        self.prover_msgs.push(prover_msg);
+        self.challenge = Some(transcript.get_and_append_challenge(b"Internal round")?);
+        Ok(())
+    }
+
+    /// Output the sum check proof. Must be called after the last round.
+    pub fn finish(mut self) -> IOPProof<F> {
+        // pushing the last challenge point to the state
+        if let Some(p) = self.challenge {
+            self.prover_state.challenges.push(p)
+        };
+
+        IOPProof {
+            point: self.prover_state.challenges,
+            proofs: self.prover_msgs,
+        }
+    }
+}
+
+/// A sum check verifier that is driven one round at a time.
+///
+/// Unlike `SumCheck::verify`, which receives the whole proof and defers the
+/// checks to the end, each round is checked as soon as its prover message is
+/// received, and only the challenges are kept. The transcript operations are
+/// the same as in `SumCheck::verify`, and so is the resulting subclaim.
+pub struct SumCheckVerifierRounds<F: PrimeField> {
+    /// the number of rounds
+    num_vars: usize,
+    /// the degree of the round polynomials
+    max_degree: usize,
+    /// the degree of each round, empty if every round has degree
+    /// `max_degree`
+    per_variable_degree: Vec<usize>,
+    /// the claimed sum of the current round
+    expected: F,
+    /// the challenges sampled so far
+    challenges: Vec<F>,
+}
+
+impl<F: PrimeField> SumCheckVerifierRounds<F> {
+    /// Initialize the verifier for `claimed_sum`, the sum of a polynomial
+    /// described by `aux_info`, and append `aux_info` to the transcript.
+    pub fn new(
+        claimed_sum: F,
+        aux_info: &VPAuxInfo<F>,
+        transcript: &mut IOPTranscript<F>,
+    ) -> Result<Self, PolyIOPErrors> {
+        check_aux_info(aux_info)?;
+        transcript.append_message(b"aux info", &aux_info.transcript_bytes())?;
+
+        Ok(Self {
+            num_vars: aux_info.num_variables,
+            max_degree: aux_info.max_degree,
+            per_variable_degree: aux_info.per_variable_degree.clone(),
+            expected: claimed_sum,
+            challenges: Vec::with_capacity(aux_info.num_variables),
+        })
+    }
+
+    /// Number of evaluations in the next prover message.
+    pub fn message_len(&self) -> usize {
+        self.round_degree() + 1
+    }
+
+    /// The degree of the next round polynomial.
+    fn round_degree(&self) -> usize {
+        self.per_variable_degree
+            .get(self.challenges.len())
+            .copied()
+            .unwrap_or(self.max_degree)
+    }
+
+    /// Whether all the rounds have been verified.
+    pub fn is_finished(&self) -> bool {
+        self.challenges.len() == self.num_vars
+    }
+
+    /// Verify the next round: check `prover_msg` against the claim of the
+    /// round, append it to the transcript and sample the challenge.
+    pub fn verify_round(
+        &mut self,
+        prover_msg: &IOPProverMessage<F>,
+        transcript: &mut IOPTranscript<F>,
+    ) -> Result<F, PolyIOPErrors> {
+        if self.is_finished() {
+            return Err(PolyIOPErrors::InvalidVerifier(
+                "Incorrect verifier state: Verifier is already finished.".to_string(),
+            ));
+        }
+        let evaluations = &prover_msg.evaluations;
+        if evaluations.len() != self.message_len() {
+            return Err(PolyIOPErrors::degree_mismatch(
+                self.round_degree(),
+                evaluations.len().saturating_sub(1),
+            ));
+        }
+        if evaluations[0] + evaluations[1] != self.expected {
+            return Err(PolyIOPErrors::InvalidProof(
+                "Prover message is not consistent with the claim.".to_string(),
+            ));
+        }
+
+        transcript.append_serializable_element(b"prover msg", prover_msg)?;
+        let challenge = transcript.get_and_append_challenge(b"Internal round")?;
+        self.expected = verifier::interpolate_uni_poly(evaluations, challenge)?;
+        self.challenges.push(challenge);
+        Ok(challenge)
+    }
+
+    /// Output the subclaim. Must be called after the last round.
+    pub fn finish(self) -> Result<SumCheckSubClaim<F>, PolyIOPErrors> {
+        if !self.is_finished() {
+            return Err(PolyIOPErrors::InvalidVerifier(
+                "Incorrect verifier state: Verifier has not finished.".to_string(),
+            ));
+        }
+        Ok(SumCheckSubClaim {
+            point: self.challenges,
+            expected_evaluation: self.expected,
+        })
+    }
+}
+
 impl<F: PrimeField> SumCheck<F> for PolyIOP<F> {
     type SumCheckProof = IOPProof<F>;
     type VirtualPolynomial = VirtualPolynomial<F>;
@@ -157,28 +450,13 @@ impl<F: PrimeField> SumCheck<F> for PolyIOP<F> {
     ) -> Result<Self::SumCheckProof, PolyIOPErrors> {
         let start = start_timer!(|| "sum check prove");
 
-        transcript.append_serializable_element(b"aux info", &poly.aux_info)?;
-
-        let mut prover_state = IOPProverState::prover_init(poly)?;
-        let mut challenge = None;
-        let mut prover_msgs = Vec::with_capacity(poly.aux_info.num_variables);
-        for _ in 0..poly.aux_info.num_variables {
-            let prover_msg =
-                IOPProverState::prove_round_and_update_state(&mut prover_state, &challenge)?;
-            transcript.append_serializable_element(b"prover msg", &prover_msg)?;
-            prover_msgs.push(prover_msg);
-            challenge = Some(transcript.get_and_append_challenge(b"Internal round")?);
+        let mut rounds = SumCheckRounds::new(poly, transcript)?;
+        while !rounds.is_finished() {
+            rounds.prove_round(transcript)?;
         }
-        // pushing the last challenge point to the state
-        if let Some(p) = challenge {
-            prover_state.challenges.push(p)
-        };
 
         end_timer!(start);
-        Ok(IOPProof {
-            point: prover_state.challenges,
-            proofs: prover_msgs,
-        })
+        Ok(rounds.finish())
     }
 
     fn verify(
@@ -187,12 +465,21 @@ impl<F: PrimeField> SumCheck<F> for PolyIOP<F> {
         aux_info: &Self::VPAuxInfo,
         transcript: &mut Self::Transcript,
     ) -> Result<Self::SumCheckSubClaim, PolyIOPErrors> {
+        Self::verify_traced(claimed_sum, proof, aux_info, transcript).map(|(subclaim, _)| subclaim)
+    }
+
+    fn verify_traced(
+        claimed_sum: F,
+        proof: &Self::SumCheckProof,
+        aux_info: &Self::VPAuxInfo,
+        transcript: &mut Self::Transcript,
+    ) -> Result<(Self::SumCheckSubClaim, VerifierTrace<F>), PolyIOPErrors> {
         let start = start_timer!(|| "sum check verify");
 
-        transcript.append_serializable_element(b"aux info", aux_info)?;
-        let mut verifier_state = IOPVerifierState::verifier_init(aux_info);
-        for i in 0..aux_info.num_variables {
-            let prover_msg = proof.proofs.get(i).expect("proof is incomplete");
+        let mut verifier_state = IOPVerifierState::verifier_init(aux_info)?;
+        check_proof_shape(proof, aux_info)?;
+        transcript.append_message(b"aux info", &aux_info.transcript_bytes())?;
+        for prover_msg in proof.proofs.iter() {
             transcript.append_serializable_element(b"prover msg", prover_msg)?;
             IOPVerifierState::verify_round_and_update_state(
                 &mut verifier_state,
@@ -201,7 +488,7 @@ impl<F: PrimeField> SumCheck<F> for PolyIOP<F> {
             )?;
         }
 
-        let res = IOPVerifierState::check_and_generate_subclaim(&verifier_state, &claimed_sum);
+        let res = verifier_state.check_and_trace(&claimed_sum);
 
         end_timer!(start);
         res
@@ -212,10 +499,12 @@ impl<F: PrimeField> SumCheck<F> for PolyIOP<F> {
 mod test {
 
     use super::*;
+    use crate::poly_iop::strategies::{check, fail, poly_shape};
     use ark_bls12_381::Fr;
-    use ark_ff::UniformRand;
+    use ark_ff::{One, UniformRand};
     use ark_poly::{DenseMultilinearExtension, MultilinearExtension};
-    use ark_std::test_rng;
+    use ark_std::{rand::RngCore, test_rng};
+    use proptest::{prop_assert, prop_assert_eq};
     use std::sync::Arc;
 
     fn test_sumcheck(
@@ -254,7 +543,7 @@ mod test {
             VirtualPolynomial::<Fr>::rand(nv, num_multiplicands_range, num_products, &mut rng)?;
         let poly_info = poly.aux_info.clone();
         let mut prover_state = IOPProverState::prover_init(&poly)?;
-        let mut verifier_state = IOPVerifierState::verifier_init(&poly_info);
+        let mut verifier_state = IOPVerifierState::verifier_init(&poly_info)?;
         let mut challenge = None;
         let mut transcript = IOPTranscript::new(b"a test transcript");
         transcript
@@ -312,6 +601,430 @@ mod test {
         assert!(test_sumcheck_internal(nv, num_multiplicands_range, num_products).is_err());
     }
 
+    #[test]
+    fn test_verifier_rounds() -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        let (poly, asserted_sum) = VirtualPolynomial::<Fr>::rand(6, (2, 5), 3, &mut rng)?;
+        let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+        let proof = <PolyIOP<Fr> as SumCheck<Fr>>::prove(&poly, &mut transcript)?;
+
+        let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+        let subclaim = <PolyIOP<Fr> as SumCheck<Fr>>::verify(
+            asserted_sum,
+            &proof,
+            &poly.aux_info,
+            &mut transcript,
+        )?;
+
+        let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+        let mut rounds =
+            SumCheckVerifierRounds::new(asserted_sum, &poly.aux_info, &mut transcript)?;
+        for prover_msg in proof.proofs.iter() {
+            rounds.verify_round(prover_msg, &mut transcript)?;
+        }
+        assert_eq!(rounds.finish()?, subclaim);
+
+        // a corrupted round is rejected right away
+        let mut bad_proof = proof;
+        bad_proof.proofs[3].evaluations[0] += Fr::from(1u64);
+        let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+        let mut rounds =
+            SumCheckVerifierRounds::new(asserted_sum, &poly.aux_info, &mut transcript)?;
+        for prover_msg in bad_proof.proofs[..3].iter() {
+            rounds.verify_round(prover_msg, &mut transcript)?;
+        }
+        assert!(rounds
+            .verify_round(&bad_proof.proofs[3], &mut transcript)
+            .is_err());
+
+        // so is a message of another degree
+        let mut short_msg = bad_proof.proofs[0].clone();
+        short_msg.evaluations.pop();
+        let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+        let mut rounds =
+            SumCheckVerifierRounds::new(asserted_sum, &poly.aux_info, &mut transcript)?;
+        assert!(matches!(
+            rounds.verify_round(&short_msg, &mut transcript),
+            Err(PolyIOPErrors::SumCheckDegreeMismatch { claimed, actual })
+                if claimed == poly.aux_info.max_degree && actual == claimed - 1
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_serial_route() -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        let (poly, _) = VirtualPolynomial::<Fr>::rand(8, (2, 5), 3, &mut rng)?;
+        let prove = || {
+            let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+            <PolyIOP<Fr> as SumCheck<Fr>>::prove(&poly, &mut transcript)
+        };
+
+        // the serial route produces the same proof
+        let proof = prove()?;
+        let count = util::serial_route_count();
+        assert_eq!(util::run_serial(prove)?, proof);
+        #[cfg(feature = "parallel")]
+        assert!(util::serial_route_count() > count);
+        #[cfg(not(feature = "parallel"))]
+        let _ = count;
+        Ok(())
+    }
+
+    #[test]
+    fn test_sparse_polynomial() -> Result<(), PolyIOPErrors> {
+        use arithmetic::SparseVirtualPolynomial;
+        use ark_ff::Zero;
+
+        let mut rng = test_rng();
+        let nv = 6;
+        let (mut poly, sum) = VirtualPolynomial::<Fr>::rand(nv, (2, 4), 3, &mut rng)?;
+        let zero = Arc::new(DenseMultilinearExtension::from_evaluations_vec(
+            nv,
+            vec![Fr::zero(); 1 << nv],
+        ));
+        poly.add_mle_list([zero, poly.flattened_ml_extensions[0].clone()], Fr::one())?;
+        let sparse = VirtualPolynomial::from(&SparseVirtualPolynomial::from(&poly));
+        assert!(sparse.products.len() < poly.products.len());
+
+        // dropping the zero products does not change the proof
+        let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+        let proof = <PolyIOP<Fr> as SumCheck<Fr>>::prove(&poly, &mut transcript)?;
+        let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+        assert_eq!(
+            <PolyIOP<Fr> as SumCheck<Fr>>::prove(&sparse, &mut transcript)?,
+            proof
+        );
+
+        let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+        let subclaim =
+            <PolyIOP<Fr> as SumCheck<Fr>>::verify(sum, &proof, &sparse.aux_info, &mut transcript)?;
+        assert_eq!(
+            sparse.evaluate(&subclaim.point)?,
+            subclaim.expected_evaluation
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_transcript_checkpoint() -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        let nv = 5;
+        let (poly, sum) = VirtualPolynomial::<Fr>::rand(nv, (2, 4), 2, &mut rng)?;
+        let (other_poly, other_sum) = VirtualPolynomial::<Fr>::rand(nv, (2, 4), 2, &mut rng)?;
+
+        let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+        transcript.append_message(b"before", b"the checkpoint")?;
+        let state = transcript.checkpoint();
+        let proof = <PolyIOP<Fr> as SumCheck<Fr>>::prove(&poly, &mut transcript)?;
+        // roll back, and prove the other polynomial instead
+        transcript.restore(state.clone());
+        let other_proof = <PolyIOP<Fr> as SumCheck<Fr>>::prove(&other_poly, &mut transcript)?;
+
+        // both proofs verify from the checkpoint
+        for (poly, sum, proof) in [(&poly, sum, &proof), (&other_poly, other_sum, &other_proof)] {
+            let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+            transcript.append_message(b"before", b"the checkpoint")?;
+            let subclaim =
+                <PolyIOP<Fr> as SumCheck<Fr>>::verify(sum, proof, &poly.aux_info, &mut transcript)?;
+            assert_eq!(
+                poly.evaluate(&subclaim.point)?,
+                subclaim.expected_evaluation
+            );
+        }
+
+        // the transcript after the rollback is the one of the second proof
+        let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+        transcript.restore(state);
+        let mut verifier_transcript = transcript.clone();
+        <PolyIOP<Fr> as SumCheck<Fr>>::prove(&other_poly, &mut transcript)?;
+        <PolyIOP<Fr> as SumCheck<Fr>>::verify(
+            other_sum,
+            &other_proof,
+            &other_poly.aux_info,
+            &mut verifier_transcript,
+        )?;
+        assert_eq!(
+            transcript.get_and_append_challenge(b"next")?,
+            verifier_transcript.get_and_append_challenge(b"next")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_aux_info_bounds() -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        let (poly, _) = VirtualPolynomial::<Fr>::rand(2, (2, 3), 2, &mut rng)?;
+        let aux_info = |num_variables, max_degree| VPAuxInfo::<Fr> {
+            max_degree,
+            num_variables,
+            ..Default::default()
+        };
+
+        assert!(check_aux_info(&aux_info(1, 1)).is_ok());
+        assert!(check_aux_info(&aux_info(MAX_NUM_VARIABLES, MAX_DEGREE)).is_ok());
+        for (num_variables, max_degree) in [
+            (0, 2),
+            (MAX_NUM_VARIABLES + 1, 2),
+            (60, 2),
+            (2, 0),
+            (2, MAX_DEGREE + 1),
+        ] {
+            let bad_aux_info = aux_info(num_variables, max_degree);
+            assert!(IOPVerifierState::verifier_init(&bad_aux_info).is_err());
+
+            let mut bad_poly = poly.clone();
+            bad_poly.aux_info = bad_aux_info;
+            assert!(IOPProverState::prover_init(&bad_poly).is_err());
+        }
+        assert!(matches!(
+            check_aux_info(&aux_info(0, 2)),
+            Err(PolyIOPErrors::InvalidNumVariables(0, MAX_NUM_VARIABLES))
+        ));
+        assert!(matches!(
+            check_aux_info(&aux_info(2, MAX_DEGREE + 1)),
+            Err(PolyIOPErrors::InvalidDegree(_, MAX_DEGREE))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_verifier_reset() -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        let (other, _) = VirtualPolynomial::<Fr>::rand(6, (2, 4), 2, &mut rng)?;
+        let mut state = IOPVerifierState::verifier_init(&other.aux_info)?;
+
+        // sequential proofs, of the same shape and of another one
+        for nv in [5, 5, 3] {
+            let (poly, sum) = VirtualPolynomial::<Fr>::rand(nv, (2, 3), 2, &mut rng)?;
+            let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+            let proof = <PolyIOP<Fr> as SumCheck<Fr>>::prove(&poly, &mut transcript)?;
+            let run = |state: &mut IOPVerifierState<Fr>| -> Result<_, PolyIOPErrors> {
+                let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+                transcript.append_message(b"aux info", &poly.aux_info.transcript_bytes())?;
+                for prover_msg in proof.proofs.iter() {
+                    transcript.append_serializable_element(b"prover msg", prover_msg)?;
+                    state.verify_round_and_update_state(prover_msg, &mut transcript)?;
+                }
+                state.check_and_generate_subclaim(&sum)
+            };
+
+            state.reset(&poly.aux_info)?;
+            let reused = run(&mut state)?;
+            let fresh = run(&mut IOPVerifierState::verifier_init(&poly.aux_info)?)?;
+            assert_eq!(reused, fresh);
+            assert_eq!(poly.evaluate(&reused.point)?, reused.expected_evaluation);
+            let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+            assert_eq!(
+                <PolyIOP<Fr> as SumCheck<Fr>>::verify(
+                    sum,
+                    &proof,
+                    &poly.aux_info,
+                    &mut transcript
+                )?,
+                fresh
+            );
+        }
+
+        // the aux info is checked as by `verifier_init`
+        let bad_aux_info = VPAuxInfo::<Fr> {
+            num_variables: 0,
+            ..other.aux_info.clone()
+        };
+        assert!(state.reset(&bad_aux_info).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn zero_variables_should_not_accept_any_sum() {
+        // with no variables there are no rounds to check, so an empty proof
+        // used to be accepted for any claimed sum
+        let mut rng = test_rng();
+        let aux_info = VPAuxInfo::<Fr> {
+            max_degree: 1,
+            num_variables: 0,
+            ..Default::default()
+        };
+        let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+        assert!(<PolyIOP<Fr> as SumCheck<Fr>>::verify(
+            Fr::rand(&mut rng),
+            &IOPProof::default(),
+            &aux_info,
+            &mut transcript,
+        )
+        .is_err());
+    }
+
+    /// A random polynomial whose mles are over random subsets of the
+    /// variables, and its sum.
+    fn rand_partial_polynomial(
+        nv: usize,
+        num_products: usize,
+        rng: &mut impl RngCore,
+    ) -> Result<(VirtualPolynomial<Fr>, Fr), PolyIOPErrors> {
+        let mut poly = VirtualPolynomial::new(nv);
+        for i in 0..num_products {
+            let mles = (0..1 + i % 3).map(|_| {
+                let variables: Vec<usize> = (0..nv)
+                    .filter(|_| rng.next_u32().is_multiple_of(2))
+                    .collect();
+                let mle = DenseMultilinearExtension::rand(variables.len(), rng);
+                (Arc::new(mle), variables)
+            });
+            let mles: Vec<_> = mles.collect();
+            poly.add_partial_mle_list(mles, Fr::rand(rng))?;
+        }
+        let lifted = poly.lift();
+        let sum = lifted
+            .products
+            .iter()
+            .map(|(coefficient, indices)| {
+                *coefficient
+                    * (0..1 << nv)
+                        .map(|b| {
+                            indices
+                                .iter()
+                                .map(|&i| lifted.flattened_ml_extensions[i].evaluations[b])
+                                .product::<Fr>()
+                        })
+                        .sum::<Fr>()
+            })
+            .sum();
+        Ok((poly, sum))
+    }
+
+    #[test]
+    fn test_partial_mles() -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        for nv in 1..7 {
+            let (mut poly, asserted_sum) = rand_partial_polynomial(nv, 4, &mut rng)?;
+            // and a product over all the variables
+            let (full, full_sum) = VirtualPolynomial::<Fr>::rand(nv, (1, 3), 1, &mut rng)?;
+            poly = &poly + &full;
+            let asserted_sum = asserted_sum + full_sum;
+            let lifted = poly.lift();
+
+            // the prover messages are the ones of the lifted polynomial
+            let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+            let proof = <PolyIOP<Fr> as SumCheck<Fr>>::prove(&poly, &mut transcript)?;
+            let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+            let lifted_proof = <PolyIOP<Fr> as SumCheck<Fr>>::prove(&lifted, &mut transcript)?;
+            assert_eq!(proof, lifted_proof);
+
+            let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+            let subclaim = <PolyIOP<Fr> as SumCheck<Fr>>::verify(
+                asserted_sum,
+                &proof,
+                &poly.aux_info,
+                &mut transcript,
+            )?;
+            assert_eq!(
+                poly.evaluate(&subclaim.point)?,
+                subclaim.expected_evaluation
+            );
+            assert_eq!(
+                lifted.evaluate(&subclaim.point)?,
+                subclaim.expected_evaluation
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_per_variable_degree() -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        let prove_and_verify = |poly: &VirtualPolynomial<Fr>, asserted_sum: Fr| {
+            let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+            let proof = <PolyIOP<Fr> as SumCheck<Fr>>::prove(poly, &mut transcript)?;
+            let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+            let subclaim = <PolyIOP<Fr> as SumCheck<Fr>>::verify(
+                asserted_sum,
+                &proof,
+                &poly.aux_info,
+                &mut transcript,
+            )?;
+            assert_eq!(
+                poly.evaluate(&subclaim.point)?,
+                subclaim.expected_evaluation
+            );
+            Ok::<_, PolyIOPErrors>(proof)
+        };
+
+        for nv in 1..7 {
+            let (poly, asserted_sum) = rand_partial_polynomial(nv, 4, &mut rng)?;
+            let uniform = prove_and_verify(&poly, asserted_sum)?;
+            let poly = poly.with_per_variable_degree();
+            let proof = prove_and_verify(&poly, asserted_sum)?;
+            for (round, msg) in proof.proofs.iter().enumerate() {
+                assert_eq!(
+                    msg.evaluations.len(),
+                    poly.aux_info.per_variable_degree[round] + 1
+                );
+            }
+            assert!(proof.compressed_size() <= uniform.compressed_size());
+        }
+
+        // half^2 * quarter + full, of degrees 3, 2, 1, 1 in its variables
+        let nv = 4;
+        let half = Arc::new(DenseMultilinearExtension::rand(2, &mut rng));
+        let quarter = Arc::new(DenseMultilinearExtension::rand(1, &mut rng));
+        let mut poly = VirtualPolynomial::<Fr>::new(nv);
+        poly.add_partial_mle_list(
+            [
+                (half.clone(), vec![0, 1]),
+                (half, vec![0, 1]),
+                (quarter, vec![0]),
+            ],
+            Fr::rand(&mut rng),
+        )?;
+        let full = Arc::new(DenseMultilinearExtension::rand(nv, &mut rng));
+        poly.add_mle_list([full], Fr::rand(&mut rng))?;
+        let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+        let uniform = <PolyIOP<Fr> as SumCheck<Fr>>::prove(&poly, &mut transcript)?;
+        let asserted_sum = <PolyIOP<Fr> as SumCheck<Fr>>::extract_sum(&uniform);
+        let poly = poly.with_per_variable_degree();
+        let proof = prove_and_verify(&poly, asserted_sum)?;
+        let lens: Vec<_> = proof
+            .proofs
+            .iter()
+            .map(|msg| msg.evaluations.len())
+            .collect();
+        assert_eq!(lens, vec![4, 3, 2, 2]);
+        assert!(proof.compressed_size() < uniform.compressed_size());
+
+        // a proof with uniform rounds is rejected against the degrees
+        let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+        assert!(matches!(
+            <PolyIOP<Fr> as SumCheck<Fr>>::verify(
+                asserted_sum,
+                &uniform,
+                &poly.aux_info,
+                &mut transcript
+            ),
+            Err(PolyIOPErrors::SumCheckDegreeMismatch {
+                claimed: 2,
+                actual: 3
+            })
+        ));
+        // and so is a declared degree below the actual one
+        let mut bad_poly = poly.clone();
+        bad_poly.aux_info.per_variable_degree[0] = 2;
+        let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+        assert!(matches!(
+            <PolyIOP<Fr> as SumCheck<Fr>>::prove(&bad_poly, &mut transcript),
+            Err(PolyIOPErrors::SumCheckDegreeMismatch {
+                claimed: 2,
+                actual: 3
+            })
+        ));
+        bad_poly.aux_info.per_variable_degree.pop();
+        assert!(matches!(
+            check_aux_info(&bad_poly.aux_info),
+            Err(PolyIOPErrors::InvalidParameters(_))
+        ));
+        Ok(())
+    }
+
     #[test]
     fn test_extract_sum() -> Result<(), PolyIOPErrors> {
         let mut rng = test_rng();
@@ -390,4 +1103,171 @@ mod test {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_verifier_trace() -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        let nv = 5;
+        let (poly, asserted_sum) = VirtualPolynomial::<Fr>::rand(nv, (2, 4), 3, &mut rng)?;
+        let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+        let proof = <PolyIOP<Fr> as SumCheck<Fr>>::prove(&poly, &mut transcript)?;
+
+        let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+        let (subclaim, trace) = <PolyIOP<Fr> as SumCheck<Fr>>::verify_traced(
+            asserted_sum,
+            &proof,
+            &poly.aux_info,
+            &mut transcript,
+        )?;
+        assert_eq!(trace.rounds.len(), nv);
+        assert_eq!(trace.claimed_sums().len(), nv + 1);
+        assert_eq!(trace.asserted_sum, asserted_sum);
+        assert_eq!(trace.final_sum(), subclaim.expected_evaluation);
+        for (round, prover_msg) in trace.rounds.iter().zip(proof.proofs.iter()) {
+            assert_eq!(round.message, prover_msg.evaluations);
+        }
+        assert_eq!(
+            trace
+                .rounds
+                .iter()
+                .map(|round| round.challenge)
+                .collect::<Vec<_>>(),
+            subclaim.point
+        );
+
+        // a wrong sum fails at the first round
+        let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+        let err = <PolyIOP<Fr> as SumCheck<Fr>>::verify(
+            asserted_sum + Fr::from(1u64),
+            &proof,
+            &poly.aux_info,
+            &mut transcript,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            PolyIOPErrors::InconsistentRound { round: 0, .. }
+        ));
+        let failed = err.verifier_trace::<Fr>().unwrap();
+        assert_eq!(failed.rounds.len(), 1);
+        assert_eq!(failed.asserted_sum, asserted_sum + Fr::from(1u64));
+
+        // a message of round 2 that keeps its sum but not its evaluation at
+        // the challenge makes round 3 fail
+        let mut bad_proof = proof.clone();
+        bad_proof.proofs[2].evaluations[0] += Fr::from(1u64);
+        bad_proof.proofs[2].evaluations[1] -= Fr::from(1u64);
+        let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+        let err = <PolyIOP<Fr> as SumCheck<Fr>>::verify_traced(
+            asserted_sum,
+            &bad_proof,
+            &poly.aux_info,
+            &mut transcript,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            PolyIOPErrors::InconsistentRound { round: 3, .. }
+        ));
+        let failed = err.verifier_trace::<Fr>().unwrap();
+        assert_eq!(failed.rounds.len(), 4);
+        assert_eq!(failed.claimed_sums()[..3], trace.claimed_sums()[..3]);
+        assert_eq!(failed.rounds[2].message, bad_proof.proofs[2].evaluations);
+        // the trace is only available over the field of the sum check
+        assert!(err.verifier_trace::<ark_bls12_381::Fq>().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sumcheck_shapes() {
+        check(poly_shape(8, 6, 4), |shape| {
+            let (poly, asserted_sum) = shape.polynomial::<Fr>();
+            let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+            let proof =
+                <PolyIOP<Fr> as SumCheck<Fr>>::prove(&poly, &mut transcript).map_err(fail)?;
+
+            let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+            let subclaim = <PolyIOP<Fr> as SumCheck<Fr>>::verify(
+                asserted_sum,
+                &proof,
+                &poly.aux_info,
+                &mut transcript,
+            )
+            .map_err(fail)?;
+            prop_assert_eq!(
+                poly.evaluate(&subclaim.point).map_err(fail)?,
+                subclaim.expected_evaluation
+            );
+
+            // a wrong sum is rejected
+            let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+            prop_assert!(<PolyIOP<Fr> as SumCheck<Fr>>::verify(
+                asserted_sum + Fr::from(1u64),
+                &proof,
+                &poly.aux_info,
+                &mut transcript,
+            )
+            .is_err());
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_proof_shape_mismatch() -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        let (poly, asserted_sum) = VirtualPolynomial::<Fr>::rand(5, (3, 4), 3, &mut rng)?;
+        let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+        let proof = <PolyIOP<Fr> as SumCheck<Fr>>::prove(&poly, &mut transcript)?;
+        let verify = |proof: &IOPProof<Fr>, aux_info: &VPAuxInfo<Fr>| {
+            let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+            <PolyIOP<Fr> as SumCheck<Fr>>::verify(asserted_sum, proof, aux_info, &mut transcript)
+        };
+        assert!(verify(&proof, &poly.aux_info).is_ok());
+
+        // a smaller or larger degree than the one of the proof
+        let degree = poly.aux_info.max_degree;
+        for max_degree in [degree - 1, degree + 1] {
+            let aux_info = VPAuxInfo {
+                max_degree,
+                ..poly.aux_info.clone()
+            };
+            assert!(matches!(
+                verify(&proof, &aux_info),
+                Err(PolyIOPErrors::SumCheckDegreeMismatch { claimed, actual })
+                    if claimed == max_degree && actual == degree
+            ));
+        }
+        // a message with an extra evaluation
+        let mut bad_proof = proof.clone();
+        bad_proof.proofs[2].evaluations.push(Fr::from(0u64));
+        assert!(matches!(
+            verify(&bad_proof, &poly.aux_info),
+            Err(PolyIOPErrors::SumCheckDegreeMismatch { claimed, actual })
+                if claimed == degree && actual == degree + 1
+        ));
+        // a missing or an extra round
+        let mut bad_proof = proof.clone();
+        bad_proof.proofs.pop();
+        assert!(matches!(
+            verify(&bad_proof, &poly.aux_info),
+            Err(PolyIOPErrors::InvalidProof(_))
+        ));
+        let mut bad_proof = proof;
+        bad_proof.proofs.push(bad_proof.proofs[0].clone());
+        assert!(matches!(
+            verify(&bad_proof, &poly.aux_info),
+            Err(PolyIOPErrors::InvalidProof(_))
+        ));
+
+        // the prover rejects a polynomial with a product above its degree
+        let mut bad_poly = poly;
+        bad_poly.aux_info.max_degree -= 1;
+        let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+        assert!(matches!(
+            <PolyIOP<Fr> as SumCheck<Fr>>::prove(&bad_poly, &mut transcript),
+            Err(PolyIOPErrors::SumCheckDegreeMismatch { claimed, actual })
+                if claimed == degree - 1 && actual == degree
+        ));
+        Ok(())
+    }
 }
@@ -6,20 +6,22 @@
 
 //! Prover subroutines for a SumCheck protocol.
 
-use super::SumCheckProver;
+use super::{check_aux_info, SumCheckProver};
 use crate::poly_iop::{
     errors::PolyIOPErrors,
     structs::{IOPProverMessage, IOPProverState},
 };
-use arithmetic::{fix_variables, VirtualPolynomial};
+use arithmetic::{fix_variables, SparseVirtualPolynomial, VirtualPolynomial};
 use ark_ff::{batch_inversion, PrimeField};
 use ark_poly::DenseMultilinearExtension;
 use ark_std::{cfg_into_iter, end_timer, start_timer, vec::Vec};
-use rayon::prelude::{IntoParallelIterator, IntoParallelRefIterator};
 use std::sync::Arc;
 
 #[cfg(feature = "parallel")]
-use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator};
+use rayon::iter::{
+    IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator,
+    IntoParallelRefMutIterator, ParallelIterator,
+};
 
 impl<F: PrimeField> SumCheckProver<F> for IOPProverState<F> {
     type VirtualPolynomial = VirtualPolynomial<F>;
@@ -29,17 +31,41 @@ impl<F: PrimeField> SumCheckProver<F> for IOPProverState<F> {
     /// over {0,1}^`num_vars`.
     fn prover_init(polynomial: &Self::VirtualPolynomial) -> Result<Self, PolyIOPErrors> {
         let start = start_timer!(|| "sum check prover init");
-        if polynomial.aux_info.num_variables == 0 {
-            return Err(PolyIOPErrors::InvalidParameters(
-                "Attempt to prove a constant.".to_string(),
+        check_aux_info(&polynomial.aux_info)?;
+        if let Some((_, product)) = polynomial
+            .products
+            .iter()
+            .find(|(_, product)| product.len() > polynomial.aux_info.max_degree)
+        {
+            return Err(PolyIOPErrors::degree_mismatch(
+                polynomial.aux_info.max_degree,
+                product.len(),
             ));
         }
+        // each round polynomial is sent with the declared degree of its
+        // variable, which must bound the actual one
+        if let Some((declared, actual)) = polynomial
+            .aux_info
+            .per_variable_degree
+            .iter()
+            .zip(polynomial.variable_degrees())
+            .find(|(declared, actual)| **declared < *actual)
+        {
+            return Err(PolyIOPErrors::degree_mismatch(*declared, actual));
+        }
+        // the zero products of a sparse polynomial add nothing to the
+        // messages, so they are dropped before any variable is fixed
+        let poly = if polynomial.aux_info.is_sparse {
+            VirtualPolynomial::from(&SparseVirtualPolynomial::from(polynomial))
+        } else {
+            polynomial.clone()
+        };
         end_timer!(start);
 
         Ok(Self {
             challenges: Vec::with_capacity(polynomial.aux_info.num_variables),
             round: 0,
-            poly: polynomial.clone(),
+            poly,
             extrapolation_aux: (1..polynomial.aux_info.max_degree)
                 .map(|degree| {
                     let points = (0..1 + degree as u64).map(F::from).collect::<Vec<_>>();
@@ -81,10 +107,33 @@ impl<F: PrimeField> SumCheckProver<F> for IOPProverState<F> {
         //    g(r_1, ..., r_{m-1}, x_m ... x_n)
         //
         // eval g over r_m, and mutate g to g(r_1, ... r_m,, x_{m+1}... x_n)
+        //
+        // An mle over a subset of the variables is only evaluated when x_m
+        // is one of its variables, and is otherwise left untouched.
+        //
+        // With a single thread, rayon only adds overhead: the serial route
+        // runs the same steps without it.
+        #[cfg(feature = "parallel")]
+        let serial = util::serial_route();
+        #[cfg(feature = "parallel")]
+        let mut flattened_ml_extensions: Vec<DenseMultilinearExtension<F>> = if serial {
+            self.poly
+                .flattened_ml_extensions
+                .iter()
+                .map(|x| x.as_ref().clone())
+                .collect()
+        } else {
+            self.poly
+                .flattened_ml_extensions
+                .par_iter()
+                .map(|x| x.as_ref().clone())
+                .collect()
+        };
+        #[cfg(not(feature = "parallel"))]
         let mut flattened_ml_extensions: Vec<DenseMultilinearExtension<F>> = self
             .poly
             .flattened_ml_extensions
-            .par_iter()
+            .iter()
             .map(|x| x.as_ref().clone())
             .collect();
 
@@ -97,14 +146,37 @@ impl<F: PrimeField> SumCheckProver<F> for IOPProverState<F> {
             self.challenges.push(*chal);
 
             let r = self.challenges[self.round - 1];
+            let fixed = self.round - 1;
+            let fix =
+                |(mle, variables): (&mut DenseMultilinearExtension<F>, &Option<Vec<usize>>)| {
+                    if depends_on(variables, fixed) {
+                        *mle = fix_variables(mle, &[r])
+                    }
+                };
+            let mle_variables = &self.poly.mle_variables;
             #[cfg(feature = "parallel")]
-            flattened_ml_extensions
-                .par_iter_mut()
-                .for_each(|mle| *mle = fix_variables(mle, &[r]));
+            if serial {
+                flattened_ml_extensions
+                    .iter_mut()
+                    .zip(mle_variables.iter())
+                    .for_each(fix);
+            } else {
+                flattened_ml_extensions
+                    .par_iter_mut()
+                    .zip(mle_variables.par_iter())
+                    .for_each(fix);
+            }
             #[cfg(not(feature = "parallel"))]
             flattened_ml_extensions
                 .iter_mut()
-                .for_each(|mle| *mle = fix_variables(mle, &[r]));
+                .zip(mle_variables.iter())
+                .for_each(fix);
+            // the fixed variable is no longer a variable of the mles
+            self.poly
+                .mle_variables
+                .iter_mut()
+                .flatten()
+                .for_each(|variables| variables.retain(|&v| v != fixed));
         } else if self.round > 0 {
             return Err(PolyIOPErrors::InvalidProver(
                 "verifier message is empty".to_string(),
@@ -120,42 +192,69 @@ impl<F: PrimeField> SumCheckProver<F> for IOPProverState<F> {
         // Step 2: generate sum for the partial evaluated polynomial:
         // f(r_1, ... r_m,, x_{m+1}... x_n)
 
+        let num_variables = self.poly.aux_info.num_variables;
+        // the variable of the current round
+        let current = self.round - 1;
         products_list.iter().for_each(|(coefficient, products)| {
-            let mut sum = cfg_into_iter!(0..1 << (self.poly.aux_info.num_variables - self.round))
-                .fold(
-                    || {
-                        (
-                            vec![(F::zero(), F::zero()); products.len()],
-                            vec![F::zero(); products.len() + 1],
-                        )
-                    },
-                    |(mut buf, mut acc), b| {
-                        buf.iter_mut()
-                            .zip(products.iter())
-                            .for_each(|((eval, step), f)| {
-                                let table = &flattened_ml_extensions[*f];
-                                *eval = table[b << 1];
-                                *step = table[(b << 1) + 1] - table[b << 1];
-                            });
-                        acc[0] += buf.iter().map(|(eval, _)| eval).product::<F>();
-                        acc[1..].iter_mut().for_each(|acc| {
-                            buf.iter_mut().for_each(|(eval, step)| *eval += step as &_);
-                            *acc += buf.iter().map(|(eval, _)| eval).product::<F>();
-                        });
-                        (buf, acc)
-                    },
+            // the sum only runs over the later variables of the product: each
+            // of the others doubles it
+            let layout =
+                ProductLayout::new(&self.poly.mle_variables, products, current, num_variables);
+            let num_evals = 1 << layout.num_free;
+            let scale = *coefficient
+                * F::from(2u64).pow([(num_variables - self.round - layout.num_free) as u64]);
+
+            // both routes accumulate the evaluations of the product with
+            // the same step, the parallel one over chunks of the hypercube
+            let init = || {
+                (
+                    vec![(F::zero(), F::zero()); products.len()],
+                    vec![F::zero(); products.len() + 1],
                 )
-                .map(|(_, partial)| partial)
-                .reduce(
-                    || vec![F::zero(); products.len() + 1],
-                    |mut sum, partial| {
-                        sum.iter_mut()
-                            .zip(partial.iter())
-                            .for_each(|(sum, partial)| *sum += partial);
-                        sum
-                    },
-                );
-            sum.iter_mut().for_each(|sum| *sum *= coefficient);
+            };
+            let accumulate = |(mut buf, mut acc): (Vec<(F, F)>, Vec<F>), b: usize| {
+                buf.iter_mut()
+                    .zip(products.iter())
+                    .zip(layout.multiplicands.iter())
+                    .for_each(|(((eval, step), f), multiplicand)| {
+                        let table = &flattened_ml_extensions[*f];
+                        let index = multiplicand.index(b);
+                        if multiplicand.depends_on_current {
+                            *eval = table[index << 1];
+                            *step = table[(index << 1) + 1] - table[index << 1];
+                        } else {
+                            *eval = table[index];
+                            *step = F::zero();
+                        }
+                    });
+                acc[0] += buf.iter().map(|(eval, _)| eval).product::<F>();
+                acc[1..].iter_mut().for_each(|acc| {
+                    buf.iter_mut().for_each(|(eval, step)| *eval += step as &_);
+                    *acc += buf.iter().map(|(eval, _)| eval).product::<F>();
+                });
+                (buf, acc)
+            };
+            #[cfg(feature = "parallel")]
+            let mut sum = if serial {
+                (0..num_evals).fold(init(), accumulate).1
+            } else {
+                (0..num_evals)
+                    .into_par_iter()
+                    .fold(init, accumulate)
+                    .map(|(_, partial)| partial)
+                    .reduce(
+                        || vec![F::zero(); products.len() + 1],
+                        |mut sum, partial| {
+                            sum.iter_mut()
+                                .zip(partial.iter())
+                                .for_each(|(sum, partial)| *sum += partial);
+                            sum
+                        },
+                    )
+            };
+            #[cfg(not(feature = "parallel"))]
+            let mut sum = (0..num_evals).fold(init(), accumulate).1;
+            sum.iter_mut().for_each(|sum| *sum *= scale);
             let extraploation = cfg_into_iter!(0..self.poly.aux_info.max_degree - products.len())
                 .map(|i| {
                     let (points, weights) = &self.extrapolation_aux[products.len() - 1];
@@ -168,12 +267,27 @@ impl<F: PrimeField> SumCheckProver<F> for IOPProverState<F> {
                 .zip(sum.iter().chain(extraploation.iter()))
                 .for_each(|(products_sum, sum)| *products_sum += sum);
         });
+        // the evaluations past the degree of the variable are determined by
+        // the others
+        products_sum.truncate(self.poly.aux_info.round_degree(current) + 1);
 
         // update prover's state to the partial evaluated polynomial
-        self.poly.flattened_ml_extensions = flattened_ml_extensions
-            .par_iter()
-            .map(|x| Arc::new(x.clone()))
-            .collect();
+        #[cfg(feature = "parallel")]
+        {
+            self.poly.flattened_ml_extensions = if serial {
+                flattened_ml_extensions.into_iter().map(Arc::new).collect()
+            } else {
+                flattened_ml_extensions
+                    .par_iter()
+                    .map(|x| Arc::new(x.clone()))
+                    .collect()
+            };
+        }
+        #[cfg(not(feature = "parallel"))]
+        {
+            self.poly.flattened_ml_extensions =
+                flattened_ml_extensions.into_iter().map(Arc::new).collect();
+        }
 
         Ok(IOPProverMessage {
             evaluations: products_sum,
@@ -181,6 +295,80 @@ impl<F: PrimeField> SumCheckProver<F> for IOPProverState<F> {
     }
 }
 
+/// Whether an mle over `variables`, or over all the remaining variables if
+/// `None`, depends on the variable `v`, among the remaining ones.
+fn depends_on(variables: &Option<Vec<usize>>, v: usize) -> bool {
+    // the variables are increasing, and the earlier ones are fixed
+    variables
+        .as_ref()
+        .is_none_or(|variables| variables.first() == Some(&v))
+}
+
+/// How the multiplicands of a product are indexed in a round.
+struct ProductLayout {
+    /// number of later variables that some multiplicand depends on
+    num_free: usize,
+    multiplicands: Vec<MultiplicandLayout>,
+}
+
+struct MultiplicandLayout {
+    depends_on_current: bool,
+    /// positions of the later variables of the multiplicand among the free
+    /// variables of the product, `None` if these are all the free variables
+    positions: Option<Vec<usize>>,
+}
+
+impl ProductLayout {
+    fn new(
+        mle_variables: &[Option<Vec<usize>>],
+        products: &[usize],
+        current: usize,
+        num_variables: usize,
+    ) -> Self {
+        let later = |f: usize| match &mle_variables[f] {
+            None => (current + 1..num_variables).collect::<Vec<_>>(),
+            Some(variables) => variables.iter().copied().filter(|&v| v > current).collect(),
+        };
+        let mut free: Vec<usize> = products.iter().flat_map(|&f| later(f)).collect();
+        free.sort_unstable();
+        free.dedup();
+        let multiplicands = products
+            .iter()
+            .map(|&f| {
+                let variables = later(f);
+                MultiplicandLayout {
+                    depends_on_current: depends_on(&mle_variables[f], current),
+                    positions: (variables.len() != free.len()).then(|| {
+                        variables
+                            .iter()
+                            .map(|v| free.binary_search(v).expect("free variables are the union"))
+                            .collect()
+                    }),
+                }
+            })
+            .collect();
+        Self {
+            num_free: free.len(),
+            multiplicands,
+        }
+    }
+}
+
+impl MultiplicandLayout {
+    /// The index in the table of the multiplicand, without the current
+    /// variable, for the assignment `b` of the free variables.
+    fn index(&self, b: usize) -> usize {
+        match &self.positions {
+            None => b,
+            Some(positions) => positions
+                .iter()
+                .enumerate()
+                .map(|(k, &p)| ((b >> p) & 1) << k)
+                .sum(),
+        }
+    }
+}
+
 fn barycentric_weights<F: PrimeField>(points: &[F]) -> Vec<F> {
     let mut weights = points
         .iter()
@@ -189,7 +377,7 @@ fn barycentric_weights<F: PrimeField>(points: &[F]) -> Vec<F> {
             points
                 .iter()
                 .enumerate()
-                .filter(|&(i, _point_i)| (i != j))
+                .filter(|&(i, _point_i)| i != j)
                 .map(|(_i, point_i)| *point_j - point_i)
                 .reduce(|acc, value| acc * value)
                 .unwrap_or_else(F::one)
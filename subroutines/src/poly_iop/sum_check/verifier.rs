@@ -6,10 +6,10 @@
 
 //! Verifier subroutines for a SumCheck protocol.
 
-use super::{SumCheckSubClaim, SumCheckVerifier};
+use super::{check_aux_info, SumCheckSubClaim, SumCheckVerifier};
 use crate::poly_iop::{
     errors::PolyIOPErrors,
-    structs::{IOPProverMessage, IOPVerifierState},
+    structs::{IOPProverMessage, IOPVerifierState, RoundTrace, VerifierTrace},
 };
 use arithmetic::VPAuxInfo;
 use ark_ff::PrimeField;
@@ -27,18 +27,20 @@ impl<F: PrimeField> SumCheckVerifier<F> for IOPVerifierState<F> {
     type SumCheckSubClaim = SumCheckSubClaim<F>;
 
     /// Initialize the verifier's state.
-    fn verifier_init(index_info: &Self::VPAuxInfo) -> Self {
+    fn verifier_init(index_info: &Self::VPAuxInfo) -> Result<Self, PolyIOPErrors> {
         let start = start_timer!(|| "sum check verifier init");
+        check_aux_info(index_info)?;
         let res = Self {
             round: 1,
             num_vars: index_info.num_variables,
             max_degree: index_info.max_degree,
+            per_variable_degree: index_info.per_variable_degree.clone(),
             finished: false,
             polynomials_received: Vec::with_capacity(index_info.num_variables),
             challenges: Vec::with_capacity(index_info.num_variables),
         };
         end_timer!(start);
-        res
+        Ok(res)
     }
 
     /// Run verifier for the current round, given a prover message.
@@ -98,6 +100,45 @@ impl<F: PrimeField> SumCheckVerifier<F> for IOPVerifierState<F> {
         &self,
         asserted_sum: &F,
     ) -> Result<Self::SumCheckSubClaim, PolyIOPErrors> {
+        self.check_and_trace(asserted_sum)
+            .map(|(subclaim, _)| subclaim)
+    }
+}
+
+impl<F: PrimeField> IOPVerifierState<F> {
+    /// Reset the state to the one `SumCheckVerifier::verifier_init` returns
+    /// on `index_info`, but keep the lists of messages and challenges
+    /// allocated, e.g., to verify many proofs in a loop.
+    pub fn reset(&mut self, index_info: &VPAuxInfo<F>) -> Result<(), PolyIOPErrors> {
+        check_aux_info(index_info)?;
+        self.round = 1;
+        self.num_vars = index_info.num_variables;
+        self.max_degree = index_info.max_degree;
+        self.per_variable_degree
+            .clone_from(&index_info.per_variable_degree);
+        self.finished = false;
+        self.polynomials_received.clear();
+        self.polynomials_received.reserve(index_info.num_variables);
+        self.challenges.clear();
+        self.challenges.reserve(index_info.num_variables);
+        Ok(())
+    }
+
+    /// The degree of the message of the round fixing the `round`-th variable
+    /// (0-indexed).
+    fn round_degree(&self, round: usize) -> usize {
+        self.per_variable_degree
+            .get(round)
+            .copied()
+            .unwrap_or(self.max_degree)
+    }
+
+    /// `check_and_generate_subclaim`, which also returns the trace of the
+    /// verifier.
+    pub(crate) fn check_and_trace(
+        &self,
+        asserted_sum: &F,
+    ) -> Result<(SumCheckSubClaim<F>, VerifierTrace<F>), PolyIOPErrors> {
         let start = start_timer!(|| "sum check check and generate subclaim");
         if !self.finished {
             return Err(PolyIOPErrors::InvalidVerifier(
@@ -119,13 +160,14 @@ impl<F: PrimeField> SumCheckVerifier<F> for IOPVerifierState<F> {
             .clone()
             .into_par_iter()
             .zip(self.challenges.clone().into_par_iter())
-            .map(|(evaluations, challenge)| {
-                if evaluations.len() != self.max_degree + 1 {
-                    return Err(PolyIOPErrors::InvalidVerifier(format!(
-                        "incorrect number of evaluations: {} vs {}",
-                        evaluations.len(),
-                        self.max_degree + 1
-                    )));
+            .enumerate()
+            .map(|(round, (evaluations, challenge))| {
+                let degree = self.round_degree(round);
+                if evaluations.len() != degree + 1 {
+                    return Err(PolyIOPErrors::degree_mismatch(
+                        degree,
+                        evaluations.len().saturating_sub(1),
+                    ));
                 }
                 interpolate_uni_poly::<F>(&evaluations, challenge)
             })
@@ -136,14 +178,15 @@ impl<F: PrimeField> SumCheckVerifier<F> for IOPVerifierState<F> {
             .polynomials_received
             .clone()
             .into_iter()
-            .zip(self.challenges.clone().into_iter())
-            .map(|(evaluations, challenge)| {
-                if evaluations.len() != self.max_degree + 1 {
-                    return Err(PolyIOPErrors::InvalidVerifier(format!(
-                        "incorrect number of evaluations: {} vs {}",
-                        evaluations.len(),
-                        self.max_degree + 1
-                    )));
+            .zip(self.challenges.clone())
+            .enumerate()
+            .map(|(round, (evaluations, challenge))| {
+                let degree = self.round_degree(round);
+                if evaluations.len() != degree + 1 {
+                    return Err(PolyIOPErrors::degree_mismatch(
+                        degree,
+                        evaluations.len().saturating_sub(1),
+                    ));
                 }
                 interpolate_uni_poly::<F>(&evaluations, challenge)
             })
@@ -152,27 +195,58 @@ impl<F: PrimeField> SumCheckVerifier<F> for IOPVerifierState<F> {
         // insert the asserted_sum to the first position of the expected vector
         expected_vec.insert(0, *asserted_sum);
 
-        for (evaluations, &expected) in self
+        let trace = self.check_rounds(&expected_vec)?;
+        end_timer!(start);
+        Ok((
+            SumCheckSubClaim {
+                point: self.challenges.clone(),
+                // the last expected value (not checked within this function) will be included in
+                // the subclaim
+                expected_evaluation: expected_vec[self.num_vars],
+            },
+            trace,
+        ))
+    }
+
+    /// The deferred checks of the rounds, given `expected_vec`, the asserted
+    /// sum followed by the claimed sum derived in each round.
+    ///
+    /// Returns the trace of the rounds, which is also attached to the error
+    /// if a round fails.
+    pub(crate) fn check_rounds(
+        &self,
+        expected_vec: &[F],
+    ) -> Result<VerifierTrace<F>, PolyIOPErrors> {
+        let mut trace = VerifierTrace {
+            asserted_sum: expected_vec[0],
+            rounds: self
+                .polynomials_received
+                .iter()
+                .zip(self.challenges.iter())
+                .zip(expected_vec[1..].iter())
+                .map(|((message, &challenge), &claimed_sum)| RoundTrace {
+                    message: message.clone(),
+                    challenge,
+                    claimed_sum,
+                })
+                .collect(),
+        };
+
+        for (round, (evaluations, &expected)) in self
             .polynomials_received
             .iter()
             .zip(expected_vec.iter())
             .take(self.num_vars)
+            .enumerate()
         {
             // the deferred check during the interactive phase:
             // 1. check if the received 'P(0) + P(1) = expected`.
             if evaluations[0] + evaluations[1] != expected {
-                return Err(PolyIOPErrors::InvalidProof(
-                    "Prover message is not consistent with the claim.".to_string(),
-                ));
+                trace.rounds.truncate(round + 1);
+                return Err(PolyIOPErrors::inconsistent_round(round, trace));
             }
         }
-        end_timer!(start);
-        Ok(SumCheckSubClaim {
-            point: self.challenges.clone(),
-            // the last expected value (not checked within this function) will be included in the
-            // subclaim
-            expected_evaluation: expected_vec[self.num_vars],
-        })
+        Ok(trace)
     }
 }
 
@@ -186,10 +260,23 @@ impl<F: PrimeField> SumCheckVerifier<F> for IOPVerifierState<F> {
 /// negligible compared to field operations.
 /// TODO: The quadratic term can be removed by precomputing the lagrange
 /// coefficients.
-fn interpolate_uni_poly<F: PrimeField>(p_i: &[F], eval_at: F) -> Result<F, PolyIOPErrors> {
+pub(crate) fn interpolate_uni_poly<F: PrimeField>(
+    p_i: &[F],
+    eval_at: F,
+) -> Result<F, PolyIOPErrors> {
     let start = start_timer!(|| "sum check interpolate uni poly opt");
 
     let len = p_i.len();
+    if len == 0 {
+        return Err(PolyIOPErrors::InvalidParameters(
+            "cannot interpolate a polynomial without evaluations".to_string(),
+        ));
+    }
+    // the formula below divides by `eval_at - j` for each evaluation point j
+    if let Some(i) = (0..len).find(|&i| eval_at == F::from(i as u64)) {
+        end_timer!(start);
+        return Ok(p_i[i]);
+    }
     let mut evals = vec![];
     let mut prod = eval_at;
     evals.push(eval_at);
@@ -355,6 +442,15 @@ mod test {
 
         assert_eq!(poly.evaluate(&query), interpolate_uni_poly(&evals, query)?);
 
+        // at the known points, and without any point
+        for i in [0u64, 5, 63] {
+            assert_eq!(
+                evals[i as usize],
+                interpolate_uni_poly(&evals, Fr::from(i))?
+            );
+        }
+        assert!(interpolate_uni_poly(&[], query).is_err());
+
         Ok(())
     }
 }
@@ -8,8 +8,13 @@
 
 use std::fmt::Debug;
 
-use crate::poly_iop::{errors::PolyIOPErrors, sum_check::SumCheck, PolyIOP};
-use arithmetic::eq_eval;
+use crate::poly_iop::{
+    errors::PolyIOPErrors,
+    structs::IOPProverMessage,
+    sum_check::{SumCheck, SumCheckRounds, SumCheckVerifierRounds},
+    PolyIOP,
+};
+use arithmetic::{eq_eval, VPAuxInfo, VirtualPolynomial};
 use ark_ff::PrimeField;
 use ark_std::{end_timer, start_timer};
 use transcript::IOPTranscript;
@@ -58,6 +63,107 @@ pub trait ZeroCheck<F: PrimeField>: SumCheck<F> {
     ) -> Result<Self::ZeroCheckSubClaim, PolyIOPErrors>;
 }
 
+/// Initialize a round-by-round prover for the zero check of `poly`.
+///
+/// This samples the challenge `r` from the transcript and returns the sum
+/// check prover for `f_hat(x) = f(x) * eq(x, r)`. Driving the returned
+/// prover to completion yields the same proof as `ZeroCheck::prove`.
+pub fn zero_check_rounds<F: PrimeField>(
+    poly: &VirtualPolynomial<F>,
+    transcript: &mut IOPTranscript<F>,
+) -> Result<SumCheckRounds<F>, PolyIOPErrors> {
+    let r = init_challenge(poly.aux_info.num_variables, transcript)?;
+    let f_hat = poly.build_f_hat(r.as_ref())?;
+    SumCheckRounds::new(&f_hat, transcript)
+}
+
+/// Sample the challenge `r` of the zero check of a polynomial with
+/// `num_variables` variables.
+fn init_challenge<F: PrimeField>(
+    num_variables: usize,
+    transcript: &mut IOPTranscript<F>,
+) -> Result<Vec<F>, PolyIOPErrors> {
+    Ok(transcript
+        .get_zero_check_challenges(b"0check r", num_variables)?
+        .into_iter()
+        .map(|r| r.0)
+        .collect())
+}
+
+/// A zero check verifier that is driven one round at a time.
+///
+/// `ZeroCheck::verify` is equivalent to calling
+/// [`ZeroCheckVerifierRounds::new`], then
+/// [`ZeroCheckVerifierRounds::verify_round`] on every prover message, and
+/// finally [`ZeroCheckVerifierRounds::finish`].
+pub struct ZeroCheckVerifierRounds<F: PrimeField> {
+    /// the verifier of the sum check on `f_hat(x) = f(x) * eq(x, r)`
+    rounds: SumCheckVerifierRounds<F>,
+    /// the initial challenge `r`
+    init_challenge: Vec<F>,
+}
+
+impl<F: PrimeField> ZeroCheckVerifierRounds<F> {
+    /// Initialize the verifier for a polynomial described by `fx_aux_info`:
+    /// sample the challenge `r` from the transcript and initialize the sum
+    /// check verifier for `f_hat(x) = f(x) * eq(x, r)`.
+    pub fn new(
+        fx_aux_info: &VPAuxInfo<F>,
+        transcript: &mut IOPTranscript<F>,
+    ) -> Result<Self, PolyIOPErrors> {
+        let init_challenge = init_challenge(fx_aux_info.num_variables, transcript)?;
+
+        // hat_fx's max degree is increased by eq(x, r).degree() which is 1
+        let mut hat_fx_aux_info = fx_aux_info.clone();
+        hat_fx_aux_info.max_degree += 1;
+        hat_fx_aux_info
+            .per_variable_degree
+            .iter_mut()
+            .for_each(|degree| *degree += 1);
+        let rounds = SumCheckVerifierRounds::new(F::zero(), &hat_fx_aux_info, transcript)?;
+
+        Ok(Self {
+            rounds,
+            init_challenge,
+        })
+    }
+
+    /// Number of evaluations in every prover message.
+    pub fn message_len(&self) -> usize {
+        self.rounds.message_len()
+    }
+
+    /// Whether all the rounds have been verified.
+    pub fn is_finished(&self) -> bool {
+        self.rounds.is_finished()
+    }
+
+    /// Verify the next round.
+    pub fn verify_round(
+        &mut self,
+        prover_msg: &IOPProverMessage<F>,
+        transcript: &mut IOPTranscript<F>,
+    ) -> Result<F, PolyIOPErrors> {
+        self.rounds.verify_round(prover_msg, transcript)
+    }
+
+    /// Output the subclaim. Must be called after the last round.
+    pub fn finish(self) -> Result<ZeroCheckSubClaim<F>, PolyIOPErrors> {
+        let sum_subclaim = self.rounds.finish()?;
+
+        // expected_eval = sumcheck.expect_eval/eq(v, r)
+        // where v = sum_check_sub_claim.point
+        let eq_x_r_eval = eq_eval(&sum_subclaim.point, &self.init_challenge)?;
+        let expected_evaluation = sum_subclaim.expected_evaluation / eq_x_r_eval;
+
+        Ok(ZeroCheckSubClaim {
+            point: sum_subclaim.point,
+            expected_evaluation,
+            init_challenge: self.init_challenge,
+        })
+    }
+}
+
 impl<F: PrimeField> ZeroCheck<F> for PolyIOP<F> {
     type ZeroCheckSubClaim = ZeroCheckSubClaim<F>;
     type ZeroCheckProof = Self::SumCheckProof;
@@ -72,13 +178,13 @@ impl<F: PrimeField> ZeroCheck<F> for PolyIOP<F> {
     ) -> Result<Self::ZeroCheckProof, PolyIOPErrors> {
         let start = start_timer!(|| "zero check prove");
 
-        let length = poly.aux_info.num_variables;
-        let r = transcript.get_and_append_challenge_vectors(b"0check r", length)?;
-        let f_hat = poly.build_f_hat(r.as_ref())?;
-        let res = <Self as SumCheck<F>>::prove(&f_hat, transcript);
+        let mut rounds = zero_check_rounds(poly, transcript)?;
+        while !rounds.is_finished() {
+            rounds.prove_round(transcript)?;
+        }
 
         end_timer!(start);
-        res
+        Ok(rounds.finish())
     }
 
     fn verify(
@@ -97,12 +203,15 @@ impl<F: PrimeField> ZeroCheck<F> for PolyIOP<F> {
         }
 
         // generate `r` and pass it to the caller for correctness check
-        let length = fx_aux_info.num_variables;
-        let r = transcript.get_and_append_challenge_vectors(b"0check r", length)?;
+        let r = init_challenge(fx_aux_info.num_variables, transcript)?;
 
         // hat_fx's max degree is increased by eq(x, r).degree() which is 1
         let mut hat_fx_aux_info = fx_aux_info.clone();
         hat_fx_aux_info.max_degree += 1;
+        hat_fx_aux_info
+            .per_variable_degree
+            .iter_mut()
+            .for_each(|degree| *degree += 1);
         let sum_subclaim =
             <Self as SumCheck<F>>::verify(F::zero(), proof, &hat_fx_aux_info, transcript)?;
 
@@ -124,10 +233,15 @@ impl<F: PrimeField> ZeroCheck<F> for PolyIOP<F> {
 mod test {
 
     use super::ZeroCheck;
-    use crate::poly_iop::{errors::PolyIOPErrors, PolyIOP};
+    use crate::poly_iop::{
+        errors::PolyIOPErrors,
+        strategies::{check, fail, poly_shape},
+        PolyIOP,
+    };
     use arithmetic::VirtualPolynomial;
     use ark_bls12_381::Fr;
     use ark_std::test_rng;
+    use proptest::{prop_assert, prop_assert_eq};
 
     fn test_zerocheck(
         nv: usize,
@@ -204,4 +318,49 @@ mod test {
         assert!(test_zerocheck(nv, num_multiplicands_range, num_products).is_err());
         Ok(())
     }
+
+    #[test]
+    fn test_zerocheck_shapes() {
+        check(poly_shape(8, 6, 4), |shape| {
+            let poly = shape.zero_polynomial::<Fr>();
+            let mut transcript = <PolyIOP<Fr> as ZeroCheck<Fr>>::init_transcript();
+            transcript
+                .append_message(b"testing", b"initializing transcript for testing")
+                .map_err(fail)?;
+            let proof =
+                <PolyIOP<Fr> as ZeroCheck<Fr>>::prove(&poly, &mut transcript).map_err(fail)?;
+
+            let mut transcript = <PolyIOP<Fr> as ZeroCheck<Fr>>::init_transcript();
+            transcript
+                .append_message(b"testing", b"initializing transcript for testing")
+                .map_err(fail)?;
+            let subclaim =
+                <PolyIOP<Fr> as ZeroCheck<Fr>>::verify(&proof, &poly.aux_info, &mut transcript)
+                    .map_err(fail)?;
+            prop_assert_eq!(
+                poly.evaluate(&subclaim.point).map_err(fail)?,
+                subclaim.expected_evaluation
+            );
+
+            // a polynomial that is not zero over the hypercube is rejected
+            let (poly, _) = shape.polynomial::<Fr>();
+            let mut transcript = <PolyIOP<Fr> as ZeroCheck<Fr>>::init_transcript();
+            transcript
+                .append_message(b"testing", b"initializing transcript for testing")
+                .map_err(fail)?;
+            let proof =
+                <PolyIOP<Fr> as ZeroCheck<Fr>>::prove(&poly, &mut transcript).map_err(fail)?;
+            let mut transcript = <PolyIOP<Fr> as ZeroCheck<Fr>>::init_transcript();
+            transcript
+                .append_message(b"testing", b"initializing transcript for testing")
+                .map_err(fail)?;
+            prop_assert!(<PolyIOP<Fr> as ZeroCheck<Fr>>::verify(
+                &proof,
+                &poly.aux_info,
+                &mut transcript
+            )
+            .is_err());
+            Ok(())
+        });
+    }
 }
@@ -0,0 +1,208 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! Deferred checks of zk sum checks, accumulated across protocol runs and
+//! checked at once.
+//!
+//! The verifier of a zk sum check only samples the challenges during the
+//! rounds, and defers the checks of the round polynomials to
+//! `check_and_generate_subclaim`.
+//! When several sub-proofs of a pipeline are verified, their finished
+//! verifier states are added to an [`AccumulatedChecks`] instead, and the
+//! checks of all of them are combined into one by
+//! [`AccumulatedChecks::finalize`].
+
+use super::{ZkConfig, ZkSumCheckSubClaim};
+use crate::poly_iop::{errors::PolyIOPErrors, structs::IOPVerifierState};
+use ark_ff::PrimeField;
+use ark_std::{end_timer, start_timer};
+use transcript::IOPTranscript;
+
+/// The deferred checks of zk sum checks with masks of the same degree.
+#[derive(Clone, Debug)]
+pub struct AccumulatedChecks<F: PrimeField> {
+    /// the degree of the masks of the sum checks
+    mask_degree: usize,
+    /// the verifier states, after their last round
+    states: Vec<IOPVerifierState<F>>,
+    /// the claimed sum of each state
+    claimed_sums: Vec<F>,
+}
+
+impl<F: PrimeField> AccumulatedChecks<F> {
+    /// No checks, for zk sum checks with the configuration `config`.
+    pub fn new(config: &ZkConfig) -> Self {
+        Self {
+            mask_degree: config.mask_degree,
+            states: vec![],
+            claimed_sums: vec![],
+        }
+    }
+
+    /// Defer the checks of `state`, which has received all the prover
+    /// messages of a zk sum check of `claimed_sum`. The state is checked by
+    /// [`AccumulatedChecks::finalize`].
+    pub fn add_check(&mut self, state: &IOPVerifierState<F>, claimed_sum: &F) {
+        self.states.push(state.clone());
+        self.claimed_sums.push(*claimed_sum);
+    }
+
+    /// Number of accumulated checks.
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    /// Whether no check has been accumulated.
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    /// Run all the accumulated checks, and return the subclaim of each in
+    /// the order they were added.
+    ///
+    /// The round checks of all the states are combined with the powers of a
+    /// scalar derived from all their messages and claimed sums, and checked
+    /// at once. If they fail, the states are checked one by one to report
+    /// the first failing one, see `PolyIOPErrors::BatchProofFailed`.
+    pub fn finalize(self) -> Result<Vec<ZkSumCheckSubClaim<F>>, PolyIOPErrors> {
+        let start = start_timer!(|| format!("finalize {} accumulated checks", self.len()));
+
+        let mut transcript = IOPTranscript::new(b"accumulated checks");
+        let mut expected = Vec::with_capacity(self.len());
+        for (index, (state, claimed_sum)) in
+            self.states.iter().zip(self.claimed_sums.iter()).enumerate()
+        {
+            expected.push(
+                state
+                    .expected_sums_masked(claimed_sum, state.num_vars, self.mask_degree)
+                    .map_err(|e| PolyIOPErrors::batch_proof_failed(index, e))?,
+            );
+            transcript.append_field_element(b"claimed sum", claimed_sum)?;
+            transcript.append_serializable_element(b"messages", &state.polynomials_received)?;
+        }
+
+        // round `i` of state `k` holds if P(0) + P(1) - expected is zero;
+        // check the combination of all the rounds with the powers of `coeff`
+        let coeff = transcript.get_and_append_challenge(b"batch sum check")?;
+        let mut combined = F::zero();
+        let mut power = F::one();
+        for (state, expected) in self.states.iter().zip(expected.iter()) {
+            for (evaluations, expected) in state.polynomials_received.iter().zip(expected.iter()) {
+                combined += power * (evaluations[0] + evaluations[1] - expected);
+                power *= coeff;
+            }
+        }
+        if !combined.is_zero() {
+            for (index, (state, expected)) in self.states.iter().zip(expected.iter()).enumerate() {
+                state
+                    .check_rounds(expected)
+                    .map_err(|e| PolyIOPErrors::batch_proof_failed(index, e))?;
+            }
+            return Err(PolyIOPErrors::ShouldNotArrive);
+        }
+
+        end_timer!(start);
+        Ok(self
+            .states
+            .into_iter()
+            .zip(expected)
+            .map(|(state, expected)| ZkSumCheckSubClaim {
+                expected_evaluation: expected[state.num_vars],
+                point: state.challenges,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::poly_iop::{
+        structs::RandomMaskPolynomial,
+        zk_sum_check::{prove_with_rho, verify_with_rho, ZkSumCheckVerifier, AUX_INFO_LABEL},
+    };
+    use arithmetic::VirtualPolynomial;
+    use ark_bls12_381::Fr;
+    use ark_ff::{One, UniformRand};
+    use ark_std::test_rng;
+
+    #[test]
+    fn test_accumulated_checks() -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        let config = ZkConfig { mask_degree: 3 };
+        let mut checks = AccumulatedChecks::new(&config);
+        let mut polys = vec![];
+        let mut masks = vec![];
+        let mut rhos = vec![];
+        let mut sums = vec![];
+        let mut proofs = vec![];
+        // three independent sum checks, of different numbers of variables
+        for nv in 3..6 {
+            let (poly, sum) = VirtualPolynomial::<Fr>::rand(nv, (2, 3), 2, &mut rng)?;
+            let (mask, mask_sum) = RandomMaskPolynomial::<Fr>::rand(nv, 3, &mut rng);
+            let rho = Fr::rand(&mut rng);
+            let mut transcript = IOPTranscript::new(b"accumulated checks test");
+            let proof = prove_with_rho(&poly, &mask, &rho, &mut transcript)?;
+
+            // the rounds of `verify_with_rho`, without the deferred checks
+            let mut transcript = IOPTranscript::new(b"accumulated checks test");
+            let mut state =
+                <IOPVerifierState<Fr> as ZkSumCheckVerifier<Fr>>::verifier_init(&poly.aux_info)?;
+            transcript.append_message(AUX_INFO_LABEL, &poly.aux_info.transcript_bytes())?;
+            for prover_msg in proof.proofs.iter() {
+                transcript.append_serializable_element(b"prover msg", prover_msg)?;
+                state.verify_round_and_update_state(prover_msg, &mut transcript)?;
+            }
+            checks.add_check(&state, &(sum + rho * mask_sum));
+
+            sums.push(sum + rho * mask_sum);
+            polys.push(poly);
+            masks.push(mask);
+            rhos.push(rho);
+            proofs.push(proof);
+        }
+        assert_eq!(checks.len(), 3);
+
+        // a bad claimed sum is reported with the index of its state
+        let mut bad_checks = checks.clone();
+        bad_checks.claimed_sums[1] += Fr::one();
+        assert!(matches!(
+            bad_checks.finalize(),
+            Err(PolyIOPErrors::BatchProofFailed { index: 1, .. })
+        ));
+
+        let subclaims = checks.finalize()?;
+        assert_eq!(subclaims.len(), 3);
+        for (k, subclaim) in subclaims.iter().enumerate() {
+            // the same subclaims as checking each state
+            let mut transcript = IOPTranscript::new(b"accumulated checks test");
+            let expected = verify_with_rho(
+                sums[k],
+                &proofs[k],
+                &polys[k].aux_info,
+                &mut transcript,
+                polys[k].aux_info.num_variables,
+                config.mask_degree,
+            )?;
+            assert_eq!(subclaim, &expected);
+            assert_eq!(
+                polys[k].evaluate(&subclaim.point)? + rhos[k] * masks[k].eval(&subclaim.point)?,
+                subclaim.expected_evaluation
+            );
+        }
+
+        // a state that has not received all its messages
+        let mut checks = AccumulatedChecks::new(&config);
+        let state =
+            <IOPVerifierState<Fr> as ZkSumCheckVerifier<Fr>>::verifier_init(&polys[0].aux_info)?;
+        checks.add_check(&state, &sums[0]);
+        assert!(matches!(
+            checks.finalize(),
+            Err(PolyIOPErrors::BatchProofFailed { index: 0, .. })
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,340 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! A single zk sum check for several polynomials over the same variables.
+//!
+//! Unlike [`ZkSumCheck::batch_verify`](super::ZkSumCheck::batch_verify),
+//! which checks many proofs at once, the polynomials are combined with the
+//! powers of a challenge before proving, so there is a single proof with
+//! one message per round, whatever the number of polynomials.
+
+use super::{prove_with_rho, verify_with_rho, ZkConfig, AUX_INFO_LABEL};
+use crate::poly_iop::{
+    errors::PolyIOPErrors,
+    structs::{IOPProof, RandomMaskPolynomial},
+    sum_check::interpolate_uni_poly,
+};
+use arithmetic::{VPAuxInfo, VirtualPolynomial};
+use ark_ff::PrimeField;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{end_timer, marker::PhantomData, start_timer};
+use transcript::IOPTranscript;
+
+/// The zk sum check of a batch of polynomials, see the module
+/// documentation.
+pub struct BatchSumCheck<F: PrimeField> {
+    phantom: PhantomData<F>,
+}
+
+/// The proof of a [`BatchSumCheck`]: the zk sum check proof of the
+/// combined polynomial.
+#[derive(Clone, Debug, Default, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct BatchSumCheckProof<F: PrimeField> {
+    pub proof: IOPProof<F>,
+}
+
+/// The subclaim of a [`BatchSumCheck`].
+///
+/// With `p_k` the polynomials, `m_k` their masks and `rho_k` their masking
+/// coefficients, the verifier must check that
+/// `sum_k coefficients[k] * (p_k(point) + rho_k * m_k(point))` is
+/// `expected_evaluation`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BatchSumCheckSubClaim<F: PrimeField> {
+    /// the point at which the polynomials are evaluated
+    pub point: Vec<F>,
+    /// the expected evaluation of the combined polynomial
+    pub expected_evaluation: F,
+    /// the coefficient of each polynomial in the combination
+    pub coefficients: Vec<F>,
+}
+
+impl<F: PrimeField> BatchSumCheck<F> {
+    /// Prove the sums of `polys`, each masked with `rhos[k]` times
+    /// `masks[k]`, with a single zk sum check.
+    ///
+    /// The polynomials must have the same number of variables; the mask of
+    /// the combination has the largest degree of `masks`, which must be at
+    /// least the degree of every polynomial.
+    pub fn prove(
+        polys: &[VirtualPolynomial<F>],
+        masks: &[RandomMaskPolynomial<F>],
+        rhos: &[F],
+        transcript: &mut IOPTranscript<F>,
+    ) -> Result<BatchSumCheckProof<F>, PolyIOPErrors> {
+        let start = start_timer!(|| format!("batch sum check prove of {} polys", polys.len()));
+
+        if masks.len() != polys.len() {
+            return Err(PolyIOPErrors::InvalidParameters(format!(
+                "{} masks for {} polynomials",
+                masks.len(),
+                polys.len()
+            )));
+        }
+        let aux_infos: Vec<_> = polys.iter().map(|poly| poly.aux_info.clone()).collect();
+        let coefficients = batch_coefficients(&aux_infos, rhos, transcript)?;
+
+        let mut batched = polys[0].scale(coefficients[0]);
+        for (poly, coefficient) in polys.iter().zip(coefficients.iter()).skip(1) {
+            batched = batched.try_add(&poly.scale(*coefficient))?;
+        }
+        // the zk sum check runs on mles over all the variables
+        if batched.mle_variables.iter().any(Option::is_some) {
+            batched = batched.lift();
+        }
+        batched.aux_info = batched_aux_info(&aux_infos);
+        let mask = batched_mask(masks, rhos, &coefficients)?;
+        let proof = prove_with_rho(&batched, &mask, &F::one(), transcript)?;
+
+        end_timer!(start);
+        Ok(BatchSumCheckProof { proof })
+    }
+
+    /// Verify a proof generated by [`BatchSumCheck::prove`] that the
+    /// polynomials described by `aux_infos`, masked with `rhos[k]` times
+    /// masks of degree `config.mask_degree` at most, sum to `sums`.
+    ///
+    /// Here `sums[k]` is the sum of the polynomial plus `rhos[k]` times the
+    /// sum of its mask.
+    pub fn verify(
+        sums: &[F],
+        proof: &BatchSumCheckProof<F>,
+        aux_infos: &[VPAuxInfo<F>],
+        rhos: &[F],
+        config: &ZkConfig,
+        transcript: &mut IOPTranscript<F>,
+    ) -> Result<BatchSumCheckSubClaim<F>, PolyIOPErrors> {
+        let start = start_timer!(|| format!("batch sum check verify of {} polys", sums.len()));
+
+        if sums.len() != aux_infos.len() {
+            return Err(PolyIOPErrors::InvalidParameters(format!(
+                "{} sums for {} polynomials",
+                sums.len(),
+                aux_infos.len()
+            )));
+        }
+        let coefficients = batch_coefficients(aux_infos, rhos, transcript)?;
+        let claimed_sum = sums
+            .iter()
+            .zip(coefficients.iter())
+            .map(|(sum, coefficient)| *sum * coefficient)
+            .sum();
+        let aux_info = batched_aux_info(aux_infos);
+        let subclaim = verify_with_rho(
+            claimed_sum,
+            &proof.proof,
+            &aux_info,
+            transcript,
+            aux_info.num_variables,
+            config.mask_degree,
+        )?;
+
+        end_timer!(start);
+        Ok(BatchSumCheckSubClaim {
+            point: subclaim.point,
+            expected_evaluation: subclaim.expected_evaluation,
+            coefficients,
+        })
+    }
+}
+
+/// Absorb the auxiliary information and the masking coefficient of each
+/// polynomial, and sample the coefficients of the combination, the powers
+/// of a challenge.
+fn batch_coefficients<F: PrimeField>(
+    aux_infos: &[VPAuxInfo<F>],
+    rhos: &[F],
+    transcript: &mut IOPTranscript<F>,
+) -> Result<Vec<F>, PolyIOPErrors> {
+    let num_variables = match aux_infos.first() {
+        Some(aux_info) => aux_info.num_variables,
+        None => {
+            return Err(PolyIOPErrors::InvalidParameters(
+                "no polynomial to batch".to_string(),
+            ))
+        },
+    };
+    if rhos.len() != aux_infos.len() {
+        return Err(PolyIOPErrors::InvalidParameters(format!(
+            "{} masking coefficients for {} polynomials",
+            rhos.len(),
+            aux_infos.len()
+        )));
+    }
+    if let Some(aux_info) = aux_infos
+        .iter()
+        .find(|aux_info| aux_info.num_variables != num_variables)
+    {
+        return Err(PolyIOPErrors::InvalidParameters(format!(
+            "cannot batch polynomials of {} and {} variables",
+            num_variables, aux_info.num_variables
+        )));
+    }
+
+    transcript.append_message(b"batch sum check", &(aux_infos.len() as u64).to_le_bytes())?;
+    for (aux_info, rho) in aux_infos.iter().zip(rhos.iter()) {
+        transcript.append_message(AUX_INFO_LABEL, &aux_info.transcript_bytes())?;
+        transcript.append_field_element(b"rho", rho)?;
+    }
+    let challenge = transcript.get_and_append_challenge(b"batch coefficient")?;
+    let mut power = F::one();
+    Ok(aux_infos
+        .iter()
+        .map(|_| {
+            let coefficient = power;
+            power *= challenge;
+            coefficient
+        })
+        .collect())
+}
+
+/// The auxiliary information of the combination of polynomials with
+/// `aux_infos`, which have the same number of variables.
+fn batched_aux_info<F: PrimeField>(aux_infos: &[VPAuxInfo<F>]) -> VPAuxInfo<F> {
+    VPAuxInfo {
+        max_degree: aux_infos
+            .iter()
+            .map(|aux_info| aux_info.max_degree)
+            .max()
+            .unwrap_or(0),
+        num_variables: aux_infos[0].num_variables,
+        ..Default::default()
+    }
+}
+
+/// The mask `sum_k coefficients[k] * rhos[k] * masks[k]`, of the largest
+/// degree of `masks`.
+fn batched_mask<F: PrimeField>(
+    masks: &[RandomMaskPolynomial<F>],
+    rhos: &[F],
+    coefficients: &[F],
+) -> Result<RandomMaskPolynomial<F>, PolyIOPErrors> {
+    let num_variables = masks[0].evaluations.len();
+    if masks.iter().any(|mask| {
+        mask.evaluations.len() != num_variables
+            || mask.evaluations.iter().any(|evals| evals.is_empty())
+    }) {
+        return Err(PolyIOPErrors::InvalidParameters(format!(
+            "masks do not all have {} variables",
+            num_variables
+        )));
+    }
+    let degree = masks
+        .iter()
+        .filter_map(|mask| mask.evaluations.first())
+        .map(|evals| evals.len() - 1)
+        .max()
+        .unwrap_or(0);
+
+    let mut res = RandomMaskPolynomial {
+        const_term: F::zero(),
+        evaluations: vec![vec![F::zero(); degree + 1]; num_variables],
+        ..Default::default()
+    };
+    for ((mask, rho), coefficient) in masks.iter().zip(rhos.iter()).zip(coefficients.iter()) {
+        let scale = *rho * coefficient;
+        res.const_term += scale * mask.const_term;
+        for (res_evals, evals) in res.evaluations.iter_mut().zip(mask.evaluations.iter()) {
+            // a mask of a smaller degree is evaluated at the extra points
+            for (j, res_eval) in res_evals.iter_mut().enumerate() {
+                let eval = match evals.get(j) {
+                    Some(eval) => *eval,
+                    None => interpolate_uni_poly(evals, F::from(j as u64))?,
+                };
+                *res_eval += scale * eval;
+            }
+        }
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::poly_iop::zk_sum_check::prove_with_rho;
+    use ark_bls12_381::Fr;
+    use ark_ff::{One, UniformRand};
+    use ark_std::test_rng;
+
+    #[test]
+    fn test_batch_sum_check() -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        let nv = 5;
+        let mut polys = vec![];
+        let mut masks = vec![];
+        let mut sums = vec![];
+        let mut rhos = vec![];
+        for degree in [2, 3, 3, 2] {
+            let (poly, sum) = VirtualPolynomial::<Fr>::rand(nv, (2, 3), 2, &mut rng)?;
+            let (mask, mask_sum) = RandomMaskPolynomial::<Fr>::rand(nv, degree, &mut rng);
+            let rho = Fr::rand(&mut rng);
+            sums.push(sum + rho * mask_sum);
+            polys.push(poly);
+            masks.push(mask);
+            rhos.push(rho);
+        }
+        let aux_infos: Vec<_> = polys.iter().map(|poly| poly.aux_info.clone()).collect();
+        let config = ZkConfig { mask_degree: 3 };
+
+        let mut transcript = IOPTranscript::new(b"batch sum check test");
+        let proof = BatchSumCheck::prove(&polys, &masks, &rhos, &mut transcript)?;
+        assert_eq!(proof.proof.proofs.len(), nv);
+        assert!(proof
+            .proof
+            .proofs
+            .iter()
+            .all(|msg| msg.evaluations.len() == config.mask_degree + 1));
+
+        let verify = |sums: &[Fr], aux_infos: &[VPAuxInfo<Fr>], rhos: &[Fr]| {
+            let mut transcript = IOPTranscript::new(b"batch sum check test");
+            BatchSumCheck::verify(sums, &proof, aux_infos, rhos, &config, &mut transcript)
+        };
+        let subclaim = verify(&sums, &aux_infos, &rhos)?;
+        let mut evaluation = Fr::from(0u64);
+        for (k, coefficient) in subclaim.coefficients.iter().enumerate() {
+            evaluation += *coefficient
+                * (polys[k].evaluate(&subclaim.point)?
+                    + rhos[k] * masks[k].eval(&subclaim.point)?);
+        }
+        assert_eq!(evaluation, subclaim.expected_evaluation);
+        assert!(subclaim.coefficients[0].is_one());
+
+        // a wrong sum, or a different masking coefficient
+        let mut bad_sums = sums.clone();
+        bad_sums[2] += Fr::one();
+        assert!(verify(&bad_sums, &aux_infos, &rhos).is_err());
+        let mut bad_rhos = rhos.clone();
+        bad_rhos[1] += Fr::one();
+        assert!(verify(&sums, &aux_infos, &bad_rhos).is_err());
+        assert!(matches!(
+            verify(&sums[1..], &aux_infos, &rhos),
+            Err(PolyIOPErrors::InvalidParameters(_))
+        ));
+
+        // a single proof instead of a proof per polynomial
+        let mut transcript = IOPTranscript::new(b"batch sum check test");
+        let mut size = 0;
+        for k in 0..polys.len() {
+            let proof = prove_with_rho(&polys[k], &masks[k], &rhos[k], &mut transcript)?;
+            size += proof.compressed_size();
+        }
+        assert!(proof.compressed_size() < size);
+
+        // the polynomials must have the same number of variables
+        let (other, _) = VirtualPolynomial::<Fr>::rand(nv + 1, (2, 3), 2, &mut rng)?;
+        let mut transcript = IOPTranscript::new(b"batch sum check test");
+        assert!(matches!(
+            BatchSumCheck::prove(
+                &[polys[0].clone(), other],
+                &masks[..2],
+                &rhos[..2],
+                &mut transcript
+            ),
+            Err(PolyIOPErrors::InvalidParameters(_))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,343 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! Random masks that are used by a single zk sum check proof.
+//!
+//! Two proofs with the same mask leak the difference of their sum check
+//! messages. A [`MaskHandle`] is consumed by [`prove_zk`], so that reusing
+//! it is a compile error. Masks that do not come from a [`MaskGenerator`],
+//! e.g. deserialized or received over FFI, are wrapped with
+//! [`MaskHandle::from_parts`]; their random tag is absorbed into the
+//! transcript and, with the `mask-registry` feature, `prove_zk` rejects a
+//! tag it has already seen in this process.
+
+use super::{prove_with_rho, verify_with_rho, ZkConfig, ZkSumCheckSubClaim};
+use crate::poly_iop::{
+    errors::PolyIOPErrors,
+    structs::{IOPProof, RandomMaskPolynomial},
+};
+use arithmetic::{VPAuxInfo, VirtualPolynomial};
+use ark_ff::PrimeField;
+#[cfg(feature = "mask-generator")]
+use ark_std::rand::{RngCore, SeedableRng};
+#[cfg(feature = "mask-generator")]
+use rand_chacha::ChaCha20Rng;
+use transcript::IOPTranscript;
+
+/// The random tag of a mask.
+pub type MaskTag = [u8; 32];
+
+/// A random mask polynomial together with its sum over the boolean
+/// hypercube, for a single proof.
+///
+/// The handle is neither `Clone` nor `Copy`, and [`prove_zk`] takes it by
+/// value:
+///
+/// ```compile_fail
+/// use arithmetic::VirtualPolynomial;
+/// use ark_bls12_381::Fr;
+/// use ark_std::test_rng;
+/// use subroutines::poly_iop::prelude::{prove_zk, MaskGenerator, PolyIOPErrors, ZkSumCheckProtocol};
+///
+/// # fn main() -> Result<(), PolyIOPErrors> {
+/// let mut rng = test_rng();
+/// let (poly, _) = VirtualPolynomial::<Fr>::rand(4, (2, 3), 2, &mut rng)?;
+/// let mask = MaskGenerator::new(&mut rng).next_mask(4, 2);
+///
+/// let mut transcript = ZkSumCheckProtocol::<Fr>::init_transcript();
+/// prove_zk(&poly, mask, &mut transcript)?;
+/// let mut transcript = ZkSumCheckProtocol::<Fr>::init_transcript();
+/// // error: use of moved value: `mask`
+/// prove_zk(&poly, mask, &mut transcript)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, PartialEq)]
+pub struct MaskHandle<F: PrimeField> {
+    mask: RandomMaskPolynomial<F>,
+    sum: F,
+}
+
+impl<F: PrimeField> MaskHandle<F> {
+    /// Wrap a mask that does not come from a [`MaskGenerator`], together
+    /// with its sum.
+    ///
+    /// Nothing prevents wrapping the same mask twice; enable the
+    /// `mask-registry` feature to reject the second proof at runtime.
+    pub fn from_parts(mask: RandomMaskPolynomial<F>, sum: F) -> Self {
+        Self { mask, sum }
+    }
+
+    /// The number of variables of the mask.
+    pub fn num_vars(&self) -> usize {
+        self.mask.evaluations.len()
+    }
+
+    /// The degree of the mask in each variable.
+    pub fn degree(&self) -> usize {
+        self.mask
+            .evaluations
+            .first()
+            .map_or(0, |evals| evals.len() - 1)
+    }
+
+    /// The sum of the mask over the boolean hypercube.
+    pub fn sum(&self) -> F {
+        self.sum
+    }
+
+    /// The random tag of the mask.
+    pub fn tag(&self) -> &MaskTag {
+        &self.mask.tag
+    }
+
+    /// The mask polynomial.
+    pub fn mask(&self) -> &RandomMaskPolynomial<F> {
+        &self.mask
+    }
+}
+
+/// Generates fresh masks from a seeded RNG, e.g. for a batch of proofs.
+///
+/// ```
+/// use ark_bls12_381::Fr;
+/// use subroutines::poly_iop::prelude::MaskGenerator;
+///
+/// let mut generator = MaskGenerator::from_seed([7u8; 32]);
+/// let masks: Vec<_> = (0..4).map(|_| generator.next_mask::<Fr>(10, 3)).collect();
+/// assert!(masks.iter().all(|mask| mask.num_vars() == 10 && mask.degree() == 3));
+/// ```
+///
+/// Requires the `mask-generator` feature, on by default.
+#[cfg(feature = "mask-generator")]
+#[derive(Clone, Debug)]
+pub struct MaskGenerator {
+    rng: ChaCha20Rng,
+}
+
+#[cfg(feature = "mask-generator")]
+impl MaskGenerator {
+    /// Create a generator seeded from `rng`.
+    pub fn new<R: RngCore>(rng: &mut R) -> Self {
+        let mut seed = [0u8; 32];
+        rng.fill_bytes(&mut seed);
+        Self::from_seed(seed)
+    }
+
+    /// Create a generator from `seed`. The same seed yields the same masks.
+    pub fn from_seed(seed: [u8; 32]) -> Self {
+        Self {
+            rng: ChaCha20Rng::from_seed(seed),
+        }
+    }
+
+    /// Generate a fresh mask with `nv` variables of degree `degree`.
+    pub fn next_mask<F: PrimeField>(&mut self, nv: usize, degree: usize) -> MaskHandle<F> {
+        let (mask, sum) = RandomMaskPolynomial::rand(nv, degree, &mut self.rng);
+        MaskHandle { mask, sum }
+    }
+}
+
+/// Generate a zk sum check proof of `poly` masked with `rho` times `mask`,
+/// whose claimed sum is `poly`'s sum plus `rho * mask.sum()`.
+///
+/// The tag of the mask is absorbed into the transcript, and `rho` is then
+/// sampled from it, so that the mask is fixed before `rho` is known. Returns
+/// the proof, the tag to be sent along with it, and `rho`. With the
+/// `mask-registry` feature, a tag that has already been used in this
+/// process is rejected with `PolyIOPErrors::MaskReused`.
+pub fn prove_zk<F: PrimeField>(
+    poly: &VirtualPolynomial<F>,
+    mask: MaskHandle<F>,
+    transcript: &mut IOPTranscript<F>,
+) -> Result<(IOPProof<F>, MaskTag, F), PolyIOPErrors> {
+    #[cfg(feature = "mask-registry")]
+    registry::register(mask.tag())?;
+
+    let rho = sample_rho(mask.tag(), transcript)?;
+    let proof = prove_with_rho(poly, &mask.mask, &rho, transcript)?;
+    Ok((proof, mask.mask.tag, rho))
+}
+
+/// Verify a proof generated by [`prove_zk`] with a mask of tag `tag`, that
+/// the polynomial sums to `sum` and the mask to `mask_sum`, re-deriving
+/// `rho` from the transcript in the same way.
+///
+/// Returns the subclaim, which is about the polynomial plus `rho` times the
+/// mask, and `rho`.
+pub fn verify_zk<F: PrimeField>(
+    sum: F,
+    mask_sum: F,
+    proof: &IOPProof<F>,
+    tag: &MaskTag,
+    aux_info: &VPAuxInfo<F>,
+    config: &ZkConfig,
+    transcript: &mut IOPTranscript<F>,
+) -> Result<(ZkSumCheckSubClaim<F>, F), PolyIOPErrors> {
+    let rho = sample_rho(tag, transcript)?;
+    let subclaim = verify_with_rho(
+        sum + rho * mask_sum,
+        proof,
+        aux_info,
+        transcript,
+        aux_info.num_variables,
+        config.mask_degree,
+    )?;
+    Ok((subclaim, rho))
+}
+
+/// Absorb the tag of the mask and sample the masking coefficient `rho`.
+fn sample_rho<F: PrimeField>(
+    tag: &MaskTag,
+    transcript: &mut IOPTranscript<F>,
+) -> Result<F, PolyIOPErrors> {
+    transcript.append_message(b"mask tag", tag)?;
+    Ok(transcript.get_and_append_challenge(b"rho")?)
+}
+
+/// The process-local registry of the mask tags used by [`prove_zk`].
+///
+/// It keeps every tag for the lifetime of the process, i.e. 32 bytes per
+/// proof.
+#[cfg(feature = "mask-registry")]
+mod registry {
+    use super::MaskTag;
+    use crate::poly_iop::errors::PolyIOPErrors;
+    use std::{
+        collections::HashSet,
+        sync::{Mutex, OnceLock},
+    };
+
+    static USED_TAGS: OnceLock<Mutex<HashSet<MaskTag>>> = OnceLock::new();
+
+    /// Record `tag`, or fail if it has already been recorded.
+    pub(super) fn register(tag: &MaskTag) -> Result<(), PolyIOPErrors> {
+        let mut used = USED_TAGS
+            .get_or_init(Default::default)
+            .lock()
+            .map_err(|_| {
+                PolyIOPErrors::InvalidProver("mask registry lock is poisoned".to_string())
+            })?;
+        if !used.insert(*tag) {
+            return Err(PolyIOPErrors::MaskReused);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poly_iop::{zk_sum_check::ZkSumCheck, PolyIOP};
+    use ark_bls12_381::Fr;
+    use ark_std::test_rng;
+
+    #[test]
+    fn test_prove_zk() -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        let nv = 5;
+        let config = ZkConfig { mask_degree: 3 };
+        let (poly, sum) = VirtualPolynomial::<Fr>::rand(nv, (2, 3), 2, &mut rng)?;
+        let mut generator = MaskGenerator::new(&mut rng);
+
+        let mask = generator.next_mask(nv, config.mask_degree);
+        let mask_poly = mask.mask().clone();
+        let mask_sum = mask.sum();
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr>>::init_transcript();
+        let (proof, tag, rho) = prove_zk(&poly, mask, &mut transcript)?;
+        assert_eq!(tag, mask_poly.tag);
+
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr>>::init_transcript();
+        let (subclaim, verifier_rho) = verify_zk(
+            sum,
+            mask_sum,
+            &proof,
+            &tag,
+            &poly.aux_info,
+            &config,
+            &mut transcript,
+        )?;
+        assert_eq!(verifier_rho, rho);
+        assert_eq!(
+            poly.evaluate(&subclaim.point)? + rho * mask_poly.eval(&subclaim.point)?,
+            subclaim.expected_evaluation
+        );
+
+        // rho is bound to the tag: another tag yields another rho, for which
+        // the proof does not verify
+        let mut other_tag = tag;
+        other_tag[0] ^= 1;
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr>>::init_transcript();
+        let res = verify_zk(
+            sum,
+            mask_sum,
+            &proof,
+            &other_tag,
+            &poly.aux_info,
+            &config,
+            &mut transcript,
+        );
+        assert!(res.map_or(true, |(subclaim, rho)| {
+            poly.evaluate(&subclaim.point).unwrap() + rho * mask_poly.eval(&subclaim.point).unwrap()
+                != subclaim.expected_evaluation
+        }));
+
+        // the next mask is a fresh one
+        let next = generator.next_mask::<Fr>(nv, config.mask_degree);
+        assert_ne!(next.tag(), &tag);
+        assert_ne!(next.mask().evaluations, mask_poly.evaluations);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mask_generator_seed() {
+        let masks = |seed| {
+            let mut generator = MaskGenerator::from_seed(seed);
+            (0..3)
+                .map(|_| generator.next_mask::<Fr>(4, 2))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(masks([1u8; 32]), masks([1u8; 32]));
+        assert_ne!(masks([1u8; 32]), masks([2u8; 32]));
+
+        let mask = masks([1u8; 32]).remove(0);
+        assert_eq!(mask.num_vars(), 4);
+        assert_eq!(mask.degree(), 2);
+        // the sum is the one of the mask polynomial
+        let expected =
+            RandomMaskPolynomial::<Fr>::rand(4, 2, &mut ChaCha20Rng::from_seed([1u8; 32]));
+        assert_eq!((mask.mask().clone(), mask.sum()), expected);
+    }
+
+    #[cfg(feature = "mask-registry")]
+    #[test]
+    fn test_mask_reuse_rejected() -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        let nv = 4;
+        let (poly, _) = VirtualPolynomial::<Fr>::rand(nv, (2, 3), 2, &mut rng)?;
+
+        // a mask that went through serialization is wrapped twice
+        let (mask, sum) = RandomMaskPolynomial::<Fr>::rand(nv, 3, &mut rng);
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr>>::init_transcript();
+        prove_zk(
+            &poly,
+            MaskHandle::from_parts(mask.clone(), sum),
+            &mut transcript,
+        )?;
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr>>::init_transcript();
+        assert!(matches!(
+            prove_zk(&poly, MaskHandle::from_parts(mask, sum), &mut transcript),
+            Err(PolyIOPErrors::MaskReused)
+        ));
+
+        // a fresh mask is still accepted
+        let (mask, sum) = RandomMaskPolynomial::<Fr>::rand(nv, 3, &mut rng);
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr>>::init_transcript();
+        prove_zk(&poly, MaskHandle::from_parts(mask, sum), &mut transcript)?;
+        Ok(())
+    }
+}
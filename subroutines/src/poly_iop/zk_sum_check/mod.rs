@@ -1,23 +1,114 @@
 use crate::poly_iop::{
     errors::PolyIOPErrors,
-    structs::{IOPProof, IOPVerifierState},
+    structs::{IOPProof, IOPVerifierState, VerifierTrace},
+    sum_check::{check_aux_info, check_proof_shape, MAX_DEGREE},
     PolyIOP,
 };
 use arithmetic::{VPAuxInfo, VirtualPolynomial};
 use ark_ff::PrimeField;
 use ark_poly::DenseMultilinearExtension;
+use ark_serialize::CanonicalSerialize;
 use ark_std::{end_timer, start_timer};
 use prover::ZkSumCheckProverState;
 use std::{fmt::Debug, sync::Arc};
-use transcript::IOPTranscript;
+use transcript::{IOPTranscript, Transcript};
 
 use super::structs::RandomMaskPolynomial;
 
+pub mod accumulator;
+pub mod batch;
+pub mod mask;
 mod prover;
 mod verifier;
 
+/// The label of the auxiliary information in the transcript. It differs from
+/// the one of the plain sum check, so that a proof of either is rejected by
+/// the verifier of the other.
+const AUX_INFO_LABEL: &[u8] = b"zk aux info";
+
+/// The configuration of a zk sum check, shared by the prover and the
+/// verifier.
+///
+/// The round polynomials have the degree of the polynomial or of the mask,
+/// whichever is larger, so the verifier must know the mask degree from the
+/// configuration rather than from the prover. If the polynomial declares the
+/// degree of each variable, see [`VPAuxInfo::per_variable_degree`], the mask
+/// follows these degrees instead, and so do the round polynomials.
+///
+/// There is no switch for hiding commitments: the zk sum check commits to
+/// nothing itself. Its caller commits to the multilinear polynomials of the
+/// virtual polynomial, and may do so with
+/// [`MultilinearKzgPCS::commit_hiding`](crate::pcs::prelude::MultilinearKzgPCS::commit_hiding).
+/// The mask is a sum of univariate polynomials, which that scheme does not
+/// commit to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ZkConfig {
+    /// the degree of the random mask polynomial in each variable
+    pub mask_degree: usize,
+}
+
+impl ZkConfig {
+    /// The degree of the round polynomials for a polynomial with `aux_info`.
+    pub fn effective_degree<F: PrimeField>(&self, aux_info: &VPAuxInfo<F>) -> usize {
+        ark_std::cmp::max(aux_info.max_degree, self.mask_degree)
+    }
+
+    /// The degree of the mask in variable `variable`: the degree of the
+    /// variable if the polynomial with `aux_info` declares it, and
+    /// `mask_degree` otherwise.
+    pub fn mask_variable_degree<F: PrimeField>(
+        &self,
+        aux_info: &VPAuxInfo<F>,
+        variable: usize,
+    ) -> usize {
+        aux_info
+            .per_variable_degree
+            .get(variable)
+            .copied()
+            .unwrap_or(self.mask_degree)
+    }
+
+    /// Check the configuration against the polynomial with `aux_info`: both
+    /// the polynomial and the mask degree must be within the bounds of the
+    /// sum check.
+    pub fn check<F: PrimeField>(&self, aux_info: &VPAuxInfo<F>) -> Result<(), PolyIOPErrors> {
+        check_aux_info(aux_info)?;
+        if self.mask_degree == 0 || self.mask_degree > MAX_DEGREE {
+            return Err(PolyIOPErrors::InvalidDegree(self.mask_degree, MAX_DEGREE));
+        }
+        Ok(())
+    }
+
+    /// Check that `mask_poly` has the configured number of variables and
+    /// degree in each of them, see [`Self::mask_variable_degree`].
+    pub fn check_mask<F: PrimeField>(
+        &self,
+        aux_info: &VPAuxInfo<F>,
+        mask_poly: &RandomMaskPolynomial<F>,
+    ) -> Result<(), PolyIOPErrors> {
+        self.check(aux_info)?;
+        if mask_poly.evaluations.len() != aux_info.num_variables
+            || mask_poly
+                .evaluations
+                .iter()
+                .enumerate()
+                .any(|(i, evals)| evals.len() != self.mask_variable_degree(aux_info, i) + 1)
+        {
+            return Err(PolyIOPErrors::InvalidParameters(format!(
+                "mask polynomial does not have {} variables of the configured degrees",
+                aux_info.num_variables
+            )));
+        }
+        Ok(())
+    }
+}
+
 /// Trait for doing zk sum check protocols.
-pub trait ZkSumCheck<F: PrimeField> {
+///
+/// The transcript `T` is an [`IOPTranscript`] by default, and may be any
+/// other [`Transcript`], e.g. a `PoseidonTranscript` for proofs verified in
+/// a circuit.
+pub trait ZkSumCheck<F: PrimeField, T = IOPTranscript<F>> {
     type VirtualPolynomial;
     type VPAuxInfo;
     type MultilinearExtension;
@@ -40,9 +131,15 @@ pub trait ZkSumCheck<F: PrimeField> {
     /// SumCheck prover/verifier.
     fn init_transcript() -> Self::Transcript;
 
+    /// Initialize a transcript as [`ZkSumCheck::init_transcript`] does, or,
+    /// given a `domain`, from the domain separator `domain`, so that the
+    /// checks of a larger protocol can run on independent transcripts.
+    fn init_transcript_with_domain(domain: Option<&[u8]>) -> Self::Transcript;
+
     /// Generate proof of the sum of polynomial over {0,1}^`num_vars`
     ///
     /// The polynomial is represented in the form of a VirtualPolynomial.
+    #[deprecated(note = "rho must come from the transcript, use `prove_bound`")]
     fn prove(
         poly: &Self::VirtualPolynomial,
         mask_poly: &Self::RandomMaskPolynomial,
@@ -51,14 +148,79 @@ pub trait ZkSumCheck<F: PrimeField> {
     ) -> Result<Self::SumCheckProof, PolyIOPErrors>;
 
     /// Verify the claimed sum using the proof
+    #[deprecated(note = "rho must come from the transcript, use `verify_bound`")]
     fn verify(
         sum: F,
         proof: &Self::SumCheckProof,
         aux_info: &Self::VPAuxInfo,
         transcript: &mut Self::Transcript,
         mask_poly_nv: Self::MPNumV,
-        mask_poly_degree: Self::MPDeg
+        mask_poly_degree: Self::MPDeg,
     ) -> Result<Self::SumCheckSubClaim, PolyIOPErrors>;
+
+    /// Verify the claimed sums `sums` with the proofs `proofs` of
+    /// polynomials sharing `aux_info`, as `verify` does for each proof in
+    /// turn on the same transcript.
+    ///
+    /// The round checks of all proofs are combined with powers of a scalar
+    /// sampled from the transcript after the last proof, with label
+    /// `batch sum check`, and checked at once. If they fail, the proofs are
+    /// checked one by one to report the first failing proof, see
+    /// `PolyIOPErrors::BatchProofFailed`.
+    fn batch_verify(
+        sums: &[F],
+        proofs: &[Self::SumCheckProof],
+        aux_info: &Self::VPAuxInfo,
+        transcript: &mut Self::Transcript,
+        mask_poly_nv: Self::MPNumV,
+        mask_poly_degree: Self::MPDeg,
+    ) -> Result<Vec<Self::SumCheckSubClaim>, PolyIOPErrors>;
+
+    /// Generate proof of the sum of `poly` masked with `rho` times
+    /// `mask_poly`, where `rho` is sampled from the transcript right after
+    /// absorbing `mask_commitment`, so the mask is fixed before `rho` is
+    /// known.
+    ///
+    /// Returns the proof and `rho`; the claimed sum is the sum of `poly`
+    /// plus `rho` times the sum of the mask.
+    fn prove_bound<C: CanonicalSerialize>(
+        poly: &Self::VirtualPolynomial,
+        mask_poly: &Self::RandomMaskPolynomial,
+        mask_commitment: &C,
+        transcript: &mut Self::Transcript,
+    ) -> Result<(Self::SumCheckProof, F), PolyIOPErrors>;
+
+    /// Verify a proof generated by `prove_bound` that the polynomial sums to
+    /// `sum` and the mask to `mask_sum`, re-deriving `rho` from the
+    /// transcript in the same way.
+    ///
+    /// Returns the subclaim, which is about the polynomial plus `rho` times
+    /// the mask, and `rho`.
+    fn verify_bound<C: CanonicalSerialize>(
+        sum: F,
+        mask_sum: F,
+        proof: &Self::SumCheckProof,
+        mask_commitment: &C,
+        aux_info: &Self::VPAuxInfo,
+        transcript: &mut Self::Transcript,
+        mask_poly_degree: Self::MPDeg,
+    ) -> Result<(Self::SumCheckSubClaim, F), PolyIOPErrors>;
+
+    /// Same as `verify_bound`, and also return the claimed sum derived in
+    /// each round.
+    ///
+    /// If a round is not consistent with its claimed sum, the trace up to
+    /// that round is attached to the error, see
+    /// `PolyIOPErrors::verifier_trace`.
+    fn verify_traced<C: CanonicalSerialize>(
+        sum: F,
+        mask_sum: F,
+        proof: &Self::SumCheckProof,
+        mask_commitment: &C,
+        aux_info: &Self::VPAuxInfo,
+        transcript: &mut Self::Transcript,
+        mask_poly_degree: Self::MPDeg,
+    ) -> Result<(Self::SumCheckSubClaim, F, VerifierTrace<F>), PolyIOPErrors>;
 }
 
 /// Trait for zk sum check protocol prover side APIs.
@@ -72,7 +234,10 @@ where
 
     /// Initialize the prover state to argue for the sum of the input polynomial
     /// over {0,1}^`num_vars`.
-    fn prover_init(polynomial: &Self::VirtualPolynomial, mask_poly: &Self::RandomMaskPolynomial) -> Result<Self, PolyIOPErrors>;
+    fn prover_init(
+        polynomial: &Self::VirtualPolynomial,
+        mask_poly: &Self::RandomMaskPolynomial,
+    ) -> Result<Self, PolyIOPErrors>;
 
     /// Receive message from verifier, generate prover message, and proceed to
     /// next round.
@@ -94,7 +259,9 @@ pub trait ZkSumCheckVerifier<F: PrimeField> {
     type MPDeg;
 
     /// Initialize the verifier's state.
-    fn verifier_init(index_info: &Self::VPAuxInfo) -> Self;
+    fn verifier_init(index_info: &Self::VPAuxInfo) -> Result<Self, PolyIOPErrors>
+    where
+        Self: Sized;
 
     /// Run verifier for the current round, given a prover message.
     ///
@@ -120,7 +287,7 @@ pub trait ZkSumCheckVerifier<F: PrimeField> {
         &self,
         asserted_sum: &F,
         mask_poly_nv: Self::MPNumV,
-        mask_poly_degree: Self::MPDeg
+        mask_poly_degree: Self::MPDeg,
     ) -> Result<Self::ZkSumCheckSubClaim, PolyIOPErrors>;
 }
 
@@ -135,14 +302,59 @@ pub struct ZkSumCheckSubClaim<F: PrimeField> {
     pub expected_evaluation: F,
 }
 
-impl<F: PrimeField> ZkSumCheck<F> for PolyIOP<F> {
+impl<F: PrimeField> ZkSumCheckSubClaim<F> {
+    /// Combine `subclaims` at the same point into one: the combination of
+    /// their polynomials with `scalars` evaluates at the point to the same
+    /// combination of their expected evaluations. The polynomials are then
+    /// opened once, at the common point, instead of once per subclaim.
+    ///
+    /// The scalars must be sampled after the subclaims are fixed, e.g.,
+    /// from the transcript, for the combination to be as sound as checking
+    /// each subclaim.
+    pub fn combine(subclaims: &[Self], scalars: &[F]) -> Result<Self, PolyIOPErrors> {
+        let point = match subclaims.first() {
+            Some(subclaim) => subclaim.point.clone(),
+            None => {
+                return Err(PolyIOPErrors::InvalidParameters(
+                    "no subclaim to combine".to_string(),
+                ))
+            },
+        };
+        if scalars.len() != subclaims.len() {
+            return Err(PolyIOPErrors::InvalidParameters(format!(
+                "{} scalars for {} subclaims",
+                scalars.len(),
+                subclaims.len()
+            )));
+        }
+        if let Some(index) = subclaims
+            .iter()
+            .position(|subclaim| subclaim.point != point)
+        {
+            return Err(PolyIOPErrors::InvalidParameters(format!(
+                "subclaim {} is not at the point of the first one",
+                index
+            )));
+        }
+        Ok(Self {
+            point,
+            expected_evaluation: subclaims
+                .iter()
+                .zip(scalars.iter())
+                .map(|(subclaim, scalar)| subclaim.expected_evaluation * scalar)
+                .sum(),
+        })
+    }
+}
+
+impl<F: PrimeField, T: Transcript<F>> ZkSumCheck<F, T> for PolyIOP<F> {
     type SumCheckProof = IOPProof<F>;
     type VirtualPolynomial = VirtualPolynomial<F>;
     type VPAuxInfo = VPAuxInfo<F>;
     type MultilinearExtension = Arc<DenseMultilinearExtension<F>>;
     type RandomMaskPolynomial = RandomMaskPolynomial<F>;
     type SumCheckSubClaim = ZkSumCheckSubClaim<F>;
-    type Transcript = IOPTranscript<F>;
+    type Transcript = T;
     type MPDeg = usize;
     type MPNumV = usize;
 
@@ -155,43 +367,25 @@ impl<F: PrimeField> ZkSumCheck<F> for PolyIOP<F> {
 
     fn init_transcript() -> Self::Transcript {
         let start = start_timer!(|| "init transcript");
-        let res = IOPTranscript::<F>::new(b"Initializing SumCheck transcript");
+        let res = T::new(b"Initializing SumCheck transcript");
         end_timer!(start);
         res
     }
 
+    fn init_transcript_with_domain(domain: Option<&[u8]>) -> Self::Transcript {
+        match domain {
+            Some(domain) => T::with_domain_separator(domain),
+            None => <Self as ZkSumCheck<F, T>>::init_transcript(),
+        }
+    }
+
     fn prove(
         poly: &Self::VirtualPolynomial,
         mask_poly: &Self::RandomMaskPolynomial,
         rho: &F,
         transcript: &mut Self::Transcript,
     ) -> Result<Self::SumCheckProof, PolyIOPErrors> {
-        let start = start_timer!(|| "sum check prove");
-
-        transcript.append_serializable_element(b"aux info", &poly.aux_info)?;
-
-        let mut prover_state = ZkSumCheckProverState::prover_init(poly, mask_poly)?;
-        let mut challenge = None;
-        let mut prover_msgs = Vec::with_capacity(poly.aux_info.num_variables);
-        for _ in 0..poly.aux_info.num_variables {
-            let prover_msg =
-                ZkSumCheckProverState::prove_round_and_update_state(&mut prover_state, rho, &challenge)?;
-            transcript.append_serializable_element(b"prover msg", &prover_msg)?;
-            prover_msgs.push(prover_msg);
-            challenge = Some(transcript.get_and_append_challenge(b"Internal round")?);
-            assert!(challenge.unwrap() != F::zero());
-            assert!(challenge.unwrap() != F::one());
-        }
-        // pushing the last challenge point to the state
-        if let Some(p) = challenge {
-            prover_state.sum_check_prover_state.challenges.push(p)
-        };
-
-        end_timer!(start);
-        Ok(IOPProof {
-            point: prover_state.sum_check_prover_state.challenges,
-            proofs: prover_msgs,
-        })
+        prove_with_rho(poly, mask_poly, rho, transcript)
     }
 
     fn verify(
@@ -200,35 +394,316 @@ impl<F: PrimeField> ZkSumCheck<F> for PolyIOP<F> {
         aux_info: &Self::VPAuxInfo,
         transcript: &mut Self::Transcript,
         mask_poly_nv: usize,
-        mask_poly_degree: usize
+        mask_poly_degree: usize,
     ) -> Result<Self::SumCheckSubClaim, PolyIOPErrors> {
-        let start = start_timer!(|| "sum check verify");
+        verify_with_rho(
+            claimed_sum,
+            proof,
+            aux_info,
+            transcript,
+            mask_poly_nv,
+            mask_poly_degree,
+        )
+    }
+
+    fn batch_verify(
+        sums: &[F],
+        proofs: &[Self::SumCheckProof],
+        aux_info: &Self::VPAuxInfo,
+        transcript: &mut Self::Transcript,
+        mask_poly_nv: usize,
+        mask_poly_degree: usize,
+    ) -> Result<Vec<Self::SumCheckSubClaim>, PolyIOPErrors> {
+        batch_verify_with_rho(
+            sums,
+            proofs,
+            aux_info,
+            transcript,
+            mask_poly_nv,
+            mask_poly_degree,
+        )
+    }
+
+    fn prove_bound<C: CanonicalSerialize>(
+        poly: &Self::VirtualPolynomial,
+        mask_poly: &Self::RandomMaskPolynomial,
+        mask_commitment: &C,
+        transcript: &mut Self::Transcript,
+    ) -> Result<(Self::SumCheckProof, F), PolyIOPErrors> {
+        let rho = sample_rho(mask_commitment, transcript)?;
+        let proof = prove_with_rho(poly, mask_poly, &rho, transcript)?;
+        Ok((proof, rho))
+    }
+
+    fn verify_bound<C: CanonicalSerialize>(
+        sum: F,
+        mask_sum: F,
+        proof: &Self::SumCheckProof,
+        mask_commitment: &C,
+        aux_info: &Self::VPAuxInfo,
+        transcript: &mut Self::Transcript,
+        mask_poly_degree: usize,
+    ) -> Result<(Self::SumCheckSubClaim, F), PolyIOPErrors> {
+        <Self as ZkSumCheck<F, T>>::verify_traced(
+            sum,
+            mask_sum,
+            proof,
+            mask_commitment,
+            aux_info,
+            transcript,
+            mask_poly_degree,
+        )
+        .map(|(subclaim, rho, _)| (subclaim, rho))
+    }
+
+    fn verify_traced<C: CanonicalSerialize>(
+        sum: F,
+        mask_sum: F,
+        proof: &Self::SumCheckProof,
+        mask_commitment: &C,
+        aux_info: &Self::VPAuxInfo,
+        transcript: &mut Self::Transcript,
+        mask_poly_degree: usize,
+    ) -> Result<(Self::SumCheckSubClaim, F, VerifierTrace<F>), PolyIOPErrors> {
+        let rho = sample_rho(mask_commitment, transcript)?;
+        let (subclaim, trace) = verify_traced_with_rho(
+            sum + rho * mask_sum,
+            proof,
+            aux_info,
+            transcript,
+            aux_info.num_variables,
+            mask_poly_degree,
+        )?;
+        Ok((subclaim, rho, trace))
+    }
+}
+
+/// Absorb `mask_commitment` and sample the masking coefficient `rho`.
+fn sample_rho<F: PrimeField, C: CanonicalSerialize, T: Transcript<F>>(
+    mask_commitment: &C,
+    transcript: &mut T,
+) -> Result<F, PolyIOPErrors> {
+    transcript.append_serializable_element(b"mask commitment", mask_commitment)?;
+    Ok(transcript.get_and_append_challenge(b"rho")?)
+}
+
+/// Reject a challenge of round `round` of 0 or 1, the points at which the
+/// prover has already evaluated the round polynomial.
+pub(crate) fn check_challenge<F: PrimeField>(
+    round: usize,
+    challenge: F,
+) -> Result<F, PolyIOPErrors> {
+    if challenge.is_zero() || challenge.is_one() {
+        return Err(PolyIOPErrors::InvalidChallenge {
+            round,
+            value: format!("{} is 0 or 1", challenge),
+        });
+    }
+    Ok(challenge)
+}
+
+/// The zk sum check with a caller supplied `rho`, for protocols that already
+/// derive `rho` from the transcript after fixing the mask.
+pub(crate) fn prove_with_rho<F: PrimeField, T: Transcript<F>>(
+    poly: &VirtualPolynomial<F>,
+    mask_poly: &RandomMaskPolynomial<F>,
+    rho: &F,
+    transcript: &mut T,
+) -> Result<IOPProof<F>, PolyIOPErrors> {
+    let start = start_timer!(|| "sum check prove");
+
+    transcript.append_message(AUX_INFO_LABEL, &poly.aux_info.transcript_bytes())?;
+
+    let mut prover_state = ZkSumCheckProverState::prover_init(poly, mask_poly)?;
+    let mut challenge = None;
+    let mut prover_msgs = Vec::with_capacity(poly.aux_info.num_variables);
+    for round in 0..poly.aux_info.num_variables {
+        let prover_msg = ZkSumCheckProverState::prove_round_and_update_state(
+            &mut prover_state,
+            rho,
+            &challenge,
+        )?;
+        transcript.append_serializable_element(b"prover msg", &prover_msg)?;
+        prover_msgs.push(prover_msg);
+        challenge = Some(check_challenge(
+            round,
+            transcript.get_and_append_challenge(b"Internal round")?,
+        )?);
+    }
+    // pushing the last challenge point to the state
+    if let Some(p) = challenge {
+        prover_state.sum_check_prover_state.challenges.push(p)
+    };
+
+    end_timer!(start);
+    Ok(IOPProof {
+        point: prover_state.sum_check_prover_state.challenges,
+        proofs: prover_msgs,
+    })
+}
+
+/// See [`prove_with_rho`].
+pub(crate) fn verify_with_rho<F: PrimeField, T: Transcript<F>>(
+    claimed_sum: F,
+    proof: &IOPProof<F>,
+    aux_info: &VPAuxInfo<F>,
+    transcript: &mut T,
+    mask_poly_nv: usize,
+    mask_poly_degree: usize,
+) -> Result<ZkSumCheckSubClaim<F>, PolyIOPErrors> {
+    verify_traced_with_rho(
+        claimed_sum,
+        proof,
+        aux_info,
+        transcript,
+        mask_poly_nv,
+        mask_poly_degree,
+    )
+    .map(|(subclaim, _)| subclaim)
+}
+
+/// See [`prove_with_rho`] and [`ZkSumCheck::verify_traced`].
+pub(crate) fn verify_traced_with_rho<F: PrimeField, T: Transcript<F>>(
+    claimed_sum: F,
+    proof: &IOPProof<F>,
+    aux_info: &VPAuxInfo<F>,
+    transcript: &mut T,
+    mask_poly_nv: usize,
+    mask_poly_degree: usize,
+) -> Result<(ZkSumCheckSubClaim<F>, VerifierTrace<F>), PolyIOPErrors> {
+    let start = start_timer!(|| "sum check verify");
 
-        transcript.append_serializable_element(b"aux info", aux_info)?;
-        let mut verifier_state = IOPVerifierState::verifier_init(aux_info);
-        for i in 0..aux_info.num_variables {
-            let prover_msg = proof.proofs.get(i).expect("proof is incomplete");
+    ZkConfig {
+        mask_degree: mask_poly_degree,
+    }
+    .check(aux_info)?;
+    let mut verifier_state = IOPVerifierState::verifier_init(aux_info)?;
+    // the messages of the masked polynomial have the degree of the larger of
+    // the polynomial and the mask, or of their variable
+    check_proof_shape(
+        proof,
+        &VPAuxInfo {
+            max_degree: aux_info.max_degree.max(mask_poly_degree),
+            ..aux_info.clone()
+        },
+    )?;
+    transcript.append_message(AUX_INFO_LABEL, &aux_info.transcript_bytes())?;
+    for prover_msg in proof.proofs.iter() {
+        transcript.append_serializable_element(b"prover msg", prover_msg)?;
+        verifier_state.verify_round_masked(prover_msg, transcript)?;
+    }
+
+    let res = verifier_state.check_and_trace_masked(&claimed_sum, mask_poly_nv, mask_poly_degree);
+
+    end_timer!(start);
+    res
+}
+
+/// See [`ZkSumCheck::batch_verify`].
+pub(crate) fn batch_verify_with_rho<F: PrimeField, T: Transcript<F>>(
+    sums: &[F],
+    proofs: &[IOPProof<F>],
+    aux_info: &VPAuxInfo<F>,
+    transcript: &mut T,
+    mask_poly_nv: usize,
+    mask_poly_degree: usize,
+) -> Result<Vec<ZkSumCheckSubClaim<F>>, PolyIOPErrors> {
+    let start = start_timer!(|| format!("batch sum check verify of {} proofs", proofs.len()));
+
+    if sums.len() != proofs.len() {
+        return Err(PolyIOPErrors::InvalidParameters(format!(
+            "{} sums for {} proofs",
+            sums.len(),
+            proofs.len()
+        )));
+    }
+    ZkConfig {
+        mask_degree: mask_poly_degree,
+    }
+    .check(aux_info)?;
+    let shape = VPAuxInfo {
+        max_degree: aux_info.max_degree.max(mask_poly_degree),
+        ..aux_info.clone()
+    };
+
+    // run the rounds of each proof, deferring all the checks
+    let mut states = Vec::with_capacity(proofs.len());
+    let mut expected = Vec::with_capacity(proofs.len());
+    for (index, (sum, proof)) in sums.iter().zip(proofs.iter()).enumerate() {
+        let at_index = |e| PolyIOPErrors::batch_proof_failed(index, e);
+        check_proof_shape(proof, &shape).map_err(at_index)?;
+        let mut verifier_state = IOPVerifierState::verifier_init(aux_info)?;
+        transcript.append_message(AUX_INFO_LABEL, &aux_info.transcript_bytes())?;
+        for prover_msg in proof.proofs.iter() {
             transcript.append_serializable_element(b"prover msg", prover_msg)?;
-            IOPVerifierState::verify_round_and_update_state(
-                &mut verifier_state,
-                prover_msg,
-                transcript,
-            )?;
+            verifier_state.verify_round_masked(prover_msg, transcript)?;
         }
+        expected.push(
+            verifier_state
+                .expected_sums_masked(sum, mask_poly_nv, mask_poly_degree)
+                .map_err(at_index)?,
+        );
+        states.push(verifier_state);
+    }
 
-        let res = IOPVerifierState::check_and_generate_subclaim(&verifier_state, &claimed_sum, mask_poly_nv, mask_poly_degree);
-
-        end_timer!(start);
-        res
+    // round `i` of proof `k` holds if P(0) + P(1) - expected is zero; check
+    // the combination of proof `k` with the k-th power of `coeff` instead
+    let coeff = transcript.get_and_append_challenge(b"batch sum check")?;
+    let mut combined = vec![F::zero(); aux_info.num_variables];
+    let mut power = F::one();
+    for (state, expected) in states.iter().zip(expected.iter()) {
+        for (round, (evaluations, &expected)) in state
+            .polynomials_received
+            .iter()
+            .zip(expected.iter())
+            .enumerate()
+        {
+            combined[round] += power * (evaluations[0] + evaluations[1] - expected);
+        }
+        power *= coeff;
+    }
+    if combined.iter().any(|c| !c.is_zero()) {
+        for (index, (state, expected)) in states.iter().zip(expected.iter()).enumerate() {
+            state
+                .check_rounds(expected)
+                .map_err(|e| PolyIOPErrors::batch_proof_failed(index, e))?;
+        }
+        return Err(PolyIOPErrors::ShouldNotArrive);
     }
+
+    end_timer!(start);
+    Ok(states
+        .into_iter()
+        .zip(expected)
+        .map(|(state, expected)| ZkSumCheckSubClaim {
+            point: state.challenges,
+            expected_evaluation: expected[aux_info.num_variables],
+        })
+        .collect())
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::poly_iop::strategies::{check, fail, poly_shape, PolyShape};
+    use arbitrary::{Arbitrary, Unstructured};
+    use ark_poly::MultilinearExtension;
     use ark_secp256k1::Fr;
-    use ark_ff::UniformRand;
-    use ark_std::test_rng;
+    use ark_serialize::{Compress, Validate};
+    use ark_std::{test_rng, One, UniformRand, Zero};
+    use proptest::{
+        collection::vec,
+        prelude::{any, Strategy},
+        prop_assert, prop_assert_eq,
+    };
+    use transcript::{PoseidonTranscript, TestTranscript};
+
+    /// The transcript of the tests, a [`transcript::Blake3Transcript`] with
+    /// the `blake3-transcript-tests` feature.
+    #[cfg(not(feature = "blake3-transcript-tests"))]
+    type SumCheckTranscript = IOPTranscript<Fr>;
+    #[cfg(feature = "blake3-transcript-tests")]
+    type SumCheckTranscript = transcript::Blake3Transcript<Fr>;
 
     fn test_sumcheck(
         nv: usize,
@@ -236,39 +711,1043 @@ mod test {
         num_products: usize,
     ) -> Result<(), PolyIOPErrors> {
         let mut rng = test_rng();
-        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr>>::init_transcript();
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript();
 
         let (poly, asserted_sum) =
             VirtualPolynomial::rand(nv, num_multiplicands_range, num_products, &mut rng)?;
         let (mask, sum) = RandomMaskPolynomial::rand(nv, num_multiplicands_range.1, &mut rng);
-        let rho = Fr::rand(&mut rng);
+        let (proof, rho) = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::prove_bound(
+            &poly,
+            &mask,
+            &mask.evaluations,
+            &mut transcript,
+        )?;
         assert!(rho != Fr::from(0));
-        let asserted_sum = asserted_sum + rho * sum; 
-        let proof = <PolyIOP<Fr> as ZkSumCheck<Fr>>::prove(&poly, &mask, &rho, &mut transcript)?;
         let poly_info = poly.aux_info.clone();
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript();
+        let (subclaim, verifier_rho) =
+            <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::verify_bound(
+                asserted_sum,
+                sum,
+                &proof,
+                &mask.evaluations,
+                &poly_info,
+                &mut transcript,
+                mask.evaluations[0].len() - 1,
+            )?;
+        assert_eq!(rho, verifier_rho);
+        let res = poly.evaluate(&subclaim.point).unwrap() + rho * mask.eval(&subclaim.point)?;
+        assert!(res == subclaim.expected_evaluation, "wrong subclaim");
+        Ok(())
+    }
+
+    #[test]
+    fn test_trivial_polynomial() -> Result<(), PolyIOPErrors> {
+        let nv = 10;
+        let num_multiplicands_range = (2, 6);
+        let num_products = 2;
+
+        test_sumcheck(nv, num_multiplicands_range, num_products)
+    }
+
+    #[test]
+    fn test_plain_proofs_rejected() -> Result<(), PolyIOPErrors> {
+        use crate::poly_iop::sum_check::SumCheck;
+
+        let mut rng = test_rng();
+        let nv = 5;
+        // the mask has a lower degree, so the proofs have the same shape
+        let (poly, sum) = VirtualPolynomial::<Fr>::rand(nv, (3, 4), 2, &mut rng)?;
+        let (mask, mask_sum) = RandomMaskPolynomial::<Fr>::rand(nv, 2, &mut rng);
+        let rho = Fr::rand(&mut rng);
+
+        // a plain proof is rejected by the zk verifier
+        let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+        let plain_proof = <PolyIOP<Fr> as SumCheck<Fr>>::prove(&poly, &mut transcript)?;
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript();
+        assert!(
+            verify_with_rho(sum, &plain_proof, &poly.aux_info, &mut transcript, nv, 2).is_err()
+        );
+
+        // a zk proof is rejected by the plain verifier, even for the sum of
+        // the masked polynomial
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript();
+        let zk_proof = prove_with_rho(&poly, &mask, &rho, &mut transcript)?;
+        assert_eq!(
+            plain_proof.proofs[0].evaluations.len(),
+            zk_proof.proofs[0].evaluations.len()
+        );
+        let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+        assert!(<PolyIOP<Fr> as SumCheck<Fr>>::verify(
+            sum + rho * mask_sum,
+            &zk_proof,
+            &poly.aux_info,
+            &mut transcript,
+        )
+        .is_err());
+
+        // each is accepted by its own verifier
+        let mut transcript = <PolyIOP<Fr> as SumCheck<Fr>>::init_transcript();
+        <PolyIOP<Fr> as SumCheck<Fr>>::verify(sum, &plain_proof, &poly.aux_info, &mut transcript)?;
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript();
+        verify_with_rho(
+            sum + rho * mask_sum,
+            &zk_proof,
+            &poly.aux_info,
+            &mut transcript,
+            nv,
+            2,
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_domain_separated_transcripts() -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        let nv = 4;
+        let (poly, sum) = VirtualPolynomial::<Fr>::rand(nv, (2, 3), 2, &mut rng)?;
+        let (mask, mask_sum) = RandomMaskPolynomial::<Fr>::rand(nv, 3, &mut rng);
+        let init = |domain: Option<&[u8]>| {
+            <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript_with_domain(domain)
+        };
+
+        let (proof, rho) = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::prove_bound(
+            &poly,
+            &mask,
+            &mask.evaluations,
+            &mut init(Some(b"zero check")),
+        )?;
+        let verify = |domain: Option<&[u8]>| {
+            <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::verify_bound(
+                sum,
+                mask_sum,
+                &proof,
+                &mask.evaluations,
+                &poly.aux_info,
+                &mut init(domain),
+                3,
+            )
+        };
+        assert_eq!(verify(Some(b"zero check"))?.1, rho);
+        // the same proof under another domain, or none, is rejected
+        assert!(verify(Some(b"perm check")).is_err());
+        assert!(verify(None).is_err());
+
+        // without a domain, the transcript is the usual one
+        let (proof, _) = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::prove_bound(
+            &poly,
+            &mask,
+            &mask.evaluations,
+            &mut init(None),
+        )?;
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript();
+        <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::verify_bound(
+            sum,
+            mask_sum,
+            &proof,
+            &mask.evaluations,
+            &poly.aux_info,
+            &mut transcript,
+            3,
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_poseidon_transcript() -> Result<(), PolyIOPErrors> {
+        type Poseidon = PoseidonTranscript<Fr>;
+
+        let mut rng = test_rng();
+        let nv = 5;
+        let (poly, sum) = VirtualPolynomial::<Fr>::rand(nv, (2, 4), 2, &mut rng)?;
+        let (mask, mask_sum) = RandomMaskPolynomial::<Fr>::rand(nv, 3, &mut rng);
+
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr, Poseidon>>::init_transcript();
+        let (proof, rho) = <PolyIOP<Fr> as ZkSumCheck<Fr, Poseidon>>::prove_bound(
+            &poly,
+            &mask,
+            &mask.evaluations,
+            &mut transcript,
+        )?;
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr, Poseidon>>::init_transcript();
+        let (subclaim, verifier_rho) = <PolyIOP<Fr> as ZkSumCheck<Fr, Poseidon>>::verify_bound(
+            sum,
+            mask_sum,
+            &proof,
+            &mask.evaluations,
+            &poly.aux_info,
+            &mut transcript,
+            3,
+        )?;
+        assert_eq!(rho, verifier_rho);
+        assert_eq!(
+            poly.evaluate(&subclaim.point)? + rho * mask.eval(&subclaim.point)?,
+            subclaim.expected_evaluation
+        );
+
+        // the challenges differ from those of the Merlin transcript
         let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr>>::init_transcript();
-        let subclaim = <PolyIOP<Fr> as ZkSumCheck<Fr>>::verify(
+        assert!(<PolyIOP<Fr> as ZkSumCheck<Fr>>::verify_bound(
+            sum,
+            mask_sum,
+            &proof,
+            &mask.evaluations,
+            &poly.aux_info,
+            &mut transcript,
+            3
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_challenge_at_root() -> Result<(), PolyIOPErrors> {
+        use ark_poly::MultilinearExtension;
+        use ark_std::{One, Zero};
+
+        let mut rng = test_rng();
+        let nv = 3;
+        let root = Fr::from(5u64);
+        // (x_0 - root) * h(x), whose first round polynomial vanishes at `root`
+        let l = Arc::new(DenseMultilinearExtension::from_evaluations_vec(
+            nv,
+            (0..1u64 << nv).map(|i| Fr::from(i & 1) - root).collect(),
+        ));
+        let h = Arc::new(DenseMultilinearExtension::<Fr>::rand(nv, &mut rng));
+        let sum: Fr = l
+            .evaluations
+            .iter()
+            .zip(h.evaluations.iter())
+            .map(|(a, b)| *a * b)
+            .sum();
+        let mut poly = VirtualPolynomial::new(nv);
+        poly.add_mle_list([l, h], Fr::one())?;
+        // a zero mask, so that the masked round polynomials are those of `poly`
+        let mask = RandomMaskPolynomial {
+            const_term: Fr::zero(),
+            evaluations: vec![vec![Fr::zero(); 3]; nv],
+            tag: [0u8; 32],
+        };
+        let rho = Fr::from(3u64);
+
+        let challenges = vec![root, Fr::from(7u64), Fr::from(11u64)];
+        let proof = prove_with_rho(
+            &poly,
+            &mask,
+            &rho,
+            &mut TestTranscript::new(challenges.clone()),
+        )?;
+        assert_eq!(proof.point, challenges);
+        // the claimed sum of the second round is the first round polynomial
+        // at its root
+        assert!((proof.proofs[1].evaluations[0] + proof.proofs[1].evaluations[1]).is_zero());
+
+        let mut transcript = TestTranscript::new(challenges.clone());
+        let subclaim = verify_with_rho(sum, &proof, &poly.aux_info, &mut transcript, nv, 2)?;
+        assert_eq!(transcript.remaining(), 0);
+        assert_eq!(subclaim.point, challenges);
+        assert!(subclaim.expected_evaluation.is_zero());
+        assert_eq!(
+            poly.evaluate(&subclaim.point)?,
+            subclaim.expected_evaluation
+        );
+
+        // the challenges are still checked, and must be enough
+        assert!(matches!(
+            prove_with_rho(
+                &poly,
+                &mask,
+                &rho,
+                &mut TestTranscript::new(vec![root, Fr::one(), root])
+            ),
+            Err(PolyIOPErrors::InvalidChallenge { round: 1, .. })
+        ));
+        assert!(prove_with_rho(
+            &poly,
+            &mask,
+            &rho,
+            &mut TestTranscript::new(challenges[..2].to_vec())
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    #[allow(non_local_definitions)]
+    fn test_invalid_challenge() -> Result<(), PolyIOPErrors> {
+        use ark_ff::fields::{Fp64, MontBackend, MontConfig};
+
+        // a field small enough for the transcript to sample 0 or 1
+        #[derive(MontConfig)]
+        #[modulus = "101"]
+        #[generator = "2"]
+        struct SmallConfig;
+        type SmallField = Fp64<MontBackend<SmallConfig, 1>>;
+
+        let mut rng = test_rng();
+        let nv = 3;
+        let (poly, sum) = VirtualPolynomial::<SmallField>::rand(nv, (2, 3), 2, &mut rng)?;
+        let (mask, mask_sum) = RandomMaskPolynomial::<SmallField>::rand(nv, 2, &mut rng);
+        let rho = SmallField::from(3u64);
+        let claimed_sum = sum + rho * mask_sum;
+        // the transcript of an outer protocol that absorbed statement `i`
+        let init = |i: u64| {
+            let mut transcript = <PolyIOP<SmallField> as ZkSumCheck<SmallField>>::init_transcript();
+            transcript
+                .append_message(b"statement", &i.to_le_bytes())
+                .unwrap();
+            transcript
+        };
+
+        // the first prover message does not depend on the transcript, so the
+        // first challenge only depends on the statement: find a statement
+        // for which it is 0 or 1
+        let mut prover_state = ZkSumCheckProverState::prover_init(&poly, &mask)?;
+        let first_msg = prover_state.prove_round_and_update_state(&rho, &None)?;
+        let i = (0..1000)
+            .find(|&i| {
+                let mut transcript = init(i);
+                transcript
+                    .append_message(AUX_INFO_LABEL, &poly.aux_info.transcript_bytes())
+                    .unwrap();
+                transcript
+                    .append_serializable_element(b"prover msg", &first_msg)
+                    .unwrap();
+                let challenge = transcript
+                    .get_and_append_challenge(b"Internal round")
+                    .unwrap();
+                challenge.is_zero() || challenge.is_one()
+            })
+            .unwrap();
+
+        // both the prover and the verifier reject it
+        assert!(matches!(
+            prove_with_rho(&poly, &mask, &rho, &mut init(i)),
+            Err(PolyIOPErrors::InvalidChallenge { round: 0, .. })
+        ));
+        let proof = IOPProof {
+            point: vec![],
+            proofs: vec![first_msg; nv],
+        };
+        assert!(matches!(
+            verify_with_rho(claimed_sum, &proof, &poly.aux_info, &mut init(i), nv, 2),
+            Err(PolyIOPErrors::InvalidChallenge { round: 0, .. })
+        ));
+        assert!(check_challenge(0, SmallField::from(2u64)).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_serial_route() -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        let (poly, _) = VirtualPolynomial::<Fr>::rand(8, (2, 5), 3, &mut rng)?;
+        let (mask, _) = RandomMaskPolynomial::<Fr>::rand(8, 5, &mut rng);
+        let prove = || {
+            let mut transcript =
+                <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript();
+            <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::prove_bound(
+                &poly,
+                &mask,
+                &mask.evaluations,
+                &mut transcript,
+            )
+        };
+
+        // the serial route produces the same proof
+        let proof = prove()?;
+        let count = util::serial_route_count();
+        assert_eq!(util::run_serial(prove)?, proof);
+        #[cfg(feature = "parallel")]
+        assert!(util::serial_route_count() > count);
+        #[cfg(not(feature = "parallel"))]
+        let _ = count;
+        Ok(())
+    }
+
+    #[test]
+    fn test_mask_degree() -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        let nv = 6;
+        let (poly, asserted_sum) = VirtualPolynomial::<Fr>::rand(nv, (2, 3), 3, &mut rng)?;
+        assert_eq!(poly.aux_info.max_degree, 2);
+
+        // a mask of a higher degree than the polynomial
+        for mask_degree in [2, 5, 8] {
+            let (mask, mask_sum) =
+                RandomMaskPolynomial::rand_for(&poly.aux_info, mask_degree, &mut rng)?;
+            assert!(mask
+                .evaluations
+                .iter()
+                .all(|evals| evals.len() == mask_degree + 1));
+            let mut transcript =
+                <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript();
+            let (proof, rho) = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::prove_bound(
+                &poly,
+                &mask,
+                &mask.evaluations,
+                &mut transcript,
+            )?;
+            assert!(proof
+                .proofs
+                .iter()
+                .all(|msg| msg.evaluations.len() == mask_degree + 1));
+
+            let mut transcript =
+                <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript();
+            let (subclaim, _) = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::verify_bound(
+                asserted_sum,
+                mask_sum,
+                &proof,
+                &mask.evaluations,
+                &poly.aux_info,
+                &mut transcript,
+                mask_degree,
+            )?;
+            assert_eq!(
+                poly.evaluate(&subclaim.point)? + rho * mask.eval(&subclaim.point)?,
+                subclaim.expected_evaluation
+            );
+        }
+
+        // a mask of a lower degree, or above the bound, is rejected
+        for mask_degree in [1, MAX_DEGREE + 1] {
+            assert!(matches!(
+                RandomMaskPolynomial::<Fr>::rand_for(&poly.aux_info, mask_degree, &mut rng),
+                Err(PolyIOPErrors::InvalidParameters(_)) | Err(PolyIOPErrors::InvalidDegree(..))
+            ));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_per_variable_mask_degree() -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        // half^2 * quarter + full, of degrees 3, 2, 1, 1 in its variables
+        let nv = 4;
+        let half = Arc::new(DenseMultilinearExtension::rand(2, &mut rng));
+        let quarter = Arc::new(DenseMultilinearExtension::rand(1, &mut rng));
+        let mut poly = VirtualPolynomial::<Fr>::new(nv).with_per_variable_degree();
+        poly.add_partial_mle_list(
+            [
+                (half.clone(), vec![0, 1]),
+                (half, vec![0, 1]),
+                (quarter, vec![0]),
+            ],
+            Fr::rand(&mut rng),
+        )?;
+        let full = Arc::new(DenseMultilinearExtension::rand(nv, &mut rng));
+        poly.add_mle_list([full], Fr::rand(&mut rng))?;
+        let poly = poly.lift();
+        assert_eq!(poly.aux_info.per_variable_degree, vec![3, 2, 1, 1]);
+        let asserted_sum = (0..1 << nv)
+            .map(|b| {
+                poly.evaluate(
+                    &(0..nv)
+                        .map(|i| Fr::from(((b >> i) & 1) as u64))
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .sum::<Result<Fr, _>>()?;
+
+        // the mask and the messages follow the degrees of the variables
+        let (mask, mask_sum) = RandomMaskPolynomial::rand_for(&poly.aux_info, 3, &mut rng)?;
+        let degrees: Vec<_> = mask
+            .evaluations
+            .iter()
+            .map(|evals| evals.len() - 1)
+            .collect();
+        assert_eq!(degrees, vec![3, 2, 1, 1]);
+        ZkConfig { mask_degree: 3 }.check_mask(&poly.aux_info, &mask)?;
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript();
+        let (proof, rho) = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::prove_bound(
+            &poly,
+            &mask,
+            &mask.evaluations,
+            &mut transcript,
+        )?;
+        let lens: Vec<_> = proof
+            .proofs
+            .iter()
+            .map(|msg| msg.evaluations.len())
+            .collect();
+        assert_eq!(lens, vec![4, 3, 2, 2]);
+
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript();
+        let (subclaim, _) = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::verify_bound(
             asserted_sum,
+            mask_sum,
             &proof,
-            &poly_info,
+            &mask.evaluations,
+            &poly.aux_info,
+            &mut transcript,
+            3,
+        )?;
+        assert_eq!(
+            poly.evaluate(&subclaim.point)? + rho * mask.eval(&subclaim.point)?,
+            subclaim.expected_evaluation
+        );
+
+        // a proof with uniform rounds is rejected against the degrees
+        let mut uniform_poly = poly.clone();
+        uniform_poly.aux_info.per_variable_degree = vec![];
+        let (uniform_mask, _) =
+            RandomMaskPolynomial::rand_for(&uniform_poly.aux_info, 3, &mut rng)?;
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript();
+        let (uniform, _) = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::prove_bound(
+            &uniform_poly,
+            &uniform_mask,
+            &uniform_mask.evaluations,
             &mut transcript,
-            mask.evaluations.len(),
-            mask.evaluations[0].len()-1
         )?;
-        let res = poly.evaluate(&subclaim.point).unwrap() + rho * mask.eval(&subclaim.point)?; 
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript();
         assert!(
-            res == subclaim.expected_evaluation,
-            "wrong subclaim"
+            <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::verify_bound(
+                asserted_sum,
+                mask_sum,
+                &uniform,
+                &uniform_mask.evaluations,
+                &poly.aux_info,
+                &mut transcript,
+                3,
+            )
+            .is_err()
         );
+        // and so is a mask of the uniform degree
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript();
+        assert!(matches!(
+            <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::prove_bound(
+                &poly,
+                &uniform_mask,
+                &uniform_mask.evaluations,
+                &mut transcript
+            ),
+            Err(PolyIOPErrors::InvalidParameters(_))
+        ));
         Ok(())
     }
 
     #[test]
-    fn test_trivial_polynomial() -> Result<(), PolyIOPErrors> {
-        let nv = 10;
-        let num_multiplicands_range = (2, 6);
-        let num_products = 2;
+    fn test_mask_serialization() -> Result<(), PolyIOPErrors> {
+        use ark_serialize::CanonicalDeserialize;
 
-        test_sumcheck(nv, num_multiplicands_range, num_products)
+        let mut rng = test_rng();
+        let nv = 5;
+        let (mask, _) = RandomMaskPolynomial::<Fr>::rand(nv, 3, &mut rng);
+        let point: Vec<Fr> = (0..nv).map(|_| Fr::rand(&mut rng)).collect();
+
+        for compress in [Compress::Yes, Compress::No] {
+            let mut bytes = vec![];
+            mask.serialize_with_mode(&mut bytes, compress)?;
+            assert_eq!(bytes.len(), mask.serialized_size(compress));
+            let read = RandomMaskPolynomial::<Fr>::deserialize_with_mode(
+                bytes.as_slice(),
+                compress,
+                Validate::Yes,
+            )?;
+            assert_eq!(read, mask);
+            assert_eq!(read.eval(&point)?, mask.eval(&point)?);
+        }
+        let mut bytes = vec![];
+        mask.serialize_compressed(&mut bytes)?;
+        assert_eq!(RandomMaskPolynomial::<Fr>::from_bytes(&bytes)?, mask);
+
+        // univariate polynomials of different degrees are rejected
+        let mut bad_mask = mask.clone();
+        bad_mask.evaluations[2].push(Fr::rand(&mut rng));
+        let mut bytes = vec![];
+        bad_mask.serialize_compressed(&mut bytes)?;
+        assert!(matches!(
+            RandomMaskPolynomial::<Fr>::from_bytes(&bytes),
+            Err(PolyIOPErrors::SerializationErrors(_))
+        ));
+        // and truncated bytes
+        assert!(RandomMaskPolynomial::<Fr>::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_mask_eval_malformed() -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        let nv = 3;
+        let (mask, _) = RandomMaskPolynomial::<Fr>::rand(nv, 2, &mut rng);
+
+        // a point of the wrong length is an error, not a panic
+        assert!(mask.eval(&vec![Fr::rand(&mut rng); nv - 1]).is_err());
+        assert!(mask.eval(&vec![Fr::rand(&mut rng); nv + 1]).is_err());
+        // so is a univariate polynomial without evaluations
+        let mut bad_mask = mask.clone();
+        bad_mask.evaluations[1].clear();
+        assert!(bad_mask.eval(&vec![Fr::rand(&mut rng); nv]).is_err());
+        // the mask is defined at the points where it is given
+        let point = vec![Fr::from(2u64); nv];
+        let expected = mask.evaluations.iter().map(|evals| evals[2]).sum::<Fr>() + mask.const_term;
+        assert_eq!(mask.eval(&point)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_folded_tables() -> Result<(), PolyIOPErrors> {
+        use arithmetic::fix_variables;
+
+        let mut rng = test_rng();
+        let nv = 6;
+        let (poly, _) = VirtualPolynomial::<Fr>::rand(nv, (2, 4), 3, &mut rng)?;
+        let (mask, _) = RandomMaskPolynomial::<Fr>::rand(nv, 3, &mut rng);
+        let rho = Fr::rand(&mut rng);
+
+        // the tables carried from round to round are the ones of the
+        // polynomial with all the challenges so far fixed at once
+        let mut prover_state = ZkSumCheckProverState::prover_init(&poly, &mask)?;
+        let mut challenge = None;
+        for _ in 0..nv {
+            prover_state.prove_round_and_update_state(&rho, &challenge)?;
+            let challenges = &prover_state.sum_check_prover_state.challenges;
+            for (folded, mle) in prover_state
+                .sum_check_prover_state
+                .poly
+                .flattened_ml_extensions
+                .iter()
+                .zip(poly.flattened_ml_extensions.iter())
+            {
+                assert_eq!(folded.as_ref(), &fix_variables(mle, challenges));
+            }
+            challenge = Some(Fr::rand(&mut rng));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_zk_config() -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        let nv = 4;
+        let (poly, asserted_sum) = VirtualPolynomial::<Fr>::rand(nv, (2, 3), 2, &mut rng)?;
+        let config = ZkConfig { mask_degree: 5 };
+        assert_eq!(config.effective_degree(&poly.aux_info), 5);
+
+        // mismatched configurations
+        assert!(ZkConfig { mask_degree: 0 }.check(&poly.aux_info).is_err());
+        assert!(ZkConfig {
+            mask_degree: MAX_DEGREE + 1
+        }
+        .check(&poly.aux_info)
+        .is_err());
+        let (mask, sum) = RandomMaskPolynomial::<Fr>::rand(nv, 5, &mut rng);
+        config.check_mask(&poly.aux_info, &mask)?;
+        assert!(ZkConfig { mask_degree: 3 }
+            .check_mask(&poly.aux_info, &mask)
+            .is_err());
+
+        // a proof made with mask degree 5 only verifies with mask degree 5
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript();
+        let (proof, _) = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::prove_bound(
+            &poly,
+            &mask,
+            &mask.evaluations,
+            &mut transcript,
+        )?;
+        for mask_degree in [3, 5] {
+            let mut transcript =
+                <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript();
+            let res = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::verify_bound(
+                asserted_sum,
+                sum,
+                &proof,
+                &mask.evaluations,
+                &poly.aux_info,
+                &mut transcript,
+                mask_degree,
+            );
+            assert_eq!(res.is_ok(), mask_degree == 5);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_rho_bound_to_mask_commitment() -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        let nv = 4;
+        let (poly, sum) = VirtualPolynomial::<Fr>::rand(nv, (2, 3), 2, &mut rng)?;
+        let (mask, mask_sum) = RandomMaskPolynomial::<Fr>::rand(nv, 3, &mut rng);
+        let mask_commitment = mask.evaluations.clone();
+        // the transcript of an outer protocol that already absorbed a statement
+        let init = || {
+            let mut transcript =
+                <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript();
+            transcript
+                .append_message(b"statement", b"zk sum check")
+                .unwrap();
+            transcript
+        };
+
+        // the prover and the verifier derive the same rho
+        let (proof, rho) = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::prove_bound(
+            &poly,
+            &mask,
+            &mask_commitment,
+            &mut init(),
+        )?;
+        let (subclaim, verifier_rho) =
+            <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::verify_bound(
+                sum,
+                mask_sum,
+                &proof,
+                &mask_commitment,
+                &poly.aux_info,
+                &mut init(),
+                3,
+            )?;
+        assert_eq!(rho, verifier_rho);
+        assert_eq!(
+            poly.evaluate(&subclaim.point)? + rho * mask.eval(&subclaim.point)?,
+            subclaim.expected_evaluation
+        );
+
+        // a prover that derives rho before absorbing the mask commitment
+        // gets another rho, and its proof is rejected
+        let mut transcript = init();
+        let early_rho = transcript.get_and_append_challenge(b"rho")?;
+        transcript.append_serializable_element(b"mask commitment", &mask_commitment)?;
+        assert_ne!(early_rho, rho);
+        let proof = prove_with_rho(&poly, &mask, &early_rho, &mut transcript)?;
+        assert!(
+            <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::verify_bound(
+                sum,
+                mask_sum,
+                &proof,
+                &mask_commitment,
+                &poly.aux_info,
+                &mut init(),
+                3,
+            )
+            .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_verifier_trace() -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        let nv = 4;
+        let (poly, sum) = VirtualPolynomial::<Fr>::rand(nv, (2, 3), 2, &mut rng)?;
+        let (mask, mask_sum) = RandomMaskPolynomial::<Fr>::rand(nv, 3, &mut rng);
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript();
+        let (proof, _) = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::prove_bound(
+            &poly,
+            &mask,
+            &mask.evaluations,
+            &mut transcript,
+        )?;
+
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript();
+        let (subclaim, rho, trace) =
+            <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::verify_traced(
+                sum,
+                mask_sum,
+                &proof,
+                &mask.evaluations,
+                &poly.aux_info,
+                &mut transcript,
+                3,
+            )?;
+        assert_eq!(trace.rounds.len(), nv);
+        assert_eq!(trace.asserted_sum, sum + rho * mask_sum);
+        assert_eq!(trace.final_sum(), subclaim.expected_evaluation);
+
+        // a wrong sum fails at the first round
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript();
+        let err = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::verify_bound(
+            sum + Fr::from(1u64),
+            mask_sum,
+            &proof,
+            &mask.evaluations,
+            &poly.aux_info,
+            &mut transcript,
+            3,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            PolyIOPErrors::InconsistentRound { round: 0, .. }
+        ));
+        assert_eq!(err.verifier_trace::<Fr>().unwrap().rounds.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_combine_subclaims() -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        let nv = 4;
+        // the sum checks sample the same challenges, so they end at the same
+        // point
+        let challenges: Vec<Fr> = (0..nv).map(|_| Fr::rand(&mut rng)).collect();
+        let mut polys = vec![];
+        let mut masks = vec![];
+        let mut rhos = vec![];
+        let mut subclaims = vec![];
+        for _ in 0..3 {
+            let (poly, sum) = VirtualPolynomial::<Fr>::rand(nv, (2, 3), 2, &mut rng)?;
+            let (mask, mask_sum) = RandomMaskPolynomial::<Fr>::rand(nv, 2, &mut rng);
+            let rho = Fr::rand(&mut rng);
+            let mut transcript = TestTranscript::new(challenges.clone());
+            let proof = prove_with_rho(&poly, &mask, &rho, &mut transcript)?;
+            let mut transcript = TestTranscript::new(challenges.clone());
+            subclaims.push(verify_with_rho(
+                sum + rho * mask_sum,
+                &proof,
+                &poly.aux_info,
+                &mut transcript,
+                nv,
+                2,
+            )?);
+            polys.push(poly);
+            masks.push(mask);
+            rhos.push(rho);
+        }
+        let scalars: Vec<Fr> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+
+        // a single evaluation of the combined polynomial and mask at the point
+        let check = |subclaim: &ZkSumCheckSubClaim<Fr>| -> Result<bool, PolyIOPErrors> {
+            let mut poly = VirtualPolynomial::new(nv);
+            let mut mask_eval = Fr::from(0u64);
+            for k in 0..polys.len() {
+                poly = poly.try_add(&polys[k].scale(scalars[k]))?;
+                mask_eval += scalars[k] * rhos[k] * masks[k].eval(&subclaim.point)?;
+            }
+            Ok(poly.evaluate(&subclaim.point)? + mask_eval == subclaim.expected_evaluation)
+        };
+        let combined = ZkSumCheckSubClaim::combine(&subclaims, &scalars)?;
+        assert_eq!(combined.point, challenges);
+        assert!(check(&combined)?);
+        // as checking each subclaim
+        for (k, subclaim) in subclaims.iter().enumerate() {
+            assert_eq!(
+                polys[k].evaluate(&subclaim.point)? + rhos[k] * masks[k].eval(&subclaim.point)?,
+                subclaim.expected_evaluation
+            );
+        }
+        // and a wrong subclaim makes the combination wrong
+        let mut bad_subclaims = subclaims.clone();
+        bad_subclaims[1].expected_evaluation += Fr::from(1u64);
+        assert!(!check(&ZkSumCheckSubClaim::combine(
+            &bad_subclaims,
+            &scalars
+        )?)?);
+
+        // bad path: subclaims at different points, or missing scalars
+        let mut bad_subclaims = subclaims.clone();
+        bad_subclaims[2].point[0] += Fr::from(1u64);
+        assert!(matches!(
+            ZkSumCheckSubClaim::combine(&bad_subclaims, &scalars),
+            Err(PolyIOPErrors::InvalidParameters(_))
+        ));
+        assert!(ZkSumCheckSubClaim::combine(&subclaims, &scalars[1..]).is_err());
+        assert!(ZkSumCheckSubClaim::<Fr>::combine(&[], &[]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_batch_verify() -> Result<(), PolyIOPErrors> {
+        let mut rng = test_rng();
+        let nv = 5;
+        let mut polys = vec![];
+        let mut masks = vec![];
+        let mut sums = vec![];
+        let mut rhos = vec![];
+        let mut proofs = vec![];
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript();
+        for _ in 0..4 {
+            let (poly, sum) = VirtualPolynomial::<Fr>::rand(nv, (2, 3), 2, &mut rng)?;
+            let (mask, mask_sum) = RandomMaskPolynomial::<Fr>::rand(nv, 3, &mut rng);
+            let rho = Fr::rand(&mut rng);
+            proofs.push(prove_with_rho(&poly, &mask, &rho, &mut transcript)?);
+            sums.push(sum + rho * mask_sum);
+            polys.push(poly);
+            masks.push(mask);
+            rhos.push(rho);
+        }
+        let aux_info = polys[0].aux_info.clone();
+        assert!(polys.iter().all(|poly| poly.aux_info == aux_info));
+
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript();
+        let subclaims = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::batch_verify(
+            &sums,
+            &proofs,
+            &aux_info,
+            &mut transcript,
+            nv,
+            3,
+        )?;
+        // the same subclaims as verifying the proofs one by one
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript();
+        for (k, subclaim) in subclaims.iter().enumerate() {
+            let expected = verify_with_rho(sums[k], &proofs[k], &aux_info, &mut transcript, nv, 3)?;
+            assert_eq!(subclaim, &expected);
+            assert_eq!(
+                polys[k].evaluate(&subclaim.point)? + rhos[k] * masks[k].eval(&subclaim.point)?,
+                subclaim.expected_evaluation
+            );
+        }
+
+        // bad path: a wrong sum is reported with the index of its proof
+        let mut bad_sums = sums.clone();
+        bad_sums[2] += Fr::from(1u64);
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript();
+        let err = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::batch_verify(
+            &bad_sums,
+            &proofs,
+            &aux_info,
+            &mut transcript,
+            nv,
+            3,
+        )
+        .unwrap_err();
+        match err {
+            PolyIOPErrors::BatchProofFailed { index, source } => {
+                assert_eq!(index, 2);
+                assert!(matches!(
+                    *source,
+                    PolyIOPErrors::InconsistentRound { round: 0, .. }
+                ));
+            },
+            _ => panic!("unexpected error {:?}", err),
+        }
+
+        // bad path: a proof of the wrong shape
+        let mut bad_proofs = proofs.clone();
+        bad_proofs[1].proofs.pop();
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript();
+        assert!(matches!(
+            <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::batch_verify(
+                &sums,
+                &bad_proofs,
+                &aux_info,
+                &mut transcript,
+                nv,
+                3,
+            ),
+            Err(PolyIOPErrors::BatchProofFailed { index: 1, .. })
+        ));
+
+        // bad path: a sum is missing
+        let mut transcript = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript();
+        assert!(
+            <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::batch_verify(
+                &sums[1..],
+                &proofs,
+                &aux_info,
+                &mut transcript,
+                nv,
+                3,
+            )
+            .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_sumcheck_shapes() {
+        check(poly_shape(6, 5, 3), |shape| {
+            let (poly, asserted_sum) = shape.polynomial::<Fr>();
+            let mask_degree = shape.max_degree() + 1;
+            let (mask, mask_sum) =
+                RandomMaskPolynomial::rand(shape.num_vars, mask_degree, &mut shape.rng());
+            let mut transcript =
+                <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript();
+            let (proof, rho) = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::prove_bound(
+                &poly,
+                &mask,
+                &mask.evaluations,
+                &mut transcript,
+            )
+            .map_err(fail)?;
+
+            let mut transcript =
+                <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript();
+            let (subclaim, verifier_rho) =
+                <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::verify_bound(
+                    asserted_sum,
+                    mask_sum,
+                    &proof,
+                    &mask.evaluations,
+                    &poly.aux_info,
+                    &mut transcript,
+                    mask_degree,
+                )
+                .map_err(fail)?;
+            prop_assert_eq!(rho, verifier_rho);
+            let evaluation = poly.evaluate(&subclaim.point).map_err(fail)?
+                + rho * mask.eval(&subclaim.point).map_err(fail)?;
+            prop_assert_eq!(evaluation, subclaim.expected_evaluation);
+
+            // a wrong sum is rejected
+            let mut transcript =
+                <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript();
+            prop_assert!(
+                <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::verify_bound(
+                    asserted_sum + Fr::from(1u64),
+                    mask_sum,
+                    &proof,
+                    &mask.evaluations,
+                    &poly.aux_info,
+                    &mut transcript,
+                    mask_degree,
+                )
+                .is_err()
+            );
+            Ok(())
+        });
+    }
+
+    /// For any polynomial, mask and `rho`, the honest proof is accepted, and
+    /// an arbitrary proof is rejected without panicking.
+    #[test]
+    #[allow(deprecated)]
+    fn test_prove_verify_any_rho() {
+        let zero = Fr::from(0u64);
+        let mask = |shape: &PolyShape| {
+            RandomMaskPolynomial::<Fr>::rand(shape.num_vars, shape.max_degree(), &mut shape.rng())
+        };
+        let strategy = (poly_shape(5, 4, 3), any::<[u8; 32]>(), vec(any::<u8>(), 0..512))
+            .prop_map(|(shape, rho, bytes)| (shape, Fr::from_le_bytes_mod_order(&rho), bytes))
+            // the generated values do not exclude degenerate instances: a
+            // rho of zero drops the mask, and the claimed sum may be zero
+            .prop_filter("degenerate instance", move |(shape, rho, _)| {
+                *rho != zero && shape.polynomial::<Fr>().1 + *rho * mask(shape).1 != zero
+            });
+        check(strategy, |(shape, rho, bytes)| {
+            let (poly, sum) = shape.polynomial::<Fr>();
+            let (mask, mask_sum) = mask(&shape);
+            let mask_degree = shape.max_degree();
+            let mut transcript =
+                <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript();
+            let proof = <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::prove(
+                &poly,
+                &mask,
+                &rho,
+                &mut transcript,
+            )
+            .map_err(fail)?;
+            let verify = |proof: &IOPProof<Fr>| {
+                let mut transcript =
+                    <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::init_transcript();
+                <PolyIOP<Fr> as ZkSumCheck<Fr, SumCheckTranscript>>::verify(
+                    sum + rho * mask_sum,
+                    proof,
+                    &poly.aux_info,
+                    &mut transcript,
+                    shape.num_vars,
+                    mask_degree,
+                )
+            };
+            let subclaim = verify(&proof).map_err(fail)?;
+            let evaluation = poly.evaluate(&subclaim.point).map_err(fail)?
+                + rho * mask.eval(&subclaim.point).map_err(fail)?;
+            prop_assert_eq!(evaluation, subclaim.expected_evaluation);
+
+            let arbitrary_proof =
+                IOPProof::<Fr>::arbitrary(&mut Unstructured::new(&bytes)).map_err(fail)?;
+            prop_assert!(verify(&arbitrary_proof).is_err());
+            Ok(())
+        });
     }
 }
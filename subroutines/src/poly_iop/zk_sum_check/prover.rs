@@ -1,52 +1,94 @@
-use super::ZkSumCheckProver;
+use super::{ZkConfig, ZkSumCheckProver};
 use crate::poly_iop::{
     errors::PolyIOPErrors,
     structs::{IOPProverMessage, IOPProverState, RandomMaskPolynomial},
+    sum_check::check_aux_info,
 };
-use arithmetic::{fix_variables, VirtualPolynomial};
+use arithmetic::{fix_variables, SparseVirtualPolynomial, VPAuxInfo, VirtualPolynomial};
 use ark_ff::{batch_inversion, PrimeField};
 use ark_poly::DenseMultilinearExtension;
-use ark_std::{cfg_into_iter, end_timer, rand::RngCore, start_timer, vec::Vec};
-use itertools::max;
-use rayon::prelude::{IntoParallelIterator, IntoParallelRefIterator};
-use std::sync::Arc;
+use ark_std::{end_timer, rand::RngCore, start_timer, vec::Vec};
+use std::{cmp::max, sync::Arc};
 
 #[cfg(feature = "parallel")]
-use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator};
+use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
 
 impl<F: PrimeField> RandomMaskPolynomial<F> {
-    pub fn rand<R: RngCore>(
-        nv: usize,
-        degree: usize,
-        rng: &mut R
+    pub fn rand<R: RngCore>(nv: usize, degree: usize, rng: &mut R) -> (RandomMaskPolynomial<F>, F) {
+        Self::rand_with_degrees(&vec![degree; nv], rng)
+    }
+
+    /// Sample a mask of degree `degrees[i]` in its `i`-th variable, with its
+    /// sum.
+    pub fn rand_with_degrees<R: RngCore>(
+        degrees: &[usize],
+        rng: &mut R,
     ) -> (RandomMaskPolynomial<F>, F) {
-        let mut evals = vec![vec![F::zero(); degree+1]; nv];
-        for i in 0..nv {
-            for j in 1..degree+1 {
-                evals[i][j] = F::rand(rng);
+        let nv = degrees.len();
+        let mut evals: Vec<_> = degrees
+            .iter()
+            .map(|degree| vec![F::zero(); degree + 1])
+            .collect();
+        for row in evals.iter_mut() {
+            for eval in row.iter_mut().skip(1) {
+                *eval = F::rand(rng);
             }
         }
         let const_term = F::rand(rng);
+        let mut tag = [0u8; 32];
+        rng.fill_bytes(&mut tag);
         let mask_poly = RandomMaskPolynomial::<F> {
             const_term,
-            evaluations: evals
+            evaluations: evals,
+            tag,
         };
         let mut sum = mask_poly.evaluations.iter().map(|row| row[1]).sum();
-        sum *= F::from((1 << (nv-1)) as u64);
+        sum *= F::from((1 << (nv - 1)) as u64);
         sum += F::from((1 << nv) as u64) * const_term;
-        
+
         (mask_poly, sum)
     }
 
-    pub fn eval(
-        &self,
-        point: &[F]
-    ) -> Result<F, PolyIOPErrors> {
-        assert_eq!(point.len(), self.evaluations.len());
+    /// Sample a mask of degree `mask_degree` for the polynomial described
+    /// by `aux_info`, with its sum.
+    ///
+    /// The degree is chosen by the caller rather than derived from the
+    /// polynomial, and may exceed its degree; it must be at least the
+    /// degree of the polynomial and at most `MAX_DEGREE`. If the polynomial
+    /// declares the degree of each variable, the mask has these degrees
+    /// instead, see [`ZkConfig::mask_variable_degree`].
+    pub fn rand_for<R: RngCore>(
+        aux_info: &VPAuxInfo<F>,
+        mask_degree: usize,
+        rng: &mut R,
+    ) -> Result<(RandomMaskPolynomial<F>, F), PolyIOPErrors> {
+        let config = ZkConfig { mask_degree };
+        config.check(aux_info)?;
+        if mask_degree < aux_info.max_degree {
+            return Err(PolyIOPErrors::InvalidParameters(format!(
+                "mask degree {} is less than the polynomial degree {}",
+                mask_degree, aux_info.max_degree
+            )));
+        }
+        let degrees: Vec<_> = (0..aux_info.num_variables)
+            .map(|i| config.mask_variable_degree(aux_info, i))
+            .collect();
+        Ok(Self::rand_with_degrees(&degrees, rng))
+    }
+
+    /// Evaluate the mask at `point`, which has one coordinate per variable.
+    pub fn eval(&self, point: &[F]) -> Result<F, PolyIOPErrors> {
+        if point.len() != self.evaluations.len() {
+            return Err(PolyIOPErrors::InvalidParameters(format!(
+                "mask of {} variables evaluated at a point of {} coordinates",
+                self.evaluations.len(),
+                point.len()
+            )));
+        }
 
         let mut res = F::zero();
-        for i in 0..self.evaluations.len() {
-            res += interpolate_uni_poly(&self.evaluations[i], point[i])?;
+        for (evals, &x) in self.evaluations.iter().zip(point.iter()) {
+            res += interpolate_uni_poly(evals, x)?;
         }
 
         Ok(res + self.const_term)
@@ -54,14 +96,15 @@ impl<F: PrimeField> RandomMaskPolynomial<F> {
 }
 
 pub struct ZkSumCheckProverState<F: PrimeField> {
-    // sum check prover state
+    // sum check prover state; its mles are the tables of the previous round
+    // with the last challenge fixed, so that each round only folds them once
     pub(crate) sum_check_prover_state: IOPProverState<F>,
     // mask polynomial
     pub(crate) mask_poly: RandomMaskPolynomial<F>,
     // `sum_aux[i]` is \sum_{j=i+1, ..., num_variables}g_j(1)
     pub(crate) sum_aux: Vec<F>,
     // current_sum of evaluaions of g_i in random challenge
-    pub(crate) current_sum: F
+    pub(crate) current_sum: F,
 }
 
 impl<F: PrimeField> ZkSumCheckProver<F> for ZkSumCheckProverState<F> {
@@ -71,23 +114,47 @@ impl<F: PrimeField> ZkSumCheckProver<F> for ZkSumCheckProverState<F> {
 
     /// Initialize the prover state to argue for the sum of the input polynomial
     /// over {0,1}^`num_vars`.
-    fn prover_init(polynomial: &Self::VirtualPolynomial, mask_poly: &Self::RandomMaskPolynomial) -> Result<Self, PolyIOPErrors> {
+    fn prover_init(
+        polynomial: &Self::VirtualPolynomial,
+        mask_poly: &Self::RandomMaskPolynomial,
+    ) -> Result<Self, PolyIOPErrors> {
         let start = start_timer!(|| "sum check prover init");
-        if polynomial.aux_info.num_variables == 0 {
+        check_aux_info(&polynomial.aux_info)?;
+        if mask_poly.evaluations.len() != polynomial.aux_info.num_variables {
             return Err(PolyIOPErrors::InvalidParameters(
-                "Attempt to prove a constant.".to_string(),
+                "Number of variables is not match".to_string(),
             ));
-        } else if mask_poly.evaluations.len() != polynomial.aux_info.num_variables {
+        }
+        if polynomial.mle_variables.iter().any(Option::is_some) {
             return Err(PolyIOPErrors::InvalidParameters(
-                "Number of variables is not match".to_string(),
+                "zk sum check requires mles over all the variables, lift the polynomial"
+                    .to_string(),
             ));
         }
-        let max_degree = max([polynomial.aux_info.max_degree, mask_poly.evaluations[0].len()-1]).unwrap();
+        // with declared variable degrees, the mask has the degree of each
+        // variable, so that the round polynomial does too
+        if !polynomial.aux_info.per_variable_degree.is_empty()
+            && mask_poly
+                .evaluations
+                .iter()
+                .zip(polynomial.aux_info.per_variable_degree.iter())
+                .any(|(evals, degree)| evals.len() != degree + 1)
+        {
+            return Err(PolyIOPErrors::InvalidParameters(
+                "mask degrees do not match the variable degrees".to_string(),
+            ));
+        }
+        let max_degree = max(polynomial.aux_info.max_degree, mask_degree(mask_poly));
+        let poly = if polynomial.aux_info.is_sparse {
+            VirtualPolynomial::from(&SparseVirtualPolynomial::from(polynomial))
+        } else {
+            polynomial.clone()
+        };
 
         let sum_check_prover_state = IOPProverState {
             challenges: Vec::with_capacity(polynomial.aux_info.num_variables),
             round: 0,
-            poly: polynomial.clone(),
+            poly,
             extrapolation_aux: (1..max_degree)
                 .map(|degree| {
                     let points = (0..1 + degree as u64).map(F::from).collect::<Vec<_>>();
@@ -100,7 +167,7 @@ impl<F: PrimeField> ZkSumCheckProver<F> for ZkSumCheckProverState<F> {
         let mut sum_aux = vec![F::zero(); polynomial.aux_info.num_variables];
         sum_aux[0] = mask_poly.evaluations.iter().map(|row| row[1]).sum();
         for i in 1..sum_aux.len() {
-            sum_aux[i] = sum_aux[i-1] - mask_poly.evaluations[i-1][1];
+            sum_aux[i] = sum_aux[i - 1] - mask_poly.evaluations[i - 1][1];
         }
         end_timer!(start);
 
@@ -108,7 +175,7 @@ impl<F: PrimeField> ZkSumCheckProver<F> for ZkSumCheckProverState<F> {
             sum_check_prover_state,
             mask_poly: mask_poly.clone(),
             sum_aux,
-            current_sum: mask_poly.const_term
+            current_sum: mask_poly.const_term,
         })
     }
 
@@ -119,7 +186,9 @@ impl<F: PrimeField> ZkSumCheckProver<F> for ZkSumCheckProverState<F> {
         rho: &F,
         challenge: &Option<F>,
     ) -> Result<Self::ProverMessage, PolyIOPErrors> {
-        if self.sum_check_prover_state.round >= self.sum_check_prover_state.poly.aux_info.num_variables {
+        if self.sum_check_prover_state.round
+            >= self.sum_check_prover_state.poly.aux_info.num_variables
+        {
             return Err(PolyIOPErrors::InvalidProver(
                 "Prover is not active".to_string(),
             ));
@@ -135,15 +204,12 @@ impl<F: PrimeField> ZkSumCheckProver<F> for ZkSumCheckProverState<F> {
         //
         //    g(r_1, ..., r_{m-1}, x_m ... x_n)
         //
-        // eval g over r_m, and mutate g to g(r_1, ... r_m,, x_{m+1}... x_n)
-        let mut flattened_ml_extensions: Vec<DenseMultilinearExtension<F>> = self
-            .sum_check_prover_state
-            .poly
-            .flattened_ml_extensions
-            .par_iter()
-            .map(|x| x.as_ref().clone())
-            .collect();
-
+        // eval g over r_m, and replace g with g(r_1, ... r_m,, x_{m+1}... x_n)
+        //
+        // With a single thread, rayon only adds overhead: the serial route
+        // runs the same steps without it.
+        #[cfg(feature = "parallel")]
+        let serial = util::serial_route();
         if let Some(chal) = challenge {
             if self.sum_check_prover_state.round == 0 {
                 return Err(PolyIOPErrors::InvalidProver(
@@ -153,15 +219,21 @@ impl<F: PrimeField> ZkSumCheckProver<F> for ZkSumCheckProverState<F> {
             self.sum_check_prover_state.challenges.push(*chal);
 
             let r = self.sum_check_prover_state.challenges[self.sum_check_prover_state.round - 1];
+            let mles = &self.sum_check_prover_state.poly.flattened_ml_extensions;
+            let fix = |mle: &Arc<DenseMultilinearExtension<F>>| Arc::new(fix_variables(mle, &[r]));
             #[cfg(feature = "parallel")]
-            flattened_ml_extensions
-                .par_iter_mut()
-                .for_each(|mle| *mle = fix_variables(mle, &[r]));
+            let fixed = if serial {
+                mles.iter().map(fix).collect()
+            } else {
+                mles.par_iter().map(fix).collect()
+            };
             #[cfg(not(feature = "parallel"))]
-            flattened_ml_extensions
-                .iter_mut()
-                .for_each(|mle| *mle = fix_variables(mle, &[r]));
-            self.current_sum += interpolate_uni_poly(&self.mask_poly.evaluations[self.sum_check_prover_state.round-1], r)?;
+            let fixed = mles.iter().map(fix).collect();
+            self.sum_check_prover_state.poly.flattened_ml_extensions = fixed;
+            self.current_sum += interpolate_uni_poly(
+                &self.mask_poly.evaluations[self.sum_check_prover_state.round - 1],
+                r,
+            )?;
         } else if self.sum_check_prover_state.round > 0 {
             return Err(PolyIOPErrors::InvalidProver(
                 "verifier message is empty".to_string(),
@@ -170,85 +242,130 @@ impl<F: PrimeField> ZkSumCheckProver<F> for ZkSumCheckProverState<F> {
         // end_timer!(fix_argument);
 
         let mut temp = self.current_sum
-            * F::from((1 << (self.sum_check_prover_state.poly.aux_info.num_variables-self.sum_check_prover_state.round-1)) as u64);
-        
-        if self.sum_check_prover_state.poly.aux_info.num_variables-1 != self.sum_check_prover_state.round {
-            temp += self.sum_aux[self.sum_check_prover_state.round+1]
-                * F::from((1 << (self.sum_check_prover_state.poly.aux_info.num_variables-self.sum_check_prover_state.round-2)) as u64);
+            * F::from(
+                (1 << (self.sum_check_prover_state.poly.aux_info.num_variables
+                    - self.sum_check_prover_state.round
+                    - 1)) as u64,
+            );
+
+        if self.sum_check_prover_state.poly.aux_info.num_variables - 1
+            != self.sum_check_prover_state.round
+        {
+            temp += self.sum_aux[self.sum_check_prover_state.round + 1]
+                * F::from(
+                    (1 << (self.sum_check_prover_state.poly.aux_info.num_variables
+                        - self.sum_check_prover_state.round
+                        - 2)) as u64,
+                );
         }
 
-        let mut g_sum: Vec<F> = vec![F::zero(); self.mask_poly.evaluations[0].len()];
-        for j in 0..self.mask_poly.evaluations[0].len() {
-            g_sum[j] = self.mask_poly.evaluations[self.sum_check_prover_state.round][j]
-                * F::from((1 << (self.sum_check_prover_state.poly.aux_info.num_variables-self.sum_check_prover_state.round-1)) as u64)
-                + temp;
-        }
+        let aux_info = &self.sum_check_prover_state.poly.aux_info;
+        let max_degree = max(aux_info.max_degree, mask_degree(&self.mask_poly));
+        // the message has the degree of the variable, if it is declared
+        let round_degree = aux_info
+            .per_variable_degree
+            .get(self.sum_check_prover_state.round)
+            .copied()
+            .unwrap_or(max_degree);
+
+        // a mask of a lower degree than the polynomial is extrapolated to
+        // the points of the message
+        let mask_evals = &self.mask_poly.evaluations[self.sum_check_prover_state.round];
+        let g_sum: Vec<F> = (0..=round_degree)
+            .map(|j| {
+                let eval = match mask_evals.get(j) {
+                    Some(eval) => *eval,
+                    None => interpolate_uni_poly(mask_evals, F::from(j as u64))?,
+                };
+                Ok(eval
+                    * F::from(
+                        (1 << (self.sum_check_prover_state.poly.aux_info.num_variables
+                            - self.sum_check_prover_state.round
+                            - 1)) as u64,
+                    )
+                    + temp)
+            })
+            .collect::<Result<_, PolyIOPErrors>>()?;
 
         self.sum_check_prover_state.round += 1;
 
-        let max_degree = max([self.sum_check_prover_state.poly.aux_info.max_degree, self.mask_poly.evaluations[0].len()-1]).unwrap();
-
-        let products_list = self.sum_check_prover_state.poly.products.clone();
-        let mut products_sum = vec![F::zero(); max_degree + 1];
+        let flattened_ml_extensions = &self.sum_check_prover_state.poly.flattened_ml_extensions;
+        let extrapolation_aux = &self.sum_check_prover_state.extrapolation_aux;
+        let num_evals = 1
+            << (self.sum_check_prover_state.poly.aux_info.num_variables
+                - self.sum_check_prover_state.round);
 
         // Step 2: generate sum for the partial evaluated polynomial:
         // f(r_1, ... r_m,, x_{m+1}... x_n)
-
-        products_list.iter().for_each(|(coefficient, products)| {
-            let mut sum = cfg_into_iter!(0..1 << (self.sum_check_prover_state.poly.aux_info.num_variables - self.sum_check_prover_state.round))
-                .fold(
-                    || {
-                        (
-                            vec![(F::zero(), F::zero()); products.len()],
-                            vec![F::zero(); products.len() + 1],
-                        )
-                    },
-                    |(mut buf, mut acc), b| {
-                        buf.iter_mut()
-                            .zip(products.iter())
-                            .for_each(|((eval, step), f)| {
-                                let table = &flattened_ml_extensions[*f];
-                                *eval = table[b << 1];
-                                *step = table[(b << 1) + 1] - table[b << 1];
-                            });
-                        acc[0] += buf.iter().map(|(eval, _)| eval).product::<F>();
-                        acc[1..].iter_mut().for_each(|acc| {
-                            buf.iter_mut().for_each(|(eval, step)| *eval += step as &_);
-                            *acc += buf.iter().map(|(eval, _)| eval).product::<F>();
-                        });
-                        (buf, acc)
-                    },
+        //
+        // Each product contributes its own evaluations, which are computed
+        // independently and then added up: field additions are exact, so
+        // the message does not depend on how the work is split.
+        let product_sum = |(coefficient, products): &(F, Vec<usize>)| {
+            let init = || {
+                (
+                    vec![(F::zero(), F::zero()); products.len()],
+                    vec![F::zero(); products.len() + 1],
                 )
-                .map(|(_, partial)| partial)
-                .reduce(
-                    || vec![F::zero(); products.len() + 1],
-                    |mut sum, partial| {
-                        sum.iter_mut()
-                            .zip(partial.iter())
-                            .for_each(|(sum, partial)| *sum += partial);
-                        sum
-                    },
-                );
+            };
+            let accumulate = |(mut buf, mut acc): (Vec<(F, F)>, Vec<F>), b: usize| {
+                buf.iter_mut()
+                    .zip(products.iter())
+                    .for_each(|((eval, step), f)| {
+                        let table = &flattened_ml_extensions[*f];
+                        *eval = table[b << 1];
+                        *step = table[(b << 1) + 1] - table[b << 1];
+                    });
+                acc[0] += buf.iter().map(|(eval, _)| eval).product::<F>();
+                acc[1..].iter_mut().for_each(|acc| {
+                    buf.iter_mut().for_each(|(eval, step)| *eval += step as &_);
+                    *acc += buf.iter().map(|(eval, _)| eval).product::<F>();
+                });
+                (buf, acc)
+            };
+            #[cfg(feature = "parallel")]
+            let mut sum = if serial {
+                (0..num_evals).fold(init(), accumulate).1
+            } else {
+                (0..num_evals)
+                    .into_par_iter()
+                    .fold(init, accumulate)
+                    .map(|(_, partial)| partial)
+                    .reduce(|| vec![F::zero(); products.len() + 1], add_evaluations)
+            };
+            #[cfg(not(feature = "parallel"))]
+            let mut sum = (0..num_evals).fold(init(), accumulate).1;
             sum.iter_mut().for_each(|sum| *sum *= coefficient);
-            let extraploation = cfg_into_iter!(0..max_degree - products.len())
-                .map(|i| {
-                    let (points, weights) = &self.sum_check_prover_state.extrapolation_aux[products.len() - 1];
-                    let at = F::from((products.len() + 1 + i) as u64);
-                    extrapolate(points, weights, &sum, &at)
-                })
-                .collect::<Vec<_>>();
-            products_sum
-                .iter_mut()
-                .zip(sum.iter().chain(extraploation.iter()))
-                .for_each(|(products_sum, sum)| *products_sum += sum);
-        });
-
-        // update prover's state to the partial evaluated polynomial
-        self.sum_check_prover_state.poly.flattened_ml_extensions = flattened_ml_extensions
-            .par_iter()
-            .map(|x| Arc::new(x.clone()))
-            .collect();
-
+            let extrapolation = (0..max_degree - products.len()).map(|i| {
+                let (points, weights) = &extrapolation_aux[products.len() - 1];
+                let at = F::from((products.len() + 1 + i) as u64);
+                extrapolate(points, weights, &sum, &at)
+            });
+            sum.iter().copied().chain(extrapolation).collect::<Vec<_>>()
+        };
+        let products_list = &self.sum_check_prover_state.poly.products;
+        let zero = || vec![F::zero(); max_degree + 1];
+        #[cfg(feature = "parallel")]
+        let mut products_sum = if serial {
+            products_list
+                .iter()
+                .map(product_sum)
+                .fold(zero(), add_evaluations)
+        } else {
+            products_list
+                .par_iter()
+                .map(product_sum)
+                .reduce(zero, add_evaluations)
+        };
+        #[cfg(not(feature = "parallel"))]
+        let mut products_sum = products_list
+            .iter()
+            .map(product_sum)
+            .fold(zero(), add_evaluations);
+
+        // the evaluations past the degree of the variable are determined by
+        // the others
+        products_sum.truncate(round_degree + 1);
         assert_eq!(products_sum.len(), g_sum.len());
         for j in 0..products_sum.len() {
             products_sum[j] += *rho * g_sum[j];
@@ -260,6 +377,24 @@ impl<F: PrimeField> ZkSumCheckProver<F> for ZkSumCheckProverState<F> {
     }
 }
 
+/// The largest degree of `mask_poly` in its variables.
+fn mask_degree<F: PrimeField>(mask_poly: &RandomMaskPolynomial<F>) -> usize {
+    mask_poly
+        .evaluations
+        .iter()
+        .map(|evals| evals.len() - 1)
+        .max()
+        .unwrap_or_default()
+}
+
+/// Add the evaluations `rhs` to `lhs`, which has at least as many.
+fn add_evaluations<F: PrimeField>(mut lhs: Vec<F>, rhs: Vec<F>) -> Vec<F> {
+    lhs.iter_mut()
+        .zip(rhs.iter())
+        .for_each(|(lhs, rhs)| *lhs += rhs);
+    lhs
+}
+
 fn barycentric_weights<F: PrimeField>(points: &[F]) -> Vec<F> {
     let mut weights = points
         .iter()
@@ -268,7 +403,7 @@ fn barycentric_weights<F: PrimeField>(points: &[F]) -> Vec<F> {
             points
                 .iter()
                 .enumerate()
-                .filter(|&(i, _point_i)| (i != j))
+                .filter(|&(i, _point_i)| i != j)
                 .map(|(_i, point_i)| *point_j - point_i)
                 .reduce(|acc, value| acc * value)
                 .unwrap_or_else(F::one)
@@ -310,6 +445,16 @@ fn interpolate_uni_poly<F: PrimeField>(p_i: &[F], eval_at: F) -> Result<F, PolyI
     let start = start_timer!(|| "sum check interpolate uni poly opt");
 
     let len = p_i.len();
+    if len == 0 {
+        return Err(PolyIOPErrors::InvalidParameters(
+            "cannot interpolate a polynomial without evaluations".to_string(),
+        ));
+    }
+    // the formula below divides by `eval_at - j` for each evaluation point j
+    if let Some(i) = (0..len).find(|&i| eval_at == F::from(i as u64)) {
+        end_timer!(start);
+        return Ok(p_i[i]);
+    }
     let mut evals = vec![];
     let mut prod = eval_at;
     evals.push(eval_at);
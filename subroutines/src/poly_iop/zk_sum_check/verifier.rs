@@ -1,13 +1,14 @@
-use super::{ZkSumCheckSubClaim, ZkSumCheckVerifier};
+use super::{check_challenge, ZkSumCheckSubClaim, ZkSumCheckVerifier};
 use crate::poly_iop::{
     errors::PolyIOPErrors,
-    structs::{IOPProverMessage, IOPVerifierState},
+    structs::{IOPProverMessage, IOPVerifierState, VerifierTrace},
+    sum_check::check_aux_info,
 };
 use arithmetic::VPAuxInfo;
 use ark_ff::PrimeField;
 use ark_std::{end_timer, start_timer};
-use itertools::max;
-use transcript::IOPTranscript;
+use std::cmp::max;
+use transcript::{IOPTranscript, Transcript};
 
 #[cfg(feature = "parallel")]
 use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
@@ -22,18 +23,20 @@ impl<F: PrimeField> ZkSumCheckVerifier<F> for IOPVerifierState<F> {
     type MPNumV = usize;
 
     /// Initialize the verifier's state.
-    fn verifier_init(index_info: &Self::VPAuxInfo) -> Self {
+    fn verifier_init(index_info: &Self::VPAuxInfo) -> Result<Self, PolyIOPErrors> {
         let start = start_timer!(|| "sum check verifier init");
+        check_aux_info(index_info)?;
         let res = Self {
             round: 1,
             num_vars: index_info.num_variables,
             max_degree: index_info.max_degree,
+            per_variable_degree: index_info.per_variable_degree.clone(),
             finished: false,
             polynomials_received: Vec::with_capacity(index_info.num_variables),
             challenges: Vec::with_capacity(index_info.num_variables),
         };
         end_timer!(start);
-        res
+        Ok(res)
     }
 
     /// Run verifier for the current round, given a prover message.
@@ -47,6 +50,36 @@ impl<F: PrimeField> ZkSumCheckVerifier<F> for IOPVerifierState<F> {
         prover_msg: &Self::ProverMessage,
         transcript: &mut Self::Transcript,
     ) -> Result<Self::Challenge, PolyIOPErrors> {
+        self.verify_round_masked(prover_msg, transcript)
+    }
+
+    /// This function verifies the deferred checks in the interactive version of
+    /// the protocol; and generate the subclaim. Returns an error if the
+    /// proof failed to verify.
+    ///
+    /// If the asserted sum is correct, then the multilinear polynomial
+    /// evaluated at `subclaim.point` will be `subclaim.expected_evaluation`.
+    /// Otherwise, it is highly unlikely that those two will be equal.
+    /// Larger field size guarantees smaller soundness error.
+    fn check_and_generate_subclaim(
+        &self,
+        asserted_sum: &F,
+        mask_poly_nv: usize,
+        mask_poly_degree: usize,
+    ) -> Result<Self::ZkSumCheckSubClaim, PolyIOPErrors> {
+        self.check_and_trace_masked(asserted_sum, mask_poly_nv, mask_poly_degree)
+            .map(|(subclaim, _)| subclaim)
+    }
+}
+
+impl<F: PrimeField> IOPVerifierState<F> {
+    /// `verify_round_and_update_state` of the zk sum check, on any
+    /// transcript.
+    pub(crate) fn verify_round_masked<T: Transcript<F>>(
+        &mut self,
+        prover_msg: &IOPProverMessage<F>,
+        transcript: &mut T,
+    ) -> Result<F, PolyIOPErrors> {
         let start =
             start_timer!(|| format!("sum check verify {}-th round and update state", self.round));
 
@@ -64,7 +97,10 @@ impl<F: PrimeField> ZkSumCheckVerifier<F> for IOPVerifierState<F> {
         // When we turn the protocol to a non-interactive one, it is sufficient to defer
         // such checks to `check_and_generate_subclaim` after the last round.
 
-        let challenge = transcript.get_and_append_challenge(b"Internal round")?;
+        let challenge = check_challenge(
+            self.round - 1,
+            transcript.get_and_append_challenge(b"Internal round")?,
+        )?;
         self.challenges.push(challenge);
         self.polynomials_received
             .push(prover_msg.evaluations.to_vec());
@@ -81,21 +117,39 @@ impl<F: PrimeField> ZkSumCheckVerifier<F> for IOPVerifierState<F> {
         Ok(challenge)
     }
 
-    /// This function verifies the deferred checks in the interactive version of
-    /// the protocol; and generate the subclaim. Returns an error if the
-    /// proof failed to verify.
-    ///
-    /// If the asserted sum is correct, then the multilinear polynomial
-    /// evaluated at `subclaim.point` will be `subclaim.expected_evaluation`.
-    /// Otherwise, it is highly unlikely that those two will be equal.
-    /// Larger field size guarantees smaller soundness error.
-    fn check_and_generate_subclaim(
+    /// `check_and_generate_subclaim` of the zk sum check, which also returns
+    /// the trace of the verifier.
+    pub(crate) fn check_and_trace_masked(
         &self,
         asserted_sum: &F,
         mask_poly_nv: usize,
-        mask_poly_degree: usize
-    ) -> Result<Self::ZkSumCheckSubClaim, PolyIOPErrors> {
+        mask_poly_degree: usize,
+    ) -> Result<(ZkSumCheckSubClaim<F>, VerifierTrace<F>), PolyIOPErrors> {
         let start = start_timer!(|| "sum check check and generate subclaim");
+        let expected_vec =
+            self.expected_sums_masked(asserted_sum, mask_poly_nv, mask_poly_degree)?;
+        let trace = self.check_rounds(&expected_vec)?;
+        end_timer!(start);
+        Ok((
+            ZkSumCheckSubClaim {
+                point: self.challenges.clone(),
+                // the last expected value (not checked within this function) will be included in the
+                // subclaim
+                expected_evaluation: expected_vec[self.num_vars],
+            },
+            trace,
+        ))
+    }
+
+    /// The claimed sum of each round of the zk sum check, starting with
+    /// `asserted_sum`, and ending with the expected evaluation of the
+    /// subclaim. These are not checked against the prover messages yet.
+    pub(crate) fn expected_sums_masked(
+        &self,
+        asserted_sum: &F,
+        mask_poly_nv: usize,
+        mask_poly_degree: usize,
+    ) -> Result<Vec<F>, PolyIOPErrors> {
         if !self.finished {
             return Err(PolyIOPErrors::InvalidVerifier(
                 "Incorrect verifier state: Verifier has not finished.".to_string(),
@@ -113,7 +167,14 @@ impl<F: PrimeField> ZkSumCheckVerifier<F> for IOPVerifierState<F> {
                 "Number of variables doesn't match".to_string(),
             ));
         }
-        let max_degree = max([self.max_degree, mask_poly_degree]).unwrap();
+        // the mask follows the degree of each variable, if they are declared
+        let max_degree = max(self.max_degree, mask_poly_degree);
+        let round_degree = |round: usize| {
+            self.per_variable_degree
+                .get(round)
+                .copied()
+                .unwrap_or(max_degree)
+        };
 
         // the deferred check during the interactive phase:
         // 2. set `expected` to P(r)`
@@ -123,13 +184,14 @@ impl<F: PrimeField> ZkSumCheckVerifier<F> for IOPVerifierState<F> {
             .clone()
             .into_par_iter()
             .zip(self.challenges.clone().into_par_iter())
-            .map(|(evaluations, challenge)| {
-                if evaluations.len() != max_degree + 1 {
-                    return Err(PolyIOPErrors::InvalidVerifier(format!(
-                        "incorrect number of evaluations: {} vs {}",
-                        evaluations.len(),
-                        max_degree + 1
-                    )));
+            .enumerate()
+            .map(|(round, (evaluations, challenge))| {
+                let degree = round_degree(round);
+                if evaluations.len() != degree + 1 {
+                    return Err(PolyIOPErrors::degree_mismatch(
+                        degree,
+                        evaluations.len().saturating_sub(1),
+                    ));
                 }
                 interpolate_uni_poly::<F>(&evaluations, challenge)
             })
@@ -140,14 +202,15 @@ impl<F: PrimeField> ZkSumCheckVerifier<F> for IOPVerifierState<F> {
             .polynomials_received
             .clone()
             .into_iter()
-            .zip(self.challenges.clone().into_iter())
-            .map(|(evaluations, challenge)| {
-                if evaluations.len() != max_degree + 1 {
-                    return Err(PolyIOPErrors::InvalidVerifier(format!(
-                        "incorrect number of evaluations: {} vs {}",
-                        evaluations.len(),
-                        max_degree + 1
-                    )));
+            .zip(self.challenges.clone())
+            .enumerate()
+            .map(|(round, (evaluations, challenge))| {
+                let degree = round_degree(round);
+                if evaluations.len() != degree + 1 {
+                    return Err(PolyIOPErrors::degree_mismatch(
+                        degree,
+                        evaluations.len().saturating_sub(1),
+                    ));
                 }
                 interpolate_uni_poly::<F>(&evaluations, challenge)
             })
@@ -155,28 +218,7 @@ impl<F: PrimeField> ZkSumCheckVerifier<F> for IOPVerifierState<F> {
 
         // insert the asserted_sum to the first position of the expected vector
         expected_vec.insert(0, *asserted_sum);
-
-        for (evaluations, &expected) in self
-            .polynomials_received
-            .iter()
-            .zip(expected_vec.iter())
-            .take(self.num_vars)
-        {
-            // the deferred check during the interactive phase:
-            // 1. check if the received 'P(0) + P(1) = expected`.
-            if evaluations[0] + evaluations[1] != expected {
-                return Err(PolyIOPErrors::InvalidProof(
-                    "Prover message is not consistent with the claim.".to_string(),
-                ));
-            }
-        }
-        end_timer!(start);
-        Ok(ZkSumCheckSubClaim {
-            point: self.challenges.clone(),
-            // the last expected value (not checked within this function) will be included in the
-            // subclaim
-            expected_evaluation: expected_vec[self.num_vars],
-        })
+        Ok(expected_vec)
     }
 }
 
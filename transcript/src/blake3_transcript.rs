@@ -0,0 +1,138 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! A transcript over a BLAKE3 hasher, faster than Merlin's Keccak-f for the
+//! proofs that are not verified in a circuit.
+
+use crate::{to_bytes, Transcript, TranscriptError};
+use ark_ff::PrimeField;
+use ark_serialize::CanonicalSerialize;
+use ark_std::marker::PhantomData;
+
+/// A transcript over a BLAKE3 hasher.
+///
+/// Labels and messages are absorbed with their lengths, so that distinct
+/// sequences of operations never absorb the same bytes. A challenge is read
+/// from the extendable output of the hasher, 64 bytes reduced modulo the
+/// order of `F`, so its bias is negligible. As with [`crate::IOPTranscript`],
+/// the challenges are absorbed back once squeezed, and a challenge of an
+/// empty transcript is rejected.
+#[derive(Clone)]
+pub struct Blake3Transcript<F: PrimeField> {
+    hasher: ::blake3::Hasher,
+    is_empty: bool,
+    #[doc(hidden)]
+    phantom: PhantomData<F>,
+}
+
+impl<F: PrimeField> Blake3Transcript<F> {
+    fn absorb(&mut self, bytes: &[u8]) {
+        self.hasher.update(&(bytes.len() as u64).to_le_bytes());
+        self.hasher.update(bytes);
+    }
+}
+
+impl<F: PrimeField> Transcript<F> for Blake3Transcript<F> {
+    fn new(label: &'static [u8]) -> Self {
+        let mut transcript = Self {
+            hasher: ::blake3::Hasher::new(),
+            is_empty: true,
+            phantom: PhantomData,
+        };
+        transcript.absorb(label);
+        transcript
+    }
+
+    fn with_domain_separator(separator: &[u8]) -> Self {
+        let mut transcript = Self::new(b"domain separated transcript");
+        transcript.absorb(b"domain separator");
+        transcript.absorb(separator);
+        transcript
+    }
+
+    fn append_message(&mut self, label: &'static [u8], msg: &[u8]) -> Result<(), TranscriptError> {
+        self.absorb(label);
+        self.absorb(msg);
+        self.is_empty = false;
+        Ok(())
+    }
+
+    fn append_field_element(
+        &mut self,
+        label: &'static [u8],
+        field_elem: &F,
+    ) -> Result<(), TranscriptError> {
+        self.append_message(label, &to_bytes!(field_elem)?)
+    }
+
+    fn append_serializable_element<S: CanonicalSerialize>(
+        &mut self,
+        label: &'static [u8],
+        group_elem: &S,
+    ) -> Result<(), TranscriptError> {
+        self.append_message(label, &to_bytes!(group_elem)?)
+    }
+
+    fn get_and_append_challenge(&mut self, label: &'static [u8]) -> Result<F, TranscriptError> {
+        //  we need to reject when transcript is empty
+        if self.is_empty {
+            return Err(TranscriptError::InvalidTranscript(
+                "transcript is empty".to_string(),
+            ));
+        }
+
+        self.absorb(label);
+        let mut buf = [0u8; 64];
+        self.hasher.finalize_xof().fill(&mut buf);
+        let challenge = F::from_le_bytes_mod_order(&buf);
+        self.append_field_element(label, &challenge)?;
+        Ok(challenge)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IOPTranscript;
+    use ark_bls12_381::Fr;
+
+    #[test]
+    fn test_blake3_transcript() -> Result<(), TranscriptError> {
+        let mut transcript = <Blake3Transcript<Fr> as Transcript<Fr>>::new(b"test");
+        assert!(transcript.get_and_append_challenge(b"empty").is_err());
+
+        transcript.append_message(b"message", b"some bytes")?;
+        transcript.append_field_element(b"element", &Fr::from(7u64))?;
+        let mut other = transcript.clone();
+        let challenge = transcript.get_and_append_challenge(b"challenge")?;
+
+        // the same operations yield the same challenges
+        assert_eq!(other.get_and_append_challenge(b"challenge")?, challenge);
+        assert_ne!(
+            transcript.get_and_append_challenge(b"challenge")?,
+            challenge
+        );
+
+        // a different message yields a different challenge
+        let mut other = <Blake3Transcript<Fr> as Transcript<Fr>>::new(b"test");
+        other.append_message(b"message", b"other bytes")?;
+        other.append_field_element(b"element", &Fr::from(7u64))?;
+        assert_ne!(other.get_and_append_challenge(b"challenge")?, challenge);
+
+        // moving bytes from a label to its message is not a collision
+        let mut other = <Blake3Transcript<Fr> as Transcript<Fr>>::new(b"test");
+        other.append_message(b"messag", b"esome bytes")?;
+        other.append_field_element(b"element", &Fr::from(7u64))?;
+        assert_ne!(other.get_and_append_challenge(b"challenge")?, challenge);
+
+        // and the challenges differ from those of the Merlin transcript
+        let mut merlin = <IOPTranscript<Fr> as Transcript<Fr>>::new(b"test");
+        merlin.append_message(b"message", b"some bytes")?;
+        merlin.append_field_element(b"element", &Fr::from(7u64))?;
+        assert_ne!(merlin.get_and_append_challenge(b"challenge")?, challenge);
+        Ok(())
+    }
+}
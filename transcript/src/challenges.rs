@@ -0,0 +1,183 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! Challenges typed by the sub-protocol that derives them.
+//!
+//! A HyperPlonk proof runs the gate zero check, the permutation check, the
+//! lookup check and the batched opening over a single transcript. Each of them derives its
+//! challenges with its own method below, which absorbs the tag of the
+//! sub-protocol before the derivation, and gets them wrapped in its own
+//! type, so that a challenge of one sub-protocol cannot be passed to
+//! another. A transcript created with `IOPTranscript::new_untagged` skips
+//! the tags, but still types the challenges.
+
+use crate::{errors::TranscriptError, IOPTranscript};
+use ark_ff::PrimeField;
+use ark_std::vec::Vec;
+
+/// Tag absorbed before the challenges of the zero check.
+pub const ZERO_CHECK_TAG: u8 = 1;
+/// Tag absorbed before the challenges of the permutation check, including
+/// its product check.
+pub const PERM_CHECK_TAG: u8 = 2;
+/// Tag absorbed before the challenges of the batched opening.
+pub const OPENING_TAG: u8 = 3;
+/// Tag absorbed before the challenges of the lookup check.
+pub const LOOKUP_TAG: u8 = 4;
+
+/// A challenge of the zero check.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ZeroCheckChallenge<F: PrimeField>(pub F);
+
+/// A challenge of the permutation check.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PermCheckChallenge<F: PrimeField>(pub F);
+
+/// A challenge of the batched opening.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OpeningChallenge<F: PrimeField>(pub F);
+
+/// A challenge of the lookup check.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LookupChallenge<F: PrimeField>(pub F);
+
+impl<F: PrimeField> IOPTranscript<F> {
+    /// Absorb `tag`, unless the transcript is untagged, then generate `len`
+    /// challenges labelled `label`.
+    fn get_and_append_tagged_challenges(
+        &mut self,
+        tag: u8,
+        label: &'static [u8],
+        len: usize,
+    ) -> Result<Vec<F>, TranscriptError> {
+        // the tag must not hide an empty transcript
+        if self.is_empty {
+            return Err(TranscriptError::InvalidTranscript(
+                "transcript is empty".to_string(),
+            ));
+        }
+        if self.tagged {
+            self.append_message(b"sub-protocol", &[tag])?;
+        }
+        self.get_and_append_challenge_vectors(label, len)
+    }
+
+    /// Generate `len` challenges of the zero check.
+    pub fn get_zero_check_challenges(
+        &mut self,
+        label: &'static [u8],
+        len: usize,
+    ) -> Result<Vec<ZeroCheckChallenge<F>>, TranscriptError> {
+        Ok(self
+            .get_and_append_tagged_challenges(ZERO_CHECK_TAG, label, len)?
+            .into_iter()
+            .map(ZeroCheckChallenge)
+            .collect())
+    }
+
+    /// Generate a challenge of the permutation check.
+    pub fn get_perm_check_challenge(
+        &mut self,
+        label: &'static [u8],
+    ) -> Result<PermCheckChallenge<F>, TranscriptError> {
+        Ok(PermCheckChallenge(
+            self.get_and_append_tagged_challenges(PERM_CHECK_TAG, label, 1)?[0],
+        ))
+    }
+
+    /// Generate `len` challenges of the batched opening.
+    pub fn get_opening_challenges(
+        &mut self,
+        label: &'static [u8],
+        len: usize,
+    ) -> Result<Vec<OpeningChallenge<F>>, TranscriptError> {
+        Ok(self
+            .get_and_append_tagged_challenges(OPENING_TAG, label, len)?
+            .into_iter()
+            .map(OpeningChallenge)
+            .collect())
+    }
+
+    /// Generate `len` challenges of the lookup check.
+    pub fn get_lookup_challenges(
+        &mut self,
+        label: &'static [u8],
+        len: usize,
+    ) -> Result<Vec<LookupChallenge<F>>, TranscriptError> {
+        Ok(self
+            .get_and_append_tagged_challenges(LOOKUP_TAG, label, len)?
+            .into_iter()
+            .map(LookupChallenge)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Fr;
+
+    fn transcript() -> IOPTranscript<Fr> {
+        let mut transcript = IOPTranscript::new(b"test");
+        transcript
+            .append_message(b"msg", b"the same message")
+            .unwrap();
+        transcript
+    }
+
+    #[test]
+    fn test_tags_change_challenges() -> Result<(), TranscriptError> {
+        let untagged = transcript().get_and_append_challenge(b"c")?;
+        let zero_check = transcript().get_zero_check_challenges(b"c", 1)?[0].0;
+        let perm_check = transcript().get_perm_check_challenge(b"c")?.0;
+        let opening = transcript().get_opening_challenges(b"c", 1)?[0].0;
+        let lookup = transcript().get_lookup_challenges(b"c", 1)?[0].0;
+
+        let all = [untagged, zero_check, perm_check, opening, lookup];
+        for i in 0..all.len() {
+            for j in i + 1..all.len() {
+                assert_ne!(all[i], all[j]);
+            }
+        }
+
+        // the derivation is deterministic
+        assert_eq!(transcript().get_perm_check_challenge(b"c")?.0, perm_check);
+        // and a tagged challenge is the untagged one after the tag
+        let mut tagged = transcript();
+        tagged.append_message(b"sub-protocol", &[PERM_CHECK_TAG])?;
+        assert_eq!(tagged.get_and_append_challenge(b"c")?, perm_check);
+        Ok(())
+    }
+
+    #[test]
+    fn test_untagged_transcript() -> Result<(), TranscriptError> {
+        let untagged = || {
+            let mut transcript = IOPTranscript::<Fr>::new_untagged(b"test");
+            transcript.append_message(b"msg", b"the same message")?;
+            Ok::<_, TranscriptError>(transcript)
+        };
+        let plain = transcript().get_and_append_challenge(b"c")?;
+        assert_eq!(untagged()?.get_perm_check_challenge(b"c")?.0, plain);
+        assert_eq!(untagged()?.get_zero_check_challenges(b"c", 1)?[0].0, plain);
+        assert_eq!(untagged()?.get_opening_challenges(b"c", 1)?[0].0, plain);
+        assert_eq!(untagged()?.get_lookup_challenges(b"c", 1)?[0].0, plain);
+        assert!(untagged()?.is_legacy());
+        assert!(!transcript().is_legacy());
+        assert!(IOPTranscript::<Fr>::new_untagged(b"test")
+            .get_perm_check_challenge(b"c")
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_tagged_challenges_reject_empty_transcript() {
+        let mut transcript = IOPTranscript::<Fr>::new(b"test");
+        assert!(transcript.get_zero_check_challenges(b"c", 2).is_err());
+        assert!(transcript.get_perm_check_challenge(b"c").is_err());
+        assert!(transcript.get_opening_challenges(b"c", 2).is_err());
+        assert!(transcript.get_lookup_challenges(b"c", 2).is_err());
+    }
+}
@@ -7,10 +7,12 @@
 //! Error module.
 
 use ark_std::string::String;
+#[cfg(feature = "display")]
 use displaydoc::Display;
 
 /// A `enum` specifying the possible failure modes of the Transcript.
-#[derive(Display, Debug)]
+#[derive(Debug)]
+#[cfg_attr(feature = "display", derive(Display))]
 pub enum TranscriptError {
     /// Invalid Transcript: {0}
     InvalidTranscript(String),
@@ -18,6 +20,14 @@ pub enum TranscriptError {
     SerializationError(ark_serialize::SerializationError),
 }
 
+/// Display as `Debug` without the `display` feature.
+#[cfg(not(feature = "display"))]
+impl ark_std::fmt::Display for TranscriptError {
+    fn fmt(&self, f: &mut ark_std::fmt::Formatter<'_>) -> ark_std::fmt::Result {
+        ark_std::fmt::Debug::fmt(self, f)
+    }
+}
+
 impl From<ark_serialize::SerializationError> for TranscriptError {
     fn from(e: ark_serialize::SerializationError) -> Self {
         Self::SerializationError(e)
@@ -9,13 +9,52 @@
 //! useful.
 //! TODO(ZZ): decide which APIs need to be public.
 
+mod blake3_transcript;
+mod challenges;
 mod errors;
+mod poseidon;
+#[cfg(any(test, feature = "test-utils"))]
+mod test_transcript;
+pub use blake3_transcript::Blake3Transcript;
+pub use challenges::{
+    LookupChallenge, OpeningChallenge, PermCheckChallenge, ZeroCheckChallenge, LOOKUP_TAG,
+    OPENING_TAG, PERM_CHECK_TAG, ZERO_CHECK_TAG,
+};
 pub use errors::TranscriptError;
+pub use poseidon::PoseidonTranscript;
+#[cfg(any(test, feature = "test-utils"))]
+pub use test_transcript::TestTranscript;
 
 use ark_ff::PrimeField;
-use ark_serialize::CanonicalSerialize;
-use merlin::Transcript;
-use std::marker::PhantomData;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::io::{Read, Write};
+use merlin::Transcript as MerlinTranscript;
+use std::{
+    collections::BTreeSet,
+    marker::PhantomData,
+    sync::{Mutex, OnceLock},
+};
+
+/// Maximal length of a label read by [`IOPTranscript::replay_log`].
+const MAX_LABEL_LEN: usize = 64;
+
+/// The label of the transcripts created by `with_domain_separator`.
+const DOMAIN_SEPARATED_LABEL: &[u8] = b"domain separated transcript";
+/// The label under which `with_domain_separator` absorbs the separator.
+const DOMAIN_SEPARATOR_LABEL: &[u8] = b"domain separator";
+/// The label under which `fork` absorbs the index of a child transcript.
+const FORK_LABEL: &[u8] = b"fork";
+
+/// An operation on the underlying Merlin transcript, recorded so that the
+/// transcript can be restored or inspected, see
+/// [`IOPTranscript::start_recording`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TranscriptOp {
+    /// a message with its label
+    Message(&'static [u8], Vec<u8>),
+    /// the label of a challenge
+    Challenge(&'static [u8]),
+}
 
 /// An IOP transcript consists of a Merlin transcript and a flag `is_empty` to
 /// indicate that if the transcript is empty.
@@ -26,33 +65,91 @@ use std::marker::PhantomData;
 /// The `is_empty` flag is useful in the case where a protocol is initiated by
 /// the verifier, in which case the prover should start its phase by receiving a
 /// `non-empty` transcript.
+///
+/// The `tagged` flag is cleared by `new_untagged`, for the protocols that
+/// predate the sub-protocol tags.
+///
+/// The `log` holds the operations since `start_recording`, if any.
+///
+/// The `forked` flag is set by `fork`, after which the transcript rejects
+/// any operation in favor of its children.
 #[derive(Clone)]
 pub struct IOPTranscript<F: PrimeField> {
-    transcript: Transcript,
+    transcript: MerlinTranscript,
     is_empty: bool,
+    tagged: bool,
+    forked: bool,
+    log: Option<Vec<TranscriptOp>>,
     #[doc(hidden)]
     phantom: PhantomData<F>,
 }
 
+/// A checkpoint of an [`IOPTranscript`], see [`IOPTranscript::checkpoint`].
+///
+/// Merlin transcripts cannot be serialized, and neither can a checkpoint: it
+/// only lives in the process that took it. To resume a transcript elsewhere,
+/// record it and replay its log instead, see
+/// [`IOPTranscript::start_recording`].
+#[derive(Clone)]
+pub struct TranscriptState<F: PrimeField> {
+    transcript: IOPTranscript<F>,
+}
+
 // TODO: Make this into a Trait
 impl<F: PrimeField> IOPTranscript<F> {
     /// Create a new IOP transcript.
     pub fn new(label: &'static [u8]) -> Self {
         Self {
-            transcript: Transcript::new(label),
+            transcript: MerlinTranscript::new(label),
             is_empty: true,
+            tagged: true,
+            forked: false,
+            log: None,
             phantom: PhantomData,
         }
     }
 
+    /// Create a new IOP transcript whose initial state depends on
+    /// `separator`, so that protocols sharing labels such as `b"prover msg"`
+    /// derive independent challenges when their separators differ.
+    pub fn with_domain_separator(separator: &[u8]) -> Self {
+        let mut res = Self::new(DOMAIN_SEPARATED_LABEL);
+        res.transcript
+            .append_message(DOMAIN_SEPARATOR_LABEL, separator);
+        res
+    }
+
+    /// Create a new IOP transcript whose sub-protocol challenges are derived
+    /// without absorbing the tag of the sub-protocol first, as they were
+    /// before the tags were introduced. Only use it to verify such legacy
+    /// proofs, see [`Self::is_legacy`].
+    pub fn new_untagged(label: &'static [u8]) -> Self {
+        Self {
+            tagged: false,
+            ..Self::new(label)
+        }
+    }
+
+    /// Whether the transcript was created by [`Self::new_untagged`], i.e.,
+    /// is the transcript of the legacy proofs. The protocols skip on such a
+    /// transcript every absorption they added after the legacy proofs, and
+    /// not only the tags.
+    pub fn is_legacy(&self) -> bool {
+        !self.tagged
+    }
+
     // Append the message to the transcript.
     pub fn append_message(
         &mut self,
         label: &'static [u8],
         msg: &[u8],
     ) -> Result<(), TranscriptError> {
+        self.check_not_forked()?;
         self.transcript.append_message(label, msg);
         self.is_empty = false;
+        if let Some(log) = &mut self.log {
+            log.push(TranscriptOp::Message(label, msg.to_vec()));
+        }
         Ok(())
     }
 
@@ -86,9 +183,13 @@ impl<F: PrimeField> IOPTranscript<F> {
                 "transcript is empty".to_string(),
             ));
         }
+        self.check_not_forked()?;
 
         let mut buf = [0u8; 64];
         self.transcript.challenge_bytes(label, &mut buf);
+        if let Some(log) = &mut self.log {
+            log.push(TranscriptOp::Challenge(label));
+        }
         let challenge = F::from_le_bytes_mod_order(&buf);
         self.append_serializable_element(label, &challenge)?;
         Ok(challenge)
@@ -117,6 +218,270 @@ impl<F: PrimeField> IOPTranscript<F> {
         }
         Ok(res)
     }
+
+    /// Generate `n` challenges from a single squeeze of the transcript, and
+    /// append them to the transcript as a single message.
+    ///
+    /// The i-th challenge is the first 64 bytes of the BLAKE3 output on the
+    /// squeezed bytes and `i`, reduced modulo the order of `F`. Unlike
+    /// [`Self::get_and_append_challenge_vectors`], the Merlin transcript is
+    /// only updated twice, whatever `n`, but the challenges differ from those
+    /// of `n` calls to [`Self::get_and_append_challenge`]: the prover and the
+    /// verifier must both use this method.
+    pub fn get_n_challenges(
+        &mut self,
+        n: usize,
+        label: &'static [u8],
+    ) -> Result<Vec<F>, TranscriptError> {
+        //  we need to reject when transcript is empty
+        if self.is_empty {
+            return Err(TranscriptError::InvalidTranscript(
+                "transcript is empty".to_string(),
+            ));
+        }
+        self.check_not_forked()?;
+
+        let mut seed = [0u8; 64];
+        self.transcript.challenge_bytes(label, &mut seed);
+        if let Some(log) = &mut self.log {
+            log.push(TranscriptOp::Challenge(label));
+        }
+        let challenges = (0..n as u64)
+            .map(|i| {
+                let mut hasher = ::blake3::Hasher::new();
+                hasher.update(&seed);
+                hasher.update(&i.to_le_bytes());
+                let mut buf = [0u8; 64];
+                hasher.finalize_xof().fill(&mut buf);
+                F::from_le_bytes_mod_order(&buf)
+            })
+            .collect::<Vec<_>>();
+        self.append_serializable_element(label, &challenges)?;
+        Ok(challenges)
+    }
+
+    /// Split the transcript into `n` children for independent sub-protocols,
+    /// e.g., to run them in different threads.
+    ///
+    /// The i-th child is the transcript with `n` and `i` appended under a
+    /// dedicated label, so the children derive independent challenges from
+    /// the same messages. The transcript itself is then marked as forked,
+    /// and any later operation on it fails, as does forking it again. The
+    /// children are recorded if the transcript is.
+    pub fn fork(&mut self, n: usize) -> Result<Vec<Self>, TranscriptError> {
+        self.check_not_forked()?;
+        let children = (0..n as u64)
+            .map(|i| {
+                let mut child = self.clone();
+                let index = [(n as u64).to_le_bytes(), i.to_le_bytes()].concat();
+                child.append_message(FORK_LABEL, &index)?;
+                Ok(child)
+            })
+            .collect::<Result<Vec<_>, TranscriptError>>()?;
+        self.forked = true;
+        Ok(children)
+    }
+
+    /// Whether the transcript has been forked, see [`Self::fork`].
+    pub fn is_forked(&self) -> bool {
+        self.forked
+    }
+
+    fn check_not_forked(&self) -> Result<(), TranscriptError> {
+        if self.forked {
+            return Err(TranscriptError::InvalidTranscript(
+                "the transcript has been forked".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// The current state of the transcript, to go back to with
+    /// [`Self::restore`], e.g., when a sub-protocol fails and another one is
+    /// run in its place.
+    pub fn checkpoint(&self) -> TranscriptState<F> {
+        TranscriptState {
+            transcript: self.clone(),
+        }
+    }
+
+    /// Go back to the state of `state`: the messages and the challenges
+    /// since the checkpoint are forgotten, as are the operations recorded
+    /// since then.
+    pub fn restore(&mut self, state: TranscriptState<F>) {
+        *self = state.transcript;
+    }
+
+    /// Record the operations on the transcript from now on.
+    ///
+    /// Merlin transcripts cannot be serialized. Instead, [`Self::write_log`]
+    /// writes the recorded operations, and [`Self::replay_log`] replays them
+    /// on a transcript in the state in which the recording started, e.g.,
+    /// to resume a proof in another process.
+    pub fn start_recording(&mut self) {
+        self.log.get_or_insert_with(Vec::new);
+    }
+
+    /// Whether the operations on the transcript are recorded.
+    pub fn is_recording(&self) -> bool {
+        self.log.is_some()
+    }
+
+    /// The operations recorded since [`Self::start_recording`], if the
+    /// transcript is recorded.
+    pub fn recorded_ops(&self) -> Option<&[TranscriptOp]> {
+        self.log.as_deref()
+    }
+
+    /// Write the operations recorded since [`Self::start_recording`].
+    pub fn write_log<W: Write>(&self, mut writer: W) -> Result<(), TranscriptError> {
+        let log = self.log.as_ref().ok_or_else(|| {
+            TranscriptError::InvalidTranscript("the transcript is not recorded".to_string())
+        })?;
+        log.len().serialize_compressed(&mut writer)?;
+        for op in log.iter() {
+            match op {
+                TranscriptOp::Message(label, msg) => {
+                    0u8.serialize_compressed(&mut writer)?;
+                    label.to_vec().serialize_compressed(&mut writer)?;
+                    msg.serialize_compressed(&mut writer)?;
+                },
+                TranscriptOp::Challenge(label) => {
+                    1u8.serialize_compressed(&mut writer)?;
+                    label.to_vec().serialize_compressed(&mut writer)?;
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// Replay the operations written by [`Self::write_log`]. They are
+    /// recorded again if the transcript is recorded.
+    pub fn replay_log<R: Read>(&mut self, mut reader: R) -> Result<(), TranscriptError> {
+        self.check_not_forked()?;
+        let len = usize::deserialize_compressed(&mut reader)?;
+        for _ in 0..len {
+            let kind = u8::deserialize_compressed(&mut reader)?;
+            let label = Vec::<u8>::deserialize_compressed(&mut reader)?;
+            if label.len() > MAX_LABEL_LEN {
+                return Err(TranscriptError::InvalidTranscript(format!(
+                    "label of {} bytes in the transcript log",
+                    label.len()
+                )));
+            }
+            let label = intern(label);
+            match kind {
+                0 => {
+                    let msg = Vec::<u8>::deserialize_compressed(&mut reader)?;
+                    self.append_message(label, &msg)?;
+                },
+                1 => {
+                    let mut buf = [0u8; 64];
+                    self.transcript.challenge_bytes(label, &mut buf);
+                    if let Some(log) = &mut self.log {
+                        log.push(TranscriptOp::Challenge(label));
+                    }
+                },
+                _ => {
+                    return Err(TranscriptError::InvalidTranscript(format!(
+                        "unknown operation {} in the transcript log",
+                        kind
+                    )))
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A Fiat-Shamir transcript over the field `F`: the prover and the verifier
+/// absorb the same labelled messages, and derive the same challenges from
+/// them.
+///
+/// It is implemented by [`IOPTranscript`], over Merlin, by
+/// [`PoseidonTranscript`], over a Poseidon sponge on `F`, and by
+/// [`Blake3Transcript`], over BLAKE3.
+pub trait Transcript<F: PrimeField> {
+    /// Create a new transcript.
+    fn new(label: &'static [u8]) -> Self
+    where
+        Self: Sized;
+
+    /// Create a new transcript whose initial state depends on `separator`.
+    fn with_domain_separator(separator: &[u8]) -> Self
+    where
+        Self: Sized;
+
+    /// Append the message to the transcript.
+    fn append_message(&mut self, label: &'static [u8], msg: &[u8]) -> Result<(), TranscriptError>;
+
+    /// Append the field element to the transcript.
+    fn append_field_element(
+        &mut self,
+        label: &'static [u8],
+        field_elem: &F,
+    ) -> Result<(), TranscriptError>;
+
+    /// Append the serialization of the element to the transcript.
+    fn append_serializable_element<S: CanonicalSerialize>(
+        &mut self,
+        label: &'static [u8],
+        group_elem: &S,
+    ) -> Result<(), TranscriptError>;
+
+    /// Generate the challenge from the current transcript and append it to
+    /// the transcript. Returns an error if nothing has been appended yet.
+    fn get_and_append_challenge(&mut self, label: &'static [u8]) -> Result<F, TranscriptError>;
+}
+
+impl<F: PrimeField> Transcript<F> for IOPTranscript<F> {
+    fn new(label: &'static [u8]) -> Self {
+        IOPTranscript::new(label)
+    }
+
+    fn with_domain_separator(separator: &[u8]) -> Self {
+        IOPTranscript::with_domain_separator(separator)
+    }
+
+    fn append_message(&mut self, label: &'static [u8], msg: &[u8]) -> Result<(), TranscriptError> {
+        IOPTranscript::append_message(self, label, msg)
+    }
+
+    fn append_field_element(
+        &mut self,
+        label: &'static [u8],
+        field_elem: &F,
+    ) -> Result<(), TranscriptError> {
+        IOPTranscript::append_field_element(self, label, field_elem)
+    }
+
+    fn append_serializable_element<S: CanonicalSerialize>(
+        &mut self,
+        label: &'static [u8],
+        group_elem: &S,
+    ) -> Result<(), TranscriptError> {
+        IOPTranscript::append_serializable_element(self, label, group_elem)
+    }
+
+    fn get_and_append_challenge(&mut self, label: &'static [u8]) -> Result<F, TranscriptError> {
+        IOPTranscript::get_and_append_challenge(self, label)
+    }
+}
+
+/// The label `label` with the static lifetime that Merlin requires. Every
+/// distinct label is allocated once for the whole process.
+fn intern(label: Vec<u8>) -> &'static [u8] {
+    static LABELS: OnceLock<Mutex<BTreeSet<&'static [u8]>>> = OnceLock::new();
+    let mut labels = LABELS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    if let Some(&interned) = labels.get(label.as_slice()) {
+        return interned;
+    }
+    let interned: &'static [u8] = Box::leak(label.into_boxed_slice());
+    labels.insert(interned);
+    interned
 }
 
 /// Takes as input a struct, and converts them to a series of bytes. All traits
@@ -129,3 +494,185 @@ macro_rules! to_bytes {
         ark_serialize::CanonicalSerialize::serialize_compressed($x, &mut buf).map(|_| buf)
     }};
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Fr;
+
+    #[test]
+    fn test_replay_log() -> Result<(), TranscriptError> {
+        let mut transcript = IOPTranscript::<Fr>::new(b"test");
+        transcript.append_message(b"before", b"not recorded")?;
+        assert!(transcript.write_log(vec![]).is_err());
+
+        // the recording starts from the same state on both sides
+        let mut resumed = transcript.clone();
+        transcript.start_recording();
+        transcript.append_field_element(b"x", &Fr::from(3u64))?;
+        transcript.get_and_append_challenge_vectors(b"challenges", 2)?;
+        transcript.append_message(b"message", b"recorded")?;
+        let mut log = vec![];
+        transcript.write_log(&mut log)?;
+
+        resumed.start_recording();
+        resumed.replay_log(log.as_slice())?;
+        assert_eq!(
+            resumed.get_and_append_challenge(b"next")?,
+            transcript.get_and_append_challenge(b"next")?
+        );
+        // the replayed operations are recorded again
+        let (mut log, mut resumed_log) = (vec![], vec![]);
+        transcript.write_log(&mut log)?;
+        resumed.write_log(&mut resumed_log)?;
+        assert_eq!(log, resumed_log);
+        assert_eq!(resumed.recorded_ops(), transcript.recorded_ops());
+        assert_eq!(
+            transcript.recorded_ops().map(|ops| ops[..2].to_vec()),
+            Some(vec![
+                TranscriptOp::Message(b"x", to_bytes!(&Fr::from(3u64))?),
+                TranscriptOp::Challenge(b"challenges"),
+            ])
+        );
+
+        // a truncated log
+        let mut other = IOPTranscript::<Fr>::new(b"test");
+        assert!(other.replay_log(&log[..log.len() - 1]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_n_challenges() -> Result<(), TranscriptError> {
+        let mut prover = IOPTranscript::<Fr>::new(b"test");
+        assert!(prover.get_n_challenges(2, b"challenges").is_err());
+        prover.append_message(b"message", b"some bytes")?;
+        prover.start_recording();
+        let mut verifier = prover.clone();
+
+        // the verifier derives the same challenges, and the same transcript
+        let n = 256;
+        let challenges = prover.get_n_challenges(n, b"challenges")?;
+        assert_eq!(verifier.get_n_challenges(n, b"challenges")?, challenges);
+        assert_eq!(
+            prover.get_and_append_challenge(b"next")?,
+            verifier.get_and_append_challenge(b"next")?
+        );
+        // which are recorded as a squeeze and a message, as is the next
+        // challenge
+        assert_eq!(prover.recorded_ops().map(|ops| ops.len()), Some(4));
+
+        // the challenges are pairwise distinct, and each of their low bits is
+        // set in about half of them: 256 fair coins land within 64 of the
+        // mean but with probability 2^-31
+        let mut sorted = challenges.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(sorted.len(), n);
+        for bit in 0..64 {
+            let ones = challenges
+                .iter()
+                .filter(|c| (c.into_bigint().as_ref()[0] >> bit) & 1 == 1)
+                .count();
+            assert!((n / 2 - 64..=n / 2 + 64).contains(&ones), "bit {}", bit);
+        }
+
+        // consecutive challenges are uncorrelated: their low bits agree in
+        // about half of the pairs
+        let agree = challenges
+            .windows(2)
+            .filter(|w| {
+                (w[0].into_bigint().as_ref()[0] & 1) == (w[1].into_bigint().as_ref()[0] & 1)
+            })
+            .count();
+        assert!((n / 2 - 64..=n / 2 + 64).contains(&agree));
+        Ok(())
+    }
+
+    #[test]
+    fn test_fork() -> Result<(), TranscriptError> {
+        let mut transcript = IOPTranscript::<Fr>::new(b"test");
+        transcript.append_message(b"message", b"some bytes")?;
+        let mut other = transcript.clone();
+        let mut children = transcript.fork(2)?;
+        assert!(transcript.is_forked());
+
+        // the children derive different challenges from the same label and
+        // messages, and each one is independent of what the other does
+        let challenges = children
+            .iter_mut()
+            .map(|child| {
+                child.append_message(b"prover msg", b"the same message")?;
+                child.get_and_append_challenge(b"challenge")
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_ne!(challenges[0], challenges[1]);
+        let mut again = other.clone().fork(2)?.remove(1);
+        again.append_message(b"prover msg", b"the same message")?;
+        assert_eq!(again.get_and_append_challenge(b"challenge")?, challenges[1]);
+
+        // and they differ from the challenges of the transcript itself
+        other.append_message(b"prover msg", b"the same message")?;
+        assert!(!challenges.contains(&other.get_and_append_challenge(b"challenge")?));
+
+        // the forked transcript rejects any further use
+        assert!(transcript.append_message(b"message", b"late").is_err());
+        assert!(transcript.get_and_append_challenge(b"challenge").is_err());
+        assert!(transcript.get_n_challenges(2, b"challenges").is_err());
+        assert!(transcript.fork(2).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_domain_separator() -> Result<(), TranscriptError> {
+        let challenge = |separator: &[u8]| {
+            let mut transcript = IOPTranscript::<Fr>::with_domain_separator(separator);
+            // the separator alone does not make the transcript non-empty
+            assert!(transcript
+                .clone()
+                .get_and_append_challenge(b"empty")
+                .is_err());
+            transcript.append_message(b"prover msg", b"the same message")?;
+            transcript.get_and_append_challenge(b"challenge")
+        };
+        assert_eq!(challenge(b"zero check")?, challenge(b"zero check")?);
+        assert_ne!(challenge(b"zero check")?, challenge(b"perm check")?);
+        assert_ne!(challenge(b"")?, challenge(b"zero check")?);
+
+        let mut transcript = IOPTranscript::<Fr>::new(DOMAIN_SEPARATED_LABEL);
+        transcript.append_message(b"prover msg", b"the same message")?;
+        assert_ne!(
+            transcript.get_and_append_challenge(b"challenge")?,
+            challenge(b"")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_checkpoint() -> Result<(), TranscriptError> {
+        let mut transcript = IOPTranscript::<Fr>::new(b"test");
+        transcript.append_message(b"message", b"before the checkpoint")?;
+        transcript.start_recording();
+        let fresh = transcript.clone();
+        let state = transcript.checkpoint();
+
+        transcript.append_field_element(b"x", &Fr::from(3u64))?;
+        let first = transcript.get_and_append_challenge(b"challenge")?;
+
+        // after a rollback, the transcript is the one at the checkpoint
+        transcript.restore(state.clone());
+        assert_eq!(transcript.recorded_ops(), Some(&[][..]));
+        transcript.append_field_element(b"x", &Fr::from(4u64))?;
+        let second = transcript.get_and_append_challenge(b"challenge")?;
+        assert_ne!(first, second);
+
+        let mut other = fresh.clone();
+        other.append_field_element(b"x", &Fr::from(4u64))?;
+        assert_eq!(other.get_and_append_challenge(b"challenge")?, second);
+
+        // a checkpoint can be restored more than once
+        transcript.restore(state);
+        transcript.append_field_element(b"x", &Fr::from(3u64))?;
+        assert_eq!(transcript.get_and_append_challenge(b"challenge")?, first);
+        Ok(())
+    }
+}
@@ -0,0 +1,176 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! A transcript over a Poseidon sponge on the field of the challenges, whose
+//! challenges are cheap to derive again in a circuit over that field.
+
+use crate::{to_bytes, Transcript, TranscriptError};
+use ark_crypto_primitives::sponge::{
+    poseidon::{find_poseidon_ark_and_mds, PoseidonConfig, PoseidonSponge},
+    Absorb, CryptographicSponge,
+};
+use ark_ff::PrimeField;
+use ark_serialize::CanonicalSerialize;
+
+/// The number of full rounds of the permutation.
+const FULL_ROUNDS: usize = 8;
+/// The number of partial rounds of the permutation. This is a conservative
+/// choice for fields of up to 256 bits, not an optimized one.
+const PARTIAL_ROUNDS: usize = 60;
+/// The rate of the sponge, in field elements.
+const RATE: usize = 2;
+/// The capacity of the sponge, in field elements.
+const CAPACITY: usize = 1;
+
+/// A transcript over a Poseidon sponge on `F`.
+///
+/// Labels and messages are absorbed as bytes packed into field elements, and
+/// field elements as themselves, so that a verifier circuit over `F` absorbs
+/// them without decomposing them. As with [`crate::IOPTranscript`], the
+/// challenges are absorbed back once squeezed, and a challenge of an empty
+/// transcript is rejected.
+#[derive(Clone)]
+pub struct PoseidonTranscript<F: PrimeField + Absorb> {
+    sponge: PoseidonSponge<F>,
+    is_empty: bool,
+}
+
+impl<F: PrimeField + Absorb> PoseidonTranscript<F> {
+    /// The parameters of the permutation over `F`.
+    ///
+    /// The S-box is `x^alpha` for the smallest prime `alpha` coprime to
+    /// `|F| - 1`, and the round constants and the MDS matrix are generated
+    /// as in the reference implementation.
+    pub fn config() -> PoseidonConfig<F> {
+        let (ark, mds) = find_poseidon_ark_and_mds::<F>(
+            F::MODULUS_BIT_SIZE as u64,
+            RATE,
+            FULL_ROUNDS as u64,
+            PARTIAL_ROUNDS as u64,
+            0,
+        );
+        PoseidonConfig::new(
+            FULL_ROUNDS,
+            PARTIAL_ROUNDS,
+            alpha::<F>(),
+            mds,
+            ark,
+            RATE,
+            CAPACITY,
+        )
+    }
+
+    fn absorb_label(&mut self, label: &'static [u8]) {
+        self.sponge.absorb(&label);
+    }
+}
+
+impl<F: PrimeField + Absorb> Transcript<F> for PoseidonTranscript<F> {
+    fn new(label: &'static [u8]) -> Self {
+        let mut transcript = Self {
+            sponge: PoseidonSponge::new(&Self::config()),
+            is_empty: true,
+        };
+        transcript.absorb_label(label);
+        transcript
+    }
+
+    fn with_domain_separator(separator: &[u8]) -> Self {
+        let mut transcript = Self::new(b"domain separated transcript");
+        transcript.absorb_label(b"domain separator");
+        transcript.sponge.absorb(&separator);
+        transcript
+    }
+
+    fn append_message(&mut self, label: &'static [u8], msg: &[u8]) -> Result<(), TranscriptError> {
+        self.absorb_label(label);
+        self.sponge.absorb(&msg);
+        self.is_empty = false;
+        Ok(())
+    }
+
+    fn append_field_element(
+        &mut self,
+        label: &'static [u8],
+        field_elem: &F,
+    ) -> Result<(), TranscriptError> {
+        self.absorb_label(label);
+        self.sponge.absorb(field_elem);
+        self.is_empty = false;
+        Ok(())
+    }
+
+    fn append_serializable_element<S: CanonicalSerialize>(
+        &mut self,
+        label: &'static [u8],
+        group_elem: &S,
+    ) -> Result<(), TranscriptError> {
+        self.append_message(label, &to_bytes!(group_elem)?)
+    }
+
+    fn get_and_append_challenge(&mut self, label: &'static [u8]) -> Result<F, TranscriptError> {
+        //  we need to reject when transcript is empty
+        if self.is_empty {
+            return Err(TranscriptError::InvalidTranscript(
+                "transcript is empty".to_string(),
+            ));
+        }
+
+        self.absorb_label(label);
+        let challenge = self.sponge.squeeze_field_elements::<F>(1)[0];
+        self.sponge.absorb(&challenge);
+        Ok(challenge)
+    }
+}
+
+/// The smallest prime `alpha` such that `x^alpha` is a permutation of `F`,
+/// i.e., that does not divide `|F| - 1`.
+fn alpha<F: PrimeField>() -> u64 {
+    [3u64, 5, 7, 11, 13, 17, 19, 23, 29, 31]
+        .into_iter()
+        .find(|&alpha| {
+            // |F| mod alpha, from the most significant limb
+            let rem = F::MODULUS.as_ref().iter().rev().fold(0u128, |rem, &limb| {
+                ((rem << 64) | limb as u128) % alpha as u128
+            });
+            rem != 1
+        })
+        .expect("no small prime is coprime to |F| - 1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Fr;
+
+    #[test]
+    fn test_poseidon_transcript() -> Result<(), TranscriptError> {
+        // x^5 is a permutation of the scalar field of BLS12-381
+        assert_eq!(alpha::<Fr>(), 5);
+
+        let mut transcript = <PoseidonTranscript<Fr> as Transcript<Fr>>::new(b"test");
+        assert!(transcript.get_and_append_challenge(b"empty").is_err());
+
+        transcript.append_message(b"message", b"some bytes")?;
+        transcript.append_field_element(b"element", &Fr::from(7u64))?;
+        let mut other = transcript.clone();
+        let challenge = transcript.get_and_append_challenge(b"challenge")?;
+
+        // the same operations yield the same challenges
+        assert_eq!(other.get_and_append_challenge(b"challenge")?, challenge);
+        assert_ne!(
+            transcript.get_and_append_challenge(b"challenge")?,
+            challenge
+        );
+
+        // a different message yields a different challenge
+        let mut other = <PoseidonTranscript<Fr> as Transcript<Fr>>::new(b"test");
+        other.append_message(b"message", b"other bytes")?;
+        other.append_field_element(b"element", &Fr::from(7u64))?;
+        assert_ne!(other.get_and_append_challenge(b"challenge")?, challenge);
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+// Copyright (c) 2023 Espresso Systems (espressosys.com)
+// This file is part of the HyperPlonk library.
+
+// You should have received a copy of the MIT License
+// along with the HyperPlonk library. If not, see <https://mit-license.org/>.
+
+//! A transcript replaying given challenges, to test protocols on challenges
+//! that a hash would hardly ever sample.
+
+use crate::{Transcript, TranscriptError};
+use ark_ff::PrimeField;
+use ark_serialize::CanonicalSerialize;
+
+/// A transcript whose challenges are given in advance, and returned in
+/// order whatever the messages.
+///
+/// The messages are ignored, and requesting more challenges than given is
+/// an error. Since the challenges do not depend on the messages, it is only
+/// meant for tests, e.g., of a challenge at a root of a round polynomial.
+#[derive(Clone, Debug)]
+pub struct TestTranscript<F: PrimeField> {
+    challenges: Vec<F>,
+    next: usize,
+}
+
+impl<F: PrimeField> TestTranscript<F> {
+    /// A transcript replaying `challenges`.
+    pub fn new(challenges: Vec<F>) -> Self {
+        Self {
+            challenges,
+            next: 0,
+        }
+    }
+
+    /// The number of challenges that have not been requested yet.
+    pub fn remaining(&self) -> usize {
+        self.challenges.len() - self.next
+    }
+}
+
+impl<F: PrimeField> Transcript<F> for TestTranscript<F> {
+    /// A transcript without any challenge.
+    fn new(_label: &'static [u8]) -> Self {
+        Self::new(vec![])
+    }
+
+    /// A transcript without any challenge.
+    fn with_domain_separator(_separator: &[u8]) -> Self {
+        Self::new(vec![])
+    }
+
+    fn append_message(
+        &mut self,
+        _label: &'static [u8],
+        _msg: &[u8],
+    ) -> Result<(), TranscriptError> {
+        Ok(())
+    }
+
+    fn append_field_element(
+        &mut self,
+        _label: &'static [u8],
+        _field_elem: &F,
+    ) -> Result<(), TranscriptError> {
+        Ok(())
+    }
+
+    fn append_serializable_element<S: CanonicalSerialize>(
+        &mut self,
+        _label: &'static [u8],
+        _group_elem: &S,
+    ) -> Result<(), TranscriptError> {
+        Ok(())
+    }
+
+    fn get_and_append_challenge(&mut self, _label: &'static [u8]) -> Result<F, TranscriptError> {
+        let challenge = self.challenges.get(self.next).copied().ok_or_else(|| {
+            TranscriptError::InvalidTranscript(format!(
+                "all the {} challenges have been requested",
+                self.challenges.len()
+            ))
+        })?;
+        self.next += 1;
+        Ok(challenge)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Fr;
+
+    #[test]
+    fn test_test_transcript() -> Result<(), TranscriptError> {
+        let challenges = vec![Fr::from(0u64), Fr::from(1u64), Fr::from(0u64)];
+        let mut transcript = TestTranscript::new(challenges.clone());
+        for &challenge in challenges.iter() {
+            transcript.append_message(b"message", b"ignored")?;
+            assert_eq!(
+                transcript.get_and_append_challenge(b"challenge")?,
+                challenge
+            );
+        }
+        assert_eq!(transcript.remaining(), 0);
+        assert!(transcript.get_and_append_challenge(b"challenge").is_err());
+
+        let mut transcript = <TestTranscript<Fr> as Transcript<Fr>>::new(b"test");
+        assert!(transcript.get_and_append_challenge(b"challenge").is_err());
+        Ok(())
+    }
+}
@@ -5,6 +5,14 @@
 // along with the HyperPlonk library. If not, see <https://mit-license.org/>.
 
 //! Utilities for parallel code.
+//!
+//! A proof must not depend on the number of threads it is generated with.
+//! Parallel code whose output feeds the transcript or the proof therefore
+//! collects with indexed iterators, e.g., `par_iter().map(..).collect()`, or
+//! writes to positions fixed in advance, and only reduces with associative
+//! and commutative operations such as field additions; it never pushes into
+//! a container in completion order. Where an order cannot be fixed, the
+//! items are sorted canonically before they are absorbed.
 
 /// this function helps with slice iterator creation that optionally use
 /// `par_iter()` when feature flag `parallel` is on.
@@ -19,12 +27,96 @@
 /// #[cfg(not(feature = "parallel"))]
 /// let sum = v.iter().sum();
 #[cfg(feature = "parallel")]
-pub fn parallelizable_slice_iter<T: Sync>(data: &[T]) -> rayon::slice::Iter<T> {
+pub fn parallelizable_slice_iter<T: Sync>(data: &[T]) -> rayon::slice::Iter<'_, T> {
     use rayon::iter::IntoParallelIterator;
     data.into_par_iter()
 }
 
 #[cfg(not(feature = "parallel"))]
-pub fn parallelizable_slice_iter<T>(data: &[T]) -> core::slice::Iter<T> {
+pub fn parallelizable_slice_iter<T>(data: &[T]) -> core::slice::Iter<'_, T> {
     data.iter()
 }
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Number of serial routes taken with the `parallel` feature on.
+static SERIAL_ROUTES: AtomicUsize = AtomicUsize::new(0);
+
+/// Whether the hot loops, e.g., the sum check rounds and the eq table build,
+/// should take their serial route.
+///
+/// Without the `parallel` feature they always do. With it, they do when the
+/// current rayon pool has a single thread: the task splitting of rayon then
+/// only adds overhead. Each such serial route is counted by
+/// [`serial_route_count`].
+pub fn serial_route() -> bool {
+    #[cfg(feature = "parallel")]
+    {
+        let serial = rayon::current_num_threads() == 1;
+        if serial {
+            SERIAL_ROUTES.fetch_add(1, Ordering::Relaxed);
+        }
+        serial
+    }
+    #[cfg(not(feature = "parallel"))]
+    true
+}
+
+/// Number of serial routes taken by the hot loops of this process with the
+/// `parallel` feature on, e.g., to check that [`run_serial`] is honored.
+pub fn serial_route_count() -> usize {
+    SERIAL_ROUTES.load(Ordering::Relaxed)
+}
+
+/// Run `f` with the hot loops on their serial route.
+///
+/// With the `parallel` feature, `f` runs in a rayon pool of a single thread,
+/// in which everything else that uses rayon runs serially too. If the pool
+/// cannot be created, `f` runs in the current pool: the result is the same.
+pub fn run_serial<R: Send>(f: impl FnOnce() -> R + Send) -> R {
+    run_with_threads(1, f)
+}
+
+/// Run `f` in a rayon pool of `num_threads` threads with the `parallel`
+/// feature, e.g., to check that a result does not depend on the number of
+/// threads. If the pool cannot be created, or without the feature, `f` runs
+/// in the current pool.
+pub fn run_with_threads<R: Send>(num_threads: usize, f: impl FnOnce() -> R + Send) -> R {
+    #[cfg(feature = "parallel")]
+    if let Ok(pool) = rayon::ThreadPoolBuilder::new()
+        .num_threads(num_threads)
+        .build()
+    {
+        return pool.install(f);
+    }
+    #[cfg(not(feature = "parallel"))]
+    let _ = num_threads;
+    f()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_serial() {
+        let count = serial_route_count();
+        assert!(run_serial(serial_route));
+        #[cfg(feature = "parallel")]
+        assert!(serial_route_count() > count);
+        #[cfg(not(feature = "parallel"))]
+        assert_eq!(serial_route_count(), count);
+    }
+
+    #[test]
+    fn test_run_with_threads() {
+        for num_threads in [1, 4, 16] {
+            let serial = run_with_threads(num_threads, || {
+                #[cfg(feature = "parallel")]
+                assert_eq!(rayon::current_num_threads(), num_threads);
+                serial_route()
+            });
+            assert_eq!(serial, !cfg!(feature = "parallel") || num_threads == 1);
+        }
+    }
+}